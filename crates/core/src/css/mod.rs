@@ -2,7 +2,7 @@
 
 pub mod subscriptions;
 
-pub use subscriptions::{DomBroadcast, Subscriber, Subscriptions};
+pub use subscriptions::{DomBroadcast, Subscriber, SubscriberId, Subscriptions};
 
 // Re-export lightningcss types
 pub use lightningcss::properties::Property;
@@ -73,6 +73,36 @@ impl Ord for Specificity {
     }
 }
 
+/// Style sheet origin of a rule.
+///
+/// Normal declarations rank user-agent < user < author; important
+/// declarations reverse that order and outrank every normal declaration.
+/// Specificity only breaks ties within the same origin and importance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Origin {
+    /// Browser default styles.
+    UserAgent,
+    /// Embedder/user styles (theming, accessibility overrides).
+    User,
+    /// Page styles, including inline `style` attributes.
+    #[default]
+    Author,
+}
+
+impl Origin {
+    /// Cascade precedence of a declaration from this origin; higher wins.
+    pub const fn precedence(self, important: bool) -> u8 {
+        match (important, self) {
+            (false, Self::UserAgent) => 0,
+            (false, Self::User) => 1,
+            (false, Self::Author) => 2,
+            (true, Self::Author) => 3,
+            (true, Self::User) => 4,
+            (true, Self::UserAgent) => 5,
+        }
+    }
+}
+
 impl From<(u32, u32, u32)> for Specificity {
     fn from((ids, classes, elements): (u32, u32, u32)) -> Self {
         Self {
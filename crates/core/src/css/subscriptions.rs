@@ -5,8 +5,9 @@
 //! visibility tracking and property interest logic.
 
 use crate::NodeId;
-use lightningcss::properties::Property;
+use lightningcss::properties::{Property, PropertyId};
 use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// DOM broadcast events sent to subscribers.
 #[derive(Debug, Clone, Copy)]
@@ -20,14 +21,31 @@ pub trait Subscriber: Send + Sync {
     /// Called when a CSS property changes on a node.
     fn on_property(&self, node: NodeId, property: &Property<'static>);
 
+    /// Called when no rule declares a property on a node any more, so it
+    /// reverts to its inherited or initial value.
+    fn on_property_removed(&self, _node: NodeId, _property: &PropertyId<'static>) {}
+
+    /// Called when a rule for the `::before`, `::after` or `::marker` box
+    /// of a node starts or stops applying.
+    fn on_generated_content(&self, _node: NodeId) {}
+
     /// Called when the DOM structure changes.
     fn on_dom(&self, update: DomBroadcast);
 }
 
+/// Handle to a registered subscriber, for replacing or removing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriberId(u64);
+
+/// Registered subscribers, each with the handle it was registered under.
+type Subscribers = Vec<(SubscriberId, Box<dyn Subscriber>)>;
+
 /// Change notification broadcaster.
 /// Notifies all registered subscribers when CSS properties or DOM structure change.
 pub struct Subscriptions {
-    subscribers: RwLock<Vec<Box<dyn Subscriber>>>,
+    /// Subscribers in registration order, which is notification order.
+    subscribers: RwLock<Subscribers>,
+    next_id: AtomicU64,
 }
 
 impl Default for Subscriptions {
@@ -41,29 +59,69 @@ impl Subscriptions {
     pub fn new() -> Self {
         Self {
             subscribers: RwLock::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Add a subscriber to receive notifications, after every subscriber
+    /// added so far.
+    pub fn add_subscriber(&self, subscriber: Box<dyn Subscriber>) -> SubscriberId {
+        let id = SubscriberId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        if let Ok(mut subs) = self.subscribers.write() {
+            subs.push((id, subscriber));
+        }
+        id
+    }
+
+    /// Swap the subscriber registered as `id` for `subscriber`, keeping its
+    /// place in the notification order. Does nothing if `id` was removed.
+    pub fn replace_subscriber(&self, id: SubscriberId, subscriber: Box<dyn Subscriber>) {
+        if let Ok(mut subs) = self.subscribers.write()
+            && let Some(entry) = subs.iter_mut().find(|(entry_id, _)| *entry_id == id)
+        {
+            entry.1 = subscriber;
         }
     }
 
-    /// Add a subscriber to receive notifications.
-    pub fn add_subscriber(&self, subscriber: Box<dyn Subscriber>) {
+    /// Stop notifying the subscriber registered as `id`.
+    pub fn remove_subscriber(&self, id: SubscriberId) {
         if let Ok(mut subs) = self.subscribers.write() {
-            subs.push(subscriber);
+            subs.retain(|(entry_id, _)| *entry_id != id);
         }
     }
 
     /// Notify all subscribers that a property changed on a node.
     pub fn notify_property(&self, node: NodeId, property: &Property<'static>) {
         if let Ok(subs) = self.subscribers.read() {
-            for subscriber in subs.iter() {
+            for (_, subscriber) in subs.iter() {
                 subscriber.on_property(node, property);
             }
         }
     }
 
+    /// Notify all subscribers that a property no longer applies to a node.
+    pub fn notify_property_removed(&self, node: NodeId, property: &PropertyId<'static>) {
+        if let Ok(subs) = self.subscribers.read() {
+            for (_, subscriber) in subs.iter() {
+                subscriber.on_property_removed(node, property);
+            }
+        }
+    }
+
+    /// Notify all subscribers that the rules for a node's generated boxes
+    /// changed.
+    pub fn notify_generated_content(&self, node: NodeId) {
+        if let Ok(subs) = self.subscribers.read() {
+            for (_, subscriber) in subs.iter() {
+                subscriber.on_generated_content(node);
+            }
+        }
+    }
+
     /// Notify all subscribers of a DOM update.
     pub fn notify_dom(&self, update: DomBroadcast) {
         if let Ok(subs) = self.subscribers.read() {
-            for subscriber in subs.iter() {
+            for (_, subscriber) in subs.iter() {
                 subscriber.on_dom(update);
             }
         }
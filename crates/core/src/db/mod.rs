@@ -6,7 +6,10 @@ pub mod sparse_tree;
 mod storage;
 pub mod tree_access;
 
-pub use property_group::{PropertyGroup, classify as classify_property};
+pub use property_group::{
+    PROPERTY_NAMES, PropertyGroup, classify as classify_property, float_id, is_paint_only,
+    outline_offset_id, overflow_anchor_id, pointer_events_id, text_wrap_id,
+};
 pub use query::{Query, ScopedDb};
 pub use sparse_tree::SparseTree;
 pub use storage::{Database, is_css_initial_value};
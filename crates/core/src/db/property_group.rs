@@ -5,6 +5,53 @@
 //! fall through to the legacy per-node store.
 
 use lightningcss::properties::PropertyId;
+use lightningcss::properties::custom::CustomPropertyName;
+use lightningcss::values::ident::Ident;
+
+/// Name of `outline-offset`, which lightningcss does not model as a typed
+/// property — it parses as an unknown custom property.
+const OUTLINE_OFFSET: &str = "outline-offset";
+
+/// The `PropertyId` under which `outline-offset` is stored.
+pub fn outline_offset_id() -> PropertyId<'static> {
+    PropertyId::Custom(CustomPropertyName::Unknown(Ident(OUTLINE_OFFSET.into())))
+}
+
+/// Name of `float`, which lightningcss also parses as an unknown custom
+/// property.
+const FLOAT: &str = "float";
+
+/// The `PropertyId` under which `float` is stored.
+pub fn float_id() -> PropertyId<'static> {
+    PropertyId::Custom(CustomPropertyName::Unknown(Ident(FLOAT.into())))
+}
+
+/// Name of `overflow-anchor`, which lightningcss also parses as an unknown
+/// custom property.
+const OVERFLOW_ANCHOR: &str = "overflow-anchor";
+
+/// The `PropertyId` under which `overflow-anchor` is stored.
+pub fn overflow_anchor_id() -> PropertyId<'static> {
+    PropertyId::Custom(CustomPropertyName::Unknown(Ident(OVERFLOW_ANCHOR.into())))
+}
+
+/// Name of `pointer-events`, which lightningcss also parses as an unknown
+/// custom property.
+const POINTER_EVENTS: &str = "pointer-events";
+
+/// The `PropertyId` under which `pointer-events` is stored.
+pub fn pointer_events_id() -> PropertyId<'static> {
+    PropertyId::Custom(CustomPropertyName::Unknown(Ident(POINTER_EVENTS.into())))
+}
+
+/// Name of `text-wrap`, which lightningcss also parses as an unknown
+/// custom property.
+const TEXT_WRAP: &str = "text-wrap";
+
+/// The `PropertyId` under which `text-wrap` is stored.
+pub fn text_wrap_id() -> PropertyId<'static> {
+    PropertyId::Custom(CustomPropertyName::Unknown(Ident(TEXT_WRAP.into())))
+}
 
 /// Which sparse tree a CSS property belongs to.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -22,12 +69,22 @@ pub enum PropertyGroup {
     /// Display, flex-*, grid-*, gap, order, align-*, justify-*.
     /// Inherited: no.
     Layout,
-    /// Position, top/right/bottom/left, z-index.
+    /// Position, top/right/bottom/left, z-index, and the transforms and
+    /// filters that make containing blocks.
     /// Inherited: no.
     Position,
 }
 
 impl PropertyGroup {
+    /// Every group, in storage order.
+    pub const ALL: [Self; 5] = [
+        Self::Text,
+        Self::Background,
+        Self::BoxModel,
+        Self::Layout,
+        Self::Position,
+    ];
+
     /// Whether this group's properties are inherited per the CSS spec.
     /// When `true`, a query miss on a node walks to its sparse-tree parent.
     pub const fn is_inherited(self) -> bool {
@@ -35,6 +92,171 @@ impl PropertyGroup {
     }
 }
 
+/// Name of every property `classify` assigns a group, in group order.
+/// Lets callers enumerate the properties the database stores, which a
+/// `match` can't offer.
+pub const PROPERTY_NAMES: &[&str] = &[
+    // ── Text (inherited) ──
+    "font-family",
+    "font-size",
+    "font-weight",
+    "font-style",
+    "font-variant-caps",
+    "font",
+    "line-height",
+    "letter-spacing",
+    "word-spacing",
+    "color",
+    "text-align",
+    "text-indent",
+    "white-space",
+    "text-transform",
+    "direction",
+    "print-color-adjust",
+    "visibility",
+    "caret-color",
+    "accent-color",
+    "cursor",
+    "fill",
+    "fill-rule",
+    "fill-opacity",
+    "stroke",
+    "stroke-opacity",
+    "stroke-width",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "clip-rule",
+    "color-interpolation",
+    "shape-rendering",
+    "marker-start",
+    "marker-mid",
+    "marker-end",
+    "pointer-events",
+    "text-wrap",
+    "border-spacing",
+    // ── Background / visual ──
+    "background-color",
+    "background",
+    "background-image",
+    "background-position",
+    "background-position-x",
+    "background-position-y",
+    "background-size",
+    "background-repeat",
+    "border-top-color",
+    "border-right-color",
+    "border-bottom-color",
+    "border-left-color",
+    "border-color",
+    "border-top-style",
+    "border-right-style",
+    "border-bottom-style",
+    "border-left-style",
+    "border-style",
+    "border-top-left-radius",
+    "border-top-right-radius",
+    "border-bottom-left-radius",
+    "border-bottom-right-radius",
+    "border-radius",
+    "box-shadow",
+    "opacity",
+    "outline-color",
+    "outline-style",
+    "outline-width",
+    "outline-offset",
+    "overflow-anchor",
+    // ── Box model ──
+    "width",
+    "height",
+    "min-width",
+    "min-height",
+    "max-width",
+    "max-height",
+    "margin",
+    "margin-top",
+    "margin-right",
+    "margin-bottom",
+    "margin-left",
+    "margin-block",
+    "margin-block-start",
+    "margin-block-end",
+    "margin-inline",
+    "margin-inline-start",
+    "margin-inline-end",
+    "padding",
+    "padding-top",
+    "padding-right",
+    "padding-bottom",
+    "padding-left",
+    "padding-block",
+    "padding-block-start",
+    "padding-block-end",
+    "padding-inline",
+    "padding-inline-start",
+    "padding-inline-end",
+    "border-top-width",
+    "border-right-width",
+    "border-bottom-width",
+    "border-left-width",
+    "border-width",
+    "box-sizing",
+    // ── Layout mode ──
+    "display",
+    "flex-direction",
+    "flex-wrap",
+    "flex-flow",
+    "flex-grow",
+    "flex-shrink",
+    "flex-basis",
+    "flex",
+    "justify-content",
+    "align-items",
+    "align-self",
+    "align-content",
+    "order",
+    "grid-template-columns",
+    "grid-template-rows",
+    "grid-template-areas",
+    "grid-auto-columns",
+    "grid-auto-rows",
+    "grid-auto-flow",
+    "grid-column",
+    "grid-row",
+    "grid-column-start",
+    "grid-column-end",
+    "grid-row-start",
+    "grid-row-end",
+    "gap",
+    "row-gap",
+    "column-gap",
+    "overflow",
+    "overflow-x",
+    "overflow-y",
+    // ── Position ──
+    "position",
+    "top",
+    "right",
+    "bottom",
+    "left",
+    "inset-block-start",
+    "inset-block-end",
+    "inset-inline-start",
+    "inset-inline-end",
+    "inset",
+    "inset-block",
+    "inset-inline",
+    "z-index",
+    "transform",
+    "transform-origin",
+    "perspective",
+    "filter",
+    "backdrop-filter",
+    "float",
+];
+
 /// Classify a `PropertyId` into its property group.
 ///
 /// Returns `None` for properties we don't track in any sparse tree
@@ -61,13 +283,46 @@ pub fn classify(prop_id: &PropertyId<'static>) -> Option<PropertyGroup> {
         | PropertyId::WhiteSpace
         | PropertyId::TextTransform
         | PropertyId::Direction
-        | PropertyId::Visibility => Some(PropertyGroup::Text),
+        | PropertyId::PrintColorAdjust(_)
+        | PropertyId::Visibility
+        | PropertyId::CaretColor
+        | PropertyId::AccentColor
+        | PropertyId::Cursor
+        | PropertyId::ListStyleType
+        | PropertyId::ListStylePosition
+        | PropertyId::ListStyleImage
+        // SVG painting properties are inherited too.
+        | PropertyId::Fill
+        | PropertyId::FillRule
+        | PropertyId::FillOpacity
+        | PropertyId::Stroke
+        | PropertyId::StrokeOpacity
+        | PropertyId::StrokeWidth
+        | PropertyId::StrokeLinecap
+        | PropertyId::StrokeLinejoin
+        | PropertyId::StrokeMiterlimit
+        | PropertyId::StrokeDasharray
+        | PropertyId::StrokeDashoffset
+        | PropertyId::ClipRule
+        | PropertyId::ColorInterpolation
+        | PropertyId::ShapeRendering
+        | PropertyId::MarkerStart
+        | PropertyId::MarkerMid
+        | PropertyId::MarkerEnd
+        | PropertyId::BorderSpacing => Some(PropertyGroup::Text),
+        PropertyId::Custom(CustomPropertyName::Unknown(name))
+            if matches!(name.0.as_ref(), POINTER_EVENTS | TEXT_WRAP) =>
+        {
+            Some(PropertyGroup::Text)
+        }
 
         // ── Background / visual (non-inherited) ──────────────────
         PropertyId::BackgroundColor
         | PropertyId::Background
         | PropertyId::BackgroundImage
         | PropertyId::BackgroundPosition
+        | PropertyId::BackgroundPositionX
+        | PropertyId::BackgroundPositionY
         | PropertyId::BackgroundSize
         | PropertyId::BackgroundRepeat
         | PropertyId::BorderTopColor
@@ -90,6 +345,11 @@ pub fn classify(prop_id: &PropertyId<'static>) -> Option<PropertyGroup> {
         | PropertyId::OutlineColor
         | PropertyId::OutlineStyle
         | PropertyId::OutlineWidth => Some(PropertyGroup::Background),
+        PropertyId::Custom(CustomPropertyName::Unknown(name))
+            if matches!(name.0.as_ref(), OUTLINE_OFFSET | OVERFLOW_ANCHOR) =>
+        {
+            Some(PropertyGroup::Background)
+        }
 
         // ── Box model (non-inherited) ────────────────────────────
         PropertyId::Width
@@ -173,8 +433,36 @@ pub fn classify(prop_id: &PropertyId<'static>) -> Option<PropertyGroup> {
         | PropertyId::Inset
         | PropertyId::InsetBlock
         | PropertyId::InsetInline
-        | PropertyId::ZIndex => Some(PropertyGroup::Position),
+        | PropertyId::ZIndex
+        | PropertyId::Transform(_)
+        | PropertyId::TransformOrigin(_)
+        | PropertyId::Perspective(_)
+        | PropertyId::Filter(_)
+        | PropertyId::BackdropFilter(_) => Some(PropertyGroup::Position),
+        PropertyId::Custom(CustomPropertyName::Unknown(name)) if name.0.as_ref() == FLOAT => {
+            Some(PropertyGroup::Position)
+        }
 
         _ => None,
     }
 }
+
+/// Whether `prop_id` is inherited but only read when painting.
+///
+/// No box or text measurement changes with these. They sit in the `Text`
+/// group for inheritance, but a change to one needs a repaint, not a
+/// relayout.
+pub fn is_paint_only(prop_id: &PropertyId<'static>) -> bool {
+    matches!(
+        prop_id,
+        PropertyId::Color
+            | PropertyId::CaretColor
+            | PropertyId::AccentColor
+            | PropertyId::Cursor
+            | PropertyId::PrintColorAdjust(_)
+            | PropertyId::Fill
+            | PropertyId::FillOpacity
+            | PropertyId::Stroke
+            | PropertyId::StrokeOpacity
+    )
+}
@@ -163,6 +163,16 @@ impl SparseTree {
         true
     }
 
+    /// Remove a property from a DOM node. The node stays in the tree.
+    ///
+    /// Returns `true` if the property was present.
+    pub fn remove_property(&self, node: NodeId, prop_id: &PropertyId<'static>) -> bool {
+        let Some(local) = self.local_id(node) else {
+            return false;
+        };
+        self.props[local.0 as usize].remove(prop_id).is_some()
+    }
+
     /// Get a property for a DOM node (no inheritance — just this node).
     pub fn get_local(
         &self,
@@ -262,6 +272,26 @@ impl SparseTree {
         self.adopt_descendants(local, new_parent_local, is_dom_ancestor);
     }
 
+    /// Re-parent to `node` the members of this tree that are its DOM
+    /// descendants, for a node that entered the tree after they did.
+    ///
+    /// `is_dom_ancestor(a, b)` returns true if `a` is a DOM ancestor of
+    /// `b`.
+    pub fn adopt_dom_descendants(
+        &self,
+        node: NodeId,
+        is_dom_ancestor: &impl Fn(NodeId, NodeId) -> bool,
+    ) {
+        let Some(local) = self.local_id(node) else {
+            return;
+        };
+        let parent = self.relationships[local.0 as usize]
+            .parent
+            .load(Ordering::Acquire);
+        let parent = (parent != NO_NODE).then_some(LocalId(parent));
+        self.adopt_descendants(local, parent, is_dom_ancestor);
+    }
+
     /// Remove all properties for a DOM node and unlink it from the tree.
     pub fn remove_node(&self, node: NodeId) {
         if let Some((_, local)) = self.dom_to_local.remove(&node) {
@@ -467,6 +497,35 @@ impl SparseTree {
         }
     }
 
+    /// DOM IDs of every node currently in this tree.
+    pub fn dom_nodes(&self) -> Vec<NodeId> {
+        self.dom_to_local.iter().map(|entry| *entry.key()).collect()
+    }
+
+    /// Sparse-tree parent of a DOM node, if it is in this tree and linked.
+    pub fn parent_of(&self, node: NodeId) -> Option<NodeId> {
+        let local = self.local_id(node)?;
+        self.sparse_parent(local).map(|parent| self.dom_id(parent))
+    }
+
+    /// Sparse-tree children of a DOM node, in link order (not DOM order).
+    pub fn children_of(&self, node: NodeId) -> Vec<NodeId> {
+        let Some(local) = self.local_id(node) else {
+            return Vec::new();
+        };
+        let mut children = Vec::new();
+        let mut child_raw = self.relationships[local.0 as usize]
+            .first_child
+            .load(Ordering::Acquire);
+        while child_raw != NO_NODE {
+            children.push(self.dom_id(LocalId(child_raw)));
+            child_raw = self.relationships[child_raw as usize]
+                .next_sibling
+                .load(Ordering::Acquire);
+        }
+        children
+    }
+
     /// Collect the sparse-tree neighbors of a node: parent, children,
     /// and siblings. Returns only nodes present in this sparse tree.
     pub fn neighbors(&self, node: NodeId) -> Vec<NodeId> {
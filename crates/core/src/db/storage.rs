@@ -10,6 +10,7 @@ use crate::db::sparse_tree::SparseTree;
 use crate::db::tree_access::TreeAccess;
 use crate::{NodeId, Specificity};
 use lightningcss::properties::border::LineStyle;
+use lightningcss::properties::display::{Display, DisplayKeyword};
 use lightningcss::properties::{Property, PropertyId};
 use std::sync::Arc;
 
@@ -84,6 +85,7 @@ impl Database {
 
         let tree = self.tree_for_group(group);
         let dom_tree = &self.tree;
+        let is_new = !tree.contains(node);
 
         let changed = tree.set_property(node, property.clone(), specificity, |node_id| {
             // Walk up DOM ancestors to find the nearest one already in this sparse tree.
@@ -97,6 +99,15 @@ impl Database {
             None
         });
 
+        // Descendants that entered the tree first are linked past this
+        // node; they inherit through it now. Nodes still being parsed
+        // have no children, so streaming insertion skips the search.
+        if is_new && !dom_tree.children(node).is_empty() {
+            tree.adopt_dom_descendants(node, &|ancestor, descendant| {
+                self.is_dom_ancestor(ancestor, descendant)
+            });
+        }
+
         // Update style fingerprint when a non-inherited property changes.
         if changed && !group.is_inherited() {
             self.update_fingerprint(node, &prop_id);
@@ -105,6 +116,22 @@ impl Database {
         changed
     }
 
+    /// Remove a property from a node so it falls back to inheritance or
+    /// its initial value.
+    ///
+    /// Returns `true` if the property was stored.
+    pub fn remove_property(&self, node: NodeId, prop_id: &PropertyId<'static>) -> bool {
+        let Some(group) = classify(prop_id) else {
+            return false;
+        };
+
+        let removed = self.tree_for_group(group).remove_property(node, prop_id);
+        if removed && !group.is_inherited() {
+            self.update_fingerprint(node, prop_id);
+        }
+        removed
+    }
+
     /// Get the style fingerprint for a node.
     ///
     /// Nodes with the same fingerprint have identical non-inherited CSS
@@ -163,16 +190,8 @@ impl Database {
             None
         };
 
-        let is_ancestor = |ancestor: NodeId, descendant: NodeId| -> bool {
-            let mut current = dom_tree.parent(descendant);
-            while let Some(candidate) = current {
-                if candidate == ancestor {
-                    return true;
-                }
-                current = dom_tree.parent(candidate);
-            }
-            false
-        };
+        let is_ancestor =
+            |ancestor: NodeId, descendant: NodeId| self.is_dom_ancestor(ancestor, descendant);
 
         for tree in [
             &self.text,
@@ -185,6 +204,18 @@ impl Database {
         }
     }
 
+    /// Whether `ancestor` is a proper DOM ancestor of `descendant`.
+    fn is_dom_ancestor(&self, ancestor: NodeId, descendant: NodeId) -> bool {
+        let mut current = self.tree.parent(descendant);
+        while let Some(candidate) = current {
+            if candidate == ancestor {
+                return true;
+            }
+            current = self.tree.parent(candidate);
+        }
+        false
+    }
+
     /// Get a property for a node by `PropertyId`.
     ///
     /// For inherited groups (Text), walks up DOM ancestors via the
@@ -194,7 +225,8 @@ impl Database {
     /// the value explicitly set on this node.
     ///
     /// Applies computed-value dependencies: `border-*-width` returns
-    /// `None` when the corresponding `border-*-style` is absent or `none`.
+    /// `None` when the corresponding `border-*-style` is absent, `none` or
+    /// `hidden`.
     #[allow(
         clippy::needless_pass_by_value,
         reason = "matches lightningcss property_id() API"
@@ -204,7 +236,8 @@ impl Database {
         node: NodeId,
         prop_id: PropertyId<'static>,
     ) -> Option<Property<'static>> {
-        // CSS spec: border-width computes to 0 when border-style is none.
+        // CSS spec: border-width computes to 0 when border-style is none
+        // or hidden.
         if is_border_width_prop(&prop_id) && !self.has_border_style(node, &prop_id) {
             return None;
         }
@@ -225,8 +258,26 @@ impl Database {
         }
     }
 
-    /// Check if a node has a non-`none` border-style for the side
-    /// corresponding to the given border-width property.
+    /// Whether `node` generates a box: `false` when it or one of its DOM
+    /// ancestors computes to `display: none`, which takes the whole
+    /// subtree out of layout and painting.
+    pub fn generates_box(&self, node: NodeId) -> bool {
+        let subtree = DomAncestors {
+            current: Some(node),
+            tree: &self.tree,
+        };
+        !subtree
+            .filter(|&ancestor| self.layout.contains(ancestor))
+            .any(|ancestor| {
+                matches!(
+                    self.layout.get_local(ancestor, &PropertyId::Display),
+                    Some(Property::Display(Display::Keyword(DisplayKeyword::None)))
+                )
+            })
+    }
+
+    /// Check if a node has a border-style other than `none`/`hidden` for
+    /// the side corresponding to the given border-width property.
     fn has_border_style(&self, node: NodeId, width_prop_id: &PropertyId<'static>) -> bool {
         let style_prop_id = border_style_for_width(width_prop_id);
         // border-style lives in the Background group.
@@ -235,10 +286,10 @@ impl Database {
         };
         !matches!(
             prop,
-            Property::BorderTopStyle(LineStyle::None)
-                | Property::BorderRightStyle(LineStyle::None)
-                | Property::BorderBottomStyle(LineStyle::None)
-                | Property::BorderLeftStyle(LineStyle::None)
+            Property::BorderTopStyle(LineStyle::None | LineStyle::Hidden)
+                | Property::BorderRightStyle(LineStyle::None | LineStyle::Hidden)
+                | Property::BorderBottomStyle(LineStyle::None | LineStyle::Hidden)
+                | Property::BorderLeftStyle(LineStyle::None | LineStyle::Hidden)
         )
     }
 
@@ -321,7 +372,7 @@ pub fn is_css_initial_value(property: &Property<'static>) -> bool {
     matches!(
         property,
         // display: block is the default for flow elements
-        Property::Display(Display::Pair(DisplayPair { outside: DisplayOutside::Block, inside: DisplayInside::Flow, .. }))
+        Property::Display(Display::Pair(DisplayPair { outside: DisplayOutside::Block, inside: DisplayInside::Flow, is_list_item: false }))
         // width/height: auto is the initial value
         | Property::Width(Size::Auto)
         | Property::Height(Size::Auto)
@@ -0,0 +1,89 @@
+//! Embedder-supplied layout algorithms.
+//!
+//! A box with `display: layout(<name>)` places its children with the
+//! `CustomLayout` registered under `<name>` instead of block layout, in the
+//! spirit of the CSS Layout API's layout worklets. This lets experimental
+//! layouts be prototyped outside the layout queries.
+//!
+//! The box itself stays block-level: its width is resolved like a block's
+//! and its children are sized as block children of it. The custom layout
+//! only decides where the children go and how tall the box's content is.
+
+use crate::{NodeId, Subpixel};
+use lightningcss::properties::custom::{Token, TokenOrValue};
+use lightningcss::properties::{Property, PropertyId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Custom layouts by the name `display: layout(<name>)` selects them with.
+pub type CustomLayoutRegistry = HashMap<String, Arc<dyn CustomLayout>>;
+
+/// A layout algorithm for the children of a box.
+pub trait CustomLayout: Send + Sync {
+    /// Place `children`, the box's in-flow element children in DOM order,
+    /// within a content box `constraints` describes.
+    ///
+    /// Called whenever one of the children's positions or the box's height
+    /// is resolved, so it should be a pure function of its inputs.
+    fn layout(&self, constraints: &LayoutConstraints, children: &[LayoutChild]) -> LayoutFragments;
+}
+
+/// Space available to a custom layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutConstraints {
+    /// Width of the box's content box.
+    pub available_inline_size: Subpixel,
+    /// Height of the box's content box, when it doesn't depend on the
+    /// layout (an explicit `height`).
+    pub fixed_block_size: Option<Subpixel>,
+}
+
+/// A child handed to a custom layout, with its resolved border-box size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutChild {
+    pub node: NodeId,
+    pub inline_size: Subpixel,
+    pub block_size: Subpixel,
+}
+
+/// Result of a custom layout.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayoutFragments {
+    /// Height of the box's content box. Ignored when the box has an
+    /// explicit height.
+    pub block_size: Subpixel,
+    /// Border-box position of each placed child, relative to the box's
+    /// content box. Children left out are placed at its origin.
+    pub fragments: Vec<LayoutFragment>,
+}
+
+/// Where a custom layout put one child.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutFragment {
+    pub node: NodeId,
+    pub inline_offset: Subpixel,
+    pub block_offset: Subpixel,
+}
+
+/// The `<name>` of a `display: layout(<name>)` value.
+///
+/// lightningcss doesn't know `layout()`, so such a value is kept as an
+/// unparsed `display` declaration.
+pub fn custom_layout_name<'prop>(display: &'prop Property<'_>) -> Option<&'prop str> {
+    let Property::Unparsed(unparsed) = display else {
+        return None;
+    };
+    if unparsed.property_id != PropertyId::Display {
+        return None;
+    }
+    let [TokenOrValue::Function(function)] = unparsed.value.0.as_slice() else {
+        return None;
+    };
+    if !function.name.eq_ignore_ascii_case("layout") {
+        return None;
+    }
+    match function.arguments.0.as_slice() {
+        [TokenOrValue::Token(Token::Ident(name))] => Some(name.as_ref()),
+        _ => None,
+    }
+}
@@ -0,0 +1,75 @@
+//! Resolution failure diagnostics.
+//!
+//! Cross-node formulas (`Related`, `Imperative`) can ask for a value that
+//! is already being computed further up the stack, e.g. a margin query
+//! that re-enters the size query it is part of. The resolver detects the
+//! re-entry, resolves the inner request to `None` instead of recursing, and
+//! records the chain of requests that led there.
+
+use crate::{Formula, NodeId};
+use std::fmt;
+use std::ptr::from_ref;
+
+/// One in-progress cross-node request: a formula being resolved for a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResolveFrame {
+    pub node: NodeId,
+    /// Address of the `static` formula, identifying it within a run.
+    pub formula: usize,
+}
+
+impl ResolveFrame {
+    pub(crate) fn new(node: NodeId, formula: &'static Formula) -> Self {
+        Self {
+            node,
+            formula: from_ref::<Formula>(formula) as usize,
+        }
+    }
+}
+
+/// Why a formula resolved to `None` without being evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolveFailure {
+    /// The request was already in progress (a dependency cycle).
+    Cycle,
+    /// The recursion limit was hit before any request repeated.
+    DepthLimit,
+}
+
+/// A recorded resolution failure with the request chain that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveDiagnostic {
+    pub failure: ResolveFailure,
+    /// Cross-node requests, outermost first. For a cycle this starts and
+    /// ends with the repeated request.
+    pub path: Vec<ResolveFrame>,
+}
+
+impl ResolveDiagnostic {
+    /// The diagnostic as text, naming each node with `node_name`, e.g.
+    /// by its DOM path, which unlike its ID is stable across runs.
+    pub fn describe(&self, node_name: &dyn Fn(NodeId) -> String) -> String {
+        let kind = match self.failure {
+            ResolveFailure::Cycle => "dependency cycle",
+            ResolveFailure::DepthLimit => "recursion limit",
+        };
+        let path: Vec<String> = self
+            .path
+            .iter()
+            .map(|frame| {
+                format!(
+                    "node {} (formula {:#x})",
+                    node_name(frame.node),
+                    frame.formula
+                )
+            })
+            .collect();
+        format!("{kind}: {}", path.join(" -> "))
+    }
+}
+
+impl fmt::Display for ResolveDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe(&|node| node.0.to_string()))
+    }
+}
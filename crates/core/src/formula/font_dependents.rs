@@ -0,0 +1,74 @@
+//! Lengths resolved against a font.
+//!
+//! `em` and `lh` lengths resolve against the font of the box they are on,
+//! `rem` and `rlh` against the root element's. Layout formulas name the
+//! length properties, not the font size they were resolved with, so a
+//! font size change would leave such lengths stale. Value resolution
+//! records each such use here instead, and the boxes to re-resolve on a
+//! font change are looked up rather than found by walking the tree.
+
+use crate::NodeId;
+use std::collections::HashSet;
+use std::sync::{Mutex, PoisonError};
+
+/// The font a length was resolved against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontRelative {
+    /// `em` or `lh`: the font of the box the length is on.
+    Own,
+    /// `rem` or `rlh`: the root element's font.
+    Root,
+}
+
+/// Boxes with lengths resolved against a font, as recorded during value
+/// resolution. Shared between resolvers, so recording takes `&self`.
+#[derive(Debug, Default)]
+pub struct FontDependents {
+    sets: Mutex<DependentSets>,
+}
+
+#[derive(Debug, Default)]
+struct DependentSets {
+    own: HashSet<NodeId>,
+    root: HashSet<NodeId>,
+}
+
+impl FontDependents {
+    /// Record that a length of `node` was resolved against `font`.
+    pub fn record(&self, node: NodeId, font: FontRelative) {
+        let mut sets = self.sets.lock().unwrap_or_else(PoisonError::into_inner);
+        match font {
+            FontRelative::Own => sets.own.insert(node),
+            FontRelative::Root => sets.root.insert(node),
+        };
+    }
+
+    /// Whether a length of `node` was resolved against its own font.
+    pub fn uses_own_font(&self, node: NodeId) -> bool {
+        let sets = self.sets.lock().unwrap_or_else(PoisonError::into_inner);
+        sets.own.contains(&node)
+    }
+
+    /// Boxes with a length resolved against the root element's font, in
+    /// node order.
+    pub fn root_font_users(&self) -> Vec<NodeId> {
+        let sets = self.sets.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut users: Vec<NodeId> = sets.root.iter().copied().collect();
+        users.sort_unstable();
+        users
+    }
+
+    /// Forget the uses recorded for `node`, e.g. once it has no box.
+    pub fn forget(&self, node: NodeId) {
+        let mut sets = self.sets.lock().unwrap_or_else(PoisonError::into_inner);
+        sets.own.remove(&node);
+        sets.root.remove(&node);
+    }
+
+    /// Forget every recorded use, e.g. for a new document.
+    pub fn clear(&self) {
+        let mut sets = self.sets.lock().unwrap_or_else(PoisonError::into_inner);
+        sets.own.clear();
+        sets.root.clear();
+    }
+}
@@ -11,19 +11,40 @@
 //! - Formulas are pure arithmetic over values from self/parent/children
 //! - `PropertyResolver` trait provides CSS property access and tree navigation
 //! - Formulas are non-generic — they use `NodeId` + `&dyn PropertyResolver`
-//! - No separate dependency tracking — the formula tree is the dependency graph
+//! - No separate dependency tracking — the formula tree is the dependency graph,
+//!   except for font-relative lengths (see `FontDependents`) and lengths
+//!   relative to a viewport (see `ViewportDependents`)
 //! - **Construct formulas using the macros only** — never build `Formula`
 //!   variants directly in query code.
 
 #[macro_use]
 mod macros;
+mod custom_layout;
+mod diagnostics;
+mod font_dependents;
 mod resolver;
-
+mod scratch;
+mod stats;
+mod text_wrap;
+mod viewport_dependents;
+
+pub use custom_layout::{
+    CustomLayout, CustomLayoutRegistry, LayoutChild, LayoutConstraints, LayoutFragment,
+    LayoutFragments, custom_layout_name,
+};
+pub use diagnostics::{ResolveDiagnostic, ResolveFailure, ResolveFrame};
+pub use font_dependents::{FontDependents, FontRelative};
 pub use resolver::{ResolveContext, FONT_SIZE_FORMULA};
+pub use stats::ResolveStats;
+pub use text_wrap::TextWrapStyle;
+pub use viewport_dependents::{ViewportDependents, ViewportKind};
 
-use lightningcss::properties::PropertyId;
+use lightningcss::properties::custom::{Token, TokenOrValue};
+use lightningcss::properties::position::Position;
+use lightningcss::properties::{Property, PropertyId};
 
-use crate::{MultiRelationship, NodeId, SingleRelationship, Subpixel};
+use crate::{MultiRelationship, NodeId, SingleRelationship, Subpixel, float_id, text_wrap_id};
+use std::sync::Arc;
 
 // ============================================================================
 // PropertyResolver trait
@@ -68,12 +89,34 @@ pub trait PropertyResolver: Send + Sync {
     /// Get the viewport height in pixels.
     fn viewport_height(&self) -> u32;
 
+    /// `(width, height)` of the viewport of `kind`, in pixels. The default
+    /// has no retractable browser UI: every kind is the layout viewport.
+    fn viewport_size(&self, _kind: ViewportKind) -> (u32, u32) {
+        (self.viewport_width(), self.viewport_height())
+    }
+
     /// Whether a node is intrinsic (text node, replaced element).
     fn is_intrinsic(&self, node: NodeId) -> bool;
 
     /// Whether a node is a DOM element (not text, comment, or document).
     fn is_element(&self, node: NodeId) -> bool;
 
+    /// Whether a node generates a box. `false` for `display: none` and
+    /// everything inside it, which layout doesn't visit.
+    fn generates_box(&self, _node: NodeId) -> bool {
+        true
+    }
+
+    /// Whether a node is a preserved segment break (e.g. a newline in
+    /// `white-space: pre` text), which ends the line it sits on.
+    fn forces_line_break(&self, _node: NodeId) -> bool {
+        false
+    }
+
+    /// Get the local tag name of an element (e.g. `"body"`), or `None`
+    /// for non-element nodes.
+    fn tag_name(&self, node: NodeId) -> Option<String>;
+
     /// Get the text content of a node, if it is a text node.
     fn text_content(&self, node: NodeId) -> Option<String>;
 
@@ -88,6 +131,221 @@ pub trait PropertyResolver: Send + Sync {
         font_size: f32,
         max_width: Option<f32>,
     ) -> Option<TextMeasurement>;
+
+    /// Measure the text of `node`'s `::marker` box in the marker's font.
+    /// `None` if `node` has no marker or there is no text backend.
+    fn measure_marker(&self, _node: NodeId) -> Option<TextMeasurement> {
+        None
+    }
+
+    /// Measure the text of `node`'s `::before` or `::after` box in its
+    /// font. `None` if `node` generates no such box or there is no text
+    /// backend.
+    fn measure_generated(&self, _node: NodeId, _pseudo: GeneratedBox) -> Option<TextMeasurement> {
+        None
+    }
+
+    /// The sizes of `ch` and `ex` in `node`'s font at `font_size`, or
+    /// `None` without a text backend, in which case both are `0.5em`.
+    fn font_unit_sizes(&self, _node: NodeId, _font_size: f32) -> Option<FontUnitSizes> {
+        None
+    }
+
+    /// Split text into the pieces that can't be broken across lines, whose
+    /// widest is the text's min-content width. The default breaks at
+    /// whitespace only; resolvers with Unicode line breaking or
+    /// `white-space` support override it.
+    fn unbreakable_segments<'text>(&self, _node: NodeId, text: &'text str) -> Vec<&'text str> {
+        text.split_whitespace().collect()
+    }
+
+    /// How the lines of a text node break, from its `text-wrap`. The
+    /// default fills each line, as without a text backend there are no
+    /// line breaks to choose between.
+    fn text_wrap_style(&self, _node: NodeId) -> TextWrapStyle {
+        TextWrapStyle::Auto
+    }
+
+    /// `(horizontal, vertical)` `border-spacing` of a table, in pixels.
+    /// The default has no spacing.
+    fn border_spacing(&self, _node: NodeId) -> (Subpixel, Subpixel) {
+        (Subpixel::ZERO, Subpixel::ZERO)
+    }
+
+    /// Whether a property is set to a percentage of the containing block's
+    /// inline size, or a math function with one in it: e.g.
+    /// `margin-left: 10%` or `width: calc(100% - 2em)`. Only for properties
+    /// whose percentages refer to the containing block's width (margins,
+    /// paddings and widths).
+    fn has_inline_percentage(&self, _node: NodeId, _prop_id: &PropertyId<'static>) -> bool {
+        false
+    }
+
+    /// The value of a property `has_inline_percentage` holds for, with its
+    /// percentages of `basis`, the containing block's used inline size.
+    fn resolve_inline_percentage(
+        &self,
+        _node: NodeId,
+        _prop_id: &PropertyId<'static>,
+        _basis: Subpixel,
+    ) -> Option<Subpixel> {
+        None
+    }
+
+    /// Debug switches that alter which layout formulas are selected.
+    fn layout_flags(&self) -> LayoutFlags {
+        LayoutFlags::default()
+    }
+
+    /// Text autosizing applied to used font sizes, or `None` to lay out
+    /// text at its computed font size.
+    fn text_autosizing(&self) -> Option<TextAutosizing> {
+        None
+    }
+
+    /// The custom layout placing `node`'s children, if its `display` is
+    /// `layout(<name>)` and a layout is registered under that name.
+    fn custom_layout(&self, _node: NodeId) -> Option<Arc<dyn CustomLayout>> {
+        None
+    }
+
+    /// Natural size of a replaced element, once the embedder knows it
+    /// (e.g. an image's header has arrived). Such elements are intrinsic:
+    /// they sit on line boxes like text, sized by `IntrinsicSize::used_size`.
+    fn intrinsic_size(&self, _node: NodeId) -> Option<IntrinsicSize> {
+        None
+    }
+
+    /// Note that a length of `node` was resolved against `font`, so that
+    /// a change of that font size re-resolves the node.
+    fn record_font_dependency(&self, _node: NodeId, _font: FontRelative) {}
+
+    /// Note that a length of `node` was resolved against the viewport of
+    /// `kind`, so that resizing it re-resolves the node.
+    fn record_viewport_dependency(&self, _node: NodeId, _kind: ViewportKind) {}
+
+    /// The side a node floats to, or `None` for `float: none` or when
+    /// `LayoutFlags::disable_floats` is set.
+    fn float_side(&self, node: NodeId) -> Option<FloatSide> {
+        if self.layout_flags().disable_floats {
+            return None;
+        }
+        let Some(Property::Custom(custom)) = self.get_css_property(node, &float_id()) else {
+            return None;
+        };
+        match custom.value.0.as_slice() {
+            [TokenOrValue::Token(Token::Ident(ident))] => match ident.as_ref() {
+                "left" => Some(FloatSide::Left),
+                "right" => Some(FloatSide::Right),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether a node is taken out of normal flow (absolutely positioned,
+    /// fixed, floated, or a fieldset's rendered legend), so it doesn't push
+    /// its following siblings down.
+    fn is_out_of_flow(&self, node: NodeId) -> bool {
+        self.is_absolute_or_floated(node) || self.is_rendered_legend(node)
+    }
+
+    /// Whether a node is absolutely positioned, fixed, or floated.
+    fn is_absolute_or_floated(&self, node: NodeId) -> bool {
+        matches!(
+            self.get_css_property(node, &PropertyId::Position),
+            Some(Property::Position(Position::Absolute | Position::Fixed))
+        ) || self.float_side(node).is_some()
+    }
+
+    /// Whether a node is the rendered legend of its fieldset: the first
+    /// `legend` child that is neither floated nor absolutely positioned.
+    /// It sits over the fieldset's top border instead of in its content.
+    fn is_rendered_legend(&self, _node: NodeId) -> bool {
+        false
+    }
+}
+
+/// Which edge of its container a floated box is placed against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatSide {
+    Left,
+    Right,
+}
+
+// ============================================================================
+// Layout debug flags
+// ============================================================================
+
+/// Switches for isolating layout subsystems when bisecting a geometry diff.
+///
+/// All flags default to `false` (normal, spec-conformant layout). Queries
+/// read them through `PropertyResolver::layout_flags`, so toggling a flag
+/// only changes which formulas are selected — cached values must be
+/// cleared by the owner of the `ResolveContext`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayoutFlags {
+    /// Treat every vertical margin as non-collapsing: parent/first-child,
+    /// parent/last-child and adjacent-sibling margins are all added.
+    pub disable_margin_collapsing: bool,
+    /// Lay out every box as if it had `float: none`, in normal flow.
+    pub disable_floats: bool,
+    /// Re-resolve every known node on each change instead of propagating
+    /// incrementally from the changed node.
+    pub force_full_layout: bool,
+    /// Check the incrementally maintained paint order against a full
+    /// rebuild after every change, recording where they differ.
+    pub verify_paint_order: bool,
+    /// Record the metrics of the container each box is laid out against,
+    /// for `LayoutState::container_metrics`.
+    pub record_container_metrics: bool,
+}
+
+/// Whether `prop_id` changes how text is measured: the properties an
+/// `InlineMeasure` formula reads through `PropertyResolver::measure_text`.
+pub fn affects_text_measurement(prop_id: &PropertyId<'static>) -> bool {
+    matches!(
+        prop_id,
+        PropertyId::FontSize
+            | PropertyId::FontFamily
+            | PropertyId::FontWeight
+            | PropertyId::FontStyle
+            | PropertyId::FontVariantCaps
+            | PropertyId::LineHeight
+            | PropertyId::WhiteSpace
+            | PropertyId::LetterSpacing
+            | PropertyId::WordSpacing
+            | PropertyId::TextTransform
+    ) || *prop_id == text_wrap_id()
+}
+
+// ============================================================================
+// Text autosizing
+// ============================================================================
+
+/// Font size boost for laying out pages written for desktop widths on a
+/// narrow viewport.
+///
+/// Applied when text is measured, so computed values (and `em` lengths
+/// derived from them) are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextAutosizing {
+    /// Viewports wider than this, in px, are laid out unadjusted.
+    pub max_viewport_width: u32,
+    /// Factor every computed font size is multiplied by.
+    pub scale: f32,
+    /// Smallest used font size in px, applied after scaling.
+    pub min_font_size: f32,
+}
+
+impl TextAutosizing {
+    /// Used font size for text with the given computed font size.
+    pub fn used_font_size(&self, font_size: f32, viewport_width: u32) -> f32 {
+        if viewport_width > self.max_viewport_width {
+            return font_size;
+        }
+        (font_size * self.scale).max(self.min_font_size)
+    }
 }
 
 // ============================================================================
@@ -137,6 +395,68 @@ pub struct TextMeasurement {
     pub ascent: f32,
     /// Descent below the baseline in pixels (positive downward).
     pub descent: f32,
+    /// The text asks for small capitals the font doesn't have, so they
+    /// were measured as scaled-down capitals and must be drawn that way.
+    pub synthesized_small_caps: bool,
+}
+
+/// A box generated by a pseudo-element with `content`, laid out as the
+/// first or last inline child of its element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeneratedBox {
+    /// `::before`
+    Before,
+    /// `::after`
+    After,
+}
+
+/// What the font-metric units resolve to for a node's font, in pixels.
+///
+/// Returned by `PropertyResolver::font_unit_sizes`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontUnitSizes {
+    /// `1ch`: the advance of the "0" glyph.
+    pub zero_advance: f32,
+    /// `1ex`: the font's x-height.
+    pub x_height: f32,
+}
+
+// ============================================================================
+// Replaced elements
+// ============================================================================
+
+/// Natural size of a replaced element's content, e.g. the pixel
+/// dimensions from an image's header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntrinsicSize {
+    pub width: Subpixel,
+    pub height: Subpixel,
+}
+
+impl IntrinsicSize {
+    /// Used content-box size given the specified `width` and `height`
+    /// (`None` for `auto`).
+    ///
+    /// CSS 2.2 §10.3.2 / §10.6.2: an auto dimension takes the natural size,
+    /// or keeps the natural aspect ratio when the other one is specified.
+    pub fn used_size(
+        self,
+        width: Option<Subpixel>,
+        height: Option<Subpixel>,
+    ) -> (Subpixel, Subpixel) {
+        match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            (Some(width), None) if self.width > Subpixel::ZERO => (
+                width,
+                Subpixel::from_f32(width.to_f32() * self.height.to_f32() / self.width.to_f32()),
+            ),
+            (None, Some(height)) if self.height > Subpixel::ZERO => (
+                Subpixel::from_f32(height.to_f32() * self.width.to_f32() / self.height.to_f32()),
+                height,
+            ),
+            (width, height) => (width.unwrap_or(self.width), height.unwrap_or(self.height)),
+        }
+    }
 }
 
 // ============================================================================
@@ -297,8 +617,9 @@ pub enum Formula {
     /// (iteration, conditionals on computed values, multi-step algorithms
     /// like CSS Flexbox §9.7 freeze-and-redistribute).
     ///
-    /// The function returns results for multiple nodes at once. The resolver
-    /// inserts all of them into the cache, so sibling lookups are O(1).
+    /// The function may return results for multiple nodes at once. While a
+    /// batch is open (`ResolveContext::begin_batch`), the resolver keeps the
+    /// other nodes' results, so resolving them next is O(1).
     Imperative(ImperativeFn),
 }
 
@@ -452,15 +773,7 @@ impl Formula {
                 // Conservative: assume they might depend on any property
                 true
             }
-            Formula::InlineMeasure(_, _) => matches!(
-                prop_id,
-                PropertyId::FontSize
-                    | PropertyId::FontFamily
-                    | PropertyId::FontWeight
-                    | PropertyId::FontStyle
-                    | PropertyId::LineHeight
-                    | PropertyId::WhiteSpace
-            ),
+            Self::InlineMeasure(_, _) => affects_text_measurement(prop_id),
             Formula::Constant(_) | Formula::ViewportWidth | Formula::ViewportHeight => false,
         }
     }
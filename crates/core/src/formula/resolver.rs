@@ -6,22 +6,48 @@
 //! value may be stale, evict the relevant cache entries and
 //! re-resolve the formula.
 
+use super::diagnostics::{ResolveDiagnostic, ResolveFailure, ResolveFrame};
+use super::scratch::Scratch;
+use super::stats::ResolveStats;
+use super::text_wrap::wrap_text;
 use crate::{
-    Aggregation, Formula, FormulaList, LineAggregateParams, LineItemAggregateParams, MeasureAxis,
-    MeasureMode, MultiRelationship, NodeId, Operation, PrevLinesAggregateParams,
+    Aggregation, Formula, FormulaList, GeneratedBox, LineAggregateParams, LineItemAggregateParams,
+    MeasureAxis, MeasureMode, MultiRelationship, NodeId, Operation, PrevLinesAggregateParams,
     PropertyResolver, QueryFn, SingleRelationship, Subpixel,
 };
 use lightningcss::properties::{Property, PropertyId};
 use lightningcss::vendor_prefix::VendorPrefix;
 use std::collections::HashMap;
+use std::mem;
 use std::ptr::from_ref;
+use std::sync::Arc;
+
+/// Nesting depth at which resolution gives up.
+const MAX_DEPTH: u32 = 200;
+
+/// Diagnostics kept before further failures are dropped.
+const MAX_DIAGNOSTICS: usize = 64;
 
 /// Per-node cache: maps formula pointer → resolved value.
 type NodeCache = HashMap<usize, Subpixel>;
 
-/// Font-size formula resolved explicitly by InlineMeasure.
+/// Font-size formula resolved explicitly by `InlineMeasure`: the computed
+/// font size with text autosizing applied.
 /// Public so the renderer can use the same pointer for invalidation.
-pub static FONT_SIZE_FORMULA: Formula = Formula::CssValue(PropertyId::FontSize);
+pub static FONT_SIZE_FORMULA: Formula = Formula::Imperative(used_font_size);
+
+/// Used font size of `node`'s text.
+fn used_font_size(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    _resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let font_size = ctx.get_property(node, &PropertyId::FontSize)?;
+    let used = ctx.text_autosizing().map_or(font_size, |autosizing| {
+        Subpixel::from_f32(autosizing.used_font_size(font_size.to_f32(), ctx.viewport_width()))
+    });
+    Some(vec![(node, used)])
+}
 
 /// Key for cached line assignments: (parent_node, item_main_size_fn_ptr, available_main_ptr, gap_ptr).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -34,8 +60,9 @@ struct LineCacheKey {
 
 /// Cached line assignment for a parent's children.
 /// Each inner `Vec<usize>` contains the child indices on that line.
+/// Shared with the aggregates that read it rather than copied for each.
 struct LineAssignment {
-    lines: Vec<Vec<usize>>,
+    lines: Arc<Vec<Vec<usize>>>,
 }
 
 /// Key for cached prefix-sum computations: (parent_node, aggregate_formula_ptr).
@@ -45,11 +72,31 @@ struct PrefixKey {
     formula_ptr: usize,
 }
 
-/// Cached prefix values for all children of a parent.
+/// Cached prefix values for the leading children of a parent, extended as
+/// later children ask for theirs.
 /// Each child's value is the aggregate of all previous siblings.
+#[derive(Default)]
 struct PrefixValues {
     /// Map from child NodeId to the prefix aggregate up to (not including) that child.
     values: HashMap<NodeId, Subpixel>,
+    /// Children whose contributions are in `total`, in order.
+    children: Vec<NodeId>,
+    /// Aggregate of all of `children`.
+    total: Subpixel,
+}
+
+impl PrefixValues {
+    /// Drop the contributions of `child` and the children after it. The
+    /// prefix values up to `child` stay valid.
+    fn truncate_from(&mut self, child: NodeId) {
+        let Some(index) = self.children.iter().position(|&known| known == child) else {
+            return;
+        };
+        self.total = self.values[&child];
+        for later in self.children.drain(index..) {
+            self.values.remove(&later);
+        }
+    }
 }
 
 /// Context for formula resolution with memoization.
@@ -69,6 +116,13 @@ pub struct ResolveContext {
     /// Computed once per (parent, aggregate_formula), reused for all children.
     prefix_cache: HashMap<PrefixKey, PrefixValues>,
 
+    /// Values imperative formulas laid out for nodes other than the one
+    /// they were resolved for, reused until the open batches end.
+    batch_values: HashMap<NodeId, NodeCache>,
+
+    /// Calls to `begin_batch` not yet matched by `end_batch`.
+    open_batches: u32,
+
     /// Viewport width in pixels.
     pub viewport_width: u32,
     /// Viewport height in pixels.
@@ -76,6 +130,22 @@ pub struct ResolveContext {
 
     /// Debug: recursion depth counter.
     depth: u32,
+
+    /// Cross-node requests currently being resolved, outermost first.
+    in_progress: Vec<ResolveFrame>,
+
+    /// Failures since the last `take_diagnostics`.
+    diagnostics: Vec<ResolveDiagnostic>,
+
+    /// Query for a block's content-box width, used as the line length when
+    /// wrapping text. Without one, only explicit widths are honoured.
+    available_inline_size: Option<QueryFn>,
+
+    /// Work done since the last `reset_stats`.
+    stats: ResolveStats,
+
+    /// Buffers aggregates collect their values into.
+    scratch: Scratch,
 }
 
 impl ResolveContext {
@@ -85,12 +155,26 @@ impl ResolveContext {
             cache: HashMap::new(),
             line_cache: HashMap::new(),
             prefix_cache: HashMap::new(),
+            batch_values: HashMap::new(),
+            open_batches: 0,
             viewport_width,
             viewport_height,
             depth: 0,
+            in_progress: Vec::new(),
+            diagnostics: Vec::new(),
+            available_inline_size: None,
+            stats: ResolveStats::default(),
+            scratch: Scratch::default(),
         }
     }
 
+    /// Set the query that gives a containing block's content-box width, so
+    /// text wraps at the resolved inline size of its block rather than at
+    /// the block's specified `width`.
+    pub fn set_available_inline_size(&mut self, query: QueryFn) {
+        self.available_inline_size = Some(query);
+    }
+
     /// Invalidate cached values for specific formulas on a node.
     ///
     /// Given a list of formulas, removes only those formulas' cached values
@@ -107,6 +191,25 @@ impl ResolveContext {
         // since children's values may have changed.
         self.line_cache.retain(|key, _| key.parent != node);
         self.prefix_cache.retain(|key, _| key.parent != node);
+        self.batch_values.clear();
+    }
+
+    /// Keep the values imperative formulas lay out for other nodes until
+    /// the matching `end_batch`, so resolving those nodes reuses them.
+    ///
+    /// Only valid while nothing the formulas read changes; invalidating
+    /// any cached value drops the kept ones.
+    pub fn begin_batch(&mut self) {
+        self.open_batches += 1;
+    }
+
+    /// End a batch started by `begin_batch`, dropping the kept values once
+    /// no batch is open.
+    pub fn end_batch(&mut self) {
+        self.open_batches = self.open_batches.saturating_sub(1);
+        if self.open_batches == 0 {
+            self.batch_values.clear();
+        }
     }
 
     /// Look up a previously resolved value from the cache.
@@ -123,32 +226,107 @@ impl ResolveContext {
     pub fn invalidate_parent_aggregates(&mut self, parent: NodeId) {
         self.prefix_cache.retain(|key, _| key.parent != parent);
         self.line_cache.retain(|key, _| key.parent != parent);
+        self.batch_values.clear();
+    }
+
+    /// Invalidate the aggregates over `parent`'s children that include
+    /// `child`. Prefix sums up to `child` don't, so they're kept, and only
+    /// the children after it are summed again.
+    pub fn invalidate_aggregates_from(&mut self, parent: NodeId, child: NodeId) {
+        for (key, prefix) in &mut self.prefix_cache {
+            if key.parent == parent {
+                prefix.truncate_from(child);
+            }
+        }
+        self.line_cache.retain(|key, _| key.parent != parent);
+        self.batch_values.clear();
     }
 
     /// Clear all caches. Call before starting a fresh resolution pass.
     pub fn clear_cache(&mut self) {
+        self.diagnostics.clear();
         self.cache.clear();
         self.line_cache.clear();
         self.prefix_cache.clear();
+        self.batch_values.clear();
+        self.scratch.reset();
     }
 
-    /// Resolve a formula for a node. No caching.
+    /// Resolve a formula for a node. Nested formulas are always recomputed;
+    /// the result of a top-level call is recorded for `get_cached`.
+    ///
+    /// Returns `None` (and records a diagnostic) instead of recursing when
+    /// a cross-node request is already in progress or the nesting limit is
+    /// reached.
     pub fn resolve(
         &mut self,
         formula: &'static Formula,
         node: NodeId,
         ctx: &dyn PropertyResolver,
     ) -> Option<Subpixel> {
-        self.depth += 1;
-        if self.depth > 200 {
-            self.depth -= 1;
+        let frame = matches!(formula, Formula::Related(..) | Formula::Imperative(_))
+            .then(|| ResolveFrame::new(node, formula));
+        if let Some(frame) = frame
+            && let Some(start) = self.in_progress.iter().position(|&open| open == frame)
+        {
+            let mut path = self.in_progress[start..].to_vec();
+            path.push(frame);
+            self.report(ResolveFailure::Cycle, path);
             return None;
         }
+        if self.depth >= MAX_DEPTH {
+            self.report(ResolveFailure::DepthLimit, self.in_progress.clone());
+            return None;
+        }
+
+        if self.depth == 0 {
+            self.stats.top_level += 1;
+        }
+        self.stats.evaluations += 1;
+        self.depth += 1;
+        self.in_progress.extend(frame);
         let value = self.resolve_inner(formula, node, ctx);
+        if frame.is_some() {
+            self.in_progress.pop();
+        }
         self.depth -= 1;
+        if self.depth == 0
+            && let Some(val) = value
+        {
+            let formula_ptr = from_ref::<Formula>(formula) as usize;
+            self.cache.entry(node).or_default().insert(formula_ptr, val);
+        }
         value
     }
 
+    fn report(&mut self, failure: ResolveFailure, path: Vec<ResolveFrame>) {
+        if self.diagnostics.len() < MAX_DIAGNOSTICS {
+            self.diagnostics.push(ResolveDiagnostic { failure, path });
+        }
+    }
+
+    /// Resolution failures (cycles, runaway recursion) recorded since the
+    /// last call, oldest first. At most the first 64 are kept.
+    pub fn take_diagnostics(&mut self) -> Vec<ResolveDiagnostic> {
+        mem::take(&mut self.diagnostics)
+    }
+
+    /// Formula evaluations since the last `reset_stats`. Not affected by
+    /// `clear_cache`.
+    pub fn stats(&self) -> ResolveStats {
+        self.stats
+    }
+
+    /// Count a node skipped because it generates no box.
+    pub fn record_skipped_node(&mut self) {
+        self.stats.skipped_nodes += 1;
+    }
+
+    /// Zero the evaluation counters.
+    pub fn reset_stats(&mut self) {
+        self.stats = ResolveStats::default();
+    }
+
     /// Navigate to a related node from `node` using a `SingleRelationship`.
     fn navigate_single(
         &self,
@@ -161,7 +339,8 @@ impl ResolveContext {
             SingleRelationship::Parent => ctx.parent(node).unwrap_or(NodeId(0)),
             SingleRelationship::PrevSibling => {
                 // Find the closest previous element sibling that participates
-                // in layout (must be a DOM element, not text/comment/display:none).
+                // in flow layout (must be a DOM element, not text/comment/
+                // display:none, and not floated or absolutely positioned).
                 ctx.prev_siblings(node)
                     .into_iter()
                     .find(|&id| {
@@ -174,6 +353,7 @@ impl ResolveContext {
                                     ),
                                 ))
                             )
+                            && !ctx.is_out_of_flow(id)
                     })
                     .unwrap_or(node)
             }
@@ -306,9 +486,9 @@ impl ResolveContext {
                     }
                 })
             }
-            Formula::CssValue(prop_id) => ctx.get_property(node, prop_id),
+            Formula::CssValue(prop_id) => self.css_value(node, prop_id, ctx),
             Formula::CssValueOrDefault(prop_id, default) => {
-                Some(ctx.get_property(node, prop_id).unwrap_or(*default))
+                Some(self.css_value(node, prop_id, ctx).unwrap_or(*default))
             }
             Formula::Related(rel, query_fn) => {
                 self.resolve_related(node, *rel, *query_fn, ctx)
@@ -325,13 +505,28 @@ impl ResolveContext {
                 self.resolve_prev_lines_aggregate(params, node, ctx)
             }
             Formula::Imperative(func) => {
-                let results = func(node, ctx, &mut |f, n| self.resolve(f, n, ctx))?;
                 let formula_ptr = from_ref::<Formula>(formula) as usize;
+                if let Some(&val) = self
+                    .batch_values
+                    .get(&node)
+                    .and_then(|values| values.get(&formula_ptr))
+                {
+                    return Some(val);
+                }
+                let results = func(node, ctx, &mut |f, n| self.resolve(f, n, ctx))?;
                 let mut my_value = None;
-                for &(n, val) in &results {
-                    self.cache.entry(n).or_default().insert(formula_ptr, val);
-                    if n == node {
+                for (laid_out, val) in results {
+                    if laid_out == node {
+                        self.cache.entry(node).or_default().insert(formula_ptr, val);
                         my_value = Some(val);
+                    } else if self.open_batches > 0 {
+                        // Other nodes' values stay out of `cache` until they
+                        // are resolved themselves, which is what the renderer
+                        // compares their new boxes against.
+                        self.batch_values
+                            .entry(laid_out)
+                            .or_default()
+                            .insert(formula_ptr, val);
                     }
                 }
                 my_value
@@ -339,60 +534,70 @@ impl ResolveContext {
         }
     }
 
-    /// Compute the available inline width from the containing block.
+    /// Read a CSS property in pixels.
     ///
-    /// Walks up from the node to find the nearest block-level ancestor
-    /// (the containing block per CSS 2.2 §10.1), then computes its
-    /// content width (explicit width minus padding and border).
-    /// Falls back to viewport width if no block ancestor is found.
-    fn containing_block_width(&self, node: NodeId, ctx: &dyn PropertyResolver) -> f32 {
-        let mut current = ctx.parent(node).unwrap_or(NodeId(0));
-        loop {
-            // Check if this ancestor has a Display property.
-            let display = ctx.get_css_property(current, &PropertyId::Display);
-            let is_block = match &display {
-                Some(Property::Display(display)) => {
-                    use lightningcss::properties::display::{
-                        Display, DisplayInside, DisplayOutside,
-                    };
-                    !matches!(
-                        display,
-                        Display::Pair(pair)
-                            if matches!(pair.outside, DisplayOutside::Inline)
-                                && matches!(pair.inside, DisplayInside::Flow)
-                    )
-                }
-                // No Display property or non-Display property — treat as block.
-                _ => true,
-            };
+    /// Percentages of the containing block's inline size resolve against
+    /// its used content width, so they follow the block when it resizes.
+    fn css_value(
+        &mut self,
+        node: NodeId,
+        prop_id: &PropertyId<'static>,
+        ctx: &dyn PropertyResolver,
+    ) -> Option<Subpixel> {
+        if ctx.has_inline_percentage(node, prop_id)
+            && let Some(width) = self.used_content_width(containing_block(node, ctx), ctx)
+            && let Some(value) = ctx.resolve_inline_percentage(node, prop_id, width)
+        {
+            return Some(value);
+        }
+        ctx.get_property(node, prop_id)
+    }
 
-            if is_block {
-                let raw_width = ctx
-                    .get_property(current, &PropertyId::Width)
-                    .unwrap_or_else(|| Subpixel::from_px(self.viewport_width as i32));
-                let padding_left = ctx
-                    .get_property(current, &PropertyId::PaddingLeft)
-                    .unwrap_or(Subpixel::ZERO);
-                let padding_right = ctx
-                    .get_property(current, &PropertyId::PaddingRight)
-                    .unwrap_or(Subpixel::ZERO);
-                let border_left = ctx
-                    .get_property(current, &PropertyId::BorderLeftWidth)
-                    .unwrap_or(Subpixel::ZERO);
-                let border_right = ctx
-                    .get_property(current, &PropertyId::BorderRightWidth)
-                    .unwrap_or(Subpixel::ZERO);
-                return (raw_width - padding_left - padding_right - border_left - border_right)
-                    .max(Subpixel::ZERO)
-                    .to_f32();
-            }
+    /// The content-box width of `block` through `available_inline_size`.
+    ///
+    /// `None` if that isn't set or can't be resolved, e.g. for a
+    /// shrink-to-fit block whose width depends on the caller.
+    fn used_content_width(
+        &mut self,
+        block: NodeId,
+        ctx: &dyn PropertyResolver,
+    ) -> Option<Subpixel> {
+        let formula = self
+            .available_inline_size
+            .and_then(|query| query(block, ctx))?;
+        self.resolve(formula, block, ctx)
+            .map(|width| width.max(Subpixel::ZERO))
+    }
 
-            let parent = ctx.parent(current);
-            match parent {
-                Some(p) if p != current => current = p,
-                _ => return self.viewport_width as f32,
-            }
+    /// Compute the available inline width from the containing block.
+    ///
+    /// Takes the nearest block-level ancestor (the containing block per
+    /// CSS 2.2 §10.1) and uses its resolved content width. If that can't
+    /// be resolved, uses its explicit width minus padding and border,
+    /// falling back to the viewport width.
+    fn containing_block_width(&mut self, node: NodeId, ctx: &dyn PropertyResolver) -> f32 {
+        let block = containing_block(node, ctx);
+        if let Some(width) = self.used_content_width(block, ctx) {
+            return width.to_f32();
         }
+        let raw_width = ctx
+            .get_property(block, &PropertyId::Width)
+            .unwrap_or_else(|| Subpixel::from_px(self.viewport_width as i32));
+        let padding_left = ctx
+            .get_property(block, &PropertyId::PaddingLeft)
+            .unwrap_or(Subpixel::ZERO);
+        let padding_right = ctx
+            .get_property(block, &PropertyId::PaddingRight)
+            .unwrap_or(Subpixel::ZERO);
+        let border_left = ctx
+            .get_property(block, &PropertyId::BorderLeftWidth)
+            .unwrap_or(Subpixel::ZERO);
+        let border_right = ctx
+            .get_property(block, &PropertyId::BorderRightWidth)
+            .unwrap_or(Subpixel::ZERO);
+        (raw_width - padding_left - padding_right - border_left - border_right)
+            .max(Subpixel::ZERO)
+            .to_f32()
     }
 
     /// Resolve `InlineMeasure` for a single node.
@@ -421,10 +626,10 @@ impl ResolveContext {
                 .unwrap_or(Subpixel::from_px(16))
                 .to_f32();
 
-            // For MinContent width, we need the longest word's width.
+            // For MinContent width, we need the widest unbreakable segment.
             if mode == MeasureMode::MinContent && axis == MeasureAxis::Width {
                 let mut max_word_width: f32 = 0.0;
-                for word in text.split_whitespace() {
+                for word in ctx.unbreakable_segments(node, &text) {
                     if let Some(wm) = ctx.measure_text(node, word, font_size, None) {
                         if wm.width > max_word_width {
                             max_word_width = wm.width;
@@ -440,43 +645,134 @@ impl ResolveContext {
                 MeasureMode::MaxContent | MeasureMode::Baseline => None,
             };
 
-            let m = ctx.measure_text(node, &text, font_size, max_width)?;
+            let mut measured = ctx.measure_text(node, &text, font_size, max_width)?;
+            // `text-wrap` may pick a narrower width to break lines at, with
+            // as many lines, so only the width changes.
+            if let (MeasureMode::FitAvailable, MeasureAxis::Width, Some(max_width)) =
+                (mode, axis, max_width)
+            {
+                let style = ctx.text_wrap_style(node);
+                measured = wrap_text(style, &text, max_width, measured, &mut |trial, width| {
+                    ctx.measure_text(node, trial, font_size, width)
+                });
+            }
 
             return Some(Subpixel::from_f32(match (axis, mode) {
-                (MeasureAxis::Width, _) => m.width,
-                (MeasureAxis::Height, MeasureMode::Baseline) => m.ascent,
-                (MeasureAxis::Height, _) => m.height,
+                (MeasureAxis::Width, _) => measured.width,
+                (MeasureAxis::Height, MeasureMode::Baseline) => measured.ascent,
+                (MeasureAxis::Height, _) => measured.height,
             }));
         }
 
-        // Inline element (e.g. <span>): recurse into children.
+        // Replaced element (e.g. a loaded <img>): its border box, whatever
+        // the mode. Its baseline is its bottom edge.
+        if let Some(intrinsic) = ctx.intrinsic_size(node) {
+            let (width, height) = intrinsic.used_size(
+                ctx.get_property(node, &PropertyId::Width),
+                ctx.get_property(node, &PropertyId::Height),
+            );
+            return Some(match axis {
+                MeasureAxis::Width => width + sum_properties(node, ctx, &HORIZONTAL_DECORATION),
+                MeasureAxis::Height => height + sum_properties(node, ctx, &VERTICAL_DECORATION),
+            });
+        }
+
+        // Inline element (e.g. <span>): recurse into children. Its
+        // `::before` and `::after` boxes are content too, and are never
+        // broken.
         let children = ctx.children(node);
-        if children.is_empty() {
-            return None;
+        let generated: Vec<_> = [GeneratedBox::Before, GeneratedBox::After]
+            .into_iter()
+            .filter_map(|pseudo| ctx.measure_generated(node, pseudo))
+            .collect();
+        if children.is_empty() && generated.is_empty() {
+            return self.measure_empty_inline(axis, mode, node, ctx);
         }
 
-        match axis {
+        let mut has_content = !generated.is_empty();
+        let result = match axis {
             MeasureAxis::Width => {
                 // Sum children's widths (inline elements flow horizontally).
-                let mut total = Subpixel::ZERO;
+                // CSS 2.2 §10.3.1: an inline child's horizontal margins add to
+                // the line advance, and its own padding and border are already
+                // part of the measured width.
+                let mut total = generated
+                    .iter()
+                    .map(|measured| Subpixel::from_f32(measured.width))
+                    .fold(Subpixel::ZERO, |sum, width| sum + width);
                 for &child in &children {
                     if let Some(val) = self.resolve_inline_measure(axis, mode, child, ctx) {
-                        total = total + val;
+                        has_content = true;
+                        total = total + val + sum_properties(child, ctx, &HORIZONTAL_MARGINS);
                     }
                 }
-                Some(total)
+                total + sum_properties(node, ctx, &HORIZONTAL_DECORATION)
             }
             MeasureAxis::Height => {
                 // Max of children's heights (tallest child determines line height).
-                let mut max_val = Subpixel::ZERO;
+                // Vertical padding, border and margin of inline boxes are
+                // painted but never enlarge the line.
+                let mut max_val = generated
+                    .iter()
+                    .map(|measured| {
+                        Subpixel::from_f32(if mode == MeasureMode::Baseline {
+                            measured.ascent
+                        } else {
+                            measured.height
+                        })
+                    })
+                    .fold(Subpixel::ZERO, Subpixel::max);
                 for &child in &children {
-                    if let Some(val) = self.resolve_inline_measure(axis, mode, child, ctx)
-                        && val > max_val
-                    {
-                        max_val = val;
+                    if let Some(val) = self.resolve_inline_measure(axis, mode, child, ctx) {
+                        has_content = true;
+                        max_val = max_val.max(val);
                     }
                 }
-                Some(max_val)
+                max_val
+            }
+        };
+
+        if !has_content {
+            // Whitespace-only children: fall back to the empty-box metrics.
+            return self
+                .measure_empty_inline(axis, mode, node, ctx)
+                .or(Some(result));
+        }
+        Some(result)
+    }
+
+    /// Measure an inline element with no content, e.g.
+    /// `<span class="badge"></span>`.
+    ///
+    /// CSS 2.2 §9.4.2: such a box still takes part in the line box when it
+    /// has horizontal padding or border. It becomes a zero-length fragment
+    /// whose width is its decoration width and whose height comes from the
+    /// font's metrics (the strut). Returns `None` if it has no decoration.
+    fn measure_empty_inline(
+        &mut self,
+        axis: MeasureAxis,
+        mode: MeasureMode,
+        node: NodeId,
+        ctx: &dyn PropertyResolver,
+    ) -> Option<Subpixel> {
+        let decoration = sum_properties(node, ctx, &HORIZONTAL_DECORATION);
+        if decoration <= Subpixel::ZERO {
+            return None;
+        }
+
+        match axis {
+            MeasureAxis::Width => Some(decoration),
+            MeasureAxis::Height => {
+                let font_size = self
+                    .resolve(&FONT_SIZE_FORMULA, node, ctx)
+                    .unwrap_or(Subpixel::from_px(16))
+                    .to_f32();
+                let metrics = ctx.measure_text(node, "", font_size, None)?;
+                Some(Subpixel::from_f32(if mode == MeasureMode::Baseline {
+                    metrics.ascent
+                } else {
+                    metrics.height
+                }))
             }
         }
     }
@@ -484,8 +780,8 @@ impl ResolveContext {
     /// Resolve an aggregate formula.
     ///
     /// For `Sum` and `Count` aggregates over `PrevSiblings` / `OrderedPrevSiblings`,
-    /// uses prefix caching: compute prefix sums for ALL children of the parent
-    /// in one pass, then look up the result for any child in O(1).
+    /// uses prefix caching: prefix sums are computed in one pass up to the
+    /// requested child, then looked up in O(1) for it and the ones before.
     fn resolve_aggregate(
         &mut self,
         agg: Aggregation,
@@ -521,7 +817,8 @@ impl ResolveContext {
                     }
                 }
 
-                // Compute prefix values for all children of the parent.
+                // Extend the prefix values up to this child, starting after
+                // the children still in the same place.
                 let all_children = if matches!(rel, MultiRelationship::OrderedPrevSiblings) {
                     self.navigate_multi(parent, MultiRelationship::OrderedChildren, ctx)
                 } else {
@@ -531,42 +828,37 @@ impl ResolveContext {
                     c
                 };
 
-                let mut prefix_values = HashMap::new();
-                let mut running = Subpixel::ZERO;
+                let mut prefix = self.prefix_cache.remove(&prefix_key).unwrap_or_default();
+                let unmoved = prefix
+                    .children
+                    .iter()
+                    .zip(&all_children)
+                    .take_while(|(known, current)| known == current)
+                    .count();
+                if let Some(&moved) = prefix.children.get(unmoved) {
+                    prefix.truncate_from(moved);
+                }
 
-                for &child in &all_children {
+                for &child in &all_children[unmoved..] {
                     // Store the prefix value BEFORE this child's contribution.
-                    prefix_values.insert(child, running);
-
-                    // Compute this child's contribution.
-                    if let Some(formula) = query_fn(child, ctx) {
-                        if let Some(val) = self.resolve(formula, child, ctx) {
-                            match agg {
-                                Aggregation::Sum => running = running + val,
-                                Aggregation::Count => {
-                                    running = running + Subpixel::raw(1);
-                                }
-                                _ => unreachable!(),
-                            }
-                        }
+                    prefix.values.insert(child, prefix.total);
+
+                    prefix.total += self.prefix_contribution(agg, *query_fn, child, ctx);
+                    prefix.children.push(child);
+                    if child == node {
+                        break;
                     }
                 }
 
-                self.prefix_cache.insert(prefix_key, PrefixValues {
-                    values: prefix_values,
-                });
-
-                // Return the value for the requested node.
-                return self
-                    .prefix_cache
-                    .get(&prefix_key)
-                    .and_then(|pv| pv.values.get(&node).copied());
+                let value = prefix.values.get(&node).copied();
+                self.prefix_cache.insert(prefix_key, prefix);
+                return value;
             }
         }
 
         // Non-prefix path: standard aggregation.
         let targets = self.navigate_multi(node, *rel, ctx);
-        let mut values = Vec::new();
+        let mut values = self.take_buffer();
         for &target in &targets {
             if let Some(formula) = query_fn(target, ctx) {
                 if let Some(val) = self.resolve(formula, target, ctx) {
@@ -575,7 +867,39 @@ impl ResolveContext {
             }
         }
 
-        Some(aggregate_values(agg, &values))
+        let result = aggregate_values(agg, &values);
+        self.scratch.give_back(values);
+        Some(result)
+    }
+
+    /// What `child` adds to a prefix `Sum` or `Count` over its siblings:
+    /// its value or one, or nothing if it has none.
+    fn prefix_contribution(
+        &mut self,
+        agg: Aggregation,
+        query_fn: QueryFn,
+        child: NodeId,
+        ctx: &dyn PropertyResolver,
+    ) -> Subpixel {
+        let Some(val) = query_fn(child, ctx).and_then(|formula| self.resolve(formula, child, ctx))
+        else {
+            return Subpixel::ZERO;
+        };
+        match agg {
+            Aggregation::Sum => val,
+            Aggregation::Count => Subpixel::raw(1),
+            _ => unreachable!(),
+        }
+    }
+
+    /// An empty buffer for an aggregate's values, from the pool when one
+    /// is free. Hand it back with `self.scratch.give_back`.
+    fn take_buffer(&mut self) -> Vec<Subpixel> {
+        let (buffer, reused) = self.scratch.take();
+        if reused {
+            self.stats.reused_buffers += 1;
+        }
+        buffer
     }
 
     // ========================================================================
@@ -595,7 +919,7 @@ impl ResolveContext {
         available_main: &'static Formula,
         gap: &'static Formula,
         ctx: &dyn PropertyResolver,
-    ) -> Vec<Vec<usize>> {
+    ) -> Arc<Vec<Vec<usize>>> {
         let line_key = LineCacheKey {
             parent: parent_node,
             main_size_fn: item_main_size as usize,
@@ -604,7 +928,7 @@ impl ResolveContext {
         };
 
         if let Some(cached) = self.line_cache.get(&line_key) {
-            return cached.lines.clone();
+            return Arc::clone(&cached.lines);
         }
 
         let available = self
@@ -623,10 +947,19 @@ impl ResolveContext {
         // from the prepend-to-head linked list). Line-breaking must process
         // items in DOM order, so iterate in reverse. We still store the
         // original indices so callers can look up `children[idx]`.
-        let dom_order: Vec<usize> = (0..children.len()).rev().collect();
-
-        for &idx in &dom_order {
+        for idx in (0..children.len()).rev() {
             let child = children[idx];
+
+            // A preserved segment break ends the current line.
+            if ctx.forces_line_break(child) {
+                if let Some(line) = lines.last_mut() {
+                    line.push(idx);
+                }
+                lines.push(Vec::new());
+                line_used = 0.0;
+                continue;
+            }
+
             let child_formula = item_main_size(child, ctx);
 
             // If the main-size query returns None, this item forces a line
@@ -673,11 +1006,13 @@ impl ResolveContext {
         if lines.is_empty() {
             lines.push(Vec::new());
         }
+        self.stats.line_boxes += lines.len() as u64;
 
+        let lines = Arc::new(lines);
         self.line_cache.insert(
             line_key,
             LineAssignment {
-                lines: lines.clone(),
+                lines: Arc::clone(&lines),
             },
         );
 
@@ -712,10 +1047,10 @@ impl ResolveContext {
             .resolve(params.line_gap, node, ctx)
             .unwrap_or(Subpixel::ZERO);
 
-        let mut line_values: Vec<Subpixel> = Vec::new();
+        let mut line_values = self.take_buffer();
 
-        for line_indices in &lines {
-            let mut item_values: Vec<Subpixel> = Vec::new();
+        for line_indices in lines.iter() {
+            let mut item_values = self.take_buffer();
 
             for &child_idx in line_indices {
                 let child = children[child_idx];
@@ -727,6 +1062,7 @@ impl ResolveContext {
             }
 
             let line_val = aggregate_values(params.within_line_agg, &item_values);
+            self.scratch.give_back(item_values);
             line_values.push(line_val);
         }
 
@@ -738,6 +1074,7 @@ impl ResolveContext {
         };
 
         let result = aggregate_values(params.line_agg, &line_values);
+        self.scratch.give_back(line_values);
         Some(result + total_line_gap)
     }
 
@@ -784,38 +1121,25 @@ impl ResolveContext {
 
         let my_line = &lines[my_line_idx];
 
-        // Determine which children to aggregate based on the relationship.
-        let target_indices: Vec<usize> = match params.relationship {
+        // Determine which children to aggregate based on the relationship:
+        // a range of the line, skipping `node` itself for Siblings.
+        let (target_indices, skip_self): (&[usize], bool) = match params.relationship {
             MultiRelationship::PrevSiblings | MultiRelationship::OrderedPrevSiblings => {
-                my_line[..my_pos_in_line].to_vec()
+                (&my_line[..my_pos_in_line], false)
             }
             MultiRelationship::NextSiblings => {
-                if my_pos_in_line + 1 < my_line.len() {
-                    my_line[my_pos_in_line + 1..].to_vec()
-                } else {
-                    Vec::new()
-                }
-            }
-            MultiRelationship::Children
-            | MultiRelationship::OrderedChildren
-            | MultiRelationship::Siblings => {
-                // All items on the same line (including self for Children,
-                // excluding self for Siblings).
-                if matches!(params.relationship, MultiRelationship::Siblings) {
-                    my_line
-                        .iter()
-                        .copied()
-                        .filter(|&idx| all_children[idx] != node)
-                        .collect()
-                } else {
-                    my_line.to_vec()
-                }
+                (my_line.get(my_pos_in_line + 1..).unwrap_or_default(), false)
             }
+            MultiRelationship::Children | MultiRelationship::OrderedChildren => (my_line, false),
+            MultiRelationship::Siblings => (my_line, true),
         };
 
-        let mut values: Vec<Subpixel> = Vec::new();
-        for &child_idx in &target_indices {
+        let mut values = self.take_buffer();
+        for &child_idx in target_indices {
             let child = all_children[child_idx];
+            if skip_self && child == node {
+                continue;
+            }
             if let Some(formula) = (params.query)(child, ctx) {
                 if let Some(val) = self.resolve(formula, child, ctx) {
                     values.push(val);
@@ -823,7 +1147,9 @@ impl ResolveContext {
             }
         }
 
-        Some(aggregate_values(params.agg, &values))
+        let result = aggregate_values(params.agg, &values);
+        self.scratch.give_back(values);
+        Some(result)
     }
 
     /// Resolve a `PrevLinesAggregate` formula.
@@ -876,9 +1202,9 @@ impl ResolveContext {
         }
 
         // Aggregate values from lines 0..my_line_idx.
-        let mut line_values: Vec<Subpixel> = Vec::new();
+        let mut line_values = self.take_buffer();
         for line_indices in &lines[..my_line_idx] {
-            let mut item_values: Vec<Subpixel> = Vec::new();
+            let mut item_values = self.take_buffer();
             for &child_idx in line_indices {
                 let child = all_children[child_idx];
                 if let Some(formula) = (params.item_value)(child, ctx)
@@ -888,6 +1214,7 @@ impl ResolveContext {
                 }
             }
             let line_val = aggregate_values(params.within_line_agg, &item_values);
+            self.scratch.give_back(item_values);
             line_values.push(line_val);
         }
 
@@ -903,10 +1230,69 @@ impl ResolveContext {
         };
 
         let result = aggregate_values(params.line_agg, &line_values);
+        self.scratch.give_back(line_values);
         Some(result + total_line_gap)
     }
 }
 
+/// Horizontal padding and border of an inline box: its start and end
+/// decorations.
+static HORIZONTAL_DECORATION: [PropertyId<'static>; 4] = [
+    PropertyId::PaddingLeft,
+    PropertyId::PaddingRight,
+    PropertyId::BorderLeftWidth,
+    PropertyId::BorderRightWidth,
+];
+
+/// Vertical padding and border of a replaced box.
+static VERTICAL_DECORATION: [PropertyId<'static>; 4] = [
+    PropertyId::PaddingTop,
+    PropertyId::PaddingBottom,
+    PropertyId::BorderTopWidth,
+    PropertyId::BorderBottomWidth,
+];
+
+/// Horizontal margins of an inline box.
+static HORIZONTAL_MARGINS: [PropertyId<'static>; 2] =
+    [PropertyId::MarginLeft, PropertyId::MarginRight];
+
+/// Sum the resolved lengths of `props` on `node`, treating missing ones as
+/// zero.
+fn sum_properties(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    props: &[PropertyId<'static>],
+) -> Subpixel {
+    props
+        .iter()
+        .filter_map(|prop_id| ctx.get_property(node, prop_id))
+        .fold(Subpixel::ZERO, |acc, val| acc + val)
+}
+
+/// The nearest ancestor of `node` that isn't an inline box.
+///
+/// This is the containing block for inline-size percentages and line
+/// wrapping. Nodes with no `display` (the document) count as blocks.
+fn containing_block(node: NodeId, ctx: &dyn PropertyResolver) -> NodeId {
+    use lightningcss::properties::display::{Display, DisplayInside, DisplayOutside};
+    let mut current = ctx.parent(node).unwrap_or(NodeId(0));
+    loop {
+        let is_inline = matches!(
+            ctx.get_css_property(current, &PropertyId::Display),
+            Some(Property::Display(Display::Pair(pair)))
+                if matches!(pair.outside, DisplayOutside::Inline)
+                    && matches!(pair.inside, DisplayInside::Flow)
+        );
+        if !is_inline {
+            return current;
+        }
+        match ctx.parent(current) {
+            Some(parent) if parent != current => current = parent,
+            _ => return current,
+        }
+    }
+}
+
 /// Aggregate a slice of values using the given aggregation mode.
 fn aggregate_values(agg: Aggregation, values: &[Subpixel]) -> Subpixel {
     match agg {
@@ -924,3 +1310,142 @@ fn aggregate_values(agg: Aggregation, values: &[Subpixel]) -> Subpixel {
         Aggregation::Count => Subpixel::raw(values.len() as i32),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextMeasurement;
+
+    /// A document of two nodes: `NodeId(1)` inside the root.
+    struct TwoNodes;
+
+    impl PropertyResolver for TwoNodes {
+        fn get_property(&self, _node: NodeId, _prop_id: &PropertyId<'static>) -> Option<Subpixel> {
+            None
+        }
+
+        fn get_css_property(
+            &self,
+            _node: NodeId,
+            _prop_id: &PropertyId<'static>,
+        ) -> Option<Property<'static>> {
+            None
+        }
+
+        fn parent(&self, node: NodeId) -> Option<NodeId> {
+            (node == CHILD).then_some(NodeId::ROOT)
+        }
+
+        fn children(&self, node: NodeId) -> Vec<NodeId> {
+            if node == NodeId::ROOT {
+                vec![CHILD]
+            } else {
+                Vec::new()
+            }
+        }
+
+        fn prev_siblings(&self, _node: NodeId) -> Vec<NodeId> {
+            Vec::new()
+        }
+
+        fn next_siblings(&self, _node: NodeId) -> Vec<NodeId> {
+            Vec::new()
+        }
+
+        fn viewport_width(&self) -> u32 {
+            800
+        }
+
+        fn viewport_height(&self) -> u32 {
+            600
+        }
+
+        fn is_intrinsic(&self, _node: NodeId) -> bool {
+            false
+        }
+
+        fn is_element(&self, _node: NodeId) -> bool {
+            true
+        }
+
+        fn tag_name(&self, _node: NodeId) -> Option<String> {
+            Some("div".to_owned())
+        }
+
+        fn text_content(&self, _node: NodeId) -> Option<String> {
+            None
+        }
+
+        fn measure_text(
+            &self,
+            _node: NodeId,
+            _text: &str,
+            _font_size: f32,
+            _max_width: Option<f32>,
+        ) -> Option<TextMeasurement> {
+            None
+        }
+    }
+
+    const CHILD: NodeId = NodeId(1);
+
+    /// The child asks its parent for `PARENT_OF_CHILD`...
+    static FROM_CHILD: Formula =
+        Formula::Related(SingleRelationship::Parent, |_, _| Some(&PARENT_OF_CHILD));
+
+    /// ...which asks the child for `FROM_CHILD` again.
+    static PARENT_OF_CHILD: Formula = Formula::Imperative(|_, _, resolve| {
+        let value = resolve(&FROM_CHILD, CHILD)?;
+        Some(vec![(NodeId::ROOT, value)])
+    });
+
+    #[test]
+    fn cycle_resolves_to_none_and_records_its_path() {
+        let mut context = ResolveContext::new(800, 600);
+        assert_eq!(context.resolve(&FROM_CHILD, CHILD, &TwoNodes), None);
+
+        let from_child = ResolveFrame::new(CHILD, &FROM_CHILD);
+        let parent_of_child = ResolveFrame::new(NodeId::ROOT, &PARENT_OF_CHILD);
+        assert_eq!(
+            context.take_diagnostics(),
+            vec![ResolveDiagnostic {
+                failure: ResolveFailure::Cycle,
+                path: vec![from_child, parent_of_child, from_child],
+            }]
+        );
+        assert_eq!(context.take_diagnostics(), []);
+        assert_eq!(context.in_progress, []);
+    }
+
+    static VIEWPORT_WIDTH: Formula = Formula::ViewportWidth;
+
+    /// Splits the viewport width between the root and the child.
+    static SPLIT_WIDTH: Formula = Formula::Imperative(|_, _, resolve| {
+        let width = resolve(&VIEWPORT_WIDTH, NodeId::ROOT)?;
+        let half = Subpixel::from_f32(width.to_f32() / 2.0);
+        Some(vec![(NodeId::ROOT, half), (CHILD, width - half)])
+    });
+
+    #[test]
+    fn batch_reuses_values_laid_out_for_other_nodes() {
+        let mut context = ResolveContext::new(800, 600);
+        context.resolve(&SPLIT_WIDTH, NodeId::ROOT, &TwoNodes);
+        // The child's value isn't cached until it's resolved itself.
+        assert_eq!(context.get_cached(&SPLIT_WIDTH, CHILD), None);
+
+        context.begin_batch();
+        context.resolve(&SPLIT_WIDTH, NodeId::ROOT, &TwoNodes);
+        let evaluations = context.stats().evaluations;
+        assert_eq!(
+            context.resolve(&SPLIT_WIDTH, CHILD, &TwoNodes),
+            Some(Subpixel::from_px(400))
+        );
+        assert_eq!(context.stats().evaluations, evaluations + 1);
+
+        // Invalidating a cache drops the values laid out with it.
+        context.invalidate_parent_aggregates(NodeId::ROOT);
+        context.resolve(&SPLIT_WIDTH, CHILD, &TwoNodes);
+        assert_eq!(context.stats().evaluations, evaluations + 3);
+        context.end_batch();
+    }
+}
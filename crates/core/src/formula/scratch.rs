@@ -0,0 +1,48 @@
+//! Scratch buffers reused across the aggregations of a resolution pass.
+//!
+//! Every aggregate collects its items' values before combining them, and
+//! a full layout resolves several aggregates per node. Allocating a fresh
+//! `Vec` for each made the allocator the busiest part of layout on large
+//! trees. Aggregations nest (an item's value can itself be an aggregate),
+//! so a single buffer won't do: each one takes a buffer from the pool and
+//! hands it back when done, and nested ones find the next free buffer.
+
+use crate::Subpixel;
+
+/// Buffers kept between passes. Enough for the nesting depth of ordinary
+/// layouts; deeper nesting allocates.
+const MAX_POOLED: usize = 32;
+
+/// Capacity above which a buffer is released at the end of a pass rather
+/// than kept, so one huge container doesn't pin its memory forever.
+const MAX_RETAINED_CAPACITY: usize = 4096;
+
+/// Pool of value buffers. `take` hands out an empty buffer, `give_back`
+/// returns it.
+#[derive(Debug, Default)]
+pub(super) struct Scratch {
+    free: Vec<Vec<Subpixel>>,
+}
+
+impl Scratch {
+    /// An empty buffer, and whether it came from the pool.
+    pub(super) fn take(&mut self) -> (Vec<Subpixel>, bool) {
+        self.free
+            .pop()
+            .map_or_else(|| (Vec::new(), false), |buffer| (buffer, true))
+    }
+
+    /// Return a buffer taken with `take`.
+    pub(super) fn give_back(&mut self, mut buffer: Vec<Subpixel>) {
+        if self.free.len() < MAX_POOLED {
+            buffer.clear();
+            self.free.push(buffer);
+        }
+    }
+
+    /// End a pass: release oversized buffers.
+    pub(super) fn reset(&mut self) {
+        self.free
+            .retain(|buffer| buffer.capacity() <= MAX_RETAINED_CAPACITY);
+    }
+}
@@ -0,0 +1,41 @@
+//! Resolution work counters.
+//!
+//! Cheap enough to keep on in release builds. Benchmarks and tests read
+//! them to catch work that grows faster than the document, which wall
+//! clock time alone hides on small inputs.
+
+use std::ops::Sub;
+
+/// Formula evaluation counts since the counters were last reset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResolveStats {
+    /// `resolve` calls made from outside the resolver (one per requested
+    /// value).
+    pub top_level: u64,
+    /// Formula nodes evaluated, nested ones included.
+    pub evaluations: u64,
+    /// Lines built by line breaking: the line boxes of inline content and
+    /// the lines of wrapping flex containers. Cached lines aren't counted
+    /// again.
+    pub line_boxes: u64,
+    /// Nodes left unresolved because they generate no box
+    /// (`display: none` and everything inside it).
+    pub skipped_nodes: u64,
+    /// Aggregations that collected their values into a pooled buffer
+    /// instead of allocating one.
+    pub reused_buffers: u64,
+}
+
+impl Sub for ResolveStats {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            top_level: self.top_level - rhs.top_level,
+            evaluations: self.evaluations - rhs.evaluations,
+            line_boxes: self.line_boxes - rhs.line_boxes,
+            skipped_nodes: self.skipped_nodes - rhs.skipped_nodes,
+            reused_buffers: self.reused_buffers - rhs.reused_buffers,
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! `text-wrap: balance` and `text-wrap: pretty` (CSS Text 4 §6.3).
+//!
+//! Both pick a narrower width to wrap a text node at than the available
+//! width, keeping its number of lines: `balance` the narrowest, so its
+//! lines come out about equally long, `pretty` the widest that moves a
+//! word down to a last line holding just one. Either is found by
+//! re-running the line breaker at trial widths, so each costs a bounded
+//! number of extra measurements: `balance` only applies to text short
+//! enough for a handful of lines, as in headings, and both searches stop
+//! after a fixed number of steps.
+//!
+//! The text node's width is its longest line at the chosen width, so
+//! wrapping it again at its own width, as painting does, breaks it the
+//! same way.
+
+use super::TextMeasurement;
+
+/// How a text node picks where its lines break, from `text-wrap`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextWrapStyle {
+    /// Fill each line before starting the next.
+    #[default]
+    Auto,
+    /// Make the lines about equally long.
+    Balance,
+    /// Avoid a last line with a single word.
+    Pretty,
+}
+
+/// Text that would wrap into more lines than this is wrapped as `auto`
+/// even when `balance` asks otherwise.
+const MAX_BALANCED_LINES: f32 = 6.0;
+
+/// Trial widths each search measures the text at, at most.
+const MAX_SEARCH_STEPS: usize = 12;
+
+/// Searches stop once the trial widths are this close, in pixels.
+const SEARCH_PRECISION: f32 = 1.0;
+
+/// Measures text wrapped at a width, or unwrapped for `None`.
+type Measure<'measure> = dyn FnMut(&str, Option<f32>) -> Option<TextMeasurement> + 'measure;
+
+/// Measure `text` wrapped within `max_width` as `style` asks. `wrapped`
+/// is the text measured at `max_width`, as `auto` wraps it.
+pub fn wrap_text(
+    style: TextWrapStyle,
+    text: &str,
+    max_width: f32,
+    wrapped: TextMeasurement,
+    measure: &mut Measure<'_>,
+) -> TextMeasurement {
+    let better = match style {
+        TextWrapStyle::Auto => None,
+        TextWrapStyle::Balance => balance(text, max_width, wrapped, measure),
+        TextWrapStyle::Pretty => pretty(text, max_width, wrapped, measure),
+    };
+    better.unwrap_or(wrapped)
+}
+
+/// The narrowest wrap of `text` with as many lines as at `max_width`.
+fn balance(
+    text: &str,
+    max_width: f32,
+    wrapped: TextMeasurement,
+    measure: &mut Measure<'_>,
+) -> Option<TextMeasurement> {
+    let unwrapped = measure(text, None)?;
+    // Text on one line, or that doesn't wrap, has nothing to balance.
+    if wrapped.height <= unwrapped.height || unwrapped.width > max_width * MAX_BALANCED_LINES {
+        return None;
+    }
+    let mut keeps_lines =
+        |width: f32| measure(text, Some(width)).is_some_and(|trial| trial.height <= wrapped.height);
+    let (_, narrowest) = search(0.0, max_width, &mut keeps_lines);
+    measure(text, Some(narrowest))
+}
+
+/// The widest wrap of `text` with as many lines as at `max_width` and
+/// more than one word on the last, if its last line at `max_width` holds
+/// one word.
+fn pretty(
+    text: &str,
+    max_width: f32,
+    wrapped: TextMeasurement,
+    measure: &mut Measure<'_>,
+) -> Option<TextMeasurement> {
+    let (head, _) = text.trim_end().rsplit_once(char::is_whitespace)?;
+    // The last word sits alone exactly when the text before it takes a
+    // line less.
+    if measure(head, Some(max_width))?.height >= wrapped.height {
+        return None;
+    }
+    let mut head_fills_lines =
+        |width: f32| measure(head, Some(width)).is_some_and(|trial| trial.height >= wrapped.height);
+    let (_, widest) = search(max_width, 0.0, &mut head_fills_lines);
+    let trial = measure(text, Some(widest))?;
+    (trial.height <= wrapped.height).then_some(trial)
+}
+
+/// Move a width where `test` fails and one where it holds toward where
+/// it starts holding, returning both.
+fn search(mut fails: f32, mut holds: f32, test: &mut dyn FnMut(f32) -> bool) -> (f32, f32) {
+    for _ in 0..MAX_SEARCH_STEPS {
+        if (holds - fails).abs() < SEARCH_PRECISION {
+            break;
+        }
+        let middle = f32::midpoint(fails, holds);
+        if test(middle) {
+            holds = middle;
+        } else {
+            fails = middle;
+        }
+    }
+    (fails, holds)
+}
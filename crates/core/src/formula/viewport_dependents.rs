@@ -0,0 +1,87 @@
+//! Lengths resolved against a viewport.
+//!
+//! Viewport-relative lengths (`vw`, `svh`, `dvmin`, ...) and the few sizes
+//! that fall back to the viewport, like a root percentage height, don't
+//! name the viewport in any layout formula. Like font-relative lengths
+//! (see `FontDependents`), value resolution records the boxes using them,
+//! so resizing a viewport re-resolves just those boxes. The dynamic
+//! viewport in particular is resized as browser UI such as an address bar
+//! slides in and out, often on every frame of a scroll.
+
+use crate::NodeId;
+use std::collections::HashSet;
+use std::sync::{Mutex, PoisonError};
+
+/// The viewport a viewport-relative length resolves against (CSS Values 4
+/// §6.1.2.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewportKind {
+    /// `sv*`: the viewport with retractable browser UI shown.
+    Small,
+    /// `lv*` and unprefixed units: the viewport with that UI retracted,
+    /// the layout viewport.
+    Large,
+    /// `dv*`: whichever of the two, or in between, is current.
+    Dynamic,
+}
+
+/// Boxes with lengths resolved against a viewport, by the kind of
+/// viewport, as recorded during value resolution. Shared between
+/// resolvers, so recording takes `&self`.
+#[derive(Debug, Default)]
+pub struct ViewportDependents {
+    sets: Mutex<DependentSets>,
+}
+
+#[derive(Debug, Default)]
+struct DependentSets {
+    small: HashSet<NodeId>,
+    large: HashSet<NodeId>,
+    dynamic: HashSet<NodeId>,
+}
+
+impl DependentSets {
+    fn of_kind(&mut self, kind: ViewportKind) -> &mut HashSet<NodeId> {
+        match kind {
+            ViewportKind::Small => &mut self.small,
+            ViewportKind::Large => &mut self.large,
+            ViewportKind::Dynamic => &mut self.dynamic,
+        }
+    }
+}
+
+impl ViewportDependents {
+    /// Record that a length of `node` was resolved against the viewport
+    /// of `kind`.
+    pub fn record(&self, node: NodeId, kind: ViewportKind) {
+        let mut sets = self.sets.lock().unwrap_or_else(PoisonError::into_inner);
+        sets.of_kind(kind).insert(node);
+    }
+
+    /// Boxes with a length resolved against a viewport of one of `kinds`,
+    /// in node order.
+    pub fn users(&self, kinds: &[ViewportKind]) -> Vec<NodeId> {
+        let mut sets = self.sets.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut users = Vec::new();
+        for &kind in kinds {
+            users.extend(sets.of_kind(kind).iter().copied());
+        }
+        users.sort_unstable();
+        users.dedup();
+        users
+    }
+
+    /// Forget the uses recorded for `node`, e.g. once it has no box.
+    pub fn forget(&self, node: NodeId) {
+        let mut sets = self.sets.lock().unwrap_or_else(PoisonError::into_inner);
+        sets.small.remove(&node);
+        sets.large.remove(&node);
+        sets.dynamic.remove(&node);
+    }
+
+    /// Forget every recorded use, e.g. for a new document.
+    pub fn clear(&self) {
+        let mut sets = self.sets.lock().unwrap_or_else(PoisonError::into_inner);
+        *sets = DependentSets::default();
+    }
+}
@@ -13,9 +13,13 @@ pub mod types;
 pub use css::*;
 pub use db::*;
 pub use formula::{
-    Aggregation, Formula, FormulaDependency, FormulaList, ImperativeFn, LineAggregateParams,
-    LineItemAggregateParams, MeasureAxis, MeasureMode, Operation, PrevLinesAggregateParams,
-    PropertyResolver, QueryFn, ResolveContext, TextMeasurement, FONT_SIZE_FORMULA,
+    Aggregation, CustomLayout, CustomLayoutRegistry, FONT_SIZE_FORMULA, FloatSide, FontDependents,
+    FontRelative, FontUnitSizes, Formula, FormulaDependency, FormulaList, GeneratedBox,
+    ImperativeFn, IntrinsicSize, LayoutChild, LayoutConstraints, LayoutFlags, LayoutFragment,
+    LayoutFragments, LineAggregateParams, LineItemAggregateParams, MeasureAxis, MeasureMode,
+    Operation, PrevLinesAggregateParams, PropertyResolver, QueryFn, ResolveContext,
+    ResolveDiagnostic, ResolveFailure, ResolveFrame, ResolveStats, TextAutosizing, TextMeasurement,
+    TextWrapStyle, ViewportDependents, ViewportKind, affects_text_measurement, custom_layout_name,
 };
 pub use rayon_dispatch::rayon_dispatch;
 pub use types::*;
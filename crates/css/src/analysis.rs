@@ -0,0 +1,194 @@
+//! Standalone stylesheet analysis.
+//!
+//! Parses a stylesheet with the same lightningcss options as the streaming
+//! parser and reports statistics useful for build tooling (minifiers,
+//! linters, size budgets). Nothing here touches the DOM or the `Styler`.
+
+use lightningcss::declaration::DeclarationBlock;
+use lightningcss::printer::PrinterOptions;
+use lightningcss::properties::Property;
+use lightningcss::properties::custom::CustomPropertyName;
+use lightningcss::rules::{CssRule, CssRuleList};
+use lightningcss::selector::{Component, Selector};
+use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+use lightningcss::traits::ToCss as _;
+use parcel_selectors::parser::Combinator;
+use std::collections::BTreeMap;
+
+/// Statistics collected from a single stylesheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StylesheetStats {
+    /// Number of style rules (including rules nested in `@media` / `@supports`).
+    pub rule_count: usize,
+    /// Selector count keyed by complexity (number of compound selectors,
+    /// i.e. combinators + 1). `div` is 1, `ul > li a` is 3.
+    pub selectors_by_complexity: BTreeMap<usize, usize>,
+    /// How often each declared property name appears, before shorthand expansion.
+    pub property_histogram: BTreeMap<String, usize>,
+    /// Selector lists that appear on more than one rule. These rules can be
+    /// merged by a minifier.
+    pub duplicate_rules: Vec<DuplicateRule>,
+    /// Declared property names lightningcss does not recognise.
+    pub unknown_properties: Vec<String>,
+}
+
+/// A selector list that is repeated across several rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateRule {
+    /// Serialized selector list, e.g. `".a, .b"`.
+    pub selectors: String,
+    /// Number of rules using this selector list.
+    pub occurrences: usize,
+}
+
+impl StylesheetStats {
+    /// Total number of selectors across all rules.
+    pub fn selector_count(&self) -> usize {
+        self.selectors_by_complexity.values().sum()
+    }
+}
+
+/// Parse `css_text` and collect statistics.
+///
+/// Parsing uses error recovery, so invalid rules are skipped rather than
+/// aborting the analysis. Returns `None` only if the stylesheet cannot be
+/// parsed at all.
+pub fn analyze_stylesheet(css_text: &str) -> Option<StylesheetStats> {
+    let options = ParserOptions {
+        error_recovery: true,
+        ..Default::default()
+    };
+    let stylesheet = StyleSheet::parse(css_text, options).ok()?;
+
+    let mut stats = StylesheetStats::default();
+    let mut selector_counts: BTreeMap<String, usize> = BTreeMap::new();
+    collect_rules(&stylesheet.rules, &mut stats, &mut selector_counts);
+
+    stats.duplicate_rules = selector_counts
+        .into_iter()
+        .filter(|&(_, occurrences)| occurrences > 1)
+        .map(|(selectors, occurrences)| DuplicateRule {
+            selectors,
+            occurrences,
+        })
+        .collect();
+
+    Some(stats)
+}
+
+/// Walk a rule list, descending into conditional group rules.
+fn collect_rules(
+    rules: &CssRuleList<'_>,
+    stats: &mut StylesheetStats,
+    selector_counts: &mut BTreeMap<String, usize>,
+) {
+    for rule in &rules.0 {
+        match rule {
+            CssRule::Style(style_rule) => {
+                stats.rule_count += 1;
+                for selector in &style_rule.selectors.0 {
+                    *stats
+                        .selectors_by_complexity
+                        .entry(selector_complexity(selector))
+                        .or_default() += 1;
+                }
+                if let Ok(text) = style_rule
+                    .selectors
+                    .to_css_string(PrinterOptions::default())
+                {
+                    *selector_counts.entry(text).or_default() += 1;
+                }
+                collect_declarations(&style_rule.declarations, stats);
+                collect_rules(&style_rule.rules, stats, selector_counts);
+            }
+            CssRule::Media(media) => collect_rules(&media.rules, stats, selector_counts),
+            CssRule::Supports(supports) => {
+                collect_rules(&supports.rules, stats, selector_counts);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Record property names from a declaration block.
+fn collect_declarations(decls: &DeclarationBlock<'_>, stats: &mut StylesheetStats) {
+    for prop in decls
+        .declarations
+        .iter()
+        .chain(decls.important_declarations.iter())
+    {
+        let id = prop.property_id();
+        let name = id.name().to_owned();
+        if let Property::Custom(custom) = prop
+            && matches!(custom.name, CustomPropertyName::Unknown(_))
+            && !stats.unknown_properties.contains(&name)
+        {
+            stats.unknown_properties.push(name.clone());
+        }
+        *stats.property_histogram.entry(name).or_default() += 1;
+    }
+}
+
+/// Number of compound selectors in a complex selector.
+fn selector_complexity(selector: &Selector<'_>) -> usize {
+    let combinators = selector
+        .iter_raw_match_order()
+        .filter(|component| {
+            matches!(
+                component,
+                Component::Combinator(comb) if !matches!(comb, Combinator::PseudoElement)
+            )
+        })
+        .count();
+    combinators + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_selectors_by_complexity() {
+        let stats = analyze_stylesheet("div { color: red } ul > li a, .x { margin: 0 }")
+            .unwrap_or_default();
+        assert_eq!(stats.rule_count, 2);
+        assert_eq!(stats.selector_count(), 3);
+        assert_eq!(stats.selectors_by_complexity.get(&1), Some(&2));
+        assert_eq!(stats.selectors_by_complexity.get(&3), Some(&1));
+    }
+
+    #[test]
+    fn builds_property_histogram() {
+        let stats = analyze_stylesheet("a { color: red; margin: 0 } b { color: blue !important }")
+            .unwrap_or_default();
+        assert_eq!(stats.property_histogram.get("color"), Some(&2));
+        assert_eq!(stats.property_histogram.get("margin"), Some(&1));
+    }
+
+    #[test]
+    fn reports_duplicate_rules() {
+        let stats = analyze_stylesheet(".a { color: red } .b { color: red } .a { margin: 0 }")
+            .unwrap_or_default();
+        assert_eq!(
+            stats.duplicate_rules,
+            vec![DuplicateRule {
+                selectors: ".a".to_owned(),
+                occurrences: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_properties() {
+        let stats =
+            analyze_stylesheet("a { colr: red; --custom: 1; color: red }").unwrap_or_default();
+        assert_eq!(stats.unknown_properties, vec!["colr".to_owned()]);
+    }
+
+    #[test]
+    fn descends_into_media_rules() {
+        let stats =
+            analyze_stylesheet("@media (min-width: 10px) { p { color: red } }").unwrap_or_default();
+        assert_eq!(stats.rule_count, 1);
+    }
+}
@@ -0,0 +1,191 @@
+//! Math function evaluation.
+//!
+//! lightningcss parses `calc()`, `min()`, `max()`, `clamp()` and the other
+//! math functions (CSS Values 4 §10) into a `Calc` tree, folding whatever
+//! it can at parse time: `calc(10px + 2em)` stays a tree, and so does
+//! anything mixing lengths and percentages, since a percentage's basis is
+//! only known once the box is laid out. `evaluate` walks such a tree with
+//! the caller resolving each leaf, so percentages can be resolved against
+//! the used size of the containing block.
+
+use lightningcss::values::calc::{Calc, MathFunction, RoundingStrategy};
+use lightningcss::values::length::{Length, LengthValue};
+use lightningcss::values::percentage::DimensionPercentage;
+
+/// Resolves one value in a math function to a number.
+type Leaf<'leaf, V> = dyn FnMut(&V) -> Option<f32> + 'leaf;
+
+/// Evaluate `calc`, resolving each value in it with `leaf`. `None` if any
+/// leaf can't be resolved or a step has no defined result, e.g. `mod()`
+/// by zero.
+pub fn evaluate<V>(calc: &Calc<V>, leaf: &mut Leaf<'_, V>) -> Option<f32> {
+    let result = match calc {
+        Calc::Value(value) => leaf(value)?,
+        Calc::Number(number) => *number,
+        Calc::Sum(left, right) => evaluate(left, leaf)? + evaluate(right, leaf)?,
+        Calc::Product(factor, operand) => factor * evaluate(operand, leaf)?,
+        Calc::Function(function) => evaluate_function(function, leaf)?,
+    };
+    result.is_finite().then_some(result)
+}
+
+fn evaluate_function<V>(function: &MathFunction<V>, leaf: &mut Leaf<'_, V>) -> Option<f32> {
+    Some(match function {
+        MathFunction::Calc(inner) => evaluate(inner, leaf)?,
+        MathFunction::Min(args) => evaluate_all(args, leaf)?.into_iter().reduce(f32::min)?,
+        MathFunction::Max(args) => evaluate_all(args, leaf)?.into_iter().reduce(f32::max)?,
+        // The lower bound wins when the bounds cross.
+        MathFunction::Clamp(lower, value, upper) => {
+            let (lower, value) = (evaluate(lower, leaf)?, evaluate(value, leaf)?);
+            value.min(evaluate(upper, leaf)?).max(lower)
+        }
+        MathFunction::Round(strategy, value, step) => {
+            let (value, step) = (evaluate(value, leaf)?, evaluate(step, leaf)?);
+            if step == 0.0 {
+                return None;
+            }
+            let steps = value / step;
+            let rounded = match strategy {
+                RoundingStrategy::Nearest => (steps + 0.5).floor(),
+                RoundingStrategy::Up => steps.ceil(),
+                RoundingStrategy::Down => steps.floor(),
+                RoundingStrategy::ToZero => steps.trunc(),
+            };
+            rounded * step
+        }
+        // `rem()` takes the sign of the dividend, `mod()` of the divisor.
+        MathFunction::Rem(dividend, divisor) => {
+            let (dividend, divisor) = (evaluate(dividend, leaf)?, evaluate(divisor, leaf)?);
+            divisor.mul_add(-(dividend / divisor).trunc(), dividend)
+        }
+        MathFunction::Mod(dividend, divisor) => {
+            let (dividend, divisor) = (evaluate(dividend, leaf)?, evaluate(divisor, leaf)?);
+            divisor.mul_add(-(dividend / divisor).floor(), dividend)
+        }
+        MathFunction::Abs(value) => evaluate(value, leaf)?.abs(),
+        MathFunction::Sign(value) => {
+            let value = evaluate(value, leaf)?;
+            if value == 0.0 { 0.0 } else { value.signum() }
+        }
+        MathFunction::Hypot(args) => {
+            let args = evaluate_all(args, leaf)?;
+            args.iter().map(|arg| arg * arg).sum::<f32>().sqrt()
+        }
+    })
+}
+
+fn evaluate_all<V>(args: &[Calc<V>], leaf: &mut Leaf<'_, V>) -> Option<Vec<f32>> {
+    args.iter().map(|arg| evaluate(arg, leaf)).collect()
+}
+
+/// Evaluate a length-percentage, resolving lengths with `length` and
+/// percentages against `basis`. `None` if it has a percentage and there
+/// is no basis.
+pub fn length_percentage(
+    value: &DimensionPercentage<LengthValue>,
+    basis: Option<f32>,
+    length: &mut dyn FnMut(&LengthValue) -> f32,
+) -> Option<f32> {
+    match value {
+        DimensionPercentage::Dimension(value) => Some(length(value)),
+        DimensionPercentage::Percentage(percentage) => Some(basis? * percentage.0),
+        DimensionPercentage::Calc(calc) => {
+            evaluate(calc, &mut |leaf| length_percentage(leaf, basis, length))
+        }
+    }
+}
+
+/// Evaluate a length, resolving its units with `length`.
+pub fn length(value: &Length, length_value: &mut dyn FnMut(&LengthValue) -> f32) -> Option<f32> {
+    match value {
+        Length::Value(value) => Some(length_value(value)),
+        Length::Calc(calc) => evaluate(calc, &mut |leaf| length(leaf, length_value)),
+    }
+}
+
+/// Whether a length-percentage has a percentage anywhere in it.
+pub fn has_percentage(value: &DimensionPercentage<LengthValue>) -> bool {
+    match value {
+        DimensionPercentage::Dimension(_) => false,
+        DimensionPercentage::Percentage(_) => true,
+        DimensionPercentage::Calc(calc) => calc_has_percentage(calc),
+    }
+}
+
+fn calc_has_percentage(calc: &Calc<DimensionPercentage<LengthValue>>) -> bool {
+    match calc {
+        Calc::Value(value) => has_percentage(value),
+        Calc::Number(_) => false,
+        Calc::Sum(left, right) => calc_has_percentage(left) || calc_has_percentage(right),
+        Calc::Product(_, operand) => calc_has_percentage(operand),
+        Calc::Function(function) => match &**function {
+            MathFunction::Calc(inner) | MathFunction::Abs(inner) | MathFunction::Sign(inner) => {
+                calc_has_percentage(inner)
+            }
+            MathFunction::Min(args) | MathFunction::Max(args) | MathFunction::Hypot(args) => {
+                args.iter().any(calc_has_percentage)
+            }
+            MathFunction::Clamp(first, second, third) => {
+                [first, second, third].into_iter().any(calc_has_percentage)
+            }
+            MathFunction::Round(_, first, second)
+            | MathFunction::Rem(first, second)
+            | MathFunction::Mod(first, second) => {
+                calc_has_percentage(first) || calc_has_percentage(second)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightningcss::properties::size::Size;
+    use lightningcss::properties::{Property, PropertyId};
+    use lightningcss::stylesheet::ParserOptions;
+
+    fn width(text: &str) -> DimensionPercentage<LengthValue> {
+        match Property::parse_string(PropertyId::Width, text, ParserOptions::default()) {
+            Ok(Property::Width(Size::LengthPercentage(value))) => value,
+            other => panic!("{text} parsed as {other:?}"),
+        }
+    }
+
+    /// `text` as a width, with `em` at 10px and percentages of 200px.
+    fn evaluate_width(text: &str) -> Option<f32> {
+        let mut length = |value: &LengthValue| match value {
+            LengthValue::Em(ems) => ems * 10.0,
+            other => other.to_px().unwrap_or(0.0),
+        };
+        length_percentage(&width(text), Some(200.0), &mut length)
+    }
+
+    #[test]
+    fn mixes_lengths_and_percentages() {
+        assert_eq!(evaluate_width("calc(100% - 32px)"), Some(168.0));
+        assert_eq!(evaluate_width("calc(50% + 2em)"), Some(120.0));
+        assert_eq!(evaluate_width("calc((100% - 20px) / 4)"), Some(45.0));
+        assert_eq!(evaluate_width("calc(2 * (10% + 1em))"), Some(60.0));
+        assert!(has_percentage(&width("calc(10% + 1em)")));
+        assert!(!has_percentage(&width("calc(10px + 1em)")));
+    }
+
+    #[test]
+    fn math_functions() {
+        assert_eq!(evaluate_width("min(50%, 2em + 50px)"), Some(70.0));
+        assert_eq!(evaluate_width("max(10%, 5em)"), Some(50.0));
+        assert_eq!(evaluate_width("clamp(100px, 10%, 150px)"), Some(100.0));
+        assert_eq!(evaluate_width("clamp(100px, 100%, 150px)"), Some(150.0));
+        assert_eq!(evaluate_width("round(up, 33%, 10px)"), Some(70.0));
+        assert_eq!(evaluate_width("mod(-50px, 30px)"), Some(10.0));
+        assert_eq!(evaluate_width("rem(-50px, 30px)"), Some(-20.0));
+        assert_eq!(evaluate_width("abs(calc(1em - 100%))"), Some(190.0));
+    }
+
+    #[test]
+    fn percentages_need_a_basis() {
+        let mut length = |value: &LengthValue| value.to_px().unwrap_or(0.0);
+        let value = width("calc(100% - 32px)");
+        assert_eq!(length_percentage(&value, None, &mut length), None);
+    }
+}
@@ -0,0 +1,135 @@
+//! Colors in the space they were written in.
+//!
+//! lightningcss parses `color(display-p3 …)`, `lab()`, `oklch()` and the
+//! other CSS Color 4 forms without converting them, and `ComputedColor`
+//! keeps them that way up to paint. Renderers with a wide-gamut surface
+//! read the components directly; everyone else calls `to_srgb`, which gamut
+//! maps (CSS Color 4 §13.2) instead of clipping each channel.
+
+use lightningcss::values::color::{
+    ColorGamut as _, ColorSpace as _, CssColor, LABColor, P3, PredefinedColor, RGBA, SRGB,
+};
+
+/// A used color value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComputedColor {
+    /// An sRGB color with 8-bit channels: hex, named, `rgb()`, `hsl()`,
+    /// `hwb()`, system and palette colors.
+    Srgb(RGBA),
+    /// A color in a predefined RGB or XYZ space (`color()`).
+    Predefined(PredefinedColor),
+    /// A color in a Lab-like space (`lab()`, `lch()`, `oklab()`,
+    /// `oklch()`).
+    Lab(LABColor),
+}
+
+impl ComputedColor {
+    /// Convert a parsed color. `None` for colors that depend on context
+    /// (`currentColor`, system colors, `light-dark()`).
+    pub fn from_css(color: &CssColor) -> Option<Self> {
+        match color {
+            CssColor::Predefined(predefined) => Some(Self::Predefined(**predefined)),
+            CssColor::LAB(lab) => Some(Self::Lab(**lab)),
+            CssColor::RGBA(_) | CssColor::Float(_) => RGBA::try_from(color).ok().map(Self::Srgb),
+            CssColor::CurrentColor | CssColor::LightDark(..) | CssColor::System(_) => None,
+        }
+    }
+
+    /// The color in sRGB as floats, unmapped, with `none` components as
+    /// zero.
+    fn srgb_floats(&self) -> SRGB {
+        let srgb = match *self {
+            Self::Srgb(rgba) => SRGB::from(rgba),
+            Self::Predefined(predefined) => SRGB::from(predefined),
+            Self::Lab(lab) => SRGB::from(lab),
+        };
+        srgb.resolve_missing()
+    }
+
+    /// The color in sRGB, gamut mapped if it lies outside it.
+    pub fn to_srgb(&self) -> RGBA {
+        match *self {
+            Self::Srgb(rgba) => rgba,
+            _ => RGBA::from(self.srgb_floats()),
+        }
+    }
+
+    /// The color in Display P3, gamut mapped if it lies outside it.
+    pub fn to_display_p3(&self) -> P3 {
+        let display_p3 = match *self {
+            Self::Srgb(rgba) => P3::from(SRGB::from(rgba)),
+            Self::Predefined(predefined) => P3::from(predefined),
+            Self::Lab(lab) => P3::from(lab),
+        };
+        display_p3.resolve()
+    }
+
+    /// Whether the color can't be shown in sRGB without changing it.
+    pub fn is_wide_gamut(&self) -> bool {
+        !matches!(self, Self::Srgb(_)) && !self.srgb_floats().in_gamut()
+    }
+
+    /// Opacity between 0 and 1.
+    pub fn alpha(&self) -> f32 {
+        self.srgb_floats().alpha
+    }
+
+    /// Whether the color paints nothing.
+    pub fn is_transparent(&self) -> bool {
+        self.alpha() <= 0.0
+    }
+}
+
+impl From<RGBA> for ComputedColor {
+    fn from(rgba: RGBA) -> Self {
+        Self::Srgb(rgba)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightningcss::traits::Parse as _;
+
+    fn computed(css: &str) -> ComputedColor {
+        let color = CssColor::parse_string(css).unwrap();
+        ComputedColor::from_css(&color).unwrap()
+    }
+
+    #[test]
+    fn srgb_colors_stay_bytes() {
+        assert_eq!(
+            computed("#ff8000"),
+            ComputedColor::Srgb(RGBA::new(255, 128, 0, 1.0))
+        );
+        assert!(!computed("rgb(10 20 30)").is_wide_gamut());
+    }
+
+    #[test]
+    fn display_p3_is_kept_and_mapped_for_srgb() {
+        let red = computed("color(display-p3 1 0 0)");
+        assert!(matches!(
+            red,
+            ComputedColor::Predefined(PredefinedColor::DisplayP3(_))
+        ));
+        assert!(red.is_wide_gamut());
+        let display_p3 = red.to_display_p3();
+        assert_eq!((display_p3.r, display_p3.g, display_p3.b), (1.0, 0.0, 0.0));
+        let srgb = red.to_srgb();
+        assert_eq!((srgb.red, srgb.alpha), (255, 255));
+    }
+
+    #[test]
+    fn in_gamut_p3_converts_exactly() {
+        let white = computed("color(display-p3 1 1 1 / 50%)");
+        assert!(!white.is_wide_gamut());
+        assert_eq!(white.to_srgb(), RGBA::new(255, 255, 255, 0.5));
+        assert!((white.alpha() - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn context_dependent_colors_have_no_computed_value() {
+        let current = CssColor::CurrentColor;
+        assert_eq!(ComputedColor::from_css(&current), None);
+    }
+}
@@ -0,0 +1,297 @@
+//! Named CSS counters (CSS Lists 3 §4).
+//!
+//! `counter-reset` creates a counter instance on an element, scoped to the
+//! element, its descendants and its following siblings with their
+//! descendants. `counter-set` and `counter-increment` change the innermost
+//! instance in scope, and instantiate one at zero if there is none.
+//! `::before` and `::after` boxes count as the first and last child of
+//! their element, so they see and change counters in document order.
+//! Elements with `display: none`, and their subtrees, don't count.
+//!
+//! The counters in scope at every box that reads them are found in one
+//! walk of the document. Comparing two walks finds the boxes a change to
+//! the counters reached.
+
+use lightningcss::properties::custom::{Token, TokenList, TokenOrValue};
+use rewrite_core::{GeneratedBox, NodeId};
+use rewrite_html::{DomTree, NodeData};
+use std::collections::HashMap;
+
+/// The counter properties cascaded onto an element or pseudo-element,
+/// as `(name, value)` pairs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CounterChanges {
+    pub reset: Vec<(String, i32)>,
+    pub set: Vec<(String, i32)>,
+    pub increment: Vec<(String, i32)>,
+    /// Whether the box reads counters: a generated box, or a list item
+    /// with a marker.
+    pub reads: bool,
+}
+
+/// The counter instances in scope at some point of the document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Counters {
+    /// Innermost last. `owner` is the element whose subtree scopes the
+    /// instance.
+    instances: Vec<Instance>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Instance {
+    name: String,
+    value: i32,
+    owner: NodeId,
+}
+
+impl Counters {
+    /// Values of every instance of `name` in scope, outermost first.
+    pub fn values(&self, name: &str) -> Vec<i32> {
+        self.instances
+            .iter()
+            .filter(|instance| instance.name == name)
+            .map(|instance| instance.value)
+            .collect()
+    }
+
+    /// Apply the counter properties of a box whose parent is `owner`.
+    fn apply(&mut self, changes: &CounterChanges, owner: NodeId) {
+        for (name, value) in &changes.reset {
+            let innermost = self
+                .instances
+                .iter_mut()
+                .rfind(|instance| instance.name == *name);
+            match innermost {
+                // A sibling's reset of the same counter is replaced.
+                Some(instance) if instance.owner == owner => instance.value = *value,
+                _ => self.instances.push(Instance {
+                    name: name.clone(),
+                    value: *value,
+                    owner,
+                }),
+            }
+        }
+        for (name, value) in &changes.set {
+            *self.innermost(name, owner) = *value;
+        }
+        for (name, value) in &changes.increment {
+            let current = self.innermost(name, owner);
+            *current = current.saturating_add(*value);
+        }
+    }
+
+    /// The value of the innermost instance of `name`, instantiated at
+    /// zero on `owner` if there is none.
+    fn innermost(&mut self, name: &str, owner: NodeId) -> &mut i32 {
+        let index = match self
+            .instances
+            .iter()
+            .rposition(|instance| instance.name == name)
+        {
+            Some(index) => index,
+            None => {
+                self.instances.push(Instance {
+                    name: name.to_owned(),
+                    value: 0,
+                    owner,
+                });
+                self.instances.len() - 1
+            }
+        };
+        &mut self.instances[index].value
+    }
+
+    /// Leave the subtree of `owner`, ending the instances it scopes.
+    fn leave(&mut self, owner: NodeId) {
+        self.instances.retain(|instance| instance.owner != owner);
+    }
+}
+
+/// Where in the document counters are read: on an element itself, or on
+/// one of its generated boxes.
+pub type CounterTarget = (NodeId, Option<GeneratedBox>);
+
+/// The counter properties of an element (`None`) or of one of its
+/// generated boxes, or `None` for an element that doesn't count, such as
+/// one with `display: none`, or a box that isn't generated.
+pub type ChangesLookup<'lookup> =
+    &'lookup dyn Fn(NodeId, Option<GeneratedBox>) -> Option<CounterChanges>;
+
+/// The counters in scope at every box that reads them, from one walk of
+/// the document.
+#[derive(Debug, Default)]
+pub struct CounterScopes {
+    /// Counters in scope at each reading box, after its own counter
+    /// properties are applied.
+    read: HashMap<CounterTarget, Counters>,
+}
+
+impl CounterScopes {
+    /// Walk the document in order, applying the counter properties
+    /// `changes` gives.
+    pub fn compute(tree: &DomTree, changes: ChangesLookup<'_>) -> Self {
+        let mut scopes = Self::default();
+        let mut counters = Counters::default();
+        scopes.visit(tree, NodeId::ROOT, changes, &mut counters);
+        scopes
+    }
+
+    /// Values of every instance of `name` in scope at `target`, outermost
+    /// first. Empty unless `target` reads counters.
+    pub fn values(&self, target: CounterTarget, name: &str) -> Vec<i32> {
+        self.read
+            .get(&target)
+            .map_or_else(Vec::new, |counters| counters.values(name))
+    }
+
+    /// Elements with a box that reads different counters than in `old`,
+    /// or that started or stopped reading them, by node ID. Every element
+    /// with a reading box if there is no `old`.
+    pub fn changed_users(&self, old: Option<&Self>) -> Vec<NodeId> {
+        let mut users: Vec<NodeId> = old.map_or_else(
+            || self.read.keys().map(|&(node, _)| node).collect(),
+            |old| {
+                self.read
+                    .iter()
+                    .filter(|&(target, counters)| old.read.get(target) != Some(counters))
+                    .map(|(&(node, _), _)| node)
+                    .chain(
+                        old.read
+                            .keys()
+                            .filter(|target| !self.read.contains_key(target))
+                            .map(|&(node, _)| node),
+                    )
+                    .collect()
+            },
+        );
+        users.sort_unstable_by_key(|node| node.0);
+        users.dedup();
+        users
+    }
+
+    /// Walk the subtree of `node` in document order, recording the
+    /// counters at each reading box, and leave the instances it scopes.
+    fn visit(
+        &mut self,
+        tree: &DomTree,
+        node: NodeId,
+        changes: ChangesLookup<'_>,
+        counters: &mut Counters,
+    ) {
+        let is_element = matches!(tree.get_node(node), Some(NodeData::Element { .. }));
+        if node != NodeId::ROOT && !is_element {
+            return;
+        }
+        if is_element {
+            let Some(own) = changes(node, None) else {
+                return;
+            };
+            let owner = tree.parent(node).unwrap_or(NodeId::ROOT);
+            self.apply(&own, (node, None), owner, counters);
+        }
+        if let Some(before) = changes(node, Some(GeneratedBox::Before)) {
+            self.apply(&before, (node, Some(GeneratedBox::Before)), node, counters);
+        }
+        // The tree's child lists run in reverse DOM order.
+        let mut children: Vec<NodeId> = tree.children(node).collect();
+        children.reverse();
+        for child in children {
+            self.visit(tree, child, changes, counters);
+        }
+        if let Some(after) = changes(node, Some(GeneratedBox::After)) {
+            self.apply(&after, (node, Some(GeneratedBox::After)), node, counters);
+        }
+        counters.leave(node);
+    }
+
+    /// Apply the counter properties of `target`, whose parent is `owner`,
+    /// recording the counters there if it reads them.
+    fn apply(
+        &mut self,
+        changes: &CounterChanges,
+        target: CounterTarget,
+        owner: NodeId,
+        counters: &mut Counters,
+    ) {
+        counters.apply(changes, owner);
+        if changes.reads {
+            self.read.insert(target, counters.clone());
+        }
+    }
+}
+
+/// Parse the value of `counter-reset`, `counter-set` or
+/// `counter-increment`: counter names, each optionally followed by an
+/// integer that defaults to `default`. `none` gives nothing.
+pub fn parse_counter_list(tokens: &TokenList<'_>, default: i32) -> Vec<(String, i32)> {
+    let mut counters: Vec<(String, i32)> = Vec::new();
+    for token in &tokens.0 {
+        match token {
+            TokenOrValue::Token(Token::Ident(name)) => {
+                counters.push((name.to_string(), default));
+            }
+            TokenOrValue::Token(Token::Number {
+                int_value: Some(value),
+                ..
+            }) => {
+                if let Some(last) = counters.last_mut() {
+                    last.1 = *value;
+                }
+            }
+            _ => {}
+        }
+    }
+    counters.retain(|(name, _)| name != "none");
+    counters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightningcss::properties::Property;
+    use lightningcss::stylesheet::ParserOptions;
+
+    fn tokens(value: &'static str) -> TokenList<'static> {
+        let prop = Property::parse_string("counter-reset".into(), value, ParserOptions::default())
+            .expect("parses");
+        match prop {
+            Property::Custom(custom) => custom.value,
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_counter_lists() {
+        assert_eq!(
+            parse_counter_list(&tokens("chapter section 3"), 0),
+            vec![("chapter".to_owned(), 0), ("section".to_owned(), 3)]
+        );
+        assert_eq!(
+            parse_counter_list(&tokens("item -2"), 1),
+            vec![("item".to_owned(), -2)]
+        );
+        assert!(parse_counter_list(&tokens("none"), 1).is_empty());
+    }
+
+    #[test]
+    fn sibling_resets_share_a_scope() {
+        let owner = NodeId(1);
+        let mut counters = Counters::default();
+        let reset = CounterChanges {
+            reset: vec![("a".to_owned(), 0)],
+            ..CounterChanges::default()
+        };
+        let increment = CounterChanges {
+            increment: vec![("a".to_owned(), 1)],
+            ..CounterChanges::default()
+        };
+        counters.apply(&reset, owner);
+        counters.apply(&increment, owner);
+        counters.apply(&reset, NodeId(2));
+        counters.apply(&increment, NodeId(2));
+        assert_eq!(counters.values("a"), vec![1, 1]);
+        counters.leave(NodeId(2));
+        counters.apply(&reset, owner);
+        assert_eq!(counters.values("a"), vec![0]);
+    }
+}
@@ -0,0 +1,268 @@
+//! Forced-colors (high contrast) mode.
+//!
+//! When the embedder installs a `ForcedColorsPalette` on the `Styler`,
+//! author colors are replaced by palette colors at used-value time: the
+//! cascade and the database keep the author values, and layout never sees a
+//! change. `used_color` is the single place painting should read colors from.
+
+use crate::{ComputedColor, Styler};
+use lightningcss::properties::ui::ColorOrAuto;
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::values::color::{CssColor, RGBA, SystemColor};
+use rewrite_core::{Database, NodeId};
+use rewrite_html::{DomTree, NodeData};
+
+/// System colors supplied by the embedder for forced-colors mode.
+///
+/// The default is the light CSS system color scheme, which is also used to
+/// resolve system color keywords when forced colors are off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForcedColorsPalette {
+    pub canvas: RGBA,
+    pub canvas_text: RGBA,
+    pub link_text: RGBA,
+    pub visited_text: RGBA,
+    pub active_text: RGBA,
+    pub button_face: RGBA,
+    pub button_text: RGBA,
+    pub button_border: RGBA,
+    pub field: RGBA,
+    pub field_text: RGBA,
+    pub gray_text: RGBA,
+    pub highlight: RGBA,
+    pub highlight_text: RGBA,
+}
+
+impl Default for ForcedColorsPalette {
+    fn default() -> Self {
+        Self {
+            canvas: RGBA::new(255, 255, 255, 1.0),
+            canvas_text: RGBA::new(0, 0, 0, 1.0),
+            link_text: RGBA::new(0, 0, 238, 1.0),
+            visited_text: RGBA::new(85, 26, 139, 1.0),
+            active_text: RGBA::new(255, 0, 0, 1.0),
+            button_face: RGBA::new(240, 240, 240, 1.0),
+            button_text: RGBA::new(0, 0, 0, 1.0),
+            button_border: RGBA::new(118, 118, 118, 1.0),
+            field: RGBA::new(255, 255, 255, 1.0),
+            field_text: RGBA::new(0, 0, 0, 1.0),
+            gray_text: RGBA::new(109, 109, 109, 1.0),
+            highlight: RGBA::new(0, 120, 215, 1.0),
+            highlight_text: RGBA::new(255, 255, 255, 1.0),
+        }
+    }
+}
+
+impl ForcedColorsPalette {
+    /// White text on black, the common "high contrast dark" theme.
+    pub fn high_contrast_dark() -> Self {
+        Self {
+            canvas: RGBA::new(0, 0, 0, 1.0),
+            canvas_text: RGBA::new(255, 255, 255, 1.0),
+            link_text: RGBA::new(255, 255, 0, 1.0),
+            visited_text: RGBA::new(255, 255, 0, 1.0),
+            active_text: RGBA::new(255, 255, 0, 1.0),
+            button_face: RGBA::new(0, 0, 0, 1.0),
+            button_text: RGBA::new(255, 255, 255, 1.0),
+            button_border: RGBA::new(255, 255, 255, 1.0),
+            field: RGBA::new(0, 0, 0, 1.0),
+            field_text: RGBA::new(255, 255, 255, 1.0),
+            gray_text: RGBA::new(63, 242, 63, 1.0),
+            highlight: RGBA::new(26, 235, 255, 1.0),
+            highlight_text: RGBA::new(0, 0, 0, 1.0),
+        }
+    }
+
+    /// The palette color for a system color keyword.
+    pub fn system_color(&self, color: SystemColor) -> RGBA {
+        match color {
+            SystemColor::Canvas | SystemColor::Background | SystemColor::Window => self.canvas,
+            SystemColor::LinkText => self.link_text,
+            SystemColor::VisitedText => self.visited_text,
+            SystemColor::ActiveText => self.active_text,
+            SystemColor::ButtonFace
+            | SystemColor::ButtonHighlight
+            | SystemColor::ButtonShadow
+            | SystemColor::ThreeDFace => self.button_face,
+            SystemColor::ButtonText => self.button_text,
+            SystemColor::ButtonBorder => self.button_border,
+            SystemColor::Field => self.field,
+            SystemColor::FieldText => self.field_text,
+            SystemColor::GrayText => self.gray_text,
+            SystemColor::Highlight | SystemColor::SelectedItem | SystemColor::AccentColor => {
+                self.highlight
+            }
+            SystemColor::HighlightText
+            | SystemColor::SelectedItemText
+            | SystemColor::AccentColorText => self.highlight_text,
+            _ => self.canvas_text,
+        }
+    }
+}
+
+/// What a node is, as far as forced colors are concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorRole {
+    Text,
+    Link,
+    Button,
+    Field,
+    Disabled,
+}
+
+/// The used value of a color property on `node`, after `currentColor`,
+/// system colors and forced colors are applied, in sRGB.
+///
+/// Returns `None` for properties that aren't colors. Wide-gamut colors are
+/// gamut mapped; see `used_computed_color` to paint them unchanged.
+pub fn used_color(
+    styler: &Styler,
+    database: &Database,
+    node: NodeId,
+    prop_id: &PropertyId<'static>,
+) -> Option<RGBA> {
+    used_computed_color(styler, database, node, prop_id).map(|color| color.to_srgb())
+}
+
+/// `used_color` in the color space the author wrote it in.
+pub fn used_computed_color(
+    styler: &Styler,
+    database: &Database,
+    node: NodeId,
+    prop_id: &PropertyId<'static>,
+) -> Option<ComputedColor> {
+    let palette = styler.forced_colors();
+    let system = palette.unwrap_or_default();
+
+    let Some(forced) = palette else {
+        let color = declared_color(database, node, prop_id)?;
+        return resolve(&color, &system, || {
+            used_computed_color(styler, database, node, &PropertyId::Color)
+        });
+    };
+
+    let role = color_role(styler.tree(), node);
+    match prop_id {
+        PropertyId::Color => Some(ComputedColor::Srgb(match role {
+            ColorRole::Text => forced.canvas_text,
+            ColorRole::Link => forced.link_text,
+            ColorRole::Button => forced.button_text,
+            ColorRole::Field => forced.field_text,
+            ColorRole::Disabled => forced.gray_text,
+        })),
+        // Backgrounds keep the author's alpha so translucent overlays stay
+        // translucent.
+        PropertyId::BackgroundColor => {
+            let author = declared_color(database, node, prop_id)
+                .and_then(|color| resolve(&color, &system, || None))
+                .map_or_else(RGBA::transparent, |color| color.to_srgb());
+            let base = match role {
+                ColorRole::Button => forced.button_face,
+                ColorRole::Field => forced.field,
+                _ => forced.canvas,
+            };
+            Some(ComputedColor::Srgb(RGBA {
+                alpha: author.alpha,
+                ..base
+            }))
+        }
+        PropertyId::BorderTopColor
+        | PropertyId::BorderRightColor
+        | PropertyId::BorderBottomColor
+        | PropertyId::BorderLeftColor
+        | PropertyId::OutlineColor
+        | PropertyId::TextDecorationColor(_)
+        | PropertyId::CaretColor => used_computed_color(styler, database, node, &PropertyId::Color),
+        // Forced colors reset `accent-color` to `auto`.
+        PropertyId::AccentColor => Some(ComputedColor::Srgb(forced.highlight)),
+        _ => None,
+    }
+}
+
+/// Used value of `accent-color: auto`: the platform accent, which the
+/// palette supplies as its highlight.
+const AUTO_ACCENT_COLOR: CssColor = CssColor::System(SystemColor::AccentColor);
+
+/// The cascaded color for a property, if it is a color property.
+fn declared_color(
+    database: &Database,
+    node: NodeId,
+    prop_id: &PropertyId<'static>,
+) -> Option<CssColor> {
+    let Some(property) = database.get_property(node, prop_id.clone()) else {
+        // Unset colors fall back to their initial value.
+        return match prop_id {
+            PropertyId::Color => Some(CssColor::System(SystemColor::CanvasText)),
+            PropertyId::BackgroundColor => Some(CssColor::RGBA(RGBA::transparent())),
+            PropertyId::AccentColor => Some(AUTO_ACCENT_COLOR),
+            _ => Some(CssColor::CurrentColor),
+        };
+    };
+    match property {
+        Property::Color(color)
+        | Property::BackgroundColor(color)
+        | Property::BorderTopColor(color)
+        | Property::BorderRightColor(color)
+        | Property::BorderBottomColor(color)
+        | Property::BorderLeftColor(color)
+        | Property::OutlineColor(color)
+        | Property::TextDecorationColor(color, _)
+        | Property::CaretColor(ColorOrAuto::Color(color))
+        | Property::AccentColor(ColorOrAuto::Color(color)) => Some(color),
+        Property::CaretColor(ColorOrAuto::Auto) => Some(CssColor::CurrentColor),
+        Property::AccentColor(ColorOrAuto::Auto) => Some(AUTO_ACCENT_COLOR),
+        _ => None,
+    }
+}
+
+/// Compute a color. `current` supplies `currentColor`.
+fn resolve(
+    color: &CssColor,
+    system: &ForcedColorsPalette,
+    current: impl FnOnce() -> Option<ComputedColor>,
+) -> Option<ComputedColor> {
+    match color {
+        CssColor::CurrentColor => current(),
+        CssColor::System(system_color) => {
+            Some(ComputedColor::Srgb(system.system_color(*system_color)))
+        }
+        CssColor::LightDark(light, _) => resolve(light, system, current),
+        other => ComputedColor::from_css(other),
+    }
+}
+
+/// Classify `node` by its nearest interactive element ancestor (or itself).
+fn color_role(tree: &DomTree, node: NodeId) -> ColorRole {
+    let mut current = Some(node);
+    while let Some(id) = current {
+        if let Some(NodeData::Element {
+            tag, attributes, ..
+        }) = tree.get_node(id)
+        {
+            let attribute = |name: &str| attributes.get(&tree.interner.get(name)?);
+            let form_control = |role: ColorRole| {
+                if attribute("disabled").is_some() {
+                    ColorRole::Disabled
+                } else {
+                    role
+                }
+            };
+            match tree.interner.resolve(tag) {
+                "a" | "area" if attribute("href").is_some() => return ColorRole::Link,
+                "button" | "select" => return form_control(ColorRole::Button),
+                "textarea" => return form_control(ColorRole::Field),
+                "input" => {
+                    let kind = attribute("type");
+                    let role = match kind.as_deref() {
+                        Some("button" | "submit" | "reset" | "image") => ColorRole::Button,
+                        _ => ColorRole::Field,
+                    };
+                    return form_control(role);
+                }
+                _ => {}
+            }
+        }
+        current = tree.parent(id);
+    }
+    ColorRole::Text
+}
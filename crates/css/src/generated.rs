@@ -0,0 +1,47 @@
+//! `::before` and `::after` boxes (CSS Generated Content 3 §2).
+//!
+//! An element's `::before` and `::after` pseudo-elements generate an
+//! inline box when their `content` is neither `none` nor `normal` and
+//! their `display` isn't `none`. The box is the first or last child of the
+//! element and shows the text `content` evaluates to: strings, quotes,
+//! `attr()` and the element's counters (see `counters`).
+
+use lightningcss::properties::custom::TokenList;
+use lightningcss::properties::display::{Display, DisplayKeyword};
+use lightningcss::properties::{Property, PropertyId};
+
+/// The generated `::before` or `::after` box of an element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedContent {
+    /// The text `content` evaluates to.
+    pub text: String,
+    /// Winning declarations of the pseudo-element's rules. `content` is
+    /// folded into `text`.
+    pub properties: Vec<Property<'static>>,
+}
+
+impl GeneratedContent {
+    /// The winning declaration for `prop_id`, if any.
+    pub fn property(&self, prop_id: &PropertyId<'static>) -> Option<&Property<'static>> {
+        self.properties
+            .iter()
+            .find(|prop| prop.property_id() == *prop_id)
+    }
+}
+
+/// Whether a pseudo-element with these winning declarations generates a
+/// box, and its `content` if so.
+pub fn generated_content_tokens<'prop>(
+    winners: &[&'prop Property<'static>],
+) -> Option<&'prop TokenList<'static>> {
+    let hidden = winners.iter().any(|prop| {
+        matches!(
+            prop,
+            Property::Display(Display::Keyword(DisplayKeyword::None))
+        )
+    });
+    let content = winners
+        .iter()
+        .find_map(|prop| crate::lists::content_tokens(prop))?;
+    (!hidden && !crate::lists::is_normal(content)).then_some(content)
+}
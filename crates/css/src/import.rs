@@ -0,0 +1,150 @@
+//! `@import` resolution.
+//!
+//! An `@import` is replaced by the rules of the stylesheet it names, in its
+//! place, so imported rules precede the importing sheet's own rules in
+//! source order and lose ties to them. Imported rules keep the importing
+//! sheet's origin, and the import's media list wraps everything it brings
+//! in like an `@media` block.
+//!
+//! Stylesheets are fetched through the embedder's `ResourceResolver` with
+//! the URL as written. Without a resolver, or when it has nothing for the
+//! URL, the import is dropped. So is an import of a sheet that is already
+//! being imported further up the chain, which breaks cycles. `layer()` and
+//! `supports()` conditions are ignored, as the engine has neither cascade
+//! layers nor feature queries.
+
+use crate::RuleMedia;
+use crate::parser::{ParsedRule, collect_style_rules};
+use lightningcss::rules::CssRule;
+use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+use lightningcss::traits::IntoOwned as _;
+use rewrite_core::Origin;
+use std::sync::Arc;
+
+/// Supplies the contents of an external resource (such as the `href` of a
+/// `<link rel="stylesheet">` or the URL of an `@import`), or `None` if it
+/// is unavailable.
+pub type ResourceResolver = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Flattens the top-level rules of one stylesheet, following its imports.
+pub struct RuleCollector<'res> {
+    origin: Origin,
+    resolver: Option<&'res ResourceResolver>,
+    /// URLs of the sheets currently being imported, outermost first.
+    importing: Vec<String>,
+}
+
+impl<'res> RuleCollector<'res> {
+    pub fn new(origin: Origin, resolver: Option<&'res ResourceResolver>) -> Self {
+        Self {
+            origin,
+            resolver,
+            importing: Vec::new(),
+        }
+    }
+
+    /// Flatten a top-level rule into `out`, replacing an `@import` with the
+    /// imported sheet's rules.
+    pub fn collect(&mut self, rule: CssRule<'_>, out: &mut Vec<ParsedRule>) {
+        self.collect_in(rule, &RuleMedia::default(), out);
+    }
+
+    fn collect_in(&mut self, rule: CssRule<'_>, media: &RuleMedia, out: &mut Vec<ParsedRule>) {
+        let CssRule::Import(import_rule) = rule else {
+            collect_style_rules(rule, media, self.origin, out);
+            return;
+        };
+        let url = import_rule.url.to_string();
+        if self.importing.contains(&url) {
+            return;
+        }
+        let Some(css) = self.resolver.and_then(|resolver| resolver(&url)) else {
+            return;
+        };
+        let options = ParserOptions {
+            error_recovery: true,
+            ..ParserOptions::default()
+        };
+        let Ok(sheet) = StyleSheet::parse(&css, options) else {
+            return;
+        };
+        let media = if import_rule.media.media_queries.is_empty() {
+            media.clone()
+        } else {
+            media.nested(import_rule.media.into_owned())
+        };
+
+        self.importing.push(url);
+        for imported in sheet.rules.0 {
+            self.collect_in(imported, &media, out);
+        }
+        self.importing.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MediaEnvironment;
+    use lightningcss::printer::PrinterOptions;
+    use lightningcss::traits::ToCss as _;
+    use std::collections::HashMap;
+
+    /// Selector text of each collected style rule, with whether it
+    /// applies on screen.
+    fn collected(css: &str, files: &[(&'static str, &'static str)]) -> Vec<(String, bool)> {
+        let files: HashMap<&str, &str> = files.iter().copied().collect();
+        let resolver: ResourceResolver =
+            Arc::new(move |url| files.get(url).map(|sheet| (*sheet).to_owned()));
+        let sheet = StyleSheet::parse(css, ParserOptions::default()).unwrap();
+        let mut collector = RuleCollector::new(Origin::User, Some(&resolver));
+        let mut rules = Vec::new();
+        for rule in sheet.rules.0 {
+            collector.collect(rule, &mut rules);
+        }
+        rules
+            .iter()
+            .map(|rule| {
+                assert_eq!(rule.origin(), Origin::User);
+                let ParsedRule::Stylesheet { selectors, .. } = rule else {
+                    panic!("unexpected rule {rule:?}");
+                };
+                let selectors = selectors.to_css_string(PrinterOptions::default()).unwrap();
+                (selectors, rule.media_matches(MediaEnvironment::default()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn imports_are_inlined_in_source_order() {
+        let rules = collected(
+            "@import url(a.css); @import 'b.css' print; main { color: red }",
+            &[
+                ("a.css", "@import 'c.css'; a { color: red }"),
+                ("b.css", "b { color: red }"),
+                ("c.css", "c { color: red }"),
+            ],
+        );
+        let expected = [("c", true), ("a", true), ("b", false), ("main", true)];
+        assert_eq!(
+            rules,
+            expected.map(|(selector, screen)| (selector.to_owned(), screen))
+        );
+    }
+
+    #[test]
+    fn import_cycles_and_missing_sheets_are_dropped() {
+        let rules = collected(
+            "@import 'a.css'; @import 'missing.css'; main { color: red }",
+            &[
+                ("a.css", "@import 'b.css'; a { color: red }"),
+                ("b.css", "@import 'a.css'; b { color: red }"),
+            ],
+        );
+        let selectors: Vec<&str> = rules
+            .iter()
+            .map(|(selector, _)| selector.as_str())
+            .collect();
+        assert_eq!(selectors, ["b", "a", "main"]);
+    }
+}
@@ -1,14 +1,48 @@
 //! CSS parsing.
 
+pub mod analysis;
+mod calc;
+mod computed_color;
+mod counters;
+mod forced_colors;
+mod generated;
+mod import;
+mod lists;
+mod media;
+mod paged;
 mod parser;
+mod presentational;
+mod resources;
 mod selectors;
+mod sheet_worker;
+mod shorthand;
+mod snapshot;
 mod style;
 mod styler_context;
 pub mod value_resolver;
-pub use parser::{CssParser, ParsedRule, Properties};
-pub use selectors::matches_selector_list;
-pub use style::Styler;
+mod values;
+pub use analysis::{StylesheetStats, analyze_stylesheet};
+pub use computed_color::ComputedColor;
+pub use forced_colors::{ForcedColorsPalette, used_color, used_computed_color};
+pub use generated::GeneratedContent;
+pub use import::ResourceResolver;
+pub use lists::Marker;
+pub use media::{MediaEnvironment, RuleMedia};
+pub use paged::{PageBox, PageContext, PageDimensions, PageRule, prints_backgrounds};
+pub use parser::{CssParser, OpaqueAtRule, ParsedRule, Properties};
+pub use presentational::{is_presentation_attribute, presentational_hints};
+pub use resources::{ResourceKind, ResourceUrl, resource_urls};
+pub use selectors::{
+    AncestorFilter, AncestorFilterStats, AncestorHashes, StructuralFilter, matches_selector_list,
+};
+pub use sheet_worker::{ParsedStylesheet, SheetId, StylesheetWorker};
+pub use shorthand::{SHORTHANDS, Shorthand, expand_shorthand, shorthand};
+pub use snapshot::{RuleSnapshot, RuleSource, SnapshotError, StyleSnapshot};
+pub use style::{Styler, is_counter_property};
 pub use styler_context::CssPropertyResolver;
+pub use values::{
+    CssValue, ValueKind, ValueParseFn, ValueRegistry, parse_value, parse_values, values_from_tokens,
+};
 
 pub use lightningcss::properties::Property;
 pub use lightningcss::properties::PropertyId;
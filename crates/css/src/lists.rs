@@ -0,0 +1,397 @@
+//! List item counters and `::marker` boxes.
+//!
+//! `list-item` is an ordinary counter that every `display: list-item`
+//! element increments unless its `counter-increment` names it. Every `ol`,
+//! `ul` and `menu` element also resets it, so nested lists number their
+//! items independently while `counters(list-item, ".")` can still read the
+//! outer instances. The `start`, `reversed` and `value` attributes set
+//! where the count starts, its direction and an item's own value.
+//!
+//! A marker's text comes from the `content` of its `::marker` rules when
+//! one is given, and otherwise from the item's `list-style-type`. Elements
+//! with `display: list-item` generate a marker, placed by the item's
+//! `list-style-position`.
+
+use crate::counters::CounterChanges;
+use crate::values::{CssValue, values_from_tokens};
+use lightningcss::properties::custom::{Token, TokenList, TokenOrValue};
+use lightningcss::properties::list::ListStylePosition;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::NodeId;
+use rewrite_html::{DomTree, NodeData};
+use std::iter;
+use std::sync::Arc;
+
+/// Elements that instantiate a `list-item` counter.
+const LIST_ELEMENTS: &[&str] = &["ol", "ul", "menu"];
+
+/// The counter list items increment.
+const LIST_ITEM: &str = "list-item";
+
+/// The generated `::marker` box of a list item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    /// The text the marker shows, suffix included.
+    pub text: String,
+    /// `outside` markers sit in the margin before the item's first line,
+    /// `inside` ones start it.
+    pub position: ListStylePosition,
+    /// Winning `::marker` declarations, limited to the properties that
+    /// apply to markers. `content` is folded into `text`.
+    pub properties: Vec<Property<'static>>,
+}
+
+impl Marker {
+    /// The winning `::marker` declaration for `prop_id`, if any.
+    pub fn property(&self, prop_id: &PropertyId<'static>) -> Option<&Property<'static>> {
+        self.properties
+            .iter()
+            .find(|prop| prop.property_id() == *prop_id)
+    }
+}
+
+/// Whether a declaration applies to `::marker` boxes (CSS Lists 3 §3.1.1):
+/// font properties, `white-space`, `color`, `direction`, `unicode-bidi`
+/// and `content`.
+pub fn applies_to_marker(prop: &Property<'_>) -> bool {
+    match prop {
+        Property::Custom(custom) => custom.name.as_ref() == "content",
+        _ => matches!(
+            prop.property_id(),
+            PropertyId::Color
+                | PropertyId::FontFamily
+                | PropertyId::FontSize
+                | PropertyId::FontStyle
+                | PropertyId::FontWeight
+                | PropertyId::FontStretch
+                | PropertyId::FontVariantCaps
+                | PropertyId::LineHeight
+                | PropertyId::WhiteSpace
+                | PropertyId::Direction
+                | PropertyId::UnicodeBidi
+        ),
+    }
+}
+
+/// The `content` tokens of a `::marker` declaration, if `prop` is one.
+pub fn content_tokens<'prop>(prop: &'prop Property<'static>) -> Option<&'prop TokenList<'static>> {
+    match prop {
+        Property::Custom(custom) if custom.name.as_ref() == "content" => Some(&custom.value),
+        _ => None,
+    }
+}
+
+/// Add the `list-item` changes HTML lists imply to the cascaded counter
+/// properties of `node`, where those don't name `list-item` themselves.
+/// `is_item` tells whether an element is `display: list-item`.
+pub fn add_list_item_changes(
+    tree: &DomTree,
+    node: NodeId,
+    is_item: &dyn Fn(NodeId) -> bool,
+    changes: &mut CounterChanges,
+) {
+    let names = |list: &[(String, i32)]| list.iter().any(|(name, _)| name == LIST_ITEM);
+    if has_tag(tree, node, LIST_ELEMENTS) && !names(&changes.reset) {
+        let step = step(tree, node);
+        let start = integer_attribute(tree, node, "start").unwrap_or_else(|| {
+            if step < 0 {
+                items_in_order(tree, node, is_item).len() as i32
+            } else {
+                1
+            }
+        });
+        // The counter starts one step before the first item's value.
+        changes.reset.push((LIST_ITEM.to_owned(), start - step));
+    }
+    if !is_item(node) {
+        return;
+    }
+    let step = ancestors(tree, node)
+        .find(|&id| has_tag(tree, id, LIST_ELEMENTS))
+        .map_or(1, |list| step(tree, list));
+    if let Some(value) = integer_attribute(tree, node, "value")
+        && !names(&changes.set)
+    {
+        // `counter-set` applies before `counter-increment`.
+        changes.set.push((LIST_ITEM.to_owned(), value - step));
+    }
+    if !names(&changes.increment) {
+        changes.increment.push((LIST_ITEM.to_owned(), step));
+    }
+}
+
+/// How much each item changes `list`'s counter by: down for `reversed`
+/// lists.
+fn step(tree: &DomTree, list: NodeId) -> i32 {
+    if attribute(tree, list, "reversed").is_some() {
+        -1
+    } else {
+        1
+    }
+}
+
+fn integer_attribute(tree: &DomTree, node: NodeId, name: &str) -> Option<i32> {
+    attribute(tree, node, name)?.trim().parse().ok()
+}
+
+/// The list items in `list`'s counter scope, in document order. Items of
+/// nested lists belong to those lists' own counters.
+fn items_in_order(tree: &DomTree, list: NodeId, is_item: &dyn Fn(NodeId) -> bool) -> Vec<NodeId> {
+    let mut items = Vec::new();
+    let mut stack = children_in_order(tree, list);
+    stack.reverse();
+    while let Some(node) = stack.pop() {
+        if is_item(node) {
+            items.push(node);
+        }
+        if has_tag(tree, node, LIST_ELEMENTS) {
+            continue;
+        }
+        let mut children = children_in_order(tree, node);
+        children.reverse();
+        stack.extend(children);
+    }
+    items
+}
+
+/// Children of `node` in DOM order. The tree's child lists run in
+/// reverse DOM order.
+fn children_in_order(tree: &DomTree, node: NodeId) -> Vec<NodeId> {
+    let mut children: Vec<NodeId> = tree.children(node).collect();
+    children.reverse();
+    children
+}
+
+fn ancestors(tree: &DomTree, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    iter::successors(tree.parent(node), |&id| tree.parent(id))
+}
+
+fn has_tag(tree: &DomTree, node: NodeId, tags: &[&str]) -> bool {
+    match tree.get_node(node) {
+        Some(NodeData::Element { tag, .. }) => {
+            let name = tree.interner.resolve(tag);
+            tags.iter().any(|wanted| name.eq_ignore_ascii_case(wanted))
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn attribute(tree: &DomTree, node: NodeId, name: &str) -> Option<Arc<str>> {
+    let key = tree.interner.get(name)?;
+    match tree.get_node(node) {
+        Some(NodeData::Element { attributes, .. }) => attributes.get(&key),
+        _ => None,
+    }
+}
+
+/// Values of the counter a `counter()` or `counters()` function names, in
+/// scope where the content is generated, outermost first.
+pub type CounterLookup<'lookup> = &'lookup dyn Fn(&str) -> Vec<i32>;
+
+/// Marker text for a list item with the given `list-style-type`,
+/// serialized as CSS. `None` means the item has no marker.
+pub fn marker_text(
+    content: Option<&TokenList<'static>>,
+    counters: CounterLookup<'_>,
+    list_style_type: &str,
+) -> Option<String> {
+    if let Some(tokens) = content.filter(|tokens| !is_normal(tokens)) {
+        return content_text(tokens, counters, &|_| None);
+    }
+    if let Some(literal) = unquote(list_style_type) {
+        return Some(literal);
+    }
+    if is_symbolic(list_style_type) {
+        return format_counter(0, list_style_type).map(|symbol| symbol + " ");
+    }
+    let value = counters(LIST_ITEM).last().copied().unwrap_or(0);
+    Some(format_counter(value, list_style_type)? + ". ")
+}
+
+/// Whether a `content` value is `normal`, which keeps the list style's
+/// marker and generates no `::before` or `::after` box.
+pub(crate) fn is_normal(tokens: &TokenList<'static>) -> bool {
+    matches!(
+        values_from_tokens(tokens).as_slice(),
+        [CssValue::Keyword(keyword)] if keyword == "normal"
+    )
+}
+
+/// Evaluate a non-`normal` `content` value: strings, quotes, `counter()`,
+/// `counters()` and `attr()`, whose attribute values `attribute` gives.
+/// `none` generates no box.
+pub(crate) fn content_text(
+    tokens: &TokenList<'static>,
+    counters: CounterLookup<'_>,
+    attribute: &dyn Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let mut text = String::new();
+    for token in &tokens.0 {
+        match token {
+            TokenOrValue::Token(Token::String(value)) => text.push_str(value),
+            TokenOrValue::Token(Token::Ident(ident)) => match ident.as_ref() {
+                "none" => return None,
+                "open-quote" => text.push('\u{201c}'),
+                "close-quote" => text.push('\u{201d}'),
+                _ => {}
+            },
+            TokenOrValue::Function(function) => {
+                let arguments = function_arguments(&function.arguments);
+                match function.name.0.as_ref() {
+                    "counter" => text.push_str(&counter_function(&arguments, counters)),
+                    "counters" => text.push_str(&counters_function(&arguments, counters)),
+                    "attr" => {
+                        let name = arguments.first().map_or("", String::as_str);
+                        text.push_str(&attribute(name).unwrap_or_default());
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(text)
+}
+
+/// Comma-separated idents and strings of a function's arguments.
+fn function_arguments(tokens: &TokenList<'static>) -> Vec<String> {
+    let mut arguments = vec![String::new()];
+    for token in &tokens.0 {
+        match token {
+            TokenOrValue::Token(Token::Comma) => arguments.push(String::new()),
+            TokenOrValue::Token(Token::Ident(value) | Token::String(value)) => {
+                if let Some(last) = arguments.last_mut() {
+                    last.push_str(value);
+                }
+            }
+            _ => {}
+        }
+    }
+    arguments
+}
+
+/// `counter(name[, style])`. A counter not in scope reads as zero.
+fn counter_function(arguments: &[String], counters: CounterLookup<'_>) -> String {
+    let name = arguments.first().map_or("", String::as_str);
+    let value = counters(name).last().copied().unwrap_or(0);
+    let style = arguments.get(1).map_or("decimal", String::as_str);
+    format_counter(value, style).unwrap_or_default()
+}
+
+/// `counters(name, separator[, style])`.
+fn counters_function(arguments: &[String], counters: CounterLookup<'_>) -> String {
+    let name = arguments.first().map_or("", String::as_str);
+    let mut values = counters(name);
+    if values.is_empty() {
+        values.push(0);
+    }
+    let separator = arguments.get(1).map_or("", String::as_str);
+    let style = arguments.get(2).map_or("decimal", String::as_str);
+    values
+        .iter()
+        .map(|&value| format_counter(value, style).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// The text of a quoted `list-style-type` string, e.g. `"- "`.
+fn unquote(list_style_type: &str) -> Option<String> {
+    let inner = list_style_type
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))?;
+    Some(inner.replace("\\\"", "\""))
+}
+
+fn is_symbolic(style: &str) -> bool {
+    matches!(style, "disc" | "circle" | "square")
+}
+
+/// Represent `value` in a predefined counter style. Styles outside their
+/// range fall back to `decimal`; `none` has no representation.
+pub fn format_counter(value: i32, style: &str) -> Option<String> {
+    Some(match style {
+        "none" => return None,
+        "disc" => "\u{2022}".to_owned(),
+        "circle" => "\u{25e6}".to_owned(),
+        "square" => "\u{25aa}".to_owned(),
+        "decimal-leading-zero" if (0..10).contains(&value) => format!("0{value}"),
+        "lower-alpha" | "lower-latin" if value > 0 => alphabetic(value),
+        "upper-alpha" | "upper-latin" if value > 0 => alphabetic(value).to_ascii_uppercase(),
+        "lower-roman" if (1..4000).contains(&value) => roman(value),
+        "upper-roman" if (1..4000).contains(&value) => roman(value).to_ascii_uppercase(),
+        _ => value.to_string(),
+    })
+}
+
+/// `a`, `b`, ..., `z`, `aa`, `ab`, ... for a positive value.
+fn alphabetic(value: i32) -> String {
+    let mut remaining = value;
+    let mut letters = Vec::new();
+    while remaining > 0 {
+        remaining -= 1;
+        letters.push(b'a' + (remaining % 26) as u8);
+        remaining /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+/// Lowercase roman numerals for a value in `1..4000`.
+fn roman(value: i32) -> String {
+    const NUMERALS: [(i32, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut remaining = value;
+    let mut text = String::new();
+    for (amount, numeral) in NUMERALS {
+        while remaining >= amount {
+            text.push_str(numeral);
+            remaining -= amount;
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_predefined_counter_styles() {
+        assert_eq!(format_counter(3, "decimal").as_deref(), Some("3"));
+        assert_eq!(
+            format_counter(7, "decimal-leading-zero").as_deref(),
+            Some("07")
+        );
+        assert_eq!(format_counter(28, "lower-alpha").as_deref(), Some("ab"));
+        assert_eq!(
+            format_counter(1994, "upper-roman").as_deref(),
+            Some("MCMXCIV")
+        );
+        assert_eq!(format_counter(0, "lower-roman").as_deref(), Some("0"));
+        assert_eq!(format_counter(2, "none"), None);
+        let nested = |_: &str| vec![1, 4];
+        let second = |_: &str| vec![2];
+        assert_eq!(
+            marker_text(None, &nested, "lower-alpha").as_deref(),
+            Some("d. ")
+        );
+        assert_eq!(
+            marker_text(None, &second, "disc").as_deref(),
+            Some("\u{2022} ")
+        );
+        assert_eq!(marker_text(None, &second, "\"-\"").as_deref(), Some("-"));
+    }
+}
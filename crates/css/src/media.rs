@@ -0,0 +1,319 @@
+//! Media query evaluation.
+//!
+//! Style rules nested in `@media` blocks keep their conditions as a
+//! `RuleMedia` and are only matched while the Styler's `MediaEnvironment`
+//! satisfies them. Features the engine cannot answer evaluate to "unknown",
+//! which (as in browsers) makes the enclosing query false.
+//!
+//! The viewport features (`width`, `height`, `aspect-ratio`,
+//! `orientation`) are answered once the renderer has set a viewport size;
+//! changing it rematches every node like any other environment change.
+
+use lightningcss::media_query::{
+    MediaCondition, MediaFeature, MediaFeatureComparison, MediaFeatureId, MediaFeatureName,
+    MediaFeatureValue, MediaList, MediaQuery, MediaType, Operator, Qualifier, QueryFeature,
+};
+use lightningcss::values::length::{Length, LengthValue};
+use std::sync::Arc;
+
+/// The environment media queries are evaluated against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MediaEnvironment {
+    /// Whether forced-colors mode is active (`(forced-colors: active)`).
+    pub forced_colors: bool,
+    /// Whether the document is being paginated for print (the `print`
+    /// media type instead of `screen`).
+    pub print: bool,
+    /// Viewport `(width, height)` in CSS px. Without one, viewport
+    /// features are unknown.
+    pub viewport: Option<(u32, u32)>,
+}
+
+/// Font size `em` and `rem` resolve against in media queries: the initial
+/// value, since queries can't see the document's styles.
+const QUERY_FONT_SIZE: f32 = 16.0;
+
+impl MediaEnvironment {
+    /// Whether a media query list matches. An empty list always matches.
+    pub fn matches(self, list: &MediaList<'_>) -> bool {
+        list.media_queries.is_empty()
+            || list
+                .media_queries
+                .iter()
+                .any(|query| self.query_matches(query))
+    }
+
+    fn query_matches(self, query: &MediaQuery<'_>) -> bool {
+        let type_matches = match query.media_type {
+            MediaType::All => true,
+            MediaType::Screen => !self.print,
+            MediaType::Print => self.print,
+            MediaType::Custom(_) => false,
+        };
+        let condition = query
+            .condition
+            .as_ref()
+            .map_or(Some(true), |condition| self.evaluate_condition(condition));
+        // An unknown condition makes the whole query false, even under `not`.
+        let Some(condition) = condition else {
+            return false;
+        };
+        let matched = type_matches && condition;
+        if query.qualifier == Some(Qualifier::Not) {
+            !matched
+        } else {
+            matched
+        }
+    }
+
+    /// Three-valued evaluation: `None` is "unknown".
+    fn evaluate_condition(self, condition: &MediaCondition<'_>) -> Option<bool> {
+        match condition {
+            MediaCondition::Feature(feature) => self.evaluate_feature(feature),
+            MediaCondition::Not(inner) => self.evaluate_condition(inner).map(|value| !value),
+            MediaCondition::Operation {
+                operator,
+                conditions,
+            } => {
+                let mut results = conditions
+                    .iter()
+                    .map(|inner| self.evaluate_condition(inner));
+                match operator {
+                    Operator::And => results.try_fold(true, |acc, value| Some(acc && value?)),
+                    Operator::Or => any_known(results),
+                }
+            }
+            MediaCondition::Unknown(_) => None,
+        }
+    }
+
+    fn evaluate_feature(self, feature: &MediaFeature<'_>) -> Option<bool> {
+        match feature {
+            QueryFeature::Boolean {
+                name: MediaFeatureName::Standard(MediaFeatureId::ForcedColors),
+            } => Some(self.forced_colors),
+            QueryFeature::Plain {
+                name: MediaFeatureName::Standard(MediaFeatureId::ForcedColors),
+                value: MediaFeatureValue::Ident(ident),
+            } => match ident.0.as_ref() {
+                "active" => Some(self.forced_colors),
+                "none" => Some(!self.forced_colors),
+                _ => None,
+            },
+            QueryFeature::Boolean {
+                name: MediaFeatureName::Standard(id),
+            } => self.viewport_feature(*id).map(|value| value != 0.0),
+            QueryFeature::Plain {
+                name: MediaFeatureName::Standard(id),
+                value,
+            } => self.compare_feature(*id, MediaFeatureComparison::Equal, value),
+            QueryFeature::Range {
+                name: MediaFeatureName::Standard(id),
+                operator,
+                value,
+            } => self.compare_feature(*id, *operator, value),
+            QueryFeature::Interval {
+                name: MediaFeatureName::Standard(id),
+                start,
+                start_operator,
+                end,
+                end_operator,
+            } => {
+                // `start < width` is `width > start`.
+                let above_start = self.compare_feature(*id, flip(*start_operator), start)?;
+                let below_end = self.compare_feature(*id, *end_operator, end)?;
+                Some(above_start && below_end)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the feature `id` compares to `value` as `operator` says,
+    /// with the feature on the left.
+    fn compare_feature(
+        self,
+        id: MediaFeatureId,
+        operator: MediaFeatureComparison,
+        value: &MediaFeatureValue<'_>,
+    ) -> Option<bool> {
+        if id == MediaFeatureId::Orientation {
+            let MediaFeatureValue::Ident(ident) = value else {
+                return None;
+            };
+            let (width, height) = self.viewport?;
+            return match ident.0.as_ref() {
+                "portrait" => Some(height >= width),
+                "landscape" => Some(width > height),
+                _ => None,
+            };
+        }
+        let actual = self.viewport_feature(id)?;
+        let expected = match (id, value) {
+            (MediaFeatureId::Width | MediaFeatureId::Height, MediaFeatureValue::Length(length)) => {
+                length_px(length)?
+            }
+            (MediaFeatureId::AspectRatio, MediaFeatureValue::Ratio(ratio)) => ratio.0 / ratio.1,
+            (MediaFeatureId::AspectRatio, MediaFeatureValue::Number(number)) => *number,
+            (MediaFeatureId::AspectRatio, MediaFeatureValue::Integer(integer)) => *integer as f32,
+            _ => return None,
+        };
+        Some(match operator {
+            MediaFeatureComparison::Equal => (actual - expected).abs() < f32::EPSILON,
+            MediaFeatureComparison::GreaterThan => actual > expected,
+            MediaFeatureComparison::GreaterThanEqual => actual >= expected,
+            MediaFeatureComparison::LessThan => actual < expected,
+            MediaFeatureComparison::LessThanEqual => actual <= expected,
+        })
+    }
+
+    /// Value of a numeric viewport feature, or `None` if `id` isn't one or
+    /// there is no viewport.
+    fn viewport_feature(self, id: MediaFeatureId) -> Option<f32> {
+        let (width, height) = self.viewport?;
+        match id {
+            MediaFeatureId::Width => Some(width as f32),
+            MediaFeatureId::Height => Some(height as f32),
+            MediaFeatureId::AspectRatio => Some(width as f32 / height.max(1) as f32),
+            _ => None,
+        }
+    }
+}
+
+/// The comparison with its operands swapped.
+fn flip(operator: MediaFeatureComparison) -> MediaFeatureComparison {
+    match operator {
+        MediaFeatureComparison::Equal => MediaFeatureComparison::Equal,
+        MediaFeatureComparison::GreaterThan => MediaFeatureComparison::LessThan,
+        MediaFeatureComparison::GreaterThanEqual => MediaFeatureComparison::LessThanEqual,
+        MediaFeatureComparison::LessThan => MediaFeatureComparison::GreaterThan,
+        MediaFeatureComparison::LessThanEqual => MediaFeatureComparison::GreaterThanEqual,
+    }
+}
+
+/// A query length in px. Font-relative units use the initial font size.
+fn length_px(length: &Length) -> Option<f32> {
+    match length {
+        Length::Value(LengthValue::Em(value) | LengthValue::Rem(value)) => {
+            Some(value * QUERY_FONT_SIZE)
+        }
+        Length::Value(value) => value.to_px(),
+        Length::Calc(_) => None,
+    }
+}
+
+/// Three-valued `or`: true if any value is true, unknown if none is true but
+/// some are unknown.
+fn any_known(values: impl Iterator<Item = Option<bool>>) -> Option<bool> {
+    let mut unknown = false;
+    for value in values {
+        match value {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => unknown = true,
+        }
+    }
+    (!unknown).then_some(false)
+}
+
+/// The `@media` conditions enclosing a style rule, outermost first. All of
+/// them must match for the rule to apply.
+#[derive(Debug, Clone, Default)]
+pub struct RuleMedia(Arc<[MediaList<'static>]>);
+
+impl RuleMedia {
+    /// Conditions for a rule nested one `@media` block deeper.
+    #[must_use]
+    pub fn nested(&self, list: MediaList<'static>) -> Self {
+        Self(self.0.iter().cloned().chain([list]).collect())
+    }
+
+    /// The enclosing conditions, outermost first.
+    pub fn lists(&self) -> &[MediaList<'static>] {
+        &self.0
+    }
+
+    /// Whether the rule is outside any `@media` block.
+    pub fn is_unconditional(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether every enclosing condition matches `env`.
+    pub fn matches(&self, env: MediaEnvironment) -> bool {
+        self.0.iter().all(|list| env.matches(list))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightningcss::rules::CssRule;
+    use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+    use lightningcss::traits::IntoOwned as _;
+
+    fn query(text: &str) -> MediaList<'static> {
+        let css = format!("@media {text} {{ a {{ color: red }} }}");
+        let sheet = StyleSheet::parse(&css, ParserOptions::default()).unwrap();
+        sheet
+            .rules
+            .0
+            .into_iter()
+            .find_map(|rule| match rule {
+                CssRule::Media(media_rule) => Some(media_rule.query.into_owned()),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn forced_colors_feature() {
+        let normal = MediaEnvironment::default();
+        let forced = MediaEnvironment {
+            forced_colors: true,
+            ..MediaEnvironment::default()
+        };
+        assert!(forced.matches(&query("(forced-colors: active)")));
+        assert!(!normal.matches(&query("(forced-colors: active)")));
+        assert!(normal.matches(&query("(forced-colors: none)")));
+        assert!(forced.matches(&query("screen and (forced-colors)")));
+        assert!(!forced.matches(&query("print and (forced-colors)")));
+    }
+
+    #[test]
+    fn unknown_features_never_match() {
+        let env = MediaEnvironment::default();
+        assert!(!env.matches(&query("(min-width: 100px)")));
+        assert!(!env.matches(&query("not (min-width: 100px)")));
+        assert!(env.matches(&query("(min-width: 100px), screen")));
+    }
+
+    #[test]
+    fn viewport_features() {
+        let env = MediaEnvironment {
+            viewport: Some((800, 600)),
+            ..MediaEnvironment::default()
+        };
+        assert!(env.matches(&query("(min-width: 600px)")));
+        assert!(!env.matches(&query("(max-width: 600px)")));
+        assert!(env.matches(&query("(width = 800px)")));
+        assert!(env.matches(&query("(400px < width <= 800px)")));
+        assert!(!env.matches(&query("(400px < width < 800px)")));
+        assert!(env.matches(&query("(max-height: 40em)")));
+        assert!(env.matches(&query("(orientation: landscape)")));
+        assert!(env.matches(&query("(min-aspect-ratio: 4/3)")));
+        assert!(!env.matches(&query("(min-aspect-ratio: 16/9)")));
+        assert!(env.matches(&query("screen and (width)")));
+    }
+
+    #[test]
+    fn print_media_type() {
+        let screen = MediaEnvironment::default();
+        let print = MediaEnvironment {
+            print: true,
+            ..MediaEnvironment::default()
+        };
+        assert!(print.matches(&query("print")));
+        assert!(!print.matches(&query("screen")));
+        assert!(!screen.matches(&query("print")));
+        assert!(screen.matches(&query("not print")));
+    }
+}
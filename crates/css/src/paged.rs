@@ -0,0 +1,365 @@
+//! Paged media: `@page` rules and page boxes.
+//!
+//! `@page` rules are kept apart from style rules (they never match DOM
+//! nodes) and cascaded per page on demand. Only `size` and the margin
+//! longhands are used; page-margin boxes (`@top-left` etc.) are ignored.
+
+use crate::values::{CssValue, ValueKind, ValueRegistry};
+use crate::{Properties, RuleMedia};
+use lightningcss::printer::PrinterOptions;
+use lightningcss::properties::ui::PrintColorAdjust;
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::rules::page::{PagePseudoClass, PageSelector};
+use lightningcss::values::length::{LengthPercentage, LengthPercentageOrAuto};
+use lightningcss::vendor_prefix::VendorPrefix;
+use rewrite_core::{Database, NodeId, Origin, Subpixel};
+
+/// CSS px per millimetre.
+const PX_PER_MM: f32 = 96.0 / 25.4;
+
+/// A parsed `@page` rule.
+#[derive(Debug, Clone)]
+pub struct PageRule {
+    /// Page selectors; empty matches every page.
+    pub selectors: Vec<PageSelector<'static>>,
+    /// Declarations, with `margin` expanded to longhands. `size` is kept as
+    /// an unparsed custom property and read by `page_box`.
+    pub properties: Properties,
+    pub origin: Origin,
+    /// Enclosing `@media` conditions.
+    pub media: RuleMedia,
+}
+
+/// Which page a page box is being computed for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PageContext {
+    /// Zero-based page index. With left-to-right page progression the
+    /// first page is a right page.
+    pub index: usize,
+    /// Whether the page was inserted only to satisfy a forced break.
+    pub blank: bool,
+}
+
+/// Page dimensions in CSS px.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageDimensions {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PageDimensions {
+    /// US Letter, 8.5in × 11in.
+    pub const LETTER: Self = Self {
+        width: 816.0,
+        height: 1056.0,
+    };
+    /// ISO A4, 210mm × 297mm.
+    pub const ISO_A4: Self = Self::from_mm(210.0, 297.0);
+
+    const fn from_mm(width: f32, height: f32) -> Self {
+        Self {
+            width: width * PX_PER_MM,
+            height: height * PX_PER_MM,
+        }
+    }
+
+    const fn from_in(width: f32, height: f32) -> Self {
+        Self {
+            width: width * 96.0,
+            height: height * 96.0,
+        }
+    }
+
+    fn landscape(self) -> Self {
+        Self {
+            width: self.width.max(self.height),
+            height: self.width.min(self.height),
+        }
+    }
+
+    fn portrait(self) -> Self {
+        Self {
+            width: self.width.min(self.height),
+            height: self.width.max(self.height),
+        }
+    }
+}
+
+/// The page box for one page: sheet size and page margins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageBox {
+    pub width: Subpixel,
+    pub height: Subpixel,
+    pub margin_top: Subpixel,
+    pub margin_right: Subpixel,
+    pub margin_bottom: Subpixel,
+    pub margin_left: Subpixel,
+}
+
+impl PageBox {
+    /// Width of the page area (the box content is laid out into).
+    pub fn content_width(&self) -> Subpixel {
+        self.width - self.margin_left - self.margin_right
+    }
+
+    /// Height of the page area.
+    pub fn content_height(&self) -> Subpixel {
+        self.height - self.margin_top - self.margin_bottom
+    }
+}
+
+/// Page selector specificity per css-page-3: (named page, `:first`/`:blank`
+/// count, `:left`/`:right` count).
+type PageSpecificity = (u8, u8, u8);
+
+/// Origin/importance precedence, page specificity, then source order.
+type PageCascadeKey = (u8, PageSpecificity, usize);
+
+/// Specificity of the most specific selector of `rule` that matches `page`,
+/// or `None` if the rule doesn't apply. Named pages aren't supported, so
+/// selectors with a page name never match.
+fn matching_specificity(rule: &PageRule, page: PageContext) -> Option<PageSpecificity> {
+    if rule.selectors.is_empty() {
+        return Some((0, 0, 0));
+    }
+    rule.selectors
+        .iter()
+        .filter(|selector| selector.name.is_none())
+        .filter_map(|selector| {
+            let mut specificity = (0, 0, 0);
+            for pseudo in &selector.pseudo_classes {
+                let matches = match pseudo {
+                    PagePseudoClass::First => page.index == 0,
+                    PagePseudoClass::Blank => page.blank,
+                    PagePseudoClass::Right => page.index % 2 == 0,
+                    PagePseudoClass::Left => page.index % 2 == 1,
+                    PagePseudoClass::Last => false,
+                };
+                if !matches {
+                    return None;
+                }
+                match pseudo {
+                    PagePseudoClass::First | PagePseudoClass::Blank => specificity.1 += 1,
+                    _ => specificity.2 += 1,
+                }
+            }
+            Some(specificity)
+        })
+        .max()
+}
+
+/// Cascade `rules` for `page` and compute its page box. Rules must already
+/// be filtered by their media conditions and be in source order.
+pub fn page_box<'rules>(
+    rules: impl IntoIterator<Item = &'rules PageRule>,
+    page: PageContext,
+    default_size: PageDimensions,
+) -> PageBox {
+    let mut declarations: Vec<(PageCascadeKey, &Property<'static>)> = Vec::new();
+    for (order, rule) in rules.into_iter().enumerate() {
+        let Some(specificity) = matching_specificity(rule, page) else {
+            continue;
+        };
+        for (important, props) in [
+            (false, &rule.properties.normal),
+            (true, &rule.properties.important),
+        ] {
+            let precedence = rule.origin.precedence(important);
+            declarations.extend(
+                props
+                    .iter()
+                    .map(|prop| ((precedence, specificity, order), prop)),
+            );
+        }
+    }
+    // Stable sort: equal keys keep declaration order, so later ones win.
+    declarations.sort_by_key(|&(key, _)| key);
+
+    let mut size = default_size;
+    let mut margins: [Option<&LengthPercentageOrAuto>; 4] = [None; 4];
+    for (_, prop) in declarations {
+        match prop {
+            Property::MarginTop(value) => margins[0] = Some(value),
+            Property::MarginRight(value) => margins[1] = Some(value),
+            Property::MarginBottom(value) => margins[2] = Some(value),
+            Property::MarginLeft(value) => margins[3] = Some(value),
+            Property::Custom(custom) if custom.name.as_ref() == "size" => {
+                if let Some(parsed) = prop
+                    .value_to_css_string(PrinterOptions::default())
+                    .ok()
+                    .and_then(|text| parse_page_size(&text, default_size))
+                {
+                    size = parsed;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let resolve = |margin: Option<&LengthPercentageOrAuto>, basis: f32| {
+        Subpixel::from_f32(margin.map_or(0.0, |value| resolve_margin(value, basis)))
+    };
+    PageBox {
+        width: Subpixel::from_f32(size.width),
+        height: Subpixel::from_f32(size.height),
+        margin_top: resolve(margins[0], size.height),
+        margin_right: resolve(margins[1], size.width),
+        margin_bottom: resolve(margins[2], size.height),
+        margin_left: resolve(margins[3], size.width),
+    }
+}
+
+/// Resolve a page margin. Percentages refer to the page width for left and
+/// right margins and the page height for top and bottom; `auto` is zero.
+fn resolve_margin(value: &LengthPercentageOrAuto, basis: f32) -> f32 {
+    match value {
+        LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Dimension(length)) => {
+            length.to_px().unwrap_or(0.0)
+        }
+        LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Percentage(percent)) => {
+            percent.0 * basis
+        }
+        // `calc()` page margins aren't resolved yet.
+        LengthPercentageOrAuto::Auto
+        | LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Calc(_)) => 0.0,
+    }
+}
+
+/// Parse the value of the `size` descriptor.
+fn parse_page_size(text: &str, default_size: PageDimensions) -> Option<PageDimensions> {
+    let tokens: Vec<String> = text
+        .split_ascii_whitespace()
+        .map(str::to_ascii_lowercase)
+        .collect();
+
+    let mut named = None;
+    let mut orientation = None;
+    let mut lengths = Vec::new();
+    for token in &tokens {
+        match token.as_str() {
+            "auto" if tokens.len() == 1 => return Some(default_size),
+            "portrait" | "landscape" if orientation.is_none() => orientation = Some(token.as_str()),
+            _ => {
+                if let Some(dimensions) = named_page_size(token) {
+                    if named.replace(dimensions).is_some() {
+                        return None;
+                    }
+                } else {
+                    let Some(CssValue::Length(length)) =
+                        ValueRegistry::standard().parse_as(ValueKind::Length, token)
+                    else {
+                        return None;
+                    };
+                    let length = length.to_px()?;
+                    (length >= 0.0).then_some(())?;
+                    lengths.push(length);
+                }
+            }
+        }
+    }
+
+    match (lengths.as_slice(), named, orientation) {
+        ([width], None, None) => Some(PageDimensions {
+            width: *width,
+            height: *width,
+        }),
+        ([width, height], None, None) => Some(PageDimensions {
+            width: *width,
+            height: *height,
+        }),
+        ([], base, Some("landscape")) => Some(base.unwrap_or(default_size).landscape()),
+        ([], base, Some(_)) => Some(base.unwrap_or(default_size).portrait()),
+        ([], Some(base), None) => Some(base),
+        _ => None,
+    }
+}
+
+/// `<page-size>` keywords.
+fn named_page_size(name: &str) -> Option<PageDimensions> {
+    Some(match name {
+        "a5" => PageDimensions::from_mm(148.0, 210.0),
+        "a4" => PageDimensions::ISO_A4,
+        "a3" => PageDimensions::from_mm(297.0, 420.0),
+        "b5" => PageDimensions::from_mm(176.0, 250.0),
+        "b4" => PageDimensions::from_mm(250.0, 353.0),
+        "jis-b5" => PageDimensions::from_mm(182.0, 257.0),
+        "jis-b4" => PageDimensions::from_mm(257.0, 364.0),
+        "letter" => PageDimensions::LETTER,
+        "legal" => PageDimensions::from_in(8.5, 14.0),
+        "ledger" => PageDimensions::from_in(11.0, 17.0),
+        _ => return None,
+    })
+}
+
+/// Whether backgrounds and other "ink-heavy" decoration of `node` should be
+/// printed: `print-color-adjust: exact` (or legacy `color-adjust: exact`).
+pub fn prints_backgrounds(database: &Database, node: NodeId) -> bool {
+    let prop_id = PropertyId::PrintColorAdjust(VendorPrefix::None);
+    matches!(
+        database.get_property(node, prop_id),
+        Some(Property::PrintColorAdjust(PrintColorAdjust::Exact, _))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightningcss::rules::CssRule;
+    use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+    use lightningcss::traits::IntoOwned as _;
+
+    fn rules(css: &str) -> Vec<PageRule> {
+        let sheet = StyleSheet::parse(css, ParserOptions::default()).unwrap();
+        sheet
+            .rules
+            .0
+            .into_iter()
+            .filter_map(|rule| match rule {
+                CssRule::Page(page) => Some(PageRule {
+                    selectors: page.selectors.into_owned(),
+                    properties: page.declarations.into(),
+                    origin: Origin::Author,
+                    media: RuleMedia::default(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn page(index: usize) -> PageContext {
+        PageContext {
+            index,
+            blank: false,
+        }
+    }
+
+    #[test]
+    fn size_and_margins() {
+        let rules = rules("@page { size: A4 landscape; margin: 10mm 5% }");
+        let page_box = page_box(&rules, page(0), PageDimensions::LETTER);
+        assert_eq!(page_box.width, Subpixel::from_f32(297.0 * PX_PER_MM));
+        assert_eq!(page_box.height, Subpixel::from_f32(210.0 * PX_PER_MM));
+        assert_eq!(page_box.margin_top, Subpixel::from_f32(10.0 * PX_PER_MM));
+        assert_eq!(
+            page_box.margin_left,
+            Subpixel::from_f32(297.0 * PX_PER_MM * 0.05)
+        );
+    }
+
+    #[test]
+    fn first_page_rule_wins() {
+        let rules = rules("@page :first { margin-top: 2in } @page { margin-top: 1in }");
+        let first = page_box(&rules, page(0), PageDimensions::LETTER);
+        let second = page_box(&rules, page(1), PageDimensions::LETTER);
+        assert_eq!(first.margin_top, Subpixel::from_px(192));
+        assert_eq!(second.margin_top, Subpixel::from_px(96));
+    }
+
+    #[test]
+    fn explicit_lengths() {
+        let rules = rules("@page { size: 400px 300px }");
+        let page_box = page_box(&rules, page(0), PageDimensions::LETTER);
+        assert_eq!(page_box.width, Subpixel::from_px(400));
+        assert_eq!(page_box.height, Subpixel::from_px(300));
+    }
+}
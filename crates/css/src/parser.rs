@@ -4,14 +4,23 @@
 //! Each chunk is processed asynchronously: tokio sends to rayon, awaits completion,
 //! then proceeds to the next chunk.
 
+use crate::import::RuleCollector;
+use crate::selectors::{AncestorHashes, StructuralFilter, matches_selector};
+use crate::shorthand::push_longhands;
+use crate::{MediaEnvironment, PageRule, ResourceResolver, RuleMedia};
 use lasso::ThreadedRodeo;
 use lightningcss::declaration::DeclarationBlock;
+use lightningcss::printer::PrinterOptions;
 use lightningcss::properties::Property;
+use lightningcss::properties::custom::TokenList;
 use lightningcss::rules::CssRule;
-use lightningcss::selector::SelectorList;
+use lightningcss::rules::unknown::UnknownAtRule;
+use lightningcss::selector::{Selector, SelectorList};
 use lightningcss::stylesheet::{ParserOptions, StyleSheet};
-use lightningcss::traits::IntoOwned;
-use rewrite_core::rayon_dispatch;
+use lightningcss::traits::{IntoOwned, ToCss};
+use lightningcss::vendor_prefix::VendorPrefix;
+use rewrite_core::{NodeId, Origin, Specificity, rayon_dispatch};
+use rewrite_html::DomTree;
 use std::sync::Arc;
 
 use lightningcss::properties::PropertyId;
@@ -45,27 +54,46 @@ impl From<DeclarationBlock<'_>> for Properties {
     }
 }
 
-/// Expand shorthand properties into their longhand equivalents.
-/// Properties that aren't shorthands are kept as-is.
+/// Expand shorthand properties into their longhand equivalents (see
+/// `shorthand`). Properties that aren't shorthands are kept as-is.
 fn expand_shorthands(props: Vec<Property<'_>>) -> Vec<Property<'static>> {
     let mut result = Vec::with_capacity(props.len());
     for prop in props {
-        let owned = prop.into_owned();
-        let prop_id = owned.property_id();
-
-        if let Some(longhands) = prop_id.longhands() {
-            for longhand_id in &longhands {
-                if let Some(longhand) = owned.longhand(longhand_id) {
-                    result.push(longhand.into_owned());
-                }
-            }
-        } else {
-            result.push(owned);
-        }
+        push_longhands(legacy_alias(prop.into_owned()), &mut result);
     }
     result
 }
 
+/// Map property names lightningcss doesn't know onto ones it does.
+/// `color-adjust` is the old name of `print-color-adjust`. Of the
+/// `font-variant` shorthand only the caps are kept, as
+/// `font-variant-caps`; the shorthand resets them to `normal` when it
+/// doesn't set them.
+fn legacy_alias(prop: Property<'static>) -> Property<'static> {
+    let Property::Custom(custom) = &prop else {
+        return prop;
+    };
+    let value = || prop.value_to_css_string(PrinterOptions::default()).ok();
+    let parse = |id: PropertyId<'static>, value: &str| {
+        Property::parse_string(id, value, ParserOptions::default())
+            .ok()
+            .map(IntoOwned::into_owned)
+    };
+    let aliased = match custom.name.as_ref() {
+        "color-adjust" => value()
+            .and_then(|value| parse(PropertyId::PrintColorAdjust(VendorPrefix::None), &value)),
+        "font-variant" => value().and_then(|value| {
+            value
+                .split_whitespace()
+                .filter_map(|word| parse(PropertyId::FontVariantCaps, word))
+                .find(|caps| matches!(caps, Property::FontVariantCaps(_)))
+                .or_else(|| parse(PropertyId::FontVariantCaps, "normal"))
+        }),
+        _ => None,
+    };
+    aliased.unwrap_or(prop)
+}
+
 /// A parsed CSS rule with owned data.
 #[derive(Debug, Clone)]
 pub enum ParsedRule {
@@ -73,36 +101,169 @@ pub enum ParsedRule {
     Stylesheet {
         selectors: SelectorList<'static>,
         properties: Properties,
+        origin: Origin,
+        /// Enclosing `@media` conditions.
+        media: RuleMedia,
+        /// Sibling positions the subject must have, checked before the
+        /// selectors are matched.
+        structural: StructuralFilter,
+        /// Tags, ids and classes the subject's ancestors must have, checked
+        /// against the Styler's ancestor filter before the selectors are
+        /// matched.
+        ancestors: AncestorHashes,
     },
     /// An inline style rule targeting a specific node.
     Inline {
         node_id: rewrite_core::NodeId,
         properties: Properties,
     },
+    /// Presentational hints (SVG presentation attributes) for one node.
+    /// Author origin with zero specificity.
+    Presentational {
+        node_id: rewrite_core::NodeId,
+        properties: Properties,
+    },
+    /// An `@page` rule. Never matches a node; see `Styler::page_box`.
+    Page(PageRule),
+    /// An at-rule the engine doesn't understand. Never matches a node; kept
+    /// so tooling can write the stylesheet back out.
+    Opaque(OpaqueAtRule),
+}
+
+/// An unknown at-rule, preserved as text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpaqueAtRule {
+    /// The at-keyword without the `@`, e.g. `property` for `@property`.
+    pub name: String,
+    /// Everything between the name and the block or `;`, serialized.
+    pub prelude: String,
+    /// The contents of the `{}` block, serialized, or `None` for a
+    /// statement at-rule ending in `;`.
+    pub body_text: Option<String>,
+}
+
+impl OpaqueAtRule {
+    /// Serialize `rule`'s prelude and block separately.
+    fn from_unknown(rule: UnknownAtRule<'_>) -> Self {
+        let name = rule.name.to_string();
+        let prelude = UnknownAtRule {
+            block: None,
+            ..rule.clone()
+        }
+        .to_css_string(PrinterOptions::default())
+        .ok()
+        .and_then(|text| {
+            let prelude = text.strip_prefix('@')?.strip_prefix(name.as_str())?;
+            Some(prelude.strip_suffix(';')?.trim().to_owned())
+        })
+        .unwrap_or_default();
+        let body_text = rule.block.map(|block| {
+            UnknownAtRule {
+                name: "".into(),
+                prelude: TokenList(Vec::new()),
+                block: Some(block),
+                loc: rule.loc,
+            }
+            .to_css_string(PrinterOptions {
+                minify: true,
+                ..PrinterOptions::default()
+            })
+            .ok()
+            .and_then(|text| Some(text.strip_prefix("@{")?.strip_suffix('}')?.to_owned()))
+            .unwrap_or_default()
+        });
+        Self {
+            name,
+            prelude,
+            body_text,
+        }
+    }
+
+    /// The rule as CSS text.
+    pub fn css_text(&self) -> String {
+        let mut css = format!("@{}", self.name);
+        if !self.prelude.is_empty() {
+            css.push(' ');
+            css.push_str(&self.prelude);
+        }
+        match &self.body_text {
+            Some(body) => {
+                css.push_str(" {");
+                css.push_str(body);
+                css.push('}');
+            }
+            None => css.push(';'),
+        }
+        css
+    }
 }
 
 impl ParsedRule {
     /// Check if this rule applies to the given node.
-    pub fn matches(&self, node_id: rewrite_core::NodeId, tree: &rewrite_html::DomTree) -> bool {
+    pub fn matches(&self, node_id: NodeId, tree: &DomTree) -> bool {
         match self {
-            Self::Stylesheet { selectors, .. } => {
-                crate::matches_selector_list(tree, node_id, selectors)
+            Self::Stylesheet {
+                selectors,
+                structural,
+                ..
+            } => {
+                structural.admits(tree, node_id)
+                    && crate::matches_selector_list(tree, node_id, selectors)
             }
             Self::Inline {
                 node_id: target, ..
+            }
+            | Self::Presentational {
+                node_id: target, ..
             } => *target == node_id,
+            Self::Page(_) | Self::Opaque(_) => false,
+        }
+    }
+
+    /// Style sheet origin of this rule. Inline styles and unknown
+    /// at-rules are author styles.
+    pub fn origin(&self) -> Origin {
+        match self {
+            Self::Stylesheet { origin, .. } | Self::Page(PageRule { origin, .. }) => *origin,
+            Self::Inline { .. } | Self::Presentational { .. } | Self::Opaque(_) => Origin::Author,
+        }
+    }
+
+    /// Whether the rule's `@media` conditions hold in `env`. Inline styles
+    /// are unconditional.
+    pub fn media_matches(&self, env: MediaEnvironment) -> bool {
+        match self {
+            Self::Stylesheet { media, .. } | Self::Page(PageRule { media, .. }) => {
+                media.matches(env)
+            }
+            Self::Inline { .. } | Self::Presentational { .. } | Self::Opaque(_) => true,
         }
     }
 
-    /// Get the properties for this rule.
+    /// Whether the rule belongs to a single node (inline style or
+    /// presentational hints) rather than being matched by selectors.
+    pub fn is_node_scoped(&self) -> bool {
+        matches!(self, Self::Inline { .. } | Self::Presentational { .. })
+    }
+
+    /// Get the properties for this rule. Unknown at-rules have none.
     pub fn properties(&self) -> &Properties {
+        const NO_PROPERTIES: &Properties = &Properties {
+            normal: Vec::new(),
+            important: Vec::new(),
+        };
         match self {
-            Self::Stylesheet { properties, .. } | Self::Inline { properties, .. } => properties,
+            Self::Stylesheet { properties, .. }
+            | Self::Inline { properties, .. }
+            | Self::Presentational { properties, .. }
+            | Self::Page(PageRule { properties, .. }) => properties,
+            Self::Opaque(_) => NO_PROPERTIES,
         }
     }
 
-    /// Get the base specificity for this rule (without importance flag).
-    pub fn specificity(&self) -> rewrite_core::Specificity {
+    /// Get the base specificity for this rule (without importance flag):
+    /// that of its most specific selector.
+    pub fn specificity(&self) -> Specificity {
         match self {
             Self::Stylesheet { selectors, .. } => {
                 let spec = selectors
@@ -111,14 +272,43 @@ impl ParsedRule {
                     .map(|s| s.specificity())
                     .max()
                     .unwrap_or(0);
-                let ids = (spec >> 20) & 0x3FF;
-                let classes = (spec >> 10) & 0x3FF;
-                let elements = spec & 0x3FF;
-                rewrite_core::Specificity::new(ids, classes, elements)
+                unpack_specificity(spec)
+            }
+            Self::Inline { .. } => Specificity::INLINE,
+            Self::Presentational { .. } | Self::Page(_) | Self::Opaque(_) => {
+                Specificity::new(0, 0, 0)
             }
-            Self::Inline { .. } => rewrite_core::Specificity::INLINE,
         }
     }
+
+    /// The specificity this rule applies to `node_id` with: that of its most
+    /// specific selector matching the node. A list like
+    /// `li, #nav` is as specific as `li` on list items outside `#nav`.
+    pub fn matched_specificity(&self, node_id: NodeId, tree: &DomTree) -> Specificity {
+        let Self::Stylesheet { selectors, .. } = self else {
+            return self.specificity();
+        };
+        let mut specificities = selectors.0.iter().map(Selector::specificity);
+        let first = specificities.next().unwrap_or(0);
+        if specificities.all(|spec| spec == first) {
+            return unpack_specificity(first);
+        }
+        selectors
+            .0
+            .iter()
+            .filter(|selector| matches_selector(tree, node_id, selector))
+            .map(Selector::specificity)
+            .max()
+            .map_or_else(|| self.specificity(), unpack_specificity)
+    }
+}
+
+/// Split a packed selector specificity into its id, class and type counts.
+fn unpack_specificity(spec: u32) -> Specificity {
+    let ids = (spec >> 20) & 0x3FF;
+    let classes = (spec >> 10) & 0x3FF;
+    let elements = spec & 0x3FF;
+    Specificity::new(ids, classes, elements)
 }
 
 /// Streaming CSS parser that uses rayon for parsing.
@@ -128,6 +318,9 @@ impl ParsedRule {
 pub struct CssParser<F> {
     buffer: String,
     callback: Arc<F>,
+    origin: Origin,
+    /// Fetches the sheets named by `@import`.
+    resolver: Option<ResourceResolver>,
     #[allow(dead_code)]
     interner: Arc<ThreadedRodeo>,
 }
@@ -138,10 +331,27 @@ impl<F: Fn(ParsedRule) + Send + Sync + 'static> CssParser<F> {
         Self {
             buffer: String::new(),
             callback: Arc::new(callback),
+            origin: Origin::Author,
+            resolver: None,
             interner,
         }
     }
 
+    /// Tag emitted rules with `origin` instead of the default author origin.
+    #[must_use]
+    pub fn with_origin(mut self, origin: Origin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Follow `@import`s by fetching the imported sheets through `resolver`.
+    /// Without one, imports are dropped.
+    #[must_use]
+    pub fn with_resource_resolver(mut self, resolver: ResourceResolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
     /// Add a chunk of CSS text and parse on rayon. Awaits until parsing completes.
     pub async fn push_chunk(&mut self, chunk: &str) {
         self.buffer.push_str(chunk);
@@ -149,9 +359,12 @@ impl<F: Fn(ParsedRule) + Send + Sync + 'static> CssParser<F> {
         // Take ownership of buffer, get it back after parsing (avoids clone)
         let mut buffer = std::mem::take(&mut self.buffer);
         let callback = self.callback.clone();
+        let origin = self.origin;
+        let resolver = self.resolver.clone();
 
         self.buffer = rayon_dispatch(move || {
-            let consumed = parse_and_emit(&buffer, callback.as_ref(), true);
+            let consumed =
+                parse_and_emit(&buffer, callback.as_ref(), origin, resolver.as_ref(), true);
             buffer.drain(..consumed);
             buffer
         })
@@ -166,16 +379,25 @@ impl<F: Fn(ParsedRule) + Send + Sync + 'static> CssParser<F> {
 
         let buffer = self.buffer;
         let callback = self.callback.clone();
+        let origin = self.origin;
+        let resolver = self.resolver.clone();
 
         rayon_dispatch(move || {
-            parse_and_emit(&buffer, callback.as_ref(), false);
+            parse_and_emit(&buffer, callback.as_ref(), origin, resolver.as_ref(), false);
         })
         .await;
     }
 }
 
-/// Parse CSS text and invoke callback for each rule. Returns bytes consumed.
-fn parse_and_emit<F: Fn(ParsedRule)>(css_text: &str, callback: &F, error_recovery: bool) -> usize {
+/// Parse CSS text and invoke callback for each rule, with `@import`s
+/// resolved through `resolver`. Returns bytes consumed.
+fn parse_and_emit<F: Fn(ParsedRule)>(
+    css_text: &str,
+    callback: &F,
+    origin: Origin,
+    resolver: Option<&ResourceResolver>,
+    error_recovery: bool,
+) -> usize {
     let options = ParserOptions {
         error_recovery,
         ..Default::default()
@@ -187,15 +409,18 @@ fn parse_and_emit<F: Fn(ParsedRule)>(css_text: &str, callback: &F, error_recover
 
     let mut rules = Vec::new();
     let mut last_loc = None;
+    let mut collector = RuleCollector::new(origin, resolver);
 
     for rule in stylesheet.rules.0 {
-        if let CssRule::Style(style_rule) = rule {
-            last_loc = Some(style_rule.loc);
-            rules.push(ParsedRule::Stylesheet {
-                selectors: style_rule.selectors.into_owned(),
-                properties: style_rule.declarations.into(),
-            });
-        }
+        last_loc = match &rule {
+            CssRule::Style(style_rule) => Some(style_rule.loc),
+            CssRule::Media(media_rule) => Some(media_rule.loc),
+            CssRule::Page(page_rule) => Some(page_rule.loc),
+            CssRule::Import(import_rule) => Some(import_rule.loc),
+            CssRule::Unknown(unknown_rule) => Some(unknown_rule.loc),
+            _ => last_loc,
+        };
+        collector.collect(rule, &mut rules);
     }
 
     // Invoke callbacks
@@ -209,6 +434,43 @@ fn parse_and_emit<F: Fn(ParsedRule)>(css_text: &str, callback: &F, error_recover
         .unwrap_or(0)
 }
 
+/// Flatten a rule into `ParsedRule`s, descending into `@media` blocks.
+/// Unknown at-rules are kept as `ParsedRule::Opaque`; `@import`s are
+/// dropped (see `RuleCollector` for resolving them).
+pub fn collect_style_rules(
+    rule: CssRule<'_>,
+    media: &RuleMedia,
+    origin: Origin,
+    out: &mut Vec<ParsedRule>,
+) {
+    match rule {
+        CssRule::Style(style_rule) => out.push(ParsedRule::Stylesheet {
+            structural: StructuralFilter::for_selectors(&style_rule.selectors),
+            ancestors: AncestorHashes::for_selectors(&style_rule.selectors),
+            selectors: style_rule.selectors.into_owned(),
+            properties: style_rule.declarations.into(),
+            origin,
+            media: media.clone(),
+        }),
+        CssRule::Media(media_rule) => {
+            let media = media.nested(media_rule.query.into_owned());
+            for nested in media_rule.rules.0 {
+                collect_style_rules(nested, &media, origin, out);
+            }
+        }
+        CssRule::Page(page_rule) => out.push(ParsedRule::Page(PageRule {
+            selectors: page_rule.selectors.into_owned(),
+            properties: page_rule.declarations.into(),
+            origin,
+            media: media.clone(),
+        })),
+        CssRule::Unknown(unknown_rule) => {
+            out.push(ParsedRule::Opaque(OpaqueAtRule::from_unknown(unknown_rule)));
+        }
+        _ => {}
+    }
+}
+
 fn line_col_to_byte(s: &str, line: u32, col: u32) -> usize {
     let mut current_line = 0u32;
     let mut current_col = 1u32;
@@ -228,3 +490,102 @@ fn line_col_to_byte(s: &str, line: u32, col: u32) -> usize {
 
     s.len()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expanded(css: &str) -> Vec<String> {
+        let block = DeclarationBlock::parse_string(css, ParserOptions::default()).unwrap();
+        Properties::from(block)
+            .normal
+            .iter()
+            .map(|prop| {
+                let value = prop.value_to_css_string(PrinterOptions::default()).unwrap();
+                format!("{}: {value}", prop.property_id().name())
+            })
+            .collect()
+    }
+
+    fn opaque_rules(css: &str) -> Vec<OpaqueAtRule> {
+        let stylesheet = StyleSheet::parse(css, ParserOptions::default()).unwrap();
+        let mut rules = Vec::new();
+        for rule in stylesheet.rules.0 {
+            collect_style_rules(rule, &RuleMedia::default(), Origin::Author, &mut rules);
+        }
+        rules
+            .into_iter()
+            .filter_map(|rule| match rule {
+                ParsedRule::Opaque(opaque_rule) => Some(opaque_rule),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unknown_at_rules_keep_their_payload() {
+        let rules = opaque_rules(
+            "@tailwind base;\n\
+             div { color: red }\n\
+             @media screen { @layout-hint grid(3) { columns: 3; gap: 1em } }",
+        );
+        assert_eq!(
+            rules,
+            [
+                OpaqueAtRule {
+                    name: "tailwind".to_owned(),
+                    prelude: "base".to_owned(),
+                    body_text: None,
+                },
+                OpaqueAtRule {
+                    name: "layout-hint".to_owned(),
+                    prelude: "grid(3)".to_owned(),
+                    body_text: Some("columns: 3; gap: 1em".to_owned()),
+                },
+            ]
+        );
+        assert_eq!(rules[0].css_text(), "@tailwind base;");
+        assert_eq!(
+            rules[1].css_text(),
+            "@layout-hint grid(3) {columns: 3; gap: 1em}"
+        );
+    }
+
+    #[test]
+    fn border_expands_to_per_side_longhands() {
+        let props = expanded("border: 1px solid red");
+        assert_eq!(props.len(), 12);
+        assert!(props.contains(&"border-top-width: 1px".to_owned()));
+        assert!(props.contains(&"border-left-style: solid".to_owned()));
+        assert!(props.contains(&"border-bottom-color: red".to_owned()));
+    }
+
+    #[test]
+    fn single_side_border_leaves_other_sides_alone() {
+        let props = expanded("border-left: 2px dashed");
+        assert_eq!(
+            props,
+            [
+                "border-left-width: 2px",
+                "border-left-style: dashed",
+                "border-left-color: currentColor",
+            ]
+        );
+    }
+
+    #[test]
+    fn font_variant_sets_caps() {
+        assert_eq!(
+            expanded("font-variant: small-caps"),
+            ["font-variant-caps: small-caps"]
+        );
+        assert_eq!(
+            expanded("font-variant: oldstyle-nums all-small-caps"),
+            ["font-variant-caps: all-small-caps"]
+        );
+        assert_eq!(
+            expanded("font-variant: none"),
+            ["font-variant-caps: normal"]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Presentational hints from SVG presentation attributes.
+//!
+//! Attributes such as `fill="red"` on elements in the SVG namespace enter
+//! the cascade as author-origin declarations with zero specificity that
+//! precede every stylesheet rule, so any author rule overrides them.
+
+use crate::values::{ValueKind, ValueRegistry};
+use crate::{ParsedRule, Properties};
+use lightningcss::declaration::DeclarationBlock;
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::stylesheet::ParserOptions;
+use lightningcss::traits::IntoOwned;
+use rewrite_core::NodeId;
+use rewrite_html::{DomTree, Namespace, NodeData};
+
+/// Presentation attributes valid on any SVG element.
+const PRESENTATION_ATTRIBUTES: &[&str] = &[
+    "clip-rule",
+    "color",
+    "color-interpolation",
+    "display",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "marker-end",
+    "marker-mid",
+    "marker-start",
+    "opacity",
+    "overflow",
+    "shape-rendering",
+    "stroke",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-opacity",
+    "stroke-width",
+    "visibility",
+];
+
+/// Geometry properties, which are presentation attributes only on the
+/// elements that use them.
+const SIZING_ATTRIBUTES: &[&str] = &["width", "height"];
+
+/// Elements whose `width`/`height` attributes map to the CSS properties.
+const SIZED_ELEMENTS: &[&str] = &["svg", "rect", "image", "foreignObject"];
+
+/// Attributes whose values may be unitless lengths (user units, i.e. px).
+const LENGTH_ATTRIBUTES: &[&str] = &[
+    "font-size",
+    "height",
+    "stroke-dashoffset",
+    "stroke-width",
+    "width",
+];
+
+/// Whether `name` is a presentation attribute on an SVG `tag`.
+pub fn is_presentation_attribute(tag: &str, name: &str) -> bool {
+    PRESENTATION_ATTRIBUTES.contains(&name)
+        || (SIZING_ATTRIBUTES.contains(&name) && SIZED_ELEMENTS.contains(&tag))
+}
+
+/// Build the presentational-hint rule for `node_id`, or `None` if it isn't
+/// an SVG element or has no valid presentation attributes.
+pub fn presentational_hints(tree: &DomTree, node_id: NodeId) -> Option<ParsedRule> {
+    let NodeData::Element {
+        tag,
+        namespace: Namespace::Svg,
+        attributes,
+    } = tree.get_node(node_id)?
+    else {
+        return None;
+    };
+    let tag = tree.interner.resolve(tag);
+
+    let mut declarations = Vec::new();
+    attributes.for_each(|name, value| {
+        let name = tree.interner.resolve(&name);
+        if is_presentation_attribute(tag, name)
+            && let Some(property) = parse_attribute(name, value)
+        {
+            declarations.push(property);
+        }
+    });
+    if declarations.is_empty() {
+        return None;
+    }
+
+    // Through a declaration block so shorthands (`overflow`) get expanded.
+    Some(ParsedRule::Presentational {
+        node_id,
+        properties: Properties::from(DeclarationBlock {
+            declarations,
+            important_declarations: Vec::new(),
+        }),
+    })
+}
+
+/// Parse an attribute value with the grammar of the property of the same
+/// name. Bare numbers on length attributes are treated as px.
+fn parse_attribute(name: &str, value: &str) -> Option<Property<'static>> {
+    let value = value.trim();
+    let parse = |text: &str| {
+        Property::parse_string(PropertyId::from(name), text, ParserOptions::default())
+            .ok()
+            .filter(|property| !matches!(property, Property::Unparsed(_) | Property::Custom(_)))
+            .map(IntoOwned::into_owned)
+    };
+    if LENGTH_ATTRIBUTES.contains(&name)
+        && ValueRegistry::standard()
+            .parse_as(ValueKind::Number, value)
+            .is_some()
+    {
+        return parse(&format!("{value}px"));
+    }
+    parse(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_paint_and_lengths() {
+        assert!(matches!(
+            parse_attribute("fill", "red"),
+            Some(Property::Fill(_))
+        ));
+        assert!(matches!(
+            parse_attribute("stroke-width", " 2 "),
+            Some(Property::StrokeWidth(_))
+        ));
+        assert!(matches!(
+            parse_attribute("width", "100"),
+            Some(Property::Width(_))
+        ));
+        assert!(parse_attribute("fill", "not a paint!").is_none());
+    }
+
+    #[test]
+    fn sizing_only_on_sized_elements() {
+        assert!(is_presentation_attribute("svg", "width"));
+        assert!(!is_presentation_attribute("circle", "width"));
+        assert!(is_presentation_attribute("circle", "fill"));
+    }
+}
@@ -0,0 +1,291 @@
+//! Resource URLs referenced by a stylesheet.
+//!
+//! Lists every URL a stylesheet may fetch (`@import`, `@font-face` `src`,
+//! images and cursors) together with the rule it appears in and the
+//! cascade origin of the sheet, so an embedder can apply a content policy
+//! before anything is requested. Like `analysis`, this parses the text on
+//! its own and doesn't touch the DOM or the `Styler`.
+
+use lightningcss::declaration::DeclarationBlock;
+use lightningcss::printer::PrinterOptions;
+use lightningcss::properties::custom::{TokenList, TokenOrValue};
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::rules::font_face::{FontFaceProperty, Source};
+use lightningcss::rules::{CssRule, CssRuleList, Location};
+use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+use lightningcss::traits::ToCss as _;
+use lightningcss::values::image::Image;
+use rewrite_core::Origin;
+
+/// What a referenced URL would be fetched as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    /// Another stylesheet, from `@import`.
+    Stylesheet,
+    /// A font file, from an `@font-face` `src` descriptor.
+    Font,
+    /// An image, from `background`, `background-image` or `list-style`.
+    Image,
+    /// A cursor image, from `cursor`.
+    Cursor,
+}
+
+/// A URL referenced by a stylesheet, with where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceUrl {
+    /// The URL as written, not resolved against the sheet's base URL.
+    pub url: String,
+    pub kind: ResourceKind,
+    /// Origin of the sheet, as passed to `resource_urls`.
+    pub origin: Origin,
+    /// The rule the URL appears in: `@import`, `@font-face` or the
+    /// serialized selector list of a style rule.
+    pub rule: String,
+    /// The declaring property, e.g. `background-image`; `None` for
+    /// `@import`.
+    pub property: Option<String>,
+    /// Line of the rule in the sheet, starting at 1.
+    pub line: u32,
+    /// Column of the rule, starting at 1.
+    pub column: u32,
+}
+
+/// Parse `css_text` and list the URLs it references, in source order.
+///
+/// Rules nested in `@media`, `@supports` and style rules are included
+/// whether or not their condition holds, since a policy has to cover every
+/// URL the sheet could fetch. URLs inside declarations that use `var()`
+/// are listed too. Returns `None` only if the stylesheet cannot be parsed
+/// at all.
+pub fn resource_urls(css_text: &str, origin: Origin) -> Option<Vec<ResourceUrl>> {
+    let options = ParserOptions {
+        error_recovery: true,
+        ..Default::default()
+    };
+    let stylesheet = StyleSheet::parse(css_text, options).ok()?;
+    let mut urls = Vec::new();
+    collect_rules(&stylesheet.rules, origin, &mut urls);
+    Some(urls)
+}
+
+/// The rule a URL is being collected from.
+struct RuleContext<'rule> {
+    origin: Origin,
+    rule: &'rule str,
+    loc: Location,
+}
+
+impl RuleContext<'_> {
+    fn url(&self, url: &str, kind: ResourceKind, property: Option<&str>) -> ResourceUrl {
+        ResourceUrl {
+            url: url.to_owned(),
+            kind,
+            origin: self.origin,
+            rule: self.rule.to_owned(),
+            property: property.map(str::to_owned),
+            line: self.loc.line + 1,
+            column: self.loc.column,
+        }
+    }
+}
+
+/// Walk a rule list, descending into conditional group and nested rules.
+fn collect_rules(rules: &CssRuleList<'_>, origin: Origin, urls: &mut Vec<ResourceUrl>) {
+    for rule in &rules.0 {
+        match rule {
+            CssRule::Import(import) => {
+                let context = RuleContext {
+                    origin,
+                    rule: "@import",
+                    loc: import.loc,
+                };
+                urls.push(context.url(&import.url, ResourceKind::Stylesheet, None));
+            }
+            CssRule::FontFace(font_face) => {
+                let context = RuleContext {
+                    origin,
+                    rule: "@font-face",
+                    loc: font_face.loc,
+                };
+                let sources = font_face
+                    .properties
+                    .iter()
+                    .filter_map(|property| match property {
+                        FontFaceProperty::Source(sources) => Some(sources),
+                        _ => None,
+                    })
+                    .flatten();
+                for source in sources {
+                    if let Source::Url(source) = source {
+                        urls.push(context.url(&source.url.url, ResourceKind::Font, Some("src")));
+                    }
+                }
+            }
+            CssRule::Style(style_rule) => {
+                let selectors = style_rule
+                    .selectors
+                    .to_css_string(PrinterOptions::default())
+                    .unwrap_or_default();
+                let context = RuleContext {
+                    origin,
+                    rule: &selectors,
+                    loc: style_rule.loc,
+                };
+                collect_declarations(&style_rule.declarations, &context, urls);
+                collect_rules(&style_rule.rules, origin, urls);
+            }
+            CssRule::Media(media) => collect_rules(&media.rules, origin, urls),
+            CssRule::Supports(supports) => collect_rules(&supports.rules, origin, urls),
+            _ => {}
+        }
+    }
+}
+
+/// Collect the URLs of the fetching properties in a declaration block.
+fn collect_declarations(
+    decls: &DeclarationBlock<'_>,
+    context: &RuleContext<'_>,
+    urls: &mut Vec<ResourceUrl>,
+) {
+    for prop in decls
+        .declarations
+        .iter()
+        .chain(decls.important_declarations.iter())
+    {
+        let id = prop.property_id();
+        let Some(kind) = resource_kind(&id) else {
+            continue;
+        };
+        let mut found = Vec::new();
+        match prop {
+            Property::BackgroundImage(images) => {
+                for image in images {
+                    image_urls(image, &mut found);
+                }
+            }
+            Property::Background(backgrounds) => {
+                for background in backgrounds {
+                    image_urls(&background.image, &mut found);
+                }
+            }
+            Property::ListStyleImage(image) => image_urls(image, &mut found),
+            Property::ListStyle(list_style) => image_urls(&list_style.image, &mut found),
+            Property::Cursor(cursor) => {
+                found.extend(cursor.images.iter().map(|image| image.url.url.to_string()));
+            }
+            Property::Unparsed(unparsed) => token_urls(&unparsed.value, &mut found),
+            _ => {}
+        }
+        urls.extend(
+            found
+                .iter()
+                .map(|url| context.url(url, kind, Some(id.name()))),
+        );
+    }
+}
+
+/// What the URLs of a property are fetched as, if it fetches any.
+fn resource_kind(id: &PropertyId<'_>) -> Option<ResourceKind> {
+    match id {
+        PropertyId::BackgroundImage
+        | PropertyId::Background
+        | PropertyId::ListStyleImage
+        | PropertyId::ListStyle => Some(ResourceKind::Image),
+        PropertyId::Cursor => Some(ResourceKind::Cursor),
+        _ => None,
+    }
+}
+
+/// URLs of an image, including each `image-set()` option.
+fn image_urls(image: &Image<'_>, found: &mut Vec<String>) {
+    match image {
+        Image::Url(url) => found.push(url.url.to_string()),
+        Image::ImageSet(set) => {
+            for option in &set.options {
+                image_urls(&option.image, found);
+            }
+        }
+        Image::None | Image::Gradient(_) => {}
+    }
+}
+
+/// URLs in an unparsed value, including `var()` fallbacks and function
+/// arguments.
+fn token_urls(tokens: &TokenList<'_>, found: &mut Vec<String>) {
+    for token in &tokens.0 {
+        match token {
+            TokenOrValue::Url(url) => found.push(url.url.to_string()),
+            TokenOrValue::Var(variable) => {
+                if let Some(fallback) = &variable.fallback {
+                    token_urls(fallback, found);
+                }
+            }
+            TokenOrValue::Function(function) => token_urls(&function.arguments, found),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(css_text: &str) -> Vec<(String, ResourceKind)> {
+        resource_urls(css_text, Origin::Author)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|resource| (resource.url, resource.kind))
+            .collect()
+    }
+
+    #[test]
+    fn lists_imports_fonts_images_and_cursors() {
+        let found = urls(
+            "@import url(theme.css);
+             @font-face { font-family: Brand; src: url(brand.woff2) format(woff2), local(Arial) }
+             .hero { background: url(hero.png) no-repeat; cursor: url(hand.cur) 4 4, pointer }
+             ul { list-style-image: url(dot.svg) }",
+        );
+        assert_eq!(
+            found,
+            vec![
+                ("theme.css".to_owned(), ResourceKind::Stylesheet),
+                ("brand.woff2".to_owned(), ResourceKind::Font),
+                ("hero.png".to_owned(), ResourceKind::Image),
+                ("hand.cur".to_owned(), ResourceKind::Cursor),
+                ("dot.svg".to_owned(), ResourceKind::Image),
+            ]
+        );
+    }
+
+    #[test]
+    fn labels_each_url_with_its_rule_and_origin() {
+        let found = resource_urls(
+            "a { color: red }\n.card, .tile { background-image: url(card.png) }",
+            Origin::User,
+        )
+        .unwrap_or_default();
+        assert_eq!(
+            found,
+            vec![ResourceUrl {
+                url: "card.png".to_owned(),
+                kind: ResourceKind::Image,
+                origin: Origin::User,
+                rule: ".card, .tile".to_owned(),
+                property: Some("background-image".to_owned()),
+                line: 2,
+                column: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn includes_conditional_rules_image_sets_and_var_fallbacks() {
+        let found = urls(
+            "@media print { p { background-image: image-set(url(a.png) 1x, url(b.png) 2x) } }
+             @supports (display: grid) { div { background-image: var(--bg, url(c.png)) } }",
+        );
+        let found: Vec<_> = found.into_iter().map(|(url, _)| url).collect();
+        assert_eq!(found, ["a.png", "b.png", "c.png"]);
+    }
+}
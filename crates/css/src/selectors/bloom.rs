@@ -0,0 +1,269 @@
+//! Ancestor Bloom filter prefilter.
+//!
+//! A descendant or child combinator makes the matcher walk up from the
+//! subject looking for an ancestor that matches the compound to its left.
+//! Most rules fail that walk. The Styler keeps, for every element, a Bloom
+//! filter of the tags, ids and classes of its ancestors, and each rule
+//! records the tags, ids and classes its ancestor compounds require when
+//! it is parsed. A required identifier missing from the filter rejects
+//! the rule in a few bit tests, before any ancestor is visited.
+//!
+//! The filter has no false negatives as long as it is up to date, so it
+//! only ever skips rules that cannot match.
+
+use lightningcss::selector::{Component, Selector, SelectorList};
+use parcel_selectors::parser::Combinator;
+use rewrite_core::NodeId;
+use rewrite_html::{DomTree, NodeData};
+use std::iter;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of 64-bit words in a filter.
+const WORDS: usize = 4;
+
+/// Most identifiers recorded per selector. More only sharpen an already
+/// selective check.
+const MAX_HASHES: usize = 4;
+
+/// What kind of identifier a hash is of, so `#a`, `.a` and `a` differ.
+#[derive(Clone, Copy)]
+enum Identifier {
+    TagName = 1,
+    IdValue = 2,
+    ClassName = 3,
+}
+
+/// 32-bit FNV-1a hash of an identifier.
+fn identifier_hash(kind: Identifier, name: &str) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in iter::once(kind as u8).chain(name.bytes()) {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+/// Tags match case-insensitively, so they are hashed lowercased.
+fn tag_hash(tag: &str) -> u32 {
+    identifier_hash(Identifier::TagName, &tag.to_ascii_lowercase())
+}
+
+/// Bloom filter of the identifiers on an element's ancestors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AncestorFilter {
+    bits: [u64; WORDS],
+}
+
+impl AncestorFilter {
+    /// The filter for the children of `parent`: `parent_filter` with the
+    /// identifiers of `parent` added. Non-element parents add nothing.
+    pub fn for_children_of(tree: &DomTree, parent: NodeId, parent_filter: Self) -> Self {
+        let mut filter = parent_filter;
+        let Some(NodeData::Element {
+            tag, attributes, ..
+        }) = tree.get_node(parent)
+        else {
+            return filter;
+        };
+        filter.insert(tag_hash(tree.interner.resolve(tag)));
+        let value = |name| attributes.get(&tree.interner.get(name)?);
+        if let Some(id) = value("id") {
+            filter.insert(identifier_hash(Identifier::IdValue, &id));
+        }
+        if let Some(classes) = value("class") {
+            for class in classes.split_whitespace() {
+                filter.insert(identifier_hash(Identifier::ClassName, class));
+            }
+        }
+        filter
+    }
+
+    /// Each hash sets two bits, taken from its low and high halves.
+    fn bit_indices(hash: u32) -> [usize; 2] {
+        let bits = (WORDS * 64) as u32;
+        [(hash & 0xffff) % bits, (hash >> 16) % bits].map(|bit| bit as usize)
+    }
+
+    fn insert(&mut self, hash: u32) {
+        for bit in Self::bit_indices(hash) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    /// `false` if no ancestor has the identifier hashed to `hash`.
+    fn might_contain(&self, hash: u32) -> bool {
+        Self::bit_indices(hash)
+            .iter()
+            .all(|&bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// The identifiers a rule's selectors require of the subject's ancestors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AncestorHashes {
+    /// Per selector, hashes that must all be in the filter. Empty when
+    /// some selector requires nothing, since the rule can't be rejected.
+    selectors: Vec<Box<[u32]>>,
+}
+
+impl AncestorHashes {
+    /// The requirements of every selector in `selectors`.
+    pub fn for_selectors(selectors: &SelectorList<'_>) -> Self {
+        let per_selector: Vec<Box<[u32]>> = selectors.0.iter().map(for_selector).collect();
+        if per_selector.iter().any(|hashes| hashes.is_empty()) {
+            return Self::default();
+        }
+        Self {
+            selectors: per_selector,
+        }
+    }
+
+    /// Whether the filter never rejects anything.
+    pub fn is_trivial(&self) -> bool {
+        self.selectors.is_empty()
+    }
+
+    /// Cheap necessary condition for a match: `false` means no selector
+    /// can match an element whose ancestors are summarized by `filter`.
+    pub fn admits(&self, filter: &AncestorFilter) -> bool {
+        self.is_trivial()
+            || self
+                .selectors
+                .iter()
+                .any(|hashes| hashes.iter().all(|&hash| filter.might_contain(hash)))
+    }
+}
+
+/// Tags, ids and classes `selector` requires of the subject's ancestors.
+///
+/// Those are the compounds left of a child or descendant combinator.
+/// Compounds left of a sibling combinator match siblings, and the
+/// subject's own compound (including the element of a pseudo-element
+/// selector) matches the subject.
+fn for_selector(selector: &Selector<'_>) -> Box<[u32]> {
+    let mut hashes = Vec::new();
+    let mut ancestor = false;
+    for component in selector.iter_raw_match_order() {
+        let hash = match component {
+            Component::Combinator(combinator) => {
+                ancestor = matches!(combinator, Combinator::Child | Combinator::Descendant);
+                continue;
+            }
+            _ if !ancestor => continue,
+            Component::LocalName(local) => tag_hash(local.lower_name.0.as_ref()),
+            Component::ID(id) => identifier_hash(Identifier::IdValue, id.0.as_ref()),
+            Component::Class(class) => identifier_hash(Identifier::ClassName, class.0.as_ref()),
+            _ => continue,
+        };
+        hashes.push(hash);
+        if hashes.len() == MAX_HASHES {
+            break;
+        }
+    }
+    hashes.into_boxed_slice()
+}
+
+/// How often the ancestor filter decided a rule's match, since the
+/// counters were last reset. Only rules with ancestor requirements are
+/// counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AncestorFilterStats {
+    /// Rules rejected by the filter without running the matcher.
+    pub rejected: u64,
+    /// Rules the filter let through to the matcher.
+    pub admitted: u64,
+    /// Admitted rules the matcher then rejected.
+    pub false_positives: u64,
+}
+
+/// Shared counters behind `AncestorFilterStats`.
+#[derive(Debug, Default)]
+pub struct AncestorFilterCounters {
+    rejected: AtomicU64,
+    admitted: AtomicU64,
+    false_positives: AtomicU64,
+}
+
+impl AncestorFilterCounters {
+    pub fn record(&self, admitted: bool, matched: bool) {
+        let counter = match (admitted, matched) {
+            (false, _) => &self.rejected,
+            (true, true) => &self.admitted,
+            (true, false) => {
+                self.false_positives.fetch_add(1, Ordering::Relaxed);
+                &self.admitted
+            }
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> AncestorFilterStats {
+        AncestorFilterStats {
+            rejected: self.rejected.load(Ordering::Relaxed),
+            admitted: self.admitted.load(Ordering::Relaxed),
+            false_positives: self.false_positives.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset(&self) {
+        for counter in [&self.rejected, &self.admitted, &self.false_positives] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightningcss::rules::CssRule;
+    use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+
+    fn hashes(selector: &str) -> AncestorHashes {
+        let css = format!("{selector} {{ color: red }}");
+        let sheet = StyleSheet::parse(&css, ParserOptions::default()).unwrap();
+        let selectors = sheet
+            .rules
+            .0
+            .iter()
+            .find_map(|rule| match rule {
+                CssRule::Style(style_rule) => Some(&style_rule.selectors),
+                _ => None,
+            })
+            .unwrap();
+        AncestorHashes::for_selectors(selectors)
+    }
+
+    fn filter_of(identifiers: &[(Identifier, &str)]) -> AncestorFilter {
+        let mut filter = AncestorFilter::default();
+        for &(kind, name) in identifiers {
+            filter.insert(match kind {
+                Identifier::TagName => tag_hash(name),
+                _ => identifier_hash(kind, name),
+            });
+        }
+        filter
+    }
+
+    #[test]
+    fn requirements_from_ancestor_compounds() {
+        assert!(hashes("span").is_trivial());
+        assert!(hashes("div + span").is_trivial());
+        assert!(hashes("span::before").is_trivial());
+        assert!(hashes("div span, em").is_trivial());
+        assert!(!hashes("div span").is_trivial());
+        assert!(!hashes("div > p + span").is_trivial());
+    }
+
+    #[test]
+    fn admits_only_with_every_identifier() {
+        let nav = filter_of(&[
+            (Identifier::TagName, "NAV"),
+            (Identifier::ClassName, "menu"),
+        ]);
+        assert!(hashes("nav.menu a").admits(&nav));
+        assert!(hashes("nav a::before").admits(&nav));
+        assert!(!hashes("nav.other a").admits(&nav));
+        assert!(!hashes("#menu a").admits(&nav), "ids and classes differ");
+        assert!(hashes("#menu a, .menu b").admits(&nav));
+    }
+}
@@ -0,0 +1,151 @@
+//! Attribute, element state and sibling dependencies of selectors.
+//!
+//! Collected when rules are added so the `Styler` knows which attribute
+//! and dynamic state (`:hover`, `:focus`, ...) changes can affect selector
+//! matching, whether appending a child can change how its earlier siblings
+//! match, and how much of the tree has to be rematched when one of them
+//! changes.
+
+use lightningcss::selector::{Component, PseudoClass, Selector, SelectorList};
+use parcel_selectors::parser::{Combinator, NthSelectorData, NthType};
+use rewrite_html::ElementState;
+
+/// Which nodes may change their matched rules when an attribute or state
+/// changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AttributeReach {
+    /// Only the element whose attribute changed (rightmost compound).
+    Subject,
+    /// The element, its descendants and its later siblings' subtrees
+    /// (attribute used left of a descendant/child/sibling combinator).
+    Relatives,
+    /// Anything in the document (attribute used inside `:has()`).
+    Document,
+}
+
+/// Something other than the tree's shape a selector's match depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dependency<'sel> {
+    /// An attribute, by name.
+    Attribute(&'sel str),
+    /// A dynamic state flag, as `effective_state` reports it.
+    State(ElementState),
+    /// The element siblings after the subject (`:last-child`,
+    /// `:nth-last-of-type()`, ...), so appending a child can change how its
+    /// earlier siblings match.
+    LaterSiblings,
+}
+
+/// Call `record` for every attribute name, state flag and sibling
+/// dependency of `selectors`.
+pub fn selector_dependencies(
+    selectors: &SelectorList<'_>,
+    record: &mut impl FnMut(Dependency<'_>, AttributeReach),
+) {
+    for selector in &selectors.0 {
+        collect_dependencies(selector, AttributeReach::Subject, record);
+    }
+}
+
+fn collect_dependencies(
+    selector: &Selector<'_>,
+    reach: AttributeReach,
+    record: &mut impl FnMut(Dependency<'_>, AttributeReach),
+) {
+    let mut reach = reach;
+    for component in selector.iter_raw_match_order() {
+        match component {
+            Component::Combinator(Combinator::PseudoElement) => {}
+            Component::Combinator(_) => reach = reach.max(AttributeReach::Relatives),
+            Component::ID(_) => record(Dependency::Attribute("id"), reach),
+            Component::Class(_) => record(Dependency::Attribute("class"), reach),
+            Component::AttributeInNoNamespaceExists {
+                local_name_lower, ..
+            } => record(Dependency::Attribute(local_name_lower.0.as_ref()), reach),
+            Component::AttributeInNoNamespace { local_name, .. } => {
+                record(Dependency::Attribute(local_name.0.as_ref()), reach);
+            }
+            Component::AttributeOther(attr) => {
+                record(
+                    Dependency::Attribute(attr.local_name_lower.0.as_ref()),
+                    reach,
+                );
+            }
+            Component::NonTSPseudoClass(pseudo) => {
+                for &name in pseudo_class_attributes(pseudo) {
+                    record(Dependency::Attribute(name), reach);
+                }
+                if let Some(state) = pseudo_class_state(pseudo) {
+                    record(Dependency::State(state), reach);
+                }
+            }
+            Component::Negation(nested)
+            | Component::Is(nested)
+            | Component::Where(nested)
+            | Component::Any(_, nested) => {
+                for inner in nested {
+                    collect_dependencies(inner, reach, record);
+                }
+            }
+            Component::Has(nested) => {
+                for inner in nested {
+                    collect_dependencies(inner, AttributeReach::Document, record);
+                }
+            }
+            Component::Nth(data) => {
+                if counts_from_end(data) {
+                    record(Dependency::LaterSiblings, reach);
+                }
+            }
+            Component::NthOf(data) => {
+                if counts_from_end(data.nth_data()) {
+                    record(Dependency::LaterSiblings, reach);
+                }
+                // A change to any sibling can move the subject's index.
+                for inner in data.selectors() {
+                    collect_dependencies(inner, AttributeReach::Document, record);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether an `:nth-*` pseudo-class looks at the siblings after the
+/// subject.
+fn counts_from_end(data: &NthSelectorData) -> bool {
+    matches!(
+        data.ty,
+        NthType::LastChild | NthType::LastOfType | NthType::OnlyChild | NthType::OnlyOfType
+    )
+}
+
+/// Attributes read by the matcher for a non-tree-structural pseudo-class.
+fn pseudo_class_attributes(pseudo: &PseudoClass<'_>) -> &'static [&'static str] {
+    match pseudo {
+        PseudoClass::Link | PseudoClass::AnyLink(_) => &["href"],
+        PseudoClass::Enabled | PseudoClass::Disabled => &["disabled"],
+        PseudoClass::Checked => &["checked"],
+        PseudoClass::ReadOnly(_) | PseudoClass::ReadWrite(_) => &["readonly"],
+        PseudoClass::Required | PseudoClass::Optional => &["required"],
+        PseudoClass::Default => &["checked", "selected"],
+        PseudoClass::PlaceholderShown(_) => &["placeholder"],
+        PseudoClass::Open | PseudoClass::Closed => &["open"],
+        PseudoClass::PopoverOpen => &["popover"],
+        PseudoClass::Lang { .. } => &["lang"],
+        PseudoClass::Dir { .. } => &["dir"],
+        _ => &[],
+    }
+}
+
+/// The state flag a dynamic pseudo-class matches.
+fn pseudo_class_state(pseudo: &PseudoClass<'_>) -> Option<ElementState> {
+    Some(match pseudo {
+        PseudoClass::Hover => ElementState::HOVER,
+        PseudoClass::Active => ElementState::ACTIVE,
+        PseudoClass::Focus => ElementState::FOCUS,
+        PseudoClass::FocusVisible => ElementState::FOCUS_VISIBLE,
+        PseudoClass::FocusWithin => ElementState::FOCUS_WITHIN,
+        _ => return None,
+    })
+}
@@ -4,13 +4,14 @@
 //! avoiding the need to re-parse selectors from strings.
 
 use lasso::Spur;
-use lightningcss::selector::{Component, PseudoClass, Selector, SelectorList};
+use lightningcss::selector::{Component, PseudoClass, PseudoElement, Selector, SelectorList};
 use parcel_selectors::attr::{
     AttrSelectorOperator, ParsedAttrSelectorOperation, ParsedCaseSensitivity,
 };
 use parcel_selectors::parser::{Combinator, NthSelectorData, NthType};
 use rewrite_core::NodeId;
-use rewrite_html::{DomTree, NodeData};
+use rewrite_html::{DomTree, ElementState, NodeData};
+use std::iter;
 
 /// Check if any selector in the list matches the element.
 pub fn matches_selector_list(
@@ -24,30 +25,69 @@ pub fn matches_selector_list(
         .any(|sel| matches_selector(tree, node_id, sel))
 }
 
+/// Check if any selector in the list selects `pseudo` of the element: the
+/// selector ends in `pseudo` and the rest of it matches the element.
+pub fn matches_pseudo_element<'rule>(
+    tree: &DomTree,
+    node_id: NodeId,
+    selectors: &SelectorList<'rule>,
+    pseudo: &PseudoElement<'rule>,
+) -> bool {
+    selectors.0.iter().any(|selector| {
+        let mut iter = selector.iter_raw_match_order();
+        matches!(iter.next(), Some(Component::PseudoElement(target)) if target == pseudo)
+            && iter.next() == Some(&Component::Combinator(Combinator::PseudoElement))
+            && matches_components(tree, node_id, iter)
+    })
+}
+
 /// Check if a single selector matches an element.
-fn matches_selector(tree: &DomTree, node_id: NodeId, selector: &Selector<'_>) -> bool {
-    // Selectors are stored in matching order (right-to-left).
-    // We need to match from the rightmost compound selector first.
-    let mut current_node = node_id;
-    let mut iter = selector.iter_raw_match_order().peekable();
+pub fn matches_selector(tree: &DomTree, node_id: NodeId, selector: &Selector<'_>) -> bool {
+    matches_components(tree, node_id, selector.iter_raw_match_order())
+}
 
-    // Match the first (rightmost) compound selector
+/// Match selector components, in matching order, against an element.
+///
+/// Selectors are stored right-to-left, so the rightmost compound is matched
+/// first. Descendant and later-sibling combinators try every candidate
+/// element before giving up.
+fn matches_components<'sel, 'rule: 'sel>(
+    tree: &DomTree,
+    node_id: NodeId,
+    components: impl Iterator<Item = &'sel Component<'rule>> + Clone,
+) -> bool {
+    let mut iter = components;
     loop {
-        match iter.peek() {
+        match iter.next() {
             None => return true, // Matched everything
             Some(Component::Combinator(comb)) => {
-                // Move past combinator and find next element based on combinator type
-                let comb = *comb;
-                iter.next();
-                match find_next_element(tree, current_node, comb) {
-                    Some(next) => current_node = next,
-                    None => return false,
-                }
+                let rest = |next: NodeId| matches_components(tree, next, iter.clone());
+                return match comb {
+                    Combinator::Child | Combinator::DeepDescendant | Combinator::Deep => {
+                        parent_element(tree, node_id).is_some_and(rest)
+                    }
+                    Combinator::Descendant => {
+                        iter::successors(parent_element(tree, node_id), |&ancestor| {
+                            parent_element(tree, ancestor)
+                        })
+                        .any(rest)
+                    }
+                    Combinator::NextSibling => {
+                        prev_sibling_element(tree, node_id).is_some_and(rest)
+                    }
+                    Combinator::LaterSibling => {
+                        iter::successors(prev_sibling_element(tree, node_id), |&sibling| {
+                            prev_sibling_element(tree, sibling)
+                        })
+                        .any(rest)
+                    }
+                    Combinator::PseudoElement | Combinator::SlotAssignment | Combinator::Part => {
+                        false
+                    }
+                };
             }
-            Some(_) => {
-                // Match simple selector component
-                let component = iter.next().unwrap();
-                if !matches_component(tree, current_node, component) {
+            Some(component) => {
+                if !matches_component(tree, node_id, component) {
                     return false;
                 }
             }
@@ -55,23 +95,13 @@ fn matches_selector(tree: &DomTree, node_id: NodeId, selector: &Selector<'_>) ->
     }
 }
 
-/// Find the next element to match based on the combinator.
-fn find_next_element(tree: &DomTree, node_id: NodeId, combinator: Combinator) -> Option<NodeId> {
-    match combinator {
-        Combinator::Child => parent_element(tree, node_id),
-        Combinator::Descendant => parent_element(tree, node_id),
-        Combinator::NextSibling => prev_sibling_element(tree, node_id),
-        Combinator::LaterSibling => prev_sibling_element(tree, node_id),
-        Combinator::PseudoElement | Combinator::SlotAssignment | Combinator::Part => None,
-        // Deep combinators (deprecated shadow DOM piercing)
-        Combinator::DeepDescendant | Combinator::Deep => parent_element(tree, node_id),
-    }
-}
-
 /// Match a single component against an element.
 fn matches_component(tree: &DomTree, node_id: NodeId, component: &Component<'_>) -> bool {
     let node = &tree.nodes[node_id.0 as usize];
-    let NodeData::Element { tag, attributes } = node else {
+    let NodeData::Element {
+        tag, attributes, ..
+    } = node
+    else {
         return false;
     };
 
@@ -169,7 +199,10 @@ fn matches_component(tree: &DomTree, node_id: NodeId, component: &Component<'_>)
         Component::Empty => is_empty(tree, node_id),
         Component::Scope => true, // Default scope is the root
 
-        Component::Nth(data) => matches_nth(tree, node_id, data),
+        Component::Nth(data) => matches_nth(tree, node_id, data, None),
+        Component::NthOf(data) => {
+            matches_nth(tree, node_id, data.nth_data(), Some(data.selectors()))
+        }
 
         // Non-tree-structural pseudo-classes from lightningcss
         Component::NonTSPseudoClass(pc) => matches_pseudo_class(tree, node_id, pc),
@@ -193,12 +226,14 @@ fn matches_component(tree: &DomTree, node_id: NodeId, component: &Component<'_>)
             matches_has_selector(tree, node_id, s)
         }),
 
+        // The DOM has no shadow trees, so every sheet is a document-level
+        // sheet. There `:host` and `::part()` have no shadow host to match
+        // and `::slotted()` no slot assignment (CSS Scoping 1 §3.2), so
+        // rules using them never apply.
+        Component::Slotted(_) | Component::Part(_) | Component::Host(_) => false,
+
         // Not supported in our DOM model
-        Component::Slotted(_)
-        | Component::Part(_)
-        | Component::Host(_)
-        | Component::NthOf(_)
-        | Component::Nesting => false,
+        Component::Nesting => false,
 
         Component::Combinator(_) => {
             // Combinators are handled in the main loop
@@ -263,122 +298,117 @@ fn match_attr_value(
 }
 
 /// Match :nth-child, :nth-last-child, :nth-of-type, :nth-last-of-type, etc.
-fn matches_nth(tree: &DomTree, node_id: NodeId, data: &NthSelectorData) -> bool {
-    let index = match data.ty {
-        NthType::Child | NthType::LastChild | NthType::OnlyChild => {
-            get_sibling_index(tree, node_id, data.ty, false)
-        }
-        NthType::OfType | NthType::LastOfType | NthType::OnlyOfType => {
-            get_sibling_index(tree, node_id, data.ty, true)
-        }
+///
+/// `of_selectors` is the `S` of `:nth-child(An+B of S)`: only siblings
+/// matching it are counted, and the element must match it too.
+fn matches_nth(
+    tree: &DomTree,
+    node_id: NodeId,
+    data: &NthSelectorData,
+    of_selectors: Option<&[Selector<'_>]>,
+) -> bool {
+    let same_type = match data.ty {
+        NthType::Child | NthType::LastChild | NthType::OnlyChild => false,
+        NthType::OfType | NthType::LastOfType | NthType::OnlyOfType => true,
         // Table column pseudo-classes - not supported
         NthType::Col | NthType::LastCol => return false,
     };
+    let NodeData::Element { tag, .. } = &tree.nodes[node_id.0 as usize] else {
+        return false;
+    };
+    let counted = |sibling: NodeId| match &tree.nodes[sibling.0 as usize] {
+        NodeData::Element {
+            tag: sibling_tag, ..
+        } => {
+            (!same_type || sibling_tag == tag)
+                && of_selectors.is_none_or(|selectors| {
+                    selectors
+                        .iter()
+                        .any(|selector| matches_selector(tree, sibling, selector))
+                })
+        }
+        _ => false,
+    };
+    if !counted(node_id) {
+        return false;
+    }
 
-    let Some(index) = index else {
+    // Elements without a parent still count as their own only sibling.
+    let siblings = element_siblings_in_dom_order(tree, node_id);
+    let mut counted_siblings = siblings.iter().copied().filter(|&sibling| counted(sibling));
+    let from_end = matches!(data.ty, NthType::LastChild | NthType::LastOfType);
+    let position = if from_end {
+        counted_siblings
+            .rev()
+            .position(|sibling| sibling == node_id)
+    } else {
+        counted_siblings.position(|sibling| sibling == node_id)
+    };
+    let Some(position) = position else {
         return false;
     };
 
     // Check :only-child / :only-of-type
     if matches!(data.ty, NthType::OnlyChild | NthType::OnlyOfType) {
-        return index == 1
-            && is_only_sibling(tree, node_id, matches!(data.ty, NthType::OnlyOfType));
-    }
-
-    // Check :first-child / :first-of-type (a=0, b=1)
-    if data.a == 0 {
-        return index == data.b;
+        return siblings.iter().filter(|&&sibling| counted(sibling)).count() == 1;
     }
+    matches_an_plus_b(data.a, data.b, position as i32 + 1)
+}
 
-    // General case: an+b
-    // index = a*n + b for some non-negative integer n
-    // n = (index - b) / a
-    let diff = index - data.b;
-    if data.a == 0 {
+/// Whether the 1-based `index` is `step*n + offset` for some integer
+/// `n >= 0`.
+fn matches_an_plus_b(step: i32, offset: i32, index: i32) -> bool {
+    let diff = index - offset;
+    if step == 0 {
         diff == 0
-    } else if (data.a > 0 && diff >= 0) || (data.a < 0 && diff <= 0) {
-        diff % data.a == 0 && diff / data.a >= 0
     } else {
-        false
+        diff % step == 0 && diff / step >= 0
     }
 }
 
-/// Get the 1-based sibling index.
-fn get_sibling_index(tree: &DomTree, node_id: NodeId, ty: NthType, same_type: bool) -> Option<i32> {
-    let from_end = matches!(ty, NthType::LastChild | NthType::LastOfType);
-    let tag = if same_type {
-        if let NodeData::Element { tag, .. } = &tree.nodes[node_id.0 as usize] {
-            Some(*tag)
-        } else {
-            return None;
-        }
-    } else {
-        None
-    };
-
-    let parent = parent_element(tree, node_id)?;
-
-    // Collect siblings using tree's public children iterator
-    let mut siblings = Vec::new();
-    for current in tree.children(parent) {
-        if let NodeData::Element { tag: elem_tag, .. } = &tree.nodes[current.0 as usize] {
-            if same_type {
-                if Some(*elem_tag) == tag {
-                    siblings.push(current);
-                }
-            } else {
-                siblings.push(current);
-            }
-        }
-    }
-
-    let pos = siblings.iter().position(|&id| id == node_id)?;
-    let index = if from_end {
-        siblings.len() - pos
-    } else {
-        pos + 1
+/// The element children of `node_id`'s parent, `node_id` included, in DOM
+/// order. The tree's child lists run in reverse DOM order.
+fn element_siblings_in_dom_order(tree: &DomTree, node_id: NodeId) -> Vec<NodeId> {
+    let Some(parent) = tree.parent(node_id) else {
+        return vec![node_id];
     };
-    Some(index as i32)
+    let mut siblings: Vec<NodeId> = tree
+        .children(parent)
+        .filter(|&child| matches!(&tree.nodes[child.0 as usize], NodeData::Element { .. }))
+        .collect();
+    siblings.reverse();
+    siblings
 }
 
-/// Check if the element is the only sibling (optionally of same type).
-fn is_only_sibling(tree: &DomTree, node_id: NodeId, same_type: bool) -> bool {
-    let tag = if same_type {
-        if let NodeData::Element { tag, .. } = &tree.nodes[node_id.0 as usize] {
-            Some(*tag)
-        } else {
-            return false;
-        }
-    } else {
-        None
-    };
-
-    let Some(parent) = parent_element(tree, node_id) else {
-        return true;
-    };
-
-    let mut count = 0;
-    for current in tree.children(parent) {
-        if let NodeData::Element { tag: elem_tag, .. } = &tree.nodes[current.0 as usize] {
-            if same_type {
-                if Some(*elem_tag) == tag {
-                    count += 1;
-                }
-            } else {
-                count += 1;
-            }
-            if count > 1 {
-                return false;
-            }
+/// The dynamic state `node_id` matches as. `:hover` and `:active` also
+/// match the ancestors of the element the flag is set on, and
+/// `:focus-within` the focused element and its ancestors; `:focus` and
+/// `:focus-visible` match the element alone.
+pub fn effective_state(tree: &DomTree, node_id: NodeId) -> ElementState {
+    let own = tree.element_state(node_id);
+    let mut state = own.difference(
+        ElementState::HOVER
+            .union(ElementState::ACTIVE)
+            .union(ElementState::FOCUS_WITHIN),
+    );
+    for flag in [ElementState::HOVER, ElementState::ACTIVE] {
+        if tree.state_in_subtree(node_id, flag) {
+            state = state.union(flag);
         }
     }
-    count == 1
+    if tree.state_in_subtree(node_id, ElementState::FOCUS) {
+        state = state.union(ElementState::FOCUS_WITHIN);
+    }
+    state
 }
 
 /// Match lightningcss pseudo-classes.
 fn matches_pseudo_class(tree: &DomTree, node_id: NodeId, pc: &PseudoClass<'_>) -> bool {
     let node = &tree.nodes[node_id.0 as usize];
-    let NodeData::Element { tag, attributes } = node else {
+    let NodeData::Element {
+        tag, attributes, ..
+    } = node
+    else {
         return false;
     };
     let tag_str = tree.interner.resolve(tag);
@@ -393,12 +423,14 @@ fn matches_pseudo_class(tree: &DomTree, node_id: NodeId, pc: &PseudoClass<'_>) -
                     .is_some()
         }
         PseudoClass::Visited => false, // Never match :visited for privacy
-        PseudoClass::Hover
-        | PseudoClass::Active
-        | PseudoClass::Focus
-        | PseudoClass::FocusVisible
-        | PseudoClass::FocusWithin => {
-            false // Dynamic state not tracked
+        PseudoClass::Hover => effective_state(tree, node_id).contains(ElementState::HOVER),
+        PseudoClass::Active => effective_state(tree, node_id).contains(ElementState::ACTIVE),
+        PseudoClass::Focus => effective_state(tree, node_id).contains(ElementState::FOCUS),
+        PseudoClass::FocusVisible => {
+            effective_state(tree, node_id).contains(ElementState::FOCUS_VISIBLE)
+        }
+        PseudoClass::FocusWithin => {
+            effective_state(tree, node_id).contains(ElementState::FOCUS_WITHIN)
         }
         PseudoClass::Enabled => !is_disabled(tree, node_id),
         PseudoClass::Disabled => is_disabled(tree, node_id),
@@ -570,7 +602,10 @@ fn is_html_element(_tree: &DomTree, _node_id: NodeId) -> bool {
 }
 
 fn is_disabled(tree: &DomTree, node_id: NodeId) -> bool {
-    let NodeData::Element { tag, attributes } = &tree.nodes[node_id.0 as usize] else {
+    let NodeData::Element {
+        tag, attributes, ..
+    } = &tree.nodes[node_id.0 as usize]
+    else {
         return false;
     };
     let tag_str = tree.interner.resolve(tag);
@@ -606,17 +641,123 @@ fn parent_element(tree: &DomTree, node_id: NodeId) -> Option<NodeId> {
     }
 }
 
+/// The closest element before `node_id` in DOM order. The tree's child
+/// lists run in reverse DOM order, so earlier siblings follow `node_id`.
 fn prev_sibling_element(tree: &DomTree, node_id: NodeId) -> Option<NodeId> {
-    let parent = parent_element(tree, node_id)?;
+    parent_element(tree, node_id)?;
+    iter::successors(tree.next_sibling(node_id), |&sibling| {
+        tree.next_sibling(sibling)
+    })
+    .find(|&sibling| matches!(&tree.nodes[sibling.0 as usize], NodeData::Element { .. }))
+}
 
-    let mut prev: Option<NodeId> = None;
-    for current in tree.children(parent) {
-        if current == node_id {
-            break;
-        }
-        if matches!(&tree.nodes[current.0 as usize], NodeData::Element { .. }) {
-            prev = Some(current);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lasso::ThreadedRodeo;
+    use lightningcss::rules::CssRule;
+    use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+    use rewrite_html::{Attributes, DomUpdate, Namespace};
+    use std::sync::Arc;
+
+    /// `<ul>` holding `li, p, li, li, p, li`, each with a `class` of its
+    /// DOM position.
+    fn list() -> (DomTree, Vec<NodeId>) {
+        let tree = DomTree::new(Arc::new(ThreadedRodeo::new()));
+        let element = |tag: &str, class: Option<&str>| {
+            let attributes = Attributes::default();
+            if let Some(class) = class {
+                attributes.set(tree.interner.get_or_intern("class"), Some(class.into()));
+            }
+            tree.apply_update(DomUpdate::CreateNode(NodeData::Element {
+                tag: tree.interner.get_or_intern(tag),
+                namespace: Namespace::Html,
+                attributes,
+            }))
+        };
+        let parent = element("ul", None);
+        let children: Vec<NodeId> = ["li", "p", "li", "li", "p", "li"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, tag)| element(tag, Some(&format!("item{}", index + 1))))
+            .collect();
+        for &child in &children {
+            tree.apply_update(DomUpdate::AppendChild { parent, child });
+        }
+        (tree, children)
+    }
+
+    /// DOM positions (1-based) of the children `selector` matches.
+    fn matching(selector: &str) -> Vec<usize> {
+        let (tree, children) = list();
+        let css = format!("{selector} {{ color: red }}");
+        let sheet = StyleSheet::parse(&css, ParserOptions::default()).unwrap();
+        let Some(CssRule::Style(rule)) = sheet.rules.0.first() else {
+            panic!("no style rule in {css}");
+        };
+        (1..=children.len())
+            .filter(|&position| {
+                matches_selector_list(&tree, children[position - 1], &rule.selectors)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nth_child_counts_element_siblings_in_dom_order() {
+        assert_eq!(matching(":first-child"), [1]);
+        assert_eq!(matching(":last-child"), [6]);
+        assert_eq!(matching(":nth-child(2)"), [2]);
+        assert_eq!(matching(":nth-child(odd)"), [1, 3, 5]);
+        assert_eq!(matching(":nth-child(even)"), [2, 4, 6]);
+        assert_eq!(matching(":nth-child(3n+1)"), [1, 4]);
+        assert_eq!(matching(":nth-child(-n+3)"), [1, 2, 3]);
+        assert_eq!(matching(":nth-last-child(2)"), [5]);
+        assert_eq!(matching(":nth-last-child(-n+2)"), [5, 6]);
+    }
+
+    #[test]
+    fn nth_of_type_counts_same_tag_siblings() {
+        assert_eq!(matching("li:first-of-type"), [1]);
+        assert_eq!(matching("p:last-of-type"), [5]);
+        assert_eq!(matching("li:nth-of-type(2)"), [3]);
+        assert_eq!(matching("li:nth-of-type(even)"), [3, 6]);
+        assert_eq!(matching(":nth-last-of-type(1)"), [5, 6]);
+        assert_eq!(matching("li:nth-last-of-type(n+2)"), [1, 3, 4]);
+        assert_eq!(matching("p:only-of-type"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn nth_child_of_selector_counts_matching_siblings() {
+        assert_eq!(matching(":nth-child(2 of li)"), [3]);
+        assert_eq!(matching(":nth-child(odd of li, .item2)"), [1, 3, 6]);
+        assert_eq!(matching(":nth-last-child(1 of p)"), [5]);
+        assert_eq!(matching("p:nth-child(1 of li)"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn shadow_scoped_selectors_never_match_the_document() {
+        assert_eq!(matching(":host"), Vec::<usize>::new());
+        assert_eq!(matching(":host(ul) li"), Vec::<usize>::new());
+        assert_eq!(matching("::slotted(li)"), Vec::<usize>::new());
+        assert_eq!(matching(":is(:host, li)"), [1, 3, 4, 6]);
+    }
+
+    #[test]
+    fn functional_pseudo_classes_match_their_arguments() {
+        assert_eq!(matching(":not(.item1)"), [2, 3, 4, 5, 6]);
+        assert_eq!(matching("li:not(:first-child)"), [3, 4, 6]);
+        assert_eq!(matching(":not(li, .item2)"), [5]);
+        assert_eq!(matching(":is(p, .item1)"), [1, 2, 5]);
+        assert_eq!(matching(":where(ul > .item3, .item6)"), [3, 6]);
+        assert_eq!(matching("li:not(:is(.item3, :last-child))"), [1, 4]);
+        assert_eq!(matching(":is(ol, ul) > :not(p)"), [1, 3, 4, 6]);
+    }
+
+    #[test]
+    fn sibling_combinators_follow_dom_order() {
+        assert_eq!(matching(".item1 + li"), Vec::<usize>::new());
+        assert_eq!(matching(".item2 + li"), [3]);
+        assert_eq!(matching(".item4 ~ *"), [5, 6]);
+        assert_eq!(matching("ul li"), [1, 3, 4, 6]);
     }
-    prev
 }
@@ -1,5 +1,13 @@
 //! CSS selector matching against DOM nodes.
 
+mod bloom;
+mod dependencies;
 mod matcher;
+mod structural;
 
-pub use matcher::matches_selector_list;
+pub use bloom::{AncestorFilter, AncestorFilterCounters, AncestorFilterStats, AncestorHashes};
+pub use dependencies::{AttributeReach, Dependency, selector_dependencies};
+pub use matcher::{
+    effective_state, matches_pseudo_element, matches_selector, matches_selector_list,
+};
+pub use structural::StructuralFilter;
@@ -0,0 +1,183 @@
+//! Structural pseudo-class prefilters.
+//!
+//! A rule whose rightmost compound contains `:first-child`, `:last-child`,
+//! `:only-child` or an `:nth-child()` that excludes the first position can
+//! only match elements in certain sibling positions. `StructuralFilter`
+//! records that requirement when the rule is parsed, so the Styler can
+//! reject most candidates by following a couple of sibling links instead
+//! of running the full matcher.
+
+use lightningcss::selector::{Component, Selector, SelectorList};
+use parcel_selectors::parser::{NthSelectorData, NthType};
+use rewrite_core::NodeId;
+use rewrite_html::{DomTree, NodeData};
+use std::iter;
+
+/// Sibling positions a rule's subject is required to have. `None` means
+/// no requirement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StructuralFilter {
+    /// Whether the subject must (`true`) or must not (`false`) be the
+    /// first element child of its parent.
+    pub first: Option<bool>,
+    /// Likewise for the last element child.
+    pub last: Option<bool>,
+}
+
+impl StructuralFilter {
+    /// The requirement shared by every selector in `selectors`.
+    pub fn for_selectors(selectors: &SelectorList<'_>) -> Self {
+        let mut filters = selectors.0.iter().map(for_selector);
+        let Some(first) = filters.next() else {
+            return Self::default();
+        };
+        filters.fold(first, |acc, filter| Self {
+            first: acc.first.filter(|&value| filter.first == Some(value)),
+            last: acc.last.filter(|&value| filter.last == Some(value)),
+        })
+    }
+
+    /// Whether the filter never rejects anything.
+    pub fn is_trivial(self) -> bool {
+        self.first.is_none() && self.last.is_none()
+    }
+
+    /// Cheap necessary condition for a match: `false` means the rule
+    /// cannot match `node`; `true` means the full matcher must decide.
+    pub fn admits(self, tree: &DomTree, node: NodeId) -> bool {
+        if self.is_trivial() {
+            return true;
+        }
+        let Some(parent) = tree.parent(node) else {
+            return true;
+        };
+        self.first
+            .is_none_or(|first| first == is_first_element(tree, node))
+            && self
+                .last
+                .is_none_or(|last| last == is_last_element(tree, parent, node))
+    }
+}
+
+/// The requirement of the rightmost compound of one selector.
+fn for_selector(selector: &Selector<'_>) -> StructuralFilter {
+    let mut filter = StructuralFilter::default();
+    for component in selector.iter_raw_match_order() {
+        let Component::Nth(data) = component else {
+            if matches!(component, Component::Combinator(_)) {
+                break;
+            }
+            continue;
+        };
+        let (first, last) = nth_requirement(data);
+        filter.first = filter.first.or(first);
+        filter.last = filter.last.or(last);
+    }
+    filter
+}
+
+/// First/last requirements of a child-indexed `:nth-*` pseudo-class.
+fn nth_requirement(data: &NthSelectorData) -> (Option<bool>, Option<bool>) {
+    // Positions an+b (n >= 0) with a >= 0 never reach 1 once b >= 2.
+    let excludes_first = data.a >= 0 && data.b >= 2;
+    let position = if data.a == 0 && data.b == 1 {
+        Some(true)
+    } else if excludes_first {
+        Some(false)
+    } else {
+        None
+    };
+    match data.ty {
+        NthType::Child => (position, None),
+        NthType::LastChild => (None, position),
+        NthType::OnlyChild => (Some(true), Some(true)),
+        _ => (None, None),
+    }
+}
+
+fn is_element(tree: &DomTree, node: NodeId) -> bool {
+    matches!(tree.get_node(node), Some(NodeData::Element { .. }))
+}
+
+/// Whether no element precedes `node` among its siblings. The child list
+/// runs in reverse DOM order, so DOM-earlier siblings follow `node`.
+fn is_first_element(tree: &DomTree, node: NodeId) -> bool {
+    iter::successors(tree.next_sibling(node), |&sibling| {
+        tree.next_sibling(sibling)
+    })
+    .all(|sibling| !is_element(tree, sibling))
+}
+
+/// Whether no element follows `node` among the children of `parent`.
+fn is_last_element(tree: &DomTree, parent: NodeId, node: NodeId) -> bool {
+    tree.children(parent)
+        .find(|&child| is_element(tree, child))
+        .is_none_or(|child| child == node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lasso::ThreadedRodeo;
+    use lightningcss::rules::CssRule;
+    use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+    use rewrite_html::{Attributes, DomUpdate, Namespace};
+    use std::sync::Arc;
+
+    fn filter(selector: &str) -> StructuralFilter {
+        let css = format!("{selector} {{ color: red }}");
+        let sheet = StyleSheet::parse(&css, ParserOptions::default()).unwrap();
+        let selectors = sheet
+            .rules
+            .0
+            .iter()
+            .find_map(|rule| match rule {
+                CssRule::Style(style_rule) => Some(&style_rule.selectors),
+                _ => None,
+            })
+            .unwrap();
+        StructuralFilter::for_selectors(selectors)
+    }
+
+    #[test]
+    fn requirements_from_rightmost_compound() {
+        assert_eq!(filter("li:first-child").first, Some(true));
+        assert_eq!(filter("li:last-child").last, Some(true));
+        assert_eq!(filter("li:nth-child(2n+3)").first, Some(false));
+        assert!(filter("li:nth-child(odd)").is_trivial());
+        assert!(filter("li:first-child span").is_trivial());
+        assert!(filter("li:first-child, li:last-child").is_trivial());
+        assert_eq!(
+            filter("li:only-child"),
+            StructuralFilter {
+                first: Some(true),
+                last: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn admits_by_sibling_position() {
+        let tree = DomTree::new(Arc::new(ThreadedRodeo::new()));
+        let element = || {
+            tree.apply_update(DomUpdate::CreateNode(NodeData::Element {
+                tag: tree.interner.get_or_intern("li"),
+                namespace: Namespace::Html,
+                attributes: Attributes::default(),
+            }))
+        };
+        let parent = element();
+        let (first, middle, last) = (element(), element(), element());
+        for child in [first, middle, last] {
+            tree.apply_update(DomUpdate::AppendChild { parent, child });
+        }
+
+        let first_child = filter(":first-child");
+        assert!(first_child.admits(&tree, first));
+        assert!(!first_child.admits(&tree, middle));
+        let last_child = filter(":last-child");
+        assert!(last_child.admits(&tree, last));
+        assert!(!last_child.admits(&tree, middle));
+        assert!(!filter(":nth-child(3n+2)").admits(&tree, first));
+    }
+}
@@ -0,0 +1,211 @@
+//! Whole-stylesheet parsing on a worker thread.
+//!
+//! Large stylesheets are parsed off the frame loop: `StylesheetWorker::parse`
+//! queues the text and returns immediately, and the completed rules arrive as
+//! a `ParsedStylesheet` on the worker's channel. Each stylesheet is keyed by
+//! a `SheetId`; queueing new text for a sheet supersedes any parse still in
+//! flight for it, which is then abandoned at the next rule boundary and never
+//! delivered.
+
+use crate::import::RuleCollector;
+use crate::{ParsedRule, ResourceResolver};
+use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+use rewrite_core::Origin;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+/// Identifies a replaceable stylesheet (a `<style>` element, a `<link>`ed
+/// sheet, an embedder-supplied sheet).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SheetId(pub u32);
+
+impl SheetId {
+    /// The user-agent default stylesheet every page starts with.
+    pub const USER_AGENT: Self = Self(u32::MAX);
+}
+
+/// A fully parsed stylesheet, ready for `Styler::replace_stylesheet`.
+#[derive(Debug)]
+pub struct ParsedStylesheet {
+    pub sheet: SheetId,
+    /// Increases with every `parse` call for the same sheet.
+    pub generation: u64,
+    pub rules: Vec<ParsedRule>,
+}
+
+/// Latest requested generation per sheet. A parse whose generation is
+/// older has been superseded.
+type Generations = Arc<Mutex<HashMap<SheetId, u64>>>;
+
+/// A queued parse.
+struct Job {
+    sheet: SheetId,
+    generation: u64,
+    css: String,
+    origin: Origin,
+    resolver: Option<ResourceResolver>,
+}
+
+/// Handle to the stylesheet parsing thread. Dropping it stops the thread
+/// once queued work is drained.
+pub struct StylesheetWorker {
+    jobs: Sender<Job>,
+    generations: Generations,
+    /// Fetches the sheets named by `@import`.
+    resolver: Option<ResourceResolver>,
+}
+
+impl StylesheetWorker {
+    /// Start the worker thread. Completed stylesheets are delivered on the
+    /// returned receiver in completion order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the OS refuses to spawn a thread.
+    pub fn spawn() -> (Self, Receiver<ParsedStylesheet>) {
+        let (jobs, job_rx) = mpsc::channel::<Job>();
+        let (done, done_rx) = mpsc::channel();
+        let generations = Generations::default();
+        let worker_generations = Arc::clone(&generations);
+
+        thread::spawn(move || {
+            for job in job_rx {
+                let Some(parsed) = parse_job(&job, &worker_generations) else {
+                    continue;
+                };
+                if done.send(parsed).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (
+            Self {
+                jobs,
+                generations,
+                resolver: None,
+            },
+            done_rx,
+        )
+    }
+
+    /// Follow `@import`s in later parses by fetching the imported sheets
+    /// through `resolver`. Without one, imports are dropped.
+    pub fn set_resource_resolver(&mut self, resolver: Option<ResourceResolver>) {
+        self.resolver = resolver;
+    }
+
+    /// Queue `css` as the new contents of `sheet`, superseding any earlier
+    /// parse of the same sheet. Returns the generation of this parse.
+    pub fn parse(&self, sheet: SheetId, css: String, origin: Origin) -> u64 {
+        let generation = {
+            let mut generations = self
+                .generations
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let generation = generations.entry(sheet).or_default();
+            *generation += 1;
+            *generation
+        };
+        let job = Job {
+            sheet,
+            generation,
+            css,
+            origin,
+            resolver: self.resolver.clone(),
+        };
+        // Only fails if the worker thread died; the sheet then never arrives.
+        if self.jobs.send(job).is_err() {
+            return generation;
+        }
+        generation
+    }
+
+    /// Abandon any in-flight parse of `sheet` without queueing a new one.
+    pub fn cancel(&self, sheet: SheetId) {
+        let mut generations = self
+            .generations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(generation) = generations.get_mut(&sheet) {
+            *generation += 1;
+        }
+    }
+
+    /// Whether `parsed` is still the latest requested contents of its sheet.
+    /// Results can be superseded after delivery but before being applied.
+    pub fn is_current(&self, parsed: &ParsedStylesheet) -> bool {
+        is_current(&self.generations, parsed.sheet, parsed.generation)
+    }
+}
+
+fn is_current(generations: &Generations, sheet: SheetId, generation: u64) -> bool {
+    generations
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&sheet)
+        .is_some_and(|latest| *latest == generation)
+}
+
+/// Parse a job, or `None` if it was superseded before finishing.
+fn parse_job(job: &Job, generations: &Generations) -> Option<ParsedStylesheet> {
+    let current = || is_current(generations, job.sheet, job.generation);
+    if !current() {
+        return None;
+    }
+
+    let options = ParserOptions {
+        error_recovery: true,
+        ..ParserOptions::default()
+    };
+    let stylesheet = StyleSheet::parse(&job.css, options).ok()?;
+
+    let mut rules = Vec::new();
+    let mut collector = RuleCollector::new(job.origin, job.resolver.as_ref());
+    for rule in stylesheet.rules.0 {
+        if !current() {
+            return None;
+        }
+        collector.collect(rule, &mut rules);
+    }
+
+    current().then_some(ParsedStylesheet {
+        sheet: job.sheet,
+        generation: job.generation,
+        rules,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn newer_parse_supersedes_older() {
+        let (worker, parsed) = StylesheetWorker::spawn();
+        let sheet = SheetId(1);
+        worker.parse(sheet, "a { color: red }".to_owned(), Origin::Author);
+        let latest = worker.parse(
+            sheet,
+            "a { color: blue } b { color: red }".to_owned(),
+            Origin::Author,
+        );
+
+        // The first parse may or may not be delivered, but never as current.
+        let delivered = loop {
+            let result = parsed.recv_timeout(Duration::from_secs(10)).unwrap();
+            if result.generation == latest {
+                break result;
+            }
+            assert!(!worker.is_current(&result));
+        };
+        assert!(worker.is_current(&delivered));
+        assert_eq!(delivered.rules.len(), 2);
+
+        worker.cancel(sheet);
+        assert!(!worker.is_current(&delivered));
+    }
+}
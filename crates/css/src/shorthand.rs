@@ -0,0 +1,250 @@
+//! Shorthand expansion.
+//!
+//! Declarations are stored as longhands only: the cascade, the sparse
+//! trees and layout never see `margin`, just `margin-top` and friends.
+//! Every declaration source (style sheets, `style` attributes,
+//! presentational hints, snapshots) expands through `expand_shorthand`.
+//!
+//! `SHORTHANDS` lists the shorthands layout depends on and the longhands
+//! each sets, in the order they're emitted. Entries can name other
+//! shorthands, which expand in turn (`border` sets `border-width`, which
+//! sets its four sides). Shorthands not in the table, such as
+//! `border-top` or `list-style`, and vendor-prefixed ones, expand to the
+//! longhands lightningcss lists for them.
+
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::traits::IntoOwned as _;
+use lightningcss::vendor_prefix::VendorPrefix;
+
+/// A shorthand property and the properties it sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shorthand {
+    pub name: &'static str,
+    /// In emission order. May include other shorthands.
+    pub longhands: &'static [&'static str],
+}
+
+/// Shorthands with a fixed expansion.
+pub const SHORTHANDS: &[Shorthand] = &[
+    Shorthand {
+        name: "margin",
+        longhands: &["margin-top", "margin-right", "margin-bottom", "margin-left"],
+    },
+    Shorthand {
+        name: "padding",
+        longhands: &[
+            "padding-top",
+            "padding-right",
+            "padding-bottom",
+            "padding-left",
+        ],
+    },
+    Shorthand {
+        name: "border",
+        longhands: &["border-width", "border-style", "border-color"],
+    },
+    Shorthand {
+        name: "border-width",
+        longhands: &[
+            "border-top-width",
+            "border-right-width",
+            "border-bottom-width",
+            "border-left-width",
+        ],
+    },
+    Shorthand {
+        name: "border-style",
+        longhands: &[
+            "border-top-style",
+            "border-right-style",
+            "border-bottom-style",
+            "border-left-style",
+        ],
+    },
+    Shorthand {
+        name: "border-color",
+        longhands: &[
+            "border-top-color",
+            "border-right-color",
+            "border-bottom-color",
+            "border-left-color",
+        ],
+    },
+    Shorthand {
+        name: "background",
+        longhands: &[
+            "background-color",
+            "background-image",
+            "background-position-x",
+            "background-position-y",
+            "background-repeat",
+            "background-size",
+            "background-attachment",
+            "background-origin",
+            "background-clip",
+        ],
+    },
+    Shorthand {
+        name: "font",
+        longhands: &[
+            "font-family",
+            "font-size",
+            "font-style",
+            "font-weight",
+            "font-stretch",
+            "line-height",
+            "font-variant-caps",
+        ],
+    },
+    Shorthand {
+        name: "flex",
+        longhands: &["flex-grow", "flex-shrink", "flex-basis"],
+    },
+    Shorthand {
+        name: "gap",
+        longhands: &["row-gap", "column-gap"],
+    },
+    Shorthand {
+        name: "inset",
+        longhands: &["top", "right", "bottom", "left"],
+    },
+    Shorthand {
+        name: "overflow",
+        longhands: &["overflow-x", "overflow-y"],
+    },
+];
+
+/// The table entry for the property called `name`.
+pub fn shorthand(name: &str) -> Option<&'static Shorthand> {
+    SHORTHANDS.iter().find(|entry| entry.name == name)
+}
+
+/// The longhand declarations `prop` stands for: itself if it isn't a
+/// shorthand. A longhand the value doesn't give (an unparsed value with
+/// `var()`, say) is left out.
+pub fn expand_shorthand(prop: Property<'_>) -> Vec<Property<'static>> {
+    let mut longhands = Vec::new();
+    push_longhands(prop.into_owned(), &mut longhands);
+    longhands
+}
+
+/// Push the longhands of `prop` onto `out`, recursing through nested
+/// shorthands.
+pub fn push_longhands(prop: Property<'static>, out: &mut Vec<Property<'static>>) {
+    let prop_id = prop.property_id();
+    let entry = (prop_id.prefix() == VendorPrefix::None)
+        .then(|| shorthand(prop_id.name()))
+        .flatten();
+    let longhand_ids: Vec<PropertyId<'static>> = if let Some(entry) = entry {
+        entry
+            .longhands
+            .iter()
+            .map(|&name| PropertyId::from(name))
+            .collect()
+    } else if let Some(ids) = prop_id.longhands() {
+        ids
+    } else {
+        out.push(prop);
+        return;
+    };
+    for longhand_id in &longhand_ids {
+        if let Some(longhand) = prop.longhand(longhand_id) {
+            push_longhands(longhand.into_owned(), out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightningcss::printer::PrinterOptions;
+    use lightningcss::stylesheet::ParserOptions;
+
+    fn expanded(name: &str, value: &str) -> Vec<String> {
+        let prop = Property::parse_string(PropertyId::from(name), value, ParserOptions::default())
+            .expect("parses");
+        expand_shorthand(prop)
+            .iter()
+            .map(|longhand| {
+                let text = longhand
+                    .value_to_css_string(PrinterOptions::default())
+                    .expect("serializes");
+                format!("{}: {text}", longhand.property_id().name())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn table_entries_expand_to_their_longhands() {
+        let samples = [
+            ("margin", "1px"),
+            ("padding", "1px 2px"),
+            ("border", "1px solid red"),
+            ("border-width", "1px"),
+            ("border-style", "solid"),
+            ("border-color", "red"),
+            ("background", "red"),
+            ("font", "12px serif"),
+            ("flex", "1"),
+            ("gap", "4px"),
+            ("inset", "0"),
+            ("overflow", "hidden"),
+        ];
+        assert_eq!(samples.len(), SHORTHANDS.len());
+        for (name, value) in samples {
+            let longhands = expanded(name, value);
+            let mut expected: Vec<&str> = Vec::new();
+            let mut pending = vec![name];
+            while let Some(next) = pending.pop() {
+                match shorthand(next) {
+                    Some(entry) => pending.extend(entry.longhands.iter().rev()),
+                    None => expected.push(next),
+                }
+            }
+            let names: Vec<&str> = longhands
+                .iter()
+                .filter_map(|longhand| longhand.split(':').next())
+                .collect();
+            assert_eq!(names, expected, "{name}: {value}");
+        }
+    }
+
+    #[test]
+    fn values_are_distributed_to_sides() {
+        assert_eq!(
+            expanded("margin", "1px 2px 3px"),
+            [
+                "margin-top: 1px",
+                "margin-right: 2px",
+                "margin-bottom: 3px",
+                "margin-left: 2px",
+            ]
+        );
+        assert_eq!(
+            expanded("flex", "2 1 10px"),
+            ["flex-grow: 2", "flex-shrink: 1", "flex-basis: 10px"]
+        );
+    }
+
+    #[test]
+    fn other_shorthands_and_longhands() {
+        assert_eq!(
+            expanded("flex-flow", "column wrap"),
+            ["flex-direction: column", "flex-wrap: wrap"]
+        );
+        assert_eq!(expanded("margin-top", "1px"), ["margin-top: 1px"]);
+        let prefixed = Property::parse_string(
+            PropertyId::from("-webkit-flex"),
+            "1",
+            ParserOptions::default(),
+        )
+        .expect("parses");
+        let longhands = expand_shorthand(prefixed);
+        assert_eq!(longhands.len(), 3);
+        assert!(
+            longhands
+                .iter()
+                .all(|longhand| longhand.property_id().prefix() == VendorPrefix::WebKit)
+        );
+    }
+}
@@ -0,0 +1,531 @@
+//! Styler snapshots for replaying cascade state.
+//!
+//! A `StyleSnapshot` captures everything the Styler keeps between calls:
+//! the rule list with its retired and per-sheet indices, each node's
+//! matched rules in match order, the `@page` and unknown at-rules, the
+//! media environment and the forced-colors palette. Rules are stored as
+//! CSS text that reparses to the same rule, so a snapshot taken from a
+//! failing run can be written out with `to_text` and loaded in a test with
+//! `from_text` and `Styler::restore`.
+//!
+//! Restoring doesn't re-run selector matching. A match that was stale when
+//! the snapshot was taken stays stale, which is what makes an invalidation
+//! bug reproducible. The DOM isn't part of the snapshot; restore against a
+//! tree built from the same document so node ids line up.
+
+use crate::parser::collect_style_rules;
+use crate::{ForcedColorsPalette, MediaEnvironment, ParsedRule, Properties, RuleMedia, SheetId};
+use lightningcss::declaration::DeclarationBlock;
+use lightningcss::printer::PrinterOptions;
+use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+use lightningcss::traits::ToCss as _;
+use lightningcss::values::color::RGBA;
+use rewrite_core::{NodeId, Origin};
+use std::fmt;
+
+/// First line of the text form, naming the format version.
+const HEADER: &str = "style-snapshot 2";
+
+/// Source position and rule indices of each replaceable sheet.
+type SheetIndices = Vec<(SheetId, usize, Vec<usize>)>;
+
+/// `@page` and unknown at-rules, each with the sheet it came from.
+type SheetAtRules = Vec<(Option<SheetId>, RuleSnapshot)>;
+
+/// What kind of rule a `RuleSnapshot` holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleSource {
+    /// A style rule from a stylesheet.
+    Stylesheet,
+    /// The `style` attribute of a node.
+    Inline(NodeId),
+    /// Presentational hints of a node.
+    Presentational(NodeId),
+    /// An `@page` rule.
+    Page,
+    /// An unknown at-rule.
+    Opaque,
+}
+
+/// One rule, as CSS text that reparses to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSnapshot {
+    pub source: RuleSource,
+    pub origin: Origin,
+    /// The rule with its enclosing `@media` blocks; for node-scoped rules,
+    /// just the declarations.
+    pub css: String,
+}
+
+impl RuleSnapshot {
+    /// Serialize `rule`.
+    pub fn capture(rule: &ParsedRule) -> Self {
+        let options = PrinterOptions::default;
+        let (source, css) = match rule {
+            ParsedRule::Stylesheet {
+                selectors,
+                properties,
+                media,
+                ..
+            } => {
+                let selectors = selectors.to_css_string(options()).unwrap_or_default();
+                let css = format!("{selectors} {{ {} }}", declarations(properties));
+                (RuleSource::Stylesheet, in_media(media, css))
+            }
+            ParsedRule::Inline {
+                node_id,
+                properties,
+            } => (RuleSource::Inline(*node_id), declarations(properties)),
+            ParsedRule::Presentational {
+                node_id,
+                properties,
+            } => (
+                RuleSource::Presentational(*node_id),
+                declarations(properties),
+            ),
+            ParsedRule::Page(page_rule) => {
+                let selectors: Vec<String> = page_rule
+                    .selectors
+                    .iter()
+                    .filter_map(|selector| selector.to_css_string(options()).ok())
+                    .collect();
+                let css = format!(
+                    "@page {} {{ {} }}",
+                    selectors.join(", "),
+                    declarations(&page_rule.properties)
+                );
+                (RuleSource::Page, in_media(&page_rule.media, css))
+            }
+            ParsedRule::Opaque(opaque_rule) => (RuleSource::Opaque, opaque_rule.css_text()),
+        };
+        Self {
+            source,
+            origin: rule.origin(),
+            css,
+        }
+    }
+
+    /// Reparse the rule.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SnapshotError::InvalidRule` if the text doesn't parse to a
+    /// single rule of the recorded kind.
+    pub fn parse(&self) -> Result<ParsedRule, SnapshotError> {
+        let invalid = || SnapshotError::InvalidRule(self.css.clone());
+        match self.source {
+            RuleSource::Inline(node_id) => Ok(ParsedRule::Inline {
+                node_id,
+                properties: self.parse_declarations().ok_or_else(invalid)?,
+            }),
+            RuleSource::Presentational(node_id) => Ok(ParsedRule::Presentational {
+                node_id,
+                properties: self.parse_declarations().ok_or_else(invalid)?,
+            }),
+            RuleSource::Stylesheet | RuleSource::Page | RuleSource::Opaque => {
+                let sheet = StyleSheet::parse(&self.css, ParserOptions::default())
+                    .map_err(|_| invalid())?;
+                let mut rules = Vec::new();
+                for rule in sheet.rules.0 {
+                    collect_style_rules(rule, &RuleMedia::default(), self.origin, &mut rules);
+                }
+                let rule = rules
+                    .pop()
+                    .filter(|_| rules.is_empty())
+                    .ok_or_else(invalid)?;
+                let kind_matches = matches!(
+                    (self.source, &rule),
+                    (RuleSource::Stylesheet, ParsedRule::Stylesheet { .. })
+                        | (RuleSource::Page, ParsedRule::Page(_))
+                        | (RuleSource::Opaque, ParsedRule::Opaque(_))
+                );
+                if kind_matches {
+                    Ok(rule)
+                } else {
+                    Err(invalid())
+                }
+            }
+        }
+    }
+
+    fn parse_declarations(&self) -> Option<Properties> {
+        let options = ParserOptions {
+            error_recovery: true,
+            ..Default::default()
+        };
+        DeclarationBlock::parse_string(&self.css, options)
+            .ok()
+            .map(Properties::from)
+    }
+
+    /// `source origin css` as written in the text form.
+    fn to_line(&self) -> String {
+        let source = match self.source {
+            RuleSource::Stylesheet => "stylesheet".to_owned(),
+            RuleSource::Inline(node) => format!("inline:{}", node.0),
+            RuleSource::Presentational(node) => format!("presentational:{}", node.0),
+            RuleSource::Page => "page".to_owned(),
+            RuleSource::Opaque => "opaque".to_owned(),
+        };
+        let origin = match self.origin {
+            Origin::UserAgent => "ua",
+            Origin::User => "user",
+            Origin::Author => "author",
+        };
+        format!("{source} {origin} {}", escape(&self.css))
+    }
+
+    /// Inverse of `to_line`.
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(3, ' ');
+        let source = match fields.next()? {
+            "stylesheet" => RuleSource::Stylesheet,
+            "page" => RuleSource::Page,
+            "opaque" => RuleSource::Opaque,
+            source => {
+                let (kind, node) = source.split_once(':')?;
+                let node = NodeId(node.parse().ok()?);
+                match kind {
+                    "inline" => RuleSource::Inline(node),
+                    "presentational" => RuleSource::Presentational(node),
+                    _ => return None,
+                }
+            }
+        };
+        let origin = match fields.next()? {
+            "ua" => Origin::UserAgent,
+            "user" => Origin::User,
+            "author" => Origin::Author,
+            _ => return None,
+        };
+        Some(Self {
+            source,
+            origin,
+            css: unescape(fields.next()?)?,
+        })
+    }
+}
+
+/// The Styler's state at one point in time. See the module docs.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StyleSnapshot {
+    /// Every rule ever added, retired ones included, so indices match.
+    pub rules: Vec<RuleSnapshot>,
+    /// Indices of rules that no longer match, in ascending order.
+    pub retired: Vec<usize>,
+    /// Source position and rule indices of each replaceable sheet, ordered
+    /// by sheet id.
+    pub sheets: SheetIndices,
+    /// `@page` and unknown at-rules with the sheet each came from, in the
+    /// order they were added.
+    pub at_rules: SheetAtRules,
+    /// Matched rule indices of each node, indexed by node id, in match
+    /// order.
+    pub matched: Vec<Vec<usize>>,
+    pub media: MediaEnvironment,
+    pub forced_colors: Option<ForcedColorsPalette>,
+}
+
+impl StyleSnapshot {
+    /// Serialize to a line-based text form.
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![HEADER.to_owned()];
+        let viewport = self.media.viewport.map_or_else(
+            || "-".to_owned(),
+            |(width, height)| format!("{width}x{height}"),
+        );
+        lines.push(format!(
+            "media {} {} {viewport}",
+            u8::from(self.media.print),
+            u8::from(self.media.forced_colors)
+        ));
+        if let Some(palette) = &self.forced_colors {
+            let colors: Vec<String> = palette_colors(palette)
+                .iter()
+                .map(|color| {
+                    format!(
+                        "#{:02x}{:02x}{:02x}{:02x}",
+                        color.red, color.green, color.blue, color.alpha
+                    )
+                })
+                .collect();
+            lines.push(format!("forced-colors {}", colors.join(" ")));
+        }
+        for rule in &self.rules {
+            lines.push(format!("rule {}", rule.to_line()));
+        }
+        for (sheet, rule) in &self.at_rules {
+            let sheet = sheet.map_or_else(|| "-".to_owned(), |sheet| sheet.0.to_string());
+            lines.push(format!("at-rule {sheet} {}", rule.to_line()));
+        }
+        lines.push(with_indices("retired".to_owned(), &self.retired));
+        for (sheet, position, indices) in &self.sheets {
+            lines.push(with_indices(
+                format!("sheet {} {position}", sheet.0),
+                indices,
+            ));
+        }
+        for indices in &self.matched {
+            lines.push(with_indices("match".to_owned(), indices));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// Parse the output of `to_text`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SnapshotError::Malformed` with the 1-based number of the
+    /// first line that can't be read.
+    pub fn from_text(text: &str) -> Result<Self, SnapshotError> {
+        let mut snapshot = Self::default();
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(SnapshotError::Malformed { line: 1 });
+        }
+        for (index, line) in lines {
+            snapshot
+                .read_line(line)
+                .ok_or(SnapshotError::Malformed { line: index + 1 })?;
+        }
+        Ok(snapshot)
+    }
+
+    fn read_line(&mut self, line: &str) -> Option<()> {
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match kind {
+            "media" => {
+                let flags: Vec<&str> = rest.split(' ').collect();
+                let [print, forced_colors, viewport] = flags.as_slice() else {
+                    return None;
+                };
+                let viewport = match *viewport {
+                    "-" => None,
+                    viewport => {
+                        let (width, height) = viewport.split_once('x')?;
+                        Some((width.parse().ok()?, height.parse().ok()?))
+                    }
+                };
+                self.media = MediaEnvironment {
+                    print: *print == "1",
+                    forced_colors: *forced_colors == "1",
+                    viewport,
+                };
+            }
+            "forced-colors" => {
+                let colors = rest
+                    .split(' ')
+                    .map(parse_color)
+                    .collect::<Option<Vec<_>>>()?;
+                self.forced_colors = Some(palette_from_colors(colors.try_into().ok()?));
+            }
+            "rule" => self.rules.push(RuleSnapshot::from_line(rest)?),
+            "at-rule" => {
+                let (sheet, rule) = rest.split_once(' ')?;
+                let sheet = match sheet {
+                    "-" => None,
+                    sheet => Some(SheetId(sheet.parse().ok()?)),
+                };
+                self.at_rules.push((sheet, RuleSnapshot::from_line(rule)?));
+            }
+            "retired" => self.retired = parse_indices(rest)?,
+            "sheet" => {
+                let (sheet, rest) = rest.split_once(' ')?;
+                let (position, indices) = rest.split_once(' ').unwrap_or((rest, ""));
+                self.sheets.push((
+                    SheetId(sheet.parse().ok()?),
+                    position.parse().ok()?,
+                    parse_indices(indices)?,
+                ));
+            }
+            "match" => self.matched.push(parse_indices(rest)?),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Check that every index refers to a rule, and that the snapshot
+    /// covers no more nodes than `node_count`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first index past the end of the rule list, or
+    /// `SnapshotError::TreeMismatch`.
+    pub(crate) fn validate(&self, node_count: usize) -> Result<(), SnapshotError> {
+        if self.matched.len() > node_count {
+            return Err(SnapshotError::TreeMismatch {
+                snapshot_nodes: self.matched.len(),
+                tree_nodes: node_count,
+            });
+        }
+        let indices = self
+            .retired
+            .iter()
+            .chain(self.sheets.iter().flat_map(|(.., indices)| indices))
+            .chain(self.matched.iter().flatten());
+        for &index in indices {
+            if index >= self.rules.len() {
+                return Err(SnapshotError::RuleOutOfRange(index));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a snapshot couldn't be read or restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// A line of the text form couldn't be read (1-based).
+    Malformed { line: usize },
+    /// A rule's CSS didn't reparse to a rule of its recorded kind.
+    InvalidRule(String),
+    /// A retired, sheet or match index past the end of the rule list.
+    RuleOutOfRange(usize),
+    /// The snapshot has match storage for more nodes than the tree has.
+    TreeMismatch {
+        snapshot_nodes: usize,
+        tree_nodes: usize,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed { line } => write!(f, "malformed snapshot line {line}"),
+            Self::InvalidRule(css) => write!(f, "snapshot rule doesn't reparse: {css}"),
+            Self::RuleOutOfRange(index) => write!(f, "snapshot refers to missing rule {index}"),
+            Self::TreeMismatch {
+                snapshot_nodes,
+                tree_nodes,
+            } => write!(
+                f,
+                "snapshot covers {snapshot_nodes} nodes but the tree has {tree_nodes}"
+            ),
+        }
+    }
+}
+
+/// `properties` as a declaration list, `!important` ones last.
+fn declarations(properties: &Properties) -> String {
+    let options = PrinterOptions::default;
+    let normal = properties
+        .normal
+        .iter()
+        .filter_map(|prop| prop.to_css_string(false, options()).ok());
+    let important = properties
+        .important
+        .iter()
+        .filter_map(|prop| prop.to_css_string(true, options()).ok());
+    normal.chain(important).collect::<Vec<_>>().join("; ")
+}
+
+/// Wrap `css` in `media`'s `@media` blocks, outermost first.
+fn in_media(media: &RuleMedia, css: String) -> String {
+    media.lists().iter().rev().fold(css, |css, list| {
+        match list.to_css_string(PrinterOptions::default()) {
+            Ok(query) if !query.is_empty() => format!("@media {query} {{ {css} }}"),
+            _ => css,
+        }
+    })
+}
+
+fn with_indices(mut line: String, indices: &[usize]) -> String {
+    for index in indices {
+        line.push(' ');
+        line.push_str(&index.to_string());
+    }
+    line
+}
+
+fn parse_indices(text: &str) -> Option<Vec<usize>> {
+    text.split_whitespace()
+        .map(|index| index.parse().ok())
+        .collect()
+}
+
+/// Escape backslashes and line breaks so `text` fits on one line.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(character) = chars.next() {
+        if character != '\\' {
+            out.push(character);
+            continue;
+        }
+        out.push(match chars.next()? {
+            '\\' => '\\',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+fn parse_color(text: &str) -> Option<RGBA> {
+    let hex = text.strip_prefix('#').filter(|hex| hex.len() == 8)?;
+    let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+    Some(RGBA {
+        red: channel(0)?,
+        green: channel(2)?,
+        blue: channel(4)?,
+        alpha: channel(6)?,
+    })
+}
+
+fn palette_colors(palette: &ForcedColorsPalette) -> [RGBA; 13] {
+    [
+        palette.canvas,
+        palette.canvas_text,
+        palette.link_text,
+        palette.visited_text,
+        palette.active_text,
+        palette.button_face,
+        palette.button_text,
+        palette.button_border,
+        palette.field,
+        palette.field_text,
+        palette.gray_text,
+        palette.highlight,
+        palette.highlight_text,
+    ]
+}
+
+fn palette_from_colors(colors: [RGBA; 13]) -> ForcedColorsPalette {
+    let [
+        canvas,
+        canvas_text,
+        link_text,
+        visited_text,
+        active_text,
+        button_face,
+        button_text,
+        button_border,
+        field,
+        field_text,
+        gray_text,
+        highlight,
+        highlight_text,
+    ] = colors;
+    ForcedColorsPalette {
+        canvas,
+        canvas_text,
+        link_text,
+        visited_text,
+        active_text,
+        button_face,
+        button_text,
+        button_border,
+        field,
+        field_text,
+        gray_text,
+        highlight,
+        highlight_text,
+    }
+}
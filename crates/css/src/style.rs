@@ -1,23 +1,111 @@
 //! CSS style application - matches selectors against DOM and stores properties.
 
-use crate::ParsedRule;
+use crate::counters::{self, CounterChanges, CounterScopes};
+use crate::presentational::{is_presentation_attribute, presentational_hints};
+use crate::selectors::{
+    AncestorFilter, AncestorFilterCounters, AncestorFilterStats, AttributeReach, Dependency,
+    effective_state, matches_pseudo_element, selector_dependencies,
+};
+use crate::{
+    ForcedColorsPalette, Marker, MediaEnvironment, OpaqueAtRule, PageBox, PageContext,
+    PageDimensions, PageRule, ParsedRule, ParsedStylesheet, Properties, RuleSnapshot, SheetId,
+    SnapshotError, StyleSnapshot,
+};
+use crate::{GeneratedContent, generated, lists, paged};
+use lasso::Spur;
 use lightningcss::declaration::DeclarationBlock;
+use lightningcss::properties::custom::CustomPropertyName;
+use lightningcss::properties::display::{Display, DisplayKeyword};
+use lightningcss::properties::list::ListStylePosition;
 use lightningcss::properties::{Property, PropertyId};
-use lightningcss::stylesheet::ParserOptions;
-use rewrite_core::{NodeId, Specificity, Subscriptions};
-use rewrite_html::{DomTree, NodeData};
-use std::sync::Arc;
+use lightningcss::selector::{Component, PseudoElement};
+use lightningcss::stylesheet::{ParserOptions, PrinterOptions};
+use lightningcss::values::ident::Ident;
+use rewrite_core::{GeneratedBox, NodeId, Origin, Specificity, Subscriptions};
+use rewrite_html::{DomTree, ElementState, Namespace, NodeData};
+use std::collections::{HashMap, HashSet};
+use std::iter;
+use std::mem;
+use std::ops::Deref;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::time::Instant;
 
 /// Minimum specificity to be considered "confident" - at least one class or id.
 const CONFIDENCE_THRESHOLD: Specificity = Specificity::new(0, 1, 0);
 
+/// `@page` rules, each with the replaceable sheet it came from.
+type SheetPageRules = Vec<(Option<SheetId>, PageRule)>;
+
+/// Unknown at-rules, each with the replaceable sheet it came from.
+type SheetOpaqueRules = Vec<(Option<SheetId>, OpaqueAtRule)>;
+
+/// Source position and rule indices of each replaceable sheet.
+type SheetRuleIndices = HashMap<SheetId, (usize, Vec<usize>)>;
+
+/// Specificity each rule applies to one node with, by rule index.
+type RuleSpecificities = HashMap<usize, Specificity>;
+
 /// Holds parsed CSS rules and applies them to the DOM.
 pub struct Styler {
     rules: boxcar::Vec<ParsedRule>,
+    /// Source order of each rule in `rules`, by index, which breaks
+    /// cascade ties. Pushed along with the rule by `push_rule`.
+    rule_orders: boxcar::Vec<SourceOrder>,
     /// Maps each node (by index) to indices of matching rules in `rules`.
     /// Kept in sync with DomTree - a new empty vec is added for each node in style_node.
-    matched_rules: boxcar::Vec<boxcar::Vec<usize>>,
+    /// Locked so attribute changes can drop rules that no longer match.
+    matched_rules: boxcar::Vec<RwLock<Vec<usize>>>,
+    /// Specificity each rule applies to a node with, by node index like
+    /// `matched_rules` and then by rule index. Filled by the cascade on
+    /// first use and cleared when the node is rematched.
+    matched_specificities: boxcar::Vec<RwLock<RuleSpecificities>>,
+    /// Bloom filter of each node's ancestors' tags, ids and classes, by
+    /// node index like `matched_rules`. Rebuilt when a node is appended
+    /// and when an `id` or `class` above it changes.
+    ancestor_filters: boxcar::Vec<RwLock<AncestorFilter>>,
+    /// How often `ancestor_filters` rejected a rule before matching.
+    filter_counters: AncestorFilterCounters,
+    /// Attributes referenced by any stylesheet selector, with how far a
+    /// change to each one can affect matching. Collected in `add_rule`.
+    attribute_deps: RwLock<HashMap<Spur, AttributeReach>>,
+    /// Dynamic state flags referenced by any stylesheet selector, likewise.
+    state_deps: RwLock<HashMap<ElementState, AttributeReach>>,
+    /// How far appending a child can affect its earlier siblings, if any
+    /// selector counts the siblings after its subject.
+    later_sibling_deps: RwLock<Option<AttributeReach>>,
+    /// Inline style and presentational hints of each node, by node index
+    /// like `matched_rules`. They belong to their node rather than being
+    /// matched, so they're kept out of `rules` and replaced in place when
+    /// the node's `style` or presentation attributes change.
+    node_scoped_rules: boxcar::Vec<RwLock<NodeScopedRules>>,
+    /// Rules replaced by `set_user_stylesheet` or `replace_stylesheet`.
+    /// `rules` is append-only, so replaced rules stay in place but are
+    /// never matched again.
+    retired_rules: RwLock<HashSet<usize>>,
+    /// Environment `@media` conditions are evaluated against.
+    media: RwLock<MediaEnvironment>,
+    /// Embedder palette while forced-colors mode is active.
+    forced_colors: RwLock<Option<ForcedColorsPalette>>,
+    /// `@page` rules in source order, with the replaceable sheet each came
+    /// from. They don't match nodes, so they are kept out of `rules`.
+    page_rules: RwLock<SheetPageRules>,
+    /// Unknown at-rules in source order, with the replaceable sheet each
+    /// came from. Kept out of `rules` like `@page` rules.
+    opaque_rules: RwLock<SheetOpaqueRules>,
+    /// Indices in `rules` of each sheet added by `replace_stylesheet`, with
+    /// the source position the sheet took when it was first added. A
+    /// replacement keeps the position, so its rules cascade where the
+    /// sheet sits rather than after every rule added since.
+    sheets: RwLock<SheetRuleIndices>,
+    /// Rules in effect with a `::before`, `::after` or `::marker`
+    /// selector, found on first use and dropped when rules or the media
+    /// environment change.
+    pseudo_rule_sets: RwLock<Option<Arc<PseudoRules>>>,
+    /// Counters in scope at every reading box, found on first use and
+    /// dropped when matches, attributes or the tree change.
+    counter_scopes: RwLock<Option<Arc<CounterScopes>>>,
+    /// The counter scopes `changed_counter_users` last compared against.
+    reported_counter_scopes: RwLock<Option<Arc<CounterScopes>>>,
     tree: Arc<DomTree>,
     subscriptions: Arc<Subscriptions>,
 }
@@ -27,7 +115,24 @@ impl Styler {
     pub fn new(tree: Arc<DomTree>, subscriptions: Arc<Subscriptions>) -> Self {
         Self {
             rules: boxcar::Vec::new(),
+            rule_orders: boxcar::Vec::new(),
             matched_rules: boxcar::Vec::new(),
+            matched_specificities: boxcar::Vec::new(),
+            ancestor_filters: boxcar::Vec::new(),
+            filter_counters: AncestorFilterCounters::default(),
+            attribute_deps: RwLock::new(HashMap::new()),
+            state_deps: RwLock::new(HashMap::new()),
+            later_sibling_deps: RwLock::new(None),
+            node_scoped_rules: boxcar::Vec::new(),
+            retired_rules: RwLock::new(HashSet::new()),
+            media: RwLock::new(MediaEnvironment::default()),
+            forced_colors: RwLock::new(None),
+            page_rules: RwLock::new(Vec::new()),
+            opaque_rules: RwLock::new(Vec::new()),
+            sheets: RwLock::new(HashMap::new()),
+            pseudo_rule_sets: RwLock::new(None),
+            counter_scopes: RwLock::new(None),
+            reported_counter_scopes: RwLock::new(None),
             tree,
             subscriptions,
         }
@@ -35,22 +140,64 @@ impl Styler {
 
     /// Add a rule and apply it to all existing nodes in the tree.
     pub fn add_rule(&self, rule: ParsedRule) {
+        self.insert_rule(rule, None);
+    }
+
+    /// Add a rule from `sheet` (if it belongs to a replaceable one, with
+    /// the rule's source order in it) and apply it. Returns its index in
+    /// `rules`, or `None` for `@page` and unknown at-rules.
+    fn insert_rule(
+        &self,
+        rule: ParsedRule,
+        sheet: Option<(SheetId, SourceOrder)>,
+    ) -> Option<usize> {
+        let order = sheet.map(|(_, order)| order);
+        let sheet = sheet.map(|(sheet, _)| sheet);
+        let rule = match rule {
+            ParsedRule::Page(page_rule) => {
+                self.page_rules
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push((sheet, page_rule));
+                return None;
+            }
+            ParsedRule::Opaque(opaque_rule) => {
+                self.opaque_rules
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push((sheet, opaque_rule));
+                return None;
+            }
+            rule => rule,
+        };
+
         let t0 = Instant::now();
-        let rule_idx = self.rules.count();
         let node_count = self.tree.nodes.count();
-        self.rules.push(rule);
+        self.track_selector_dependencies(&rule);
+        let rule_idx = match order {
+            Some(order) => self.push_rule_at(rule, order),
+            None => self.push_rule(rule),
+        };
 
         let t_match_start = Instant::now();
-        let matching_nodes: Vec<NodeId> = (0..node_count)
-            .map(|idx| NodeId(idx as u32))
-            .filter(|&node_id| self.rules[rule_idx].matches(node_id, &self.tree))
-            .collect();
+        let added = &self.rules[rule_idx];
+        let matching_nodes: Vec<NodeId> = if added.media_matches(self.media_environment()) {
+            (0..node_count)
+                .map(|idx| NodeId(idx as u32))
+                .filter(|&node_id| self.rule_matches(added, node_id))
+                .collect()
+        } else {
+            Vec::new()
+        };
         let t_match_end = Instant::now();
 
         let t_apply_start = Instant::now();
         for &node_id in &matching_nodes {
             self.apply_rule(node_id, rule_idx);
         }
+        if added.media_matches(self.media_environment()) {
+            self.notify_generated_content(rule_idx);
+        }
         let t_apply_end = Instant::now();
 
         let elapsed = t0.elapsed();
@@ -62,27 +209,75 @@ impl Styler {
                 t_apply_end - t_apply_start
             );
         }
+        Some(rule_idx)
     }
 
-    /// Apply all rules to a newly added node, including its inline styles.
+    /// Apply all rules to a newly added node, including its presentational
+    /// hints and inline styles.
     /// Called during CreateNode — the node may not have a parent yet.
     pub fn style_node(&self, node_id: NodeId) {
+        self.invalidate_counters();
         // Ensure storage exists for this node
         while self.matched_rules.count() <= node_id.0 as usize {
-            self.matched_rules.push(boxcar::Vec::new());
+            self.matched_rules.push(RwLock::new(Vec::new()));
+        }
+        while self.matched_specificities.count() <= node_id.0 as usize {
+            self.matched_specificities.push(RwLock::new(HashMap::new()));
+        }
+        while self.node_scoped_rules.count() <= node_id.0 as usize {
+            self.node_scoped_rules
+                .push(RwLock::new(NodeScopedRules::default()));
         }
+        while self.ancestor_filters.count() <= node_id.0 as usize {
+            self.ancestor_filters
+                .push(RwLock::new(AncestorFilter::default()));
+        }
+        self.refresh_ancestor_filter(node_id);
+
+        // Presentational hints are never confident, so `flush` notifies
+        // them along with the stylesheet rules they lose to.
+        self.node_scoped_rules_mut(node_id).presentational =
+            presentational_hints(&self.tree, node_id).map(Arc::new);
 
         // Apply stylesheet rules
-        self.rules
+        let env = self.media_environment();
+        let retired = self.retired_rules();
+        let matching: Vec<usize> = self
+            .rules
             .iter()
-            .filter(|(_, rule)| rule.matches(node_id, &self.tree))
-            .for_each(|(idx, _)| self.apply_rule(node_id, idx));
+            .filter(|(idx, rule)| {
+                !retired.contains(idx)
+                    && rule.media_matches(env)
+                    && self.rule_matches(rule, node_id)
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        drop(retired);
+        for idx in matching {
+            self.apply_rule(node_id, idx);
+        }
 
         // Parse and add inline styles as a rule
         if let Some(rule) = parse_inline_styles(node_id, &self.tree) {
-            let rule_idx = self.rules.count();
-            self.rules.push(rule);
-            self.apply_rule(node_id, rule_idx);
+            let rule = Arc::new(rule);
+            self.node_scoped_rules_mut(node_id).inline = Some(Arc::clone(&rule));
+            let props = rule.properties();
+            let key = |important| node_scoped_key(&rule, important);
+            let node_rules = self.node_rules(node_id);
+            let winners: Vec<&Property<'static>> = props
+                .normal
+                .iter()
+                .map(|prop| (prop, key(false)))
+                .chain(props.important.iter().map(|prop| (prop, key(true))))
+                .filter(|(prop, key)| {
+                    !self.is_dominated(node_id, &node_rules, &prop.property_id(), *key)
+                })
+                .map(|(prop, _)| prop)
+                .collect();
+            drop(node_rules);
+            for prop in winners {
+                self.subscriptions.notify_property(node_id, prop);
+            }
         }
     }
 
@@ -90,18 +285,50 @@ impl Styler {
     /// Called during AppendChild — ancestor-dependent selectors (e.g. `div > p`)
     /// can now match because the node has a parent.
     pub fn restyle_node(&self, node_id: NodeId) {
-        let node_rules = &self.matched_rules[node_id.0 as usize];
-
+        self.invalidate_counters();
+        // An appended subtree sees new ancestors all the way down.
+        self.refresh_ancestor_filters(node_id);
+        let env = self.media_environment();
         for (rule_idx, rule) in self.rules.iter() {
-            // Skip rules already matched for this node
-            if node_rules.iter().any(|(_, &idx)| idx == rule_idx) {
+            // Skip rules already matched for this node, replaced ones, and
+            // ones whose media conditions don't hold
+            if self.node_rules(node_id).contains(&rule_idx)
+                || self.retired_rules().contains(&rule_idx)
+                || !rule.media_matches(env)
+            {
                 continue;
             }
 
-            if rule.matches(node_id, &self.tree) {
+            if self.rule_matches(rule, node_id) {
                 self.apply_rule(node_id, rule_idx);
             }
         }
+        self.rematch_earlier_siblings(node_id);
+    }
+
+    /// Rematch the siblings before a just-appended node whose selectors
+    /// count the siblings after them, such as `:last-child`.
+    fn rematch_earlier_siblings(&self, node_id: NodeId) {
+        let reach = *self
+            .later_sibling_deps
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        match reach {
+            None => {}
+            Some(AttributeReach::Subject) => {
+                for sibling in self.tree.prev_siblings(node_id) {
+                    self.rematch_node(sibling);
+                }
+            }
+            Some(AttributeReach::Relatives) => {
+                for sibling in self.tree.prev_siblings(node_id) {
+                    self.rematch_subtree(sibling);
+                }
+            }
+            Some(AttributeReach::Document) => {
+                self.rematch_reach(node_id, AttributeReach::Document);
+            }
+        }
     }
 
     /// Apply a rule to a node: record the match and notify for winning properties.
@@ -115,22 +342,28 @@ impl Styler {
         static NOTIFY_COUNT: AtomicU64 = AtomicU64::new(0);
 
         let rule = &self.rules[rule_idx];
+        // Confidence only decides when to notify, so it keys off the whole
+        // selector list; the cascade itself uses the matching selector.
         let rule_specificity = rule.specificity();
         let props = rule.properties();
-        let node_rules = &self.matched_rules[node_id.0 as usize];
+        let node_rules = self.node_rules(node_id);
         let is_confident = rule_specificity >= CONFIDENCE_THRESHOLD;
+        let specificity = self.matched_specificity(node_id, rule_idx);
 
         // Check each normal property - notify if confident and not dominated
+        let mut winners: Vec<&Property<'static>> = Vec::new();
         for prop in &props.normal {
             let prop_id = prop.property_id();
             let t0 = Instant::now();
-            let dominated = self.is_dominated(node_rules, &prop_id, rule_specificity, false);
+            let dominated = self.is_dominated(
+                node_id,
+                &node_rules,
+                &prop_id,
+                self.cascade_key(rule_idx, false, specificity),
+            );
             DOMINATED_NS.fetch_add(t0.elapsed().as_nanos() as u64, Ordering::Relaxed);
             if is_confident && !dominated {
-                let t1 = Instant::now();
-                self.subscriptions.notify_property(node_id, prop);
-                NOTIFY_NS.fetch_add(t1.elapsed().as_nanos() as u64, Ordering::Relaxed);
-                NOTIFY_COUNT.fetch_add(1, Ordering::Relaxed);
+                winners.push(prop);
             }
         }
 
@@ -138,21 +371,32 @@ impl Styler {
         for prop in &props.important {
             let prop_id = prop.property_id();
             let t0 = Instant::now();
-            let dominated = self.is_dominated(node_rules, &prop_id, rule_specificity, true);
+            let dominated = self.is_dominated(
+                node_id,
+                &node_rules,
+                &prop_id,
+                self.cascade_key(rule_idx, true, specificity),
+            );
             DOMINATED_NS.fetch_add(t0.elapsed().as_nanos() as u64, Ordering::Relaxed);
             if is_confident && !dominated {
-                let t1 = Instant::now();
-                self.subscriptions.notify_property(node_id, prop);
-                NOTIFY_NS.fetch_add(t1.elapsed().as_nanos() as u64, Ordering::Relaxed);
-                NOTIFY_COUNT.fetch_add(1, Ordering::Relaxed);
+                winners.push(prop);
             }
         }
 
-        // Record the match
+        // Record the match before notifying, so subscribers that read the
+        // cascade see it.
+        drop(node_rules);
         let t0 = Instant::now();
-        self.matched_rules[node_id.0 as usize].push(rule_idx);
+        self.node_rules_mut(node_id).push(rule_idx);
         PUSH_NS.fetch_add(t0.elapsed().as_nanos() as u64, Ordering::Relaxed);
 
+        for prop in winners {
+            let t1 = Instant::now();
+            self.subscriptions.notify_property(node_id, prop);
+            NOTIFY_NS.fetch_add(t1.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            NOTIFY_COUNT.fetch_add(1, Ordering::Relaxed);
+        }
+
         let calls = CALL_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
         if calls % 5000 == 0 {
             eprintln!(
@@ -165,39 +409,40 @@ impl Styler {
         }
     }
 
-    /// Check if there's an existing rule with higher specificity for this property.
+    /// Check if there's an existing rule that outranks a declaration with
+    /// cascade key `key` for this property.
     fn is_dominated(
         &self,
-        node_rules: &boxcar::Vec<usize>,
+        node_id: NodeId,
+        node_rules: &[usize],
         prop_id: &PropertyId<'static>,
-        specificity: Specificity,
-        is_important: bool,
+        key: CascadeKey,
     ) -> bool {
-        node_rules.iter().any(|(_, &idx)| {
-            let existing = &self.rules[idx];
-            let existing_props = existing.properties();
-            let existing_spec = existing.specificity();
-
-            if is_important {
-                // Important property: only dominated by higher-specificity important
-                existing_props.has_important(prop_id)
-                    && existing_spec.with_important(true) > specificity.with_important(true)
-            } else {
-                // Normal property: dominated by higher-specificity normal OR any important
-                (existing_props.has_property(prop_id) && existing_spec > specificity)
-                    || existing_props.has_important(prop_id)
-            }
+        // An important declaration outranks its normal counterpart, so
+        // only the stronger of the two needs checking.
+        let outranks = |props: &Properties, key_of: &dyn Fn(bool) -> CascadeKey| {
+            let important = props.has_important(prop_id);
+            (important || props.has_property(prop_id)) && key_of(important) > key
+        };
+        node_rules.iter().any(|&idx| {
+            outranks(self.rules[idx].properties(), &|important| {
+                self.cascade_key(idx, important, self.matched_specificity(node_id, idx))
+            })
+        }) || self.node_scoped_rules(node_id).iter().any(|rule| {
+            outranks(rule.properties(), &|important| {
+                node_scoped_key(rule, important)
+            })
         })
     }
 
     /// Check if a property is dominated by a confident rule for this node.
     fn is_dominated_by_confident(
         &self,
-        node_rules: &boxcar::Vec<usize>,
+        node_rules: &[usize],
         prop_id: &PropertyId<'static>,
         is_important: bool,
     ) -> bool {
-        node_rules.iter().any(|(_, &idx)| {
+        node_rules.iter().any(|&idx| {
             let existing = &self.rules[idx];
             let existing_props = existing.properties();
             let existing_spec = existing.specificity();
@@ -215,6 +460,1166 @@ impl Styler {
         })
     }
 
+    /// Re-match rules affected by an attribute change on `node_id`.
+    ///
+    /// Only attributes referenced by some selector (or `style`) trigger any
+    /// work; the set of rematched nodes depends on where in the selector the
+    /// attribute appears. Changed properties are re-cascaded and notified,
+    /// including low-confidence ones, since `flush()` has usually already run.
+    pub fn attribute_changed(&self, node_id: NodeId, name: Spur) {
+        // `start`, `reversed` and `value` number list items.
+        self.invalidate_counters();
+        if self.tree.interner.get("style") == Some(name) {
+            self.restyle_inline(node_id);
+        }
+        if let Some(NodeData::Element {
+            tag,
+            namespace: Namespace::Svg,
+            ..
+        }) = self.tree.get_node(node_id)
+            && is_presentation_attribute(
+                self.tree.interner.resolve(tag),
+                self.tree.interner.resolve(&name),
+            )
+        {
+            self.restyle_presentational(node_id);
+        }
+
+        // Descendants summarize this element's id and classes.
+        let interner = &self.tree.interner;
+        if [interner.get("id"), interner.get("class")].contains(&Some(name)) {
+            for child in self.tree.children(node_id) {
+                self.refresh_ancestor_filters(child);
+            }
+        }
+
+        let reach = self
+            .attribute_deps
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&name)
+            .copied();
+        if let Some(reach) = reach {
+            self.rematch_reach(node_id, reach);
+        }
+    }
+
+    /// Set (`enabled`) or clear dynamic state flags of an element (`:hover`,
+    /// `:active`, `:focus`, `:focus-visible`) and rematch only the nodes
+    /// whose matching can change: the element and the ancestors whose
+    /// `:hover`, `:active` or `:focus-within` state flipped, widened to
+    /// their relatives for states a selector uses left of a combinator.
+    pub fn set_element_state(&self, node_id: NodeId, state: ElementState, enabled: bool) {
+        let chain: Vec<NodeId> =
+            iter::successors(Some(node_id), |&node| self.tree.parent(node)).collect();
+        let before: Vec<ElementState> = chain
+            .iter()
+            .map(|&node| effective_state(&self.tree, node))
+            .collect();
+        if !self.tree.set_element_state(node_id, state, enabled) {
+            return;
+        }
+
+        let flipped: Vec<(NodeId, AttributeReach)> = {
+            let deps = self
+                .state_deps
+                .read()
+                .unwrap_or_else(PoisonError::into_inner);
+            chain
+                .iter()
+                .zip(before)
+                .filter_map(|(&node, old)| {
+                    let changed = effective_state(&self.tree, node).symmetric_difference(old);
+                    let reach = changed.iter().filter_map(|flag| deps.get(&flag)).max()?;
+                    Some((node, *reach))
+                })
+                .collect()
+        };
+        if flipped
+            .iter()
+            .any(|&(_, reach)| reach == AttributeReach::Document)
+        {
+            self.rematch_reach(node_id, AttributeReach::Document);
+            return;
+        }
+        for (node, reach) in flipped {
+            self.rematch_reach(node, reach);
+        }
+    }
+
+    /// Rematch the nodes a change on `node_id` can affect, given how far
+    /// selectors let it reach.
+    fn rematch_reach(&self, node_id: NodeId, reach: AttributeReach) {
+        match reach {
+            AttributeReach::Subject => self.rematch_node(node_id),
+            AttributeReach::Relatives => {
+                self.rematch_subtree(node_id);
+                for sibling in self.tree.next_siblings(node_id) {
+                    self.rematch_subtree(sibling);
+                }
+            }
+            AttributeReach::Document => {
+                for node_idx in 0..self.matched_rules.count() {
+                    self.rematch_node(NodeId(node_idx as u32));
+                }
+            }
+        }
+    }
+
+    /// Record the attributes and state flags a stylesheet rule's selectors
+    /// depend on.
+    fn track_selector_dependencies(&self, rule: &ParsedRule) {
+        let ParsedRule::Stylesheet { selectors, .. } = rule else {
+            return;
+        };
+        let mut attribute_deps = self
+            .attribute_deps
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut state_deps = self
+            .state_deps
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut later_sibling_deps = self
+            .later_sibling_deps
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        selector_dependencies(selectors, &mut |dependency, reach| {
+            let entry = match dependency {
+                Dependency::Attribute(name) => {
+                    let key = self.tree.interner.get_or_intern(name);
+                    attribute_deps.entry(key).or_insert(reach)
+                }
+                Dependency::State(flag) => state_deps.entry(flag).or_insert(reach),
+                Dependency::LaterSiblings => later_sibling_deps.get_or_insert(reach),
+            };
+            *entry = (*entry).max(reach);
+        });
+    }
+
+    fn rematch_subtree(&self, node_id: NodeId) {
+        let mut stack = vec![node_id];
+        while let Some(node) = stack.pop() {
+            self.rematch_node(node);
+            stack.extend(self.tree.children(node));
+        }
+    }
+
+    /// Re-run selector matching for one node, then re-cascade every property
+    /// touched by a rule that started or stopped matching.
+    fn rematch_node(&self, node_id: NodeId) {
+        if node_id.0 as usize >= self.matched_rules.count() {
+            return;
+        }
+        // A selector list can match through a different selector now.
+        self.node_specificities_mut(node_id).clear();
+        let env = self.media_environment();
+        let mut affected: Vec<PropertyId<'static>> = Vec::new();
+        {
+            let retired = self.retired_rules();
+            let mut node_rules = self.node_rules_mut(node_id);
+            for (rule_idx, rule) in &self.rules {
+                if rule.is_node_scoped() || retired.contains(&rule_idx) {
+                    continue;
+                }
+                let was_matched = node_rules.contains(&rule_idx);
+                let matches = rule.media_matches(env) && self.rule_matches(rule, node_id);
+                if was_matched == matches {
+                    continue;
+                }
+                if was_matched {
+                    node_rules.retain(|&idx| idx != rule_idx);
+                } else {
+                    node_rules.push(rule_idx);
+                }
+                collect_property_ids(rule.properties(), &mut affected);
+            }
+        }
+        self.recascade(node_id, &affected);
+    }
+
+    /// Replace the inline style rule of a node after its `style` attribute changed.
+    fn restyle_inline(&self, node_id: NodeId) {
+        let rule = parse_inline_styles(node_id, &self.tree);
+        self.replace_node_rule(node_id, rule, |rules| &mut rules.inline);
+    }
+
+    /// Rebuild the presentational hints of an SVG element after one of its
+    /// presentation attributes changed.
+    fn restyle_presentational(&self, node_id: NodeId) {
+        let rule = presentational_hints(&self.tree, node_id);
+        self.replace_node_rule(node_id, rule, |rules| &mut rules.presentational);
+    }
+
+    /// Swap the node-scoped rule in the slot `slot` selects for `rule`,
+    /// and re-cascade the properties either declares.
+    fn replace_node_rule(
+        &self,
+        node_id: NodeId,
+        rule: Option<ParsedRule>,
+        slot: impl Fn(&mut NodeScopedRules) -> &mut Option<Arc<ParsedRule>>,
+    ) {
+        if node_id.0 as usize >= self.node_scoped_rules.count() {
+            return;
+        }
+        let mut affected: Vec<PropertyId<'static>> = Vec::new();
+        let new = rule.map(Arc::new);
+        let old = mem::replace(slot(&mut self.node_scoped_rules_mut(node_id)), new.clone());
+        for scoped in old.iter().chain(&new) {
+            collect_property_ids(scoped.properties(), &mut affected);
+        }
+        self.invalidate_counters();
+        self.recascade(node_id, &affected);
+    }
+
+    /// Notify the cascade winner for each property, or its removal if no
+    /// matched rule declares it any more.
+    fn recascade(&self, node_id: NodeId, prop_ids: &[PropertyId<'static>]) {
+        for prop_id in prop_ids {
+            match self.cascade_winner(node_id, prop_id) {
+                Some(winner) => self.subscriptions.notify_property(node_id, &winner),
+                None => self.subscriptions.notify_property_removed(node_id, prop_id),
+            }
+        }
+    }
+
+    /// Replace the user-origin stylesheet.
+    ///
+    /// Rules from a previous call stop applying, `rules` are added with
+    /// `Origin::User` (whatever origin they were parsed with), and every
+    /// property they touched is re-cascaded. User rules rank above the UA
+    /// sheet and below author rules for normal declarations; `!important`
+    /// user declarations beat everything except `!important` UA ones.
+    pub fn set_user_stylesheet(&self, rules: Vec<ParsedRule>) {
+        let old_rules: Vec<usize> = {
+            let retired = self.retired_rules();
+            self.rules
+                .iter()
+                .filter(|(idx, rule)| rule.origin() == Origin::User && !retired.contains(idx))
+                .map(|(idx, _)| idx)
+                .collect()
+        };
+        self.retire_rules(&old_rules);
+        self.page_rules
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|(_, page_rule)| page_rule.origin != Origin::User);
+
+        for rule in rules {
+            let rule = match rule {
+                ParsedRule::Stylesheet {
+                    selectors,
+                    properties,
+                    media,
+                    structural,
+                    ancestors,
+                    ..
+                } => ParsedRule::Stylesheet {
+                    selectors,
+                    properties,
+                    origin: Origin::User,
+                    media,
+                    structural,
+                    ancestors,
+                },
+                ParsedRule::Page(page_rule) => ParsedRule::Page(PageRule {
+                    origin: Origin::User,
+                    ..page_rule
+                }),
+                ParsedRule::Inline { .. }
+                | ParsedRule::Presentational { .. }
+                | ParsedRule::Opaque(_) => continue,
+            };
+            self.add_rule(rule);
+        }
+
+        // Low-confidence user rules only notify on flush.
+        self.flush();
+    }
+
+    /// Append `rule` after every rule added so far in source order, and
+    /// return its index.
+    fn push_rule(&self, rule: ParsedRule) -> usize {
+        let order = if matches!(rule, ParsedRule::Presentational { .. }) {
+            (0, 0)
+        } else {
+            (self.rule_orders.count() + 1, 0)
+        };
+        self.push_rule_at(rule, order)
+    }
+
+    /// Append `rule` at `order` in source order, and return its index.
+    fn push_rule_at(&self, rule: ParsedRule, order: SourceOrder) -> usize {
+        self.invalidate_pseudo_rules();
+        self.rule_orders.push(order);
+        self.rules.push(rule)
+    }
+
+    /// Cascade key of a declaration of the rule at `rule_idx` applying to
+    /// a node with `specificity` (see `matched_specificity`).
+    fn cascade_key(
+        &self,
+        rule_idx: usize,
+        important: bool,
+        specificity: Specificity,
+    ) -> CascadeKey {
+        (
+            self.rules[rule_idx].origin().precedence(important),
+            specificity,
+            self.rule_orders[rule_idx],
+        )
+    }
+
+    /// The specificity the rule at `rule_idx` applies to `node_id` with,
+    /// from the node's cache if it's been worked out since the node was
+    /// last rematched.
+    fn matched_specificity(&self, node_id: NodeId, rule_idx: usize) -> Specificity {
+        let Some(cache) = self.matched_specificities.get(node_id.0 as usize) else {
+            return self.rules[rule_idx].matched_specificity(node_id, &self.tree);
+        };
+        let cached = cache
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&rule_idx)
+            .copied();
+        cached.unwrap_or_else(|| {
+            let specificity = self.rules[rule_idx].matched_specificity(node_id, &self.tree);
+            cache
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(rule_idx, specificity);
+            specificity
+        })
+    }
+
+    /// Replace the rules of a replaceable stylesheet, e.g. with the result
+    /// of an off-thread parse (`StylesheetWorker`). Rules from the sheet's
+    /// previous contents stop applying and every property they or the new
+    /// rules touch is re-cascaded. Inline and presentational rules are
+    /// ignored.
+    ///
+    /// The new rules take the source position of the sheet's previous
+    /// contents, or follow every rule added so far if it had none.
+    pub fn replace_stylesheet(&self, sheet: SheetId, rules: Vec<ParsedRule>) {
+        let old = self
+            .sheets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&sheet);
+        let position = old
+            .as_ref()
+            .map_or(self.rule_orders.count() + 1, |(position, _)| *position);
+        if let Some((_, old_rules)) = old {
+            self.retire_rules(&old_rules);
+        }
+        self.page_rules
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|(owner, _)| *owner != Some(sheet));
+        self.opaque_rules
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|(owner, _)| *owner != Some(sheet));
+
+        let indices: Vec<usize> = rules
+            .into_iter()
+            .filter(|rule| !rule.is_node_scoped())
+            .enumerate()
+            .filter_map(|(index, rule)| self.insert_rule(rule, Some((sheet, (position, index)))))
+            .collect();
+        if !indices.is_empty() {
+            self.sheets
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(sheet, (position, indices));
+        }
+
+        self.flush();
+    }
+
+    /// Apply a stylesheet parsed off-thread.
+    pub fn apply_parsed_stylesheet(&self, parsed: ParsedStylesheet) {
+        self.replace_stylesheet(parsed.sheet, parsed.rules);
+    }
+
+    /// Remove a replaceable stylesheet's rules.
+    pub fn remove_stylesheet(&self, sheet: SheetId) {
+        self.replace_stylesheet(sheet, Vec::new());
+    }
+
+    /// Stop matching the rules at `old_rules` and drop them from every node
+    /// that matched them. `rules` is append-only, so they stay in place.
+    fn retire_rules(&self, old_rules: &[usize]) {
+        if old_rules.is_empty() {
+            return;
+        }
+        self.retired_rules
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(old_rules.iter().copied());
+        self.invalidate_pseudo_rules();
+
+        for node_idx in 0..self.matched_rules.count() {
+            let node_id = NodeId(node_idx as u32);
+            let mut affected: Vec<PropertyId<'static>> = Vec::new();
+            self.node_rules_mut(node_id).retain(|idx| {
+                let is_old = old_rules.contains(idx);
+                if is_old {
+                    collect_property_ids(self.rules[*idx].properties(), &mut affected);
+                }
+                !is_old
+            });
+            self.node_specificities_mut(node_id)
+                .retain(|idx, _| !old_rules.contains(idx));
+            self.recascade(node_id, &affected);
+        }
+        for &rule_idx in old_rules {
+            self.notify_generated_content(rule_idx);
+        }
+    }
+
+    /// The environment `@media` conditions are currently evaluated against.
+    pub fn media_environment(&self) -> MediaEnvironment {
+        *self.media.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Change the media environment and rematch every node, so rules in
+    /// `@media` blocks start or stop applying. When no rule's conditions
+    /// change outcome, e.g. on a resize or orientation change no `@media`
+    /// block tests, nothing is rematched.
+    pub fn set_media_environment(&self, env: MediaEnvironment) {
+        let old = {
+            let mut media = self.media.write().unwrap_or_else(PoisonError::into_inner);
+            if *media == env {
+                return;
+            }
+            mem::replace(&mut *media, env)
+        };
+        let changed: Vec<usize> = self
+            .rules
+            .iter()
+            .filter(|(_, rule)| rule.media_matches(old) != rule.media_matches(env))
+            .map(|(rule_idx, _)| rule_idx)
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        self.invalidate_pseudo_rules();
+        for node_idx in 0..self.matched_rules.count() {
+            self.rematch_node(NodeId(node_idx as u32));
+        }
+        for rule_idx in changed {
+            self.notify_generated_content(rule_idx);
+        }
+    }
+
+    /// Page box for `page`, cascaded from the `@page` rules that apply to
+    /// print media. `default_size` is used when no rule sets `size`.
+    pub fn page_box(&self, page: PageContext, default_size: PageDimensions) -> PageBox {
+        let env = MediaEnvironment {
+            print: true,
+            ..self.media_environment()
+        };
+        let page_rules = self
+            .page_rules
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        paged::page_box(
+            page_rules
+                .iter()
+                .map(|(_, page_rule)| page_rule)
+                .filter(|page_rule| page_rule.media.matches(env)),
+            page,
+            default_size,
+        )
+    }
+
+    /// Unknown at-rules of every stylesheet, in the order they were added.
+    pub fn opaque_at_rules(&self) -> Vec<OpaqueAtRule> {
+        self.opaque_rules
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(_, opaque_rule)| opaque_rule.clone())
+            .collect()
+    }
+
+    /// The `::marker` box of a list item, or `None` if `node_id` isn't
+    /// `display: list-item` or its marker is suppressed (`content: none`,
+    /// `list-style-type: none`).
+    pub fn marker(&self, node_id: NodeId) -> Option<Marker> {
+        if (node_id.0 as usize) >= self.matched_rules.count() || !self.is_list_item(node_id) {
+            return None;
+        }
+        let rules = self.pseudo_rules();
+        let winners: Vec<&Property<'static>> = self
+            .pseudo_winners(node_id, &PseudoElement::Marker, &rules.marker)
+            .into_iter()
+            .filter(|prop| lists::applies_to_marker(prop))
+            .collect();
+
+        let list_style_type = self
+            .inherited_winner(node_id, &PropertyId::ListStyleType)
+            .and_then(|prop| prop.value_to_css_string(PrinterOptions::default()).ok())
+            .unwrap_or_else(|| "disc".to_owned());
+        let content = winners.iter().find_map(|prop| lists::content_tokens(prop));
+        let text = lists::marker_text(
+            content,
+            &|name| self.counter_values(node_id, None, name),
+            &list_style_type,
+        )?;
+        let position = match self
+            .inherited_winner(node_id, &PropertyId::ListStylePosition)
+            .as_deref()
+        {
+            Some(Property::ListStylePosition(position)) => *position,
+            _ => ListStylePosition::Outside,
+        };
+        Some(Marker {
+            text,
+            position,
+            properties: winners
+                .into_iter()
+                .filter(|prop| lists::content_tokens(prop).is_none())
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// The `::before` or `::after` box of an element, or `None` if its
+    /// `content` is `none` or `normal` or either it or the element is
+    /// `display: none`.
+    pub fn generated_content(
+        &self,
+        node_id: NodeId,
+        pseudo: GeneratedBox,
+    ) -> Option<GeneratedContent> {
+        if (node_id.0 as usize) >= self.matched_rules.count() || !self.counts(node_id) {
+            return None;
+        }
+        let rules = self.pseudo_rules();
+        let winners =
+            self.pseudo_winners(node_id, &pseudo_element(pseudo), rules.generating(pseudo));
+        let content = generated::generated_content_tokens(&winners)?;
+        let text = lists::content_text(
+            content,
+            &|name| self.counter_values(node_id, Some(pseudo), name),
+            &|name| lists::attribute(&self.tree, node_id, name).map(|value| value.to_string()),
+        )?;
+        Some(GeneratedContent {
+            text,
+            properties: winners
+                .into_iter()
+                .filter(|prop| lists::content_tokens(prop).is_none())
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Values of every instance of the counter `name` in scope on
+    /// `node_id`, or on its generated box, outermost first.
+    fn counter_values(
+        &self,
+        node_id: NodeId,
+        pseudo: Option<GeneratedBox>,
+        name: &str,
+    ) -> Vec<i32> {
+        self.counter_scopes().values((node_id, pseudo), name)
+    }
+
+    /// Elements whose marker or generated boxes read different counters
+    /// than at the previous call, or that started or stopped reading them,
+    /// by node ID. Every element that reads counters on the first call.
+    pub fn changed_counter_users(&self) -> Vec<NodeId> {
+        let scopes = self.counter_scopes();
+        let old = self
+            .reported_counter_scopes
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .replace(Arc::clone(&scopes));
+        if old.as_ref().is_some_and(|old| Arc::ptr_eq(old, &scopes)) {
+            return Vec::new();
+        }
+        scopes.changed_users(old.as_deref())
+    }
+
+    /// The counters in scope at every reading box, walking the document
+    /// if they were dropped since the last walk.
+    fn counter_scopes(&self) -> Arc<CounterScopes> {
+        if let Some(scopes) = self
+            .counter_scopes
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            return Arc::clone(scopes);
+        }
+        let rules = self.pseudo_rules();
+        let is_item = |node: NodeId| self.counts(node) && self.is_list_item(node);
+        let changes = |node: NodeId, at: Option<GeneratedBox>| -> Option<CounterChanges> {
+            let Some(pseudo) = at else {
+                if !self.counts(node) {
+                    return None;
+                }
+                let winners: Vec<_> = COUNTER_PROPERTIES
+                    .iter()
+                    .map(|&(name, _)| self.cascade_winner(node, &counter_property(name)))
+                    .collect();
+                let winners: Vec<_> = winners.iter().map(Option::as_deref).collect();
+                let mut changes = counter_changes(&winners);
+                lists::add_list_item_changes(&self.tree, node, &is_item, &mut changes);
+                // A list item's marker reads counters.
+                changes.reads = self.is_list_item(node);
+                return Some(changes);
+            };
+            let candidates = rules.generating(pseudo);
+            if candidates.is_empty() {
+                return None;
+            }
+            let winners = self.pseudo_winners(node, &pseudo_element(pseudo), candidates);
+            generated::generated_content_tokens(&winners)?;
+            let counter_winners: Vec<_> = COUNTER_PROPERTIES
+                .iter()
+                .map(|&(name, _)| {
+                    let prop_id = counter_property(name);
+                    winners
+                        .iter()
+                        .find(|prop| prop.property_id() == prop_id)
+                        .copied()
+                })
+                .collect();
+            Some(CounterChanges {
+                reads: true,
+                ..counter_changes(&counter_winners)
+            })
+        };
+        let scopes = Arc::new(CounterScopes::compute(&self.tree, &changes));
+        *self
+            .counter_scopes
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Arc::clone(&scopes));
+        scopes
+    }
+
+    /// Drop the counter scopes, after a change to matches, attributes or
+    /// the tree.
+    fn invalidate_counters(&self) {
+        *self
+            .counter_scopes
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Whether `node_id` is styled and not `display: none`, so that it and
+    /// its generated boxes count.
+    fn counts(&self, node_id: NodeId) -> bool {
+        (node_id.0 as usize) < self.matched_rules.count()
+            && !matches!(
+                self.cascade_winner(node_id, &PropertyId::Display)
+                    .as_deref(),
+                Some(Property::Display(Display::Keyword(DisplayKeyword::None)))
+            )
+    }
+
+    /// Whether `node_id` is `display: list-item`.
+    fn is_list_item(&self, node_id: NodeId) -> bool {
+        matches!(
+            self.cascade_winner(node_id, &PropertyId::Display).as_deref(),
+            Some(Property::Display(Display::Pair(pair))) if pair.is_list_item
+        )
+    }
+
+    /// Rules in effect with a selector for a generated box, finding them
+    /// if they were dropped since they were last found.
+    fn pseudo_rules(&self) -> Arc<PseudoRules> {
+        if let Some(rules) = self
+            .pseudo_rule_sets
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            return Arc::clone(rules);
+        }
+        let env = self.media_environment();
+        let retired = self.retired_rules();
+        let mut rules = PseudoRules::default();
+        for (rule_idx, rule) in &self.rules {
+            if retired.contains(&rule_idx) || !rule.media_matches(env) {
+                continue;
+            }
+            for (pseudo, indices) in [
+                (PseudoElement::Before, &mut rules.before),
+                (PseudoElement::After, &mut rules.after),
+                (PseudoElement::Marker, &mut rules.marker),
+            ] {
+                if selects_pseudo_element(rule, &pseudo) {
+                    indices.push(rule_idx);
+                }
+            }
+        }
+        drop(retired);
+        let rules = Arc::new(rules);
+        *self
+            .pseudo_rule_sets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Arc::clone(&rules));
+        rules
+    }
+
+    /// Drop the rules for generated boxes, and the counters they change,
+    /// after rules were added or retired or the media environment changed.
+    fn invalidate_pseudo_rules(&self) {
+        *self
+            .pseudo_rule_sets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+        self.invalidate_counters();
+    }
+
+    /// Notify subscribers that the generated boxes of every element the
+    /// rule at `rule_idx` selects a `::before`, `::after` or `::marker` of
+    /// may have changed.
+    fn notify_generated_content(&self, rule_idx: usize) {
+        let rule = &self.rules[rule_idx];
+        let ParsedRule::Stylesheet { selectors, .. } = rule else {
+            return;
+        };
+        let pseudos: Vec<PseudoElement<'static>> = [
+            PseudoElement::Before,
+            PseudoElement::After,
+            PseudoElement::Marker,
+        ]
+        .into_iter()
+        .filter(|pseudo| selects_pseudo_element(rule, pseudo))
+        .collect();
+        if pseudos.is_empty() {
+            return;
+        }
+        for node_idx in 0..self.matched_rules.count() {
+            let node_id = NodeId(node_idx as u32);
+            if pseudos
+                .iter()
+                .any(|pseudo| matches_pseudo_element(&self.tree, node_id, selectors, pseudo))
+            {
+                self.subscriptions.notify_generated_content(node_id);
+            }
+        }
+    }
+
+    /// The winning declaration of each property the `candidates` rules
+    /// that select `pseudo` of `node_id` declare.
+    fn pseudo_winners(
+        &self,
+        node_id: NodeId,
+        pseudo: &PseudoElement<'static>,
+        candidates: &[usize],
+    ) -> Vec<&Property<'static>> {
+        let mut winners: Vec<(CascadeKey, &Property<'static>)> = Vec::new();
+        for &rule_idx in candidates {
+            let rule = &self.rules[rule_idx];
+            let ParsedRule::Stylesheet { selectors, .. } = rule else {
+                continue;
+            };
+            if !matches_pseudo_element(&self.tree, node_id, selectors, pseudo) {
+                continue;
+            }
+            let props = rule.properties();
+            let specificity = self.matched_specificity(node_id, rule_idx);
+            let (important, normal) = (
+                self.cascade_key(rule_idx, true, specificity),
+                self.cascade_key(rule_idx, false, specificity),
+            );
+            let declarations = props
+                .important
+                .iter()
+                .map(|prop| (important, prop))
+                .chain(props.normal.iter().map(|prop| (normal, prop)));
+            for (key, prop) in declarations {
+                let prop_id = prop.property_id();
+                match winners
+                    .iter_mut()
+                    .find(|(_, winner)| winner.property_id() == prop_id)
+                {
+                    Some(entry) if key >= entry.0 => *entry = (key, prop),
+                    Some(_) => {}
+                    None => winners.push((key, prop)),
+                }
+            }
+        }
+        winners.into_iter().map(|(_, prop)| prop).collect()
+    }
+
+    /// The cascaded value of an inherited property: the winner on
+    /// `node_id` or its nearest ancestor that declares it.
+    fn inherited_winner(
+        &self,
+        node_id: NodeId,
+        prop_id: &PropertyId<'static>,
+    ) -> Option<Declaration<'_>> {
+        iter::successors(Some(node_id), |&node| self.tree.parent(node))
+            .filter(|node| (node.0 as usize) < self.matched_rules.count())
+            .find_map(|node| self.cascade_winner(node, prop_id))
+    }
+
+    /// Palette used for forced-colors mode, if it is active.
+    pub fn forced_colors(&self) -> Option<ForcedColorsPalette> {
+        *self
+            .forced_colors
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Enter (`Some`) or leave (`None`) forced-colors mode. Colors are
+    /// replaced at used-value time (see `used_color`); the only cascade
+    /// change is the `forced-colors` media feature.
+    pub fn set_forced_colors(&self, palette: Option<ForcedColorsPalette>) {
+        *self
+            .forced_colors
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = palette;
+        let mut env = self.media_environment();
+        env.forced_colors = palette.is_some();
+        self.set_media_environment(env);
+    }
+
+    /// Capture the Styler's rules, matches and environment; see
+    /// `StyleSnapshot`.
+    pub fn snapshot(&self) -> StyleSnapshot {
+        let mut retired: Vec<usize> = self.retired_rules().iter().copied().collect();
+        retired.sort_unstable();
+        let mut sheets: Vec<_> = self
+            .sheets
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(sheet, (position, indices))| (*sheet, *position, indices.clone()))
+            .collect();
+        sheets.sort_unstable_by_key(|(sheet, ..)| sheet.0);
+        let mut at_rules: Vec<_> = self
+            .page_rules
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(sheet, page_rule)| {
+                let rule = ParsedRule::Page(page_rule.clone());
+                (*sheet, RuleSnapshot::capture(&rule))
+            })
+            .collect();
+        at_rules.extend(
+            self.opaque_rules
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .map(|(sheet, opaque_rule)| {
+                    let rule = ParsedRule::Opaque(opaque_rule.clone());
+                    (*sheet, RuleSnapshot::capture(&rule))
+                }),
+        );
+
+        // Node-scoped rules follow the matched ones, and are listed first
+        // (presentational hints) or last (inline styles) among their
+        // node's matches.
+        let mut rules: Vec<RuleSnapshot> = self
+            .rules
+            .iter()
+            .map(|(_, rule)| RuleSnapshot::capture(rule))
+            .collect();
+        let mut matched = Vec::new();
+        for node_idx in 0..self.matched_rules.count() {
+            let node_id = NodeId(node_idx as u32);
+            let scoped = self.node_scoped_rules(node_id);
+            let mut capture = |rule: &ParsedRule| {
+                rules.push(RuleSnapshot::capture(rule));
+                rules.len() - 1
+            };
+            let mut indices: Vec<usize> = scoped
+                .presentational
+                .iter()
+                .map(|rule| capture(rule))
+                .collect();
+            indices.extend(self.node_rules(node_id).iter());
+            indices.extend(scoped.inline.iter().map(|rule| capture(rule)));
+            matched.push(indices);
+        }
+
+        StyleSnapshot {
+            rules,
+            retired,
+            sheets,
+            at_rules,
+            matched,
+            media: self.media_environment(),
+            forced_colors: self.forced_colors(),
+        }
+    }
+
+    /// Rebuild a Styler from `snapshot` over `tree`, which must be built
+    /// from the document the snapshot was taken against. Matches are taken
+    /// from the snapshot as-is, then the cascade winner of every matched
+    /// property is notified, as `flush` would.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first rule that doesn't reparse, an index past the end
+    /// of the rule list, or a tree with fewer nodes than the snapshot.
+    pub fn restore(
+        tree: Arc<DomTree>,
+        subscriptions: Arc<Subscriptions>,
+        snapshot: &StyleSnapshot,
+    ) -> Result<Self, SnapshotError> {
+        snapshot.validate(tree.nodes.count())?;
+        let styler = Self::new(tree, subscriptions);
+        let sheet_orders: HashMap<usize, SourceOrder> = snapshot
+            .sheets
+            .iter()
+            .flat_map(|(_, position, indices)| {
+                indices
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &rule_idx)| (rule_idx, (*position, index)))
+            })
+            .collect();
+        // Node-scoped rules go back to their nodes rather than into
+        // `rules`, so the other indices are mapped past them.
+        let mut indices: Vec<Option<usize>> = Vec::new();
+        let mut scoped: HashMap<NodeId, NodeScopedRules> = HashMap::new();
+        for (rule_idx, rule) in snapshot.rules.iter().enumerate() {
+            let rule = rule.parse()?;
+            if let ParsedRule::Inline { node_id, .. } | ParsedRule::Presentational { node_id, .. } =
+                rule
+            {
+                let rules = scoped.entry(node_id).or_default();
+                let slot = if matches!(rule, ParsedRule::Inline { .. }) {
+                    &mut rules.inline
+                } else {
+                    &mut rules.presentational
+                };
+                if !snapshot.retired.contains(&rule_idx) {
+                    *slot = Some(Arc::new(rule));
+                }
+                indices.push(None);
+                continue;
+            }
+            styler.track_selector_dependencies(&rule);
+            indices.push(Some(match sheet_orders.get(&rule_idx) {
+                Some(&order) => styler.push_rule_at(rule, order),
+                None => styler.push_rule(rule),
+            }));
+        }
+        let map = |snapshot_indices: &[usize]| -> Vec<usize> {
+            snapshot_indices
+                .iter()
+                .filter_map(|&rule_idx| indices[rule_idx])
+                .collect()
+        };
+        for (sheet, at_rule) in &snapshot.at_rules {
+            match at_rule.parse()? {
+                ParsedRule::Page(page_rule) => styler
+                    .page_rules
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push((*sheet, page_rule)),
+                ParsedRule::Opaque(opaque_rule) => styler
+                    .opaque_rules
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push((*sheet, opaque_rule)),
+                _ => return Err(SnapshotError::InvalidRule(at_rule.css.clone())),
+            }
+        }
+        styler
+            .retired_rules
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(map(&snapshot.retired));
+        styler
+            .sheets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(
+                snapshot
+                    .sheets
+                    .iter()
+                    .map(|(sheet, position, sheet_rules)| (*sheet, (*position, map(sheet_rules)))),
+            );
+        *styler.media.write().unwrap_or_else(PoisonError::into_inner) = snapshot.media;
+        *styler
+            .forced_colors
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = snapshot.forced_colors;
+
+        for (node_idx, matched) in snapshot.matched.iter().enumerate() {
+            let node_id = NodeId(node_idx as u32);
+            let matched = map(matched);
+            let node_scoped = scoped.remove(&node_id).unwrap_or_default();
+            let mut prop_ids: Vec<PropertyId<'static>> = Vec::new();
+            for &rule_idx in &matched {
+                collect_property_ids(styler.rules[rule_idx].properties(), &mut prop_ids);
+            }
+            for rule in node_scoped.iter() {
+                collect_property_ids(rule.properties(), &mut prop_ids);
+            }
+            styler.matched_rules.push(RwLock::new(matched));
+            styler
+                .matched_specificities
+                .push(RwLock::new(HashMap::new()));
+            styler.node_scoped_rules.push(RwLock::new(node_scoped));
+            styler.recascade(node_id, &prop_ids);
+        }
+
+        // Later matching needs the ancestor filters the snapshot leaves out.
+        for _ in 0..styler.matched_rules.count() {
+            styler
+                .ancestor_filters
+                .push(RwLock::new(AncestorFilter::default()));
+        }
+        for node_idx in 0..styler.matched_rules.count() {
+            let node_id = NodeId(node_idx as u32);
+            if styler.tree.parent(node_id).is_none() {
+                styler.refresh_ancestor_filters(node_id);
+            }
+        }
+        Ok(styler)
+    }
+
+    fn retired_rules(&self) -> RwLockReadGuard<'_, HashSet<usize>> {
+        self.retired_rules
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether `rule` matches `node_id`, rejecting rules whose ancestor
+    /// requirements aren't in the node's ancestor filter without running
+    /// the matcher.
+    fn rule_matches(&self, rule: &ParsedRule, node_id: NodeId) -> bool {
+        let ParsedRule::Stylesheet { ancestors, .. } = rule else {
+            return rule.matches(node_id, &self.tree);
+        };
+        if ancestors.is_trivial() {
+            return rule.matches(node_id, &self.tree);
+        }
+        let admitted = ancestors.admits(&self.ancestor_filter(node_id));
+        let matched = admitted && rule.matches(node_id, &self.tree);
+        self.filter_counters.record(admitted, matched);
+        matched
+    }
+
+    /// The ancestor filter of `node_id`. Empty for nodes without storage,
+    /// which admits only rules without ancestor requirements.
+    fn ancestor_filter(&self, node_id: NodeId) -> AncestorFilter {
+        self.ancestor_filters
+            .get(node_id.0 as usize)
+            .map(|filter| *filter.read().unwrap_or_else(PoisonError::into_inner))
+            .unwrap_or_default()
+    }
+
+    /// Rebuild the ancestor filter of `node_id` from its parent's.
+    fn refresh_ancestor_filter(&self, node_id: NodeId) {
+        let Some(slot) = self.ancestor_filters.get(node_id.0 as usize) else {
+            return;
+        };
+        let filter = self
+            .tree
+            .parent(node_id)
+            .map_or_else(AncestorFilter::default, |parent| {
+                AncestorFilter::for_children_of(&self.tree, parent, self.ancestor_filter(parent))
+            });
+        *slot.write().unwrap_or_else(PoisonError::into_inner) = filter;
+    }
+
+    /// Rebuild the ancestor filters of `node_id` and its descendants,
+    /// parents before children.
+    fn refresh_ancestor_filters(&self, node_id: NodeId) {
+        let mut stack = vec![node_id];
+        while let Some(node) = stack.pop() {
+            self.refresh_ancestor_filter(node);
+            stack.extend(self.tree.children(node));
+        }
+    }
+
+    /// Ancestor filter decisions since the last
+    /// `reset_ancestor_filter_stats`.
+    pub fn ancestor_filter_stats(&self) -> AncestorFilterStats {
+        self.filter_counters.snapshot()
+    }
+
+    /// Zero the ancestor filter counters.
+    pub fn reset_ancestor_filter_stats(&self) {
+        self.filter_counters.reset();
+    }
+
+    fn node_rules(&self, node_id: NodeId) -> RwLockReadGuard<'_, Vec<usize>> {
+        self.matched_rules[node_id.0 as usize]
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn node_rules_mut(&self, node_id: NodeId) -> RwLockWriteGuard<'_, Vec<usize>> {
+        self.invalidate_counters();
+        self.matched_rules[node_id.0 as usize]
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The inline style and presentational hints of `node_id`, none for a
+    /// node that hasn't been styled.
+    fn node_scoped_rules(&self, node_id: NodeId) -> NodeScopedRules {
+        self.node_scoped_rules
+            .get(node_id.0 as usize)
+            .map_or_default(|rules| rules.read().unwrap_or_else(PoisonError::into_inner).clone())
+    }
+
+    fn node_scoped_rules_mut(&self, node_id: NodeId) -> RwLockWriteGuard<'_, NodeScopedRules> {
+        self.node_scoped_rules[node_id.0 as usize]
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn node_specificities_mut(&self, node_id: NodeId) -> RwLockWriteGuard<'_, RuleSpecificities> {
+        self.matched_specificities[node_id.0 as usize]
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Number of nodes the Styler has match storage for. Every DOM node
+    /// should be covered once it has been passed to `style_node`.
+    pub fn styled_node_count(&self) -> usize {
+        self.matched_rules.count()
+    }
+
+    /// Indices of the stylesheet rules recorded as matching `node_id`, and
+    /// of those that match it when checked again from scratch, both
+    /// ascending. Inline styles and presentational hints belong to their
+    /// node rather than being matched, so neither list includes them. The
+    /// lists differ when a DOM or stylesheet change was missed.
+    pub fn recorded_and_current_matches(&self, node_id: NodeId) -> (Vec<usize>, Vec<usize>) {
+        if node_id.0 as usize >= self.matched_rules.count() {
+            return (Vec::new(), Vec::new());
+        }
+        let mut recorded: Vec<usize> = self
+            .node_rules(node_id)
+            .iter()
+            .copied()
+            .filter(|&idx| !self.rules[idx].is_node_scoped())
+            .collect();
+        recorded.sort_unstable();
+
+        let env = self.media_environment();
+        let retired = self.retired_rules();
+        let current = self
+            .rules
+            .iter()
+            .filter(|(idx, rule)| {
+                !rule.is_node_scoped()
+                    && !retired.contains(idx)
+                    && rule.media_matches(env)
+                    && rule.matches(node_id, &self.tree)
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        (recorded, current)
+    }
+
     /// Get a reference to the DOM tree.
     pub fn tree(&self) -> &DomTree {
         &self.tree
@@ -222,48 +1627,59 @@ impl Styler {
 
     /// Resolve the cascade for a single property on a node.
     ///
-    /// Returns the winning (highest-specificity) property from matched rules,
-    /// without inheritance or unit resolution. Used internally by `flush()`
-    /// and `apply_rule()`.
+    /// Returns the winning property from matched rules (by origin and
+    /// importance, then specificity, then source order), without inheritance
+    /// or unit resolution. Used internally by `flush()` and `apply_rule()`.
     fn cascade_winner(
         &self,
         node_id: NodeId,
         prop_id: &PropertyId<'static>,
-    ) -> Option<&Property<'static>> {
-        let node_rules = &self.matched_rules[node_id.0 as usize];
-        let mut winner: Option<&Property<'static>> = None;
-        let mut best_spec = Specificity::ZERO;
+    ) -> Option<Declaration<'_>> {
+        let node_rules = self.node_rules(node_id);
+        let mut winner: Option<(CascadeKey, Declaration<'_>)> = None;
 
-        for (_, &rule_idx) in node_rules.iter() {
-            let rule = &self.rules[rule_idx];
-            let spec = rule.specificity();
-            let props = rule.properties();
+        for &rule_idx in node_rules.iter() {
+            let props = self.rules[rule_idx].properties();
+            let declarations = props
+                .important
+                .iter()
+                .map(|prop| (true, prop))
+                .chain(props.normal.iter().map(|prop| (false, prop)))
+                .filter(|(_, prop)| prop.property_id() == *prop_id);
 
-            // Check important properties first (they always win over normal)
-            for prop in &props.important {
-                if prop.property_id() == *prop_id {
-                    let important_spec = spec.with_important(true);
-                    if important_spec >= best_spec {
-                        winner = Some(prop);
-                        best_spec = important_spec;
-                    }
+            for (important, prop) in declarations {
+                let specificity = self.matched_specificity(node_id, rule_idx);
+                let key = self.cascade_key(rule_idx, important, specificity);
+                if winner.as_ref().is_none_or(|(best, _)| key >= *best) {
+                    winner = Some((key, Declaration::Matched(prop)));
                 }
             }
+        }
+        drop(node_rules);
 
-            // Check normal properties (only if no important winner yet)
-            if !best_spec.important {
-                for prop in &props.normal {
-                    if prop.property_id() == *prop_id {
-                        if spec >= best_spec {
-                            winner = Some(prop);
-                            best_spec = spec;
-                        }
-                    }
+        for rule in self.node_scoped_rules(node_id).iter() {
+            let props = rule.properties();
+            let declarations = (props.important.iter().enumerate())
+                .map(|(index, prop)| (true, index, prop))
+                .chain((props.normal.iter().enumerate()).map(|(index, prop)| (false, index, prop)))
+                .filter(|(.., prop)| prop.property_id() == *prop_id);
+            for (important, index, _) in declarations {
+                let key = node_scoped_key(rule, important);
+                if winner.as_ref().is_none_or(|(best, _)| key >= *best) {
+                    let rule = Arc::clone(rule);
+                    winner = Some((
+                        key,
+                        Declaration::NodeScoped {
+                            rule,
+                            important,
+                            index,
+                        },
+                    ));
                 }
             }
         }
 
-        winner
+        winner.map(|(_, declaration)| declaration)
     }
 
     /// Flush all low-confidence rules: resolve the cascade for each property
@@ -277,12 +1693,16 @@ impl Styler {
         // then resolve the full cascade to find the winner.
         for node_idx in 0..self.matched_rules.count() {
             let node_id = NodeId(node_idx as u32);
-            let node_rules = &self.matched_rules[node_idx];
+            let node_rules = self.node_rules(node_id);
 
             // Collect unique property IDs from low-confidence rules for this node.
             let mut prop_ids: Vec<PropertyId<'static>> = Vec::new();
-            for (_, &rule_idx) in node_rules.iter() {
-                let rule = &self.rules[rule_idx];
+            let node_scoped = self.node_scoped_rules(node_id);
+            let rules = node_rules
+                .iter()
+                .map(|&rule_idx| &self.rules[rule_idx])
+                .chain(node_scoped.iter().map(|rule| &**rule));
+            for rule in rules {
                 if rule.specificity() >= CONFIDENCE_THRESHOLD {
                     continue;
                 }
@@ -295,10 +1715,12 @@ impl Styler {
                 }
             }
 
+            drop(node_rules);
+
             // For each property, resolve the full cascade and notify the winner.
             for prop_id in &prop_ids {
                 if let Some(winner) = self.cascade_winner(node_id, prop_id) {
-                    self.subscriptions.notify_property(node_id, winner);
+                    self.subscriptions.notify_property(node_id, &winner);
                     total_notifications += 1;
                 }
             }
@@ -318,23 +1740,634 @@ impl Styler {
     }
 }
 
+/// Position of a rule in source order: the position of the sheet it came
+/// from, then its index in that sheet. Presentational hints come before
+/// every stylesheet rule (position 0). Rules outside a replaceable sheet
+/// take a position of their own in the order they were added, and a
+/// replaceable sheet the position of its first rule.
+type SourceOrder = (usize, usize);
+
+/// Cascade sort key of a declaration: origin and importance first, then
+/// specificity, then source order.
+type CascadeKey = (u8, Specificity, SourceOrder);
+
+/// The rules that belong to one node rather than being matched.
+#[derive(Debug, Clone, Default)]
+struct NodeScopedRules {
+    presentational: Option<Arc<ParsedRule>>,
+    inline: Option<Arc<ParsedRule>>,
+}
+
+impl NodeScopedRules {
+    /// The node's rules, presentational hints first.
+    fn iter(&self) -> impl Iterator<Item = &Arc<ParsedRule>> {
+        self.presentational.iter().chain(&self.inline)
+    }
+}
+
+/// Cascade key of a declaration of a node-scoped rule. Both kinds take
+/// source position 0: presentational hints come before every stylesheet
+/// rule, and no other rule is as specific as an inline style.
+fn node_scoped_key(rule: &ParsedRule, important: bool) -> CascadeKey {
+    (
+        rule.origin().precedence(important),
+        rule.specificity(),
+        (0, 0),
+    )
+}
+
+/// A cascade winner: borrowed from a matched rule, or held together with
+/// the node-scoped rule it comes from, which a later change can replace.
+enum Declaration<'styler> {
+    Matched(&'styler Property<'static>),
+    NodeScoped {
+        rule: Arc<ParsedRule>,
+        important: bool,
+        index: usize,
+    },
+}
+
+impl Deref for Declaration<'_> {
+    type Target = Property<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Matched(prop) => prop,
+            Self::NodeScoped {
+                rule,
+                important,
+                index,
+            } => {
+                let props = rule.properties();
+                let declarations = if *important {
+                    &props.important
+                } else {
+                    &props.normal
+                };
+                &declarations[*index]
+            }
+        }
+    }
+}
+
+/// The counter properties, which lightningcss leaves unparsed, with the
+/// value a counter named without one changes by.
+const COUNTER_PROPERTIES: [(&str, i32); 3] = [
+    ("counter-reset", 0),
+    ("counter-set", 0),
+    ("counter-increment", 1),
+];
+
+fn counter_property(name: &'static str) -> PropertyId<'static> {
+    PropertyId::Custom(CustomPropertyName::Unknown(Ident(name.into())))
+}
+
+/// Whether `prop_id` is one of the `COUNTER_PROPERTIES`.
+pub fn is_counter_property(prop_id: &PropertyId<'static>) -> bool {
+    COUNTER_PROPERTIES
+        .iter()
+        .any(|&(name, _)| *prop_id == counter_property(name))
+}
+
+/// Counter changes from the winning `COUNTER_PROPERTIES` declarations, in
+/// the same order.
+fn counter_changes(winners: &[Option<&Property<'static>>]) -> CounterChanges {
+    let mut lists =
+        COUNTER_PROPERTIES
+            .iter()
+            .zip(winners)
+            .map(|(&(_, default), prop)| match prop {
+                Some(Property::Custom(custom)) => {
+                    counters::parse_counter_list(&custom.value, default)
+                }
+                _ => Vec::new(),
+            });
+    CounterChanges {
+        reset: lists.next().unwrap_or_default(),
+        set: lists.next().unwrap_or_default(),
+        increment: lists.next().unwrap_or_default(),
+        reads: false,
+    }
+}
+
+/// Indices in `rules` of the rules in effect with a selector for each
+/// kind of generated box.
+#[derive(Debug, Default)]
+struct PseudoRules {
+    before: Vec<usize>,
+    after: Vec<usize>,
+    marker: Vec<usize>,
+}
+
+impl PseudoRules {
+    /// The rules for `::before` or `::after` boxes.
+    fn generating(&self, pseudo: GeneratedBox) -> &[usize] {
+        match pseudo {
+            GeneratedBox::Before => &self.before,
+            GeneratedBox::After => &self.after,
+        }
+    }
+}
+
+/// Whether a selector of `rule` ends in `pseudo`.
+fn selects_pseudo_element(rule: &ParsedRule, pseudo: &PseudoElement<'static>) -> bool {
+    let ParsedRule::Stylesheet { selectors, .. } = rule else {
+        return false;
+    };
+    selectors.0.iter().any(|selector| {
+        matches!(
+            selector.iter_raw_match_order().next(),
+            Some(Component::PseudoElement(target)) if target == pseudo
+        )
+    })
+}
+
+fn pseudo_element(pseudo: GeneratedBox) -> PseudoElement<'static> {
+    match pseudo {
+        GeneratedBox::Before => PseudoElement::Before,
+        GeneratedBox::After => PseudoElement::After,
+    }
+}
+
+/// Add the IDs of `props` to `out`, skipping duplicates.
+fn collect_property_ids(props: &Properties, out: &mut Vec<PropertyId<'static>>) {
+    for prop in props.normal.iter().chain(props.important.iter()) {
+        let prop_id = prop.property_id();
+        if !out.contains(&prop_id) {
+            out.push(prop_id);
+        }
+    }
+}
+
 /// Parse inline style attribute into a ParsedRule if present.
 fn parse_inline_styles(node_id: NodeId, tree: &DomTree) -> Option<ParsedRule> {
     let NodeData::Element { attributes, .. } = &tree.nodes[node_id.0 as usize] else {
         return None;
     };
 
+    let style = attributes.get(&tree.interner.get("style")?)?;
     let options = ParserOptions {
         error_recovery: true,
         ..Default::default()
     };
 
-    tree.interner
-        .get("style")
-        .and_then(|key| attributes.get(&key))
-        .and_then(|style| DeclarationBlock::parse_string(style, options).ok())
+    DeclarationBlock::parse_string(&style, options)
+        .ok()
         .map(|decls| ParsedRule::Inline {
             node_id,
             properties: decls.into(),
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuleMedia;
+    use crate::parser::collect_style_rules;
+    use lasso::ThreadedRodeo;
+    use lightningcss::printer::PrinterOptions;
+    use lightningcss::stylesheet::StyleSheet;
+    use rewrite_core::{DomBroadcast, Subscriber};
+    use rewrite_html::{Attributes, DomUpdate, Namespace};
+    use std::sync::Mutex;
+
+    type Events = Arc<Mutex<Vec<String>>>;
+
+    struct Recorder(Events);
+
+    impl Subscriber for Recorder {
+        fn on_property(&self, node: NodeId, property: &Property<'static>) {
+            let name = property.property_id().name().to_owned();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("set {} {name}", node.0));
+        }
+
+        fn on_property_removed(&self, node: NodeId, property: &PropertyId<'static>) {
+            let name = property.name().to_owned();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("remove {} {name}", node.0));
+        }
+
+        fn on_dom(&self, _update: DomBroadcast) {}
+    }
+
+    fn setup(css: &str) -> (Arc<DomTree>, Styler, Events, NodeId) {
+        let interner = Arc::new(ThreadedRodeo::new());
+        let tree = Arc::new(DomTree::new(interner.clone()));
+        let recorder = Events::default();
+        let subscriptions = Arc::new(Subscriptions::new());
+        subscriptions.add_subscriber(Box::new(Recorder(recorder.clone())));
+        let styler = Styler::new(tree.clone(), subscriptions);
+
+        let root = tree.apply_update(DomUpdate::CreateNode(NodeData::Document));
+        styler.style_node(root);
+        let state = interner.get_or_intern("data-state");
+        let tag = interner.get_or_intern("div");
+        let attributes = std::iter::once((state, Box::from("closed"))).collect();
+        let div = tree.apply_update(DomUpdate::CreateNode(NodeData::Element {
+            tag,
+            namespace: Namespace::Html,
+            attributes,
+        }));
+        styler.style_node(div);
+        tree.apply_update(DomUpdate::AppendChild {
+            parent: root,
+            child: div,
+        });
+        styler.restyle_node(div);
+
+        for rule in sheet_rules(css) {
+            styler.add_rule(rule);
+        }
+        (tree, styler, recorder, div)
+    }
+
+    fn sheet_rules(css: &str) -> Vec<ParsedRule> {
+        let sheet = StyleSheet::parse(css, ParserOptions::default()).unwrap();
+        let mut rules = Vec::new();
+        for rule in sheet.rules.0 {
+            collect_style_rules(rule, &RuleMedia::default(), Origin::Author, &mut rules);
+        }
+        rules
+    }
+
+    fn winning_color(styler: &Styler, div: NodeId) -> Option<String> {
+        styler
+            .cascade_winner(div, &PropertyId::Color)
+            .map(|prop| prop.value_to_css_string(PrinterOptions::default()).unwrap())
+    }
+
+    fn set_attr(tree: &DomTree, styler: &Styler, node: NodeId, name: &str, value: &str) {
+        let name = tree.interner.get_or_intern(name);
+        if tree.set_attribute(node, name, Some(Box::from(value))) {
+            styler.attribute_changed(node, name);
+        }
+    }
+
+    #[test]
+    fn attribute_selector_rematches_on_change() {
+        let (tree, styler, recorder, div) = setup("[data-state=open] { color: red }");
+        assert!(recorder.lock().unwrap().is_empty());
+
+        set_attr(&tree, &styler, div, "data-state", "open");
+        assert_eq!(*recorder.lock().unwrap(), vec!["set 1 color".to_owned()]);
+
+        set_attr(&tree, &styler, div, "data-state", "closed");
+        assert_eq!(recorder.lock().unwrap().last().unwrap(), "remove 1 color");
+    }
+
+    #[test]
+    fn element_state_rematches_the_flipped_chain() {
+        let (tree, styler, recorder, div) =
+            setup("div:hover { color: red } :focus { width: 1px } span:active { height: 1px }");
+        let tag = tree.interner.get_or_intern("span");
+        let span = tree.apply_update(DomUpdate::CreateNode(NodeData::Element {
+            tag,
+            namespace: Namespace::Html,
+            attributes: Attributes::default(),
+        }));
+        styler.style_node(span);
+        tree.apply_update(DomUpdate::AppendChild {
+            parent: div,
+            child: span,
+        });
+        styler.restyle_node(span);
+
+        // Hovering the child hovers its ancestors too.
+        styler.set_element_state(span, ElementState::HOVER, true);
+        assert_eq!(*recorder.lock().unwrap(), vec!["set 1 color".to_owned()]);
+        styler.set_element_state(span, ElementState::HOVER, false);
+        assert_eq!(recorder.lock().unwrap().last().unwrap(), "remove 1 color");
+
+        recorder.lock().unwrap().clear();
+        styler.set_element_state(div, ElementState::FOCUS, true);
+        assert_eq!(*recorder.lock().unwrap(), vec!["set 1 width".to_owned()]);
+
+        // No selector reads `:focus-visible`, and setting a flag twice
+        // changes nothing.
+        recorder.lock().unwrap().clear();
+        styler.set_element_state(div, ElementState::FOCUS_VISIBLE, true);
+        styler.set_element_state(div, ElementState::FOCUS, true);
+        assert!(recorder.lock().unwrap().is_empty());
+        styler.set_element_state(div, ElementState::ACTIVE, true);
+        assert!(recorder.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn appending_a_sibling_rematches_last_child() {
+        let (tree, styler, recorder, div) = setup("span:last-child { color: red }");
+        let tag = tree.interner.get_or_intern("span");
+        let append_span = || {
+            let span = tree.apply_update(DomUpdate::CreateNode(NodeData::Element {
+                tag,
+                namespace: Namespace::Html,
+                attributes: Attributes::default(),
+            }));
+            styler.style_node(span);
+            tree.apply_update(DomUpdate::AppendChild {
+                parent: div,
+                child: span,
+            });
+            styler.restyle_node(span);
+            span
+        };
+        let first = append_span();
+        assert_eq!(
+            *recorder.lock().unwrap(),
+            vec![format!("set {} color", first.0)]
+        );
+
+        recorder.lock().unwrap().clear();
+        let second = append_span();
+        assert_eq!(
+            *recorder.lock().unwrap(),
+            vec![
+                format!("set {} color", second.0),
+                format!("remove {} color", first.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn ancestor_filter_rejects_rules_and_follows_class_changes() {
+        let (tree, styler, _, div) = setup(".nav span { color: red } div span { width: 1px }");
+        let tag = tree.interner.get_or_intern("span");
+        let span = tree.apply_update(DomUpdate::CreateNode(NodeData::Element {
+            tag,
+            namespace: Namespace::Html,
+            attributes: Attributes::default(),
+        }));
+        styler.style_node(span);
+        tree.apply_update(DomUpdate::AppendChild {
+            parent: div,
+            child: span,
+        });
+        styler.reset_ancestor_filter_stats();
+        styler.restyle_node(span);
+        assert!(styler.cascade_winner(span, &PropertyId::Width).is_some());
+        assert_eq!(winning_color(&styler, span), None);
+        assert_eq!(
+            styler.ancestor_filter_stats(),
+            AncestorFilterStats {
+                rejected: 1,
+                admitted: 1,
+                false_positives: 0,
+            },
+            "`.nav span` is rejected without walking up to the div"
+        );
+
+        set_attr(&tree, &styler, div, "class", "nav");
+        assert_eq!(winning_color(&styler, span).as_deref(), Some("red"));
+    }
+
+    #[test]
+    fn untracked_attribute_is_ignored() {
+        let (tree, styler, recorder, div) = setup("[data-state=open] { color: red }");
+        set_attr(&tree, &styler, div, "title", "open");
+        assert!(recorder.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn style_attribute_replaces_inline_rule() {
+        let (tree, styler, recorder, div) = setup("");
+        set_attr(&tree, &styler, div, "style", "width: 10px");
+        assert_eq!(*recorder.lock().unwrap(), vec!["set 1 width".to_owned()]);
+
+        set_attr(&tree, &styler, div, "style", "height: 5px");
+        let events = recorder.lock().unwrap().clone();
+        assert!(events.contains(&"remove 1 width".to_owned()));
+        assert!(events.contains(&"set 1 height".to_owned()));
+    }
+
+    #[test]
+    fn rematched_rules_keep_their_source_order() {
+        let (tree, styler, _recorder, div) = setup(".a { color: red } .b { color: blue }");
+        set_attr(&tree, &styler, div, "class", "b");
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("#00f"));
+
+        // `.a` starts matching after `.b`, but comes first in the sheet.
+        set_attr(&tree, &styler, div, "class", "a b");
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("#00f"));
+
+        set_attr(&tree, &styler, div, "class", "a");
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("red"));
+    }
+
+    #[test]
+    fn replaced_inline_rules_stay_out_of_the_rule_list() {
+        let (tree, styler, _recorder, div) = setup("div { margin: 1px }");
+        let rules = styler.rules.count();
+        for color in ["red", "blue", "green", "blue"] {
+            set_attr(&tree, &styler, div, "style", &format!("color: {color}"));
+        }
+        assert_eq!(styler.rules.count(), rules);
+        assert!(styler.retired_rules().is_empty());
+
+        // Re-appending the node keeps the inline rule without matching it.
+        styler.restyle_node(div);
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("#00f"));
+        assert_eq!(styler.node_rules(div).len(), 1);
+        assert!(styler.node_scoped_rules(div).inline.is_some());
+    }
+
+    #[test]
+    fn user_normal_loses_to_author_normal() {
+        let (_tree, styler, _recorder, div) = setup("div { color: red }");
+        styler.set_user_stylesheet(sheet_rules("div { color: blue }"));
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("red"));
+    }
+
+    #[test]
+    fn user_important_beats_author_important() {
+        let (_tree, styler, _recorder, div) = setup("div { color: red !important }");
+        styler.set_user_stylesheet(sheet_rules("div { color: blue !important }"));
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("#00f"));
+
+        // Replacing the user sheet retires the previous rules.
+        styler.set_user_stylesheet(sheet_rules("p { color: green !important }"));
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("red"));
+    }
+
+    #[test]
+    fn replacing_a_sheet_retires_its_rules() {
+        let (_tree, styler, _recorder, div) = setup("div { color: red }");
+        let sheet = SheetId(7);
+        styler.replace_stylesheet(sheet, sheet_rules("#missing, div { color: blue }"));
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("#00f"));
+
+        styler.replace_stylesheet(sheet, sheet_rules("p { color: green }"));
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("red"));
+
+        styler.replace_stylesheet(sheet, sheet_rules("div { color: green }"));
+        styler.remove_stylesheet(sheet);
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("red"));
+    }
+
+    #[test]
+    fn replaced_sheets_keep_their_source_position() {
+        let (tree, styler, _recorder, div) = setup("");
+        let first = SheetId(1);
+        styler.replace_stylesheet(first, sheet_rules("div { color: red }"));
+        styler.replace_stylesheet(SheetId(2), sheet_rules("div { color: blue }"));
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("#00f"));
+
+        // The first sheet still comes before the second, so its new rule
+        // loses the tie.
+        styler.replace_stylesheet(first, sheet_rules("div { color: green }"));
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("#00f"));
+
+        let restored = Styler::restore(tree, Arc::new(Subscriptions::new()), &styler.snapshot());
+        let restored = restored.unwrap();
+        assert_eq!(winning_color(&restored, div).as_deref(), Some("#00f"));
+
+        // Removed and added again, it follows the second.
+        styler.remove_stylesheet(first);
+        styler.replace_stylesheet(first, sheet_rules("div { color: green }"));
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("green"));
+    }
+
+    #[test]
+    fn selector_lists_apply_with_the_matching_selector_specificity() {
+        let cases = [
+            // `div` is what matches, so the list is only as specific as `div`.
+            ("div, #missing { color: blue } div { color: red }", "red"),
+            (
+                "div, #missing { color: blue } [data-state] { color: red }",
+                "red",
+            ),
+            // `:is()` takes its most specific argument, matching or not.
+            (
+                ":is(div, #missing) { color: blue } [data-state] { color: red }",
+                "#00f",
+            ),
+            // `:where()` contributes nothing, `:not()` its argument's.
+            (
+                "div { color: red } :where(#missing, div) { color: blue }",
+                "red",
+            ),
+            (
+                ":where(div) div, div { color: blue } :not(#missing) { color: red }",
+                "red",
+            ),
+        ];
+        for (css, expected) in cases {
+            let (_tree, styler, _recorder, div) = setup(css);
+            assert_eq!(
+                winning_color(&styler, div).as_deref(),
+                Some(expected),
+                "{css}"
+            );
+        }
+
+        // The cached specificity follows the selector that matches now.
+        let (tree, styler, _recorder, div) =
+            setup("div, div.on { color: blue } [data-state] { color: red }");
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("red"));
+        set_attr(&tree, &styler, div, "class", "on");
+        assert_eq!(winning_color(&styler, div).as_deref(), Some("#00f"));
+    }
+
+    #[test]
+    fn forced_colors_toggles_media_rules() {
+        let (_tree, styler, recorder, _div) =
+            setup("@media (forced-colors: active) { div { color: red } }");
+        assert!(recorder.lock().unwrap().is_empty());
+
+        styler.set_forced_colors(Some(ForcedColorsPalette::high_contrast_dark()));
+        assert_eq!(*recorder.lock().unwrap(), vec!["set 1 color".to_owned()]);
+
+        styler.set_forced_colors(None);
+        assert_eq!(recorder.lock().unwrap().last().unwrap(), "remove 1 color");
+    }
+
+    #[test]
+    fn svg_presentation_attributes_lose_to_author_rules() {
+        let (tree, styler, _recorder, _div) = setup("");
+        let tag = tree.interner.get_or_intern("circle");
+        let fill = tree.interner.get_or_intern("fill");
+        let attributes = std::iter::once((fill, Box::from("red"))).collect();
+        let circle = tree.apply_update(DomUpdate::CreateNode(NodeData::Element {
+            tag,
+            namespace: Namespace::Svg,
+            attributes,
+        }));
+        styler.style_node(circle);
+
+        let winning_fill = || {
+            styler
+                .cascade_winner(circle, &PropertyId::Fill)
+                .map(|prop| prop.value_to_css_string(PrinterOptions::default()).unwrap())
+        };
+        assert_eq!(winning_fill().as_deref(), Some("red"));
+
+        set_attr(&tree, &styler, circle, "fill", "green");
+        assert_eq!(winning_fill().as_deref(), Some("green"));
+
+        for rule in sheet_rules("circle { fill: blue }") {
+            styler.add_rule(rule);
+        }
+        set_attr(&tree, &styler, circle, "fill", "#123");
+        assert_eq!(winning_fill().as_deref(), Some("#00f"));
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_text() {
+        let (tree, styler, _recorder, div) = setup(
+            "div { color: red; margin: 1px 2px }\n\
+             @media print { div { color: green !important } }\n\
+             @page :first { margin-top: 1in }\n\
+             @tailwind base;",
+        );
+        set_attr(&tree, &styler, div, "style", "width: 10px");
+        styler.replace_stylesheet(SheetId(3), sheet_rules("[data-state] { color: blue }"));
+        styler.replace_stylesheet(SheetId(3), sheet_rules("div { height: 5px }"));
+        styler.set_forced_colors(Some(ForcedColorsPalette::high_contrast_dark()));
+        styler.set_media_environment(MediaEnvironment {
+            viewport: Some((800, 600)),
+            ..styler.media_environment()
+        });
+
+        let snapshot = styler.snapshot();
+        assert_eq!(snapshot.retired.len(), 1);
+        assert_eq!(snapshot.at_rules.len(), 2);
+        let text = snapshot.to_text();
+        assert_eq!(StyleSnapshot::from_text(&text), Ok(snapshot.clone()));
+
+        let recorder = Events::default();
+        let subscriptions = Arc::new(Subscriptions::new());
+        subscriptions.add_subscriber(Box::new(Recorder(recorder.clone())));
+        let restored = Styler::restore(tree, subscriptions, &snapshot).unwrap();
+        assert_eq!(restored.snapshot(), snapshot);
+        assert_eq!(winning_color(&restored, div).as_deref(), Some("red"));
+        let events = recorder.lock().unwrap().clone();
+        for name in ["color", "width", "height", "margin-left"] {
+            assert!(
+                events.contains(&format!("set 1 {name}")),
+                "{name} not notified"
+            );
+        }
+    }
+
+    #[test]
+    fn restore_keeps_stale_matches() {
+        let (tree, styler, _recorder, div) = setup("[data-state=open] { color: red }");
+        let mut snapshot = styler.snapshot();
+        // A match the Styler should have dropped is replayed as recorded.
+        snapshot.matched[div.0 as usize].push(0);
+        let restored = Styler::restore(tree, Arc::new(Subscriptions::new()), &snapshot).unwrap();
+        assert_eq!(winning_color(&restored, div).as_deref(), Some("red"));
+
+        snapshot.matched[div.0 as usize].push(9);
+        let restored_tree = Arc::clone(&restored.tree);
+        let error = Styler::restore(restored_tree, Arc::new(Subscriptions::new()), &snapshot).err();
+        assert_eq!(error, Some(SnapshotError::RuleOutOfRange(9)));
+        assert_eq!(
+            StyleSnapshot::from_text("style-snapshot 2\nmatch x"),
+            Err(SnapshotError::Malformed { line: 2 })
+        );
+    }
+}
@@ -4,11 +4,24 @@
 //! that queries the Database (cascade + inheritance) on demand.
 
 use crate::Styler;
+use crate::calc;
 use crate::value_resolver::NodeContext;
-use lightningcss::properties::display::{Display, DisplayInside, DisplayOutside};
+use crate::values::{CssValue, values_from_tokens};
+use lightningcss::properties::display::{Display, DisplayInside, DisplayKeyword, DisplayOutside};
+use lightningcss::properties::text::WhiteSpace;
 use lightningcss::properties::{Property, PropertyId};
-use rewrite_core::{Database, NodeId, PropertyResolver, Subpixel, TextMeasurement};
+use lightningcss::values::length::{Length, LengthValue};
+use lightningcss::values::percentage::DimensionPercentage;
+use rewrite_core::{
+    CustomLayout, CustomLayoutRegistry, Database, FontDependents, FontRelative, FontUnitSizes,
+    GeneratedBox, IntrinsicSize, LayoutFlags, NodeId, PropertyResolver, Subpixel, TextAutosizing,
+    TextMeasurement, TextWrapStyle, ViewportDependents, ViewportKind, custom_layout_name,
+    text_wrap_id,
+};
 use rewrite_html::NodeData;
+use rewrite_text::TextSpacing;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// A property resolver that wraps a `Styler` and `Database`, providing
@@ -23,12 +36,96 @@ pub struct CssPropertyResolver {
     db: Arc<Database>,
     vw: u32,
     vh: u32,
+    flags: LayoutFlags,
+    autosizing: Option<TextAutosizing>,
+    custom_layouts: Arc<CustomLayoutRegistry>,
+    intrinsic_sizes: Arc<HashMap<NodeId, IntrinsicSize>>,
+    font_dependents: Option<Arc<FontDependents>>,
+    small_viewport: Option<(u32, u32)>,
+    dynamic_viewport: Option<(u32, u32)>,
+    viewport_dependents: Option<Arc<ViewportDependents>>,
 }
 
 impl CssPropertyResolver {
     /// Create a new property resolver.
     pub fn new(styler: Arc<Styler>, db: Arc<Database>, vw: u32, vh: u32) -> Self {
-        Self { styler, db, vw, vh }
+        Self {
+            styler,
+            db,
+            vw,
+            vh,
+            flags: LayoutFlags::default(),
+            autosizing: None,
+            custom_layouts: Arc::default(),
+            intrinsic_sizes: Arc::default(),
+            font_dependents: None,
+            small_viewport: None,
+            dynamic_viewport: None,
+            viewport_dependents: None,
+        }
+    }
+
+    /// Use the given layout debug flags for formula selection.
+    #[must_use]
+    pub fn with_layout_flags(mut self, flags: LayoutFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Apply the given text autosizing to used font sizes.
+    #[must_use]
+    pub fn with_text_autosizing(mut self, autosizing: Option<TextAutosizing>) -> Self {
+        self.autosizing = autosizing;
+        self
+    }
+
+    /// Lay out `display: layout(<name>)` boxes with the given layouts.
+    #[must_use]
+    pub fn with_custom_layouts(mut self, custom_layouts: Arc<CustomLayoutRegistry>) -> Self {
+        self.custom_layouts = custom_layouts;
+        self
+    }
+
+    /// Lay out the given replaced elements at their natural sizes.
+    #[must_use]
+    pub fn with_intrinsic_sizes(
+        mut self,
+        intrinsic_sizes: Arc<HashMap<NodeId, IntrinsicSize>>,
+    ) -> Self {
+        self.intrinsic_sizes = intrinsic_sizes;
+        self
+    }
+
+    /// Record the font-relative lengths resolved through this resolver
+    /// in `font_dependents`.
+    #[must_use]
+    pub fn with_font_dependents(mut self, font_dependents: Arc<FontDependents>) -> Self {
+        self.font_dependents = Some(font_dependents);
+        self
+    }
+
+    /// Resolve `sv*` and `dv*` lengths against the given `(width, height)`
+    /// viewports; `None` stands for the layout viewport.
+    #[must_use]
+    pub fn with_viewports(
+        mut self,
+        small: Option<(u32, u32)>,
+        dynamic: Option<(u32, u32)>,
+    ) -> Self {
+        self.small_viewport = small;
+        self.dynamic_viewport = dynamic;
+        self
+    }
+
+    /// Record the lengths resolved against the dynamic viewport through
+    /// this resolver in `viewport_dependents`.
+    #[must_use]
+    pub fn with_viewport_dependents(
+        mut self,
+        viewport_dependents: Arc<ViewportDependents>,
+    ) -> Self {
+        self.viewport_dependents = Some(viewport_dependents);
+        self
     }
 
     /// Determine whether a text node is at the start/end of its
@@ -55,11 +152,170 @@ impl CssPropertyResolver {
         let sole_content = !has_prev_content && !has_next_content;
         (sole_content, sole_content)
     }
+
+    /// The font properties of `node`, in `build_attrs` order: family,
+    /// weight, style and variant caps.
+    fn font_properties(&self, node: NodeId) -> [Option<Property<'static>>; 4] {
+        [
+            PropertyId::FontFamily,
+            PropertyId::FontWeight,
+            PropertyId::FontStyle,
+            PropertyId::FontVariantCaps,
+        ]
+        .map(|prop_id| self.db.get_property(node, prop_id))
+    }
+
+    /// The used `letter-spacing` or `word-spacing` of `node` in px, zero
+    /// for `normal`.
+    fn text_spacing(&self, node: NodeId, prop_id: &PropertyId<'static>) -> f32 {
+        self.get_property(node, prop_id)
+            .map_or(0.0, Subpixel::to_f32)
+    }
+
+    /// Whether `node` is an element named `name`.
+    fn has_tag(&self, node: NodeId, name: &str) -> bool {
+        let tree = self.styler.tree();
+        matches!(
+            tree.get_node(node),
+            Some(NodeData::Element { tag, .. }) if tree.interner.resolve(tag) == name
+        )
+    }
+
+    /// The `white-space` value of `node`, `normal` when unset.
+    fn white_space(&self, node: NodeId) -> WhiteSpace {
+        match self.db.get_property(node, PropertyId::WhiteSpace) {
+            Some(Property::WhiteSpace(white_space)) => white_space,
+            _ => WhiteSpace::Normal,
+        }
+    }
+}
+
+impl CssPropertyResolver {
+    /// The node whose own declaration supplies `prop_id` to `node`.
+    fn declaring_node(&self, node: NodeId, prop_id: &PropertyId<'static>) -> Option<NodeId> {
+        let group = rewrite_core::classify_property(prop_id)?;
+        let tree = self.db.tree_for_group(group);
+        let mut current = Some(node);
+        while let Some(candidate) = current {
+            if tree.get_local(candidate, prop_id).is_some() {
+                return Some(candidate);
+            }
+            current = self.parent(candidate);
+        }
+        None
+    }
+
+    /// Whether `node` declares its own `font-size` or `line-height` with a
+    /// value that doesn't depend on its parent's, so a change of the
+    /// parent's value reaches neither it nor, through inheritance, its
+    /// descendants.
+    pub fn overrides_inherited_font(&self, node: NodeId, prop_id: &PropertyId<'static>) -> bool {
+        use lightningcss::properties::font::{FontSize, LineHeight};
+        use lightningcss::values::length::LengthValue;
+        use lightningcss::values::percentage::DimensionPercentage;
+
+        if self.declaring_node(node, prop_id) != Some(node) {
+            return false;
+        }
+        let Some(prop) = self.db.get_property(node, prop_id.clone()) else {
+            return false;
+        };
+        match &prop {
+            // A line height computed from an ancestor's font size is
+            // inherited as a length, and changes with that font size.
+            Property::FontSize(FontSize::Absolute(_) | FontSize::Length(_)) => {
+                !inherits_computed_length(&prop)
+                    && (self.declaring_node(node, &PropertyId::LineHeight) == Some(node)
+                        || !matches!(
+                            self.db.get_property(node, PropertyId::LineHeight),
+                            Some(Property::LineHeight(LineHeight::Length(_)))
+                        ))
+            }
+            Property::LineHeight(LineHeight::Length(DimensionPercentage::Dimension(
+                LengthValue::Lh(_) | LengthValue::Rlh(_),
+            ))) => false,
+            Property::LineHeight(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Font size of a `::marker`, `::before` or `::after` box of `node`
+    /// in px: the element's own unless the pseudo-element's rules declare
+    /// `font_size`.
+    pub fn pseudo_font_size(&self, node: NodeId, font_size: Option<&Property<'static>>) -> f32 {
+        font_size
+            .and_then(|prop| property_to_subpixel(prop, node, self))
+            .or_else(|| self.get_property(node, &PropertyId::FontSize))
+            .map_or(rewrite_text::DEFAULT_FONT_SIZE_PX, Subpixel::to_f32)
+    }
+}
+
+/// Whether `prop` is inherited as the length it computes to where it is
+/// declared, rather than re-resolved on each descendant.
+///
+/// Covers relative and percentage font sizes and non-number line heights.
+fn inherits_computed_length(prop: &Property<'static>) -> bool {
+    use lightningcss::properties::font::{FontSize, LineHeight};
+    use lightningcss::properties::text::Spacing;
+
+    match prop {
+        Property::FontSize(FontSize::Length(length)) => !matches!(
+            length,
+            DimensionPercentage::Dimension(
+                LengthValue::Px(_)
+                    | LengthValue::In(_)
+                    | LengthValue::Cm(_)
+                    | LengthValue::Mm(_)
+                    | LengthValue::Q(_)
+                    | LengthValue::Pt(_)
+                    | LengthValue::Pc(_)
+            )
+        ),
+        Property::LineHeight(LineHeight::Length(_)) => true,
+        // Spacing inherits as a computed length, so `em` is not re-resolved.
+        Property::LetterSpacing(Spacing::Length(_)) | Property::WordSpacing(Spacing::Length(_)) => {
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The length-percentage of a property whose percentages refer to the
+/// containing block's width: a margin, padding or width.
+fn inline_length_percentage<'prop>(
+    prop: &'prop Property<'static>,
+) -> Option<&'prop DimensionPercentage<LengthValue>> {
+    use lightningcss::properties::size::{MaxSize, Size};
+    use lightningcss::values::length::LengthPercentageOrAuto;
+
+    match prop {
+        Property::MarginTop(side)
+        | Property::MarginRight(side)
+        | Property::MarginBottom(side)
+        | Property::MarginLeft(side)
+        | Property::PaddingTop(side)
+        | Property::PaddingRight(side)
+        | Property::PaddingBottom(side)
+        | Property::PaddingLeft(side) => match side {
+            LengthPercentageOrAuto::LengthPercentage(length) => Some(length),
+            LengthPercentageOrAuto::Auto => None,
+        },
+        Property::Width(Size::LengthPercentage(length))
+        | Property::MinWidth(Size::LengthPercentage(length))
+        | Property::MaxWidth(MaxSize::LengthPercentage(length)) => Some(length),
+        _ => None,
+    }
 }
 
 impl PropertyResolver for CssPropertyResolver {
     fn get_property(&self, node: NodeId, prop_id: &PropertyId<'static>) -> Option<Subpixel> {
         let prop = self.db.get_property(node, prop_id.clone())?;
+        if inherits_computed_length(&prop)
+            && let Some(parent) = self.parent(node)
+            && self.declaring_node(node, prop_id) != Some(node)
+        {
+            return self.get_property(parent, prop_id);
+        }
         property_to_subpixel(&prop, node, self)
     }
 
@@ -95,8 +351,17 @@ impl PropertyResolver for CssPropertyResolver {
         self.vh
     }
 
+    fn viewport_size(&self, kind: ViewportKind) -> (u32, u32) {
+        let size = match kind {
+            ViewportKind::Small => self.small_viewport,
+            ViewportKind::Large => None,
+            ViewportKind::Dynamic => self.dynamic_viewport,
+        };
+        size.unwrap_or((self.vw, self.vh))
+    }
+
     fn is_intrinsic(&self, node: NodeId) -> bool {
-        self.styler.tree().text_content(node).is_some()
+        self.styler.tree().text_content(node).is_some() || self.intrinsic_size(node).is_some()
     }
 
     fn is_element(&self, node: NodeId) -> bool {
@@ -106,18 +371,54 @@ impl PropertyResolver for CssPropertyResolver {
         )
     }
 
+    fn generates_box(&self, node: NodeId) -> bool {
+        self.db.generates_box(node)
+    }
+
+    fn forces_line_break(&self, node: NodeId) -> bool {
+        matches!(
+            self.styler.tree().text_content(node),
+            Some("\n" | "\r\n" | "\r")
+        ) && rewrite_text::preserves_segment_breaks(self.white_space(node))
+    }
+
+    fn tag_name(&self, node: NodeId) -> Option<String> {
+        let tree = self.styler.tree();
+        match tree.get_node(node) {
+            Some(NodeData::Element { tag, .. }) => Some(tree.interner.resolve(tag).to_owned()),
+            _ => None,
+        }
+    }
+
+    fn is_rendered_legend(&self, node: NodeId) -> bool {
+        let tree = self.styler.tree();
+        let Some(parent) = tree.parent(node) else {
+            return false;
+        };
+        if !self.has_tag(node, "legend") || !self.has_tag(parent, "fieldset") {
+            return false;
+        }
+        // HTML §15.3.13: the first `legend` child that isn't floated or
+        // absolutely positioned. Child lists run in reverse DOM order.
+        tree.children(parent)
+            .filter(|&child| self.has_tag(child, "legend") && !self.is_absolute_or_floated(child))
+            .last()
+            == Some(node)
+    }
+
     fn text_content(&self, node: NodeId) -> Option<String> {
         let text = self.styler.tree().text_content(node)?;
         if text.trim().is_empty() {
             return None;
         }
-        // CSS Text 3 §4.1.1: collapse whitespace.
+        // CSS Text 3 §4.1: collapse or preserve whitespace per `white-space`.
         let (at_start, at_end) = self.text_block_boundary(node);
-        let collapsed = rewrite_text::collapse_whitespace(text, at_start, at_end);
-        if collapsed.is_empty() {
+        let processed =
+            rewrite_text::process_whitespace(text, self.white_space(node), at_start, at_end);
+        if processed.trim().is_empty() {
             return None;
         }
-        Some(collapsed)
+        Some(processed)
     }
 
     fn measure_text(
@@ -127,53 +428,217 @@ impl PropertyResolver for CssPropertyResolver {
         font_size: f32,
         max_width: Option<f32>,
     ) -> Option<TextMeasurement> {
-        let font_family = self.db.get_property(node, PropertyId::FontFamily);
-        let font_weight = self.db.get_property(node, PropertyId::FontWeight);
-        let font_style = self.db.get_property(node, PropertyId::FontStyle);
-
+        let [font_family, font_weight, font_style, font_variant_caps] = self.font_properties(node);
         let attrs = rewrite_text::build_attrs(
             font_family.as_ref(),
             font_weight.as_ref(),
             font_style.as_ref(),
+            font_variant_caps.as_ref(),
         );
+        let text = match self.db.get_property(node, PropertyId::TextTransform) {
+            Some(Property::TextTransform(transform)) => {
+                rewrite_text::transform_text(text, &transform)
+            }
+            _ => Cow::Borrowed(text),
+        };
+        let text = text.as_ref();
+        let spacing = TextSpacing {
+            letter: self.text_spacing(node, &PropertyId::LetterSpacing),
+            word: self.text_spacing(node, &PropertyId::WordSpacing),
+        };
+
+        // `nowrap` and `pre` never soft wrap, but preserved segment breaks
+        // still force new lines.
+        let max_width = if rewrite_text::wraps(self.white_space(node)) {
+            max_width
+        } else {
+            None
+        };
+        let max_width = match max_width {
+            None if text.contains('\n') => Some(f32::MAX),
+            other => other,
+        };
 
         let font_sys = rewrite_text::get_font_system();
         let mut font_sys_guard = font_sys.lock().unwrap_or_else(|err| err.into_inner());
 
         if let Some(max_w) = max_width {
-            let wrapped = rewrite_text::measure_text_wrapped(
-                &mut font_sys_guard,
-                text,
-                &attrs,
+            let options = rewrite_text::WrapOptions {
                 font_size,
-                max_w,
-            );
+                max_width: max_w,
+                spacing,
+            };
+            let wrapped =
+                rewrite_text::measure_text_wrapped(&mut font_sys_guard, text, &attrs, options);
             Some(TextMeasurement {
                 width: wrapped.max_line_width,
                 height: wrapped.total_height,
                 ascent: wrapped.ascent,
                 descent: wrapped.descent,
+                synthesized_small_caps: wrapped.synthesized_small_caps,
             })
         } else {
-            let metrics = rewrite_text::measure_text(&mut font_sys_guard, text, &attrs, font_size);
+            let metrics =
+                rewrite_text::measure_text(&mut font_sys_guard, text, &attrs, font_size, spacing);
             Some(TextMeasurement {
                 width: metrics.width,
                 height: metrics.height,
                 ascent: metrics.ascent,
                 descent: metrics.descent,
+                synthesized_small_caps: metrics.synthesized_small_caps,
+            })
+        }
+    }
+
+    fn measure_marker(&self, node: NodeId) -> Option<TextMeasurement> {
+        let marker = self.styler.marker(node)?;
+        let font_size = self.pseudo_font_size(node, marker.property(&PropertyId::FontSize));
+        self.measure_text(node, &marker.text, font_size, None)
+    }
+
+    fn measure_generated(&self, node: NodeId, pseudo: GeneratedBox) -> Option<TextMeasurement> {
+        let generated = self.styler.generated_content(node, pseudo)?;
+        let font_size = self.pseudo_font_size(node, generated.property(&PropertyId::FontSize));
+        self.measure_text(node, &generated.text, font_size, None)
+    }
+
+    fn font_unit_sizes(&self, node: NodeId, font_size: f32) -> Option<FontUnitSizes> {
+        let [font_family, font_weight, font_style, font_variant_caps] = self.font_properties(node);
+        let attrs = rewrite_text::build_attrs(
+            font_family.as_ref(),
+            font_weight.as_ref(),
+            font_style.as_ref(),
+            font_variant_caps.as_ref(),
+        );
+        let font_sys = rewrite_text::get_font_system();
+        let mut font_sys_guard = font_sys.lock().unwrap_or_else(|err| err.into_inner());
+        let metrics = rewrite_text::get_unit_metrics(&mut font_sys_guard, &attrs, font_size)?;
+        Some(FontUnitSizes {
+            zero_advance: metrics.zero_advance,
+            // Fonts without an x-height use the fallback of half an em.
+            x_height: metrics.x_height.unwrap_or(font_size / 2.0),
+        })
+    }
+
+    fn unbreakable_segments<'text>(&self, node: NodeId, text: &'text str) -> Vec<&'text str> {
+        if rewrite_text::wraps(self.white_space(node)) {
+            rewrite_text::unbreakable_segments(text)
+        } else {
+            // Without soft wrapping, only forced line breaks split the text.
+            text.split('\n').collect()
+        }
+    }
+
+    fn text_wrap_style(&self, node: NodeId) -> TextWrapStyle {
+        let Some(Property::Custom(custom)) = self.db.get_property(node, text_wrap_id()) else {
+            return TextWrapStyle::Auto;
+        };
+        // The shorthand may also name the wrap mode, e.g. `wrap balance`.
+        values_from_tokens(&custom.value)
+            .iter()
+            .find_map(|value| match value {
+                CssValue::Keyword(keyword) if keyword == "balance" => Some(TextWrapStyle::Balance),
+                CssValue::Keyword(keyword) if keyword == "pretty" => Some(TextWrapStyle::Pretty),
+                _ => None,
             })
+            .unwrap_or_default()
+    }
+
+    fn border_spacing(&self, node: NodeId) -> (Subpixel, Subpixel) {
+        let Some(Property::BorderSpacing(spacing)) =
+            self.db.get_property(node, PropertyId::BorderSpacing)
+        else {
+            return (Subpixel::ZERO, Subpixel::ZERO);
+        };
+        // Inherited as the lengths it computes to where it is declared.
+        let declaring = self
+            .declaring_node(node, &PropertyId::BorderSpacing)
+            .unwrap_or(node);
+        let resolve = |length: &Length| {
+            resolve_calc_length(length, declaring, self)
+                .unwrap_or(Subpixel::ZERO)
+                .max(Subpixel::ZERO)
+        };
+        (resolve(&spacing.0), resolve(&spacing.1))
+    }
+
+    fn has_inline_percentage(&self, node: NodeId, prop_id: &PropertyId<'static>) -> bool {
+        self.db
+            .get_property(node, prop_id.clone())
+            .as_ref()
+            .and_then(inline_length_percentage)
+            .is_some_and(calc::has_percentage)
+    }
+
+    fn resolve_inline_percentage(
+        &self,
+        node: NodeId,
+        prop_id: &PropertyId<'static>,
+        basis: Subpixel,
+    ) -> Option<Subpixel> {
+        let prop = self.db.get_property(node, prop_id.clone())?;
+        let length = inline_length_percentage(&prop)?;
+        resolve_calc(length, || Some(basis), &mut |len| {
+            resolve_length_ctx(len, node, self)
+        })
+    }
+
+    fn layout_flags(&self) -> LayoutFlags {
+        self.flags
+    }
+
+    fn text_autosizing(&self) -> Option<TextAutosizing> {
+        self.autosizing
+    }
+
+    fn custom_layout(&self, node: NodeId) -> Option<Arc<dyn CustomLayout>> {
+        if self.custom_layouts.is_empty() {
+            return None;
+        }
+        let display = self.get_css_property(node, &PropertyId::Display)?;
+        self.custom_layouts
+            .get(custom_layout_name(&display)?)
+            .cloned()
+    }
+
+    fn intrinsic_size(&self, node: NodeId) -> Option<IntrinsicSize> {
+        let size = self.intrinsic_sizes.get(&node)?;
+        // A `display: none` replaced element generates no box to size.
+        let hidden = matches!(
+            self.get_css_property(node, &PropertyId::Display),
+            Some(Property::Display(Display::Keyword(DisplayKeyword::None)))
+        );
+        (!hidden).then_some(*size)
+    }
+
+    fn record_font_dependency(&self, node: NodeId, font: FontRelative) {
+        if let Some(dependents) = &self.font_dependents {
+            dependents.record(node, font);
+        }
+    }
+
+    fn record_viewport_dependency(&self, node: NodeId, kind: ViewportKind) {
+        if let Some(dependents) = &self.viewport_dependents {
+            dependents.record(node, kind);
         }
     }
 }
 
 /// Resolve a percentage against the containing block's width.
 fn resolve_percentage_width(pct: f32, node: NodeId, resolver: &CssPropertyResolver) -> Subpixel {
+    Subpixel::from_f32(percentage_basis_width(node, resolver).to_f32() * pct)
+}
+
+/// The containing block's width, as far as it is known without layout.
+fn percentage_basis_width(node: NodeId, resolver: &CssPropertyResolver) -> Subpixel {
     // Walk up to find the block container.
     let cb = find_block_container(node, resolver);
-    let cb_width = resolver
+    resolver
         .get_property(cb, &PropertyId::Width)
-        .unwrap_or_else(|| Subpixel::from_px(resolver.vw as i32));
-    Subpixel::from_f32(cb_width.to_f32() * pct)
+        .unwrap_or_else(|| {
+            resolver.record_viewport_dependency(node, ViewportKind::Large);
+            Subpixel::from_px(resolver.vw as i32)
+        })
 }
 
 /// Resolve a percentage against the containing block's height.
@@ -182,11 +647,41 @@ fn resolve_percentage_height(
     node: NodeId,
     resolver: &CssPropertyResolver,
 ) -> Option<Subpixel> {
-    let cb = find_block_container(node, resolver);
-    let cb_height = resolver.get_property(cb, &PropertyId::Height)?;
+    let cb_height = percentage_basis_height(node, resolver)?;
     Some(Subpixel::from_f32(cb_height.to_f32() * pct))
 }
 
+/// The containing block's height, if specified.
+///
+/// The root element's containing block is the initial containing block,
+/// whose height is the viewport height. This is what lets
+/// `html, body { height: 100% }` fill the viewport.
+fn percentage_basis_height(node: NodeId, resolver: &CssPropertyResolver) -> Option<Subpixel> {
+    let cb = find_block_container(node, resolver);
+    if cb == NodeId::ROOT {
+        resolver.record_viewport_dependency(node, ViewportKind::Large);
+        Some(Subpixel::from_px(resolver.vh as i32))
+    } else {
+        resolver.get_property(cb, &PropertyId::Height)
+    }
+}
+
+/// Evaluate a math function in a length-percentage, with percentages of
+/// `basis` (if it has any percentages to resolve).
+fn resolve_calc(
+    value: &DimensionPercentage<LengthValue>,
+    basis: impl FnOnce() -> Option<Subpixel>,
+    length: &mut dyn FnMut(&LengthValue) -> Subpixel,
+) -> Option<Subpixel> {
+    let basis = if calc::has_percentage(value) {
+        Some(basis()?.to_f32())
+    } else {
+        None
+    };
+    let pixels = calc::length_percentage(value, basis, &mut |leaf| length(leaf).to_f32())?;
+    Some(Subpixel::from_f32(pixels))
+}
+
 /// Find the nearest block container ancestor for a node.
 fn find_block_container(node: NodeId, resolver: &CssPropertyResolver) -> NodeId {
     let tree = resolver.styler.tree();
@@ -219,7 +714,11 @@ fn resolve_dim_pct_width(
     match lp {
         Dimension(len) => Some(resolve_length_ctx(len, node, resolver)),
         Percentage(pct) => Some(resolve_percentage_width(pct.0, node, resolver)),
-        Calc(_) => None,
+        Calc(_) => resolve_calc(
+            lp,
+            || Some(percentage_basis_width(node, resolver)),
+            &mut |len| resolve_length_ctx(len, node, resolver),
+        ),
     }
 }
 
@@ -235,7 +734,9 @@ fn resolve_dim_pct_height(
     match lp {
         Dimension(len) => Some(resolve_length_ctx(len, node, resolver)),
         Percentage(pct) => resolve_percentage_height(pct.0, node, resolver),
-        Calc(_) => None,
+        Calc(_) => resolve_calc(lp, || percentage_basis_height(node, resolver), &mut |len| {
+            resolve_length_ctx(len, node, resolver)
+        }),
     }
 }
 
@@ -252,6 +753,22 @@ fn resolve_length_ctx(
     crate::value_resolver::resolve_length(value, &ctx)
 }
 
+/// Resolve a length, evaluating any math function in it.
+fn resolve_calc_length(
+    length: &Length,
+    node: NodeId,
+    resolver: &CssPropertyResolver,
+) -> Option<Subpixel> {
+    match length {
+        Length::Value(value) => Some(resolve_length_ctx(value, node, resolver)),
+        Length::Calc(_) => {
+            let mut resolve =
+                |value: &LengthValue| resolve_length_ctx(value, node, resolver).to_f32();
+            calc::length(length, &mut resolve).map(Subpixel::from_f32)
+        }
+    }
+}
+
 /// Resolve a length value for font-size (em/lh reference parent).
 fn resolve_length_for_font_size_ctx(
     value: &lightningcss::values::length::LengthValue,
@@ -265,6 +782,14 @@ fn resolve_length_for_font_size_ctx(
     crate::value_resolver::resolve_length_for_font_size(value, &ctx)
 }
 
+/// `factor` times the font size of `node`.
+fn scaled_font_size(node: NodeId, factor: f32, resolver: &CssPropertyResolver) -> Subpixel {
+    let font_size = resolver
+        .get_property(node, &PropertyId::FontSize)
+        .unwrap_or(Subpixel::from_px(16));
+    Subpixel::from_f32(font_size.to_f32() * factor)
+}
+
 /// Extract a length value from a CSS property and resolve it to pixels.
 fn property_to_subpixel(
     prop: &Property<'static>,
@@ -272,7 +797,9 @@ fn property_to_subpixel(
     resolver: &CssPropertyResolver,
 ) -> Option<Subpixel> {
     use lightningcss::properties::Property::*;
+    use lightningcss::properties::border::BorderSideWidth;
     use lightningcss::properties::size::Size;
+    use lightningcss::properties::text::Spacing;
     use lightningcss::values::length::LengthPercentageOrAuto;
 
     match prop {
@@ -313,15 +840,25 @@ fn property_to_subpixel(
         BorderTopWidth(width)
         | BorderBottomWidth(width)
         | BorderLeftWidth(width)
-        | BorderRightWidth(width) => match width {
-            lightningcss::properties::border::BorderSideWidth::Length(len) => match len {
-                lightningcss::values::length::Length::Value(lv) => {
-                    Some(resolve_length_ctx(lv, node, resolver))
-                }
-                lightningcss::values::length::Length::Calc(_) => None,
-            },
-            _ => None,
+        | BorderRightWidth(width)
+        | OutlineWidth(width) => match width {
+            BorderSideWidth::Length(len) => resolve_calc_length(len, node, resolver),
+            // CSS Backgrounds 3 §4.3: `thin`, `medium` and `thick` are
+            // 1px, 3px and 5px.
+            BorderSideWidth::Thin => Some(Subpixel::from_px(1)),
+            BorderSideWidth::Medium => Some(Subpixel::from_px(3)),
+            BorderSideWidth::Thick => Some(Subpixel::from_px(5)),
         },
+        // `outline-offset` is untyped in lightningcss: read the single length token.
+        LetterSpacing(Spacing::Length(length)) | WordSpacing(Spacing::Length(length)) => {
+            resolve_calc_length(length, node, resolver)
+        }
+        Custom(custom) if custom.name.as_ref() == "outline-offset" => {
+            match values_from_tokens(&custom.value).as_slice() {
+                [CssValue::Length(length)] => Some(resolve_length_ctx(length, node, resolver)),
+                _ => None,
+            }
+        }
         Top(lpa) | Bottom(lpa) => match lpa {
             LengthPercentageOrAuto::LengthPercentage(lp) => {
                 resolve_dim_pct_height(lp, node, resolver)
@@ -336,43 +873,64 @@ fn property_to_subpixel(
         },
         FontSize(fs) => match fs {
             lightningcss::properties::font::FontSize::Length(lp) => match lp {
-                lightningcss::values::percentage::DimensionPercentage::Dimension(len) => {
+                DimensionPercentage::Dimension(len) => {
                     Some(resolve_length_for_font_size_ctx(len, node, resolver))
                 }
-                _ => None,
+                // Percentages refer to the inherited font size, like `em`.
+                DimensionPercentage::Percentage(pct) => {
+                    let parent = resolver.parent(node).unwrap_or(NodeId(0));
+                    Some(scaled_font_size(parent, pct.0, resolver))
+                }
+                DimensionPercentage::Calc(_) => {
+                    let parent = resolver.parent(node).unwrap_or(NodeId(0));
+                    resolve_calc(
+                        lp,
+                        || Some(scaled_font_size(parent, 1.0, resolver)),
+                        &mut |len| resolve_length_for_font_size_ctx(len, node, resolver),
+                    )
+                }
             },
             _ => None,
         },
         LineHeight(lh) => match lh {
             lightningcss::properties::font::LineHeight::Length(lp) => match lp {
-                lightningcss::values::percentage::DimensionPercentage::Dimension(len) => {
+                DimensionPercentage::Dimension(len) => {
                     Some(resolve_length_ctx(len, node, resolver))
                 }
-                _ => None,
+                DimensionPercentage::Percentage(pct) => {
+                    Some(scaled_font_size(node, pct.0, resolver))
+                }
+                DimensionPercentage::Calc(_) => resolve_calc(
+                    lp,
+                    || Some(scaled_font_size(node, 1.0, resolver)),
+                    &mut |len| resolve_length_ctx(len, node, resolver),
+                ),
             },
             lightningcss::properties::font::LineHeight::Number(n) => {
-                let font_size = resolver
-                    .get_property(node, &PropertyId::FontSize)
-                    .unwrap_or(Subpixel::from_px(16));
-                Some(Subpixel::from_f32(*n * font_size.to_f32()))
+                Some(scaled_font_size(node, *n, resolver))
             }
             _ => None,
         },
         // Flex properties
         FlexGrow(val, _) => Some(Subpixel::from_f32(*val)),
         FlexShrink(val, _) => Some(Subpixel::from_f32(*val)),
+        // Percentages refer to the flex container's main-axis content box,
+        // which only layout knows.
         FlexBasis(basis, _) => match basis {
-            LengthPercentageOrAuto::LengthPercentage(lp) => {
-                resolve_dim_pct_width(lp, node, resolver)
+            LengthPercentageOrAuto::LengthPercentage(length) => {
+                resolve_calc(length, || None, &mut |len| {
+                    resolve_length_ctx(len, node, resolver)
+                })
             }
             LengthPercentageOrAuto::Auto => None,
         },
-        // Gap properties (CSS Box Alignment §8)
+        // Gap properties (CSS Box Alignment §8). Percentages refer to the
+        // container's own content box, which only layout knows.
         RowGap(gap) | ColumnGap(gap) => match gap {
-            lightningcss::properties::align::GapValue::LengthPercentage(lp) => {
-                resolve_dim_pct_width(lp, node, resolver)
-            }
-            lightningcss::properties::align::GapValue::Normal => None,
+            lightningcss::properties::align::GapValue::LengthPercentage(
+                DimensionPercentage::Dimension(len),
+            ) => Some(resolve_length_ctx(len, node, resolver)),
+            _ => None,
         },
         _ => None,
     }
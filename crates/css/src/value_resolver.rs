@@ -1,6 +1,8 @@
 //! CSS length value resolution.
 //!
 //! Converts CSS `LengthValue` (px, em, rem, etc.) to `Subpixel` pixels.
+//! Absolute units convert at 96 pixels per inch; `ch` and `ex` are measured
+//! in the node's font through `PropertyResolver::font_unit_sizes`.
 //!
 //! Two resolution modes:
 //! - `resolve_length`: resolves relative units (em, rem, lh) against the
@@ -10,9 +12,11 @@
 //!   font-size, not the element's own). Used when the property being resolved
 //!   is `font-size` or `line-height` itself.
 
+use crate::calc;
 use lightningcss::properties::PropertyId;
 use lightningcss::values::length::LengthValue;
-use rewrite_core::{NodeId, PropertyResolver, Subpixel};
+use lightningcss::values::percentage::DimensionPercentage;
+use rewrite_core::{FontRelative, FontUnitSizes, NodeId, PropertyResolver, Subpixel, ViewportKind};
 
 /// Node-scoped adapter for length resolution.
 ///
@@ -29,6 +33,18 @@ pub fn resolve_length(value: &LengthValue, ctx: &NodeContext<'_>) -> Subpixel {
     resolve_length_with_context(value, ctx.resolver, ctx.node, ctx.node)
 }
 
+/// Resolve a length-percentage to pixels, with percentages of `basis`
+/// and math functions evaluated. `None` if a math function has no result,
+/// e.g. `mod()` by zero.
+pub fn resolve_length_percentage(
+    value: &DimensionPercentage<LengthValue>,
+    basis: Subpixel,
+    ctx: &NodeContext<'_>,
+) -> Option<Subpixel> {
+    let mut length = |length: &LengthValue| resolve_length(length, ctx).to_f32();
+    calc::length_percentage(value, Some(basis.to_f32()), &mut length).map(Subpixel::from_f32)
+}
+
 /// Resolve a CSS `LengthValue` to pixels for the `font-size` property.
 ///
 /// Per CSS spec, `em` units on `font-size` refer to the **inherited**
@@ -40,7 +56,7 @@ pub fn resolve_length_for_font_size(value: &LengthValue, ctx: &NodeContext<'_>)
 }
 
 /// Core resolution: `resolver` provides viewport info and property access.
-/// `node` is the current node (for viewport/root queries).
+/// `node` is the current node, whose font dependencies are recorded.
 /// `font_node` is the node whose font-size/line-height is used for relative units.
 fn resolve_length_with_context(
     value: &LengthValue,
@@ -48,7 +64,21 @@ fn resolve_length_with_context(
     node: NodeId,
     font_node: NodeId,
 ) -> Subpixel {
-    let _ = node; // viewport info comes from resolver, not node
+    // Font-relative units make `node` depend on a font size that none of
+    // its layout formulas name; record it. `em` and `lh` on `font-size`
+    // and `line-height` themselves use the inherited font, whose changes
+    // reach `node` through inheritance.
+    match value {
+        LengthValue::Em(_) | LengthValue::Lh(_) | LengthValue::Ex(_) | LengthValue::Ch(_)
+            if font_node == node =>
+        {
+            resolver.record_font_dependency(node, FontRelative::Own);
+        }
+        LengthValue::Rem(_) | LengthValue::Rlh(_) | LengthValue::Rex(_) | LengthValue::Rch(_) => {
+            resolver.record_font_dependency(node, FontRelative::Root);
+        }
+        _ => {}
+    }
     match value {
         // Absolute lengths — no context needed
         LengthValue::Px(v) => Subpixel::from_f32(*v),
@@ -77,42 +107,68 @@ fn resolve_length_with_context(
             Subpixel::from_f32(v * lh)
         }
 
-        // Font-metric-dependent lengths — require glyph measurement (not yet implemented)
-        LengthValue::Ex(_)
-        | LengthValue::Rex(_)
-        | LengthValue::Cap(_)
-        | LengthValue::Rcap(_)
-        | LengthValue::Ch(_)
-        | LengthValue::Rch(_)
-        | LengthValue::Ic(_)
-        | LengthValue::Ric(_) => Subpixel::ZERO,
+        // Font-metric lengths — measured in the font by the text backend
+        LengthValue::Ex(v) => {
+            Subpixel::from_f32(v * query_font_unit_sizes(font_node, resolver).x_height)
+        }
+        LengthValue::Rex(v) => {
+            let root = find_root_element(resolver);
+            Subpixel::from_f32(v * query_font_unit_sizes(root, resolver).x_height)
+        }
+        LengthValue::Ch(v) => {
+            Subpixel::from_f32(v * query_font_unit_sizes(font_node, resolver).zero_advance)
+        }
+        LengthValue::Rch(v) => {
+            let root = find_root_element(resolver);
+            Subpixel::from_f32(v * query_font_unit_sizes(root, resolver).zero_advance)
+        }
+        // Need cap-height and CJK advance measurement (not yet implemented)
+        LengthValue::Cap(_) | LengthValue::Rcap(_) | LengthValue::Ic(_) | LengthValue::Ric(_) => {
+            Subpixel::ZERO
+        }
 
         // Viewport-relative lengths (1 unit = 1% of viewport dimension)
-        LengthValue::Vw(v) | LengthValue::Lvw(v) | LengthValue::Svw(v) | LengthValue::Dvw(v) => {
-            Subpixel::from_f32(v * resolver.viewport_width() as f32 / 100.0)
+        LengthValue::Vw(percent)
+        | LengthValue::Vi(percent)
+        | LengthValue::Lvw(percent)
+        | LengthValue::Lvi(percent) => {
+            viewport_percent(*percent, ViewportKind::Large, resolver, node).width
+        }
+        LengthValue::Vh(percent)
+        | LengthValue::Vb(percent)
+        | LengthValue::Lvh(percent)
+        | LengthValue::Lvb(percent) => {
+            viewport_percent(*percent, ViewportKind::Large, resolver, node).height
+        }
+        LengthValue::Vmin(percent) | LengthValue::Lvmin(percent) => {
+            viewport_percent(*percent, ViewportKind::Large, resolver, node).min()
+        }
+        LengthValue::Vmax(percent) | LengthValue::Lvmax(percent) => {
+            viewport_percent(*percent, ViewportKind::Large, resolver, node).max()
+        }
+        LengthValue::Svw(percent) | LengthValue::Svi(percent) => {
+            viewport_percent(*percent, ViewportKind::Small, resolver, node).width
+        }
+        LengthValue::Svh(percent) | LengthValue::Svb(percent) => {
+            viewport_percent(*percent, ViewportKind::Small, resolver, node).height
         }
-        LengthValue::Vh(v) | LengthValue::Lvh(v) | LengthValue::Svh(v) | LengthValue::Dvh(v) => {
-            Subpixel::from_f32(v * resolver.viewport_height() as f32 / 100.0)
+        LengthValue::Svmin(percent) => {
+            viewport_percent(*percent, ViewportKind::Small, resolver, node).min()
         }
-        LengthValue::Vi(v) | LengthValue::Svi(v) | LengthValue::Lvi(v) | LengthValue::Dvi(v) => {
-            Subpixel::from_f32(v * resolver.viewport_width() as f32 / 100.0)
+        LengthValue::Svmax(percent) => {
+            viewport_percent(*percent, ViewportKind::Small, resolver, node).max()
         }
-        LengthValue::Vb(v) | LengthValue::Svb(v) | LengthValue::Lvb(v) | LengthValue::Dvb(v) => {
-            Subpixel::from_f32(v * resolver.viewport_height() as f32 / 100.0)
+        LengthValue::Dvw(percent) | LengthValue::Dvi(percent) => {
+            viewport_percent(*percent, ViewportKind::Dynamic, resolver, node).width
         }
-        LengthValue::Vmin(v)
-        | LengthValue::Svmin(v)
-        | LengthValue::Lvmin(v)
-        | LengthValue::Dvmin(v) => {
-            let min = resolver.viewport_width().min(resolver.viewport_height());
-            Subpixel::from_f32(v * min as f32 / 100.0)
+        LengthValue::Dvh(percent) | LengthValue::Dvb(percent) => {
+            viewport_percent(*percent, ViewportKind::Dynamic, resolver, node).height
         }
-        LengthValue::Vmax(v)
-        | LengthValue::Svmax(v)
-        | LengthValue::Lvmax(v)
-        | LengthValue::Dvmax(v) => {
-            let max = resolver.viewport_width().max(resolver.viewport_height());
-            Subpixel::from_f32(v * max as f32 / 100.0)
+        LengthValue::Dvmin(percent) => {
+            viewport_percent(*percent, ViewportKind::Dynamic, resolver, node).min()
+        }
+        LengthValue::Dvmax(percent) => {
+            viewport_percent(*percent, ViewportKind::Dynamic, resolver, node).max()
         }
 
         // Container query lengths — not yet implemented
@@ -125,6 +181,38 @@ fn resolve_length_with_context(
     }
 }
 
+/// `percent`% of each side of a viewport.
+struct ViewportPercent {
+    width: Subpixel,
+    height: Subpixel,
+}
+
+impl ViewportPercent {
+    fn min(&self) -> Subpixel {
+        self.width.min(self.height)
+    }
+
+    fn max(&self) -> Subpixel {
+        self.width.max(self.height)
+    }
+}
+
+/// `percent`% of the sides of the viewport of `kind`. The use is recorded,
+/// so that resizing that viewport re-resolves `node`.
+fn viewport_percent(
+    percent: f32,
+    kind: ViewportKind,
+    resolver: &dyn PropertyResolver,
+    node: NodeId,
+) -> ViewportPercent {
+    resolver.record_viewport_dependency(node, kind);
+    let (width, height) = resolver.viewport_size(kind);
+    ViewportPercent {
+        width: Subpixel::from_f32(percent * width as f32 / 100.0),
+        height: Subpixel::from_f32(percent * height as f32 / 100.0),
+    }
+}
+
 /// Query a node's font-size in px. Defaults to 16.
 fn query_font_size(node: NodeId, resolver: &dyn PropertyResolver) -> f32 {
     resolver
@@ -140,6 +228,18 @@ fn query_root_font_size(resolver: &dyn PropertyResolver) -> f32 {
         .map_or(16.0, |v| v.to_f32())
 }
 
+/// Query the sizes of `ch` and `ex` in a node's font, falling back to half
+/// its font-size for both.
+fn query_font_unit_sizes(node: NodeId, resolver: &dyn PropertyResolver) -> FontUnitSizes {
+    let font_size = query_font_size(node, resolver);
+    resolver
+        .font_unit_sizes(node, font_size)
+        .unwrap_or(FontUnitSizes {
+            zero_advance: font_size / 2.0,
+            x_height: font_size / 2.0,
+        })
+}
+
 /// Query a node's line-height in px.
 /// Falls back to font-size if line-height is not set.
 fn query_line_height(node: NodeId, resolver: &dyn PropertyResolver) -> f32 {
@@ -0,0 +1,306 @@
+//! Standalone CSS value parsing.
+//!
+//! Declarations are parsed by lightningcss against each property's grammar.
+//! Some inputs have no property to parse against: bare attribute values,
+//! descriptor tokens such as the parts of `@page { size }`, and values of
+//! properties lightningcss keeps as unparsed tokens. Those go through a
+//! `ValueRegistry`, which tries each registered kind of value in order.
+//!
+//! Values with several components go through the tokenizer instead:
+//! `parse_values` for text, `values_from_tokens` for the unparsed token
+//! lists lightningcss stores for properties it doesn't know. Consumers
+//! match on the typed components rather than on raw tokens.
+
+use lightningcss::properties::custom::{Token, TokenList, TokenOrValue};
+use lightningcss::stylesheet::ParserOptions;
+use lightningcss::traits::{Parse as _, ParseWithOptions as _};
+use lightningcss::values::color::CssColor;
+use lightningcss::values::ident::Ident;
+use lightningcss::values::length::LengthValue;
+use lightningcss::values::number::CSSNumber;
+use lightningcss::values::percentage::Percentage;
+use std::sync::LazyLock;
+
+/// A single parsed component value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CssValue {
+    Length(LengthValue),
+    /// A percentage, as a fraction (`50%` is `0.5`).
+    Percentage(f32),
+    Number(f32),
+    Color(CssColor),
+    /// An identifier, lowercased.
+    Keyword(String),
+    /// A quoted string, unquoted.
+    String(String),
+    /// A function other than a color, `var()` or `env()`: its lowercased
+    /// name and its argument components, commas left out.
+    Function {
+        name: String,
+        arguments: Vec<Self>,
+    },
+}
+
+/// The kinds of value a registry can recognise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    Length,
+    Percentage,
+    Number,
+    Color,
+    Keyword,
+    String,
+    Function,
+}
+
+/// Parses trimmed text as one kind of value.
+pub type ValueParseFn = fn(&str) -> Option<CssValue>;
+
+/// Value parsers tried in registration order.
+#[derive(Debug, Clone)]
+pub struct ValueRegistry {
+    parsers: Vec<(ValueKind, ValueParseFn)>,
+}
+
+/// The registry with the standard parsers.
+static STANDARD: LazyLock<ValueRegistry> = LazyLock::new(ValueRegistry::default);
+
+impl Default for ValueRegistry {
+    /// Numbers before lengths, so unitless zero is a number; colors before
+    /// keywords, so named colors are colors.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(ValueKind::Number, parse_number);
+        registry.register(ValueKind::Percentage, parse_percentage);
+        registry.register(ValueKind::Length, parse_length);
+        registry.register(ValueKind::Color, parse_color);
+        registry.register(ValueKind::Keyword, parse_keyword);
+        registry.register(ValueKind::String, parse_string);
+        registry.register(ValueKind::Function, parse_function);
+        registry
+    }
+}
+
+impl ValueRegistry {
+    /// A registry that recognises nothing.
+    pub const fn empty() -> Self {
+        Self {
+            parsers: Vec::new(),
+        }
+    }
+
+    /// The shared registry with the standard parsers.
+    pub fn standard() -> &'static Self {
+        &STANDARD
+    }
+
+    /// Register `parser` for `kind`, replacing any earlier parser for the
+    /// same kind in place. New kinds are tried last.
+    pub fn register(&mut self, kind: ValueKind, parser: ValueParseFn) {
+        match self
+            .parsers
+            .iter_mut()
+            .find(|(existing, _)| *existing == kind)
+        {
+            Some(entry) => entry.1 = parser,
+            None => self.parsers.push((kind, parser)),
+        }
+    }
+
+    /// Parse `text` with the first registered parser that accepts it.
+    pub fn parse(&self, text: &str) -> Option<CssValue> {
+        let text = text.trim();
+        self.parsers.iter().find_map(|(_, parser)| parser(text))
+    }
+
+    /// Parse `text` as a value of `kind` only.
+    pub fn parse_as(&self, kind: ValueKind, text: &str) -> Option<CssValue> {
+        let text = text.trim();
+        self.parsers
+            .iter()
+            .find(|(existing, _)| *existing == kind)
+            .and_then(|(_, parser)| parser(text))
+    }
+}
+
+/// Parse `text` with the standard registry.
+pub fn parse_value(text: &str) -> Option<CssValue> {
+    ValueRegistry::standard().parse(text)
+}
+
+/// The component values of `text`, tokenized. Whitespace, commas and
+/// tokens no value kind covers are left out; unparsable text gives none.
+pub fn parse_values(text: &str) -> Vec<CssValue> {
+    TokenList::parse_string_with_options(text, ParserOptions::default())
+        .map(|tokens| values_from_tokens(&tokens))
+        .unwrap_or_default()
+}
+
+/// The component values of an unparsed token list, as `parse_values`.
+pub fn values_from_tokens(tokens: &TokenList<'_>) -> Vec<CssValue> {
+    tokens.0.iter().filter_map(value_from_token).collect()
+}
+
+fn value_from_token(token: &TokenOrValue<'_>) -> Option<CssValue> {
+    match token {
+        TokenOrValue::Length(length) => Some(CssValue::Length(length.clone())),
+        TokenOrValue::Color(color) => Some(CssValue::Color(color.clone())),
+        TokenOrValue::Function(function) => Some(CssValue::Function {
+            name: function.name.0.to_ascii_lowercase(),
+            arguments: values_from_tokens(&function.arguments),
+        }),
+        TokenOrValue::Token(Token::Number { value, .. }) => Some(CssValue::Number(*value)),
+        TokenOrValue::Token(Token::Percentage { unit_value, .. }) => {
+            Some(CssValue::Percentage(*unit_value))
+        }
+        TokenOrValue::Token(Token::String(value)) => Some(CssValue::String(value.to_string())),
+        // Named colors are colors, as in the standard registry.
+        TokenOrValue::Token(Token::Ident(ident)) => {
+            parse_color(ident).or_else(|| Some(CssValue::Keyword(ident.to_ascii_lowercase())))
+        }
+        _ => None,
+    }
+}
+
+fn parse_number(text: &str) -> Option<CssValue> {
+    CSSNumber::parse_string(text).ok().map(CssValue::Number)
+}
+
+fn parse_percentage(text: &str) -> Option<CssValue> {
+    Percentage::parse_string(text)
+        .ok()
+        .map(|percentage| CssValue::Percentage(percentage.0))
+}
+
+fn parse_length(text: &str) -> Option<CssValue> {
+    LengthValue::parse_string(text).ok().map(CssValue::Length)
+}
+
+/// Hex and functional colors, the full named color table (matched
+/// case-insensitively), `transparent` and `currentColor`.
+fn parse_color(text: &str) -> Option<CssValue> {
+    CssColor::parse_string(text).ok().map(CssValue::Color)
+}
+
+fn parse_keyword(text: &str) -> Option<CssValue> {
+    Ident::parse_string(text)
+        .ok()
+        .map(|ident| CssValue::Keyword(ident.0.to_ascii_lowercase()))
+}
+
+fn parse_string(text: &str) -> Option<CssValue> {
+    match parse_single(text)? {
+        value @ CssValue::String(_) => Some(value),
+        _ => None,
+    }
+}
+
+fn parse_function(text: &str) -> Option<CssValue> {
+    match parse_single(text)? {
+        value @ CssValue::Function { .. } => Some(value),
+        _ => None,
+    }
+}
+
+/// The value of `text` if it is exactly one component.
+fn parse_single(text: &str) -> Option<CssValue> {
+    let tokens = TokenList::parse_string_with_options(text, ParserOptions::default()).ok()?;
+    match tokens.0.as_slice() {
+        [token] => value_from_token(token),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_kinds() {
+        assert_eq!(parse_value(" 2 "), Some(CssValue::Number(2.0)));
+        assert_eq!(parse_value("50%"), Some(CssValue::Percentage(0.5)));
+        assert_eq!(
+            parse_value("10px"),
+            Some(CssValue::Length(LengthValue::Px(10.0)))
+        );
+        assert!(matches!(parse_value("#fff"), Some(CssValue::Color(_))));
+        assert!(matches!(parse_value("red"), Some(CssValue::Color(_))));
+        assert_eq!(parse_value("Auto"), Some(CssValue::Keyword("auto".into())));
+        assert_eq!(parse_value("1px 2px"), None);
+    }
+
+    #[test]
+    fn named_colors() {
+        let rgb = |text| match parse_value(text) {
+            Some(CssValue::Color(CssColor::RGBA(rgba))) => {
+                Some((rgba.red, rgba.green, rgba.blue, rgba.alpha))
+            }
+            _ => None,
+        };
+        assert_eq!(rgb("red"), Some((255, 0, 0, 255)));
+        assert_eq!(rgb("RebeccaPurple"), Some((102, 51, 153, 255)));
+        assert_eq!(rgb("lightgoldenrodyellow"), Some((250, 250, 210, 255)));
+        assert_eq!(rgb("transparent"), Some((0, 0, 0, 0)));
+        assert_eq!(
+            parse_value("currentColor"),
+            Some(CssValue::Color(CssColor::CurrentColor))
+        );
+        assert_eq!(
+            parse_value("reddish"),
+            Some(CssValue::Keyword("reddish".into()))
+        );
+    }
+
+    #[test]
+    fn component_lists() {
+        assert_eq!(
+            parse_values("1px  solid 50% \"a\", auto"),
+            [
+                CssValue::Length(LengthValue::Px(1.0)),
+                CssValue::Keyword("solid".into()),
+                CssValue::Percentage(0.5),
+                CssValue::String("a".into()),
+                CssValue::Keyword("auto".into()),
+            ]
+        );
+        assert_eq!(
+            parse_values("Counter(item, \".\") 2"),
+            [
+                CssValue::Function {
+                    name: "counter".into(),
+                    arguments: vec![
+                        CssValue::Keyword("item".into()),
+                        CssValue::String(".".into()),
+                    ],
+                },
+                CssValue::Number(2.0),
+            ]
+        );
+        assert!(matches!(
+            parse_values("rgb(0 0 0) red").as_slice(),
+            [CssValue::Color(_), CssValue::Color(_)]
+        ));
+        assert_eq!(
+            parse_value("attr(href)"),
+            Some(CssValue::Function {
+                name: "attr".into(),
+                arguments: vec![CssValue::Keyword("href".into())],
+            })
+        );
+        assert_eq!(parse_value("'x'"), Some(CssValue::String("x".into())));
+        assert_eq!(parse_values(""), []);
+    }
+
+    #[test]
+    fn registered_parsers_replace_in_place() {
+        let mut registry = ValueRegistry::empty();
+        registry.register(ValueKind::Keyword, parse_keyword);
+        assert_eq!(registry.parse("10px"), None);
+        registry.register(ValueKind::Keyword, |_| Some(CssValue::Number(0.0)));
+        assert_eq!(
+            registry.parse_as(ValueKind::Keyword, "x"),
+            Some(CssValue::Number(0.0))
+        );
+        assert_eq!(registry.parse_as(ValueKind::Color, "red"), None);
+    }
+}
@@ -1,8 +1,8 @@
 //! TreeBuilder for streaming DOM construction via html5ever's TreeSink.
 
-use crate::types::{DomUpdate, NodeData};
+use crate::types::{DomUpdate, Namespace, NodeData};
 use html5ever::tree_builder::{NodeOrText, QuirksMode, TreeSink};
-use html5ever::{Attribute, QualName};
+use html5ever::{Attribute, QualName, namespace_url, ns};
 use lasso::ThreadedRodeo;
 use rewrite_core::NodeId;
 use std::cell::RefCell;
@@ -10,11 +10,17 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tendril::StrTendril;
 
+/// Text waiting to be appended to a parent as one text node.
+type PendingText = Option<(NodeId, String)>;
+
 /// Builder for streaming DOM construction - implements TreeSink and emits DomUpdate events.
 pub struct TreeBuilder<F: Fn(DomUpdate) -> NodeId> {
     callback: F,
     interner: Arc<ThreadedRodeo>,
     element_names: RefCell<HashMap<NodeId, Box<QualName>>>,
+    /// Text for the last child of a parent, held back until the parser
+    /// moves on so a run of text split across chunks is one text node.
+    pending_text: RefCell<PendingText>,
 }
 
 impl<F: Fn(DomUpdate) -> NodeId> TreeBuilder<F> {
@@ -26,12 +32,25 @@ impl<F: Fn(DomUpdate) -> NodeId> TreeBuilder<F> {
             callback,
             interner,
             element_names: RefCell::new(HashMap::new()),
+            pending_text: RefCell::new(None),
         }
     }
 
     fn emit(&self, update: DomUpdate) -> NodeId {
         (self.callback)(update)
     }
+
+    /// Append the held-back text, if any, as a new text node.
+    fn flush_text(&self) {
+        let pending = self.pending_text.borrow_mut().take();
+        if let Some((parent, text)) = pending {
+            let text_node = self.emit(DomUpdate::CreateNode(NodeData::Text(text.into())));
+            self.emit(DomUpdate::AppendChild {
+                parent,
+                child: text_node,
+            });
+        }
+    }
 }
 
 impl<F: Fn(DomUpdate) -> NodeId> TreeSink for TreeBuilder<F> {
@@ -42,7 +61,9 @@ impl<F: Fn(DomUpdate) -> NodeId> TreeSink for TreeBuilder<F> {
     where
         F: 'a;
 
-    fn finish(self) -> Self::Output {}
+    fn finish(self) -> Self::Output {
+        self.flush_text();
+    }
 
     fn parse_error(&self, _msg: std::borrow::Cow<'static, str>) {}
 
@@ -65,44 +86,62 @@ impl<F: Fn(DomUpdate) -> NodeId> TreeSink for TreeBuilder<F> {
         attrs: Vec<Attribute>,
         _flags: html5ever::tree_builder::ElementFlags,
     ) -> Self::Handle {
+        self.flush_text();
         let tag = self.interner.get_or_intern(name.local.as_ref());
-        let mut attributes = HashMap::new();
-        for attr in &attrs {
-            let key = self.interner.get_or_intern(attr.name.local.as_ref());
-            attributes.insert(key, attr.value.to_string().into_boxed_str());
-        }
-
-        let node = self.emit(DomUpdate::CreateNode(NodeData::Element { tag, attributes }));
+        let attributes = attrs
+            .iter()
+            .map(|attr| {
+                let key = self.interner.get_or_intern(attr.name.local.as_ref());
+                (key, attr.value.to_string().into_boxed_str())
+            })
+            .collect();
+
+        let namespace = match name.ns {
+            ns!(html) => Namespace::Html,
+            ns!(svg) => Namespace::Svg,
+            ns!(mathml) => Namespace::MathMl,
+            _ => Namespace::Other,
+        };
+
+        let node = self.emit(DomUpdate::CreateNode(NodeData::Element {
+            tag,
+            namespace,
+            attributes,
+        }));
         self.element_names.borrow_mut().insert(node, Box::new(name));
         node
     }
 
     fn create_comment(&self, text: StrTendril) -> Self::Handle {
+        self.flush_text();
         self.emit(DomUpdate::CreateNode(NodeData::Comment(
             text.to_string().into_boxed_str(),
         )))
     }
 
     fn create_pi(&self, _target: StrTendril, _data: StrTendril) -> Self::Handle {
+        self.flush_text();
         self.emit(DomUpdate::CreateNode(NodeData::Comment(Box::from(""))))
     }
 
     fn append(&self, parent: &Self::Handle, child: NodeOrText<Self::Handle>) {
         match child {
             NodeOrText::AppendNode(node) => {
+                self.flush_text();
                 self.emit(DomUpdate::AppendChild {
                     parent: *parent,
                     child: node,
                 });
             }
             NodeOrText::AppendText(text) => {
-                let text_node = self.emit(DomUpdate::CreateNode(NodeData::Text(
-                    text.to_string().into_boxed_str(),
-                )));
-                self.emit(DomUpdate::AppendChild {
-                    parent: *parent,
-                    child: text_node,
-                });
+                if let Some((pending_parent, pending)) = &mut *self.pending_text.borrow_mut()
+                    && pending_parent == parent
+                {
+                    pending.push_str(&text);
+                    return;
+                }
+                self.flush_text();
+                *self.pending_text.borrow_mut() = Some((*parent, text.to_string()));
             }
         }
     }
@@ -134,6 +173,10 @@ impl<F: Fn(DomUpdate) -> NodeId> TreeSink for TreeBuilder<F> {
 
     fn set_quirks_mode(&self, _mode: QuirksMode) {}
 
+    fn pop(&self, _node: &Self::Handle) {
+        self.flush_text();
+    }
+
     fn append_before_sibling(&self, _sibling: &Self::Handle, _new_node: NodeOrText<Self::Handle>) {}
 
     fn add_attrs_if_missing(&self, _target: &Self::Handle, _attrs: Vec<Attribute>) {}
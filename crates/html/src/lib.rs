@@ -2,9 +2,10 @@
 
 mod builder;
 mod parser;
+mod path;
 mod tree;
 mod types;
 
 pub use parser::HtmlParser;
-pub use tree::DomTree;
-pub use types::{DomUpdate, NodeData};
+pub use tree::{AttributeObserver, DomTree};
+pub use types::{Attributes, DomUpdate, ElementState, Namespace, NodeData};
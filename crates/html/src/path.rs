@@ -0,0 +1,74 @@
+//! DOM paths for diagnostics.
+//!
+//! Node IDs are allocation order, so the same element gets a different ID
+//! as soon as the document, or the order it streams in, changes, and a
+//! log line naming `node 412` can't be matched to anything. A path names
+//! the node by where it sits instead, in selector syntax:
+//! `html > body > div#app > p:nth-child(2)`.
+
+use crate::tree::DomTree;
+use crate::types::NodeData;
+use rewrite_core::NodeId;
+
+impl DomTree {
+    /// The path to `node` from its root, one step per ancestor.
+    ///
+    /// An element is named by its tag, plus its `id` if it has one, or
+    /// else its position among its parent's element children if a
+    /// sibling has the same tag. Text and comment nodes end the path as
+    /// `#text` and `#comment`; the document node isn't named.
+    pub fn node_path(&self, node: NodeId) -> String {
+        let mut steps = Vec::new();
+        let mut current = Some(node);
+        while let Some(id) = current {
+            if let Some(step) = self.path_step(id) {
+                steps.push(step);
+            }
+            current = self.parent(id);
+        }
+        steps.reverse();
+        steps.join(" > ")
+    }
+
+    fn path_step(&self, node: NodeId) -> Option<String> {
+        let (tag, attributes) = match self.get_node(node)? {
+            NodeData::Element {
+                tag, attributes, ..
+            } => (self.interner.resolve(tag), attributes),
+            NodeData::Text(_) => return Some("#text".to_owned()),
+            NodeData::Comment(_) => return Some("#comment".to_owned()),
+            NodeData::Document => return None,
+        };
+        if let Some(id) = self
+            .interner
+            .get("id")
+            .and_then(|name| attributes.get(&name))
+            .filter(|id| !id.is_empty())
+        {
+            return Some(format!("{tag}#{id}"));
+        }
+        let Some(parent) = self.parent(node) else {
+            return Some(tag.to_owned());
+        };
+        // `children` runs in reverse DOM order.
+        let elements: Vec<(NodeId, &str)> = self
+            .children(parent)
+            .filter_map(|child| match self.get_node(child)? {
+                NodeData::Element { tag: sibling, .. } => {
+                    Some((child, self.interner.resolve(sibling)))
+                }
+                _ => None,
+            })
+            .collect();
+        if elements
+            .iter()
+            .filter(|&&(_, sibling)| sibling == tag)
+            .count()
+            < 2
+        {
+            return Some(tag.to_owned());
+        }
+        let position = elements.len() - elements.iter().position(|&(child, _)| child == node)?;
+        Some(format!("{tag}:nth-child({position})"))
+    }
+}
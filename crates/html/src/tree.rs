@@ -1,10 +1,11 @@
 //! DOM tree structure using boxcar::Vec with atomic relationships.
 
-use crate::types::{DomUpdate, NodeData};
-use lasso::ThreadedRodeo;
+use crate::types::{DomUpdate, ElementState, NodeData};
+use lasso::{Spur, ThreadedRodeo};
 use rewrite_core::NodeId;
-use std::sync::Arc;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
 
 /// Sentinel value indicating no node (used for parent of root).
 const NO_NODE: u32 = u32::MAX;
@@ -26,11 +27,28 @@ impl Default for NodeRelationships {
     }
 }
 
+/// Callback invoked with the element and attribute name after an attribute changes.
+pub type AttributeObserver = Box<dyn Fn(NodeId, Spur) + Send + Sync>;
+
+/// A subscribed `AttributeObserver`, shared so it can be called without
+/// holding the subscription lock.
+type SharedObserver = Arc<dyn Fn(NodeId, Spur) + Send + Sync>;
+
+/// Attribute change subscriptions, keyed by attribute name.
+#[derive(Default)]
+struct AttributeObservers {
+    by_name: HashMap<Spur, Vec<SharedObserver>>,
+    any: Vec<SharedObserver>,
+}
+
 /// DOM tree using boxcar::Vec for lock-free concurrent appends.
 pub struct DomTree {
     pub nodes: boxcar::Vec<NodeData>,
     pub relationships: boxcar::Vec<NodeRelationships>,
     pub interner: Arc<ThreadedRodeo>,
+    attribute_observers: RwLock<AttributeObservers>,
+    /// Dynamic state of the few elements that have any.
+    element_states: RwLock<HashMap<NodeId, ElementState>>,
 }
 
 impl DomTree {
@@ -39,6 +57,25 @@ impl DomTree {
             nodes: boxcar::Vec::new(),
             relationships: boxcar::Vec::new(),
             interner,
+            attribute_observers: RwLock::new(AttributeObservers::default()),
+            element_states: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Subscribe to changes of one attribute (`Some(name)`) or of every
+    /// attribute (`None`).
+    ///
+    /// Observers run synchronously after the value is stored. An observer
+    /// subscribed while observers run is first called for the next change.
+    pub fn observe_attribute(&self, name: Option<Spur>, observer: AttributeObserver) {
+        let mut observers = self
+            .attribute_observers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let observer = SharedObserver::from(observer);
+        match name {
+            Some(name) => observers.by_name.entry(name).or_default().push(observer),
+            None => observers.any.push(observer),
         }
     }
 
@@ -60,7 +97,91 @@ impl DomTree {
                 }
                 child
             }
+            DomUpdate::SetAttribute { node, name, value } => {
+                self.set_attribute(node, name, value);
+                node
+            }
+        }
+    }
+
+    /// Set (`Some`) or remove (`None`) an attribute on an element and notify
+    /// observers of that attribute.
+    /// Returns `true` if the attribute changed. Non-element nodes are ignored.
+    pub fn set_attribute(&self, node: NodeId, name: Spur, value: Option<Box<str>>) -> bool {
+        let changed = match self.get_node(node) {
+            Some(NodeData::Element { attributes, .. }) => attributes.set(name, value),
+            _ => false,
+        };
+        if changed {
+            // Observers can set attributes or subscribe in turn, so they run
+            // after the lock is released.
+            let called: Vec<SharedObserver> = {
+                let observers = self
+                    .attribute_observers
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner);
+                let named = observers.by_name.get(&name).into_iter().flatten();
+                named.chain(&observers.any).cloned().collect()
+            };
+            for observer in called {
+                observer(node, name);
+            }
+        }
+        changed
+    }
+
+    /// The dynamic state flags set on `node` itself.
+    pub fn element_state(&self, node: NodeId) -> ElementState {
+        self.element_states
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&node)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Set (`enabled`) or clear the `state` flags of `node`. Returns `true` if
+    /// its flags changed. Selector matching isn't updated; go through the
+    /// Styler to restyle.
+    pub fn set_element_state(&self, node: NodeId, state: ElementState, enabled: bool) -> bool {
+        let mut states = self
+            .element_states
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let old = states.get(&node).copied().unwrap_or_default();
+        let new = if enabled {
+            old.union(state)
+        } else {
+            old.difference(state)
+        };
+        if new.is_empty() {
+            states.remove(&node);
+        } else {
+            states.insert(node, new);
         }
+        new != old
+    }
+
+    /// Whether `node` or one of its descendants has any of the `state`
+    /// flags set.
+    pub fn state_in_subtree(&self, node: NodeId, state: ElementState) -> bool {
+        let states = self
+            .element_states
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        states
+            .iter()
+            .filter(|(_, flags)| flags.intersects(state))
+            .any(|(&flagged, _)| {
+                let mut current = Some(flagged);
+                while let Some(ancestor) = current {
+                    if ancestor == node {
+                        return true;
+                    }
+                    current = self.parent(ancestor);
+                }
+                false
+            })
     }
 
     /// Get the parent of a node, if it has one.
@@ -131,13 +252,16 @@ impl DomTree {
                 index: 0,
             };
         };
-        // children() yields reverse DOM order. Skip until we find `node`,
-        // then everything after it in the iterator is a DOM-previous sibling.
-        let siblings: Vec<NodeId> = self
-            .children(parent)
-            .skip_while(|&n| n != node)
-            .skip(1) // skip `node` itself
-            .collect();
+        // children() yields reverse DOM order, so everything after `node`
+        // in the iterator is a DOM-previous sibling. Counted first so the
+        // list is allocated once.
+        let following = || {
+            self.children(parent)
+                .skip_while(move |&n| n != node)
+                .skip(1) // skip `node` itself
+        };
+        let mut siblings: Vec<NodeId> = Vec::with_capacity(following().count());
+        siblings.extend(following());
         let index = siblings.len();
         PrevSiblingsIter { siblings, index }
     }
@@ -174,7 +298,9 @@ impl DomTree {
         };
         // children() yields reverse DOM order. Nodes before `node`
         // in the iterator are DOM-later siblings.
-        let mut siblings: Vec<NodeId> = self.children(parent).take_while(|&n| n != node).collect();
+        let preceding = || self.children(parent).take_while(move |&n| n != node);
+        let mut siblings: Vec<NodeId> = Vec::with_capacity(preceding().count());
+        siblings.extend(preceding());
         siblings.reverse(); // reverse so closest-next comes first
         let index = 0;
         NextSiblingsIter { siblings, index }
@@ -213,8 +339,14 @@ impl Iterator for PrevSiblingsIter {
         self.index -= 1;
         Some(self.siblings[self.index])
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.index, Some(self.index))
+    }
 }
 
+impl ExactSizeIterator for PrevSiblingsIter {}
+
 /// Iterator over next siblings.
 pub struct NextSiblingsIter {
     siblings: Vec<NodeId>,
@@ -232,8 +364,15 @@ impl Iterator for NextSiblingsIter {
         self.index += 1;
         Some(node)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.siblings.len() - self.index;
+        (remaining, Some(remaining))
+    }
 }
 
+impl ExactSizeIterator for NextSiblingsIter {}
+
 impl rewrite_core::TreeAccess for DomTree {
     fn parent(&self, node: NodeId) -> Option<NodeId> {
         self.parent(node)
@@ -243,3 +382,92 @@ impl rewrite_core::TreeAccess for DomTree {
         self.children(node).collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Attributes, Namespace};
+    use std::sync::Mutex;
+    use std::sync::atomic::AtomicUsize;
+
+    fn element(tree: &DomTree) -> NodeId {
+        tree.apply_update(DomUpdate::CreateNode(NodeData::Element {
+            tag: tree.interner.get_or_intern("div"),
+            namespace: Namespace::Html,
+            attributes: Attributes::default(),
+        }))
+    }
+
+    #[test]
+    fn changes_bump_the_version_and_notify_matching_observers() {
+        let tree = DomTree::new(Arc::new(ThreadedRodeo::new()));
+        let node = element(&tree);
+        let class = tree.interner.get_or_intern("class");
+        let title = tree.interner.get_or_intern("title");
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        for (name, label) in [(Some(class), "class"), (None, "any")] {
+            let calls = calls.clone();
+            tree.observe_attribute(
+                name,
+                Box::new(move |_, changed| {
+                    calls.lock().unwrap().push((label, changed));
+                }),
+            );
+        }
+        let version = || match tree.get_node(node) {
+            Some(NodeData::Element { attributes, .. }) => attributes.version(),
+            _ => unreachable!(),
+        };
+
+        assert!(tree.set_attribute(node, class, Some("a".into())));
+        assert!(!tree.set_attribute(node, class, Some("a".into())));
+        assert_eq!(version(), 1, "setting the same value is not a change");
+        assert!(tree.set_attribute(node, title, Some("t".into())));
+        assert!(tree.set_attribute(node, class, None));
+        assert!(!tree.set_attribute(node, class, None));
+        assert_eq!(version(), 3);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                ("class", class),
+                ("any", class),
+                ("any", title),
+                ("class", class),
+                ("any", class),
+            ]
+        );
+    }
+
+    #[test]
+    fn observers_can_set_attributes_and_subscribe() {
+        let tree = Arc::new(DomTree::new(Arc::new(ThreadedRodeo::new())));
+        let node = element(&tree);
+        let class = tree.interner.get_or_intern("class");
+        let title = tree.interner.get_or_intern("title");
+        let late_calls = Arc::new(AtomicUsize::new(0));
+
+        let weak = Arc::downgrade(&tree);
+        let counter = late_calls.clone();
+        tree.observe_attribute(
+            Some(class),
+            Box::new(move |node, _| {
+                let Some(tree) = weak.upgrade() else {
+                    return;
+                };
+                tree.set_attribute(node, title, Some("observed".into()));
+                let counter = counter.clone();
+                tree.observe_attribute(
+                    Some(title),
+                    Box::new(move |_, _| {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    }),
+                );
+            }),
+        );
+
+        assert!(tree.set_attribute(node, class, Some("a".into())));
+        assert_eq!(late_calls.load(Ordering::Relaxed), 0);
+        assert!(tree.set_attribute(node, title, Some("changed".into())));
+        assert_eq!(late_calls.load(Ordering::Relaxed), 1);
+    }
+}
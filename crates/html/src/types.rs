@@ -3,13 +3,185 @@
 use lasso::Spur;
 use rewrite_core::NodeId;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+
+type AttributeMap = HashMap<Spur, Arc<str>>;
+
+/// Element attributes, keyed by interned name.
+///
+/// Nodes live in an append-only `boxcar::Vec`, so attribute mutation goes
+/// through a lock rather than `&mut` access. Values are reference counted
+/// so lookups don't borrow the lock.
+///
+/// Every change bumps a version counter, so consumers that derive data from
+/// attributes (serializers, caches) can detect staleness without copying
+/// the map.
+#[derive(Default)]
+pub struct Attributes {
+    values: RwLock<AttributeMap>,
+    version: AtomicU64,
+}
+
+impl Attributes {
+    /// Get an attribute value.
+    pub fn get(&self, name: &Spur) -> Option<Arc<str>> {
+        self.read().get(name).cloned()
+    }
+
+    /// Check whether an attribute is present.
+    pub fn contains_key(&self, name: &Spur) -> bool {
+        self.read().contains_key(name)
+    }
+
+    /// Number of attributes.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Whether the element has no attributes.
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Visit every attribute without copying the map.
+    /// Iteration order is unspecified.
+    pub fn for_each(&self, mut visit: impl FnMut(Spur, &str)) {
+        for (name, value) in &*self.read() {
+            visit(*name, value);
+        }
+    }
+
+    /// Change counter, incremented every time an attribute is set or removed.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Set (`Some`) or remove (`None`) an attribute.
+    /// Returns `true` if the stored value changed.
+    pub fn set(&self, name: Spur, value: Option<Box<str>>) -> bool {
+        let mut values = self.values.write().unwrap_or_else(PoisonError::into_inner);
+        let changed = match value {
+            Some(value) => {
+                if values.get(&name).is_some_and(|old| **old == *value) {
+                    return false;
+                }
+                values.insert(name, Arc::from(value));
+                true
+            }
+            None => values.remove(&name).is_some(),
+        };
+        if changed {
+            self.version.fetch_add(1, Ordering::AcqRel);
+        }
+        changed
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, AttributeMap> {
+        self.values.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl FromIterator<(Spur, Box<str>)> for Attributes {
+    fn from_iter<I: IntoIterator<Item = (Spur, Box<str>)>>(iter: I) -> Self {
+        Self {
+            values: RwLock::new(
+                iter.into_iter()
+                    .map(|(name, value)| (name, Arc::from(value)))
+                    .collect(),
+            ),
+            version: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Dynamic user-interaction state of an element, matched by `:hover`,
+/// `:active`, `:focus` and friends. A set of flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct ElementState(u8);
+
+impl ElementState {
+    /// The pointer is over the element.
+    pub const HOVER: Self = Self(1);
+    /// The element is being activated (a pressed button or link).
+    pub const ACTIVE: Self = Self(1 << 1);
+    /// The element has keyboard or programmatic focus.
+    pub const FOCUS: Self = Self(1 << 2);
+    /// The element is focused and the focus should be drawn.
+    pub const FOCUS_VISIBLE: Self = Self(1 << 3);
+    /// The element or one of its descendants is focused. Derived from
+    /// `FOCUS`; setting it directly has no effect on matching.
+    pub const FOCUS_WITHIN: Self = Self(1 << 4);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// The flags as a bit set, for serialization.
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether every flag of `other` is set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether any flag of `other` is set.
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    #[must_use]
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// Flags set in exactly one of `self` and `other`.
+    #[must_use]
+    pub const fn symmetric_difference(self, other: Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+
+    /// Each single flag set in `self`.
+    pub fn iter(self) -> impl Iterator<Item = Self> {
+        (0..u8::BITS)
+            .map(|bit| Self(1 << bit))
+            .filter(move |flag| self.contains(*flag))
+    }
+}
+
+/// Element namespace. Only namespaces the engine treats differently are
+/// told apart; everything else is `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Namespace {
+    #[default]
+    Html,
+    Svg,
+    MathMl,
+    Other,
+}
 
 /// Node data, varying by node type.
 pub enum NodeData {
     Document,
     Element {
         tag: Spur,
-        attributes: HashMap<Spur, Box<str>>,
+        namespace: Namespace,
+        attributes: Attributes,
     },
     Text(Box<str>),
     Comment(Box<str>),
@@ -21,4 +193,10 @@ pub enum DomUpdate {
     CreateNode(NodeData),
     /// Append child to parent.
     AppendChild { parent: NodeId, child: NodeId },
+    /// Set (`Some`) or remove (`None`) an attribute on an element.
+    SetAttribute {
+        node: NodeId,
+        name: Spur,
+        value: Option<Box<str>>,
+    },
 }
@@ -0,0 +1,163 @@
+//! Containing blocks of out-of-flow boxes, and stacking contexts.
+//!
+//! Which ancestor an absolutely positioned or fixed box is placed against
+//! and which boxes paint as a unit are decided by overlapping rules: a
+//! transform or filter makes an element both a containing block for its
+//! fixed descendants and a stacking context. Positioning and painting
+//! both ask this module so the two can't disagree.
+//!
+//! - An element with `position` other than `static` is the containing
+//!   block of its absolutely positioned descendants (CSS 2.2 §10.1).
+//! - An element with a `transform`, `perspective`, `filter` or
+//!   `backdrop-filter` other than `none` is the containing block of its
+//!   absolutely positioned and fixed descendants (CSS Transforms 1 §6,
+//!   Filter Effects 1 §5).
+//! - Otherwise fixed boxes are placed against the viewport, and absolutely
+//!   positioned boxes against the initial containing block.
+
+use lightningcss::properties::effects::FilterList;
+use lightningcss::properties::position::{Position, ZIndex};
+use lightningcss::properties::transform::Perspective;
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::vendor_prefix::VendorPrefix;
+use rewrite_core::{NodeId, PropertyResolver};
+
+use crate::queries::DisplayType;
+
+/// The box an out-of-flow box is positioned and sized against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainingBlock {
+    /// The viewport (for `fixed`) or the initial containing block (for
+    /// `absolute`), both the size of the viewport at the origin.
+    Viewport,
+    /// The padding box of an ancestor element.
+    Element(NodeId),
+}
+
+/// The CSS `position` of a node.
+fn position_of(node: NodeId, ctx: &dyn PropertyResolver) -> Position {
+    match ctx.get_css_property(node, &PropertyId::Position) {
+        Some(Property::Position(position)) => position,
+        _ => Position::Static,
+    }
+}
+
+/// Whether `node` has a transform, perspective or filter, each of which
+/// makes it a containing block for all out-of-flow descendants and a
+/// stacking context.
+pub fn has_transform_or_filter(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    let prefix = VendorPrefix::None;
+    let transformed = matches!(
+        ctx.get_css_property(node, &PropertyId::Transform(prefix)),
+        Some(Property::Transform(list, _)) if !list.0.is_empty()
+    );
+    let perspective = matches!(
+        ctx.get_css_property(node, &PropertyId::Perspective(prefix)),
+        Some(Property::Perspective(Perspective::Length(_), _))
+    );
+    let filtered = [
+        PropertyId::Filter(prefix),
+        PropertyId::BackdropFilter(prefix),
+    ]
+    .iter()
+    .any(|prop_id| {
+        matches!(
+            ctx.get_css_property(node, prop_id),
+            Some(
+                Property::Filter(FilterList::Filters(_), _)
+                    | Property::BackdropFilter(FilterList::Filters(_), _)
+            )
+        )
+    });
+    transformed || perspective || filtered
+}
+
+/// Whether a change of `prop_id` on an element can change the containing
+/// block of its out-of-flow descendants.
+pub fn affects_containing_blocks(prop_id: &PropertyId<'static>) -> bool {
+    matches!(
+        prop_id,
+        PropertyId::Position
+            | PropertyId::Transform(_)
+            | PropertyId::Perspective(_)
+            | PropertyId::Filter(_)
+            | PropertyId::BackdropFilter(_)
+    )
+}
+
+/// Whether `node` is the containing block of absolutely positioned
+/// descendants.
+pub fn is_absolute_containing_block(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    is_positioned(node, ctx) || has_transform_or_filter(node, ctx)
+}
+
+/// Whether `node` is the containing block of fixed descendants.
+pub fn is_fixed_containing_block(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    has_transform_or_filter(node, ctx)
+}
+
+/// The containing block of an absolutely positioned or fixed `node`: the
+/// nearest ancestor that establishes one for its `position`. Boxes that
+/// aren't out of flow get `Viewport`, like any box without such an
+/// ancestor.
+pub fn containing_block(node: NodeId, ctx: &dyn PropertyResolver) -> ContainingBlock {
+    let establishes: fn(NodeId, &dyn PropertyResolver) -> bool = match position_of(node, ctx) {
+        Position::Absolute => is_absolute_containing_block,
+        Position::Fixed => is_fixed_containing_block,
+        _ => return ContainingBlock::Viewport,
+    };
+    let mut current = node;
+    while let Some(parent) = ctx.parent(current) {
+        if parent == current || parent == NodeId::ROOT {
+            break;
+        }
+        if establishes(parent, ctx) {
+            return ContainingBlock::Element(parent);
+        }
+        current = parent;
+    }
+    ContainingBlock::Viewport
+}
+
+/// Whether `node` establishes a stacking context (CSS 2.2 §9.9.1, CSS
+/// Positioned Layout 3 §4): the root element; fixed and sticky boxes;
+/// positioned boxes and flex or grid items with an integer `z-index`;
+/// boxes with `opacity` below 1; and transformed or filtered boxes.
+pub fn creates_stacking_context(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    if ctx.parent(node) == Some(NodeId::ROOT) && ctx.is_element(node) {
+        return true;
+    }
+    if matches!(
+        position_of(node, ctx),
+        Position::Fixed | Position::Sticky(_)
+    ) {
+        return true;
+    }
+    let translucent = matches!(
+        ctx.get_css_property(node, &PropertyId::Opacity),
+        Some(Property::Opacity(opacity)) if opacity.0 < 1.0
+    );
+    used_z_index(node, ctx).is_some() || translucent || has_transform_or_filter(node, ctx)
+}
+
+/// Whether `node` has a `position` other than `static`.
+pub fn is_positioned(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    !matches!(position_of(node, ctx), Position::Static)
+}
+
+/// The integer `z-index` of `node`, if it has one and it applies: on
+/// positioned boxes and on flex and grid items. `None` for `auto`.
+pub fn used_z_index(node: NodeId, ctx: &dyn PropertyResolver) -> Option<i32> {
+    let Some(Property::ZIndex(ZIndex::Integer(z_index))) =
+        ctx.get_css_property(node, &PropertyId::ZIndex)
+    else {
+        return None;
+    };
+    let is_grid_or_flex_item = ctx.parent(node).is_some_and(|parent| {
+        matches!(
+            DisplayType::of_element(parent, ctx),
+            Some(DisplayType::Flex(..) | DisplayType::Grid)
+        )
+    });
+    (is_positioned(node, ctx) || is_grid_or_flex_item).then_some(z_index)
+}
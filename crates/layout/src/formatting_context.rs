@@ -0,0 +1,139 @@
+//! Formatting context classification.
+//!
+//! Which kind of box a node generates decides how its contents are laid
+//! out and whether margins collapse through it (CSS 2.2 §9.4, CSS Display
+//! 3 §2). Block layout asks this module whether a box is a block
+//! formatting context root; the same answer is public so tests and tools
+//! can check a tree's structure without re-deriving it.
+//!
+//! A block container establishes a new block formatting context when it is
+//! the root element, floated, absolutely positioned, `inline-block`,
+//! `flow-root`, a table cell, a flex or grid item, or has `overflow`
+//! other than `visible` (CSS 2.2 §9.4.1, CSS Display 3 §3).
+
+use lightningcss::properties::display::{Display, DisplayInside};
+use lightningcss::properties::overflow::OverflowKeyword;
+use lightningcss::properties::position::Position;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{NodeId, PropertyResolver};
+
+use crate::queries::DisplayType;
+use crate::scroll::propagates_overflow_to_viewport;
+
+/// The kind of box a node generates, as far as layout of its contents is
+/// concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormattingContext {
+    /// A block container that lays its children out in its parent's block
+    /// formatting context.
+    BlockContainer,
+    /// A block container that establishes a new block formatting context.
+    BlockFormattingContextRoot,
+    /// A flex container (`flex` or `inline-flex`).
+    Flex,
+    /// A grid container.
+    Grid,
+    /// A table, or a row group or row of one, whose grid places its cells.
+    Table,
+    /// An inline box, or text, laid out in its parent's inline formatting
+    /// context.
+    Inline,
+    /// A floated box.
+    Float,
+    /// An absolutely positioned or fixed box.
+    AbsolutelyPositioned,
+}
+
+impl FormattingContext {
+    /// Whether the box lays out its contents independently of its
+    /// surroundings, so margins and floats don't cross its edges.
+    pub fn is_independent(self) -> bool {
+        !matches!(self, Self::BlockContainer | Self::Inline)
+    }
+}
+
+/// Classify the box `node` generates, or `None` if it generates none
+/// (`display: none`).
+///
+/// Out-of-flow boxes report how they are placed rather than what they
+/// contain; use `establishes_bfc` to ask about their contents.
+pub fn formatting_context(node: NodeId, ctx: &dyn PropertyResolver) -> Option<FormattingContext> {
+    if !ctx.is_element(node) {
+        return Some(FormattingContext::Inline);
+    }
+    let display = DisplayType::of_element(node, ctx)?;
+    if is_absolutely_positioned(node, ctx) {
+        return Some(FormattingContext::AbsolutelyPositioned);
+    }
+    if ctx.float_side(node).is_some() {
+        return Some(FormattingContext::Float);
+    }
+    Some(match display {
+        DisplayType::Flex(..) => FormattingContext::Flex,
+        DisplayType::Grid => FormattingContext::Grid,
+        DisplayType::Inline => FormattingContext::Inline,
+        DisplayType::Table | DisplayType::TableRowGroup | DisplayType::TableRow => {
+            FormattingContext::Table
+        }
+        DisplayType::Block | DisplayType::TableCell if establishes_bfc(node, ctx) => {
+            FormattingContext::BlockFormattingContextRoot
+        }
+        DisplayType::Block | DisplayType::TableCell => FormattingContext::BlockContainer,
+    })
+}
+
+/// Whether `node` is a block container that establishes a new block
+/// formatting context for its contents.
+pub fn establishes_bfc(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    if !ctx.is_element(node) {
+        return false;
+    }
+    match DisplayType::of_element(node, ctx) {
+        Some(DisplayType::Block) => {}
+        // Table cells are always block formatting context roots.
+        Some(DisplayType::TableCell) => return true,
+        _ => return false,
+    }
+    let Some(parent) = ctx.parent(node) else {
+        return true;
+    };
+    parent == NodeId::ROOT
+        || is_absolutely_positioned(node, ctx)
+        || ctx.float_side(node).is_some()
+        || is_flow_root(node, ctx)
+        || has_bfc_overflow(node, ctx)
+        || matches!(
+            DisplayType::of_element(parent, ctx),
+            Some(DisplayType::Flex(..) | DisplayType::Grid)
+        )
+}
+
+/// Whether `node` has `overflow` other than `visible` that stays on the
+/// element rather than propagating to the viewport.
+pub fn has_bfc_overflow(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    // Overflow propagated to the viewport leaves the element `visible`.
+    if propagates_overflow_to_viewport(node, ctx) {
+        return false;
+    }
+    matches!(
+        ctx.get_css_property(node, &PropertyId::OverflowY),
+        Some(Property::OverflowY(keyword)) if keyword != OverflowKeyword::Visible
+    )
+}
+
+/// Whether `node` is `position: absolute` or `position: fixed`.
+fn is_absolutely_positioned(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    matches!(
+        ctx.get_css_property(node, &PropertyId::Position),
+        Some(Property::Position(Position::Absolute | Position::Fixed))
+    )
+}
+
+/// Whether `node` is `display: flow-root` or `display: inline-block`
+/// (`inline flow-root`).
+fn is_flow_root(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    matches!(
+        ctx.get_css_property(node, &PropertyId::Display),
+        Some(Property::Display(Display::Pair(pair))) if pair.inside == DisplayInside::FlowRoot
+    )
+}
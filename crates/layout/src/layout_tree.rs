@@ -91,6 +91,47 @@ impl Rect {
             && self.y < other.bottom()
             && self.bottom() > other.y
     }
+
+    /// Smallest rectangle containing both this rectangle and `other`.
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Self::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+
+    /// The area covered by both this rectangle and `other`. Empty (zero
+    /// size, at the far edge) if they don't overlap.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right()).max(x);
+        let bottom = self.bottom().min(other.bottom()).max(y);
+        Self::new(x, y, right - x, bottom - y)
+    }
+
+    /// Move the rectangle by `(dx, dy)`.
+    #[must_use]
+    pub fn translate(&self, dx: Subpixels, dy: Subpixels) -> Self {
+        Self::new(self.x + dx, self.y + dy, self.width, self.height)
+    }
+
+    /// Grow the rectangle by `amount` on every side (shrink if negative).
+    #[must_use]
+    pub fn inflate(&self, amount: Subpixels) -> Self {
+        Self::new(
+            self.x - amount,
+            self.y - amount,
+            self.width + amount + amount,
+            self.height + amount + amount,
+        )
+    }
 }
 
 /// Edge sizes (top, right, bottom, left).
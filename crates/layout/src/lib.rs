@@ -14,13 +14,29 @@ mod macros;
 pub mod queries;
 
 // Re-export query entry points
-pub use queries::{offset_query, property_query, size_query};
+pub use queries::{available_inline_size_query, offset_query, property_query, size_query};
 
 // Core layout modules
+mod containing_block;
+mod formatting_context;
 mod layout_tree;
 mod scroll;
+mod support;
 mod writing_mode;
 
+// Re-export containing block determination, shared with stacking
+pub use containing_block::{
+    ContainingBlock, affects_containing_blocks, containing_block, creates_stacking_context,
+    has_transform_or_filter, is_absolute_containing_block, is_fixed_containing_block,
+    is_positioned, used_z_index,
+};
+
+// Re-export formatting context classification
+pub use formatting_context::{FormattingContext, establishes_bfc, formatting_context};
+
+// Re-export the property support matrix
+pub use support::{PropertySupport, support_matrix, support_matrix_json};
+
 // Re-export layout tree types
 pub use layout_tree::{BoxType, EdgeSizes, LayoutBox, LayoutTreeBuilder, Rect};
 
@@ -37,8 +53,9 @@ pub use writing_mode::{
 // Re-export scroll integration
 pub use scroll::{
     EasingFunction, ScrollAlignment, ScrollAnimation, ScrollBehavior, ScrollBounds, ScrollEvent,
-    ScrollIntoViewRequest, ScrollPositionInput, ViewportScrollInput, apply_scroll_momentum,
-    calculate_scroll_into_view, can_scroll, update_sticky_scroll_state,
+    ScrollIntoViewRequest, ScrollPositionInput, ViewportOverflow, ViewportScrollInput,
+    apply_scroll_momentum, calculate_scroll_into_view, can_scroll, propagates_overflow_to_viewport,
+    update_sticky_scroll_state, viewport_overflow,
 };
 
 /// Available size for layout constraints.
@@ -6,20 +6,30 @@
 
 use lightningcss::properties::PropertyId;
 use lightningcss::values::length::LengthPercentageOrAuto;
-use rewrite_core::{Axis, Formula, NodeId, PropertyResolver, Subpixel};
+use rewrite_core::{Axis, FloatSide, Formula, NodeId, PropertyResolver, Subpixel};
 
+use super::generated::{
+    GENERATED_HEIGHT, LEADING_INDENT, TRAILING_ADVANCE, ends_generated_content,
+    has_only_generated_content, leads_generated_content,
+};
 use super::size::{content_size_query, margin_box_size_query, size_query};
+use crate::formatting_context::establishes_bfc;
 
 // ============================================================================
 // Layout participation helpers
 // ============================================================================
 
-/// Check if a node participates in layout (is a visible element, not text or display:none).
+/// Check if a node participates in flow layout (is a visible element, not
+/// text, display:none, floated or absolutely positioned).
 fn participates_in_layout(id: NodeId, ctx: &dyn PropertyResolver) -> bool {
     // Must be a DOM element (not text, comment, or document node).
     if !ctx.is_element(id) {
         return false;
     }
+    // Out-of-flow boxes don't take up space among their siblings.
+    if ctx.is_out_of_flow(id) {
+        return false;
+    }
     // display:none elements don't participate in layout.
     !matches!(
         ctx.get_css_property(id, &PropertyId::Display),
@@ -79,16 +89,58 @@ fn block_width(node: NodeId, ctx: &dyn PropertyResolver) -> &'static Formula {
     )
 }
 
-/// Per-child main-axis size query for inline line-breaking.
+/// Per-child main-axis size query for inline line-breaking. The first
+/// and last child also take the room of the container's generated boxes
+/// before and after them.
 fn inline_main_size_query(node: NodeId, ctx: &dyn PropertyResolver) -> Option<&'static Formula> {
-    if ctx.is_intrinsic(node) {
-        return Some(inline_width!());
+    let leads = leads_generated_content(node, ctx);
+    let ends = ends_generated_content(node, ctx);
+    if ctx.is_intrinsic(node) && !ctx.is_element(node) {
+        return Some(match (leads, ends) {
+            (true, true) => add!(inline_width!(), &LEADING_INDENT, &TRAILING_ADVANCE),
+            (true, false) => add!(inline_width!(), &LEADING_INDENT),
+            (false, true) => add!(inline_width!(), &TRAILING_ADVANCE),
+            (false, false) => inline_width!(),
+        });
     }
-    if matches!(
-        super::DisplayType::of_element(node, ctx),
-        Some(super::DisplayType::Inline)
-    ) {
-        return Some(inline_width!());
+    // Out-of-flow boxes take no room on the line and don't break it.
+    if ctx.is_out_of_flow(node) {
+        return Some(constant!(Subpixel::ZERO));
+    }
+    // Replaced elements sit on the line whatever their display.
+    if ctx.is_intrinsic(node)
+        || matches!(
+            super::DisplayType::of_element(node, ctx),
+            Some(super::DisplayType::Inline)
+        )
+    {
+        // Horizontal margins take room on the line too.
+        return Some(match (leads, ends) {
+            (true, true) => add!(
+                inline_width!(),
+                css_prop!(MarginLeft),
+                css_prop!(MarginRight),
+                &LEADING_INDENT,
+                &TRAILING_ADVANCE,
+            ),
+            (true, false) => add!(
+                inline_width!(),
+                css_prop!(MarginLeft),
+                css_prop!(MarginRight),
+                &LEADING_INDENT,
+            ),
+            (false, true) => add!(
+                inline_width!(),
+                css_prop!(MarginLeft),
+                css_prop!(MarginRight),
+                &TRAILING_ADVANCE,
+            ),
+            (false, false) => add!(
+                inline_width!(),
+                css_prop!(MarginLeft),
+                css_prop!(MarginRight),
+            ),
+        });
     }
     // Block-level child: return None to force line break.
     None
@@ -99,13 +151,18 @@ fn block_child_height_query(
     node: NodeId,
     ctx: &dyn PropertyResolver,
 ) -> Option<&'static Formula> {
-    if ctx.is_intrinsic(node) {
+    if ctx.is_intrinsic(node) && !ctx.is_element(node) {
         return Some(inline_height!());
     }
-    if matches!(
-        super::DisplayType::of_element(node, ctx),
-        Some(super::DisplayType::Inline)
-    ) {
+    if ctx.is_out_of_flow(node) {
+        return None;
+    }
+    if ctx.is_intrinsic(node)
+        || matches!(
+            super::DisplayType::of_element(node, ctx),
+            Some(super::DisplayType::Inline)
+        )
+    {
         return Some(inline_height!());
     }
     collapsed_margin_box_height(node, ctx)
@@ -116,6 +173,9 @@ fn collapsed_margin_box_height(
     node: NodeId,
     ctx: &dyn PropertyResolver,
 ) -> Option<&'static Formula> {
+    if ctx.layout_flags().disable_margin_collapsing {
+        return margin_box_size_query(node, ctx, Axis::Vertical);
+    }
     let prev = ctx
         .prev_siblings(node)
         .into_iter()
@@ -123,7 +183,7 @@ fn collapsed_margin_box_height(
         .unwrap_or(node);
     if prev == node {
         // First child: full margin-box.
-        return margin_box_size_query(node, ctx, Axis::Vertical);
+        return Some(first_child_margin_box_height());
     }
 
     Some(add!(
@@ -196,7 +256,58 @@ macro_rules! children_height_formula {
     };
 }
 
+/// Offset of an inline-level child (a text run, inline box or atomic
+/// inline) of a block container within the container's line boxes.
+///
+/// Children are broken onto lines the same way `children_height_formula!`
+/// breaks them, so a box sits on the line whose height it contributes to:
+/// after the earlier boxes on its line, below the lines before it. The
+/// first child starts after the container's inside marker and `::before`
+/// box.
+pub(super) fn line_box_offset(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+) -> &'static Formula {
+    match axis {
+        Axis::Horizontal if leads_generated_content(node, ctx) => {
+            add!(css_prop!(MarginLeft), &LEADING_INDENT)
+        }
+        // The child's own start margin pushes its border box along.
+        Axis::Horizontal => add!(
+            line_item_aggregate!(
+                agg: Sum,
+                rel: PrevSiblings,
+                query: inline_main_size_query,
+                item_main_size: inline_main_size_query,
+                available_main: &CONTENT_WIDTH,
+                gap: &ZERO_GAP,
+            ),
+            css_prop!(MarginLeft),
+        ),
+        Axis::Vertical => prev_lines_aggregate!(
+            line_agg: Sum,
+            within_line_agg: Max,
+            item_main_size: inline_main_size_query,
+            item_value: block_child_height_query,
+            available_main: &CONTENT_WIDTH,
+            gap: &ZERO_GAP,
+            line_gap: &ZERO_GAP,
+        ),
+    }
+}
+
 fn block_height(node: NodeId, ctx: &dyn PropertyResolver) -> &'static Formula {
+    // Generated boxes with no other content make a line of their own.
+    if has_only_generated_content(node, ctx) {
+        return add!(
+            &GENERATED_HEIGHT,
+            css_prop!(PaddingTop),
+            css_prop!(PaddingBottom),
+            css_prop!(BorderTopWidth),
+            css_prop!(BorderBottomWidth),
+        );
+    }
     let collapse_top = has_collapsing_first_child(node, ctx);
     let collapse_bottom = has_collapsing_last_child(node, ctx);
 
@@ -259,44 +370,127 @@ fn block_height(node: NodeId, ctx: &dyn PropertyResolver) -> &'static Formula {
 /// Compute block offset formula.
 pub fn block_offset(node: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> &'static Formula {
     match axis {
-        Axis::Horizontal => block_offset_x(node, ctx),
+        Axis::Horizontal => ctx
+            .float_side(node)
+            .map_or_else(|| block_offset_x(node, ctx), float_offset_x),
         Axis::Vertical => block_offset_y(node, ctx),
     }
 }
 
-fn is_element_node(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
-    ctx.is_element(node)
+/// Margin-box size of a sibling that occupies space in the block flow.
+///
+/// Floats and absolutely positioned siblings return `None`, so they are
+/// skipped when summing the siblings above a box.
+fn in_flow_margin_box_query(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+) -> Option<&'static Formula> {
+    if ctx.is_out_of_flow(node) {
+        return None;
+    }
+    let is_first = !ctx
+        .prev_siblings(node)
+        .into_iter()
+        .any(|id| participates_in_layout(id, ctx));
+    if is_first
+        && axis == Axis::Vertical
+        && !is_inline_element(node, ctx)
+        && !ctx.layout_flags().disable_margin_collapsing
+    {
+        return Some(first_child_margin_box_height());
+    }
+    margin_box_size_query(node, ctx, axis)
 }
 
-fn has_bfc_overflow(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
-    if let Some(overflow) = ctx.get_css_property(node, &PropertyId::OverflowY) {
-        use lightningcss::properties::overflow::OverflowKeyword;
-        if matches!(
-            &overflow,
-            lightningcss::properties::Property::OverflowY(kw)
-                if !matches!(kw, OverflowKeyword::Visible)
-        ) {
-            return true;
-        }
+/// Margin-box height of the first in-flow child.
+///
+/// Its top margin is the one collapsed with its own first child's, which
+/// is what its container subtracts when that margin collapses through the
+/// container too.
+fn first_child_margin_box_height() -> &'static Formula {
+    add!(
+        related!(Self_, size_query, Axis::Vertical),
+        related!(Self_, effective_margin_top_query, Axis::Vertical),
+        css_prop!(MarginBottom),
+    )
+}
+
+/// Margin-box width of an earlier left float.
+fn left_float_width_query(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+) -> Option<&'static Formula> {
+    if ctx.float_side(node) != Some(FloatSide::Left) {
+        return None;
+    }
+    margin_box_size_query(node, ctx, axis)
+}
+
+/// Margin-box width of an earlier right float.
+fn right_float_width_query(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+) -> Option<&'static Formula> {
+    if ctx.float_side(node) != Some(FloatSide::Right) {
+        return None;
+    }
+    margin_box_size_query(node, ctx, axis)
+}
+
+/// Horizontal offset of a float: against its side of the container's
+/// content box, after the earlier floats on the same side. Vertically a
+/// float keeps its static position (`block_offset_y`).
+///
+/// Floats are excluded from the in-flow stacking of their siblings, but
+/// in-flow line boxes are not yet shortened around them.
+fn float_offset_x(side: FloatSide) -> &'static Formula {
+    match side {
+        FloatSide::Left => add!(
+            css_prop!(MarginLeft),
+            aggregate!(Sum, PrevSiblings, left_float_width_query, Axis::Horizontal),
+        ),
+        FloatSide::Right => sub!(
+            related!(Parent, content_size_query, Axis::Horizontal),
+            related!(Self_, size_query, Axis::Horizontal),
+            css_prop!(MarginRight),
+            aggregate!(Sum, PrevSiblings, right_float_width_query, Axis::Horizontal),
+        ),
     }
-    false
+}
+
+fn is_element_node(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    ctx.is_element(node)
 }
 
 fn establishes_formatting_context(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
     matches!(
         super::DisplayType::of_element(node, ctx),
-        Some(super::DisplayType::Flex(_, _) | super::DisplayType::Grid)
+        Some(
+            super::DisplayType::Flex(_, _)
+                | super::DisplayType::Grid
+                | super::DisplayType::Table
+                | super::DisplayType::TableCell
+        )
     )
 }
 
 fn prevents_top_margin_collapse(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    if ctx.layout_flags().disable_margin_collapsing {
+        return true;
+    }
     let has_padding = ctx
         .get_property(node, &PropertyId::PaddingTop)
         .is_some_and(|v| v != Subpixel::ZERO);
     let has_border = ctx
         .get_property(node, &PropertyId::BorderTopWidth)
         .is_some_and(|v| v != Subpixel::ZERO);
-    has_padding || has_border || has_bfc_overflow(node, ctx) || establishes_formatting_context(node, ctx)
+    has_padding
+        || has_border
+        || establishes_bfc(node, ctx)
+        || establishes_formatting_context(node, ctx)
 }
 
 fn has_collapsing_first_child(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
@@ -308,6 +502,9 @@ fn has_collapsing_first_child(node: NodeId, ctx: &dyn PropertyResolver) -> bool
 }
 
 fn prevents_bottom_margin_collapse(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    if ctx.layout_flags().disable_margin_collapsing {
+        return true;
+    }
     if ctx.get_css_property(node, &PropertyId::Height).is_some() {
         return true;
     }
@@ -317,7 +514,7 @@ fn prevents_bottom_margin_collapse(node: NodeId, ctx: &dyn PropertyResolver) ->
     let has_border = ctx
         .get_property(node, &PropertyId::BorderBottomWidth)
         .is_some_and(|v| v != Subpixel::ZERO);
-    has_padding || has_border || has_bfc_overflow(node, ctx)
+    has_padding || has_border || establishes_bfc(node, ctx)
 }
 
 fn has_collapsing_last_child(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
@@ -333,17 +530,32 @@ fn last_child_margin_bottom_query(
     ctx: &dyn PropertyResolver,
     _axis: Axis,
 ) -> Option<&'static Formula> {
-    if !is_element_node(node, ctx) {
+    if !is_element_node(node, ctx) || ctx.is_out_of_flow(node) {
         return None;
     }
     let next = ctx.next_siblings(node);
-    let has_next_element = next.iter().any(|&sib| is_element_node(sib, ctx));
+    let has_next_element = next
+        .iter()
+        .any(|&sib| is_element_node(sib, ctx) && !ctx.is_out_of_flow(sib));
     if has_next_element {
         return None;
     }
+    // A line box separates an inline child's margins from its parent's.
+    if is_inline_element(node, ctx) {
+        return Some(constant!(Subpixel::ZERO));
+    }
     Some(css_prop!(MarginBottom))
 }
 
+/// Check if a node is a non-replaced inline element, whose vertical
+/// margins never collapse with its container's.
+fn is_inline_element(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    matches!(
+        super::DisplayType::of_element(node, ctx),
+        Some(super::DisplayType::Inline)
+    )
+}
+
 fn effective_margin_top_query(
     node: NodeId,
     ctx: &dyn PropertyResolver,
@@ -364,7 +576,7 @@ fn first_child_margin_top_query(
     ctx: &dyn PropertyResolver,
     _axis: Axis,
 ) -> Option<&'static Formula> {
-    if !is_element_node(node, ctx) {
+    if !is_element_node(node, ctx) || ctx.is_out_of_flow(node) {
         return None;
     }
     let prev = ctx
@@ -375,6 +587,9 @@ fn first_child_margin_top_query(
     if prev != node {
         return None; // Not the first child
     }
+    if is_inline_element(node, ctx) {
+        return Some(constant!(Subpixel::ZERO));
+    }
     effective_margin_top_query(node, ctx, Axis::Vertical)
 }
 
@@ -422,10 +637,18 @@ fn block_offset_y(node: NodeId, ctx: &dyn PropertyResolver) -> &'static Formula
         if prevents_top_margin_collapse(parent, ctx) {
             return add!(
                 related!(Self_, effective_margin_top_query, Axis::Vertical),
-                aggregate!(Sum, PrevSiblings, margin_box_size_query, Axis::Vertical),
+                aggregate!(Sum, PrevSiblings, in_flow_margin_box_query, Axis::Vertical),
             );
         }
-        return aggregate!(Sum, PrevSiblings, margin_box_size_query, Axis::Vertical);
+        return aggregate!(Sum, PrevSiblings, in_flow_margin_box_query, Axis::Vertical);
+    }
+
+    if ctx.layout_flags().disable_margin_collapsing {
+        // Sibling margins stack: previous margin boxes plus our own top margin.
+        return add!(
+            aggregate!(Sum, PrevSiblings, in_flow_margin_box_query, Axis::Vertical),
+            css_prop!(MarginTop),
+        );
     }
 
     let parent = ctx.parent(node).unwrap_or(NodeId(0));
@@ -434,7 +657,7 @@ fn block_offset_y(node: NodeId, ctx: &dyn PropertyResolver) -> &'static Formula
     if parent_collapses {
         add!(
             sub!(
-                aggregate!(Sum, PrevSiblings, margin_box_size_query, Axis::Vertical),
+                aggregate!(Sum, PrevSiblings, in_flow_margin_box_query, Axis::Vertical),
                 related_val!(PrevSibling, css_prop!(MarginBottom)),
                 aggregate!(
                     Max,
@@ -461,7 +684,7 @@ fn block_offset_y(node: NodeId, ctx: &dyn PropertyResolver) -> &'static Formula
     } else {
         add!(
             sub!(
-                aggregate!(Sum, PrevSiblings, margin_box_size_query, Axis::Vertical),
+                aggregate!(Sum, PrevSiblings, in_flow_margin_box_query, Axis::Vertical),
                 related_val!(PrevSibling, css_prop!(MarginBottom)),
             ),
             max!(
@@ -0,0 +1,155 @@
+//! Custom layout formulas.
+//!
+//! A `display: layout(<name>)` box hands its in-flow element children to
+//! the embedder's `CustomLayout`, which returns their positions and the
+//! box's content height. Both are read through imperative formulas that
+//! run the layout for the box; text and out-of-flow children keep their
+//! block positions.
+
+use lightningcss::properties::PropertyId;
+use rewrite_core::{
+    Axis, Formula, LayoutChild, LayoutConstraints, LayoutFragment, LayoutFragments, NodeId,
+    PropertyResolver, Subpixel,
+};
+
+use super::size::{content_size_query, size_query};
+
+/// Content-box offset of each child of a custom layout box.
+static CUSTOM_OFFSET_X: Formula = Formula::Imperative(custom_offset_x_impl);
+static CUSTOM_OFFSET_Y: Formula = Formula::Imperative(custom_offset_y_impl);
+
+/// Border-box height of a custom layout box.
+static CUSTOM_HEIGHT: Formula = Formula::Imperative(custom_height_impl);
+
+/// Whether `node` is positioned by its parent's custom layout.
+pub(super) fn is_custom_layout_child(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    ctx.is_element(node)
+        && !ctx.is_out_of_flow(node)
+        && ctx
+            .parent(node)
+            .is_some_and(|parent| ctx.custom_layout(parent).is_some())
+}
+
+/// Local offset of a child of a custom layout box.
+pub(super) fn custom_offset(axis: Axis) -> &'static Formula {
+    match axis {
+        Axis::Horizontal => &CUSTOM_OFFSET_X,
+        Axis::Vertical => &CUSTOM_OFFSET_Y,
+    }
+}
+
+/// Height of a custom layout box without an explicit `height`.
+pub(super) fn custom_height() -> &'static Formula {
+    &CUSTOM_HEIGHT
+}
+
+fn custom_offset_x_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let fragments = run_layout(ctx.parent(node)?, ctx, resolve)?;
+    Some(placements(node, &fragments, |fragment| {
+        fragment.inline_offset
+    }))
+}
+
+fn custom_offset_y_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let fragments = run_layout(ctx.parent(node)?, ctx, resolve)?;
+    Some(placements(node, &fragments, |fragment| {
+        fragment.block_offset
+    }))
+}
+
+fn custom_height_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let fragments = run_layout(node, ctx, resolve)?;
+    let decoration = [
+        PropertyId::PaddingTop,
+        PropertyId::PaddingBottom,
+        PropertyId::BorderTopWidth,
+        PropertyId::BorderBottomWidth,
+    ]
+    .iter()
+    .filter_map(|prop_id| ctx.get_property(node, prop_id))
+    .fold(Subpixel::ZERO, |acc, val| acc + val);
+    Some(vec![(
+        node,
+        fragments.block_size.max(Subpixel::ZERO) + decoration,
+    )])
+}
+
+/// One offset per placed child, with `node` at the origin if the layout
+/// left it out.
+fn placements(
+    node: NodeId,
+    fragments: &LayoutFragments,
+    offset: fn(&LayoutFragment) -> Subpixel,
+) -> Vec<(NodeId, Subpixel)> {
+    let mut values: Vec<(NodeId, Subpixel)> = fragments
+        .fragments
+        .iter()
+        .map(|fragment| (fragment.node, offset(fragment)))
+        .collect();
+    if !values.iter().any(|&(placed, _)| placed == node) {
+        values.push((node, Subpixel::ZERO));
+    }
+    values
+}
+
+/// Run `container`'s custom layout over its sized in-flow element
+/// children.
+fn run_layout(
+    container: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<LayoutFragments> {
+    let layout = ctx.custom_layout(container)?;
+    let available_inline_size = resolve(
+        content_size_query(container, ctx, Axis::Horizontal)?,
+        container,
+    )?;
+    // Only an explicit height is known before the layout runs; an auto
+    // one is what the layout computes.
+    let fixed_block_size = if ctx
+        .get_css_property(container, &PropertyId::Height)
+        .is_some()
+    {
+        content_size_query(container, ctx, Axis::Vertical)
+            .and_then(|formula| resolve(formula, container))
+    } else {
+        None
+    };
+
+    let mut children = Vec::new();
+    for child in ctx.children(container).into_iter().rev() {
+        if !ctx.is_element(child) || ctx.is_out_of_flow(child) {
+            continue;
+        }
+        let (Some(width), Some(height)) = (
+            size_query(child, ctx, Axis::Horizontal),
+            size_query(child, ctx, Axis::Vertical),
+        ) else {
+            // `display: none`.
+            continue;
+        };
+        children.push(LayoutChild {
+            node: child,
+            inline_size: resolve(width, child).unwrap_or(Subpixel::ZERO),
+            block_size: resolve(height, child).unwrap_or(Subpixel::ZERO),
+        });
+    }
+
+    let constraints = LayoutConstraints {
+        available_inline_size,
+        fixed_block_size,
+    };
+    Some(layout.layout(&constraints, &children))
+}
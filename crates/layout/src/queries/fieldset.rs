@@ -0,0 +1,127 @@
+//! Fieldset and rendered legend formulas (HTML §15.3.13).
+//!
+//! A fieldset's rendered legend (see `PropertyResolver::is_rendered_legend`)
+//! is out of flow: it sits over the fieldset's top border, centered on it,
+//! and shrinks to fit its content. When the legend is taller than the
+//! border, the border is painted lower and the fieldset's content starts
+//! below the legend; the difference is the legend's overhang, which adds to
+//! the fieldset's height and to the offset of its in-flow content.
+
+use rewrite_core::{Axis, Formula, NodeId, PropertyResolver, Subpixel};
+
+use super::DisplayType;
+use super::size::{content_size_query, margin_box_size_query};
+
+/// How far the rendered legend's margin box extends below the top border:
+/// `max(0, legend margin-box height - border-top-width)`, on the fieldset.
+fn legend_overhang() -> &'static Formula {
+    max!(
+        sub!(
+            aggregate!(Max, Children, legend_margin_box_query, Axis::Vertical),
+            css_prop!(BorderTopWidth),
+        ),
+        constant!(Subpixel::ZERO),
+    )
+}
+
+/// Whether `node` is a fieldset with a rendered legend.
+pub(super) fn has_rendered_legend(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    ctx.children(node)
+        .into_iter()
+        .any(|child| ctx.is_rendered_legend(child))
+}
+
+/// Whether `node` is in-flow content of a fieldset with a rendered legend,
+/// and so starts below the legend's overhang.
+pub(super) fn is_below_legend(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    !ctx.is_rendered_legend(node)
+        && ctx
+            .parent(node)
+            .is_some_and(|parent| has_rendered_legend(parent, ctx))
+}
+
+/// Margin-box height of a rendered legend, `None` for other children.
+fn legend_margin_box_query(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+) -> Option<&'static Formula> {
+    if !ctx.is_rendered_legend(node) {
+        return None;
+    }
+    margin_box_size_query(node, ctx, axis)
+}
+
+/// Query returning the legend overhang of a fieldset, `None` if it has no
+/// rendered legend.
+pub(super) fn legend_overhang_query(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    _axis: Axis,
+) -> Option<&'static Formula> {
+    has_rendered_legend(node, ctx).then(legend_overhang)
+}
+
+/// Query returning a fieldset's height as an ordinary block, before the
+/// legend overhang is added.
+fn flow_height_query(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+) -> Option<&'static Formula> {
+    DisplayType::Block.size(node, ctx, axis)
+}
+
+/// Auto height of a fieldset with a rendered legend.
+pub(super) fn fieldset_height() -> &'static Formula {
+    add!(
+        related!(Self_, flow_height_query, Axis::Vertical),
+        related!(Self_, legend_overhang_query, Axis::Vertical),
+    )
+}
+
+/// Auto width of a rendered legend: its max-content width, limited to the
+/// fieldset's content box.
+pub(super) fn legend_width() -> &'static Formula {
+    min!(
+        add!(
+            max_content_width!(),
+            css_prop!(PaddingLeft),
+            css_prop!(PaddingRight),
+            css_prop!(BorderLeftWidth),
+            css_prop!(BorderRightWidth),
+        ),
+        sub!(
+            related!(Parent, content_size_query, Axis::Horizontal),
+            css_prop!(MarginLeft),
+            css_prop!(MarginRight),
+        ),
+    )
+}
+
+/// Offset of a rendered legend's border box within the fieldset's content box.
+///
+/// The legend's margin box is centered on the top border, or starts at the
+/// top of the border box when it is taller than the border.
+pub(super) fn legend_offset(axis: Axis) -> &'static Formula {
+    match axis {
+        Axis::Horizontal => css_prop!(MarginLeft),
+        Axis::Vertical => sub!(
+            add!(
+                max!(
+                    div!(
+                        sub!(
+                            related_val!(Parent, css_prop!(BorderTopWidth)),
+                            related!(Self_, margin_box_size_query, Axis::Vertical),
+                        ),
+                        constant!(Subpixel::raw(2)),
+                    ),
+                    constant!(Subpixel::ZERO),
+                ),
+                css_prop!(MarginTop),
+            ),
+            related_val!(Parent, css_prop!(PaddingTop)),
+            related_val!(Parent, css_prop!(BorderTopWidth)),
+        ),
+    }
+}
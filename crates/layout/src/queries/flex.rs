@@ -13,12 +13,13 @@
 //!
 //! # Supported features
 //!
-//! - flex-grow, flex-shrink, flex-basis
+//! - flex-grow, flex-shrink, flex-basis (percentages against the container's
+//!   main-axis content box)
 //! - flex-direction (row, column, row-reverse, column-reverse)
 //! - flex-wrap (wrap, nowrap, wrap-reverse)
 //! - justify-content (flex-start, flex-end, center, space-between, space-around, space-evenly)
 //! - align-items, align-self (flex-start, flex-end, center, stretch)
-//! - gap (row-gap, column-gap)
+//! - gap (row-gap, column-gap), resolved by the `gap` module
 
 use lightningcss::properties::flex::{FlexDirection, FlexWrap};
 use lightningcss::properties::{Property, PropertyId};
@@ -26,15 +27,56 @@ use lightningcss::values::length::LengthPercentageOrAuto;
 use lightningcss::vendor_prefix::VendorPrefix;
 use rewrite_core::{Axis, Formula, NodeId, PropertyResolver, QueryFn, Subpixel};
 
-use super::size::size_query;
+use super::gap::{COLUMN_GAP, ROW_GAP, resolve_gap};
+use super::size::{content_size_query, has_definite_size, size_query};
 
 // ============================================================================
 // Static formulas for flex CSS properties
 // ============================================================================
 
-/// flex-basis formula (resolves length/percentage, None if auto).
+/// flex-basis formula (resolves lengths, None if auto or a percentage).
 static FLEX_BASIS: Formula = Formula::CssValue(PropertyId::FlexBasis(VendorPrefix::None));
 
+/// Percentage flex-basis of an item in a row container.
+static FLEX_BASIS_PERCENT_WIDTH: Formula = Formula::Imperative(flex_basis_percent_width_impl);
+
+/// Percentage flex-basis of an item in a column container.
+static FLEX_BASIS_PERCENT_HEIGHT: Formula = Formula::Imperative(flex_basis_percent_height_impl);
+
+fn flex_basis_percent_width_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let basis = resolve_percent_basis(node, ctx, Axis::Horizontal, resolve)?;
+    Some(vec![(node, basis)])
+}
+
+fn flex_basis_percent_height_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let basis = resolve_percent_basis(node, ctx, Axis::Vertical, resolve)?;
+    Some(vec![(node, basis)])
+}
+
+/// Percentage flex-basis of `node` times its container's content size
+/// along the main `axis` (CSS Flexbox §7.2.3).
+fn resolve_percent_basis(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Subpixel> {
+    let fraction = flex_basis_percentage(node, ctx)?;
+    let container = ctx.parent(node)?;
+    let basis = resolve(content_size_query(container, ctx, axis)?, container)?;
+    Some(Subpixel::from_f32(
+        basis.max(Subpixel::ZERO).to_f32() * fraction,
+    ))
+}
+
 // ============================================================================
 // Internal formula macros
 //
@@ -62,7 +104,7 @@ macro_rules! justify_free_h_row {
             related_val!(
                 Parent,
                 mul!(
-                    css_prop!(ColumnGap),
+                    &COLUMN_GAP,
                     max!(
                         sub!(
                             aggregate!(Count, OrderedChildren, always_query),
@@ -94,7 +136,7 @@ macro_rules! justify_free_v_col {
             related_val!(
                 Parent,
                 mul!(
-                    css_prop!(RowGap),
+                    &ROW_GAP,
                     max!(
                         sub!(
                             aggregate!(Count, OrderedChildren, always_query),
@@ -365,12 +407,6 @@ static COL_AVAILABLE_MAIN: Formula = Formula::BinOp(
     &Formula::CssValueOrDefault(PropertyId::BorderBottomWidth, Subpixel::ZERO),
 );
 
-/// Column gap formula (for row containers' line-breaking gap parameter).
-static COLUMN_GAP: Formula = Formula::CssValueOrDefault(PropertyId::ColumnGap, Subpixel::ZERO);
-
-/// Row gap formula (for column containers' line-breaking gap parameter).
-static ROW_GAP: Formula = Formula::CssValueOrDefault(PropertyId::RowGap, Subpixel::ZERO);
-
 // Line-breaking parameter accessors for row/column directions.
 //
 // These are expression-macros because their results are used inside other
@@ -466,7 +502,7 @@ macro_rules! lbp_baseline_query {
 // ============================================================================
 
 /// Check if the item has an explicit `flex-basis` (not `auto`).
-fn has_explicit_flex_basis(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+pub(crate) fn has_explicit_flex_basis(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
     use lightningcss::values::length::LengthPercentageOrAuto;
     match ctx.get_css_property(node, &PropertyId::FlexBasis(VendorPrefix::None)) {
         Some(lightningcss::properties::Property::FlexBasis(basis, _)) => {
@@ -476,6 +512,19 @@ fn has_explicit_flex_basis(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
     }
 }
 
+/// The fraction `flex-basis` is set to, e.g. `0.5` for `flex-basis: 50%`,
+/// or `None` if it isn't a percentage.
+fn flex_basis_percentage(node: NodeId, ctx: &dyn PropertyResolver) -> Option<f32> {
+    use lightningcss::values::percentage::DimensionPercentage;
+    match ctx.get_css_property(node, &PropertyId::FlexBasis(VendorPrefix::None)) {
+        Some(Property::FlexBasis(
+            LengthPercentageOrAuto::LengthPercentage(DimensionPercentage::Percentage(pct)),
+            _,
+        )) => Some(pct.0),
+        _ => None,
+    }
+}
+
 // ============================================================================
 // Auto margin helpers
 // ============================================================================
@@ -915,6 +964,20 @@ fn flex_item_basis_query(node: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -
     if is_flex_excluded(node, ctx) {
         return None;
     }
+    if flex_basis_percentage(node, ctx).is_some() {
+        // Against an indefinite container size a percentage behaves like
+        // `content` (CSS Flexbox §7.2.3).
+        let definite = ctx
+            .parent(node)
+            .is_some_and(|container| has_definite_size(container, ctx, axis));
+        if !definite {
+            return content_based_size(node, ctx, axis);
+        }
+        return Some(match axis {
+            Axis::Horizontal => &FLEX_BASIS_PERCENT_WIDTH,
+            Axis::Vertical => &FLEX_BASIS_PERCENT_HEIGHT,
+        });
+    }
     if has_explicit_flex_basis(node, ctx) {
         return Some(&FLEX_BASIS);
     }
@@ -1261,7 +1324,7 @@ fn flex_container_main_size(direction: FlexDirection, axis: Axis) -> &'static Fo
                 Axis::Horizontal
             ),
             mul!(
-                css_prop!(ColumnGap),
+                &COLUMN_GAP,
                 max!(
                     sub!(
                         aggregate!(Count, OrderedChildren, always_query),
@@ -1274,7 +1337,7 @@ fn flex_container_main_size(direction: FlexDirection, axis: Axis) -> &'static Fo
         (FlexDirection::Column | FlexDirection::ColumnReverse, Axis::Vertical) => add!(
             aggregate!(Sum, OrderedChildren, flex_item_basis_query, Axis::Vertical),
             mul!(
-                css_prop!(RowGap),
+                &ROW_GAP,
                 max!(
                     sub!(
                         aggregate!(Count, OrderedChildren, always_query),
@@ -1584,17 +1647,6 @@ fn resolve_container_content_main(
     Some((total - pa - pb - ba - bb).max(0.0))
 }
 
-/// Get the gap value for the main axis.
-fn resolve_main_gap(parent_id: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> f32 {
-    let gap_prop = match axis {
-        Axis::Horizontal => PropertyId::ColumnGap,
-        Axis::Vertical => PropertyId::RowGap,
-    };
-    ctx.get_property(parent_id, &gap_prop)
-        .unwrap_or(Subpixel::ZERO)
-        .to_f32()
-}
-
 /// Build the batch result Vec from resolved items, snapping to Subpixel.
 fn build_batch_result(items: &[FlexItemInfo]) -> Vec<(NodeId, Subpixel)> {
     items
@@ -1648,7 +1700,7 @@ fn flex_resolve_main_nowrap_impl(
     }
 
     let container_main = resolve_container_content_main(parent_id, ctx, axis, resolve)?;
-    let gap = resolve_main_gap(parent_id, ctx, axis);
+    let gap = resolve_gap(parent_id, ctx, axis, resolve).to_f32();
 
     resolve_flexible_lengths(&mut items, container_main, gap);
     Some(build_batch_result(&items))
@@ -1698,7 +1750,7 @@ fn flex_resolve_main_wrap_impl(
     }
 
     let container_main = resolve_container_content_main(parent_id, ctx, axis, resolve)?;
-    let gap = resolve_main_gap(parent_id, ctx, axis);
+    let gap = resolve_gap(parent_id, ctx, axis, resolve).to_f32();
 
     // Greedy line breaking: accumulate basis sizes, break when exceeding
     // available main. This matches the resolver's compute_line_assignments.
@@ -1888,7 +1940,7 @@ fn build_main_auto_margin_offset_h_row(start_auto: bool) -> &'static Formula {
             per_auto_h!(),
             mul!(
                 aggregate!(Count, OrderedPrevSiblings, always_query),
-                related_val!(Parent, css_prop!(ColumnGap))
+                related_val!(Parent, &COLUMN_GAP)
             )
         )
     } else {
@@ -1903,7 +1955,7 @@ fn build_main_auto_margin_offset_h_row(start_auto: bool) -> &'static Formula {
             aggregate!(Sum, OrderedPrevSiblings, flex_item_auto_margin_row_query),
             mul!(
                 aggregate!(Count, OrderedPrevSiblings, always_query),
-                related_val!(Parent, css_prop!(ColumnGap))
+                related_val!(Parent, &COLUMN_GAP)
             )
         )
     }
@@ -1938,7 +1990,7 @@ fn build_main_auto_margin_offset_v_col(start_auto: bool) -> &'static Formula {
             per_auto_v!(),
             mul!(
                 aggregate!(Count, OrderedPrevSiblings, always_query),
-                related_val!(Parent, css_prop!(RowGap))
+                related_val!(Parent, &ROW_GAP)
             )
         )
     } else {
@@ -1952,7 +2004,7 @@ fn build_main_auto_margin_offset_v_col(start_auto: bool) -> &'static Formula {
             aggregate!(Sum, OrderedPrevSiblings, flex_item_auto_margin_col_query),
             mul!(
                 aggregate!(Count, OrderedPrevSiblings, always_query),
-                related_val!(Parent, css_prop!(RowGap))
+                related_val!(Parent, &ROW_GAP)
             )
         )
     }
@@ -1982,7 +2034,7 @@ fn build_main_offset_normal_h_row(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(ColumnGap))
+                    related_val!(Parent, &COLUMN_GAP)
                 )
             )
         }
@@ -1999,7 +2051,7 @@ fn build_main_offset_normal_h_row(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(ColumnGap))
+                    related_val!(Parent, &COLUMN_GAP)
                 )
             )
         }
@@ -2018,7 +2070,7 @@ fn build_main_offset_normal_h_row(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(ColumnGap))
+                    related_val!(Parent, &COLUMN_GAP)
                 )
             )
         }
@@ -2036,7 +2088,7 @@ fn build_main_offset_normal_h_row(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
                     add!(
-                        related_val!(Parent, css_prop!(ColumnGap)),
+                        related_val!(Parent, &COLUMN_GAP),
                         div!(
                             max!(justify_free_h_row!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -2079,7 +2131,7 @@ fn build_main_offset_normal_h_row(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
                     add!(
-                        related_val!(Parent, css_prop!(ColumnGap)),
+                        related_val!(Parent, &COLUMN_GAP),
                         div!(
                             max!(justify_free_h_row!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -2127,7 +2179,7 @@ fn build_main_offset_normal_h_row(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(ColumnGap))
+                    related_val!(Parent, &COLUMN_GAP)
                 )
             )
         }
@@ -2152,7 +2204,7 @@ fn build_main_offset_normal_v_col(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(RowGap))
+                    related_val!(Parent, &ROW_GAP)
                 )
             )
         }
@@ -2168,7 +2220,7 @@ fn build_main_offset_normal_v_col(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(RowGap))
+                    related_val!(Parent, &ROW_GAP)
                 )
             )
         }
@@ -2187,7 +2239,7 @@ fn build_main_offset_normal_v_col(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(RowGap))
+                    related_val!(Parent, &ROW_GAP)
                 )
             )
         }
@@ -2203,7 +2255,7 @@ fn build_main_offset_normal_v_col(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
                     add!(
-                        related_val!(Parent, css_prop!(RowGap)),
+                        related_val!(Parent, &ROW_GAP),
                         div!(
                             max!(justify_free_v_col!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -2243,7 +2295,7 @@ fn build_main_offset_normal_v_col(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
                     add!(
-                        related_val!(Parent, css_prop!(RowGap)),
+                        related_val!(Parent, &ROW_GAP),
                         div!(
                             max!(justify_free_v_col!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -2288,7 +2340,7 @@ fn build_main_offset_normal_v_col(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(RowGap))
+                    related_val!(Parent, &ROW_GAP)
                 )
             )
         }
@@ -2328,7 +2380,7 @@ macro_rules! justify_free_wrap_h_row {
                 gap: lbp_main_gap!(Row),
             ),
             mul!(
-                related_val!(Parent, css_prop!(ColumnGap)),
+                related_val!(Parent, &COLUMN_GAP),
                 max!(
                     sub!(
                         line_item_aggregate!(
@@ -2373,7 +2425,7 @@ macro_rules! justify_free_wrap_v_col {
                 gap: lbp_main_gap!(Column),
             ),
             mul!(
-                related_val!(Parent, css_prop!(RowGap)),
+                related_val!(Parent, &ROW_GAP),
                 max!(
                     sub!(
                         line_item_aggregate!(
@@ -2447,7 +2499,7 @@ fn build_main_offset_wrap_h_row(jc: JustifyMode) -> &'static Formula {
             add!(
                 css_prop!(MarginLeft),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(ColumnGap)))
+                mul!(prev_count!(), related_val!(Parent, &COLUMN_GAP))
             )
         }
         JustifyMode::FlexEnd => {
@@ -2455,7 +2507,7 @@ fn build_main_offset_wrap_h_row(jc: JustifyMode) -> &'static Formula {
                 css_prop!(MarginLeft),
                 max!(justify_free_wrap_h_row!(), constant!(Subpixel::ZERO)),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(ColumnGap)))
+                mul!(prev_count!(), related_val!(Parent, &COLUMN_GAP))
             )
         }
         JustifyMode::Center => {
@@ -2466,7 +2518,7 @@ fn build_main_offset_wrap_h_row(jc: JustifyMode) -> &'static Formula {
                     constant!(Subpixel::raw(2))
                 ),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(ColumnGap)))
+                mul!(prev_count!(), related_val!(Parent, &COLUMN_GAP))
             )
         }
         JustifyMode::SpaceBetween => {
@@ -2476,7 +2528,7 @@ fn build_main_offset_wrap_h_row(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     prev_count!(),
                     add!(
-                        related_val!(Parent, css_prop!(ColumnGap)),
+                        related_val!(Parent, &COLUMN_GAP),
                         div!(
                             max!(justify_free_wrap_h_row!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -2502,7 +2554,7 @@ fn build_main_offset_wrap_h_row(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     prev_count!(),
                     add!(
-                        related_val!(Parent, css_prop!(ColumnGap)),
+                        related_val!(Parent, &COLUMN_GAP),
                         div!(
                             max!(justify_free_wrap_h_row!(), constant!(Subpixel::ZERO)),
                             max!(line_count!(), constant!(Subpixel::raw(1)))
@@ -2525,7 +2577,7 @@ fn build_main_offset_wrap_h_row(jc: JustifyMode) -> &'static Formula {
                     )
                 ),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(ColumnGap)))
+                mul!(prev_count!(), related_val!(Parent, &COLUMN_GAP))
             )
         }
     }
@@ -2582,7 +2634,7 @@ fn build_main_offset_wrap_v_col(jc: JustifyMode) -> &'static Formula {
             add!(
                 css_prop!(MarginTop),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(RowGap)))
+                mul!(prev_count!(), related_val!(Parent, &ROW_GAP))
             )
         }
         JustifyMode::FlexEnd => {
@@ -2590,7 +2642,7 @@ fn build_main_offset_wrap_v_col(jc: JustifyMode) -> &'static Formula {
                 css_prop!(MarginTop),
                 max!(justify_free_wrap_v_col!(), constant!(Subpixel::ZERO)),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(RowGap)))
+                mul!(prev_count!(), related_val!(Parent, &ROW_GAP))
             )
         }
         JustifyMode::Center => {
@@ -2601,7 +2653,7 @@ fn build_main_offset_wrap_v_col(jc: JustifyMode) -> &'static Formula {
                     constant!(Subpixel::raw(2))
                 ),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(RowGap)))
+                mul!(prev_count!(), related_val!(Parent, &ROW_GAP))
             )
         }
         JustifyMode::SpaceBetween => {
@@ -2611,7 +2663,7 @@ fn build_main_offset_wrap_v_col(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     prev_count!(),
                     add!(
-                        related_val!(Parent, css_prop!(RowGap)),
+                        related_val!(Parent, &ROW_GAP),
                         div!(
                             max!(justify_free_wrap_v_col!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -2637,7 +2689,7 @@ fn build_main_offset_wrap_v_col(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     prev_count!(),
                     add!(
-                        related_val!(Parent, css_prop!(RowGap)),
+                        related_val!(Parent, &ROW_GAP),
                         div!(
                             max!(justify_free_wrap_v_col!(), constant!(Subpixel::ZERO)),
                             max!(line_count!(), constant!(Subpixel::raw(1)))
@@ -2660,7 +2712,7 @@ fn build_main_offset_wrap_v_col(jc: JustifyMode) -> &'static Formula {
                     )
                 ),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(RowGap)))
+                mul!(prev_count!(), related_val!(Parent, &ROW_GAP))
             )
         }
     }
@@ -2734,7 +2786,7 @@ fn build_main_offset_wrap_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 content_h!(),
                 related!(Self_, flex_item_main_query, Axis::Horizontal),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(ColumnGap)))
+                mul!(prev_count!(), related_val!(Parent, &COLUMN_GAP))
             )
         }
         JustifyMode::FlexEnd => {
@@ -2742,7 +2794,7 @@ fn build_main_offset_wrap_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 content_h!(),
                 related!(Self_, flex_item_main_query, Axis::Horizontal),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(ColumnGap))),
+                mul!(prev_count!(), related_val!(Parent, &COLUMN_GAP)),
                 max!(justify_free_wrap_h_row!(), constant!(Subpixel::ZERO))
             )
         }
@@ -2751,7 +2803,7 @@ fn build_main_offset_wrap_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 content_h!(),
                 related!(Self_, flex_item_main_query, Axis::Horizontal),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(ColumnGap))),
+                mul!(prev_count!(), related_val!(Parent, &COLUMN_GAP)),
                 div!(
                     max!(justify_free_wrap_h_row!(), constant!(Subpixel::ZERO)),
                     constant!(Subpixel::raw(2))
@@ -2766,7 +2818,7 @@ fn build_main_offset_wrap_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     prev_count!(),
                     add!(
-                        related_val!(Parent, css_prop!(ColumnGap)),
+                        related_val!(Parent, &COLUMN_GAP),
                         div!(
                             max!(justify_free_wrap_h_row!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -2786,7 +2838,7 @@ fn build_main_offset_wrap_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     prev_count!(),
                     add!(
-                        related_val!(Parent, css_prop!(ColumnGap)),
+                        related_val!(Parent, &COLUMN_GAP),
                         div!(
                             max!(justify_free_wrap_h_row!(), constant!(Subpixel::ZERO)),
                             max!(line_count!(), constant!(Subpixel::raw(1)))
@@ -2807,7 +2859,7 @@ fn build_main_offset_wrap_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 content_h!(),
                 related!(Self_, flex_item_main_query, Axis::Horizontal),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(ColumnGap))),
+                mul!(prev_count!(), related_val!(Parent, &COLUMN_GAP)),
                 mul!(
                     add!(prev_count!(), constant!(Subpixel::raw(1))),
                     div!(
@@ -2885,7 +2937,7 @@ fn build_main_offset_wrap_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 content_v!(),
                 related!(Self_, flex_item_main_query, Axis::Vertical),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(RowGap)))
+                mul!(prev_count!(), related_val!(Parent, &ROW_GAP))
             )
         }
         JustifyMode::FlexEnd => {
@@ -2893,7 +2945,7 @@ fn build_main_offset_wrap_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 content_v!(),
                 related!(Self_, flex_item_main_query, Axis::Vertical),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(RowGap))),
+                mul!(prev_count!(), related_val!(Parent, &ROW_GAP)),
                 max!(justify_free_wrap_v_col!(), constant!(Subpixel::ZERO))
             )
         }
@@ -2902,7 +2954,7 @@ fn build_main_offset_wrap_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 content_v!(),
                 related!(Self_, flex_item_main_query, Axis::Vertical),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(RowGap))),
+                mul!(prev_count!(), related_val!(Parent, &ROW_GAP)),
                 div!(
                     max!(justify_free_wrap_v_col!(), constant!(Subpixel::ZERO)),
                     constant!(Subpixel::raw(2))
@@ -2917,7 +2969,7 @@ fn build_main_offset_wrap_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     prev_count!(),
                     add!(
-                        related_val!(Parent, css_prop!(RowGap)),
+                        related_val!(Parent, &ROW_GAP),
                         div!(
                             max!(justify_free_wrap_v_col!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -2937,7 +2989,7 @@ fn build_main_offset_wrap_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     prev_count!(),
                     add!(
-                        related_val!(Parent, css_prop!(RowGap)),
+                        related_val!(Parent, &ROW_GAP),
                         div!(
                             max!(justify_free_wrap_v_col!(), constant!(Subpixel::ZERO)),
                             max!(line_count!(), constant!(Subpixel::raw(1)))
@@ -2958,7 +3010,7 @@ fn build_main_offset_wrap_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 content_v!(),
                 related!(Self_, flex_item_main_query, Axis::Vertical),
                 prev_sum!(),
-                mul!(prev_count!(), related_val!(Parent, css_prop!(RowGap))),
+                mul!(prev_count!(), related_val!(Parent, &ROW_GAP)),
                 mul!(
                     add!(prev_count!(), constant!(Subpixel::raw(1))),
                     div!(
@@ -3004,7 +3056,7 @@ fn build_main_offset_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(ColumnGap))
+                    related_val!(Parent, &COLUMN_GAP)
                 )
             )
         }
@@ -3026,7 +3078,7 @@ fn build_main_offset_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(ColumnGap))
+                    related_val!(Parent, &COLUMN_GAP)
                 ),
                 max!(justify_free_h_row!(), constant!(Subpixel::ZERO))
             )
@@ -3049,7 +3101,7 @@ fn build_main_offset_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(ColumnGap))
+                    related_val!(Parent, &COLUMN_GAP)
                 ),
                 div!(
                     max!(justify_free_h_row!(), constant!(Subpixel::ZERO)),
@@ -3076,7 +3128,7 @@ fn build_main_offset_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
                     add!(
-                        related_val!(Parent, css_prop!(ColumnGap)),
+                        related_val!(Parent, &COLUMN_GAP),
                         div!(
                             max!(justify_free_h_row!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -3113,7 +3165,7 @@ fn build_main_offset_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
                     add!(
-                        related_val!(Parent, css_prop!(ColumnGap)),
+                        related_val!(Parent, &COLUMN_GAP),
                         div!(
                             max!(justify_free_h_row!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -3156,7 +3208,7 @@ fn build_main_offset_reversed_h_row(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(ColumnGap))
+                    related_val!(Parent, &COLUMN_GAP)
                 ),
                 mul!(
                     add!(
@@ -3204,7 +3256,7 @@ fn build_main_offset_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(RowGap))
+                    related_val!(Parent, &ROW_GAP)
                 )
             )
         }
@@ -3226,7 +3278,7 @@ fn build_main_offset_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(RowGap))
+                    related_val!(Parent, &ROW_GAP)
                 ),
                 max!(justify_free_v_col!(), constant!(Subpixel::ZERO))
             )
@@ -3249,7 +3301,7 @@ fn build_main_offset_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(RowGap))
+                    related_val!(Parent, &ROW_GAP)
                 ),
                 div!(
                     max!(justify_free_v_col!(), constant!(Subpixel::ZERO)),
@@ -3276,7 +3328,7 @@ fn build_main_offset_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
                     add!(
-                        related_val!(Parent, css_prop!(RowGap)),
+                        related_val!(Parent, &ROW_GAP),
                         div!(
                             max!(justify_free_v_col!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -3313,7 +3365,7 @@ fn build_main_offset_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
                     add!(
-                        related_val!(Parent, css_prop!(RowGap)),
+                        related_val!(Parent, &ROW_GAP),
                         div!(
                             max!(justify_free_v_col!(), constant!(Subpixel::ZERO)),
                             max!(
@@ -3356,7 +3408,7 @@ fn build_main_offset_reversed_v_col(jc: JustifyMode) -> &'static Formula {
                 ),
                 mul!(
                     aggregate!(Count, OrderedPrevSiblings, always_query),
-                    related_val!(Parent, css_prop!(RowGap))
+                    related_val!(Parent, &ROW_GAP)
                 ),
                 mul!(
                     add!(
@@ -3713,7 +3765,7 @@ fn build_cross_offset_wrap_row(
                             sub!(total_line_count!(), constant!(Subpixel::raw(1))),
                             constant!(Subpixel::ZERO)
                         ),
-                        related_val!(Parent, css_prop!(RowGap))
+                        related_val!(Parent, &ROW_GAP)
                     )
                 ),
                 constant!(Subpixel::ZERO)
@@ -4146,7 +4198,7 @@ fn build_cross_offset_wrap_col(
                             sub!(total_line_count!(), constant!(Subpixel::raw(1))),
                             constant!(Subpixel::ZERO)
                         ),
-                        related_val!(Parent, css_prop!(ColumnGap))
+                        related_val!(Parent, &COLUMN_GAP)
                     )
                 ),
                 constant!(Subpixel::ZERO)
@@ -0,0 +1,83 @@
+//! Gutters between items (CSS Box Alignment 3 §8).
+//!
+//! `column-gap` separates items along the horizontal axis and `row-gap`
+//! along the vertical one, in flex and grid containers alike. Percentages
+//! refer to the container's content box on the gap's own axis (§8.3).
+//! When that size depends on the container's contents (a shrink-to-fit
+//! width, an auto height) a percentage resolves to zero, both for the
+//! container's intrinsic size and for placing its items, so a gap never
+//! feeds back into the size it is a percentage of.
+
+use lightningcss::properties::align::GapValue;
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::values::percentage::DimensionPercentage;
+use rewrite_core::{Axis, Formula, NodeId, PropertyResolver, Subpixel};
+
+use super::size::{content_size_query, has_definite_size};
+
+/// `column-gap` of the node a formula is evaluated on, in px.
+pub static COLUMN_GAP: Formula = Formula::Imperative(column_gap_impl);
+
+/// `row-gap` of the node a formula is evaluated on, in px.
+pub static ROW_GAP: Formula = Formula::Imperative(row_gap_impl);
+
+fn column_gap_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    Some(vec![(
+        node,
+        resolve_gap(node, ctx, Axis::Horizontal, resolve),
+    )])
+}
+
+fn row_gap_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    Some(vec![(
+        node,
+        resolve_gap(node, ctx, Axis::Vertical, resolve),
+    )])
+}
+
+/// The gap property separating items along `axis`.
+fn gap_property(axis: Axis) -> PropertyId<'static> {
+    match axis {
+        Axis::Horizontal => PropertyId::ColumnGap,
+        Axis::Vertical => PropertyId::RowGap,
+    }
+}
+
+/// Used gap of `container` between items along `axis`, in px. `normal`
+/// is zero in flex and grid containers.
+pub fn resolve_gap(
+    container: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Subpixel {
+    let prop_id = gap_property(axis);
+    match ctx.get_css_property(container, &prop_id) {
+        Some(
+            Property::ColumnGap(GapValue::LengthPercentage(DimensionPercentage::Percentage(pct)))
+            | Property::RowGap(GapValue::LengthPercentage(DimensionPercentage::Percentage(pct))),
+        ) => {
+            if !has_definite_size(container, ctx, axis) {
+                return Subpixel::ZERO;
+            }
+            let basis = content_size_query(container, ctx, axis)
+                .and_then(|formula| resolve(formula, container))
+                .unwrap_or(Subpixel::ZERO)
+                .max(Subpixel::ZERO);
+            Subpixel::from_f32(basis.to_f32() * pct.0)
+        }
+        Some(_) => ctx
+            .get_property(container, &prop_id)
+            .unwrap_or(Subpixel::ZERO)
+            .max(Subpixel::ZERO),
+        None => Subpixel::ZERO,
+    }
+}
@@ -0,0 +1,157 @@
+//! Generated inline boxes: `::marker` (CSS Lists 3 §3), `::before` and
+//! `::after` (CSS Generated Content 3 §2).
+//!
+//! These boxes aren't nodes, so they are laid out as room taken next to
+//! the element's real children. An `outside` marker takes no room in its
+//! list item; the renderer places it in the marker area before the item's
+//! first line. An `inside` marker and a `::before` box start the element's
+//! content: its first inline-level child is pushed along its line by their
+//! width, and the room they take counts when the line is broken. An
+//! `::after` box ends the content, and its width is added to the room the
+//! last inline-level child takes. Inside an inline element, the element's
+//! own measured width includes its `::before` and `::after` boxes and
+//! every child starts after the `::before` box.
+//!
+//! A block-level first or last child moves no generated box onto a line
+//! of its own; the boxes take no room then.
+
+use lightningcss::properties::display::Display;
+use lightningcss::properties::list::ListStylePosition;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{Formula, GeneratedBox, NodeId, PropertyResolver, Subpixel};
+
+/// Width of the inside marker and `::before` box leading the line of the
+/// node a formula is evaluated on, in px.
+pub static LEADING_INDENT: Formula = Formula::Imperative(leading_indent_impl);
+
+/// Width of the `::after` box following the node a formula is evaluated
+/// on, in px.
+pub static TRAILING_ADVANCE: Formula = Formula::Imperative(trailing_advance_impl);
+
+/// Width of the `::before` box of the parent of the node a formula is
+/// evaluated on, in px.
+pub static BEFORE_ADVANCE: Formula = Formula::Imperative(before_advance_impl);
+
+/// Height of the generated boxes of an element with no other content,
+/// in px.
+pub static GENERATED_HEIGHT: Formula = Formula::Imperative(generated_height_impl);
+
+fn leading_indent_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    _resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let width = ctx.parent(node).map_or(0.0, |parent| {
+        let marker = if has_inside_marker(parent, ctx) {
+            ctx.measure_marker(parent)
+                .map_or(0.0, |marker| marker.width)
+        } else {
+            0.0
+        };
+        marker + generated_width(parent, GeneratedBox::Before, ctx)
+    });
+    Some(vec![(node, Subpixel::from_f32(width))])
+}
+
+fn trailing_advance_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    _resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let width = ctx.parent(node).map_or(0.0, |parent| {
+        generated_width(parent, GeneratedBox::After, ctx)
+    });
+    Some(vec![(node, Subpixel::from_f32(width))])
+}
+
+fn before_advance_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    _resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let width = ctx.parent(node).map_or(0.0, |parent| {
+        generated_width(parent, GeneratedBox::Before, ctx)
+    });
+    Some(vec![(node, Subpixel::from_f32(width))])
+}
+
+fn generated_height_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    _resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let height = [GeneratedBox::Before, GeneratedBox::After]
+        .into_iter()
+        .filter_map(|pseudo| ctx.measure_generated(node, pseudo))
+        .fold(0.0, |height: f32, measured| height.max(measured.height));
+    Some(vec![(node, Subpixel::from_f32(height))])
+}
+
+fn generated_width(node: NodeId, pseudo: GeneratedBox, ctx: &dyn PropertyResolver) -> f32 {
+    ctx.measure_generated(node, pseudo)
+        .map_or(0.0, |measured| measured.width)
+}
+
+/// Whether `node` is `display: list-item` with an `inside` marker.
+fn has_inside_marker(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    matches!(
+        ctx.get_css_property(node, &PropertyId::Display),
+        Some(Property::Display(Display::Pair(pair))) if pair.is_list_item
+    ) && matches!(
+        ctx.get_css_property(node, &PropertyId::ListStylePosition),
+        Some(Property::ListStylePosition(ListStylePosition::Inside))
+    )
+}
+
+/// Whether `node` is the first child that takes room on a line of an
+/// element with an inside marker or a `::before` box.
+pub fn leads_generated_content(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    let Some(parent) = ctx.parent(node).filter(|&parent| parent != node) else {
+        return false;
+    };
+    takes_room(node, ctx)
+        && !ctx
+            .prev_siblings(node)
+            .into_iter()
+            .any(|sibling| takes_room(sibling, ctx))
+        && (has_inside_marker(parent, ctx)
+            || ctx
+                .measure_generated(parent, GeneratedBox::Before)
+                .is_some())
+}
+
+/// Whether `node` is the last child that takes room on a line of an
+/// element with an `::after` box.
+pub fn ends_generated_content(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    let Some(parent) = ctx.parent(node).filter(|&parent| parent != node) else {
+        return false;
+    };
+    takes_room(node, ctx)
+        && !ctx
+            .next_siblings(node)
+            .into_iter()
+            .any(|sibling| takes_room(sibling, ctx))
+        && ctx.measure_generated(parent, GeneratedBox::After).is_some()
+}
+
+/// Whether `node` generates a `::before` or `::after` box but has no
+/// child that takes room, so the generated boxes make its only line.
+pub fn has_only_generated_content(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    !ctx.children(node)
+        .into_iter()
+        .any(|child| takes_room(child, ctx))
+        && [GeneratedBox::Before, GeneratedBox::After]
+            .into_iter()
+            .any(|pseudo| ctx.measure_generated(node, pseudo).is_some())
+}
+
+/// Whether `node` takes room in its parent: text with something to show,
+/// or an in-flow element.
+fn takes_room(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    if ctx.is_element(node) {
+        !ctx.is_out_of_flow(node) && ctx.generates_box(node)
+    } else {
+        ctx.text_content(node)
+            .is_some_and(|text| !text.trim().is_empty())
+    }
+}
@@ -10,15 +10,21 @@ use lightningcss::vendor_prefix::VendorPrefix;
 use rewrite_core::{Axis, Formula, NodeId, PropertyResolver};
 
 pub mod block;
+mod custom;
+mod fieldset;
 pub mod flex;
+mod gap;
+mod generated;
 pub mod grid;
 pub mod offset;
+mod positioned;
 pub mod property;
 pub mod size;
+mod table;
 
 pub use offset::offset_query;
 pub use property::property_query;
-pub use size::size_query;
+pub use size::{available_inline_size_query, size_query};
 
 /// Resolved layout mode for an element, used to dispatch size/offset queries.
 pub enum DisplayType {
@@ -28,6 +34,12 @@ pub enum DisplayType {
     /// `is_inline` is true for `inline-flex`, false for `flex` (block-level).
     Flex(FlexDirection, bool),
     Grid,
+    /// `table` or `inline-table`.
+    Table,
+    /// `table-row-group`, `table-header-group` or `table-footer-group`.
+    TableRowGroup,
+    TableRow,
+    TableCell,
 }
 
 impl DisplayType {
@@ -68,11 +80,19 @@ impl DisplayType {
                         Some(Self::Flex(dir, is_inline))
                     }
                     DisplayInside::Grid => Some(Self::Grid),
+                    DisplayInside::Table => Some(Self::Table),
                     DisplayInside::Flow if matches!(pair.outside, DisplayOutside::Inline) => {
                         Some(Self::Inline)
                     }
                     _ => Some(Self::Block),
                 },
+                Display::Keyword(
+                    DisplayKeyword::TableRowGroup
+                    | DisplayKeyword::TableHeaderGroup
+                    | DisplayKeyword::TableFooterGroup,
+                ) => Some(Self::TableRowGroup),
+                Display::Keyword(DisplayKeyword::TableRow) => Some(Self::TableRow),
+                Display::Keyword(DisplayKeyword::TableCell) => Some(Self::TableCell),
                 _ => Some(Self::Block),
             },
             _ => Some(Self::Block),
@@ -99,6 +119,11 @@ impl DisplayType {
                 }
             }
             Self::Grid => Some(grid::grid_size(axis)),
+            Self::Table => Some(table::table_size(axis)),
+            // Rows and cells outside a table grid are laid out as blocks.
+            Self::TableRowGroup | Self::TableRow | Self::TableCell => {
+                Some(block::block_size(node, ctx, axis))
+            }
         }
     }
 
@@ -114,6 +139,11 @@ impl DisplayType {
             Self::Inline => Some(inline_offset(axis)),
             Self::Flex(dir, _is_inline) => Some(flex::flex_offset(*dir, axis)),
             Self::Grid => Some(grid::grid_offset(axis)),
+            // The table places its grid; anything else in it, and the
+            // contents of its cells, stack as in a block.
+            Self::Table | Self::TableRowGroup | Self::TableRow | Self::TableCell => {
+                Some(block::block_offset(node, ctx, axis))
+            }
         }
     }
 }
@@ -131,11 +161,22 @@ fn inline_size(axis: Axis) -> &'static Formula {
 }
 
 /// Offset formula for children of an inline element.
+///
+/// The parent's start padding and border are added by the static offset;
+/// siblings advance by their margin boxes, after the parent's `::before`
+/// box.
 fn inline_offset(axis: Axis) -> &'static Formula {
     match axis {
-        Axis::Horizontal => {
-            aggregate!(Sum, PrevSiblings, size::size_query, Axis::Horizontal)
-        }
+        Axis::Horizontal => add!(
+            aggregate!(
+                Sum,
+                PrevSiblings,
+                size::margin_box_size_query,
+                Axis::Horizontal
+            ),
+            css_prop!(MarginLeft),
+            &generated::BEFORE_ADVANCE,
+        ),
         Axis::Vertical => constant!(Subpixel::ZERO),
     }
 }
@@ -7,8 +7,8 @@
 //!
 //! Positioned elements override this:
 //! - `relative`: normal flow offset + top/left
-//! - `absolute`: containing block offset + top/left (containing block = nearest positioned ancestor)
-//! - `fixed`: viewport origin + top/left (or right→x for right-anchored)
+//! - `absolute` and `fixed`: placed against the containing block by the
+//!   insets (see `super::positioned`)
 
 use lightningcss::properties::position::Position;
 use lightningcss::properties::{Property, PropertyId};
@@ -24,22 +24,20 @@ fn position_of(node: NodeId, ctx: &dyn PropertyResolver) -> Position {
     }
 }
 
-/// Check if a node is a positioned ancestor (position != static).
-fn is_positioned(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
-    !matches!(position_of(node, ctx), Position::Static)
-}
-
-/// Query function that returns a formula for the node's absolute position.
+/// Query function that returns a formula for the node's absolute position,
+/// or `None` if the node generates no box.
 pub fn offset_query(
     node: NodeId,
     ctx: &dyn PropertyResolver,
     axis: Axis,
 ) -> Option<&'static Formula> {
+    if !ctx.generates_box(node) {
+        return None;
+    }
     let pos = position_of(node, ctx);
 
     match pos {
-        Position::Fixed => Some(fixed_offset(node, ctx, axis)),
-        Position::Absolute => Some(absolute_offset(node, ctx, axis)),
+        Position::Fixed | Position::Absolute => Some(super::positioned::positioned_offset(axis)),
         Position::Relative => Some(relative_offset(node, ctx, axis)),
         Position::Sticky(_) => sticky_offset(node, ctx, axis),
         _ => static_offset(node, ctx, axis),
@@ -57,6 +55,20 @@ fn static_offset(
         return Some(constant!(Subpixel::ZERO));
     }
 
+    if axis == Axis::Vertical && super::fieldset::is_below_legend(node, ctx) {
+        return Some(add!(
+            related!(Parent, offset_query, Axis::Vertical),
+            related_val!(Parent, css_prop!(PaddingTop)),
+            related_val!(Parent, css_prop!(BorderTopWidth)),
+            related!(
+                Parent,
+                super::fieldset::legend_overhang_query,
+                Axis::Vertical
+            ),
+            related!(Self_, local_offset_query, Axis::Vertical),
+        ));
+    }
+
     match axis {
         Axis::Horizontal => Some(add!(
             related!(Parent, offset_query, Axis::Horizontal),
@@ -93,7 +105,7 @@ fn relative_offset(
 
 /// Query function that always computes the static (normal flow) offset,
 /// ignoring the element's own `position` property.
-fn static_offset_query(
+pub(super) fn static_offset_query(
     node: NodeId,
     ctx: &dyn PropertyResolver,
     axis: Axis,
@@ -110,111 +122,24 @@ fn sticky_offset(
     static_offset(node, ctx, axis)
 }
 
-/// Fixed positioning: offset from viewport origin.
-fn fixed_offset(
-    node: NodeId,
-    ctx: &dyn PropertyResolver,
-    axis: Axis,
-) -> &'static Formula {
-    match axis {
-        Axis::Horizontal => {
-            if ctx.get_css_property(node, &PropertyId::Left).is_some() {
-                css_prop!(Left)
-            } else if ctx.get_css_property(node, &PropertyId::Right).is_some() {
-                sub!(
-                    viewport_width!(),
-                    css_prop!(Right),
-                    related!(Self_, super::size::size_query, Axis::Horizontal),
-                )
-            } else {
-                constant!(Subpixel::ZERO)
-            }
-        }
-        Axis::Vertical => {
-            if ctx.get_css_property(node, &PropertyId::Top).is_some() {
-                css_prop!(Top)
-            } else if ctx.get_css_property(node, &PropertyId::Bottom).is_some() {
-                sub!(
-                    viewport_height!(),
-                    css_prop!(Bottom),
-                    related!(Self_, super::size::size_query, Axis::Vertical),
-                )
-            } else {
-                constant!(Subpixel::ZERO)
-            }
-        }
-    }
-}
-
-/// Absolute positioning: offset from the nearest positioned ancestor.
-fn absolute_offset(
+/// Local offset within parent's content area, based on parent's layout mode.
+fn local_offset_query(
     node: NodeId,
     ctx: &dyn PropertyResolver,
     axis: Axis,
-) -> &'static Formula {
-    let parent = ctx.parent(node);
-    let parent_is_root = parent.is_none()
-        || parent == Some(node)
-        || parent == Some(rewrite_core::NodeId::ROOT);
-
-    if parent_is_root {
-        return match axis {
-            Axis::Horizontal => css_prop!(Left),
-            Axis::Vertical => css_prop!(Top),
-        };
-    }
-
-    let parent_id = parent.unwrap_or(NodeId(0));
-    if is_positioned(parent_id, ctx) {
-        return match axis {
-            Axis::Horizontal => add!(
-                related!(Parent, offset_query, Axis::Horizontal),
-                related_val!(Parent, css_prop!(PaddingLeft)),
-                related_val!(Parent, css_prop!(BorderLeftWidth)),
-                css_prop!(Left),
-            ),
-            Axis::Vertical => add!(
-                related!(Parent, offset_query, Axis::Vertical),
-                related_val!(Parent, css_prop!(PaddingTop)),
-                related_val!(Parent, css_prop!(BorderTopWidth)),
-                css_prop!(Top),
-            ),
-        };
+) -> Option<&'static Formula> {
+    if super::custom::is_custom_layout_child(node, ctx) {
+        return Some(super::custom::custom_offset(axis));
     }
 
-    // Parent is not positioned — pass through parent's contribution.
-    match axis {
-        Axis::Horizontal => add!(
-            related!(Parent, offset_query, Axis::Horizontal),
-            related_val!(Parent, css_prop!(PaddingLeft)),
-            related_val!(Parent, css_prop!(BorderLeftWidth)),
-            css_prop!(Left),
-        ),
-        Axis::Vertical => add!(
-            related!(Parent, offset_query, Axis::Vertical),
-            related_val!(Parent, css_prop!(PaddingTop)),
-            related_val!(Parent, css_prop!(BorderTopWidth)),
-            css_prop!(Top),
-        ),
+    if let Some(formula) = super::table::table_part_offset(node, ctx, axis) {
+        return Some(formula);
     }
-}
 
-/// Local offset for an inline child within a block parent.
-fn inline_child_offset(axis: Axis) -> &'static Formula {
-    match axis {
-        Axis::Horizontal => {
-            aggregate!(Sum, PrevSiblings, super::size::size_query, Axis::Horizontal)
-        }
-        Axis::Vertical => constant!(Subpixel::ZERO),
+    if ctx.is_rendered_legend(node) {
+        return Some(super::fieldset::legend_offset(axis));
     }
-}
 
-/// Local offset within parent's content area, based on parent's layout mode.
-fn local_offset_query(
-    node: NodeId,
-    ctx: &dyn PropertyResolver,
-    axis: Axis,
-) -> Option<&'static Formula> {
     // Block-in-inline: position as a block child of the block container.
     if super::is_block_in_inline(node, ctx) {
         return Some(super::block::block_offset(node, ctx, axis));
@@ -231,11 +156,15 @@ fn local_offset_query(
         return Some(super::block::block_offset(node, ctx, axis));
     }
 
-    // Check if this child is inline within a block parent.
-    if matches!(parent_display, DisplayType::Block) && !ctx.is_intrinsic(node) {
+    // Text runs and inline boxes within a block parent, or a table cell,
+    // sit on its line boxes.
+    if matches!(parent_display, DisplayType::Block | DisplayType::TableCell) {
+        if ctx.is_intrinsic(node) {
+            return Some(super::block::line_box_offset(node, ctx, axis));
+        }
         if let Some(DisplayType::Inline) = DisplayType::of_element(node, ctx) {
             if !super::inline_contains_block(node, ctx) {
-                return Some(inline_child_offset(axis));
+                return Some(super::block::line_box_offset(node, ctx, axis));
             }
         }
     }
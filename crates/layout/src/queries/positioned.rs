@@ -0,0 +1,267 @@
+//! Absolutely positioned and fixed boxes.
+//!
+//! CSS 2.2 §10.3.7 / §10.6.4: such a box is taken out of flow and placed
+//! against the padding box of its containing block (see
+//! `crate::containing_block`) by its `left`/`right`/`top`/`bottom` insets.
+//! Along each axis:
+//!
+//! - a start inset places the box's margin edge that far from the start
+//!   edge, an end inset (with an auto start) from the end edge;
+//! - with both insets auto, the box keeps its static position;
+//! - an auto size stretches between two non-auto insets. Otherwise an
+//!   auto width shrinks to fit the content, and an auto height is the
+//!   content height;
+//! - with both insets and the size fixed, auto margins share the space
+//!   left over, centering the box.
+
+use lightningcss::properties::position::Position;
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::values::length::LengthPercentageOrAuto;
+use lightningcss::values::percentage::DimensionPercentage;
+use rewrite_core::{Axis, Formula, NodeId, PropertyResolver, Subpixel, ViewportKind};
+
+use super::offset::{offset_query, static_offset_query};
+use super::size::size_query;
+use crate::containing_block::{ContainingBlock, containing_block};
+
+/// Border-box offset of a positioned box.
+static POSITIONED_OFFSET_X: Formula = Formula::Imperative(positioned_offset_x_impl);
+static POSITIONED_OFFSET_Y: Formula = Formula::Imperative(positioned_offset_y_impl);
+
+/// Border-box width of a positioned box with an auto `width`.
+static POSITIONED_WIDTH: Formula = Formula::Imperative(positioned_width_impl);
+
+/// Border-box height of a positioned box with an auto `height` between
+/// two non-auto vertical insets.
+static POSITIONED_STRETCH_HEIGHT: Formula = Formula::Imperative(positioned_stretch_height_impl);
+
+/// Whether `node` is absolutely positioned or fixed.
+pub(super) fn is_absolutely_positioned(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    matches!(
+        ctx.get_css_property(node, &PropertyId::Position),
+        Some(Property::Position(Position::Absolute | Position::Fixed))
+    )
+}
+
+/// Offset of an absolutely positioned or fixed box.
+pub(super) fn positioned_offset(axis: Axis) -> &'static Formula {
+    match axis {
+        Axis::Horizontal => &POSITIONED_OFFSET_X,
+        Axis::Vertical => &POSITIONED_OFFSET_Y,
+    }
+}
+
+/// Size of an absolutely positioned or fixed box without an explicit
+/// size along `axis`. `None` when it is the content height, which the
+/// box's own display type computes.
+pub(super) fn positioned_size(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+) -> Option<&'static Formula> {
+    match axis {
+        Axis::Horizontal => Some(&POSITIONED_WIDTH),
+        Axis::Vertical => {
+            let stretched = inset(node, ctx, &PropertyId::Top, Subpixel::ZERO).is_some()
+                && inset(node, ctx, &PropertyId::Bottom, Subpixel::ZERO).is_some();
+            stretched.then_some(&POSITIONED_STRETCH_HEIGHT)
+        }
+    }
+}
+
+/// The properties that place a box along one axis.
+struct AxisProps {
+    inset_start: PropertyId<'static>,
+    inset_end: PropertyId<'static>,
+    margin_start: PropertyId<'static>,
+    margin_end: PropertyId<'static>,
+    border_start: PropertyId<'static>,
+    border_end: PropertyId<'static>,
+    size: PropertyId<'static>,
+}
+
+fn axis_props(axis: Axis) -> AxisProps {
+    match axis {
+        Axis::Horizontal => AxisProps {
+            inset_start: PropertyId::Left,
+            inset_end: PropertyId::Right,
+            margin_start: PropertyId::MarginLeft,
+            margin_end: PropertyId::MarginRight,
+            border_start: PropertyId::BorderLeftWidth,
+            border_end: PropertyId::BorderRightWidth,
+            size: PropertyId::Width,
+        },
+        Axis::Vertical => AxisProps {
+            inset_start: PropertyId::Top,
+            inset_end: PropertyId::Bottom,
+            margin_start: PropertyId::MarginTop,
+            margin_end: PropertyId::MarginBottom,
+            border_start: PropertyId::BorderTopWidth,
+            border_end: PropertyId::BorderBottomWidth,
+            size: PropertyId::Height,
+        },
+    }
+}
+
+/// Used value of an inset, with percentages of `basis` (the containing
+/// block's size along the inset's axis). `None` for `auto`.
+fn inset(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    prop_id: &PropertyId<'static>,
+    basis: Subpixel,
+) -> Option<Subpixel> {
+    let (Property::Top(value)
+    | Property::Right(value)
+    | Property::Bottom(value)
+    | Property::Left(value)) = ctx.get_css_property(node, prop_id)?
+    else {
+        return None;
+    };
+    match value {
+        LengthPercentageOrAuto::Auto => None,
+        LengthPercentageOrAuto::LengthPercentage(DimensionPercentage::Percentage(pct)) => {
+            Some(Subpixel::from_f32(basis.to_f32() * pct.0))
+        }
+        LengthPercentageOrAuto::LengthPercentage(_) => ctx.get_property(node, prop_id),
+    }
+}
+
+/// Whether a margin of `node` is `auto`.
+fn is_auto_margin(node: NodeId, ctx: &dyn PropertyResolver, prop_id: &PropertyId<'static>) -> bool {
+    matches!(
+        ctx.get_css_property(node, prop_id),
+        Some(
+            Property::MarginTop(LengthPercentageOrAuto::Auto)
+                | Property::MarginRight(LengthPercentageOrAuto::Auto)
+                | Property::MarginBottom(LengthPercentageOrAuto::Auto)
+                | Property::MarginLeft(LengthPercentageOrAuto::Auto)
+        )
+    )
+}
+
+/// Resolved length of `prop_id` on `node`, zero when unset or `auto`.
+fn length(node: NodeId, ctx: &dyn PropertyResolver, prop_id: &PropertyId<'static>) -> Subpixel {
+    ctx.get_property(node, prop_id).unwrap_or(Subpixel::ZERO)
+}
+
+/// Start edge and size along `axis` of the padding box of `node`'s
+/// containing block.
+fn containing_block_area(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<(Subpixel, Subpixel)> {
+    match containing_block(node, ctx) {
+        ContainingBlock::Element(block) => {
+            let props = axis_props(axis);
+            let border_start = length(block, ctx, &props.border_start);
+            let border_end = length(block, ctx, &props.border_end);
+            let origin = resolve(offset_query(block, ctx, axis)?, block)?;
+            let size = resolve(size_query(block, ctx, axis)?, block)?;
+            Some((origin + border_start, size - border_start - border_end))
+        }
+        ContainingBlock::Viewport => {
+            ctx.record_viewport_dependency(node, ViewportKind::Large);
+            let size = match axis {
+                Axis::Horizontal => ctx.viewport_width(),
+                Axis::Vertical => ctx.viewport_height(),
+            };
+            Some((Subpixel::ZERO, Subpixel::from_px(size as i32)))
+        }
+    }
+}
+
+fn positioned_offset_x_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let offset = offset_along(node, ctx, Axis::Horizontal, resolve)?;
+    Some(vec![(node, offset)])
+}
+
+fn positioned_offset_y_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let offset = offset_along(node, ctx, Axis::Vertical, resolve)?;
+    Some(vec![(node, offset)])
+}
+
+fn offset_along(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Subpixel> {
+    let props = axis_props(axis);
+    let (origin, area) = containing_block_area(node, ctx, axis, resolve)?;
+    let start = inset(node, ctx, &props.inset_start, area);
+    let end = inset(node, ctx, &props.inset_end, area);
+    match (start, end) {
+        (Some(start), end) => {
+            let centered = end.filter(|_| {
+                ctx.get_css_property(node, &props.size).is_some()
+                    && is_auto_margin(node, ctx, &props.margin_start)
+                    && is_auto_margin(node, ctx, &props.margin_end)
+            });
+            let margin_start = match centered {
+                Some(end_inset) => {
+                    let size = resolve(size_query(node, ctx, axis)?, node)?;
+                    let free = (area - start - end_inset - size).max(Subpixel::ZERO);
+                    Subpixel::from_f32(free.to_f32() / 2.0)
+                }
+                None => length(node, ctx, &props.margin_start),
+            };
+            Some(origin + start + margin_start)
+        }
+        (None, Some(end)) => {
+            let size = resolve(size_query(node, ctx, axis)?, node)?;
+            Some(origin + area - end - length(node, ctx, &props.margin_end) - size)
+        }
+        (None, None) => resolve(static_offset_query(node, ctx, axis)?, node),
+    }
+}
+
+fn positioned_width_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let props = axis_props(Axis::Horizontal);
+    let (_, area) = containing_block_area(node, ctx, Axis::Horizontal, resolve)?;
+    let left = inset(node, ctx, &props.inset_start, area);
+    let right = inset(node, ctx, &props.inset_end, area);
+    let available = area
+        - left.unwrap_or(Subpixel::ZERO)
+        - right.unwrap_or(Subpixel::ZERO)
+        - length(node, ctx, &props.margin_start)
+        - length(node, ctx, &props.margin_end);
+    let width = if left.is_some() && right.is_some() {
+        available
+    } else {
+        // Shrink-to-fit: min(max(min-content, available), max-content).
+        let min_content = resolve(min_content_width!(), node).unwrap_or(Subpixel::ZERO);
+        let max_content = resolve(max_content_width!(), node).unwrap_or(Subpixel::ZERO);
+        available.max(min_content).min(max_content)
+    };
+    Some(vec![(node, width.max(Subpixel::ZERO))])
+}
+
+fn positioned_stretch_height_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let props = axis_props(Axis::Vertical);
+    let (_, area) = containing_block_area(node, ctx, Axis::Vertical, resolve)?;
+    let height = area
+        - inset(node, ctx, &props.inset_start, area)?
+        - inset(node, ctx, &props.inset_end, area)?
+        - length(node, ctx, &props.margin_start)
+        - length(node, ctx, &props.margin_end);
+    Some(vec![(node, height.max(Subpixel::ZERO))])
+}
@@ -2,14 +2,22 @@
 //!
 //! Dispatches to block/flex/grid modules based on the element's display mode.
 
-use lightningcss::properties::PropertyId;
-use rewrite_core::{Axis, Formula, NodeId, PropertyResolver};
+use lightningcss::properties::flex::FlexDirection;
+use lightningcss::properties::position::Position;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{Axis, Formula, NodeId, PropertyResolver, Subpixel, ViewportKind};
 
 use super::DisplayType;
+use crate::containing_block::{ContainingBlock, containing_block};
 
 /// Query function that returns a size formula based on the display property.
-/// Returns `None` if the display property isn't available yet.
+/// Returns `None` if the display property isn't available yet or the node
+/// generates no box.
 pub fn size_query(node: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> Option<&'static Formula> {
+    if !ctx.generates_box(node) {
+        return None;
+    }
+
     // Intrinsic nodes (text nodes): return InlineWidth/InlineHeight so the
     // resolver's inline aggregation handles text measurement and line breaking.
     if ctx.is_intrinsic(node) {
@@ -25,8 +33,10 @@ pub fn size_query(node: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> Optio
 
     // CSS 2.2 §10.2 / §10.5: width and height do not apply to
     // non-replaced inline elements. Skip the explicit CSS check
-    // so inline elements size from their content.
-    let is_inline = matches!(display_type, Some(DisplayType::Inline));
+    // so inline elements size from their content. Absolutely positioned
+    // boxes are blockified (§9.7), so they keep their sizes.
+    let is_positioned = super::positioned::is_absolutely_positioned(node, ctx);
+    let is_inline = matches!(display_type, Some(DisplayType::Inline)) && !is_positioned;
 
     // Root element check: use viewport dimensions if this node is at the top
     // of the layout tree.
@@ -47,18 +57,20 @@ pub fn size_query(node: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> Optio
     // element is a flex item and should be sized by the flex algorithm.
     let parent_display = DisplayType::of_element(parent_id, ctx);
     if let Some(DisplayType::Flex(dir, _)) = parent_display {
-        let is_out_of_flow = matches!(
-            ctx.get_css_property(node, &PropertyId::Position),
-            Some(lightningcss::properties::Property::Position(
-                lightningcss::properties::position::Position::Absolute
-                    | lightningcss::properties::position::Position::Fixed
-            ))
-        );
-        if !is_out_of_flow {
+        if !is_positioned {
             return Some(super::flex::flex_item_size(dir, axis));
         }
     }
 
+    // A table's grid sizes its rows and cells, and the table itself,
+    // explicit sizes included.
+    if matches!(display_type, Some(DisplayType::Table)) {
+        return Some(super::table::table_size(axis));
+    }
+    if let Some(formula) = super::table::table_part_size(node, ctx, axis) {
+        return Some(formula);
+    }
+
     // Check for explicit size (CSS width/height).
     if !is_inline {
         let explicit_prop = match axis {
@@ -68,9 +80,14 @@ pub fn size_query(node: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> Optio
         if let Some(prop) = ctx.get_css_property(node, &explicit_prop) {
             // Handle keyword sizes (min-content, max-content) which cannot
             // be resolved to a numeric value by css_val!.
-            if let Some(keyword_formula) = keyword_size_formula(prop, node, ctx, axis) {
+            if let Some(keyword_formula) = keyword_size_formula(prop.clone(), node, ctx, axis) {
                 return Some(keyword_formula);
             }
+            if axis == Axis::Vertical
+                && let Some(pct_formula) = percentage_height_formula(&prop, node, ctx)
+            {
+                return Some(pct_formula);
+            }
             return match axis {
                 Axis::Horizontal => Some(css_val!(Width)),
                 Axis::Vertical => Some(css_val!(Height)),
@@ -78,6 +95,24 @@ pub fn size_query(node: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> Optio
         }
     }
 
+    // A fieldset's rendered legend shrinks to fit; the fieldset grows by
+    // the part of the legend below its top border.
+    if axis == Axis::Horizontal && ctx.is_rendered_legend(node) {
+        return Some(super::fieldset::legend_width());
+    }
+    if axis == Axis::Vertical
+        && matches!(display_type, Some(DisplayType::Block))
+        && super::fieldset::has_rendered_legend(node, ctx)
+    {
+        return Some(super::fieldset::fieldset_height());
+    }
+
+    // Absolutely positioned and fixed boxes are sized against their
+    // containing block.
+    if is_positioned && let Some(formula) = super::positioned::positioned_size(node, ctx, axis) {
+        return Some(formula);
+    }
+
     // Inline element containing a block child: per CSS 2.2 §9.2.1.1,
     // the inline is broken around the block and treated as block-level
     // for sizing purposes (fills parent content width).
@@ -87,6 +122,11 @@ pub fn size_query(node: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> Optio
         return Some(super::block::block_size(node, ctx, axis));
     }
 
+    // A custom layout box is as tall as its layout makes its content.
+    if axis == Axis::Vertical && ctx.custom_layout(node).is_some() {
+        return Some(super::custom::custom_height());
+    }
+
     display_type?.size(node, ctx, axis)
 }
 
@@ -114,6 +154,51 @@ pub fn content_size_query(
     }
 }
 
+/// Whether the content box of `container` along `axis` is known without
+/// laying out its items: an explicit size, the viewport, or the width of
+/// a block-level box in normal flow.
+pub(crate) fn has_definite_size(container: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> bool {
+    use lightningcss::properties::size::Size;
+    let explicit = match axis {
+        Axis::Horizontal => PropertyId::Width,
+        Axis::Vertical => PropertyId::Height,
+    };
+    if matches!(
+        ctx.get_css_property(container, &explicit),
+        Some(
+            Property::Width(Size::LengthPercentage(_))
+                | Property::Height(Size::LengthPercentage(_))
+        )
+    ) {
+        return true;
+    }
+    let Some(parent) = ctx.parent(container) else {
+        return true;
+    };
+    if parent == NodeId::ROOT {
+        return true;
+    }
+    let is_flex_item = matches!(
+        DisplayType::of_element(parent, ctx),
+        Some(DisplayType::Flex(..))
+    );
+    axis == Axis::Horizontal
+        && !is_flex_item
+        && !ctx.is_out_of_flow(container)
+        && matches!(
+            DisplayType::of_element(container, ctx),
+            Some(DisplayType::Block | DisplayType::Flex(_, false) | DisplayType::Grid)
+        )
+}
+
+/// Content-box width of `node`: the line length for text it contains.
+pub fn available_inline_size_query(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+) -> Option<&'static Formula> {
+    content_size_query(node, ctx, Axis::Horizontal)
+}
+
 /// Resolve keyword size values (`min-content`, `max-content`) to intrinsic
 /// sizing formulas based on the element's display type.
 fn keyword_size_formula(
@@ -176,6 +261,125 @@ fn keyword_size_formula(
     }
 }
 
+// ============================================================================
+// Percentage heights with special containing blocks
+// ============================================================================
+
+/// Pick a formula for a percentage `height` whose basis is not the parent's
+/// specified height.
+///
+/// - CSS 2.2 §10.1: absolutely positioned and fixed boxes resolve against
+///   the padding box of their containing block.
+/// - CSS Flexbox §9.8: a flex item with a definite flex basis in a column
+///   container of definite height is treated as definite after flexing, so
+///   its children resolve percentages against the flexed size.
+///
+/// Returns `None` for the ordinary case, which `css_val!(Height)` handles.
+fn percentage_height_formula(
+    prop: &Property<'static>,
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+) -> Option<&'static Formula> {
+    percentage_of_height(prop)?;
+
+    if matches!(
+        ctx.get_css_property(node, &PropertyId::Position),
+        Some(Property::Position(Position::Absolute | Position::Fixed))
+    ) {
+        return Some(&ABSPOS_PCT_HEIGHT);
+    }
+
+    let parent = ctx.parent(node)?;
+    if is_definite_column_flex_item(parent, ctx) {
+        return Some(&FLEX_ITEM_CHILD_PCT_HEIGHT);
+    }
+    None
+}
+
+/// Extract the fraction from a pure-percentage `height` value.
+fn percentage_of_height(prop: &Property<'static>) -> Option<f32> {
+    use lightningcss::properties::size::Size;
+    use lightningcss::values::percentage::DimensionPercentage;
+
+    match prop {
+        Property::Height(Size::LengthPercentage(DimensionPercentage::Percentage(pct))) => {
+            Some(pct.0)
+        }
+        _ => None,
+    }
+}
+
+/// Whether `node` is a flex item in a column container whose flexed height
+/// counts as definite (explicit flex basis, container height specified).
+fn is_definite_column_flex_item(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    let Some(container) = ctx.parent(node) else {
+        return false;
+    };
+    let Some(DisplayType::Flex(dir, _)) = DisplayType::of_element(container, ctx) else {
+        return false;
+    };
+    let is_column = matches!(dir, FlexDirection::Column | FlexDirection::ColumnReverse);
+    is_column
+        && super::flex::has_explicit_flex_basis(node, ctx)
+        && ctx
+            .get_css_property(container, &PropertyId::Height)
+            .is_some()
+}
+
+/// Imperative formula for percentage height on absolutely positioned boxes.
+static ABSPOS_PCT_HEIGHT: Formula = Formula::Imperative(abspos_pct_height_impl);
+
+fn abspos_pct_height_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let prop = ctx.get_css_property(node, &PropertyId::Height)?;
+    let pct = percentage_of_height(&prop)?;
+
+    let basis = match containing_block(node, ctx) {
+        ContainingBlock::Element(block) => {
+            // Padding box = border box minus borders.
+            let block_size = resolve(size_query(block, ctx, Axis::Vertical)?, block)?;
+            let border_top = ctx
+                .get_property(block, &PropertyId::BorderTopWidth)
+                .unwrap_or(Subpixel::ZERO);
+            let border_bottom = ctx
+                .get_property(block, &PropertyId::BorderBottomWidth)
+                .unwrap_or(Subpixel::ZERO);
+            block_size - border_top - border_bottom
+        }
+        ContainingBlock::Viewport => {
+            ctx.record_viewport_dependency(node, ViewportKind::Large);
+            Subpixel::from_px(ctx.viewport_height() as i32)
+        }
+    };
+    Some(vec![(node, Subpixel::from_f32(basis.to_f32() * pct))])
+}
+
+/// Imperative formula for percentage height on children of a definite
+/// column flex item.
+static FLEX_ITEM_CHILD_PCT_HEIGHT: Formula = Formula::Imperative(flex_item_child_pct_height_impl);
+
+fn flex_item_child_pct_height_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel>,
+) -> Option<Vec<(NodeId, Subpixel)>> {
+    let prop = ctx.get_css_property(node, &PropertyId::Height)?;
+    let pct = percentage_of_height(&prop)?;
+
+    let parent_id = ctx.parent(node)?;
+    let parent_content = resolve(
+        content_size_query(parent_id, ctx, Axis::Vertical)?,
+        parent_id,
+    )?;
+    Some(vec![(
+        node,
+        Subpixel::from_f32(parent_content.to_f32() * pct),
+    )])
+}
+
 /// Margin-box size = border-box size + margins.
 pub fn margin_box_size_query(
     _node: NodeId,
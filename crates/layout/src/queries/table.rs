@@ -0,0 +1,520 @@
+//! Table layout formulas (CSS 2.2 §17).
+//!
+//! A `display: table` box places its rows and cells on a grid with the
+//! automatic table layout algorithm (§17.5.2.2): the table is as wide as
+//! its columns' max-content widths within its containing block, but never
+//! narrower than their min-content widths, and its columns share that
+//! width in proportion to what their cells ask for. Each row is as tall
+//! as its tallest cell, and every cell in it is stretched to that height.
+//! `border-spacing` separates the columns and rows from each other and
+//! from the table's edges (§17.6.1). Header groups are placed first and
+//! footer groups last (§17.2).
+//!
+//! The grid is computed by imperative formulas, which lay out the whole
+//! table for the value of any row group, row or cell and return the
+//! values of every part, so the resolver lays it out once for a batch of
+//! parts rather than once per part. Cells span one row and one column,
+//! and boxes outside the grid aren't wrapped in anonymous table boxes
+//! (§17.2.1): they stack as in a block. Cell contents sit at the top of
+//! the cell.
+
+use lightningcss::properties::display::{Display, DisplayKeyword};
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{Axis, Formula, NodeId, PropertyResolver, Subpixel};
+
+use super::DisplayType;
+use super::block::block_size;
+
+/// Border-box size of a table.
+static TABLE_WIDTH: Formula = Formula::Imperative(table_width_impl);
+static TABLE_HEIGHT: Formula = Formula::Imperative(table_height_impl);
+
+/// Border-box size of each row group, row and cell of a table.
+static PART_WIDTH: Formula = Formula::Imperative(part_width_impl);
+static PART_HEIGHT: Formula = Formula::Imperative(part_height_impl);
+
+/// Offset of each row group, row and cell in its parent's content box.
+static PART_OFFSET_X: Formula = Formula::Imperative(part_offset_x_impl);
+static PART_OFFSET_Y: Formula = Formula::Imperative(part_offset_y_impl);
+
+type Resolve<'resolve> = dyn FnMut(&'static Formula, NodeId) -> Option<Subpixel> + 'resolve;
+
+/// Values laid out for a table's boxes.
+type Values = Vec<(NodeId, Subpixel)>;
+
+/// Size of a table box.
+pub(super) fn table_size(axis: Axis) -> &'static Formula {
+    match axis {
+        Axis::Horizontal => &TABLE_WIDTH,
+        Axis::Vertical => &TABLE_HEIGHT,
+    }
+}
+
+/// Size of a row group, row or cell placed by its table, whose explicit
+/// sizes only feed into the grid; `None` for any other box.
+pub(super) fn table_part_size(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+) -> Option<&'static Formula> {
+    table_of(node, ctx)?;
+    Some(match axis {
+        Axis::Horizontal => &PART_WIDTH,
+        Axis::Vertical => &PART_HEIGHT,
+    })
+}
+
+/// Local offset of a row group, row or cell placed by its table; `None`
+/// for any other box.
+pub(super) fn table_part_offset(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    axis: Axis,
+) -> Option<&'static Formula> {
+    table_of(node, ctx)?;
+    Some(match axis {
+        Axis::Horizontal => &PART_OFFSET_X,
+        Axis::Vertical => &PART_OFFSET_Y,
+    })
+}
+
+/// The table whose grid `node` is part of: a row group or row in a table,
+/// a row in such a group, or a cell in such a row.
+fn table_of(node: NodeId, ctx: &dyn PropertyResolver) -> Option<NodeId> {
+    if !ctx.is_element(node) || ctx.is_out_of_flow(node) {
+        return None;
+    }
+    let parent = ctx.parent(node)?;
+    let display = DisplayType::of_element(node, ctx)?;
+    match (display, DisplayType::of_element(parent, ctx)?) {
+        (DisplayType::TableRowGroup | DisplayType::TableRow, DisplayType::Table) => Some(parent),
+        (DisplayType::TableRow, DisplayType::TableRowGroup)
+        | (DisplayType::TableCell, DisplayType::TableRow) => table_of(parent, ctx),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Grid
+// ============================================================================
+
+/// A table's rows, grouped as they are placed.
+struct Grid {
+    sections: Vec<Section>,
+    columns: usize,
+}
+
+/// A row group, or a row directly in the table (`group` is `None`).
+struct Section {
+    group: Option<NodeId>,
+    rows: Vec<Row>,
+}
+
+struct Row {
+    node: NodeId,
+    cells: Vec<NodeId>,
+}
+
+impl Grid {
+    fn rows(&self) -> impl Iterator<Item = &Row> {
+        self.sections.iter().flat_map(|section| &section.rows)
+    }
+}
+
+/// The in-flow element children of `node` with the given display, in
+/// document order.
+fn children_with_display(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    display: fn(&DisplayType) -> bool,
+) -> Vec<NodeId> {
+    ctx.children(node)
+        .into_iter()
+        .rev()
+        .filter(|&child| {
+            ctx.is_element(child)
+                && !ctx.is_out_of_flow(child)
+                && DisplayType::of_element(child, ctx).is_some_and(|found| display(&found))
+        })
+        .collect()
+}
+
+fn row(node: NodeId, ctx: &dyn PropertyResolver) -> Row {
+    Row {
+        node,
+        cells: children_with_display(node, ctx, |display| {
+            matches!(display, DisplayType::TableCell)
+        }),
+    }
+}
+
+/// Where a section goes: header groups first, footer groups last.
+fn section_order(section: &Section, ctx: &dyn PropertyResolver) -> u8 {
+    let keyword = section
+        .group
+        .and_then(|group| ctx.get_css_property(group, &PropertyId::Display));
+    match keyword {
+        Some(Property::Display(Display::Keyword(DisplayKeyword::TableHeaderGroup))) => 0,
+        Some(Property::Display(Display::Keyword(DisplayKeyword::TableFooterGroup))) => 2,
+        _ => 1,
+    }
+}
+
+fn grid(table: NodeId, ctx: &dyn PropertyResolver) -> Grid {
+    let mut sections: Vec<Section> = children_with_display(table, ctx, |display| {
+        matches!(display, DisplayType::TableRowGroup | DisplayType::TableRow)
+    })
+    .into_iter()
+    .map(|child| match DisplayType::of_element(child, ctx) {
+        Some(DisplayType::TableRowGroup) => Section {
+            group: Some(child),
+            rows: children_with_display(child, ctx, |display| {
+                matches!(display, DisplayType::TableRow)
+            })
+            .into_iter()
+            .map(|node| row(node, ctx))
+            .collect(),
+        },
+        _ => Section {
+            group: None,
+            rows: vec![row(child, ctx)],
+        },
+    })
+    .collect();
+    sections.sort_by_key(|section| section_order(section, ctx));
+    let columns = sections
+        .iter()
+        .flat_map(|section| &section.rows)
+        .map(|row| row.cells.len())
+        .max()
+        .unwrap_or(0);
+    Grid { sections, columns }
+}
+
+// ============================================================================
+// Columns
+// ============================================================================
+
+/// Padding and border of `node` along `axis`.
+fn decoration(node: NodeId, ctx: &dyn PropertyResolver, axis: Axis) -> Subpixel {
+    let sides = match axis {
+        Axis::Horizontal => [
+            PropertyId::PaddingLeft,
+            PropertyId::PaddingRight,
+            PropertyId::BorderLeftWidth,
+            PropertyId::BorderRightWidth,
+        ],
+        Axis::Vertical => [
+            PropertyId::PaddingTop,
+            PropertyId::PaddingBottom,
+            PropertyId::BorderTopWidth,
+            PropertyId::BorderBottomWidth,
+        ],
+    };
+    sides
+        .iter()
+        .filter_map(|prop_id| ctx.get_property(node, prop_id))
+        .fold(Subpixel::ZERO, |acc, val| acc + val)
+}
+
+/// Spacing taken by `count` tracks: one gap before each and one after the
+/// last, or none without tracks.
+fn spacing_around(count: usize, spacing: Subpixel) -> Subpixel {
+    if count == 0 {
+        return Subpixel::ZERO;
+    }
+    Subpixel::from_f32(spacing.to_f32() * (count + 1) as f32)
+}
+
+/// Min-content and max-content border-box widths of each column: the
+/// widest its cells' contents get, and at least a cell's explicit width.
+fn column_extents(
+    grid: &Grid,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> (Vec<Subpixel>, Vec<Subpixel>) {
+    let mut min_widths = vec![Subpixel::ZERO; grid.columns];
+    let mut max_widths = vec![Subpixel::ZERO; grid.columns];
+    for row in grid.rows() {
+        for (column, &cell) in row.cells.iter().enumerate() {
+            // Measured inline content already includes the cell's padding
+            // and border; an empty cell still has them.
+            let measure = |resolved: Option<Subpixel>| {
+                resolved.unwrap_or_else(|| decoration(cell, ctx, Axis::Horizontal))
+            };
+            let min_content = measure(resolve(min_content_width!(), cell));
+            // An explicit width is all a cell asks for, unless its content
+            // can't get any narrower.
+            let explicit = if ctx.get_css_property(cell, &PropertyId::Width).is_some() {
+                resolve(css_val!(Width), cell)
+            } else {
+                None
+            };
+            let (min_width, max_width) = explicit.map_or_else(
+                || {
+                    let max_content = measure(resolve(max_content_width!(), cell));
+                    (min_content, max_content.max(min_content))
+                },
+                |width| (min_content.max(width), min_content.max(width)),
+            );
+            min_widths[column] = min_widths[column].max(min_width);
+            max_widths[column] = max_widths[column].max(max_width);
+        }
+    }
+    (min_widths, max_widths)
+}
+
+/// Border-box width of `table` (§17.5.2.2): an explicit width, or its
+/// columns' max-content width capped at the containing block, and at
+/// least the columns' min-content width either way.
+fn table_width(
+    table: NodeId,
+    grid: &Grid,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Option<Subpixel> {
+    let (min_widths, max_widths) = column_extents(grid, ctx, resolve);
+    let (spacing, _) = ctx.border_spacing(table);
+    let outside = spacing_around(grid.columns, spacing) + decoration(table, ctx, Axis::Horizontal);
+    let min_width = min_widths.iter().sum::<Subpixel>() + outside;
+    let max_width = max_widths.iter().sum::<Subpixel>() + outside;
+    let width = if ctx.get_css_property(table, &PropertyId::Width).is_some() {
+        resolve(css_val!(Width), table)?
+    } else {
+        let available = resolve(block_size(table, ctx, Axis::Horizontal), table)?;
+        max_width.min(available)
+    };
+    Some(width.max(min_width))
+}
+
+/// Border-box width of each column once the table is `width` wide.
+///
+/// Past the columns' max-content widths, the extra is shared in
+/// proportion to them; between min-content and max-content, each column
+/// grows from its min-content width by the same fraction of its range.
+fn column_widths(
+    table: NodeId,
+    grid: &Grid,
+    width: Subpixel,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Vec<Subpixel> {
+    let (min_widths, max_widths) = column_extents(grid, ctx, resolve);
+    let (spacing, _) = ctx.border_spacing(table);
+    let available =
+        (width - spacing_around(grid.columns, spacing) - decoration(table, ctx, Axis::Horizontal))
+            .to_f32();
+    let min_total: f32 = min_widths.iter().map(|extent| extent.to_f32()).sum();
+    let max_total: f32 = max_widths.iter().map(|extent| extent.to_f32()).sum();
+    min_widths
+        .iter()
+        .zip(&max_widths)
+        .map(|(min_extent, max_extent)| {
+            let (min_width, max_width) = (min_extent.to_f32(), max_extent.to_f32());
+            let used = if available >= max_total {
+                let share = if max_total > 0.0 {
+                    max_width / max_total
+                } else {
+                    1.0 / grid.columns as f32
+                };
+                (available - max_total).mul_add(share, max_width)
+            } else if available > min_total {
+                let fraction = (available - min_total) / (max_total - min_total);
+                (max_width - min_width).mul_add(fraction, min_width)
+            } else {
+                min_width
+            };
+            Subpixel::from_f32(used)
+        })
+        .collect()
+}
+
+/// The table `node` is part of, its grid, and the border-box widths of
+/// its columns.
+type Columns = (NodeId, Grid, Vec<Subpixel>);
+
+/// Columns of the table `node` is part of.
+fn resolved_columns(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Option<Columns> {
+    let table = table_of(node, ctx)?;
+    let grid = grid(table, ctx);
+    let width = resolve(&TABLE_WIDTH, table)?;
+    let widths = column_widths(table, &grid, width, ctx, resolve);
+    Some((table, grid, widths))
+}
+
+// ============================================================================
+// Rows
+// ============================================================================
+
+/// Border-box height of each row: its tallest cell's content, and at
+/// least the explicit heights of the row and its cells.
+fn row_heights(
+    grid: &Grid,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Vec<Subpixel> {
+    grid.rows()
+        .map(|row| {
+            let explicit = |node: NodeId, resolve_height: &mut Resolve<'_>| {
+                if ctx.get_css_property(node, &PropertyId::Height).is_some() {
+                    resolve_height(css_val!(Height), node).unwrap_or(Subpixel::ZERO)
+                } else {
+                    Subpixel::ZERO
+                }
+            };
+            let mut height = explicit(row.node, resolve);
+            for &cell in &row.cells {
+                let content =
+                    resolve(block_size(cell, ctx, Axis::Vertical), cell).unwrap_or(Subpixel::ZERO);
+                height = height.max(content).max(explicit(cell, resolve));
+            }
+            height
+        })
+        .collect()
+}
+
+/// Height taken by `heights` with `spacing` between them.
+fn span(heights: &[Subpixel], spacing: Subpixel) -> Subpixel {
+    let gaps = heights.len().saturating_sub(1);
+    heights.iter().sum::<Subpixel>() + Subpixel::from_f32(spacing.to_f32() * gaps as f32)
+}
+
+// ============================================================================
+// Imperative formulas
+// ============================================================================
+
+fn table_width_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Option<Values> {
+    let grid = grid(node, ctx);
+    Some(vec![(node, table_width(node, &grid, ctx, resolve)?)])
+}
+
+fn table_height_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Option<Values> {
+    let grid = grid(node, ctx);
+    let (_, spacing) = ctx.border_spacing(node);
+    let heights = row_heights(&grid, ctx, resolve);
+    let content = if heights.is_empty() {
+        Subpixel::ZERO
+    } else {
+        span(&heights, spacing) + spacing + spacing
+    };
+    let mut height = content + decoration(node, ctx, Axis::Vertical);
+    if ctx.get_css_property(node, &PropertyId::Height).is_some() {
+        height = height.max(resolve(css_val!(Height), node)?);
+    }
+    Some(vec![(node, height)])
+}
+
+fn part_width_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Option<Values> {
+    let (table, grid, widths) = resolved_columns(node, ctx, resolve)?;
+    let (spacing, _) = ctx.border_spacing(table);
+    // Row groups and rows span every column.
+    let full_width = span(&widths, spacing);
+    let mut values = Vec::new();
+    for section in &grid.sections {
+        values.extend(section.group.map(|group| (group, full_width)));
+        for row in &section.rows {
+            values.push((row.node, full_width));
+            values.extend(row.cells.iter().copied().zip(widths.iter().copied()));
+        }
+    }
+    Some(values)
+}
+
+fn part_height_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Option<Values> {
+    let table = table_of(node, ctx)?;
+    let grid = grid(table, ctx);
+    let (_, spacing) = ctx.border_spacing(table);
+    let mut heights = row_heights(&grid, ctx, resolve).into_iter();
+    let mut values = Vec::new();
+    for section in &grid.sections {
+        let section_heights: Vec<Subpixel> = heights.by_ref().take(section.rows.len()).collect();
+        values.extend(
+            section
+                .group
+                .map(|group| (group, span(&section_heights, spacing))),
+        );
+        for (row, &height) in section.rows.iter().zip(&section_heights) {
+            values.push((row.node, height));
+            values.extend(row.cells.iter().map(|&cell| (cell, height)));
+        }
+    }
+    Some(values)
+}
+
+fn part_offset_x_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Option<Values> {
+    let (table, grid, widths) = resolved_columns(node, ctx, resolve)?;
+    let (spacing, _) = ctx.border_spacing(table);
+    // Cells are placed from the start of their row, which starts after
+    // the table's first column gap.
+    let mut cell_offsets = Vec::with_capacity(widths.len());
+    let mut offset = Subpixel::ZERO;
+    for &width in &widths {
+        cell_offsets.push(offset);
+        offset = offset + width + spacing;
+    }
+    let mut values = Vec::new();
+    for section in &grid.sections {
+        values.extend(section.group.map(|group| (group, spacing)));
+        let row_offset = if section.group.is_some() {
+            Subpixel::ZERO
+        } else {
+            spacing
+        };
+        for row in &section.rows {
+            values.push((row.node, row_offset));
+            values.extend(row.cells.iter().copied().zip(cell_offsets.iter().copied()));
+        }
+    }
+    Some(values)
+}
+
+fn part_offset_y_impl(
+    node: NodeId,
+    ctx: &dyn PropertyResolver,
+    resolve: &mut Resolve<'_>,
+) -> Option<Values> {
+    let table = table_of(node, ctx)?;
+    let grid = grid(table, ctx);
+    let (_, spacing) = ctx.border_spacing(table);
+    let mut heights = row_heights(&grid, ctx, resolve).into_iter();
+    // Offset of the next row in the table's content box. An empty group
+    // sits where its first row would.
+    let mut next_offset = spacing;
+    let mut values = Vec::new();
+    for section in &grid.sections {
+        let origin = section.group.map_or(Subpixel::ZERO, |group| {
+            values.push((group, next_offset));
+            next_offset
+        });
+        for (row, height) in section.rows.iter().zip(heights.by_ref()) {
+            values.push((row.node, next_offset - origin));
+            values.extend(row.cells.iter().map(|&cell| (cell, Subpixel::ZERO)));
+            next_offset = next_offset + height + spacing;
+        }
+    }
+    Some(values)
+}
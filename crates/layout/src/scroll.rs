@@ -8,7 +8,9 @@
 ///
 /// This provides the bridge between the layout system and the windowing/event system.
 use crate::Subpixels;
-use rewrite_core::NodeId;
+use lightningcss::properties::overflow::OverflowKeyword;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{NodeId, PropertyResolver};
 
 /// Scroll state with offsets in both directions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -253,6 +255,104 @@ pub fn can_scroll(current_scroll: Subpixels, max_scroll: Subpixels, delta: Subpi
     }
 }
 
+/// Overflow values applied to the viewport, i.e. the root scroller.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewportOverflow {
+    /// Used overflow of the viewport in the horizontal axis.
+    pub overflow_x: OverflowKeyword,
+    /// Used overflow of the viewport in the vertical axis.
+    pub overflow_y: OverflowKeyword,
+    /// The element whose overflow values were taken (`html` or `body`).
+    /// `None` if the document has no root element yet.
+    pub source: Option<NodeId>,
+}
+
+impl Default for ViewportOverflow {
+    fn default() -> Self {
+        Self {
+            overflow_x: OverflowKeyword::Auto,
+            overflow_y: OverflowKeyword::Auto,
+            source: None,
+        }
+    }
+}
+
+/// Compute the viewport's overflow per CSS Overflow 3 §3.3.
+///
+/// The root element's overflow applies to the viewport, unless the root is
+/// `html` with `overflow: visible` in both axes and has a `body` child that
+/// is displayed — then the body's values apply instead. `visible` on the
+/// viewport is used as `auto`, and `clip` as `hidden`.
+pub fn viewport_overflow(ctx: &dyn PropertyResolver) -> ViewportOverflow {
+    let Some(root) = root_element(ctx) else {
+        return ViewportOverflow::default();
+    };
+    let source = propagation_body(root, ctx).unwrap_or(root);
+    let (overflow_x, overflow_y) = overflow_of(source, ctx);
+    ViewportOverflow {
+        overflow_x: viewport_used_value(overflow_x),
+        overflow_y: viewport_used_value(overflow_y),
+        source: Some(source),
+    }
+}
+
+/// Whether `node`'s overflow values are propagated to the viewport, which
+/// makes its own used overflow `visible` (it is not a scroll container).
+pub fn propagates_overflow_to_viewport(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    if !matches!(ctx.tag_name(node).as_deref(), Some("html" | "body")) {
+        return false;
+    }
+    viewport_overflow(ctx).source == Some(node)
+}
+
+/// The document's root element (`html`), if present.
+fn root_element(ctx: &dyn PropertyResolver) -> Option<NodeId> {
+    ctx.children(NodeId::ROOT)
+        .into_iter()
+        .find(|&child| ctx.tag_name(child).as_deref() == Some("html"))
+}
+
+/// The `body` child whose overflow replaces the root's, if propagation applies.
+fn propagation_body(root: NodeId, ctx: &dyn PropertyResolver) -> Option<NodeId> {
+    let (root_x, root_y) = overflow_of(root, ctx);
+    if root_x != OverflowKeyword::Visible || root_y != OverflowKeyword::Visible {
+        return None;
+    }
+    ctx.children(root).into_iter().rev().find(|&child| {
+        ctx.tag_name(child).as_deref() == Some("body") && !is_display_none(child, ctx)
+    })
+}
+
+/// Read `overflow-x` / `overflow-y`, defaulting to `visible`.
+fn overflow_of(node: NodeId, ctx: &dyn PropertyResolver) -> (OverflowKeyword, OverflowKeyword) {
+    let overflow_x = match ctx.get_css_property(node, &PropertyId::OverflowX) {
+        Some(Property::OverflowX(keyword)) => keyword,
+        _ => OverflowKeyword::Visible,
+    };
+    let overflow_y = match ctx.get_css_property(node, &PropertyId::OverflowY) {
+        Some(Property::OverflowY(keyword)) => keyword,
+        _ => OverflowKeyword::Visible,
+    };
+    (overflow_x, overflow_y)
+}
+
+fn is_display_none(node: NodeId, ctx: &dyn PropertyResolver) -> bool {
+    use lightningcss::properties::display::{Display, DisplayKeyword};
+    matches!(
+        ctx.get_css_property(node, &PropertyId::Display),
+        Some(Property::Display(Display::Keyword(DisplayKeyword::None)))
+    )
+}
+
+/// Map a propagated overflow value to the value used by the viewport.
+fn viewport_used_value(keyword: OverflowKeyword) -> OverflowKeyword {
+    match keyword {
+        OverflowKeyword::Visible => OverflowKeyword::Auto,
+        OverflowKeyword::Clip => OverflowKeyword::Hidden,
+        other => other,
+    }
+}
+
 /// Apply scroll delta with momentum/easing.
 ///
 /// This implements smooth scrolling by applying easing to scroll deltas.
@@ -0,0 +1,158 @@
+//! Property support matrix.
+//!
+//! For every property in the style database's registry, records how far
+//! through the pipeline it gets: whether the parser gives it a typed
+//! value, whether the cascade stores it, and whether layout reads it. A
+//! property can be parsed and cascaded yet ignored by layout (say,
+//! `grid-template-areas`), so downstream users can check the matrix, or
+//! its JSON form, before relying on a feature.
+
+use lightningcss::properties::PropertyId;
+use lightningcss::properties::custom::CustomPropertyName;
+use rewrite_core::{PROPERTY_NAMES, affects_text_measurement, classify_property, float_id};
+
+/// How far through the pipeline one property is supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertySupport {
+    /// The CSS property name, e.g. `flex-basis`.
+    pub name: &'static str,
+    /// The parser produces a typed value rather than an unparsed token
+    /// list. The few untyped properties the engine relies on (`float`,
+    /// `outline-offset`, `overflow-anchor`) are read from their tokens
+    /// and still count.
+    pub parsed: bool,
+    /// The cascade stores the property in one of the sparse trees.
+    pub cascaded: bool,
+    /// Layout reads the property when sizing or placing boxes, or when
+    /// measuring text.
+    pub laid_out: bool,
+}
+
+impl PropertySupport {
+    /// Support for the property called `name`.
+    pub fn for_property(name: &'static str) -> Self {
+        let prop_id = PropertyId::from(name);
+        let cascaded = classify_property(&prop_id).is_some();
+        let untyped = matches!(prop_id, PropertyId::Custom(CustomPropertyName::Unknown(_)));
+        Self {
+            name,
+            parsed: !untyped || cascaded,
+            cascaded,
+            laid_out: is_laid_out(&prop_id),
+        }
+    }
+
+    /// The entry as a JSON object.
+    fn to_json(self) -> String {
+        format!(
+            "{{\"property\":\"{}\",\"parse\":{},\"cascade\":{},\"layout\":{}}}",
+            self.name, self.parsed, self.cascaded, self.laid_out
+        )
+    }
+}
+
+/// Support for every property in the registry, in registry order.
+pub fn support_matrix() -> Vec<PropertySupport> {
+    PROPERTY_NAMES
+        .iter()
+        .map(|&name| PropertySupport::for_property(name))
+        .collect()
+}
+
+/// The support matrix as a JSON array, one object per property with
+/// `property`, `parse`, `cascade` and `layout` fields.
+pub fn support_matrix_json() -> String {
+    let entries: Vec<String> = support_matrix()
+        .into_iter()
+        .map(PropertySupport::to_json)
+        .collect();
+    format!("[\n  {}\n]\n", entries.join(",\n  "))
+}
+
+/// Whether layout queries read `prop_id`.
+fn is_laid_out(prop_id: &PropertyId<'static>) -> bool {
+    affects_text_measurement(prop_id)
+        || *prop_id == float_id()
+        || matches!(
+            prop_id,
+            PropertyId::Display
+                | PropertyId::Width
+                | PropertyId::Height
+                | PropertyId::MinWidth
+                | PropertyId::MinHeight
+                | PropertyId::MaxWidth
+                | PropertyId::MaxHeight
+                | PropertyId::MarginTop
+                | PropertyId::MarginRight
+                | PropertyId::MarginBottom
+                | PropertyId::MarginLeft
+                | PropertyId::PaddingTop
+                | PropertyId::PaddingRight
+                | PropertyId::PaddingBottom
+                | PropertyId::PaddingLeft
+                | PropertyId::BorderTopWidth
+                | PropertyId::BorderRightWidth
+                | PropertyId::BorderBottomWidth
+                | PropertyId::BorderLeftWidth
+                | PropertyId::BorderSpacing
+                | PropertyId::FlexDirection(_)
+                | PropertyId::FlexWrap(_)
+                | PropertyId::FlexGrow(_)
+                | PropertyId::FlexShrink(_)
+                | PropertyId::FlexBasis(_)
+                | PropertyId::JustifyContent(_)
+                | PropertyId::AlignItems(_)
+                | PropertyId::AlignSelf(_)
+                | PropertyId::AlignContent(_)
+                | PropertyId::Order(_)
+                | PropertyId::RowGap
+                | PropertyId::ColumnGap
+                | PropertyId::OverflowX
+                | PropertyId::OverflowY
+                | PropertyId::Position
+                | PropertyId::Top
+                | PropertyId::Right
+                | PropertyId::Bottom
+                | PropertyId::Left
+                | PropertyId::ZIndex
+                | PropertyId::Opacity
+                | PropertyId::Transform(_)
+                | PropertyId::Perspective(_)
+                | PropertyId::Filter(_)
+                | PropertyId::BackdropFilter(_)
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_properties_are_cascaded() {
+        for support in support_matrix() {
+            assert!(support.parsed, "{} isn't parsed", support.name);
+            assert!(support.cascaded, "{} isn't cascaded", support.name);
+        }
+    }
+
+    #[test]
+    fn matrix_separates_layout_support() {
+        assert!(PropertySupport::for_property("flex-basis").laid_out);
+        assert!(PropertySupport::for_property("float").laid_out);
+        assert!(PropertySupport::for_property("font-size").laid_out);
+        assert!(!PropertySupport::for_property("background-color").laid_out);
+        assert!(!PropertySupport::for_property("grid-template-areas").laid_out);
+
+        let unknown = PropertySupport::for_property("not-a-property");
+        assert!(!unknown.parsed && !unknown.cascaded && !unknown.laid_out);
+    }
+
+    #[test]
+    fn json_lists_every_property() {
+        let json = support_matrix_json();
+        assert_eq!(json.matches("\"property\":").count(), PROPERTY_NAMES.len());
+        assert!(json.contains(
+            "{\"property\":\"flex-basis\",\"parse\":true,\"cascade\":true,\"layout\":true}"
+        ));
+    }
+}
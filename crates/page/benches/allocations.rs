@@ -0,0 +1,163 @@
+//! Allocator pressure of full layout on large synthetic documents.
+//!
+//! A counting global allocator records every allocation and reallocation
+//! made while a document loads. The bench reports allocations per node and
+//! per formula evaluation, and the aggregate buffers layout took from its
+//! scratch pool instead of allocating. The check is on allocations per
+//! evaluation: it fails if evaluating a formula allocates more on a larger
+//! document. Like evaluation counts, allocation counts are deterministic,
+//! so small documents give a reliable check.
+//!
+//! `VALOR_BENCH_NODES` overrides the larger document size.
+
+use rewrite_core::ResolveStats;
+use rewrite_page::Browser;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Larger document size, unless `VALOR_BENCH_NODES` is set.
+const NODES: usize = 200;
+
+const VIEWPORT_WIDTH: u32 = 800;
+const VIEWPORT_HEIGHT: u32 = 600;
+
+/// Nesting depth of each chain in the deep document.
+const CHAIN_DEPTH: usize = 25;
+
+/// Largest allowed ratio between the allocations per evaluation at `NODES`
+/// and at half that. Allocation proportional to the work done gives 1.
+const MAX_GROWTH: f64 = 1.5;
+
+const STYLES: &str = "<style>
+    .item { margin: 2px; padding: 4px }
+    .row { display: flex; flex-wrap: wrap; width: 300px }
+    .row > span { width: 40px }
+    div > div { margin-left: 1px }
+</style>";
+
+/// Passes every call to the system allocator, counting allocations.
+struct Counting;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+// SAFETY: defers to `System` for the actual allocation.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: same contract as `GlobalAlloc::alloc`.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: same contract as `GlobalAlloc::dealloc`.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: same contract as `GlobalAlloc::realloc`.
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// `count` sibling items in one body.
+fn flat_list(count: usize) -> String {
+    let mut html = format!("<html><head>{STYLES}</head><body>");
+    for index in 0..count {
+        html.push_str(&format!("<div class=\"item\">item {index}</div>"));
+    }
+    html + "</body></html>"
+}
+
+/// `count` nested divs, as chains of `CHAIN_DEPTH` ending in text.
+fn deep_tree(count: usize) -> String {
+    let mut html = format!("<html><head>{STYLES}</head><body>");
+    for _ in 0..count / CHAIN_DEPTH {
+        html.push_str(&"<div>".repeat(CHAIN_DEPTH));
+        html.push('x');
+        html.push_str(&"</div>".repeat(CHAIN_DEPTH));
+    }
+    html + "</body></html>"
+}
+
+/// Wrapping flex rows of ten items each, `count` items in all.
+fn flex_rows(count: usize) -> String {
+    let mut html = format!("<html><head>{STYLES}</head><body>");
+    for _ in 0..count / 10 {
+        html.push_str("<div class=\"row\">");
+        html.push_str(&"<span>x</span>".repeat(10));
+        html.push_str("</div>");
+    }
+    html + "</body></html>"
+}
+
+/// Allocations and resolution work while loading one document.
+struct Run {
+    allocations: u64,
+    stats: ResolveStats,
+}
+
+impl Run {
+    fn per_evaluation(&self) -> f64 {
+        self.allocations as f64 / self.stats.evaluations.max(1) as f64
+    }
+}
+
+/// Load `html` on a fresh page, with the head in its own chunk.
+fn full_load(html: &str) -> Run {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(VIEWPORT_WIDTH, VIEWPORT_HEIGHT);
+    let (head, body) = html.split_at(html.find("<body>").unwrap_or(0));
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let mut document = page.begin_html();
+    document.push(head);
+    document.push(body);
+    document.finish();
+    Run {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - before,
+        stats: renderer.resolve_stats(),
+    }
+}
+
+/// Larger document size.
+fn nodes() -> usize {
+    env::var("VALOR_BENCH_NODES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(NODES)
+}
+
+/// Report the allocations of `workload` at `count` nodes, and fail if
+/// allocations per evaluation grow from half that size.
+fn report(name: &str, count: usize, workload: fn(usize) -> String) {
+    let small = full_load(&workload(count / 2));
+    let large = full_load(&workload(count));
+    println!(
+        "{name:<10} {count:>6} nodes: {:>11} allocations, {:>9.1} per node, \
+         {:>5.2} per evaluation, {:>9} buffers reused",
+        large.allocations,
+        large.allocations as f64 / count as f64,
+        large.per_evaluation(),
+        large.stats.reused_buffers
+    );
+    let growth = large.per_evaluation() / small.per_evaluation();
+    assert!(
+        growth <= MAX_GROWTH,
+        "{name}: {:.2} allocations per evaluation at {count} nodes vs {:.2} at {} ({growth:.2}x)",
+        large.per_evaluation(),
+        small.per_evaluation(),
+        count / 2
+    );
+}
+
+fn main() {
+    let count = nodes();
+    report("flat_list", count, flat_list);
+    report("deep_tree", count, deep_tree);
+    report("flex_rows", count, flex_rows);
+}
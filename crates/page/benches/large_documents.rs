@@ -0,0 +1,236 @@
+//! Large synthetic documents: style matching, cascade, full layout and
+//! incremental layout at 10k nodes.
+//!
+//! Before timing, every workload is run at `GROWTH_NODES` and twice that,
+//! and the formula evaluation counts compared. Work that grows faster than
+//! the document (like the float band scan rescanning every earlier float)
+//! fails the bench instead of just making it slower. The counts are
+//! deterministic, so the check is reliable at sizes that run in seconds.
+//!
+//! `VALOR_BENCH_NODES` overrides the timed document size. Workloads listed
+//! in `KNOWN_SUPERLINEAR` are skipped by the growth check.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use rewrite_core::{NodeId, ResolveStats};
+use rewrite_html::NodeData;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+use std::env;
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Timed document size, unless `VALOR_BENCH_NODES` is set.
+const NODES: usize = 10_000;
+
+/// Smaller of the two document sizes compared by the growth check.
+const GROWTH_NODES: usize = 50;
+
+const VIEWPORT_WIDTH: u32 = 800;
+const VIEWPORT_HEIGHT: u32 = 600;
+
+/// Nesting depth of each chain in the deep document. Chains of 100 hit
+/// the resolver's recursion limit, so the deep tree is a sequence of
+/// shorter chains.
+const CHAIN_DEPTH: usize = 25;
+
+/// Paragraphs appended, one chunk each, by the text-append workload.
+const APPENDED_PARAGRAPHS: usize = 50;
+
+/// Largest allowed ratio between the evaluation counts of a workload at
+/// twice `GROWTH_NODES` and at `GROWTH_NODES`. Linear work gives 2;
+/// quadratic gives 4.
+const MAX_GROWTH: f64 = 2.5;
+
+/// Workloads whose growth is known to exceed `MAX_GROWTH`, skipped by the
+/// growth check; remove an entry once it passes.
+const KNOWN_SUPERLINEAR: &[&str] = &[];
+
+const STYLES: &str = "<style>
+    .item { margin: 2px; padding: 4px }
+    .item.active { padding: 8px; border: 1px solid black }
+    div > div { margin-left: 1px }
+</style>";
+
+/// `count` sibling items in one body.
+fn flat_list(count: usize) -> String {
+    let mut html = format!("<html><head>{STYLES}</head><body>");
+    for index in 0..count {
+        html.push_str(&format!("<div class=\"item\">item {index}</div>"));
+    }
+    html + "</body></html>"
+}
+
+/// `count` nested divs, as chains of `CHAIN_DEPTH` ending in text.
+fn deep_tree(count: usize) -> String {
+    let mut html = format!("<html><head>{STYLES}</head><body>");
+    for _ in 0..count / CHAIN_DEPTH {
+        html.push_str(&"<div>".repeat(CHAIN_DEPTH));
+        html.push('x');
+        html.push_str(&"</div>".repeat(CHAIN_DEPTH));
+    }
+    html + "</body></html>"
+}
+
+fn new_page(browser: &Browser) -> (Page<'_>, Arc<Renderer>) {
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(VIEWPORT_WIDTH, VIEWPORT_HEIGHT);
+    (page, renderer)
+}
+
+/// Load `html`, with the head in its own chunk so its styles apply as the
+/// body is built rather than to the finished tree.
+fn load(page: &Page<'_>, html: &str) {
+    let (head, body) = html.split_at(html.find("<body>").unwrap_or(0));
+    let mut document = page.begin_html();
+    document.push(head);
+    document.push(body);
+    document.finish();
+}
+
+/// Elements with a class attribute, in document order.
+fn classed_elements(page: &Page<'_>) -> Vec<NodeId> {
+    let Some(class) = page.tree.interner.get("class") else {
+        return Vec::new();
+    };
+    (0..page.styler.styled_node_count())
+        .map(|index| NodeId(index as u32))
+        .filter(|&node| {
+            matches!(
+                page.tree.get_node(node),
+                Some(NodeData::Element { attributes, .. }) if attributes.contains_key(&class)
+            )
+        })
+        .collect()
+}
+
+/// Counters and wall time for the measured part of a workload.
+struct Run {
+    stats: ResolveStats,
+    elapsed: Duration,
+}
+
+/// Load `html` on a fresh page.
+fn full_load(html: &str) -> Run {
+    let browser = Browser::default();
+    let (page, renderer) = new_page(&browser);
+    let start = Instant::now();
+    load(&page, html);
+    Run {
+        elapsed: start.elapsed(),
+        stats: renderer.resolve_stats(),
+    }
+}
+
+/// Load a flat list of `count` items, then add `active` to every item.
+/// Only the restyle is measured.
+fn class_churn(count: usize) -> Run {
+    let browser = Browser::default();
+    let (page, renderer) = new_page(&browser);
+    load(&page, &flat_list(count));
+    let items = classed_elements(&page);
+    renderer.reset_resolve_stats();
+    let start = Instant::now();
+    for &item in &items {
+        page.set_attribute(item, "class", Some("item active"));
+    }
+    Run {
+        elapsed: start.elapsed(),
+        stats: renderer.resolve_stats(),
+    }
+}
+
+/// Stream a deep tree of `count` nodes, then append paragraphs of text
+/// one chunk at a time. Only the appends are measured.
+fn text_append(count: usize) -> Run {
+    let browser = Browser::default();
+    let (page, renderer) = new_page(&browser);
+    let tree = deep_tree(count);
+    let (head, body) = tree.split_at(tree.find("<body>").unwrap_or(0));
+    let mut document = page.begin_html();
+    document.push(head);
+    document.push(body.trim_end_matches("</body></html>"));
+    renderer.reset_resolve_stats();
+    let start = Instant::now();
+    for index in 0..APPENDED_PARAGRAPHS {
+        document.push(&format!(
+            "<p>appended paragraph {index} of streamed text</p>"
+        ));
+    }
+    document.finish();
+    Run {
+        elapsed: start.elapsed(),
+        stats: renderer.resolve_stats(),
+    }
+}
+
+/// Timed document size.
+fn nodes() -> usize {
+    env::var("VALOR_BENCH_NODES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(NODES)
+}
+
+/// Fail if `workload` does superlinear work in the document size, unless
+/// it is listed in `KNOWN_SUPERLINEAR`.
+fn check_growth(name: &str, workload: impl Fn(usize) -> Run) {
+    if KNOWN_SUPERLINEAR.contains(&name) {
+        return;
+    }
+    let small = workload(GROWTH_NODES).stats.evaluations.max(1);
+    let large = workload(GROWTH_NODES * 2).stats.evaluations;
+    let growth = large as f64 / small as f64;
+    assert!(
+        growth <= MAX_GROWTH,
+        "{name}: {large} evaluations at {} nodes vs {small} at {GROWTH_NODES} ({growth:.2}x)",
+        GROWTH_NODES * 2
+    );
+}
+
+/// Time only the measured part of `workload`, `iters` times.
+fn measured(iters: u64, workload: impl Fn() -> Run) -> Duration {
+    (0..iters).map(|_| black_box(workload()).elapsed).sum()
+}
+
+fn full_layout(criterion: &mut Criterion) {
+    check_growth("flat_list", |count| full_load(&flat_list(count)));
+    check_growth("deep_tree", |count| full_load(&deep_tree(count)));
+
+    let flat = flat_list(nodes());
+    let deep = deep_tree(nodes());
+    let mut group = criterion.benchmark_group("full_layout");
+    group.sample_size(10);
+    group.bench_function("flat_list", |bencher| {
+        bencher.iter_custom(|iters| measured(iters, || full_load(&flat)));
+    });
+    group.bench_function("deep_tree", |bencher| {
+        bencher.iter_custom(|iters| measured(iters, || full_load(&deep)));
+    });
+    group.finish();
+}
+
+fn restyle(criterion: &mut Criterion) {
+    check_growth("class_churn", class_churn);
+
+    let mut group = criterion.benchmark_group("restyle");
+    group.sample_size(10);
+    group.bench_function("class_churn", |bencher| {
+        bencher.iter_custom(|iters| measured(iters, || class_churn(nodes())));
+    });
+    group.finish();
+}
+
+fn incremental_layout(criterion: &mut Criterion) {
+    check_growth("text_append", text_append);
+
+    let mut group = criterion.benchmark_group("incremental_layout");
+    group.sample_size(10);
+    group.bench_function("text_append", |bencher| {
+        bencher.iter_custom(|iters| measured(iters, || text_append(nodes())));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, full_layout, restyle, incremental_layout);
+criterion_main!(benches);
@@ -0,0 +1,275 @@
+//! Accessibility tree export.
+//!
+//! Builds a simplified accessibility tree from the DOM, computed style and
+//! layout so embedders can feed platform accessibility APIs. Roles come
+//! from tag names, names from `aria-label` / `alt` / text content, bounds
+//! from the renderer's layout, and hidden state from `display`,
+//! `visibility` and `aria-hidden`.
+
+use lightningcss::properties::display::{Display, DisplayKeyword, Visibility};
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{Database, NodeId};
+use rewrite_html::{DomTree, NodeData};
+use rewrite_renderer::{ComputedBox, Renderer};
+use std::sync::Arc;
+
+/// Role of an accessibility node, derived from its tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessibilityRole {
+    /// The document root.
+    Document,
+    /// An element with no more specific role, such as `<div>` or `<span>`.
+    Generic,
+    /// A run of text.
+    StaticText,
+    /// `<h1>` to `<h6>`, with the heading level.
+    Heading(u8),
+    /// `<p>`.
+    Paragraph,
+    /// `<a>`.
+    Link,
+    /// `<button>` and button-like `<input>` types.
+    Button,
+    /// `<img>`.
+    Image,
+    /// `<ul>` and `<ol>`.
+    List,
+    /// `<li>`.
+    ListItem,
+    /// `<textarea>` and text-entry `<input>` types.
+    TextField,
+    /// `<input type=checkbox>`.
+    Checkbox,
+    /// `<form>`.
+    Form,
+    /// `<nav>`.
+    Navigation,
+    /// `<main>`.
+    Main,
+    /// `<header>`.
+    Banner,
+    /// `<footer>`.
+    ContentInfo,
+    /// `<table>`.
+    Table,
+    /// `<tr>`.
+    Row,
+    /// `<td>` and `<th>`.
+    Cell,
+}
+
+impl AccessibilityRole {
+    /// Map an element tag (and its `type` attribute for `<input>`) to a role.
+    fn from_tag(tag: &str, input_type: Option<&str>) -> Self {
+        match tag {
+            "h1" => Self::Heading(1),
+            "h2" => Self::Heading(2),
+            "h3" => Self::Heading(3),
+            "h4" => Self::Heading(4),
+            "h5" => Self::Heading(5),
+            "h6" => Self::Heading(6),
+            "p" => Self::Paragraph,
+            "a" => Self::Link,
+            "button" => Self::Button,
+            "img" => Self::Image,
+            "ul" | "ol" => Self::List,
+            "li" => Self::ListItem,
+            "textarea" => Self::TextField,
+            "input" => match input_type {
+                Some("checkbox") => Self::Checkbox,
+                Some("button" | "submit" | "reset") => Self::Button,
+                _ => Self::TextField,
+            },
+            "form" => Self::Form,
+            "nav" => Self::Navigation,
+            "main" => Self::Main,
+            "header" => Self::Banner,
+            "footer" => Self::ContentInfo,
+            "table" => Self::Table,
+            "tr" => Self::Row,
+            "td" | "th" => Self::Cell,
+            _ => Self::Generic,
+        }
+    }
+
+    /// Whether the accessible name is computed from descendant text.
+    const fn name_from_content(self) -> bool {
+        matches!(
+            self,
+            Self::Heading(_) | Self::Link | Self::Button | Self::ListItem | Self::Cell
+        )
+    }
+}
+
+/// A node in the exported accessibility tree.
+#[derive(Debug, Clone)]
+pub struct AccessibilityNode {
+    /// The DOM node this entry describes.
+    pub node: NodeId,
+    /// What kind of object the node is to assistive technology.
+    pub role: AccessibilityRole,
+    /// Accessible name, if any.
+    pub name: Option<String>,
+    /// Layout bounds (absolute border box).
+    pub bounds: ComputedBox,
+    /// Hidden by `display: none`, `visibility: hidden` or `aria-hidden`.
+    /// Hidden state is inherited by descendants.
+    pub hidden: bool,
+    /// Entries for the node's children, in document order.
+    pub children: Vec<Self>,
+}
+
+/// Elements that never produce accessibility nodes.
+const SKIPPED_TAGS: [&str; 7] = [
+    "head", "script", "style", "meta", "title", "link", "template",
+];
+
+/// Build the accessibility tree for a document, rooted at the document node.
+pub fn accessibility_tree(
+    tree: &DomTree,
+    database: &Database,
+    renderer: &Renderer,
+) -> AccessibilityNode {
+    let ctx = ExportCtx {
+        tree,
+        database,
+        renderer,
+    };
+    let children = ctx.export_children(NodeId::ROOT, false);
+    AccessibilityNode {
+        node: NodeId::ROOT,
+        role: AccessibilityRole::Document,
+        name: None,
+        bounds: ComputedBox::default(),
+        hidden: false,
+        children,
+    }
+}
+
+/// Read an attribute value from an element.
+pub fn element_attribute(tree: &DomTree, node: NodeId, name: &str) -> Option<Arc<str>> {
+    let NodeData::Element { attributes, .. } = tree.get_node(node)? else {
+        return None;
+    };
+    attributes.get(&tree.interner.get(name)?)
+}
+
+/// Whether `node` is hidden by `display: none` or `visibility: hidden`.
+pub fn is_style_hidden(database: &Database, node: NodeId) -> bool {
+    is_display_none(database, node) || is_visibility_hidden(database, node)
+}
+
+pub fn is_display_none(database: &Database, node: NodeId) -> bool {
+    matches!(
+        database.get_property(node, PropertyId::Display),
+        Some(Property::Display(Display::Keyword(DisplayKeyword::None)))
+    )
+}
+
+pub fn is_visibility_hidden(database: &Database, node: NodeId) -> bool {
+    matches!(
+        database.get_property(node, PropertyId::Visibility),
+        Some(Property::Visibility(
+            Visibility::Hidden | Visibility::Collapse
+        ))
+    )
+}
+
+struct ExportCtx<'ctx> {
+    tree: &'ctx DomTree,
+    database: &'ctx Database,
+    renderer: &'ctx Renderer,
+}
+
+impl ExportCtx<'_> {
+    /// Export the children of `node` in DOM order.
+    fn export_children(&self, node: NodeId, parent_hidden: bool) -> Vec<AccessibilityNode> {
+        let mut children: Vec<NodeId> = self.tree.children(node).collect();
+        // Children are stored newest-first.
+        children.reverse();
+        children
+            .into_iter()
+            .filter_map(|child| self.export_node(child, parent_hidden))
+            .collect()
+    }
+
+    fn export_node(&self, node: NodeId, parent_hidden: bool) -> Option<AccessibilityNode> {
+        match self.tree.get_node(node)? {
+            NodeData::Text(text) => {
+                let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if collapsed.is_empty() {
+                    return None;
+                }
+                Some(AccessibilityNode {
+                    node,
+                    role: AccessibilityRole::StaticText,
+                    name: Some(collapsed),
+                    bounds: self.renderer.computed_box(node),
+                    hidden: parent_hidden,
+                    children: Vec::new(),
+                })
+            }
+            NodeData::Element { tag, .. } => {
+                let tag = self.tree.interner.resolve(tag).to_lowercase();
+                if SKIPPED_TAGS.contains(&tag.as_str()) {
+                    return None;
+                }
+                let role =
+                    AccessibilityRole::from_tag(&tag, self.attribute(node, "type").as_deref());
+                let hidden = parent_hidden || self.is_hidden(node);
+                let name = self
+                    .attribute(node, "aria-label")
+                    .or_else(|| {
+                        (role == AccessibilityRole::Image)
+                            .then(|| self.attribute(node, "alt"))
+                            .flatten()
+                    })
+                    .map(|name| name.to_string())
+                    .or_else(|| {
+                        role.name_from_content()
+                            .then(|| self.text_of(node))
+                            .flatten()
+                    });
+                Some(AccessibilityNode {
+                    node,
+                    role,
+                    name,
+                    bounds: self.renderer.computed_box(node),
+                    hidden,
+                    children: self.export_children(node, hidden),
+                })
+            }
+            NodeData::Document | NodeData::Comment(_) => None,
+        }
+    }
+
+    fn attribute(&self, node: NodeId, name: &str) -> Option<Arc<str>> {
+        element_attribute(self.tree, node, name)
+    }
+
+    fn is_hidden(&self, node: NodeId) -> bool {
+        is_style_hidden(self.database, node)
+            || self.attribute(node, "aria-hidden").as_deref() == Some("true")
+    }
+
+    /// Concatenated, whitespace-collapsed descendant text.
+    fn text_of(&self, node: NodeId) -> Option<String> {
+        let mut words = Vec::new();
+        self.collect_text(node, &mut words);
+        (!words.is_empty()).then(|| words.join(" "))
+    }
+
+    fn collect_text(&self, node: NodeId, words: &mut Vec<String>) {
+        let mut children: Vec<NodeId> = self.tree.children(node).collect();
+        children.reverse();
+        for child in children {
+            match self.tree.get_node(child) {
+                Some(NodeData::Text(text)) => {
+                    words.extend(text.split_whitespace().map(str::to_owned));
+                }
+                Some(NodeData::Element { .. }) => self.collect_text(child, words),
+                _ => {}
+            }
+        }
+    }
+}
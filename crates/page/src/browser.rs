@@ -46,13 +46,16 @@ impl Browser {
     pub fn new_page(&self) -> (Page<'_>, Arc<Renderer>) {
         let tree = Arc::new(DomTree::new(self.interner.clone()));
         let styler = Arc::new(Styler::new(tree.clone(), self.subscriptions.clone()));
-        let page = Page::new(&self.runtime, tree, styler, self.subscriptions.clone());
+        let mut page = Page::new(&self.runtime, tree, styler, self.subscriptions.clone());
 
         let renderer = Arc::new(Renderer::new(page.styler.clone(), page.db.clone()));
 
-        // Register renderer as subscriber
-        self.subscriptions
-            .add_subscriber(Box::new(RendererSubscriber(renderer.clone())));
+        // Register renderer as subscriber; the page unregisters it when
+        // dropped.
+        page.renderer_subscriber = Some(
+            self.subscriptions
+                .add_subscriber(Box::new(RendererSubscriber(renderer.clone()))),
+        );
 
         (page, renderer)
     }
@@ -74,6 +77,14 @@ impl rewrite_core::Subscriber for RendererSubscriber {
         self.0.on_property(node, property);
     }
 
+    fn on_property_removed(
+        &self,
+        node: rewrite_core::NodeId,
+        property: &rewrite_css::PropertyId<'static>,
+    ) {
+        self.0.on_property_removed(node, property);
+    }
+
     fn on_dom(&self, update: rewrite_core::DomBroadcast) {
         self.0.on_dom(update);
     }
@@ -0,0 +1,97 @@
+//! Keyboard focus traversal order.
+//!
+//! Follows the HTML sequential focus navigation order: elements with a
+//! positive `tabindex` come first in ascending order (ties in DOM order),
+//! followed by elements with `tabindex="0"` or natively focusable elements
+//! in DOM order. Elements with a negative `tabindex`, disabled form
+//! controls, and elements hidden by computed style are skipped.
+
+use crate::accessibility::{element_attribute, is_display_none, is_visibility_hidden};
+use rewrite_core::{Database, NodeId};
+use rewrite_html::{DomTree, NodeData};
+
+/// Compute the sequential focus order for the document.
+pub fn focus_order(tree: &DomTree, database: &Database) -> Vec<NodeId> {
+    let mut candidates = Vec::new();
+    collect_focusable(tree, database, NodeId::ROOT, &mut candidates);
+    // Stable sort keeps DOM order within each tabindex; 0 sorts last.
+    candidates.sort_by_key(|&(_, tabindex)| if tabindex > 0 { tabindex } else { i32::MAX });
+    candidates.into_iter().map(|(node, _)| node).collect()
+}
+
+/// The element focused after `node` when tabbing forward.
+///
+/// Wraps around to the first element. If `node` is not in the focus order
+/// (or is `NodeId::ROOT`), returns the first focusable element.
+pub fn next_focus(tree: &DomTree, database: &Database, node: NodeId) -> Option<NodeId> {
+    let order = focus_order(tree, database);
+    let next = order
+        .iter()
+        .position(|&candidate| candidate == node)
+        .map_or(0, |index| (index + 1) % order.len());
+    order.get(next).copied()
+}
+
+/// The element focused before `node` when tabbing backward.
+///
+/// Wraps around to the last element. If `node` is not in the focus order
+/// (or is `NodeId::ROOT`), returns the last focusable element.
+pub fn prev_focus(tree: &DomTree, database: &Database, node: NodeId) -> Option<NodeId> {
+    let order = focus_order(tree, database);
+    let prev = order
+        .iter()
+        .position(|&candidate| candidate == node)
+        .map_or(order.len().checked_sub(1)?, |index| {
+            index.checked_sub(1).unwrap_or(order.len() - 1)
+        });
+    order.get(prev).copied()
+}
+
+/// Walk the tree in DOM order collecting `(node, tabindex)` pairs.
+///
+/// Subtrees under `display: none` are skipped entirely; `visibility: hidden`
+/// only excludes the element itself since descendants may be visible.
+fn collect_focusable(
+    tree: &DomTree,
+    database: &Database,
+    node: NodeId,
+    out: &mut Vec<(NodeId, i32)>,
+) {
+    let mut children: Vec<NodeId> = tree.children(node).collect();
+    // Children are stored newest-first.
+    children.reverse();
+    for child in children {
+        let Some(NodeData::Element { tag, .. }) = tree.get_node(child) else {
+            continue;
+        };
+        if is_display_none(database, child) {
+            continue;
+        }
+        if !is_visibility_hidden(database, child)
+            && let Some(tabindex) = tabindex_of(tree, child, tree.interner.resolve(tag))
+        {
+            out.push((child, tabindex));
+        }
+        collect_focusable(tree, database, child, out);
+    }
+}
+
+/// Effective tabindex for a focusable element, or `None` if it is not
+/// reachable by sequential navigation.
+fn tabindex_of(tree: &DomTree, node: NodeId, tag: &str) -> Option<i32> {
+    if let Some(value) = element_attribute(tree, node, "tabindex")
+        && let Ok(tabindex) = value.trim().parse::<i32>()
+    {
+        return (tabindex >= 0).then_some(tabindex);
+    }
+    let disabled = element_attribute(tree, node, "disabled").is_some();
+    let focusable = match tag.to_ascii_lowercase().as_str() {
+        "a" | "area" => element_attribute(tree, node, "href").is_some(),
+        "input" => !disabled && element_attribute(tree, node, "type").as_deref() != Some("hidden"),
+        "button" | "select" | "textarea" => !disabled,
+        "summary" | "iframe" => true,
+        _ => element_attribute(tree, node, "contenteditable")
+            .is_some_and(|value| value.is_empty() || value.eq_ignore_ascii_case("true")),
+    };
+    focusable.then_some(0)
+}
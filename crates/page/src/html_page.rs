@@ -0,0 +1,157 @@
+//! A page, its renderer and its document load in one place.
+//!
+//! Every embedder showing a single document wires a `Page`, its
+//! `Renderer` and an `HtmlStream` together, decodes the bytes it receives
+//! and works out what to repaint. `HtmlPage` does that glue. Bytes given
+//! to `push` are only buffered; each `tick` parses whatever arrived since
+//! the previous one, so parsing happens once per frame however the
+//! network splits the document.
+//!
+//! Documents are decoded as UTF-8. A sequence split across pushes is
+//! decoded once complete; invalid bytes become U+FFFD.
+
+use crate::{Browser, HtmlStream, Page};
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::NodeId;
+use rewrite_layout::Rect;
+use rewrite_renderer::{BoxArea, Renderer};
+use std::sync::Arc;
+use std::{mem, str};
+
+/// A page loading one HTML document, with its renderer.
+pub struct HtmlPage<'br> {
+    page: Page<'br>,
+    renderer: Arc<Renderer>,
+    /// `None` once the document has been finished.
+    stream: Option<HtmlStream<'br>>,
+    /// The start of a UTF-8 sequence whose remaining bytes haven't
+    /// arrived.
+    undecoded: Vec<u8>,
+    /// Text received since the last tick.
+    pending: String,
+    /// `finish` was called; the next tick ends the document.
+    ended: bool,
+    /// Border boxes to repaint, since the last `take_dirty_rects`.
+    dirty: Vec<Rect>,
+}
+
+impl<'br> HtmlPage<'br> {
+    /// An empty page of `browser` with a `width`×`height` viewport.
+    pub fn new(browser: &'br Browser, width: u32, height: u32) -> Self {
+        let (page, renderer) = browser.new_page();
+        renderer.set_viewport(width, height);
+        let stream = page.begin_html();
+        Self {
+            page,
+            renderer,
+            stream: Some(stream),
+            undecoded: Vec::new(),
+            pending: String::new(),
+            ended: false,
+            dirty: Vec::new(),
+        }
+    }
+
+    pub fn page(&self) -> &Page<'br> {
+        &self.page
+    }
+
+    pub fn renderer(&self) -> &Arc<Renderer> {
+        &self.renderer
+    }
+
+    /// Whether the document is still being received or parsed.
+    pub fn is_loading(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Buffer the next bytes of the document, to be parsed on the next
+    /// tick. Ignored once `finish` has been called.
+    pub fn push(&mut self, bytes: &[u8]) {
+        if self.ended {
+            return;
+        }
+        let mut input = mem::take(&mut self.undecoded);
+        input.extend_from_slice(bytes);
+        let mut start = 0;
+        loop {
+            let error = match str::from_utf8(&input[start..]) {
+                Ok(text) => {
+                    self.pending.push_str(text);
+                    return;
+                }
+                Err(error) => error,
+            };
+            let valid = start + error.valid_up_to();
+            self.pending
+                .push_str(&String::from_utf8_lossy(&input[start..valid]));
+            let Some(invalid) = error.error_len() else {
+                self.undecoded = input.split_off(valid);
+                return;
+            };
+            self.pending.push(char::REPLACEMENT_CHARACTER);
+            start = valid + invalid;
+        }
+    }
+
+    /// Mark the end of the document. The next tick parses the rest and
+    /// applies rules deferred until every stylesheet is known.
+    pub fn finish(&mut self) {
+        if !mem::take(&mut self.undecoded).is_empty() {
+            self.pending.push(char::REPLACEMENT_CHARACTER);
+        }
+        self.ended = true;
+    }
+
+    /// Parse the text received since the last tick, ending the document
+    /// if `finish` was called, and collect the boxes to repaint. Returns
+    /// whether any box moved, resized, appeared or disappeared.
+    pub fn tick(&mut self) -> bool {
+        if let Some(stream) = &mut self.stream
+            && !self.pending.is_empty()
+        {
+            stream.push(&mem::take(&mut self.pending));
+        }
+        if self.ended
+            && let Some(stream) = self.stream.take()
+        {
+            stream.finish();
+        }
+
+        let diff = self.renderer.take_geometry_diff();
+        let changes = [
+            &diff.moved,
+            &diff.resized,
+            &diff.appeared,
+            &diff.disappeared,
+        ];
+        for change in changes.into_iter().flatten() {
+            self.dirty.extend(change.old.into_iter().chain(change.new));
+        }
+        !diff.is_empty()
+    }
+
+    /// The border box of every node with a box, in tree order.
+    pub fn geometry(&self) -> Vec<(NodeId, Rect)> {
+        self.renderer.laid_out_boxes(BoxArea::Border)
+    }
+
+    /// The old and new border boxes of every box that moved, resized,
+    /// appeared or disappeared over the ticks since the last call. Paint
+    /// changes that leave geometry alone, such as a new color, and
+    /// scrolling aren't included.
+    pub fn take_dirty_rects(&mut self) -> Vec<Rect> {
+        mem::take(&mut self.dirty)
+    }
+
+    /// The computed value of `prop_id` on `node`, inherited from its
+    /// ancestors for inherited properties. `None` if it has no value, such
+    /// as for a non-inherited property nothing declares.
+    pub fn computed_style(
+        &self,
+        node: NodeId,
+        prop_id: &PropertyId<'static>,
+    ) -> Option<Property<'static>> {
+        self.page.db.get_property(node, prop_id.clone())
+    }
+}
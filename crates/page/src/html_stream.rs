@@ -0,0 +1,243 @@
+//! Incremental HTML ingestion.
+//!
+//! `HtmlStream` feeds HTML to a page chunk by chunk. Each chunk's DOM
+//! updates (node creation, insertion, attributes) are applied to the tree,
+//! styled and broadcast to subscribers before `push` returns, so layout can
+//! be observed while the document is still loading. `<style>` contents and
+//! linked stylesheets are applied as soon as the chunk that completes them
+//! has been parsed.
+
+use crate::replay::{Recorder, ReplayEvent};
+use crate::{Page, ResourceResolver, accessibility};
+use lasso::ThreadedRodeo;
+use rewrite_core::{DomBroadcast, NodeId, Parser as _};
+use rewrite_css::{CssParser, ParsedRule, Styler};
+use rewrite_html::{DomTree, DomUpdate, HtmlParser, NodeData};
+use std::mem;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::runtime::Runtime;
+
+/// Receives DOM updates from the HTML parser.
+type DomCallback = Box<dyn Fn(DomUpdate) -> NodeId + Send>;
+
+/// Receives rules from the CSS parser.
+type RuleCallback = Box<dyn Fn(ParsedRule) + Send + Sync>;
+
+/// CSS text found while parsing, in document order, with the element
+/// whose stylesheet it belongs to, not yet applied.
+type CssChunks = Arc<Mutex<Vec<(NodeId, String)>>>;
+
+/// An HTML document being loaded into a page. It holds the page's
+/// document, not the page itself, so it can be kept next to the page.
+pub struct HtmlStream<'br> {
+    html: HtmlParser<DomCallback>,
+    css: DocumentCss<'br>,
+}
+
+impl<'br> HtmlStream<'br> {
+    pub(crate) fn new(page: &Page<'br>) -> Self {
+        let css = DocumentCss::new(page);
+        let html = HtmlParser::new(
+            dom_callback(page, Arc::clone(&css.chunks)),
+            Arc::clone(&page.tree.interner),
+        );
+        Self { html, css }
+    }
+
+    /// Parse the next chunk of HTML and apply the resulting DOM updates.
+    pub fn push(&mut self, chunk: &str) {
+        self.html.process(chunk);
+        self.css.apply();
+    }
+
+    /// End the document: flush the parser, apply the remaining CSS and
+    /// resolve rules deferred until all stylesheets are known.
+    pub fn finish(self) {
+        let Self { html, css } = self;
+        html.finish();
+        css.finish();
+    }
+}
+
+/// The document's `<style>` and linked stylesheets, parsed as the DOM
+/// updates that complete them arrive.
+pub(crate) struct DocumentCss<'br> {
+    runtime: &'br Runtime,
+    styler: Arc<Styler>,
+    interner: Arc<ThreadedRodeo>,
+    resource_resolver: Option<ResourceResolver>,
+    recorder: Arc<Recorder>,
+    /// Parser for the current stylesheet, so one split across text nodes
+    /// still parses as a whole.
+    parser: CssParser<RuleCallback>,
+    /// Element (`<style>` or `<link>`) of the stylesheet `parser` is
+    /// parsing. Each sheet gets a fresh parser, so an `@import` at the
+    /// start of one isn't dropped for following another sheet's rules.
+    owner: Option<NodeId>,
+    /// Filled by the DOM callback.
+    pub(crate) chunks: CssChunks,
+}
+
+impl<'br> DocumentCss<'br> {
+    pub(crate) fn new(page: &Page<'br>) -> Self {
+        let resource_resolver = page.resource_resolver.clone();
+        Self {
+            runtime: page.runtime,
+            parser: css_parser(
+                &page.styler,
+                &page.tree.interner,
+                resource_resolver.as_ref(),
+            ),
+            styler: Arc::clone(&page.styler),
+            interner: Arc::clone(&page.tree.interner),
+            resource_resolver,
+            recorder: Arc::clone(&page.recorder),
+            owner: None,
+            chunks: CssChunks::default(),
+        }
+    }
+
+    /// Parse the CSS buffered since the last call, at the end of an HTML
+    /// chunk.
+    pub(crate) fn apply(&mut self) {
+        self.feed();
+        self.recorder.record(|| ReplayEvent::EndChunk);
+    }
+
+    /// Apply the remaining CSS, then resolve rules deferred until all
+    /// stylesheets are known.
+    pub(crate) fn finish(mut self) {
+        self.feed();
+        let Self {
+            runtime,
+            styler,
+            recorder,
+            parser,
+            ..
+        } = self;
+        runtime.block_on(parser.finish());
+        styler.flush();
+        recorder.record(|| ReplayEvent::EndDocument);
+    }
+
+    /// Feed CSS buffered since the last call to the parser, finishing the
+    /// current stylesheet whenever the next chunk belongs to another one.
+    fn feed(&mut self) {
+        let chunks = mem::take(&mut *self.chunks.lock().unwrap_or_else(PoisonError::into_inner));
+        for (owner, chunk) in chunks {
+            if self.owner.is_some_and(|current| current != owner) {
+                let next = css_parser(
+                    &self.styler,
+                    &self.interner,
+                    self.resource_resolver.as_ref(),
+                );
+                let finished = mem::replace(&mut self.parser, next);
+                self.runtime.block_on(finished.finish());
+            }
+            self.owner = Some(owner);
+            self.runtime.block_on(self.parser.push_chunk(&chunk));
+        }
+    }
+}
+
+/// A CSS parser adding the rules of one document stylesheet to `styler`.
+fn css_parser(
+    styler: &Arc<Styler>,
+    interner: &Arc<ThreadedRodeo>,
+    resolver: Option<&ResourceResolver>,
+) -> CssParser<RuleCallback> {
+    let styler = Arc::clone(styler);
+    let css = CssParser::new(
+        Box::new(move |rule: ParsedRule| styler.add_rule(rule)) as RuleCallback,
+        Arc::clone(interner),
+    );
+    match resolver {
+        Some(resolver) => css.with_resource_resolver(Arc::clone(resolver)),
+        None => css,
+    }
+}
+
+/// Apply each DOM update to the page: build the tree, style new nodes,
+/// notify subscribers and collect document CSS into `css_chunks`.
+pub(crate) fn dom_callback(page: &Page<'_>, css_chunks: CssChunks) -> DomCallback {
+    let tree = Arc::clone(&page.tree);
+    let database = Arc::clone(&page.db);
+    let styler = Arc::clone(&page.styler);
+    let subs = Arc::clone(&page.subscriptions);
+    let resolver = page.resource_resolver.clone();
+    let recorder = Arc::clone(&page.recorder);
+
+    Box::new(move |update| match &update {
+        DomUpdate::CreateNode(_) => {
+            let node_id = tree.apply_update(update);
+            recorder.record(|| ReplayEvent::created(&tree, node_id));
+            styler.style_node(node_id);
+            node_id
+        }
+        DomUpdate::AppendChild { parent, child } => {
+            let (parent, child) = (*parent, *child);
+            recorder.record(|| ReplayEvent::AppendChild { parent, child });
+            let node_id = tree.apply_update(update);
+            database.relink_node(child);
+            styler.restyle_node(child);
+
+            if let Some(css) = document_css(&tree, parent, child, resolver.as_ref()) {
+                css_chunks
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(css);
+            }
+
+            subs.notify_dom(DomBroadcast::CreateNode {
+                node: child,
+                parent,
+            });
+            node_id
+        }
+        DomUpdate::SetAttribute { node, name, value } => {
+            recorder.record(|| ReplayEvent::attribute(&tree, *node, *name, value.as_deref()));
+            tree.apply_update(update)
+        }
+    })
+}
+
+/// Check if a node is a `<style>` element.
+fn is_style_element(tree: &DomTree, node: NodeId) -> bool {
+    match tree.get_node(node) {
+        Some(NodeData::Element { tag, .. }) => tree.interner.resolve(tag) == "style",
+        _ => false,
+    }
+}
+
+/// CSS text contributed by appending `child` to `parent`, with the element
+/// whose stylesheet it belongs to: the text of a `<style>` element, or the
+/// resolved contents of a linked stylesheet.
+fn document_css(
+    tree: &DomTree,
+    parent: NodeId,
+    child: NodeId,
+    resolver: Option<&ResourceResolver>,
+) -> Option<(NodeId, String)> {
+    if is_style_element(tree, parent) {
+        return Some((parent, tree.text_content(child)?.to_owned()));
+    }
+    Some((child, resolver?(&linked_stylesheet(tree, child)?)?))
+}
+
+/// The `href` of a `<link rel="stylesheet">` element, or `None` for any
+/// other node. Alternate stylesheets are not applied by default.
+fn linked_stylesheet(tree: &DomTree, node: NodeId) -> Option<Arc<str>> {
+    match tree.get_node(node) {
+        Some(NodeData::Element { tag, .. }) if tree.interner.resolve(tag) == "link" => {}
+        _ => return None,
+    }
+    let rel = accessibility::element_attribute(tree, node, "rel")?;
+    let mut keywords = rel.split_ascii_whitespace();
+    let is_stylesheet = keywords
+        .clone()
+        .any(|keyword| keyword.eq_ignore_ascii_case("stylesheet"));
+    if !is_stylesheet || keywords.any(|keyword| keyword.eq_ignore_ascii_case("alternate")) {
+        return None;
+    }
+    accessibility::element_attribute(tree, node, "href").filter(|href| !href.trim().is_empty())
+}
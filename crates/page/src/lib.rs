@@ -2,18 +2,39 @@
 
 use futures::Stream;
 use futures::StreamExt;
-use rewrite_core::{Database, DomBroadcast, NodeId, Parser, Specificity, Subscriptions};
-use rewrite_css::{CssParser, ParsedRule, Styler};
-use rewrite_html::{DomTree, DomUpdate, HtmlParser, NodeData};
-use std::sync::Arc;
-use std::time::Instant;
+use rewrite_core::{
+    Database, DomBroadcast, NodeId, Origin, Specificity, SubscriberId, Subscriptions,
+};
+pub use rewrite_css::ResourceResolver;
+use rewrite_css::{
+    CssParser, ForcedColorsPalette, PageBox, PageContext, PageDimensions, ParsedRule,
+    ParsedStylesheet, RuleSnapshot, SheetId, Styler, StylesheetWorker, prints_backgrounds,
+};
+use rewrite_html::{DomTree, ElementState};
+use rewrite_renderer::Renderer;
+use std::mem;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
-use tokio::task::LocalSet;
 
+mod accessibility;
 mod browser;
+mod focus_order;
+mod html_page;
+mod html_stream;
+mod mirror_audit;
+mod replay;
 mod ua_stylesheet;
 
+pub use accessibility::{AccessibilityNode, AccessibilityRole, accessibility_tree};
 pub use browser::Browser;
+pub use focus_order::{focus_order, next_focus, prev_focus};
+pub use html_page::HtmlPage;
+pub use html_stream::HtmlStream;
+pub use mirror_audit::{MirrorDivergence, audit_mirrors};
+use replay::Recorder;
+pub use replay::{ReplayError, ReplayEvent, ReplayLog, ReplayNode};
 
 /// Per-navigation page state.
 pub struct Page<'br> {
@@ -22,6 +43,23 @@ pub struct Page<'br> {
     pub styler: Arc<Styler>,
     pub subscriptions: Arc<Subscriptions>,
     runtime: &'br Runtime,
+    sheet_worker: StylesheetWorker,
+    parsed_sheets: Mutex<Receiver<ParsedStylesheet>>,
+    resource_resolver: Option<ResourceResolver>,
+    /// Source of the current UA stylesheet: the built-in defaults unless
+    /// the embedder replaced them, plus any appended rules.
+    ua_css: Mutex<String>,
+    /// Source of the current user stylesheet, reapplied to each new document.
+    user_css: Mutex<String>,
+    /// Number of `replace_document` calls so far.
+    generation: u64,
+    /// Logs updates while recording, shared with the DOM callback and
+    /// resource resolver.
+    recorder: Arc<Recorder>,
+    /// Feeds the Styler's notifications into `db`.
+    database_subscriber: SubscriberId,
+    /// This page's renderer, if `Browser::new_page` created one.
+    pub(crate) renderer_subscriber: Option<SubscriberId>,
 }
 
 impl<'br> Page<'br> {
@@ -35,130 +73,403 @@ impl<'br> Page<'br> {
 
         // Register the database as a subscriber so it receives all
         // property notifications from the Styler.
-        subscriptions.add_subscriber(Box::new(DatabaseSubscriber(database.clone())));
+        let database_subscriber =
+            subscriptions.add_subscriber(Box::new(DatabaseSubscriber(database.clone())));
+        observe_attributes(&tree, &styler);
 
-        Self {
+        let (sheet_worker, parsed_sheets) = StylesheetWorker::spawn();
+
+        let page = Self {
             db: database,
             tree,
             styler,
             subscriptions,
             runtime,
+            sheet_worker,
+            parsed_sheets: Mutex::new(parsed_sheets),
+            resource_resolver: None,
+            ua_css: Mutex::new(ua_stylesheet::UA_CSS.to_owned()),
+            user_css: Mutex::new(String::new()),
+            generation: 0,
+            recorder: Arc::default(),
+            database_subscriber,
+            renderer_subscriber: None,
+        };
+
+        // UA defaults go through the regular cascade at the lowest origin,
+        // so author and user rules override them like any other rule.
+        page.apply_ua_stylesheet();
+        page
+    }
+
+    /// Drop the current document and start an empty one, as when
+    /// navigating. The DOM, author stylesheets, per-node style and the
+    /// stylesheet parses in flight are discarded; the UA and user
+    /// stylesheets, media environment and forced colors carry over.
+    ///
+    /// `renderer` must be this page's renderer, if it has one; its layout
+    /// state is reset for the new document. Its resolve counters are kept,
+    /// so call `Renderer::reset_resolve_stats` to measure the new document
+    /// alone.
+    pub fn replace_document(&mut self, renderer: Option<&Renderer>) {
+        let tree = Arc::new(DomTree::new(Arc::clone(&self.tree.interner)));
+        let styler = Arc::new(Styler::new(tree.clone(), self.subscriptions.clone()));
+        styler.set_media_environment(self.styler.media_environment());
+        styler.set_forced_colors(self.styler.forced_colors());
+        let database = Arc::new(Database::new(tree.clone()));
+
+        // Same place in the notification order, so the database still
+        // stores each property before the renderer lays it out.
+        self.subscriptions.replace_subscriber(
+            self.database_subscriber,
+            Box::new(DatabaseSubscriber(database.clone())),
+        );
+        observe_attributes(&tree, &styler);
+        if let Some(renderer) = renderer {
+            renderer.replace_document(styler.clone(), database.clone());
+        }
+
+        let (mut sheet_worker, parsed_sheets) = StylesheetWorker::spawn();
+        sheet_worker.set_resource_resolver(self.resource_resolver.clone());
+        self.sheet_worker = sheet_worker;
+        self.parsed_sheets = Mutex::new(parsed_sheets);
+        self.tree = tree;
+        self.styler = styler;
+        self.db = database;
+        self.generation += 1;
+        self.recorder.record(|| ReplayEvent::NewDocument);
+
+        self.apply_ua_stylesheet();
+        let user_css = self
+            .user_css
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if !user_css.is_empty() {
+            self.set_user_stylesheet(&user_css);
         }
     }
 
+    /// Number of times the document has been replaced. Node ids from an
+    /// earlier generation don't refer to nodes of the current document.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Fetch external stylesheets referenced by the document through
+    /// `resolver`: `<link rel="stylesheet">` targets and `@import`s. Without
+    /// one, both are ignored.
+    #[must_use]
+    pub fn with_resource_resolver(mut self, resolver: ResourceResolver) -> Self {
+        self.set_resource_resolver(Some(resolver));
+        self
+    }
+
+    /// Use `resolver` for later loads, logging its answers while recording.
+    fn set_resource_resolver(&mut self, resolver: Option<ResourceResolver>) {
+        let resolver = resolver
+            .map(|resolver| replay::recording_resolver(resolver, Arc::clone(&self.recorder)));
+        self.sheet_worker.set_resource_resolver(resolver.clone());
+        self.resource_resolver = resolver;
+    }
+
+    /// Start logging every update that shapes layout, discarding any log
+    /// in progress. The log opens with the current media environment and
+    /// UA and user stylesheets, so start before loading the document.
+    pub fn start_recording(&self) {
+        let media = self.styler.media_environment();
+        let ua_css = self.ua_css.lock().unwrap_or_else(PoisonError::into_inner);
+        let user_css = self.user_css.lock().unwrap_or_else(PoisonError::into_inner);
+        let initial = vec![
+            ReplayEvent::Media {
+                print: media.print,
+                viewport: media.viewport,
+            },
+            ReplayEvent::UaStylesheet(ua_css.clone()),
+            ReplayEvent::UserStylesheet(user_css.clone()),
+        ];
+        self.recorder.start(initial);
+    }
+
+    /// Stop recording and return the log, or `None` if not recording.
+    pub fn stop_recording(&self) -> Option<ReplayLog> {
+        self.recorder.stop()
+    }
+
+    /// Replace the document and rebuild it from `log`, as a fresh page
+    /// given the same updates would. External resources come from the
+    /// log rather than this page's resolver. `renderer` must be this
+    /// page's renderer, if it has one; it is resized to the recorded
+    /// viewport.
+    ///
+    /// # Errors
+    ///
+    /// `ReplayError::Diverged` if the replayed DOM stops matching the
+    /// recorded one; the page is left part way through the log.
+    pub fn replay(
+        &mut self,
+        log: &ReplayLog,
+        renderer: Option<&Renderer>,
+    ) -> Result<(), ReplayError> {
+        let page_resolver = self.resource_resolver.take();
+        self.set_resource_resolver(Some(log.resource_resolver()));
+        self.replace_document(renderer);
+        let replayed = replay::replay_events(self, log, renderer);
+        self.sheet_worker
+            .set_resource_resolver(page_resolver.clone());
+        self.resource_resolver = page_resolver;
+        replayed
+    }
+
+    /// Start loading an HTML document incrementally. DOM updates from each
+    /// pushed chunk are applied before `push` returns.
+    pub fn begin_html(&self) -> HtmlStream<'br> {
+        HtmlStream::new(self)
+    }
+
     /// Load HTML from a stream.
     pub fn load_html(&self, html_stream: impl Stream<Item = String> + Send + 'static) {
-        let tree = self.tree.clone();
-        let interner = self.tree.interner.clone();
-        let css_interner = self.tree.interner.clone();
-        let styler = self.styler.clone();
-        let subs = self.subscriptions.clone();
+        let t1 = Instant::now();
+
+        let mut document = self.begin_html();
+        let mut stream = Box::pin(html_stream);
+        while let Some(chunk) = self.runtime.block_on(stream.next()) {
+            document.push(&chunk);
+        }
+
+        let t2 = Instant::now();
+
+        // Remaining CSS, then low-confidence rules now that all stylesheets
+        // are loaded.
+        document.finish();
+
+        let t3 = Instant::now();
+
+        // Timing output for debugging (only if slow)
+        let total = t3 - t1;
+        if total.as_secs() >= 1 {
+            eprintln!("      [load_html internal breakdown]");
+            eprintln!("        HTML + CSS parse, styling: {:>8.2?}", t2 - t1);
+            eprintln!("        finish + styler.flush():   {:>8.2?}", t3 - t2);
+        }
+    }
+
+    /// Set (`Some`) or remove (`None`) an attribute on an element and
+    /// restyle whatever depends on it.
+    pub fn set_attribute(&self, node: NodeId, name: &str, value: Option<&str>) {
+        let name = self.tree.interner.get_or_intern(name);
+        self.recorder
+            .record(|| ReplayEvent::attribute(&self.tree, node, name, value));
+        self.tree.set_attribute(node, name, value.map(Box::from));
+    }
+
+    /// Set (`enabled`) or clear dynamic state flags of an element, such as
+    /// `ElementState::HOVER` as the pointer moves, and restyle the nodes
+    /// whose `:hover`, `:active` or `:focus` matching changes.
+    pub fn set_element_state(&self, node: NodeId, state: ElementState, enabled: bool) {
+        self.recorder.record(|| ReplayEvent::ElementState {
+            node,
+            state,
+            enabled,
+        });
+        self.styler.set_element_state(node, state, enabled);
+    }
+
+    /// Check the Styler, database sparse trees, text node styles and
+    /// (optionally) the renderer against the DOM, reporting the first
+    /// divergence.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first `MirrorDivergence` found.
+    pub fn audit_mirrors(&self, renderer: Option<&Renderer>) -> Result<(), MirrorDivergence> {
+        audit_mirrors(&self.tree, &self.styler, &self.db, renderer)
+    }
+
+    /// The layout resolution failures `renderer` recorded since the last
+    /// call, with nodes named by their DOM path.
+    pub fn take_resolve_diagnostics(&self, renderer: &Renderer) -> Vec<String> {
+        renderer
+            .take_resolve_diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.describe(&|node| self.tree.node_path(node)))
+            .collect()
+    }
+
+    /// Export the accessibility tree, with bounds from `renderer`.
+    pub fn accessibility_tree(&self, renderer: &Renderer) -> AccessibilityNode {
+        accessibility_tree(&self.tree, &self.db, renderer)
+    }
+
+    /// Sequential keyboard focus order (positive `tabindex` first, then DOM order).
+    pub fn focus_order(&self) -> Vec<NodeId> {
+        focus_order(&self.tree, &self.db)
+    }
+
+    /// The element focused after `node` when tabbing forward.
+    pub fn next_focus(&self, node: NodeId) -> Option<NodeId> {
+        next_focus(&self.tree, &self.db, node)
+    }
+
+    /// The element focused before `node` when tabbing backward.
+    pub fn prev_focus(&self, node: NodeId) -> Option<NodeId> {
+        prev_focus(&self.tree, &self.db, node)
+    }
+
+    /// Replace the UA stylesheet, built-in defaults included, for
+    /// embedders that ship their own (an e-reader's typography, a kiosk's
+    /// margins). The rules keep the UA origin, so any author or user
+    /// declaration still overrides them. Like the user stylesheet, the
+    /// replacement carries over to later documents.
+    pub fn set_ua_stylesheet(&self, css: &str) {
+        css.clone_into(&mut self.ua_css.lock().unwrap_or_else(PoisonError::into_inner));
+        self.apply_ua_stylesheet();
+    }
+
+    /// Add rules to the end of the UA stylesheet, adjusting the defaults
+    /// without restating them. They win over earlier UA rules of equal
+    /// specificity but, being UA-origin, lose to every author and user
+    /// declaration.
+    pub fn append_ua_rules(&self, css: &str) {
+        {
+            let mut ua_css = self.ua_css.lock().unwrap_or_else(PoisonError::into_inner);
+            ua_css.push('\n');
+            ua_css.push_str(css);
+        }
+        self.apply_ua_stylesheet();
+    }
+
+    /// Re-parse the UA stylesheet source and swap it into the cascade.
+    fn apply_ua_stylesheet(&self) {
+        let ua_css = self
+            .ua_css
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let ua_rules = self.parse_rules(&ua_css, Origin::UserAgent);
+        self.styler
+            .replace_stylesheet(SheetId::USER_AGENT, ua_rules);
+        self.recorder.record(|| ReplayEvent::UaStylesheet(ua_css));
+    }
+
+    /// Replace the user stylesheet (user preferences, accessibility
+    /// overrides). Normal user declarations lose to author ones; `!important`
+    /// user declarations win over author `!important`.
+    pub fn set_user_stylesheet(&self, css: &str) {
+        css.clone_into(&mut self.user_css.lock().unwrap_or_else(PoisonError::into_inner));
+        let rules = self.parse_rules(css, Origin::User);
+        self.styler.set_user_stylesheet(rules);
+        self.recorder
+            .record(|| ReplayEvent::UserStylesheet(css.to_owned()));
+    }
+
+    /// Parse a complete stylesheet into rules tagged with `origin`, without
+    /// applying them.
+    fn parse_rules(&self, css: &str, origin: Origin) -> Vec<ParsedRule> {
+        let rules: Arc<Mutex<Vec<ParsedRule>>> = Arc::new(Mutex::new(Vec::new()));
+        let rules_cb = Arc::clone(&rules);
+        let interner = Arc::clone(&self.tree.interner);
+        let mut parser = CssParser::new(
+            move |rule: ParsedRule| {
+                rules_cb
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(rule);
+            },
+            interner,
+        )
+        .with_origin(origin);
+        if let Some(resolver) = &self.resource_resolver {
+            parser = parser.with_resource_resolver(Arc::clone(resolver));
+        }
 
         self.runtime.block_on(async move {
-            let local = LocalSet::new();
-
-            local
-                .run_until(async move {
-                    let t0 = Instant::now();
-
-                    // Load UA stylesheet first so its rules have the lowest
-                    // source-order priority in the cascade.
-                    let ua_styler = self.styler.clone();
-                    let ua_interner = css_interner.clone();
-                    let mut ua_parser = CssParser::new(
-                        move |rule: ParsedRule| ua_styler.add_rule(rule),
-                        ua_interner,
-                    );
-                    ua_parser.push_chunk(ua_stylesheet::UA_CSS).await;
-                    ua_parser.finish().await;
-
-                    let t1 = Instant::now();
-
-                    // Buffer CSS text from <style> elements during HTML parsing.
-                    // Each entry is a complete CSS chunk from a text node.
-                    let css_chunks: Arc<std::sync::Mutex<Vec<String>>> =
-                        Arc::new(std::sync::Mutex::new(Vec::new()));
-
-                    let css_chunks_cb = css_chunks.clone();
-                    let db = self.db.clone();
-
-                    let mut parser = HtmlParser::new(
-                        move |update| {
-                            match &update {
-                                DomUpdate::CreateNode(_) => {
-                                    let node_id = tree.apply_update(update);
-                                    styler.style_node(node_id);
-                                    node_id
-                                }
-                                DomUpdate::AppendChild { parent, child } => {
-                                    let parent = *parent;
-                                    let child = *child;
-                                    let node_id = tree.apply_update(update);
-                                    db.relink_node(child);
-                                    styler.restyle_node(child);
-
-                                    // Buffer CSS text from <style> elements.
-                                    if is_style_element(&tree, parent) {
-                                        if let Some(css_text) = tree.text_content(child) {
-                                            css_chunks_cb.lock().unwrap().push(css_text.to_owned());
-                                        }
-                                    }
-
-                                    subs.notify_dom(DomBroadcast::CreateNode {
-                                        node: child,
-                                        parent,
-                                    });
-                                    node_id
-                                }
-                            }
-                        },
-                        interner,
-                    );
-
-                    let mut stream = Box::pin(html_stream);
-                    while let Some(chunk) = stream.next().await {
-                        parser.process(&chunk);
-                    }
-
-                    parser.finish();
-
-                    let t2 = Instant::now();
-
-                    // Feed buffered CSS into the streaming parser.
-                    let chunks = css_chunks.lock().unwrap().clone();
-                    if !chunks.is_empty() {
-                        let styler_css = self.styler.clone();
-                        let mut css_parser = CssParser::new(
-                            move |rule: ParsedRule| styler_css.add_rule(rule),
-                            css_interner,
-                        );
-
-                        for chunk in chunks {
-                            css_parser.push_chunk(&chunk).await;
-                        }
-
-                        css_parser.finish().await;
-                    }
-
-                    let t3 = Instant::now();
-
-                    // Flush low-confidence rules now that all stylesheets are loaded.
-                    self.styler.flush();
-
-                    let t4 = Instant::now();
-
-                    // Timing output for debugging (only if slow)
-                    let total = t4 - t0;
-                    if total.as_secs() >= 1 {
-                        eprintln!("      [load_html internal breakdown]");
-                        eprintln!("        UA stylesheet parse:  {:>8.2?}", t1 - t0);
-                        eprintln!("        HTML parse + styling: {:>8.2?}", t2 - t1);
-                        eprintln!("        <style> CSS parse:    {:>8.2?}", t3 - t2);
-                        eprintln!("        styler.flush():       {:>8.2?}", t4 - t3);
-                    }
-                })
-                .await;
+            parser.push_chunk(css).await;
+            parser.finish().await;
         });
+
+        mem::take(&mut *rules.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Enter (`Some`) or leave (`None`) forced-colors mode. Author colors
+    /// are overridden by `palette` when painting (`Renderer::used_color`)
+    /// and `@media (forced-colors: active)` rules start applying.
+    pub fn set_forced_colors(&self, palette: Option<ForcedColorsPalette>) {
+        self.styler.set_forced_colors(palette);
+    }
+
+    /// Page box (sheet size and margins) for a printed page, from the
+    /// document's `@page` rules.
+    pub fn page_box(&self, page: PageContext, default_size: PageDimensions) -> PageBox {
+        self.styler.page_box(page, default_size)
+    }
+
+    /// Whether `node`'s backgrounds should be printed
+    /// (`print-color-adjust: exact`).
+    pub fn prints_backgrounds(&self, node: NodeId) -> bool {
+        prints_backgrounds(&self.db, node)
+    }
+
+    /// Parse `css` as the new contents of author stylesheet `sheet` on the
+    /// stylesheet worker thread. Nothing changes until the result is picked
+    /// up by `apply_parsed_stylesheets`; a later call for the same sheet
+    /// cancels this one.
+    pub fn parse_stylesheet(&self, sheet: SheetId, css: String) {
+        self.recorder.sheet_requested(sheet, &css);
+        self.sheet_worker.parse(sheet, css, Origin::Author);
+    }
+
+    /// Apply every stylesheet the worker has finished parsing, skipping
+    /// results superseded since. Meant to be called once per frame. Returns
+    /// the number of sheets applied.
+    pub fn apply_parsed_stylesheets(&self) -> usize {
+        let parsed_sheets = self
+            .parsed_sheets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut applied = 0;
+        for parsed in parsed_sheets.try_iter() {
+            if self.sheet_worker.is_current(&parsed) {
+                self.apply_parsed_stylesheet(parsed);
+                applied += 1;
+            }
+        }
+        applied
+    }
+
+    /// Wait up to `timeout` for the worker to deliver a current stylesheet,
+    /// then apply it and every other one delivered so far. Returns the
+    /// number of sheets applied, 0 if none arrived in time.
+    pub fn wait_for_parsed_stylesheets(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        let parsed_sheets = self
+            .parsed_sheets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let Ok(parsed) = parsed_sheets.recv_timeout(remaining) else {
+                return 0;
+            };
+            if self.sheet_worker.is_current(&parsed) {
+                self.apply_parsed_stylesheet(parsed);
+                drop(parsed_sheets);
+                return 1 + self.apply_parsed_stylesheets();
+            }
+        }
+    }
+
+    fn apply_parsed_stylesheet(&self, parsed: ParsedStylesheet) {
+        self.recorder.sheet_applied(parsed.sheet);
+        self.styler.apply_parsed_stylesheet(parsed);
+    }
+
+    /// Remove author stylesheet `sheet`, cancelling any parse in flight.
+    pub fn remove_stylesheet(&self, sheet: SheetId) {
+        self.sheet_worker.cancel(sheet);
+        self.styler.remove_stylesheet(sheet);
+        self.recorder
+            .record(|| ReplayEvent::RemoveStylesheet(sheet));
     }
 
     /// Load and parse a CSS stylesheet from a stream.
@@ -166,12 +477,26 @@ impl<'br> Page<'br> {
         let interner = self.tree.interner.clone();
         let styler = self.styler.clone();
         let styler_flush = self.styler.clone();
+        let recorder = Arc::clone(&self.recorder);
+        let rule_recorder = Arc::clone(&self.recorder);
+        // Rules are recorded as they are applied, so a replay interleaves
+        // them with the same updates, and with `@import`s already resolved.
+        let apply_rule = move |rule: ParsedRule| {
+            let css = rule_recorder
+                .is_recording()
+                .then(|| RuleSnapshot::capture(&rule).css);
+            styler.add_rule(rule);
+            if let Some(css) = css {
+                rule_recorder.record(|| ReplayEvent::StylesheetRule(css));
+            }
+        };
+        let mut parser = CssParser::new(apply_rule, interner);
+        if let Some(resolver) = &self.resource_resolver {
+            parser = parser.with_resource_resolver(Arc::clone(resolver));
+        }
 
         // Tokio handles the async stream, rayon handles the parsing
         self.runtime.spawn(async move {
-            let mut parser =
-                CssParser::new(move |rule: ParsedRule| styler.add_rule(rule), interner);
-
             let mut stream = Box::pin(css_stream);
             while let Some(chunk) = stream.next().await {
                 parser.push_chunk(&chunk).await;
@@ -181,18 +506,36 @@ impl<'br> Page<'br> {
 
             // Flush low-confidence rules now that parsing is complete
             styler_flush.flush();
+            recorder.record(|| ReplayEvent::LoadedStylesheet);
         });
     }
 }
 
-/// Check if a node is a `<style>` element.
-fn is_style_element(tree: &DomTree, node: NodeId) -> bool {
-    match tree.get_node(node) {
-        Some(NodeData::Element { tag, .. }) => tree.interner.resolve(tag) == "style",
-        _ => false,
+impl Drop for Page<'_> {
+    fn drop(&mut self) {
+        self.subscriptions
+            .remove_subscriber(self.database_subscriber);
+        if let Some(renderer) = self.renderer_subscriber {
+            self.subscriptions.remove_subscriber(renderer);
+        }
     }
 }
 
+/// Restyle through `styler` when an attribute of `tree` changes (from the
+/// parser or the embedder). The Styler ignores names no selector depends
+/// on. Weak so the tree doesn't keep its own Styler alive.
+fn observe_attributes(tree: &DomTree, styler: &Arc<Styler>) {
+    let styler_ref = Arc::downgrade(styler);
+    tree.observe_attribute(
+        None,
+        Box::new(move |node, name| {
+            if let Some(live) = styler_ref.upgrade() {
+                live.attribute_changed(node, name);
+            }
+        }),
+    );
+}
+
 /// Subscriber wrapper that feeds CSS property notifications into the `Database`.
 struct DatabaseSubscriber(Arc<Database>);
 
@@ -200,8 +543,10 @@ impl rewrite_core::Subscriber for DatabaseSubscriber {
     fn on_property(&self, node: rewrite_core::NodeId, property: &rewrite_css::Property<'static>) {
         // Don't store properties at their CSS initial value — the formula
         // system treats missing properties as having their default. Skipping
-        // storage keeps the sparse trees genuinely sparse.
+        // storage keeps the sparse trees genuinely sparse. A previously
+        // stored value must still be dropped so the initial value wins.
         if rewrite_core::is_css_initial_value(property) {
+            self.0.remove_property(node, &property.property_id());
             return;
         }
         // Store with INLINE specificity — the Styler already resolved the
@@ -211,6 +556,14 @@ impl rewrite_core::Subscriber for DatabaseSubscriber {
             .set_property(node, property.clone(), Specificity::INLINE);
     }
 
+    fn on_property_removed(
+        &self,
+        node: rewrite_core::NodeId,
+        property: &rewrite_css::PropertyId<'static>,
+    ) {
+        self.0.remove_property(node, property);
+    }
+
     fn on_dom(&self, _update: DomBroadcast) {
         // DOM structure is handled by the tree itself; the Database
         // discovers parent relationships lazily via TreeAccess.
@@ -0,0 +1,325 @@
+//! Consistency audit between the DOM and the structures that mirror it.
+//!
+//! The `DomTree` is the source of truth. The `Styler` keeps the rules
+//! matched for each node, each `Database` sparse tree keeps its own
+//! shortcut parent/child links, and the renderer's layout state tracks the
+//! nodes it has seen.
+//! Each of these is updated from DOM notifications, so a missed update
+//! makes one of them drift silently; this audit reports the first place
+//! where a mirror disagrees with the DOM.
+//!
+//! Text nodes are styled as anonymous inline boxes: they hold no
+//! declarations and see exactly the inherited values of their parent
+//! element. The audit checks that too, since a text node that picked up a
+//! sparse-tree entry of its own would shadow its parent's text style.
+
+use lightningcss::properties::PropertyId;
+use rewrite_core::{Database, NodeId, PROPERTY_NAMES, PropertyGroup, classify_property};
+use rewrite_css::Styler;
+use rewrite_html::DomTree;
+use rewrite_renderer::Renderer;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// First disagreement found between the DOM and one of its mirrors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MirrorDivergence {
+    /// The Styler has no match storage for a DOM node.
+    StylerMissingNode { node: NodeId },
+    /// The rules the Styler recorded as matching a node differ from those
+    /// that match it now. Both lists hold rule indices in ascending order.
+    MatchedRules {
+        node: NodeId,
+        expected: Vec<usize>,
+        actual: Vec<usize>,
+    },
+    /// A sparse tree links a node to a parent other than its nearest
+    /// DOM ancestor present in that tree.
+    SparseParent {
+        group: PropertyGroup,
+        node: NodeId,
+        expected: Option<NodeId>,
+        actual: Option<NodeId>,
+    },
+    /// A sparse tree's child list for `parent` disagrees with the parent
+    /// links of its members. Both lists are in DOM order; sparse children
+    /// are linked in the order they entered the tree, so the actual list
+    /// is put in DOM order before comparing.
+    SparseChildren {
+        group: PropertyGroup,
+        parent: NodeId,
+        expected: Vec<NodeId>,
+        actual: Vec<NodeId>,
+    },
+    /// A text node stores a declaration in a sparse tree of its own.
+    TextDeclaration { group: PropertyGroup, node: NodeId },
+    /// A text node's value of an inherited property differs from its
+    /// parent element's.
+    TextInheritance {
+        node: NodeId,
+        property: &'static str,
+    },
+    /// An attached DOM node was never seen by layout.
+    LayoutMissingNode { node: NodeId },
+    /// Layout tracks a node that is not attached to the document.
+    LayoutUnknownNode { node: NodeId },
+}
+
+impl MirrorDivergence {
+    /// The divergence as text, naming each node with `node_name`, e.g.
+    /// `DomTree::node_path`, which unlike its ID is stable across runs.
+    pub fn describe(&self, node_name: &dyn Fn(NodeId) -> String) -> String {
+        let optional = |node: &Option<NodeId>| node.map_or_else(|| "none".to_owned(), node_name);
+        let list = |nodes: &[NodeId]| {
+            let names: Vec<String> = nodes.iter().map(|&node| node_name(node)).collect();
+            format!("[{}]", names.join(", "))
+        };
+        match self {
+            Self::StylerMissingNode { node } => {
+                format!("styler has no entry for node {}", node_name(*node))
+            }
+            Self::MatchedRules {
+                node,
+                expected,
+                actual,
+            } => format!(
+                "styler: node {} has matched rules {actual:?}, selectors match {expected:?}",
+                node_name(*node)
+            ),
+            Self::SparseParent {
+                group,
+                node,
+                expected,
+                actual,
+            } => format!(
+                "{group:?} tree: node {} has parent {}, DOM implies {}",
+                node_name(*node),
+                optional(actual),
+                optional(expected)
+            ),
+            Self::SparseChildren {
+                group,
+                parent,
+                expected,
+                actual,
+            } => format!(
+                "{group:?} tree: node {} has children {}, DOM implies {}",
+                node_name(*parent),
+                list(actual),
+                list(expected)
+            ),
+            Self::TextDeclaration { group, node } => {
+                format!(
+                    "{group:?} tree: text node {} has declarations",
+                    node_name(*node)
+                )
+            }
+            Self::TextInheritance { node, property } => format!(
+                "text node {} doesn't inherit `{property}` from its parent",
+                node_name(*node)
+            ),
+            Self::LayoutMissingNode { node } => {
+                format!("layout never saw attached node {}", node_name(*node))
+            }
+            Self::LayoutUnknownNode { node } => {
+                format!("layout tracks detached node {}", node_name(*node))
+            }
+        }
+    }
+}
+
+impl fmt::Display for MirrorDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe(&|node| node.0.to_string()))
+    }
+}
+
+/// Compare the Styler's node storage and matched rules, every sparse tree,
+/// the style of text nodes and (optionally) the renderer's layout state
+/// against the DOM, returning the first divergence.
+///
+/// Call this while no updates are in flight; concurrent mutation can
+/// produce spurious reports.
+///
+/// # Errors
+///
+/// Returns the first `MirrorDivergence` found, checking the Styler, then
+/// each sparse tree, then text nodes, then layout. Nodes are checked in
+/// DOM order.
+pub fn audit_mirrors(
+    tree: &DomTree,
+    styler: &Styler,
+    database: &Database,
+    renderer: Option<&Renderer>,
+) -> Result<(), MirrorDivergence> {
+    let node_count = tree.nodes.count();
+    if styler.styled_node_count() < node_count {
+        return Err(MirrorDivergence::StylerMissingNode {
+            node: NodeId(styler.styled_node_count() as u32),
+        });
+    }
+
+    let attached = attached_nodes(tree);
+    if let Some(divergence) = matched_rules_divergence(&attached, styler) {
+        return Err(divergence);
+    }
+
+    let dom_order: HashMap<NodeId, usize> = attached
+        .iter()
+        .enumerate()
+        .map(|(position, &node)| (node, position))
+        .collect();
+    if let Some(divergence) = PropertyGroup::ALL
+        .into_iter()
+        .find_map(|group| sparse_tree_divergence(tree, database, group, &dom_order))
+    {
+        return Err(divergence);
+    }
+    if let Some(divergence) = text_style_divergence(tree, database) {
+        return Err(divergence);
+    }
+    if let Some(renderer) = renderer
+        && let Some(divergence) = layout_divergence(tree, renderer)
+    {
+        return Err(divergence);
+    }
+    Ok(())
+}
+
+fn matched_rules_divergence(attached: &[NodeId], styler: &Styler) -> Option<MirrorDivergence> {
+    attached.iter().find_map(|&node| {
+        let (actual, expected) = styler.recorded_and_current_matches(node);
+        (actual != expected).then_some(MirrorDivergence::MatchedRules {
+            node,
+            expected,
+            actual,
+        })
+    })
+}
+
+fn sparse_tree_divergence(
+    tree: &DomTree,
+    database: &Database,
+    group: PropertyGroup,
+    dom_order: &HashMap<NodeId, usize>,
+) -> Option<MirrorDivergence> {
+    // Detached members sort after the document, by ID.
+    let position = |node: &NodeId| (dom_order.get(node).copied().unwrap_or(usize::MAX), node.0);
+    let sparse = database.tree_for_group(group);
+    let mut nodes = sparse.dom_nodes();
+    nodes.sort_unstable_by_key(position);
+
+    let mut expected_children: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+    for &node in &nodes {
+        let expected = nearest_ancestor_in(tree, node, |ancestor| sparse.contains(ancestor));
+        let actual = sparse.parent_of(node);
+        if expected != actual {
+            return Some(MirrorDivergence::SparseParent {
+                group,
+                node,
+                expected,
+                actual,
+            });
+        }
+        if let Some(parent) = expected {
+            expected_children.entry(parent).or_default().push(node);
+        }
+    }
+
+    for &node in &nodes {
+        let expected = expected_children.remove(&node).unwrap_or_default();
+        let mut actual = sparse.children_of(node);
+        actual.sort_unstable_by_key(position);
+        if expected != actual {
+            return Some(MirrorDivergence::SparseChildren {
+                group,
+                parent: node,
+                expected,
+                actual,
+            });
+        }
+    }
+    None
+}
+
+fn text_style_divergence(tree: &DomTree, database: &Database) -> Option<MirrorDivergence> {
+    let inherited: Vec<(&'static str, PropertyId<'static>)> = PROPERTY_NAMES
+        .iter()
+        .map(|&name| (name, PropertyId::from(name)))
+        .filter(|(_, prop_id)| classify_property(prop_id) == Some(PropertyGroup::Text))
+        .collect();
+
+    for node in attached_nodes(tree) {
+        if tree.text_content(node).is_none() {
+            continue;
+        }
+        if let Some(group) = PropertyGroup::ALL
+            .into_iter()
+            .find(|&group| database.tree_for_group(group).contains(node))
+        {
+            return Some(MirrorDivergence::TextDeclaration { group, node });
+        }
+        let Some(parent) = tree.parent(node) else {
+            continue;
+        };
+        if let Some(&(property, _)) = inherited.iter().find(|(_, prop_id)| {
+            database.get_property(node, prop_id.clone())
+                != database.get_property(parent, prop_id.clone())
+        }) {
+            return Some(MirrorDivergence::TextInheritance { node, property });
+        }
+    }
+    None
+}
+
+fn layout_divergence(tree: &DomTree, renderer: &Renderer) -> Option<MirrorDivergence> {
+    let tracked: HashSet<NodeId> = renderer.tracked_nodes().into_iter().collect();
+    let attached = attached_nodes(tree);
+
+    // The document node is never announced to layout.
+    if let Some(&node) = attached
+        .iter()
+        .find(|&&node| node != NodeId::ROOT && !tracked.contains(&node))
+    {
+        return Some(MirrorDivergence::LayoutMissingNode { node });
+    }
+
+    let attached: HashSet<NodeId> = attached.into_iter().collect();
+    let mut unknown: Vec<NodeId> = tracked.difference(&attached).copied().collect();
+    unknown.sort_unstable_by_key(|node| node.0);
+    unknown
+        .first()
+        .map(|&node| MirrorDivergence::LayoutUnknownNode { node })
+}
+
+/// Nodes reachable from the document root, in DOM pre-order.
+fn attached_nodes(tree: &DomTree) -> Vec<NodeId> {
+    if tree.nodes.count() == 0 {
+        return Vec::new();
+    }
+    let mut order = Vec::new();
+    let mut stack = vec![NodeId::ROOT];
+    while let Some(node) = stack.pop() {
+        order.push(node);
+        // `children()` is newest-first, which is the reverse of DOM order;
+        // pushing it as-is pops the first DOM child next.
+        stack.extend(tree.children(node));
+    }
+    order
+}
+
+/// Nearest DOM ancestor of `node` (excluding itself) satisfying `present`.
+fn nearest_ancestor_in(
+    tree: &DomTree,
+    node: NodeId,
+    present: impl Fn(NodeId) -> bool,
+) -> Option<NodeId> {
+    let mut current = tree.parent(node);
+    while let Some(ancestor) = current {
+        if present(ancestor) {
+            return Some(ancestor);
+        }
+        current = tree.parent(ancestor);
+    }
+    None
+}
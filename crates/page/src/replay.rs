@@ -0,0 +1,645 @@
+//! Recording and deterministic replay of page updates.
+//!
+//! While a page records, every update that shapes its layout is appended
+//! to a `ReplayLog`: DOM updates from the HTML parser and the embedder,
+//! chunk and document boundaries, stylesheet replacements, and the
+//! contents of every resource the resolver handed out. Replaying the log
+//! into a page rebuilds the same DOM (with the same node ids) and the same
+//! cascade, so a bug report can carry a replay file instead of a
+//! description. Node creation is checked along the way: a replay whose
+//! node ids drift from the recording stops with `ReplayError::Diverged`.
+//!
+//! Recording starts from the page's current UA and user stylesheets and
+//! media environment, so start it before loading the document. Forced
+//! colors aren't recorded, nor are stylesheet parses requested before
+//! recording started.
+//!
+//! The text form is one event per line after a `replay-log 1` header,
+//! with fields separated by single spaces. Text fields escape backslashes,
+//! line breaks and spaces; optional fields are `-` when absent and
+//! `=`-prefixed when present.
+
+use crate::html_stream::{DocumentCss, dom_callback};
+use crate::{Page, ResourceResolver};
+use lasso::Spur;
+use rewrite_core::{NodeId, Origin};
+use rewrite_css::{MediaEnvironment, SheetId};
+use rewrite_html::{DomTree, DomUpdate, ElementState, Namespace, NodeData};
+use rewrite_renderer::Renderer;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+const HEADER: &str = "replay-log 1";
+
+/// A node as created, with its attributes at creation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayNode {
+    Document,
+    Element {
+        namespace: Namespace,
+        tag: String,
+        /// Sorted by name.
+        attributes: Vec<(String, String)>,
+    },
+    Text(String),
+    Comment(String),
+}
+
+impl ReplayNode {
+    fn to_fields(&self) -> String {
+        match self {
+            Self::Document => "document".to_owned(),
+            Self::Element {
+                namespace,
+                tag,
+                attributes,
+            } => {
+                let mut fields = format!("element {} {}", namespace_name(*namespace), escape(tag));
+                for field in attributes.iter().flat_map(|(name, value)| [name, value]) {
+                    fields.push(' ');
+                    fields.push_str(&escape(field));
+                }
+                fields
+            }
+            Self::Text(text) => format!("text {}", escape(text)),
+            Self::Comment(text) => format!("comment {}", escape(text)),
+        }
+    }
+
+    fn from_fields<'line>(fields: &mut impl Iterator<Item = &'line str>) -> Option<Self> {
+        Some(match fields.next()? {
+            "document" => Self::Document,
+            "element" => {
+                let namespace = parse_namespace(fields.next()?)?;
+                let tag = unescape(fields.next()?)?;
+                let mut attributes = Vec::new();
+                while let Some(name) = fields.next() {
+                    attributes.push((unescape(name)?, unescape(fields.next()?)?));
+                }
+                Self::Element {
+                    namespace,
+                    tag,
+                    attributes,
+                }
+            }
+            "text" => Self::Text(unescape(fields.next()?)?),
+            "comment" => Self::Comment(unescape(fields.next()?)?),
+            _ => return None,
+        })
+    }
+}
+
+/// One recorded update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayEvent {
+    /// The media type and viewport queries were evaluated against.
+    Media {
+        print: bool,
+        viewport: Option<(u32, u32)>,
+    },
+    /// The full UA stylesheet source.
+    UaStylesheet(String),
+    /// The full user stylesheet source.
+    UserStylesheet(String),
+    /// `url` resolved to `contents` (`None` if the resolver had nothing).
+    Resource {
+        url: String,
+        contents: Option<String>,
+    },
+    /// The document was replaced by an empty one.
+    NewDocument,
+    CreateNode {
+        node: NodeId,
+        data: ReplayNode,
+    },
+    AppendChild {
+        parent: NodeId,
+        child: NodeId,
+    },
+    SetAttribute {
+        node: NodeId,
+        name: String,
+        value: Option<String>,
+    },
+    /// Dynamic state flags of `node` were set (`enabled`) or cleared.
+    ElementState {
+        node: NodeId,
+        state: ElementState,
+        enabled: bool,
+    },
+    /// An HTML chunk was fully applied, along with the document CSS it
+    /// completed.
+    EndChunk,
+    /// The HTML document finished loading.
+    EndDocument,
+    /// Author stylesheet `sheet` was replaced by `css`.
+    Stylesheet {
+        sheet: SheetId,
+        css: String,
+    },
+    RemoveStylesheet(SheetId),
+    /// A rule of a stylesheet loaded from a stream was applied, as CSS
+    /// with its `@import`s resolved.
+    StylesheetRule(String),
+    /// A stylesheet loaded from a stream finished, and the rules it added
+    /// were flushed.
+    LoadedStylesheet,
+}
+
+impl ReplayEvent {
+    /// Creation of `node`, as it is in `tree`.
+    pub fn created(tree: &DomTree, node: NodeId) -> Self {
+        let data = match tree.get_node(node) {
+            Some(NodeData::Element {
+                tag,
+                namespace,
+                attributes,
+            }) => {
+                let mut recorded = Vec::with_capacity(attributes.len());
+                attributes.for_each(|name, value| {
+                    recorded.push((tree.interner.resolve(&name).to_owned(), value.to_owned()));
+                });
+                recorded.sort();
+                ReplayNode::Element {
+                    namespace: *namespace,
+                    tag: tree.interner.resolve(tag).to_owned(),
+                    attributes: recorded,
+                }
+            }
+            Some(NodeData::Text(text)) => ReplayNode::Text(text.to_string()),
+            Some(NodeData::Comment(text)) => ReplayNode::Comment(text.to_string()),
+            Some(NodeData::Document) | None => ReplayNode::Document,
+        };
+        Self::CreateNode { node, data }
+    }
+
+    /// Setting (`Some`) or removing (`None`) attribute `name` of `node`.
+    pub fn attribute(tree: &DomTree, node: NodeId, name: Spur, value: Option<&str>) -> Self {
+        Self::SetAttribute {
+            node,
+            name: tree.interner.resolve(&name).to_owned(),
+            value: value.map(str::to_owned),
+        }
+    }
+
+    fn to_line(&self) -> String {
+        match self {
+            Self::Media { print, viewport } => {
+                let media = if *print { "print" } else { "screen" };
+                let viewport = viewport.map_or_else(
+                    || "-".to_owned(),
+                    |(width, height)| format!("{width}x{height}"),
+                );
+                format!("media {media} {viewport}")
+            }
+            Self::UaStylesheet(css) => format!("ua-stylesheet {}", escape(css)),
+            Self::UserStylesheet(css) => format!("user-stylesheet {}", escape(css)),
+            Self::Resource { url, contents } => {
+                format!("resource {} {}", escape(url), optional(contents.as_deref()))
+            }
+            Self::NewDocument => "new-document".to_owned(),
+            Self::CreateNode { node, data } => format!("node {} {}", node.0, data.to_fields()),
+            Self::AppendChild { parent, child } => format!("append {} {}", parent.0, child.0),
+            Self::SetAttribute { node, name, value } => format!(
+                "attribute {} {} {}",
+                node.0,
+                escape(name),
+                optional(value.as_deref())
+            ),
+            Self::ElementState {
+                node,
+                state,
+                enabled,
+            } => {
+                let enabled = if *enabled { "on" } else { "off" };
+                format!("state {} {} {enabled}", node.0, state.bits())
+            }
+            Self::EndChunk => "end-chunk".to_owned(),
+            Self::EndDocument => "end-document".to_owned(),
+            Self::Stylesheet { sheet, css } => format!("stylesheet {} {}", sheet.0, escape(css)),
+            Self::RemoveStylesheet(sheet) => format!("remove-stylesheet {}", sheet.0),
+            Self::StylesheetRule(css) => format!("stylesheet-rule {}", escape(css)),
+            Self::LoadedStylesheet => "loaded-stylesheet".to_owned(),
+        }
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split(' ');
+        let kind = fields.next()?;
+        let event = match kind {
+            "media" => {
+                let print = match fields.next()? {
+                    "print" => true,
+                    "screen" => false,
+                    _ => return None,
+                };
+                let viewport = match fields.next()? {
+                    "-" => None,
+                    size => {
+                        let (width, height) = size.split_once('x')?;
+                        Some((width.parse().ok()?, height.parse().ok()?))
+                    }
+                };
+                Self::Media { print, viewport }
+            }
+            "ua-stylesheet" => Self::UaStylesheet(unescape(fields.next()?)?),
+            "user-stylesheet" => Self::UserStylesheet(unescape(fields.next()?)?),
+            "resource" => Self::Resource {
+                url: unescape(fields.next()?)?,
+                contents: parse_optional(fields.next()?).ok()?,
+            },
+            "new-document" => Self::NewDocument,
+            "node" => Self::CreateNode {
+                node: parse_node(fields.next()?)?,
+                data: ReplayNode::from_fields(&mut fields)?,
+            },
+            "append" => Self::AppendChild {
+                parent: parse_node(fields.next()?)?,
+                child: parse_node(fields.next()?)?,
+            },
+            "attribute" => Self::SetAttribute {
+                node: parse_node(fields.next()?)?,
+                name: unescape(fields.next()?)?,
+                value: parse_optional(fields.next()?).ok()?,
+            },
+            "state" => Self::ElementState {
+                node: parse_node(fields.next()?)?,
+                state: ElementState::from_bits(fields.next()?.parse().ok()?),
+                enabled: match fields.next()? {
+                    "on" => true,
+                    "off" => false,
+                    _ => return None,
+                },
+            },
+            "end-chunk" => Self::EndChunk,
+            "end-document" => Self::EndDocument,
+            "stylesheet" => Self::Stylesheet {
+                sheet: SheetId(fields.next()?.parse().ok()?),
+                css: unescape(fields.next()?)?,
+            },
+            "remove-stylesheet" => Self::RemoveStylesheet(SheetId(fields.next()?.parse().ok()?)),
+            "stylesheet-rule" => Self::StylesheetRule(unescape(fields.next()?)?),
+            "loaded-stylesheet" => Self::LoadedStylesheet,
+            _ => return None,
+        };
+        fields.next().is_none().then_some(event)
+    }
+}
+
+/// The updates recorded from a page, in the order they were applied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayLog {
+    pub events: Vec<ReplayEvent>,
+}
+
+impl ReplayLog {
+    /// The log in its text form.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER}\n");
+        for event in &self.events {
+            text.push_str(&event.to_line());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Read a log from its text form.
+    ///
+    /// # Errors
+    ///
+    /// `ReplayError::Malformed` for a missing header or an unreadable line.
+    pub fn from_text(text: &str) -> Result<Self, ReplayError> {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(ReplayError::Malformed { line: 1 });
+        }
+        let events = lines
+            .enumerate()
+            .map(|(index, line)| {
+                ReplayEvent::from_line(line).ok_or(ReplayError::Malformed { line: index + 2 })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { events })
+    }
+
+    /// A resolver answering with the recorded resources. The first
+    /// recording of a URL wins.
+    pub fn resource_resolver(&self) -> ResourceResolver {
+        let mut resources: HashMap<String, Option<String>> = HashMap::new();
+        for event in &self.events {
+            if let ReplayEvent::Resource { url, contents } = event {
+                resources
+                    .entry(url.clone())
+                    .or_insert_with(|| contents.clone());
+            }
+        }
+        Arc::new(move |url| resources.get(url).cloned().flatten())
+    }
+}
+
+/// Why a replay failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Line `line` (1-based) of a text log couldn't be read.
+    Malformed { line: usize },
+    /// Replay created node `actual` where the recording created `expected`.
+    Diverged { expected: NodeId, actual: NodeId },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed { line } => write!(f, "malformed replay log at line {line}"),
+            Self::Diverged { expected, actual } => write!(
+                f,
+                "replay diverged: created node {} where the recording created {}",
+                actual.0, expected.0
+            ),
+        }
+    }
+}
+
+/// A page's recording, if one is running.
+#[derive(Default)]
+pub struct Recorder {
+    recording: Mutex<Option<Recording>>,
+}
+
+/// A running recording.
+struct Recording {
+    log: ReplayLog,
+    /// Latest source requested for each author stylesheet, logged when
+    /// its parse is applied.
+    pending_sheets: HashMap<SheetId, String>,
+}
+
+impl Recorder {
+    /// Start a new log, discarding any running one, with `initial` as its
+    /// first events.
+    pub fn start(&self, initial: Vec<ReplayEvent>) {
+        *self.lock() = Some(Recording {
+            log: ReplayLog { events: initial },
+            pending_sheets: HashMap::new(),
+        });
+    }
+
+    /// End the recording, returning its log.
+    pub fn stop(&self) -> Option<ReplayLog> {
+        self.lock().take().map(|recording| recording.log)
+    }
+
+    /// Whether a recording is running.
+    pub fn is_recording(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// Log the event built by `event`, if recording.
+    pub fn record(&self, event: impl FnOnce() -> ReplayEvent) {
+        if let Some(recording) = self.lock().as_mut() {
+            recording.log.events.push(event());
+        }
+    }
+
+    /// Remember `css` as the latest source requested for `sheet`.
+    pub fn sheet_requested(&self, sheet: SheetId, css: &str) {
+        if let Some(recording) = self.lock().as_mut() {
+            recording.pending_sheets.insert(sheet, css.to_owned());
+        }
+    }
+
+    /// Log the replacement of `sheet` with its latest requested source.
+    pub fn sheet_applied(&self, sheet: SheetId) {
+        if let Some(recording) = self.lock().as_mut()
+            && let Some(css) = recording.pending_sheets.remove(&sheet)
+        {
+            recording
+                .log
+                .events
+                .push(ReplayEvent::Stylesheet { sheet, css });
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Recording>> {
+        self.recording
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Wrap `resolver` so its answers are logged while `recorder` records.
+pub fn recording_resolver(resolver: ResourceResolver, recorder: Arc<Recorder>) -> ResourceResolver {
+    Arc::new(move |url| {
+        let contents = resolver(url);
+        recorder.record(|| ReplayEvent::Resource {
+            url: url.to_owned(),
+            contents: contents.clone(),
+        });
+        contents
+    })
+}
+
+/// Apply `log` to `page`, whose document has just been replaced.
+///
+/// # Errors
+///
+/// `ReplayError::Diverged` when a node is created under another id than
+/// recorded.
+pub fn replay_events(
+    page: &mut Page<'_>,
+    log: &ReplayLog,
+    renderer: Option<&Renderer>,
+) -> Result<(), ReplayError> {
+    let mut document = ReplayDocument::new(page);
+    for event in &log.events {
+        match event {
+            ReplayEvent::Media { print, viewport } => {
+                let styler = &document.page().styler;
+                styler.set_media_environment(MediaEnvironment {
+                    print: *print,
+                    viewport: *viewport,
+                    ..styler.media_environment()
+                });
+                if let (Some(renderer), Some((width, height))) = (renderer, viewport) {
+                    renderer.set_viewport(*width, *height);
+                }
+            }
+            ReplayEvent::UaStylesheet(css) => document.page().set_ua_stylesheet(css),
+            ReplayEvent::UserStylesheet(css) => document.page().set_user_stylesheet(css),
+            ReplayEvent::Resource { .. } => {}
+            ReplayEvent::NewDocument => {
+                drop(document);
+                page.replace_document(renderer);
+                document = ReplayDocument::new(page);
+            }
+            ReplayEvent::CreateNode { node, data } => {
+                let replaying = document.page();
+                let update = DomUpdate::CreateNode(node_data(&replaying.tree, data));
+                let actual = document.apply(update);
+                if actual != *node {
+                    return Err(ReplayError::Diverged {
+                        expected: *node,
+                        actual,
+                    });
+                }
+            }
+            ReplayEvent::AppendChild { parent, child } => {
+                document.apply(DomUpdate::AppendChild {
+                    parent: *parent,
+                    child: *child,
+                });
+            }
+            ReplayEvent::SetAttribute { node, name, value } => {
+                let name = document.page().tree.interner.get_or_intern(name);
+                document.apply(DomUpdate::SetAttribute {
+                    node: *node,
+                    name,
+                    value: value.as_deref().map(Box::from),
+                });
+            }
+            ReplayEvent::ElementState {
+                node,
+                state,
+                enabled,
+            } => {
+                document.page().set_element_state(*node, *state, *enabled);
+            }
+            ReplayEvent::EndChunk => document.css.apply(),
+            ReplayEvent::EndDocument => {
+                document.css.finish();
+                document = ReplayDocument::new(page);
+            }
+            ReplayEvent::Stylesheet { sheet, css } => {
+                let replaying = document.page();
+                let rules = replaying.parse_rules(css, Origin::Author);
+                replaying.styler.replace_stylesheet(*sheet, rules);
+                replaying.recorder.record(|| event.clone());
+            }
+            ReplayEvent::RemoveStylesheet(sheet) => document.page().remove_stylesheet(*sheet),
+            ReplayEvent::StylesheetRule(css) => {
+                let replaying = document.page();
+                for rule in replaying.parse_rules(css, Origin::Author) {
+                    replaying.styler.add_rule(rule);
+                }
+                replaying.recorder.record(|| event.clone());
+            }
+            ReplayEvent::LoadedStylesheet => {
+                let replaying = document.page();
+                replaying.styler.flush();
+                replaying.recorder.record(|| event.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replays DOM updates through the same path the HTML parser's go.
+struct ReplayDocument<'page, 'br> {
+    page: &'page Page<'br>,
+    css: DocumentCss<'br>,
+    dom: Box<dyn Fn(DomUpdate) -> NodeId + Send>,
+}
+
+impl<'page, 'br> ReplayDocument<'page, 'br> {
+    fn new(page: &'page Page<'br>) -> Self {
+        let css = DocumentCss::new(page);
+        let dom = dom_callback(page, Arc::clone(&css.chunks));
+        Self { page, css, dom }
+    }
+
+    fn page(&self) -> &'page Page<'br> {
+        self.page
+    }
+
+    fn apply(&self, update: DomUpdate) -> NodeId {
+        (self.dom)(update)
+    }
+}
+
+/// Node data for a recorded node, interning its names in `tree`.
+fn node_data(tree: &DomTree, node: &ReplayNode) -> NodeData {
+    match node {
+        ReplayNode::Document => NodeData::Document,
+        ReplayNode::Element {
+            namespace,
+            tag,
+            attributes,
+        } => NodeData::Element {
+            tag: tree.interner.get_or_intern(tag),
+            namespace: *namespace,
+            attributes: attributes
+                .iter()
+                .map(|(name, value)| (tree.interner.get_or_intern(name), Box::from(value.as_str())))
+                .collect(),
+        },
+        ReplayNode::Text(text) => NodeData::Text(Box::from(text.as_str())),
+        ReplayNode::Comment(text) => NodeData::Comment(Box::from(text.as_str())),
+    }
+}
+
+fn namespace_name(namespace: Namespace) -> &'static str {
+    match namespace {
+        Namespace::Html => "html",
+        Namespace::Svg => "svg",
+        Namespace::MathMl => "mathml",
+        Namespace::Other => "other",
+    }
+}
+
+fn parse_namespace(text: &str) -> Option<Namespace> {
+    Some(match text {
+        "html" => Namespace::Html,
+        "svg" => Namespace::Svg,
+        "mathml" => Namespace::MathMl,
+        "other" => Namespace::Other,
+        _ => return None,
+    })
+}
+
+fn parse_node(text: &str) -> Option<NodeId> {
+    text.parse().ok().map(NodeId)
+}
+
+fn optional(text: Option<&str>) -> String {
+    text.map_or_else(|| "-".to_owned(), |text| format!("={}", escape(text)))
+}
+
+/// Read an optional field.
+///
+/// # Errors
+///
+/// `Err` if the field is neither `-` nor `=`-prefixed escaped text.
+fn parse_optional(text: &str) -> Result<Option<String>, ()> {
+    if text == "-" {
+        return Ok(None);
+    }
+    let escaped = text.strip_prefix('=').ok_or(())?;
+    unescape(escaped).map(Some).ok_or(())
+}
+
+/// Escape backslashes, line breaks and spaces so `text` is one field.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace(' ', "\\s")
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(character) = chars.next() {
+        if character != '\\' {
+            out.push(character);
+            continue;
+        }
+        out.push(match chars.next()? {
+            '\\' => '\\',
+            'n' => '\n',
+            'r' => '\r',
+            's' => ' ',
+            _ => return None,
+        });
+    }
+    Some(out)
+}
@@ -1,18 +1,20 @@
 //! Default User-Agent stylesheet per the HTML specification.
 //!
-//! These rules are loaded before any author CSS so they have the lowest
-//! source-order priority in the cascade.
+//! The sheet is parsed with `Origin::UserAgent`, so every author or user
+//! declaration beats it in the cascade regardless of specificity or
+//! source order. It follows the rendering section of the HTML standard,
+//! limited to properties the engine implements.
 
-/// UA stylesheet CSS text. Uses type selectors (specificity 0,0,1) so any
-/// author rule with equal or higher specificity will override these defaults.
+/// UA stylesheet CSS text.
 pub const UA_CSS: &str = "\
 html, body, div, p, h1, h2, h3, h4, h5, h6, \
-ul, ol, li, dl, dt, dd, blockquote, pre, form, fieldset, legend, \
-section, article, aside, header, footer, main, nav, address, \
-figure, figcaption, details, summary, dialog, hr, button, textarea { \
+ul, ol, menu, dl, dt, dd, blockquote, pre, form, fieldset, legend, \
+section, article, aside, header, footer, main, nav, address, hgroup, search, \
+figure, figcaption, details, summary, dialog, hr, center, button, textarea { \
     display: block; \
 } \
-head, meta, title, link, style, script, base, template, noscript { \
+head, meta, title, link, style, script, base, template, noscript, \
+area, datalist, param, rp, [hidden] { \
     display: none; \
 } \
 body { \
@@ -24,16 +26,41 @@ h3 { font-weight: 700; font-size: 1.17em; margin-top: 1em; margin-bottom: 1em; }
 h4 { font-weight: 700; font-size: 1em; margin-top: 1.33em; margin-bottom: 1.33em; } \
 h5 { font-weight: 700; font-size: 0.83em; margin-top: 1.67em; margin-bottom: 1.67em; } \
 h6 { font-weight: 700; font-size: 0.67em; margin-top: 2.33em; margin-bottom: 2.33em; } \
-p { margin-top: 1em; margin-bottom: 1em; } \
-table { display: table; } \
+p, dl, pre { margin-top: 1em; margin-bottom: 1em; } \
+blockquote, figure { margin: 1em 40px; } \
+dd { margin-left: 40px; } \
+ul, ol, menu { margin-top: 1em; margin-bottom: 1em; padding-left: 40px; } \
+ul ul, ul ol, ul menu, ol ul, ol ol, ol menu, menu ul, menu ol, menu menu { \
+    margin-top: 0; \
+    margin-bottom: 0; \
+} \
+li { display: list-item; } \
+ol { list-style-type: decimal; } \
+ul, menu { list-style-type: disc; } \
+:is(ul, ol, menu) :is(ul, menu) { list-style-type: circle; } \
+:is(ul, ol, menu) :is(ul, ol, menu) :is(ul, menu) { list-style-type: square; } \
+hr { margin: 0.5em auto; border-style: inset; border-width: 1px; } \
+fieldset { \
+    margin-left: 2px; \
+    margin-right: 2px; \
+    padding: 0.35em 0.75em 0.625em; \
+    border: 2px groove rgb(192, 192, 192); \
+} \
+legend { padding-left: 2px; padding-right: 2px; } \
+pre { white-space: pre; } \
+pre, code, kbd, samp, tt { font-family: monospace; } \
+b, strong, th { font-weight: 700; } \
+i, em, cite, var, dfn, address { font-style: italic; } \
+table { display: table; border-spacing: 2px; } \
 thead { display: table-header-group; } \
 tbody { display: table-row-group; } \
 tfoot { display: table-footer-group; } \
 tr { display: table-row; } \
-th, td { display: table-cell; } \
+th, td { display: table-cell; padding: 1px; } \
 span, a, em, strong, b, i, u, s, small, big, sub, sup, \
-abbr, cite, code, kbd, samp, var, q, mark, label { \
+abbr, cite, code, kbd, samp, var, q, mark, label, dfn, tt { \
     display: inline; \
 } \
 img, br, input, select { display: inline; } \
+a[href], area[href] { cursor: pointer; } \
 ";
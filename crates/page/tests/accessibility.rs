@@ -0,0 +1,102 @@
+//! Accessibility tree tests - checks roles, accessible names, inherited
+//! hidden state and layout bounds of the exported tree.
+
+mod common;
+
+use common::{element_by_id, open};
+use rewrite_core::NodeId;
+use rewrite_page::{AccessibilityNode, AccessibilityRole as Role, Browser};
+
+const DOCUMENT: &str = "<html><head><title>Skipped</title><style>
+    body { margin: 0 }
+    #gone { display: none }
+    #invisible { visibility: hidden }
+</style></head><body>
+    <h2 id=\"heading\">Section   <em>title</em></h2>
+    <a id=\"link\" href=\"#\" aria-label=\"Home page\">Home</a>
+    <img id=\"image\" alt=\"A cat\">
+    <p id=\"paragraph\">Words</p>
+    <input id=\"submit\" type=\"submit\">
+    <input id=\"checkbox\" type=\"checkbox\">
+    <input id=\"text\">
+    <div id=\"muted\" aria-hidden=\"true\"><button id=\"inner\">Inner</button></div>
+    <div id=\"gone\">Gone</div>
+    <div id=\"invisible\">Invisible</div>
+</body></html>";
+
+/// The entry for `node` in the subtree rooted at `entry`.
+fn find(entry: &AccessibilityNode, node: NodeId) -> Option<&AccessibilityNode> {
+    if entry.node == node {
+        return Some(entry);
+    }
+    entry.children.iter().find_map(|child| find(child, node))
+}
+
+#[test]
+fn roles_names_and_hidden_state() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    let tree = page.accessibility_tree(&renderer);
+    assert_eq!(tree.role, Role::Document);
+    let entry = |id: &str| {
+        let Some(entry) = find(&tree, element_by_id(&page, id)) else {
+            panic!("#{id} has no accessibility node");
+        };
+        (entry.role, entry.name.as_deref(), entry.hidden)
+    };
+
+    assert_eq!(
+        entry("heading"),
+        (Role::Heading(2), Some("Section title"), false)
+    );
+    assert_eq!(
+        entry("link"),
+        (Role::Link, Some("Home page"), false),
+        "aria-label wins"
+    );
+    assert_eq!(entry("image"), (Role::Image, Some("A cat"), false));
+    assert_eq!(entry("paragraph"), (Role::Paragraph, None, false));
+    assert_eq!(entry("submit"), (Role::Button, None, false));
+    assert_eq!(entry("checkbox"), (Role::Checkbox, None, false));
+    assert_eq!(entry("text"), (Role::TextField, None, false));
+    assert_eq!(entry("muted"), (Role::Generic, None, true));
+    assert_eq!(
+        entry("inner"),
+        (Role::Button, Some("Inner"), true),
+        "inherited"
+    );
+    assert_eq!(entry("gone"), (Role::Generic, None, true));
+    assert_eq!(entry("invisible"), (Role::Generic, None, true));
+
+    let Some(paragraph) = find(&tree, element_by_id(&page, "paragraph")) else {
+        panic!("#paragraph has no accessibility node");
+    };
+    let [text] = paragraph.children.as_slice() else {
+        panic!("#paragraph has {} children", paragraph.children.len());
+    };
+    assert_eq!(
+        (text.role, text.name.as_deref()),
+        (Role::StaticText, Some("Words"))
+    );
+}
+
+#[test]
+fn bounds_come_from_layout_and_skipped_elements_are_left_out() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    let tree = page.accessibility_tree(&renderer);
+
+    let heading = element_by_id(&page, "heading");
+    let Some(entry) = find(&tree, heading) else {
+        panic!("#heading has no accessibility node");
+    };
+    let bounds = entry.bounds.border_box();
+    assert!(bounds.is_some());
+    assert_eq!(bounds, renderer.computed_box(heading).border_box());
+
+    let mut stack = vec![&tree];
+    while let Some(entry) = stack.pop() {
+        assert_ne!(entry.name.as_deref(), Some("Skipped"), "<title> is skipped");
+        stack.extend(&entry.children);
+    }
+}
@@ -0,0 +1,92 @@
+//! Border side tests - checks that each side keeps its own width, style and
+//! color through shorthands and per-side overrides, and that uniform and
+//! invisible borders are recognised.
+
+mod common;
+
+use common::{element_by_id, open, px};
+use lightningcss::properties::border::LineStyle;
+use lightningcss::values::color::RGBA;
+use rewrite_css::ComputedColor;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{BorderSide, BorderSides, Renderer};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { color: rgb(0, 0, 255) }
+    #uniform { border: 2px solid red }
+    #mixed {
+        border: 1px solid red;
+        border-left: 4px dashed rgb(0, 128, 0);
+        border-top-style: dotted;
+        border-bottom-color: currentColor;
+    }
+    #none { border-width: 6px }
+    #transparent { border: 3px solid transparent }
+</style></head><body>
+    <div id=\"uniform\">Uniform</div>
+    <div id=\"mixed\">Mixed</div>
+    <div id=\"none\">None</div>
+    <div id=\"transparent\">Transparent</div>
+</body></html>";
+
+/// The border sides of `#id`.
+fn sides(page: &Page<'_>, renderer: &Renderer, id: &str) -> BorderSides {
+    renderer.border_sides(element_by_id(page, id))
+}
+
+/// A side with an opaque sRGB color.
+fn side(width: f32, style: LineStyle, [red, green, blue]: [u8; 3]) -> BorderSide {
+    BorderSide {
+        width: px(width),
+        style,
+        color: ComputedColor::Srgb(RGBA::new(red, green, blue, 1.0)),
+    }
+}
+
+#[test]
+fn shorthands_set_every_side() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+
+    let uniform = sides(&page, &renderer, "uniform");
+    assert_eq!(uniform.top, side(2.0, LineStyle::Solid, [255, 0, 0]));
+    assert!(uniform.is_uniform());
+    assert!(uniform.left.is_visible());
+}
+
+#[test]
+fn per_side_overrides_only_touch_their_side() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+
+    let mixed = sides(&page, &renderer, "mixed");
+    assert_eq!(
+        mixed,
+        BorderSides {
+            top: side(1.0, LineStyle::Dotted, [255, 0, 0]),
+            right: side(1.0, LineStyle::Solid, [255, 0, 0]),
+            bottom: side(1.0, LineStyle::Solid, [0, 0, 255]),
+            left: side(4.0, LineStyle::Dashed, [0, 128, 0]),
+        }
+    );
+    assert!(!mixed.is_uniform());
+}
+
+#[test]
+fn unstyled_and_transparent_sides_are_invisible() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+
+    let none = sides(&page, &renderer, "none");
+    assert_eq!(
+        (none.right.width, none.right.style),
+        (px(0.0), LineStyle::None),
+        "no border-style means no border"
+    );
+    assert!(!none.right.is_visible());
+    assert!(none.is_uniform());
+
+    let transparent = sides(&page, &renderer, "transparent");
+    assert_eq!(transparent.bottom.width, px(3.0));
+    assert!(!transparent.bottom.is_visible());
+}
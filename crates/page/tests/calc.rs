@@ -0,0 +1,75 @@
+//! Math function tests - checks that `calc()`, `clamp()` and friends mixing
+//! lengths and percentages size boxes, with percentages of the containing
+//! block's used width, and follow it when it resizes.
+
+mod common;
+
+use common::{element_by_id, open};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{BoxArea, Renderer};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #outer { padding: 0 100px }
+    #inner { width: calc(100% - 32px); margin-left: calc(5% + 4px); height: 10px }
+    #clamped {
+        font-size: 10px;
+        width: clamp(100px, 50%, 300px);
+        height: calc(2em + 10px);
+        padding-top: calc(1em - 2px);
+        border-top: calc(1px + 1px) solid black;
+    }
+    #plain { width: 300px; height: 30px; padding-top: 8px; border-top: 2px solid black }
+</style></head><body>
+    <div id=\"outer\"><div id=\"inner\"></div></div>
+    <div id=\"clamped\"></div>
+    <div id=\"plain\"></div>
+</body></html>";
+
+/// `(x, y, width, height)` of the `area` of `#id`, in pixels.
+fn rect(page: &Page<'_>, renderer: &Renderer, id: &str, area: BoxArea) -> (f32, f32, f32, f32) {
+    let rect = renderer
+        .used_values(element_by_id(page, id))
+        .unwrap_or_else(|| panic!("#{id} is laid out"))
+        .rect(area);
+    (
+        rect.x.to_f32(),
+        rect.y.to_f32(),
+        rect.width.to_f32(),
+        rect.height.to_f32(),
+    )
+}
+
+#[test]
+fn percentages_resolve_against_the_used_width() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    // #outer's content box is 600px wide.
+    assert_eq!(
+        rect(&page, &renderer, "inner", BoxArea::Border),
+        (134.0, 0.0, 568.0, 10.0)
+    );
+
+    renderer.set_viewport(400, 600);
+    assert_eq!(
+        rect(&page, &renderer, "inner", BoxArea::Border),
+        (114.0, 0.0, 168.0, 10.0)
+    );
+}
+
+#[test]
+fn math_functions_size_boxes() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    for area in [BoxArea::Border, BoxArea::Content] {
+        let (_, _, width, height) = rect(&page, &renderer, "clamped", area);
+        let (_, _, plain_width, plain_height) = rect(&page, &renderer, "plain", area);
+        assert_eq!((width, height), (plain_width, plain_height), "{area:?}");
+    }
+    let (_, top, _, _) = rect(&page, &renderer, "clamped", BoxArea::Content);
+    let (_, border_top, _, _) = rect(&page, &renderer, "clamped", BoxArea::Border);
+    assert_eq!(top - border_top, 10.0, "2px border and 8px padding");
+
+    renderer.set_viewport(400, 600);
+    assert_eq!(rect(&page, &renderer, "clamped", BoxArea::Content).2, 200.0);
+}
@@ -103,7 +103,10 @@ fn find_body(tree: &DomTree) -> Result<NodeId, String> {
 fn serialize_element(node_id: NodeId, ctx: &SerCtx<'_>) -> JsonValue {
     let node_data = &ctx.tree.nodes[node_id.0 as usize];
 
-    let NodeData::Element { tag, attributes } = node_data else {
+    let NodeData::Element {
+        tag, attributes, ..
+    } = node_data
+    else {
         return JsonValue::Null;
     };
 
@@ -0,0 +1,83 @@
+//! Helpers shared by the page tests: finding elements by id, loading a
+//! document at the default 800x600 viewport, reading box geometry and
+//! converting pixel values.
+
+#![allow(dead_code, reason = "each test crate uses a different subset")]
+
+use rewrite_core::{NodeId, Subpixel};
+use rewrite_html::NodeData;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+use std::sync::Arc;
+
+/// The element whose `id` attribute is `id`, if any.
+pub fn find_element_by_id(page: &Page<'_>, id: &str) -> Option<NodeId> {
+    let name = page.tree.interner.get("id")?;
+    (0..page.styler.styled_node_count())
+        .map(|index| NodeId(index as u32))
+        .find(|&node| match page.tree.get_node(node) {
+            Some(NodeData::Element { attributes, .. }) => {
+                attributes.get(&name).as_deref() == Some(id)
+            }
+            _ => false,
+        })
+}
+
+/// The element whose `id` attribute is `id`.
+///
+/// # Panics
+///
+/// Panics if there is none.
+pub fn element_by_id(page: &Page<'_>, id: &str) -> NodeId {
+    find_element_by_id(page, id).unwrap_or_else(|| panic!("no element #{id}"))
+}
+
+/// Load `html` into `page` at an 800x600 viewport.
+pub fn load(page: &Page<'_>, renderer: &Renderer, html: &str) {
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(html);
+    document.finish();
+}
+
+/// A new page of `browser` with `html` loaded at an 800x600 viewport.
+pub fn open<'br>(browser: &'br Browser, html: &str) -> (Page<'br>, Arc<Renderer>) {
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, html);
+    (page, renderer)
+}
+
+/// `value` pixels.
+pub fn px(value: f32) -> Subpixel {
+    Subpixel::from_f32(value)
+}
+
+/// `value` (0 if unset) in pixels.
+pub fn fractional_px(value: Option<Subpixel>) -> f32 {
+    value.unwrap_or_default().to_f32()
+}
+
+/// `value` (0 if unset) rounded to whole pixels.
+pub fn whole_px(value: Option<Subpixel>) -> i32 {
+    fractional_px(value).round() as i32
+}
+
+/// `(x, y, width, height)` of `node`, in whole pixels.
+pub fn node_geometry(renderer: &Renderer, node: NodeId) -> (i32, i32, i32, i32) {
+    let computed = renderer.computed_box(node);
+    (
+        whole_px(computed.x),
+        whole_px(computed.y),
+        whole_px(computed.width),
+        whole_px(computed.height),
+    )
+}
+
+/// `(x, y, width, height)` of `#id`, in whole pixels.
+///
+/// # Panics
+///
+/// Panics if there is no element `#id`.
+pub fn geometry(page: &Page<'_>, renderer: &Renderer, id: &str) -> (i32, i32, i32, i32) {
+    node_geometry(renderer, element_by_id(page, id))
+}
@@ -0,0 +1,113 @@
+//! Container metrics tests - checks that, with
+//! `LayoutFlags::record_container_metrics`, each box records the rect,
+//! line length and formatting context of the box it was laid out in, and
+//! that the recording follows incremental changes to the container.
+
+mod common;
+
+use common::{element_by_id, load, px};
+use rewrite_core::{LayoutFlags, NodeId};
+use rewrite_layout::{FormattingContext, Rect};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{BoxArea, Renderer};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #outer { width: 400px; margin-left: 20px }
+    #flex { display: flex; width: 200px }
+    #cb { position: relative; margin-left: 50px; width: 100px; padding: 7px }
+    #abs { position: absolute }
+</style></head><body>
+<div id=\"outer\"><p id=\"child\">text</p></div>
+<div id=\"flex\"><span id=\"item\">item</span></div>
+<div id=\"cb\"><p><span id=\"abs\">abs</span></p></div>
+</body></html>";
+
+fn load_recording(page: &Page<'_>, renderer: &Renderer, record: bool) {
+    renderer.set_layout_flags(LayoutFlags {
+        record_container_metrics: record,
+        ..LayoutFlags::default()
+    });
+    load(page, renderer, DOCUMENT);
+}
+
+#[test]
+fn boxes_record_the_box_they_were_laid_out_in() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load_recording(&page, &renderer, true);
+
+    let outer = element_by_id(&page, "outer");
+    let child = renderer
+        .container_metrics(element_by_id(&page, "child"))
+        .unwrap();
+    assert_eq!(child.container, Some(outer));
+    // The paragraph's top margin collapses through `#outer` and `body`.
+    let rect = child.rect.unwrap();
+    assert_eq!(
+        (rect.x, rect.y, rect.width),
+        (px(20.0), px(16.0), px(400.0))
+    );
+    assert_eq!(child.available_inline_size, Some(px(400.0)));
+    assert_eq!(
+        child.formatting_context,
+        Some(FormattingContext::BlockContainer)
+    );
+    assert_eq!((child.viewport_width, child.viewport_height), (800, 600));
+
+    let item = renderer
+        .container_metrics(element_by_id(&page, "item"))
+        .unwrap();
+    assert_eq!(item.container, Some(element_by_id(&page, "flex")));
+    assert_eq!(item.formatting_context, Some(FormattingContext::Flex));
+
+    // Out-of-flow boxes are laid out in their containing block's padding
+    // box, not their parent's content box.
+    let abs = renderer
+        .container_metrics(element_by_id(&page, "abs"))
+        .unwrap();
+    let cb = element_by_id(&page, "cb");
+    assert_eq!(abs.container, Some(cb));
+    assert_eq!(abs.rect, renderer.box_rect(cb, BoxArea::Padding));
+    assert_eq!(abs.rect.map(|rect| rect.x), Some(px(50.0)));
+
+    let html = page.tree.children(NodeId::ROOT).next().unwrap();
+    let root = renderer.container_metrics(html).unwrap();
+    assert_eq!(root.container, None, "the root element is in the viewport");
+    assert_eq!(root.rect, Some(Rect::from_size(px(800.0), px(600.0))));
+}
+
+#[test]
+fn recording_follows_container_changes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load_recording(&page, &renderer, true);
+    let outer = element_by_id(&page, "outer");
+    let child = element_by_id(&page, "child");
+
+    page.set_attribute(outer, "style", Some("width: 300px"));
+    let metrics = renderer.container_metrics(child).unwrap();
+    assert_eq!(metrics.available_inline_size, Some(px(300.0)));
+    assert_eq!(metrics.rect.map(|rect| rect.width), Some(px(300.0)));
+
+    page.set_attribute(outer, "style", Some("display: none"));
+    assert_eq!(renderer.container_metrics(child), None);
+}
+
+#[test]
+fn nothing_is_recorded_without_the_flag() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load_recording(&page, &renderer, false);
+    let child = element_by_id(&page, "child");
+    assert_eq!(renderer.container_metrics(child), None);
+
+    renderer.set_layout_flags(LayoutFlags {
+        record_container_metrics: true,
+        ..LayoutFlags::default()
+    });
+    assert!(
+        renderer.container_metrics(child).is_some(),
+        "turning the flag on lays everything out again"
+    );
+}
@@ -0,0 +1,72 @@
+//! `@import` tests - checks that imported stylesheets are fetched through
+//! the page's resource resolver and cascade before the importing sheet.
+
+mod common;
+
+use common::{element_by_id, whole_px};
+use rewrite_css::SheetId;
+use rewrite_page::{Browser, Page, ResourceResolver};
+use rewrite_renderer::Renderer;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DOCUMENT: &str = "<html><head>
+    <link rel=\"stylesheet\" href=\"theme.css\">
+    <style>@import 'local.css'; #local { height: 7px }</style>
+</head><body>
+    <div id=\"themed\"></div>
+    <div id=\"local\"></div>
+</body></html>";
+
+fn resolver() -> ResourceResolver {
+    Arc::new(|url| {
+        let css = match url {
+            "theme.css" => "@import 'base.css'; @import 'print.css' print; #themed { width: 50px }",
+            "base.css" => "body { margin: 0 } #themed { width: 10px; height: 5px }",
+            "print.css" => "#themed { height: 99px }",
+            "local.css" => "@import 'local.css'; #local { width: 30px; height: 3px }",
+            _ => return None,
+        };
+        Some(css.to_owned())
+    })
+}
+
+/// `(width, height)` of `#id`, in whole pixels.
+fn size(page: &Page<'_>, renderer: &Renderer, id: &str) -> (i32, i32) {
+    let node = element_by_id(page, id);
+    let computed = renderer.computed_box(node);
+    (whole_px(computed.width), whole_px(computed.height))
+}
+
+#[test]
+fn imported_rules_cascade_before_the_importing_sheet() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    let page = page.with_resource_resolver(resolver());
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    // The importing sheet's width wins; the import's height applies, and
+    // the print-only import doesn't.
+    assert_eq!(size(&page, &renderer, "themed"), (50, 5));
+    // A sheet importing itself is read once.
+    assert_eq!(size(&page, &renderer, "local"), (30, 7));
+}
+
+#[test]
+fn worker_parsed_sheets_follow_imports() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    let page = page.with_resource_resolver(resolver());
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push("<html><body><div id=\"themed\"></div></body></html>");
+    document.finish();
+
+    page.parse_stylesheet(SheetId(1), "@import 'theme.css';".to_owned());
+    let applied = page.wait_for_parsed_stylesheets(Duration::from_secs(10));
+    assert_eq!(applied, 1, "stylesheet never parsed");
+    assert_eq!(size(&page, &renderer, "themed"), (50, 5));
+}
@@ -0,0 +1,93 @@
+//! Cursor tests - checks that `cursor` is parsed with its image fallback
+//! list and inherited, and that the cursor at a point resolves `auto`
+//! against what's under the pointer.
+
+mod common;
+
+use common::element_by_id;
+use lightningcss::properties::ui::CursorKeyword;
+use rewrite_core::Subpixel;
+use rewrite_page::Browser;
+use rewrite_renderer::{Cursor, CursorImage, Renderer};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div { height: 50px }
+    #custom { cursor: url(hand.cur) 4 6, url(hand.png), pointer }
+    #busy { cursor: wait }
+</style></head><body>
+<div id=\"custom\"><div id=\"inner\"></div></div>
+<div id=\"busy\"></div>
+<div id=\"plain\">Text</div>
+<div><a id=\"link\" href=\"/\">Link</a></div>
+</body></html>";
+
+fn keyword_at(renderer: &Renderer, x: i32, y: i32) -> CursorKeyword {
+    renderer
+        .cursor_at(Subpixel::from_px(x), Subpixel::from_px(y))
+        .keyword
+}
+
+#[test]
+fn cursor_is_parsed_and_inherited() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let expected = Cursor {
+        images: vec![
+            CursorImage {
+                url: "hand.cur".to_owned(),
+                hotspot: Some((4.0, 6.0)),
+            },
+            CursorImage {
+                url: "hand.png".to_owned(),
+                hotspot: None,
+            },
+        ],
+        keyword: CursorKeyword::Pointer,
+    };
+    assert_eq!(renderer.cursor(element_by_id(&page, "custom")), expected);
+    assert_eq!(
+        renderer.cursor(element_by_id(&page, "inner")),
+        expected,
+        "cursor inherits"
+    );
+    assert_eq!(
+        renderer.cursor(element_by_id(&page, "plain")),
+        Cursor::default(),
+        "the initial value is auto"
+    );
+}
+
+#[test]
+fn cursor_at_resolves_auto_against_the_hit_box() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    assert_eq!(keyword_at(&renderer, 10, 10), CursorKeyword::Pointer);
+    assert_eq!(keyword_at(&renderer, 10, 60), CursorKeyword::Wait);
+    assert_eq!(
+        keyword_at(&renderer, 2, 102),
+        CursorKeyword::Text,
+        "auto over text is the text cursor"
+    );
+    assert_eq!(
+        keyword_at(&renderer, 700, 102),
+        CursorKeyword::Default,
+        "auto elsewhere is the arrow"
+    );
+    assert_eq!(
+        keyword_at(&renderer, 2, 152),
+        CursorKeyword::Pointer,
+        "links show a pointer"
+    );
+    assert_eq!(keyword_at(&renderer, 900, 10), CursorKeyword::Default);
+}
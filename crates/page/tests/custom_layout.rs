@@ -0,0 +1,107 @@
+//! Custom layout tests - checks that `display: layout(<name>)` boxes place
+//! their children with the layout registered under `<name>`, and are laid
+//! out as blocks while nothing is registered.
+
+mod common;
+
+use common::{find_element_by_id, geometry};
+use rewrite_core::{
+    CustomLayout, LayoutChild, LayoutConstraints, LayoutFragment, LayoutFragments, Subpixel,
+};
+use rewrite_page::Browser;
+use std::sync::Arc;
+
+const DOCUMENT: &str = "<html><body style=\"margin: 0\">
+    <div id=\"row\" style=\"display: layout(row); padding: 5px\">
+        <div id=\"first\" style=\"width: 40px; height: 20px\"></div>
+        <div id=\"hidden\" style=\"display: none\"></div>
+        <div id=\"second\" style=\"width: 30px; height: 50px\"></div>
+        <div id=\"third\" style=\"width: 60px; height: 10px\"></div>
+    </div>
+    <div id=\"after\" style=\"height: 10px\"></div>
+</body></html>";
+
+/// Places children left to right, `GAP` apart, wrapping to a new row
+/// when one doesn't fit.
+struct Rows;
+
+const GAP: i32 = 10;
+
+impl CustomLayout for Rows {
+    fn layout(&self, constraints: &LayoutConstraints, children: &[LayoutChild]) -> LayoutFragments {
+        let gap = Subpixel::from_px(GAP);
+        let mut fragments = Vec::new();
+        let (mut inline, mut block, mut row_height) =
+            (Subpixel::ZERO, Subpixel::ZERO, Subpixel::ZERO);
+        for child in children {
+            if inline > Subpixel::ZERO
+                && inline + child.inline_size > constraints.available_inline_size
+            {
+                inline = Subpixel::ZERO;
+                block = block + row_height + gap;
+                row_height = Subpixel::ZERO;
+            }
+            fragments.push(LayoutFragment {
+                node: child.node,
+                inline_offset: inline,
+                block_offset: block,
+            });
+            inline = inline + child.inline_size + gap;
+            row_height = row_height.max(child.block_size);
+        }
+        LayoutFragments {
+            block_size: block + row_height,
+            fragments,
+        }
+    }
+}
+
+#[test]
+fn registered_layout_places_the_children() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    renderer.register_custom_layout("row", Arc::new(Rows));
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    assert_eq!(geometry(&page, &renderer, "first"), (5, 5, 40, 20));
+    assert_eq!(geometry(&page, &renderer, "second"), (55, 5, 30, 50));
+    assert_eq!(geometry(&page, &renderer, "third"), (95, 5, 60, 10));
+    assert_eq!(
+        geometry(&page, &renderer, "row"),
+        (0, 0, 800, 60),
+        "tallest child plus padding"
+    );
+    assert_eq!(geometry(&page, &renderer, "after").1, 60);
+
+    // Narrowing the box wraps `#third` onto a second row.
+    if let Some(row) = find_element_by_id(&page, "row") {
+        page.set_attribute(
+            row,
+            "style",
+            Some("display: layout(row); padding: 5px; width: 120px"),
+        );
+    }
+    assert_eq!(geometry(&page, &renderer, "third"), (5, 65, 60, 10));
+    assert_eq!(geometry(&page, &renderer, "row").3, 80);
+}
+
+#[test]
+fn unregistered_layout_falls_back_to_block() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    assert_eq!(geometry(&page, &renderer, "second"), (5, 25, 30, 50));
+    assert_eq!(geometry(&page, &renderer, "row").3, 90);
+
+    // Registering later lays the box out again.
+    renderer.register_custom_layout("row", Arc::new(Rows));
+    assert_eq!(geometry(&page, &renderer, "second"), (55, 5, 30, 50));
+    assert_eq!(geometry(&page, &renderer, "after").1, 60);
+}
@@ -0,0 +1,102 @@
+//! Display list tests - checks that boxes and text are emitted as typed
+//! items in paint order, with `z-index` and stacking contexts applied.
+
+mod common;
+
+use common::element_by_id;
+use lightningcss::values::color::RGBA;
+use rewrite_core::{NodeId, Subpixel};
+use rewrite_css::ComputedColor;
+use rewrite_layout::Rect;
+use rewrite_page::Browser;
+use rewrite_renderer::{DisplayItem, DisplayList};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div { width: 100px; height: 50px }
+    #front { position: relative; z-index: 2; background-color: #00ff00 }
+    #back { position: relative; z-index: 1; background-color: #0000ff;
+            border: 2px solid #ff0000 }
+    #below { position: relative; z-index: -1; background-color: #ff0000 }
+    #plain { background-color: #808080; color: #ff0000; font-size: 20px }
+    #empty { height: 10px }
+</style></head><body>
+<div id=\"front\"></div><div id=\"back\"></div><div id=\"below\"></div>
+<div id=\"plain\">Hello</div><div id=\"empty\"></div>
+</body></html>";
+
+/// Index of the first item of `kind` generated for `node`.
+fn position(list: &DisplayList, node: NodeId, kind: fn(&DisplayItem) -> bool) -> usize {
+    list.items
+        .iter()
+        .position(|item| item.node() == node && kind(item))
+        .unwrap_or_else(|| panic!("no item for {node:?}"))
+}
+
+fn is_rect(item: &DisplayItem) -> bool {
+    matches!(item, DisplayItem::SolidRect { .. })
+}
+
+fn is_border(item: &DisplayItem) -> bool {
+    matches!(item, DisplayItem::Border { .. })
+}
+
+#[test]
+fn display_list_follows_paint_order() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let list = renderer.display_list();
+    let [front, back, below, plain, empty] =
+        ["front", "back", "below", "plain", "empty"].map(|id| element_by_id(&page, id));
+
+    // Negative z-index first, then in-flow boxes, then positive z-index.
+    let below_at = position(&list, below, is_rect);
+    let plain_at = position(&list, plain, is_rect);
+    let back_at = position(&list, back, is_rect);
+    let front_at = position(&list, front, is_rect);
+    assert!(below_at < plain_at && plain_at < back_at && back_at < front_at);
+
+    assert!(
+        position(&list, back, is_border) > back_at,
+        "the border is drawn over the background"
+    );
+    assert!(
+        !list.items.iter().any(|item| item.node() == empty),
+        "a box with nothing to draw emits no items"
+    );
+
+    let px = Subpixel::from_px;
+    assert_eq!(
+        list.items[front_at],
+        DisplayItem::SolidRect {
+            node: front,
+            rect: Rect::new(px(0), px(0), px(100), px(50)),
+            clip: None,
+            color: ComputedColor::Srgb(RGBA::new(0, 255, 0, 1.0)),
+            radii: Default::default(),
+        }
+    );
+
+    let Some(text) = page.tree.children(plain).next() else {
+        panic!("#plain has no text");
+    };
+    let text_at = position(&list, text, |item| matches!(item, DisplayItem::Text { .. }));
+    assert!(text_at > plain_at, "text is drawn over its box");
+    let DisplayItem::Text {
+        text: run,
+        color,
+        font_size,
+        ..
+    } = &list.items[text_at]
+    else {
+        unreachable!();
+    };
+    assert_eq!(run, "Hello");
+    assert_eq!(*color, ComputedColor::Srgb(RGBA::new(255, 0, 0, 1.0)));
+    assert!((font_size - 20.0).abs() < f32::EPSILON);
+}
@@ -0,0 +1,106 @@
+//! `display: none` tests - checks that nothing inside a `display: none`
+//! subtree is laid out or given a rect, that style changes inside it do no
+//! layout work, and that toggling `display` lays the subtree out again.
+
+mod common;
+
+use common::{element_by_id, load};
+use rewrite_core::NodeId;
+use rewrite_page::Browser;
+use rewrite_renderer::BoxArea;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    p { margin: 0; height: 20px }
+</style></head><body>
+<div id=\"hidden\" style=\"display: none\">
+  <p id=\"inner\">text <span id=\"deep\">deep</span></p>
+  <p id=\"other\">more</p>
+</div>
+<p id=\"after\">after</p>
+</body></html>";
+
+#[test]
+fn hidden_subtree_has_no_boxes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let laid_out: Vec<NodeId> = renderer
+        .laid_out_boxes(BoxArea::Border)
+        .into_iter()
+        .map(|(node, _)| node)
+        .collect();
+    let paint_order = renderer.paint_order();
+    for id in ["hidden", "inner", "deep", "other"] {
+        let node = element_by_id(&page, id);
+        let computed = renderer.computed_box(node);
+        assert_eq!(
+            [computed.width, computed.height, computed.x, computed.y],
+            [None; 4],
+            "#{id} is not laid out"
+        );
+        assert_eq!(renderer.box_rect(node, BoxArea::Border), None);
+        assert!(!laid_out.contains(&node), "#{id} has no fragment");
+        assert!(!paint_order.contains(&node), "#{id} is not painted");
+    }
+
+    let after = element_by_id(&page, "after");
+    let rect = renderer.box_rect(after, BoxArea::Border).unwrap();
+    assert_eq!(rect.y.to_f32(), 0.0, "the hidden div takes no space");
+    assert!(
+        renderer.resolve_stats().skipped_nodes > 0,
+        "skipped nodes are counted"
+    );
+}
+
+#[test]
+fn changes_inside_hidden_subtree_do_no_layout_work() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+    let deep = element_by_id(&page, "deep");
+    let inner = element_by_id(&page, "inner");
+
+    renderer.reset_resolve_stats();
+    page.set_attribute(
+        deep,
+        "style",
+        Some("display: block; width: 50px; padding: 3px"),
+    );
+    page.set_attribute(inner, "style", Some("font-size: 30px; height: 90px"));
+    let stats = renderer.resolve_stats();
+    assert_eq!(
+        stats.top_level, 0,
+        "nothing is resolved under display: none"
+    );
+    assert_eq!(stats.evaluations, 0);
+    assert_eq!(renderer.computed_box(deep).width, None);
+}
+
+#[test]
+fn toggling_display_lays_the_subtree_out_again() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+    let hidden = element_by_id(&page, "hidden");
+    let inner = element_by_id(&page, "inner");
+    let other = element_by_id(&page, "other");
+    let after = element_by_id(&page, "after");
+    let top = |node| {
+        renderer
+            .box_rect(node, BoxArea::Border)
+            .map(|rect| rect.y.to_f32())
+    };
+
+    page.set_attribute(hidden, "style", None);
+    assert_eq!(top(inner), Some(0.0));
+    assert_eq!(top(other), Some(20.0));
+    assert_eq!(top(after), Some(40.0), "the shown div pushes #after down");
+    assert!(renderer.computed_box(element_by_id(&page, "deep")).width > None);
+
+    page.set_attribute(hidden, "style", Some("display: none"));
+    assert_eq!(top(inner), None);
+    assert_eq!(top(other), None);
+    assert_eq!(top(after), Some(0.0), "hiding it again frees the space");
+}
@@ -0,0 +1,69 @@
+//! Dynamic pseudo-class tests - checks that `:hover`, `:active` and
+//! `:focus-within` follow element state set through the page, and that
+//! layout picks up the restyle.
+
+mod common;
+
+use common::{element_by_id, whole_px};
+use rewrite_html::ElementState;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div { height: 10px }
+    #menu:hover > #item { height: 30px }
+    #form:focus-within { height: 40px }
+    #item:active { width: 100px }
+</style></head><body>
+    <div id=\"menu\"><div id=\"item\"><span id=\"label\">Item</span></div></div>
+    <div id=\"form\"><div id=\"field\"></div></div>
+</body></html>";
+
+/// `(width, height)` of `#id`, in whole pixels.
+fn size(page: &Page<'_>, renderer: &Renderer, id: &str) -> (i32, i32) {
+    let computed = renderer.computed_box(element_by_id(page, id));
+    (whole_px(computed.width), whole_px(computed.height))
+}
+
+#[test]
+fn hover_and_active_follow_the_pointer() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+    assert_eq!(size(&page, &renderer, "item"), (800, 10));
+
+    // Hovering the label hovers every ancestor, `#menu` included.
+    let label = element_by_id(&page, "label");
+    page.set_element_state(label, ElementState::HOVER, true);
+    assert_eq!(size(&page, &renderer, "item"), (800, 30));
+
+    page.set_element_state(label, ElementState::ACTIVE, true);
+    assert_eq!(size(&page, &renderer, "item"), (100, 30));
+
+    page.set_element_state(
+        label,
+        ElementState::HOVER.union(ElementState::ACTIVE),
+        false,
+    );
+    assert_eq!(size(&page, &renderer, "item"), (800, 10));
+}
+
+#[test]
+fn focus_within_tracks_the_focused_descendant() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let field = element_by_id(&page, "field");
+    page.set_element_state(field, ElementState::FOCUS, true);
+    assert_eq!(size(&page, &renderer, "form"), (800, 40));
+    page.set_element_state(field, ElementState::FOCUS, false);
+    assert_eq!(size(&page, &renderer, "form"), (800, 10));
+}
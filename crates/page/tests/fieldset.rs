@@ -0,0 +1,143 @@
+//! Fieldset tests - checks that a fieldset's rendered legend sits over its
+//! top border and shrinks to fit, that the fieldset's content starts below
+//! the legend, and that the top border is painted through the legend but
+//! not behind it.
+
+mod common;
+
+use common::{element_by_id, load};
+use rewrite_core::Subpixel;
+use rewrite_layout::Rect;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{BoxArea, DisplayItem, Renderer};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    fieldset { margin: 0; padding: 10px; border: 4px solid black }
+    legend { font-size: 20px; margin: 0 }
+    #thick { border-top-width: 40px }
+    div { height: 30px }
+</style></head><body>
+<fieldset id=\"form\"><legend id=\"legend\">Shipping</legend><div id=\"first\">a</div></fieldset>
+<fieldset id=\"thick\"><legend id=\"small\" style=\"font-size: 8px\">x</legend>\
+<div id=\"thick-first\">b</div></fieldset>
+<fieldset id=\"late\"><div id=\"lead\">c</div><legend id=\"rendered\">One</legend>\
+<legend id=\"other\">Two</legend></fieldset>
+</body></html>";
+
+fn rect(page: &Page<'_>, renderer: &Renderer, id: &str, area: BoxArea) -> Rect {
+    renderer
+        .used_values(element_by_id(page, id))
+        .unwrap_or_else(|| panic!("#{id} is laid out"))
+        .rect(area)
+}
+
+#[test]
+fn legend_straddles_the_top_border() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let fieldset = rect(&page, &renderer, "form", BoxArea::Border);
+    let legend = rect(&page, &renderer, "legend", BoxArea::Margin);
+    let first = rect(&page, &renderer, "first", BoxArea::Border);
+    assert!(
+        legend.height.to_f32() > 4.0,
+        "the legend is taller than the border"
+    );
+    assert_eq!(legend.y, fieldset.y);
+
+    // Content starts below the legend instead of below the border.
+    let padding = first.y - legend.bottom();
+    assert_eq!(padding.to_f32(), 10.0);
+    assert_eq!(
+        fieldset.height,
+        legend.height + padding + first.height + padding + Subpixel::from_f32(4.0)
+    );
+
+    // A short legend is centered on a thick border.
+    let thick = rect(&page, &renderer, "thick", BoxArea::Border);
+    let small = rect(&page, &renderer, "small", BoxArea::Margin);
+    let thick_first = rect(&page, &renderer, "thick-first", BoxArea::Border);
+    assert!(small.height.to_f32() < 40.0);
+    let above = small.y - thick.y;
+    let below = thick.y + Subpixel::from_f32(40.0) - small.bottom();
+    assert!(
+        (above - below).to_f32().abs() <= 0.02,
+        "{above:?} above, {below:?} below"
+    );
+    assert_eq!((thick_first.y - thick.y).to_f32(), 50.0);
+}
+
+#[test]
+fn legend_shrinks_to_fit() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let content = rect(&page, &renderer, "form", BoxArea::Content);
+    let legend = rect(&page, &renderer, "legend", BoxArea::Border);
+    assert_eq!(legend.x, content.x);
+    assert!(legend.width.to_f32() > 0.0);
+    assert!(legend.width < content.width, "{legend:?} in {content:?}");
+}
+
+#[test]
+fn only_the_first_legend_is_rendered() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let fieldset = rect(&page, &renderer, "late", BoxArea::Border);
+    let rendered = rect(&page, &renderer, "rendered", BoxArea::Margin);
+    let lead = rect(&page, &renderer, "lead", BoxArea::Border);
+    let other = rect(&page, &renderer, "other", BoxArea::Border);
+    // A rendered legend later in the DOM still sits over the top border.
+    assert_eq!(rendered.y, fieldset.y);
+    assert_eq!((lead.y - rendered.bottom()).to_f32(), 10.0);
+    // A second legend is ordinary block content.
+    assert_eq!(other.y, lead.bottom());
+    assert_eq!(
+        other.width,
+        rect(&page, &renderer, "late", BoxArea::Content).width
+    );
+}
+
+#[test]
+fn top_border_is_open_behind_the_legend() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let form = element_by_id(&page, "form");
+    let fieldset = rect(&page, &renderer, "form", BoxArea::Border);
+    let legend = rect(&page, &renderer, "legend", BoxArea::Border);
+    let painted = renderer
+        .fieldset_border(form)
+        .expect("#form has a rendered legend");
+    let inset = (legend.height - Subpixel::from_f32(4.0)).to_f32() / 2.0;
+    assert!(((painted.rect.y - fieldset.y).to_f32() - inset).abs() <= 0.02);
+    assert_eq!(painted.rect.bottom(), fieldset.bottom());
+
+    let borders: Vec<(Rect, bool)> = renderer
+        .display_list()
+        .items
+        .into_iter()
+        .filter_map(|item| match item {
+            DisplayItem::Border {
+                node, rect, sides, ..
+            } if node == form => Some((rect, sides.top.is_visible())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(borders.len(), 3, "{borders:?}");
+    let top: Vec<Rect> = borders
+        .iter()
+        .filter(|(_, top)| *top)
+        .map(|(rect, _)| *rect)
+        .collect();
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].right(), legend.x);
+    assert_eq!(top[1].x, legend.right());
+    assert!(top.iter().all(|segment| segment.y == painted.rect.y));
+}
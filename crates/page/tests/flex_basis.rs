@@ -0,0 +1,81 @@
+//! Flex basis tests - checks that percentage flex bases resolve against
+//! the container's main-axis content box, and fall back to the content
+//! size when that box depends on the items.
+
+mod common;
+
+use common::{element_by_id, whole_px};
+use rewrite_core::NodeId;
+use rewrite_html::NodeData;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+/// The selectors are specific enough to apply while streaming, before the
+/// cascade is flushed.
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div[id] > div { flex-grow: 0; flex-shrink: 0; height: 20px }
+</style></head><body>
+    <div id=\"row\" style=\"display: flex; width: 400px\">
+        <div style=\"flex-basis: 25%\"></div><div style=\"flex-basis: 50%\"></div>
+    </div>
+    <div id=\"column\" style=\"display: flex; flex-direction: column; height: 300px\">
+        <div style=\"flex-basis: 50%\"></div><div style=\"flex-basis: 10%\"></div>
+    </div>
+    <div id=\"auto-height\" style=\"display: flex; flex-direction: column\">
+        <div style=\"flex-basis: 50%\"><div style=\"height: 15px\"></div></div>
+        <div style=\"flex-basis: 50%\"><div style=\"height: 15px\"></div></div>
+    </div>
+</body></html>";
+
+/// `(x, y, width, height)` of each element child of `#id`, relative to it.
+fn items(page: &Page<'_>, renderer: &Renderer, id: &str) -> Vec<(i32, i32, i32, i32)> {
+    let container = element_by_id(page, id);
+    let origin = renderer.computed_box(container);
+    let (origin_x, origin_y) = (origin.x.unwrap_or_default(), origin.y.unwrap_or_default());
+    let mut children: Vec<NodeId> = page
+        .tree
+        .children(container)
+        .filter(|&child| matches!(page.tree.get_node(child), Some(NodeData::Element { .. })))
+        .collect();
+    children.reverse();
+    children
+        .into_iter()
+        .map(|child| {
+            let computed = renderer.computed_box(child);
+            (
+                whole_px(computed.x.map(|x| x - origin_x)),
+                whole_px(computed.y.map(|y| y - origin_y)),
+                whole_px(computed.width),
+                whole_px(computed.height),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn percentage_basis_resolves_against_main_axis() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    // 25% and 50% of the 400px content box.
+    assert_eq!(
+        items(&page, &renderer, "row"),
+        [(0, 0, 100, 20), (100, 0, 200, 20)]
+    );
+    // Column containers resolve against the height, not the width.
+    assert_eq!(
+        items(&page, &renderer, "column"),
+        [(0, 0, 800, 150), (0, 150, 800, 30)]
+    );
+    // An auto height depends on the items: the basis is their content
+    // size, in place of the 20px height.
+    assert_eq!(
+        items(&page, &renderer, "auto-height"),
+        [(0, 0, 800, 15), (0, 15, 800, 15)]
+    );
+}
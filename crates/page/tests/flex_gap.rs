@@ -0,0 +1,86 @@
+//! Flex gap tests - checks that percentage gaps resolve against the
+//! container's content box and that gaps count against free space when
+//! items grow and shrink.
+
+mod common;
+
+use common::{element_by_id, whole_px};
+use rewrite_core::NodeId;
+use rewrite_html::NodeData;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+/// The selectors are specific enough to apply while streaming, before the
+/// cascade is flushed.
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div[id] > div { width: 100px; height: 20px }
+</style></head><body>
+    <div id=\"percent\" style=\"display: flex; width: 500px; column-gap: 10%\">
+        <div></div><div></div><div></div>
+    </div>
+    <div id=\"shrink\" style=\"display: flex; width: 300px; column-gap: 30px\">
+        <div></div><div></div><div></div>
+    </div>
+    <div id=\"grow\" style=\"display: flex; width: 400px; column-gap: 20px\">
+        <div style=\"flex-grow: 1\"></div><div style=\"flex-grow: 1\"></div>
+    </div>
+    <div id=\"auto-height\" style=\"display: flex; flex-direction: column; row-gap: 50%\">
+        <div></div><div></div>
+    </div>
+</body></html>";
+
+/// `(x, y, width)` of each element child of `#id`, relative to it.
+fn items(page: &Page<'_>, renderer: &Renderer, id: &str) -> Vec<(i32, i32, i32)> {
+    let container = element_by_id(page, id);
+    let origin = renderer.computed_box(container);
+    let (origin_x, origin_y) = (origin.x.unwrap_or_default(), origin.y.unwrap_or_default());
+    let mut children: Vec<NodeId> = page
+        .tree
+        .children(container)
+        .filter(|&child| matches!(page.tree.get_node(child), Some(NodeData::Element { .. })))
+        .collect();
+    children.reverse();
+    children
+        .into_iter()
+        .map(|child| {
+            let computed = renderer.computed_box(child);
+            (
+                whole_px(computed.x.map(|x| x - origin_x)),
+                whole_px(computed.y.map(|y| y - origin_y)),
+                whole_px(computed.width),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn gaps_resolve_and_take_free_space() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    // 10% of the 500px content box.
+    assert_eq!(
+        items(&page, &renderer, "percent"),
+        [(0, 0, 100), (150, 0, 100), (300, 0, 100)]
+    );
+    // 300px of items and 60px of gaps in 300px: each item shrinks by 20px.
+    assert_eq!(
+        items(&page, &renderer, "shrink"),
+        [(0, 0, 80), (110, 0, 80), (220, 0, 80)]
+    );
+    // 400px minus the 20px gap, split evenly.
+    assert_eq!(
+        items(&page, &renderer, "grow"),
+        [(0, 0, 190), (210, 0, 190)]
+    );
+    // The auto height depends on the items, so the percentage is zero.
+    assert_eq!(
+        items(&page, &renderer, "auto-height"),
+        [(0, 0, 100), (0, 20, 100)]
+    );
+}
@@ -0,0 +1,74 @@
+//! Focus order tests - checks that positive `tabindex` values come first,
+//! that unfocusable, disabled and hidden elements are skipped, and that
+//! tabbing wraps around in both directions.
+
+mod common;
+
+use common::{element_by_id, open};
+use rewrite_core::NodeId;
+use rewrite_page::{Browser, Page};
+
+const DOCUMENT: &str = "<html><head><style>
+    #gone { display: none }
+    #invisible { visibility: hidden }
+</style></head><body>
+    <a id=\"plain-link\">No href</a>
+    <a id=\"link\" href=\"#\">Link</a>
+    <button id=\"second\" tabindex=\"2\">Second</button>
+    <div id=\"div\">Div</div>
+    <div id=\"zero\" tabindex=\"0\">Zero</div>
+    <input id=\"disabled\" disabled>
+    <input id=\"hidden-input\" type=\"hidden\">
+    <button id=\"negative\" tabindex=\"-1\">Negative</button>
+    <button id=\"first-a\" tabindex=\"1\">First A</button>
+    <div id=\"gone\"><button id=\"inside-gone\">Gone</button></div>
+    <div id=\"invisible\"><button id=\"inside-invisible\" style=\"visibility: visible\">Visible</button></div>
+    <div id=\"editable\" contenteditable>Editable</div>
+    <textarea id=\"textarea\"></textarea>
+    <span id=\"first-b\" tabindex=\"1\">First B</span>
+</body></html>";
+
+fn ids(page: &Page<'_>, ids: &[&str]) -> Vec<NodeId> {
+    ids.iter().map(|id| element_by_id(page, id)).collect()
+}
+
+#[test]
+fn positive_tabindex_first_then_document_order() {
+    let browser = Browser::default();
+    let (page, _renderer) = open(&browser, DOCUMENT);
+
+    let expected = ids(
+        &page,
+        &[
+            "first-a",
+            "first-b",
+            "second",
+            "link",
+            "zero",
+            "inside-invisible",
+            "editable",
+            "textarea",
+        ],
+    );
+    assert_eq!(page.focus_order(), expected);
+}
+
+#[test]
+fn tabbing_wraps_around() {
+    let browser = Browser::default();
+    let (page, _renderer) = open(&browser, DOCUMENT);
+    let first = element_by_id(&page, "first-a");
+    let second = element_by_id(&page, "first-b");
+    let last = element_by_id(&page, "textarea");
+
+    assert_eq!(page.next_focus(first), Some(second));
+    assert_eq!(page.next_focus(last), Some(first));
+    assert_eq!(page.prev_focus(second), Some(first));
+    assert_eq!(page.prev_focus(first), Some(last));
+    assert_eq!(page.next_focus(NodeId::ROOT), Some(first));
+    assert_eq!(page.prev_focus(NodeId::ROOT), Some(last));
+
+    let (empty, _renderer) = open(&browser, "<html><body><p>Nothing</p></body></html>");
+    assert_eq!(empty.next_focus(NodeId::ROOT), None);
+    assert_eq!(empty.prev_focus(NodeId::ROOT), None);
+}
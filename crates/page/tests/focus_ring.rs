@@ -0,0 +1,96 @@
+//! Focus ring tests - checks the ring's size and style for declared,
+//! `auto` and missing outlines, and that it covers the boxes of an
+//! element's descendants.
+
+mod common;
+
+use common::{element_by_id, load, px};
+use rewrite_layout::Rect;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{BoxArea, FocusRing, Renderer};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div { width: 100px; height: 20px; margin-bottom: 10px }
+    #styled { outline: 2px solid red; outline-offset: 3px }
+    #auto { outline-style: auto }
+    #medium { outline-style: dashed }
+    #hidden { display: none }
+    #overflowing { height: 5px }
+    #tall { height: 40px }
+</style></head><body>
+    <div id=\"plain\"></div>
+    <div id=\"styled\"></div>
+    <div id=\"auto\"></div>
+    <div id=\"medium\"></div>
+    <div id=\"hidden\"></div>
+    <div id=\"overflowing\"><div id=\"tall\"></div></div>
+</body></html>";
+
+/// `#id`'s border box and focus ring.
+fn ring(page: &Page<'_>, renderer: &Renderer, id: &str) -> (Rect, FocusRing) {
+    let node = element_by_id(page, id);
+    let (Some(border), Some(ring)) = (
+        renderer.box_rect(node, BoxArea::Border),
+        renderer.focus_ring(node),
+    ) else {
+        panic!("#{id} has no box");
+    };
+    (border, ring)
+}
+
+#[test]
+fn ring_follows_the_outline() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let (border, plain) = ring(&page, &renderer, "plain");
+    assert_eq!(
+        plain,
+        FocusRing {
+            rect: border.inflate(px(1.0)),
+            width: px(1.0),
+            platform_style: true,
+        },
+        "no outline gets the default ring"
+    );
+
+    let (border, styled) = ring(&page, &renderer, "styled");
+    assert_eq!(
+        styled,
+        FocusRing {
+            rect: border.inflate(px(5.0)),
+            width: px(2.0),
+            platform_style: false,
+        },
+        "pushed out by the offset, then the width"
+    );
+
+    let (border, auto) = ring(&page, &renderer, "auto");
+    assert_eq!((auto.rect, auto.width), (border.inflate(px(1.0)), px(1.0)));
+    assert!(auto.platform_style);
+
+    let (border, medium) = ring(&page, &renderer, "medium");
+    assert_eq!(
+        (medium.rect, medium.width),
+        (border.inflate(px(3.0)), px(3.0))
+    );
+    assert!(!medium.platform_style);
+
+    assert_eq!(renderer.focus_ring(element_by_id(&page, "hidden")), None);
+}
+
+#[test]
+fn ring_covers_descendant_boxes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let (border, ring) = ring(&page, &renderer, "overflowing");
+    let Some(tall) = renderer.box_rect(element_by_id(&page, "tall"), BoxArea::Border) else {
+        panic!("#tall has no box");
+    };
+    assert!(tall.height > border.height);
+    assert_eq!(ring.rect, border.union(&tall).inflate(px(1.0)));
+}
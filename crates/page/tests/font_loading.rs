@@ -0,0 +1,85 @@
+//! Font loading tests - checks that text set in a font that hasn't loaded
+//! yet is measured with a fallback, and that loading the font re-measures
+//! only the text that lists it.
+
+mod common;
+
+use common::{element_by_id, whole_px};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{GeometryChange, Renderer};
+use std::sync::PoisonError;
+
+const DOCUMENT: &str = "<html><body style=\"margin: 0\">
+    <p style=\"margin: 0\"><span id=\"brand\" style=\"font-family: Brand\">iiiiiiii</span><span id=\"after\">x</span></p>
+    <p style=\"margin: 0\"><span id=\"mono\" style=\"font-family: monospace\">iiiiiiii</span></p>
+    <p style=\"margin: 0\"><span id=\"plain\">iiiiiiii</span></p>
+    <div id=\"ch\" style=\"font-family: Brand; width: 8ch; height: 1px\"></div>
+</body></html>";
+
+/// The regular face of the font the platform uses for `monospace` on
+/// Linux.
+const MONOSPACE_FACE: &str = "DejaVuSansMono";
+
+/// `(x, width)` of `#id`, in whole pixels.
+fn horizontal(page: &Page<'_>, renderer: &Renderer, id: &str) -> (i32, i32) {
+    let computed = renderer.computed_box(element_by_id(page, id));
+    (whole_px(computed.x), whole_px(computed.width))
+}
+
+/// The font data of the installed monospace face.
+fn monospace_font_data() -> Vec<u8> {
+    let font_system = rewrite_text::get_font_system();
+    let guard = font_system.lock().unwrap_or_else(PoisonError::into_inner);
+    let db = guard.db();
+    let face = db
+        .faces()
+        .find(|face| face.post_script_name == MONOSPACE_FACE)
+        .unwrap_or_else(|| panic!("{MONOSPACE_FACE} is not installed"));
+    db.with_face_data(face.id, |data, _| data.to_vec())
+        .unwrap_or_else(|| panic!("{MONOSPACE_FACE} can't be read"))
+}
+
+#[test]
+fn loading_a_font_remeasures_the_text_using_it() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let (_, mono_width) = horizontal(&page, &renderer, "mono");
+    let (_, fallback_width) = horizontal(&page, &renderer, "brand");
+    assert_ne!(
+        fallback_width, mono_width,
+        "before loading, the fallback font is used"
+    );
+    renderer.take_geometry_diff();
+
+    renderer.load_font("Brand", monospace_font_data());
+    let (_, loaded_width) = horizontal(&page, &renderer, "brand");
+    assert_eq!(loaded_width, mono_width, "the loaded font is used");
+    assert_eq!(
+        horizontal(&page, &renderer, "after").0,
+        loaded_width,
+        "the rest of the line moves along"
+    );
+    assert_eq!(
+        horizontal(&page, &renderer, "ch").1,
+        loaded_width,
+        "`ch` is measured in the loaded font"
+    );
+
+    let diff = renderer.take_geometry_diff();
+    let nodes =
+        |changes: &[GeometryChange]| changes.iter().map(|change| change.node).collect::<Vec<_>>();
+    assert!(nodes(&diff.resized).contains(&element_by_id(&page, "brand")));
+    assert!(nodes(&diff.moved).contains(&element_by_id(&page, "after")));
+    for id in ["mono", "plain"] {
+        let node = element_by_id(&page, id);
+        assert!(
+            !nodes(&diff.moved).contains(&node) && !nodes(&diff.resized).contains(&node),
+            "#{id} doesn't use the font"
+        );
+    }
+}
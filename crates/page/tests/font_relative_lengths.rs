@@ -0,0 +1,79 @@
+//! Font-relative length tests - checks that boxes sized in `em` follow a
+//! change of their own font size, and boxes sized in `rem` a change of the
+//! root element's, without re-resolving boxes that don't use them.
+
+mod common;
+
+use common::{element_by_id, geometry, open};
+use rewrite_core::NodeId;
+use rewrite_page::Browser;
+use rewrite_renderer::{GeometryChange, Renderer};
+
+const DOCUMENT: &str = "<html id=\"root\"><head><style>
+    html { font-size: 10px }
+    html.large { font-size: 20px }
+    #em { font-size: 10px; width: 10em; padding-left: 1em; height: 2em }
+    #em.large { font-size: 20px }
+</style></head><body style=\"margin: 0\">
+    <div id=\"own-font\" style=\"font-size: 12px\">
+        <div id=\"nested-rem\" style=\"width: 3rem; height: 10px\"></div>
+    </div>
+    <div id=\"em\">
+        <div id=\"inner\" style=\"height: 5px\"></div>
+    </div>
+    <div id=\"rem\" style=\"width: 5rem; height: 1rem\"></div>
+    <div id=\"fixed\" style=\"width: 30px; height: 10px\"></div>
+</body></html>";
+
+fn changed(renderer: &Renderer) -> Vec<NodeId> {
+    let diff = renderer.take_geometry_diff();
+    let nodes =
+        |changes: &[GeometryChange]| changes.iter().map(|change| change.node).collect::<Vec<_>>();
+    [nodes(&diff.moved), nodes(&diff.resized)].concat()
+}
+
+#[test]
+fn em_lengths_follow_their_own_font_size() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    assert_eq!(geometry(&page, &renderer, "em"), (0, 10, 100, 20));
+    assert_eq!(geometry(&page, &renderer, "inner"), (10, 10, 90, 5));
+    renderer.take_geometry_diff();
+
+    // Only `font-size` changes.
+    page.set_attribute(element_by_id(&page, "em"), "class", Some("large"));
+    assert_eq!(geometry(&page, &renderer, "em"), (0, 10, 200, 40));
+    assert_eq!(
+        geometry(&page, &renderer, "inner"),
+        (20, 10, 180, 5),
+        "the content box moved and widened"
+    );
+    assert_eq!(geometry(&page, &renderer, "rem").1, 50);
+
+    let changed = changed(&renderer);
+    assert!(changed.contains(&element_by_id(&page, "em")));
+    assert!(changed.contains(&element_by_id(&page, "fixed")));
+}
+
+#[test]
+fn rem_lengths_follow_the_root_font_size() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    assert_eq!(geometry(&page, &renderer, "rem"), (0, 30, 50, 10));
+    renderer.take_geometry_diff();
+
+    page.set_attribute(element_by_id(&page, "root"), "class", Some("large"));
+    assert_eq!(geometry(&page, &renderer, "rem"), (0, 30, 100, 20));
+    assert_eq!(geometry(&page, &renderer, "fixed").1, 50);
+    assert_eq!(
+        geometry(&page, &renderer, "nested-rem").2,
+        60,
+        "`rem` below a box with a font size of its own"
+    );
+    assert_eq!(
+        geometry(&page, &renderer, "em"),
+        (0, 10, 100, 20),
+        "`em` against a font size of its own"
+    );
+    assert!(!changed(&renderer).contains(&element_by_id(&page, "em")));
+}
@@ -0,0 +1,71 @@
+//! Formatting context tests - checks the public classification of the box
+//! each element generates and which block containers establish a new
+//! block formatting context.
+
+mod common;
+
+use common::element_by_id;
+use rewrite_layout::FormattingContext;
+use rewrite_page::Browser;
+
+const DOCUMENT: &str = "<html><body>
+    <div id=\"plain\"><p id=\"paragraph\">Text</p></div>
+    <div id=\"scroller\" style=\"overflow: hidden\"></div>
+    <div id=\"flow-root\" style=\"display: flow-root\"></div>
+    <div id=\"inline-block\" style=\"display: inline-block\"></div>
+    <span id=\"span\">Text</span>
+    <div id=\"flex\" style=\"display: flex\"><div id=\"flex-item\"></div></div>
+    <div id=\"grid\" style=\"display: grid\"><div id=\"grid-item\"></div></div>
+    <div id=\"float\" style=\"float: left\"></div>
+    <div id=\"absolute\" style=\"position: absolute\"></div>
+    <div id=\"hidden\" style=\"display: none\"></div>
+</body></html>";
+
+#[test]
+fn elements_report_their_formatting_context() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let classify = |id: &str| {
+        let node = element_by_id(&page, id);
+        renderer.formatting_context(node)
+    };
+
+    let expected = [
+        ("plain", Some(FormattingContext::BlockContainer)),
+        ("paragraph", Some(FormattingContext::BlockContainer)),
+        (
+            "scroller",
+            Some(FormattingContext::BlockFormattingContextRoot),
+        ),
+        (
+            "flow-root",
+            Some(FormattingContext::BlockFormattingContextRoot),
+        ),
+        (
+            "inline-block",
+            Some(FormattingContext::BlockFormattingContextRoot),
+        ),
+        ("span", Some(FormattingContext::Inline)),
+        ("flex", Some(FormattingContext::Flex)),
+        (
+            "flex-item",
+            Some(FormattingContext::BlockFormattingContextRoot),
+        ),
+        ("grid", Some(FormattingContext::Grid)),
+        (
+            "grid-item",
+            Some(FormattingContext::BlockFormattingContextRoot),
+        ),
+        ("float", Some(FormattingContext::Float)),
+        ("absolute", Some(FormattingContext::AbsolutelyPositioned)),
+        ("hidden", None),
+    ];
+    for (id, context) in expected {
+        assert_eq!(classify(id), context, "#{id}");
+    }
+}
@@ -0,0 +1,150 @@
+//! Fragment tree tests - checks that the fragment tree nests boxes like the
+//! DOM, skips `display: none` subtrees, and unites descendant overflow
+//! except where a box clips it; and that iteration and box-area rect
+//! queries visit boxes in tree order.
+
+mod common;
+
+use common::{element_by_id, load, px};
+use rewrite_core::NodeId;
+use rewrite_layout::Rect;
+use rewrite_page::Browser;
+use rewrite_renderer::{BoxArea, Fragment, FragmentTree};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #clipper { margin: 10px; padding: 5px; border: 2px solid; width: 100px; height: 50px; overflow: hidden }
+    #clipped { height: 200px }
+    #spill { height: 10px }
+    #tall { width: 900px; height: 100px }
+    #gone { display: none }
+</style></head><body>
+    <div id=\"clipper\"><div id=\"clipped\"></div></div>
+    <div id=\"spill\"><div id=\"tall\"></div></div>
+    <div id=\"gone\"><div id=\"gone-child\"></div></div>
+</body></html>";
+
+fn only_child(fragment: &Fragment) -> &Fragment {
+    let [child] = fragment.children.as_slice() else {
+        panic!(
+            "{:?} has {} children",
+            fragment.node,
+            fragment.children.len()
+        );
+    };
+    child
+}
+
+/// The `<body>` fragment, inside the document's and the `<html>` one.
+fn body(tree: &FragmentTree) -> &Fragment {
+    only_child(only_child(&tree.roots[0]))
+}
+
+#[test]
+fn fragments_nest_like_the_dom() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+    let tree = renderer.fragment_tree();
+
+    assert_eq!(tree.viewport, Rect::from_size(px(800.0), px(600.0)));
+    let roots: Vec<_> = tree.roots.iter().map(|fragment| fragment.node).collect();
+    assert_eq!(roots, [NodeId::ROOT]);
+    let clipper = element_by_id(&page, "clipper");
+    let spill = element_by_id(&page, "spill");
+    let body = body(&tree);
+    let children: Vec<_> = body.children.iter().map(|fragment| fragment.node).collect();
+    assert_eq!(children, [clipper, spill], "in DOM order, without #gone");
+    for id in ["gone", "gone-child"] {
+        assert!(tree.find(element_by_id(&page, id)).is_none(), "#{id}");
+    }
+}
+
+#[test]
+fn overflow_is_clipped_by_boxes_that_clip() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+    let tree = renderer.fragment_tree();
+    let fragment = |id: &str| {
+        let node = element_by_id(&page, id);
+        tree.find(node)
+            .unwrap_or_else(|| panic!("#{id} has no fragment"))
+    };
+
+    let clipper = fragment("clipper");
+    let node = element_by_id(&page, "clipper");
+    assert_eq!(Some(clipper.rect), renderer.box_rect(node, BoxArea::Border));
+    assert_eq!(
+        clipper.clip,
+        renderer.box_rect(node, BoxArea::Padding),
+        "clipped to the padding box"
+    );
+    assert_eq!(
+        clipper.overflow,
+        clipper.rect.union(&fragment("clipped").rect),
+        "a clipping box still scrolls over its content"
+    );
+
+    let spill = fragment("spill");
+    assert_eq!(spill.clip, None);
+    assert_eq!(spill.overflow, spill.rect.union(&fragment("tall").rect));
+    let body = body(&tree);
+    assert_eq!(
+        body.overflow,
+        body.rect.union(&clipper.rect).union(&spill.overflow),
+        "the clipped content doesn't reach past #clipper"
+    );
+}
+
+#[test]
+fn iteration_and_box_rects_follow_tree_order() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+    let tree = renderer.fragment_tree();
+    let ids = ["clipper", "clipped", "spill", "tall"];
+    let elements: Vec<_> = ids.iter().map(|id| element_by_id(&page, id)).collect();
+
+    let visited: Vec<_> = body(&tree)
+        .iter()
+        .skip(1)
+        .map(|fragment| fragment.node)
+        .collect();
+    assert_eq!(visited, elements, "parents before children");
+    let all: Vec<_> = tree.iter().map(|fragment| fragment.node).collect();
+    assert!(all.ends_with(&elements));
+
+    let borders = renderer.laid_out_boxes(BoxArea::Border);
+    let nodes: Vec<_> = borders.iter().map(|&(node, _)| node).collect();
+    assert_eq!(nodes, all);
+
+    let clipper = elements[0];
+    let Some(border) = renderer.box_rect(clipper, BoxArea::Border) else {
+        panic!("#clipper has no box");
+    };
+    let around = |outset: f32| {
+        Rect::new(
+            border.x - px(outset),
+            border.y - px(outset),
+            border.width + px(2.0 * outset),
+            border.height + px(2.0 * outset),
+        )
+    };
+    assert_eq!(
+        renderer.box_rect(clipper, BoxArea::Margin),
+        Some(around(10.0))
+    );
+    assert_eq!(
+        renderer.box_rect(clipper, BoxArea::Padding),
+        Some(around(-2.0))
+    );
+    assert_eq!(
+        renderer.box_rect(clipper, BoxArea::Content),
+        Some(around(-7.0))
+    );
+    assert_eq!(
+        renderer.box_rect(element_by_id(&page, "gone"), BoxArea::Border),
+        None
+    );
+}
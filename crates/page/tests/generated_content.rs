@@ -0,0 +1,253 @@
+//! Generated content tests - checks that `::before` and `::after` rules
+//! generate boxes with evaluated `content`, that `counter-reset`,
+//! `counter-set` and `counter-increment` are tracked in document order,
+//! that generated boxes take room in inline layout and are painted, and
+//! that counter and rule changes after load lay out the boxes they reach.
+
+mod common;
+
+use common::{element_by_id, load, open};
+use rewrite_core::GeneratedBox;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{BoxArea, DisplayItem, Renderer};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0; counter-reset: section }
+    h2 { margin: 0; counter-increment: section }
+    h2.double { counter-increment: section 5 }
+    h2::before { content: \"Section \" counter(section, upper-roman) \": \" }
+    #skip { counter-set: section 9 }
+    .toc, .toc ol { counter-reset: item; margin: 0; padding: 0 }
+    .toc li { display: block; counter-increment: item }
+    .toc li::before { content: counters(item, \".\") \" \" }
+    a::after { content: \" (\" attr(href) \")\" }
+    q::before { content: open-quote }
+    q::after { content: close-quote }
+    #none::before { content: none }
+    #normal::before { content: normal }
+    #hidden-pseudo::before { content: \"x\"; display: none }
+    p { margin: 0 }
+    #plain::before, #icon::before, #empty::before { content: \"\\2605\\2605\" }
+    #plain::after { content: \"!!\" }
+</style></head><body>
+<h2 id=\"h1\">One</h2>
+<h2 id=\"gone\" style=\"display: none\">Gone</h2>
+<h2 id=\"h2\">Two</h2>
+<h2 id=\"skip\">Skip</h2>
+<h2 id=\"h10\">Ten</h2>
+<ol class=\"toc\">
+  <li id=\"t1\">A<ol><li id=\"t1-1\">A.A</li><li id=\"t1-2\">A.B</li></ol></li>
+  <li id=\"t2\">B</li>
+</ol>
+<p><a id=\"link\" href=\"/docs\">docs</a> <q id=\"quote\">hi</q></p>
+<p id=\"none\">none</p><p id=\"normal\">normal</p><p id=\"hidden-pseudo\">hidden</p>
+<p id=\"plain\">Text</p>
+<p id=\"reference\">Text</p>
+<div id=\"icon\"></div>
+<p><span id=\"empty\"></span></p>
+<p><x-note id=\"inline\">Inline</x-note></p>
+</body></html>";
+
+fn generated_text(page: &Page<'_>, id: &str, pseudo: GeneratedBox) -> Option<String> {
+    page.styler
+        .generated_content(element_by_id(page, id), pseudo)
+        .map(|generated| generated.text)
+}
+
+/// The border box of the text node inside the element `id`.
+fn text_box(page: &Page<'_>, renderer: &Renderer, id: &str) -> rewrite_layout::Rect {
+    let element = element_by_id(page, id);
+    let text = page
+        .tree
+        .children(element)
+        .find(|&child| page.tree.text_content(child).is_some())
+        .unwrap_or_else(|| panic!("#{id} has no text"));
+    renderer
+        .used_values(text)
+        .unwrap_or_else(|| panic!("text of #{id} is laid out"))
+        .rect(BoxArea::Border)
+}
+
+#[test]
+fn counters_are_tracked_in_document_order() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let expected = [
+        ("h1", "Section I: "),
+        ("h2", "Section II: "),
+        ("skip", "Section X: "),
+        ("h10", "Section XI: "),
+        ("t1", "1 "),
+        ("t1-1", "1.1 "),
+        ("t1-2", "1.2 "),
+        ("t2", "2 "),
+    ];
+    for (id, text) in expected {
+        assert_eq!(
+            generated_text(&page, id, GeneratedBox::Before).as_deref(),
+            Some(text),
+            "::before of #{id}"
+        );
+    }
+    assert_eq!(generated_text(&page, "gone", GeneratedBox::Before), None);
+}
+
+#[test]
+fn content_evaluates_strings_attributes_and_quotes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    assert_eq!(
+        generated_text(&page, "link", GeneratedBox::After).as_deref(),
+        Some(" (/docs)")
+    );
+    assert_eq!(generated_text(&page, "link", GeneratedBox::Before), None);
+    assert_eq!(
+        generated_text(&page, "quote", GeneratedBox::Before).as_deref(),
+        Some("\u{201c}")
+    );
+    assert_eq!(
+        generated_text(&page, "quote", GeneratedBox::After).as_deref(),
+        Some("\u{201d}")
+    );
+    for id in ["none", "normal", "hidden-pseudo"] {
+        assert_eq!(
+            generated_text(&page, id, GeneratedBox::Before),
+            None,
+            "#{id} generates no ::before box"
+        );
+    }
+}
+
+#[test]
+fn generated_boxes_take_room_on_the_line() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let plain = element_by_id(&page, "plain");
+    let before = renderer
+        .generated_box(plain, GeneratedBox::Before)
+        .expect("#plain has a ::before box");
+    let after = renderer
+        .generated_box(plain, GeneratedBox::After)
+        .expect("#plain has an ::after box");
+    let content = renderer
+        .used_values(plain)
+        .expect("#plain is laid out")
+        .rect(BoxArea::Content);
+    let text = text_box(&page, &renderer, "plain");
+    let reference = text_box(&page, &renderer, "reference");
+
+    assert!(before.rect.width.to_f32() > 0.0);
+    assert_eq!(before.rect.x, content.x);
+    assert_eq!(text.x, before.rect.x + before.rect.width);
+    assert_eq!(text.x - reference.x, before.rect.width);
+    assert_eq!(after.rect.x, text.x + text.width);
+    assert_eq!(after.rect.y, text.y);
+}
+
+#[test]
+fn generated_content_alone_sizes_its_element() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let icon = element_by_id(&page, "icon");
+    let icon_box = renderer
+        .used_values(icon)
+        .expect("#icon is laid out")
+        .rect(BoxArea::Content);
+    let star = renderer
+        .generated_box(icon, GeneratedBox::Before)
+        .expect("#icon has a ::before box");
+    assert!(star.rect.height.to_f32() > 0.0);
+    assert_eq!(icon_box.height, star.rect.height);
+
+    let empty = element_by_id(&page, "empty");
+    let empty_box = renderer
+        .used_values(empty)
+        .expect("#empty is laid out")
+        .rect(BoxArea::Border);
+    let star = renderer
+        .generated_box(empty, GeneratedBox::Before)
+        .expect("#empty has a ::before box");
+    assert_eq!(empty_box.width, star.rect.width);
+}
+
+#[test]
+fn generated_content_is_painted() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let link = element_by_id(&page, "link");
+    let after = renderer
+        .generated_box(link, GeneratedBox::After)
+        .expect("#link has an ::after box");
+    let painted = renderer.display_list().items.into_iter().any(|entry| {
+        matches!(
+            entry,
+            DisplayItem::Text { node, rect, ref text, .. }
+                if node == link && rect == after.rect && *text == after.text
+        )
+    });
+    assert!(painted, "the ::after box of #link is in the display list");
+}
+
+#[test]
+fn counter_changes_relayout_later_boxes() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    let second = element_by_id(&page, "h2");
+    let old_width = renderer
+        .generated_box(second, GeneratedBox::Before)
+        .expect("#h2 has a ::before box")
+        .rect
+        .width;
+
+    page.set_attribute(element_by_id(&page, "h1"), "class", Some("double"));
+    assert_eq!(
+        generated_text(&page, "h2", GeneratedBox::Before).as_deref(),
+        Some("Section VI: ")
+    );
+    let before = renderer
+        .generated_box(second, GeneratedBox::Before)
+        .expect("#h2 has a ::before box");
+    assert_ne!(before.rect.width, old_width);
+    assert_eq!(
+        text_box(&page, &renderer, "h2").x,
+        before.rect.x + before.rect.width,
+        "the text follows the renumbered ::before box"
+    );
+    assert_eq!(
+        generated_text(&page, "h10", GeneratedBox::Before).as_deref(),
+        Some("Section XI: "),
+        "counter-set on #skip hides the change"
+    );
+}
+
+#[test]
+fn rules_added_after_load_generate_boxes() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    // No UA rule styles `x-note`, so the new rule is the only change to it.
+    let inline = element_by_id(&page, "inline");
+    let unmarked = text_box(&page, &renderer, "inline");
+
+    page.set_user_stylesheet("#inline::before { content: \"\\2605\\2605\" }");
+    let before = renderer
+        .generated_box(inline, GeneratedBox::Before)
+        .expect("#inline has a ::before box");
+    let text = text_box(&page, &renderer, "inline");
+    assert!(before.rect.width.to_f32() > 0.0);
+    assert_eq!(text.x, before.rect.x + before.rect.width);
+    assert_eq!(text.x, unmarked.x + before.rect.width);
+
+    page.set_user_stylesheet("");
+    assert_eq!(renderer.generated_box(inline, GeneratedBox::Before), None);
+    assert_eq!(text_box(&page, &renderer, "inline"), unmarked);
+}
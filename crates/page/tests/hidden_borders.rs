@@ -0,0 +1,85 @@
+//! Hidden border tests - checks that `border-style: hidden` zeroes the used
+//! border width like `none` does, and that changing only the border style
+//! re-lays out the box.
+
+mod common;
+
+use common::{element_by_id, open, px};
+use rewrite_layout::EdgeSizes;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{Renderer, UsedValues};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div { width: 100px; border: 5px solid }
+    #hidden { border-style: hidden }
+    #one-side { border-left-style: hidden }
+    .none { border-style: none }
+    .hidden-top { border-top-style: hidden }
+</style></head><body>
+    <div id=\"hidden\"></div>
+    <div id=\"one-side\"></div>
+    <div id=\"toggled\"></div>
+    <div id=\"after\"></div>
+</body></html>";
+
+/// The used values of `#id`.
+fn used(page: &Page<'_>, renderer: &Renderer, id: &str) -> UsedValues {
+    let Some(used) = renderer.used_values(element_by_id(page, id)) else {
+        panic!("#{id} has no box");
+    };
+    used
+}
+
+#[test]
+fn hidden_style_zeroes_the_width() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+
+    assert_eq!(
+        used(&page, &renderer, "hidden").border,
+        EdgeSizes::default()
+    );
+    assert_eq!(
+        used(&page, &renderer, "one-side").border,
+        EdgeSizes::new(px(5.0), px(5.0), px(5.0), px(0.0))
+    );
+    let sides = renderer.border_sides(element_by_id(&page, "one-side"));
+    assert_eq!(sides.left.width, px(0.0));
+    assert!(!sides.left.is_visible());
+    assert!(sides.right.is_visible());
+}
+
+#[test]
+fn border_style_changes_relayout() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    let toggled = element_by_id(&page, "toggled");
+    let solid = used(&page, &renderer, "toggled");
+    let after_y = used(&page, &renderer, "after").border_box.y;
+
+    page.set_attribute(toggled, "class", Some("none"));
+    let none = used(&page, &renderer, "toggled");
+    assert_eq!(none.border, EdgeSizes::default());
+    assert_eq!(none.border_box.height, solid.border_box.height - px(10.0));
+    assert_eq!(
+        used(&page, &renderer, "after").border_box.y,
+        after_y - px(10.0),
+        "later siblings move up"
+    );
+
+    page.set_attribute(toggled, "class", Some("hidden-top"));
+    let hidden_top = used(&page, &renderer, "toggled");
+    assert_eq!(
+        hidden_top.border,
+        EdgeSizes::new(px(0.0), px(5.0), px(5.0), px(5.0))
+    );
+    assert_eq!(
+        hidden_top.border_box.height,
+        solid.border_box.height - px(5.0)
+    );
+
+    page.set_attribute(toggled, "class", None);
+    assert_eq!(used(&page, &renderer, "toggled"), solid);
+    assert_eq!(used(&page, &renderer, "after").border_box.y, after_y);
+}
@@ -0,0 +1,138 @@
+//! Hit testing tests - checks that the topmost box under a point is hit,
+//! and that `pointer-events: none`, hidden and `inert` boxes are passed over.
+//! Also checks that used geometry is hit and that `hit_test_all` reports the
+//! ancestor chain.
+
+mod common;
+
+use common::element_by_id;
+use rewrite_core::{NodeId, Subpixel};
+use rewrite_html::NodeData;
+use rewrite_page::Browser;
+use rewrite_renderer::Renderer;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div { position: absolute; width: 100px; height: 100px }
+    #under { left: 0; top: 0; z-index: 1 }
+    #over { left: 50px; top: 0; z-index: 2 }
+    #ghost { left: 0; top: 200px; z-index: 2; pointer-events: NONE }
+    #ghost > div { left: 0; top: 0; width: 20px; height: 20px; pointer-events: auto }
+    #floor { left: 0; top: 200px; z-index: 1; width: 200px }
+    #hidden { left: 300px; top: 0; z-index: 2; visibility: hidden }
+    #shown { left: 300px; top: 0; z-index: 1 }
+    #inert { left: 500px; top: 0; z-index: 2 }
+    #inert > div { left: 0; top: 0; width: 50px; height: 50px; pointer-events: auto }
+    #backdrop { left: 500px; top: 0; z-index: 1 }
+    #label { left: 0; top: 400px; width: auto; height: auto }
+</style></head><body>
+<div id=\"under\"></div><div id=\"over\"></div>
+<div id=\"ghost\"><div id=\"ghost-child\"></div></div><div id=\"floor\"></div>
+<div id=\"hidden\"></div><div id=\"shown\"></div>
+<div id=\"inert\" inert><div id=\"inert-child\"></div></div><div id=\"backdrop\"></div>
+<div id=\"label\">Label</div>
+</body></html>";
+
+fn hit(renderer: &Renderer, x: i32, y: i32) -> Option<NodeId> {
+    renderer.hit_test(Subpixel::from_px(x), Subpixel::from_px(y))
+}
+
+#[test]
+fn hit_testing_skips_boxes_without_pointer_input() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+    let id = |name| element_by_id(&page, name);
+
+    assert_eq!(hit(&renderer, 10, 10), Some(id("under")));
+    assert_eq!(
+        hit(&renderer, 60, 10),
+        Some(id("over")),
+        "the topmost box wins"
+    );
+
+    assert_eq!(
+        hit(&renderer, 10, 210),
+        Some(id("ghost-child")),
+        "a descendant can take input back with `pointer-events: auto`"
+    );
+    assert_eq!(
+        hit(&renderer, 50, 250),
+        Some(id("floor")),
+        "`pointer-events: none` falls through to the box below"
+    );
+
+    assert_eq!(
+        hit(&renderer, 310, 10),
+        Some(id("shown")),
+        "hidden boxes are passed over"
+    );
+
+    assert_eq!(
+        hit(&renderer, 510, 10),
+        Some(id("backdrop")),
+        "an inert subtree is passed over whatever its style"
+    );
+
+    assert_eq!(
+        hit(&renderer, 2, 402),
+        Some(id("label")),
+        "text hits its element"
+    );
+}
+
+#[test]
+fn hit_testing_follows_used_geometry_and_reports_ancestors() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(
+        "<html><body style=\"margin: 0\">
+        <div id=\"shifted\" style=\"position: relative; left: 200px; width: 50px; height: 50px\">
+            <div id=\"leaf\" style=\"height: 20px\"></div>
+        </div>
+        <div id=\"float\" style=\"float: left; width: 100px; height: 100px\"></div>
+        <div id=\"block\" style=\"height: 200px\"></div>
+        </body></html>",
+    );
+    document.finish();
+    let id = |name| element_by_id(&page, name);
+
+    assert_eq!(
+        hit(&renderer, 210, 30),
+        Some(id("shifted")),
+        "relative offsets move the box"
+    );
+    assert_ne!(hit(&renderer, 10, 30), Some(id("shifted")));
+    assert_eq!(
+        hit(&renderer, 50, 100),
+        Some(id("float")),
+        "a float is over the block it overlaps"
+    );
+    assert_eq!(hit(&renderer, 150, 100), Some(id("block")));
+
+    let chain = renderer.hit_test_all(Subpixel::from_px(210), Subpixel::from_px(10));
+    let [leaf, shifted] = ["leaf", "shifted"].map(id);
+    assert_eq!(chain.get(..2), Some(&[leaf, shifted][..]));
+    let names: Vec<_> = chain[2..]
+        .iter()
+        .filter_map(|&node| match page.tree.get_node(node) {
+            Some(NodeData::Element { tag, .. }) => Some(page.tree.interner.resolve(tag)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        names,
+        ["body", "html"],
+        "the chain ends at the root element"
+    );
+    assert!(
+        renderer
+            .hit_test_all(Subpixel::from_px(900), Subpixel::from_px(10))
+            .is_empty()
+    );
+}
@@ -0,0 +1,102 @@
+//! HtmlPage tests - checks that streamed bytes are decoded across chunk
+//! boundaries, parsed progressively on each tick, and that geometry, dirty
+//! rects and computed style are reported as the document loads.
+
+mod common;
+
+use common::element_by_id;
+use lightningcss::properties::display::{Display, DisplayInside, DisplayOutside, DisplayPair};
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::NodeId;
+use rewrite_page::{Browser, HtmlPage};
+
+fn text_of(html_page: &HtmlPage<'_>, node: NodeId) -> String {
+    let tree = &html_page.page().tree;
+    tree.children(node)
+        .filter_map(|child| tree.text_content(child).map(str::to_owned))
+        .collect()
+}
+
+#[test]
+fn decodes_characters_split_across_pushes() {
+    let browser = Browser::default();
+    let mut html_page = HtmlPage::new(&browser, 800, 600);
+    let bytes = "<p id=\"text\">café ☕</p>".as_bytes();
+    for byte in bytes {
+        html_page.push(&[*byte]);
+    }
+    html_page.push(b"<p id=\"bad\">a\xffb\xe2\x98");
+    html_page.finish();
+    html_page.tick();
+
+    assert!(!html_page.is_loading());
+    assert_eq!(
+        text_of(&html_page, element_by_id(html_page.page(), "text")),
+        "café ☕"
+    );
+    assert_eq!(
+        text_of(&html_page, element_by_id(html_page.page(), "bad")),
+        "a\u{fffd}b\u{fffd}"
+    );
+}
+
+#[test]
+fn each_tick_lays_out_what_arrived() {
+    let browser = Browser::default();
+    let mut html_page = HtmlPage::new(&browser, 800, 600);
+    html_page.push(b"<html><body><div id=\"first\" style=\"height: 30px\"></div>");
+    assert!(html_page.tick());
+    assert!(html_page.is_loading());
+
+    let first = element_by_id(html_page.page(), "first");
+    let first_box = html_page
+        .geometry()
+        .into_iter()
+        .find(|&(node, _)| node == first)
+        .map(|(_, rect)| rect)
+        .expect("#first has a box");
+    assert!(html_page.take_dirty_rects().contains(&first_box));
+
+    assert!(!html_page.tick(), "nothing arrived");
+    assert!(html_page.take_dirty_rects().is_empty());
+
+    html_page.push(b"<div id=\"second\" style=\"height: 20px\"></div></body></html>");
+    html_page.finish();
+    assert!(html_page.tick());
+    let second = element_by_id(html_page.page(), "second");
+    let geometry = html_page.geometry();
+    assert!(geometry.iter().any(|&(node, _)| node == second));
+    assert!(!html_page.take_dirty_rects().is_empty());
+}
+
+#[test]
+fn computed_style_includes_inherited_values() {
+    let browser = Browser::default();
+    let mut html_page = HtmlPage::new(&browser, 800, 600);
+    html_page.push(
+        b"<html><head><style>#flex { display: flex; color: red }</style></head>\
+          <body><div id=\"flex\"><span id=\"child\">child</span></div></body></html>",
+    );
+    html_page.finish();
+    html_page.tick();
+
+    let flex = element_by_id(html_page.page(), "flex");
+    let child = element_by_id(html_page.page(), "child");
+    assert_eq!(
+        html_page.computed_style(flex, &PropertyId::Display),
+        Some(Property::Display(Display::Pair(DisplayPair {
+            outside: DisplayOutside::Block,
+            inside: DisplayInside::Flex(Default::default()),
+            is_list_item: false,
+        })))
+    );
+    assert_eq!(
+        html_page.computed_style(child, &PropertyId::Color),
+        html_page.computed_style(flex, &PropertyId::Color)
+    );
+    assert!(
+        html_page
+            .computed_style(child, &PropertyId::Color)
+            .is_some()
+    );
+}
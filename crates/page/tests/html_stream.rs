@@ -0,0 +1,66 @@
+//! Streaming HTML tests - checks that a document pushed in arbitrary chunks
+//! through `Page::begin_html` lays out like one pushed whole, and that
+//! content is laid out before the document is finished.
+
+mod common;
+
+use common::{element_by_id, find_element_by_id, whole_px};
+use rewrite_page::Browser;
+use rewrite_renderer::BoxArea;
+use std::str;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #first { height: 12px }
+</style><style>
+    #second { height: 34px; padding-left: 5px }
+</style></head><body>
+    <div id=\"first\">First</div>
+    <div id=\"second\">Second</div>
+</body></html>";
+
+/// Every laid-out border box of `DOCUMENT` pushed `chunk_len` bytes at a
+/// time.
+fn boxes_in_chunks(chunk_len: usize) -> Vec<String> {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    for chunk in DOCUMENT.as_bytes().chunks(chunk_len) {
+        document.push(str::from_utf8(chunk).unwrap());
+    }
+    document.finish();
+    renderer
+        .laid_out_boxes(BoxArea::Border)
+        .into_iter()
+        .map(|(node, rect)| format!("{node:?} {rect:?}"))
+        .collect()
+}
+
+#[test]
+fn chunk_boundaries_dont_change_layout() {
+    let whole = boxes_in_chunks(DOCUMENT.len());
+    for chunk_len in [1, 7, 64] {
+        assert_eq!(boxes_in_chunks(chunk_len), whole, "{chunk_len}-byte chunks");
+    }
+}
+
+#[test]
+fn pushed_content_is_laid_out_before_finish() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    let split = DOCUMENT.find("<div id=\"second\"").unwrap();
+    document.push(&DOCUMENT[..split]);
+
+    let first = element_by_id(&page, "first");
+    assert_eq!(whole_px(renderer.computed_box(first).height), 12);
+    assert_eq!(find_element_by_id(&page, "second"), None);
+
+    document.push(&DOCUMENT[split..]);
+    document.finish();
+    let second = element_by_id(&page, "second");
+    assert_eq!(whole_px(renderer.computed_box(second).height), 34);
+    assert_eq!(whole_px(renderer.computed_box(second).y), 12);
+}
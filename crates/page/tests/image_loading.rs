@@ -0,0 +1,72 @@
+//! Image loading tests - checks that giving a replaced element its natural
+//! size after the first layout sizes it on its line and moves only the
+//! boxes that depend on it.
+
+mod common;
+
+use common::{element_by_id, geometry, open};
+use rewrite_core::{IntrinsicSize, Subpixel};
+use rewrite_page::Browser;
+use rewrite_renderer::GeometryChange;
+
+const DOCUMENT: &str = "<html><body style=\"margin: 0\">
+    <div id=\"before\" style=\"height: 30px\"></div>
+    <p id=\"para\" style=\"margin: 0\"><img id=\"photo\" style=\"margin-left: 4px\"><img id=\"scaled\" style=\"width: 40px\"><img id=\"hidden\" style=\"display: none\"></p>
+    <div id=\"after\" style=\"height: 10px\"></div>
+</body></html>";
+
+const NATURAL: IntrinsicSize = IntrinsicSize {
+    width: Subpixel::from_px(100),
+    height: Subpixel::from_px(50),
+};
+
+#[test]
+fn natural_size_reflows_the_line() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    assert_eq!(
+        geometry(&page, &renderer, "para").3,
+        0,
+        "nothing loaded yet"
+    );
+    assert_eq!(geometry(&page, &renderer, "after").1, 30);
+    renderer.take_geometry_diff();
+
+    renderer.set_intrinsic_size(element_by_id(&page, "photo"), Some(NATURAL));
+    assert_eq!(geometry(&page, &renderer, "photo"), (4, 30, 100, 50));
+    assert_eq!(geometry(&page, &renderer, "para").3, 50);
+    assert_eq!(geometry(&page, &renderer, "after").1, 80);
+
+    let diff = renderer.take_geometry_diff();
+    let nodes =
+        |changes: &[GeometryChange]| changes.iter().map(|change| change.node).collect::<Vec<_>>();
+    assert!(nodes(&diff.resized).contains(&element_by_id(&page, "para")));
+    assert!(nodes(&diff.moved).contains(&element_by_id(&page, "after")));
+    let before = element_by_id(&page, "before");
+    assert!(
+        !nodes(&diff.moved).contains(&before) && !nodes(&diff.resized).contains(&before),
+        "boxes above the image keep their place"
+    );
+}
+
+#[test]
+fn specified_sizes_keep_the_aspect_ratio() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    renderer.set_intrinsic_size(element_by_id(&page, "photo"), Some(NATURAL));
+    renderer.set_intrinsic_size(element_by_id(&page, "scaled"), Some(NATURAL));
+    renderer.set_intrinsic_size(element_by_id(&page, "hidden"), Some(NATURAL));
+
+    assert_eq!(geometry(&page, &renderer, "scaled"), (104, 30, 40, 20));
+    assert_eq!(
+        renderer.computed_box(element_by_id(&page, "hidden")).height,
+        None,
+        "display: none boxes stay hidden"
+    );
+
+    // Forgetting the size (e.g. the load failed) empties the line again.
+    renderer.set_intrinsic_size(element_by_id(&page, "photo"), None);
+    renderer.set_intrinsic_size(element_by_id(&page, "scaled"), None);
+    assert_eq!(geometry(&page, &renderer, "para").3, 0);
+    assert_eq!(geometry(&page, &renderer, "after").1, 30);
+}
@@ -0,0 +1,55 @@
+//! Incremental layout tests - checks that a size change re-resolves the
+//! boxes it moves once each, rather than every sibling again for every
+//! box that moved.
+
+mod common;
+
+use common::element_by_id;
+use rewrite_core::Subpixel;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+fn top(page: &Page<'_>, renderer: &Renderer, id: &str) -> Option<Subpixel> {
+    renderer.computed_box(element_by_id(page, id)).y
+}
+
+/// Grow a box followed by `count` paragraphs of text. Returns the
+/// top-level resolves the change took.
+fn grow_before(count: usize) -> u64 {
+    let mut html = String::from(
+        "<html><body style=\"margin: 0\"><div><div id=\"grow\" style=\"height: 10px\"></div></div>",
+    );
+    for index in 0..count {
+        html.push_str(&format!("<p id=\"p{index}\">Paragraph {index}</p>"));
+    }
+    html.push_str("</body></html>");
+
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(&html);
+    document.finish();
+
+    let last = format!("p{}", count - 1);
+    let before = top(&page, &renderer, &last);
+    renderer.reset_resolve_stats();
+    page.set_attribute(element_by_id(&page, "grow"), "style", Some("height: 30px"));
+    let work = renderer.resolve_stats().top_level;
+    assert_eq!(
+        top(&page, &renderer, &last),
+        before.map(|y| y + Subpixel::from_px(20)),
+        "the following boxes move down"
+    );
+    work
+}
+
+#[test]
+fn moved_siblings_are_resolved_once() {
+    let small = grow_before(10);
+    let large = grow_before(40);
+    assert!(
+        large < small * 6,
+        "four times the siblings took {large} resolves against {small}"
+    );
+}
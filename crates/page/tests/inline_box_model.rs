@@ -0,0 +1,65 @@
+//! Inline box model tests - checks that the horizontal padding, border and
+//! margin of inline elements advance the line while their vertical ones
+//! leave the line height alone.
+
+mod common;
+
+use common::geometry;
+use rewrite_page::Browser;
+
+const DOCUMENT: &str = "<html><body style=\"margin: 0\">
+    <p id=\"plain-line\" style=\"margin: 0\"><span id=\"plain\">Text</span><span id=\"plain-next\">Next</span></p>
+    <p id=\"decorated-line\" style=\"margin: 0\"><span id=\"decorated\" style=\"padding: 20px 10px; border: 3px solid black; margin: 30px 5px\">Text</span><span id=\"decorated-next\">Next</span></p>
+    <p id=\"nested-line\" style=\"margin: 0\"><span id=\"outer\" style=\"padding-left: 8px\"><span id=\"inner\" style=\"margin-left: 4px\">Text</span></span></p>
+</body></html>";
+
+#[test]
+fn horizontal_decorations_advance_the_line() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let (plain_x, _, plain_width, _) = geometry(&page, &renderer, "plain");
+    let (decorated_x, _, decorated_width, _) = geometry(&page, &renderer, "decorated");
+    assert_eq!(decorated_x, plain_x + 5, "start margin shifts the box");
+    assert_eq!(
+        decorated_width,
+        plain_width + 2 * 10 + 2 * 3,
+        "padding and border widen the box"
+    );
+
+    let (plain_next_x, _, _, _) = geometry(&page, &renderer, "plain-next");
+    let (decorated_next_x, _, _, _) = geometry(&page, &renderer, "decorated-next");
+    assert_eq!(plain_next_x, plain_x + plain_width);
+    assert_eq!(
+        decorated_next_x,
+        decorated_x + decorated_width + 5,
+        "end margin advances the next box"
+    );
+
+    let (outer_x, _, outer_width, _) = geometry(&page, &renderer, "outer");
+    let (inner_x, _, inner_width, _) = geometry(&page, &renderer, "inner");
+    assert_eq!(inner_x, outer_x + 8 + 4);
+    assert_eq!(outer_width, inner_width + 8 + 4);
+}
+
+#[test]
+fn vertical_decorations_leave_the_line_height_alone() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let (_, _, _, plain_height) = geometry(&page, &renderer, "plain");
+    let (_, _, _, decorated_height) = geometry(&page, &renderer, "decorated");
+    assert_eq!(decorated_height, plain_height);
+
+    let (_, _, _, plain_line) = geometry(&page, &renderer, "plain-line");
+    let (_, _, _, decorated_line) = geometry(&page, &renderer, "decorated-line");
+    assert_eq!(decorated_line, plain_line);
+}
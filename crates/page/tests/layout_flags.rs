@@ -0,0 +1,41 @@
+//! Layout flag tests - checks that `LayoutFlags::disable_floats` lays
+//! floats out in normal flow, and that turning it off again restores them.
+
+mod common;
+
+use common::{element_by_id, load, px};
+use rewrite_core::LayoutFlags;
+use rewrite_page::Browser;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #float { float: right; width: 100px; height: 50px }
+    #after { height: 20px }
+</style></head><body>
+<div id=\"float\"></div>
+<div id=\"after\"></div>
+</body></html>";
+
+#[test]
+fn disable_floats_lays_floats_out_in_flow() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+    let float = element_by_id(&page, "float");
+    let after = element_by_id(&page, "after");
+
+    assert_eq!(renderer.computed_box(float).x, Some(px(700.0)));
+    assert_eq!(renderer.computed_box(after).y, Some(px(0.0)));
+
+    let flags = LayoutFlags {
+        disable_floats: true,
+        ..LayoutFlags::default()
+    };
+    renderer.set_layout_flags(flags);
+    assert_eq!(renderer.computed_box(float).x, Some(px(0.0)));
+    assert_eq!(renderer.computed_box(after).y, Some(px(50.0)));
+
+    renderer.set_layout_flags(LayoutFlags::default());
+    assert_eq!(renderer.computed_box(float).x, Some(px(700.0)));
+    assert_eq!(renderer.computed_box(after).y, Some(px(0.0)));
+}
@@ -0,0 +1,86 @@
+//! Length unit tests - checks that absolute units convert at 96 pixels per
+//! inch, that `ch` and `ex` are measured in the box's font, and that
+//! viewport-relative units follow a resize of the viewport.
+
+mod common;
+
+use common::{element_by_id, fractional_px, open};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+const DOCUMENT: &str = "<html><head><style>
+    .mono { font-family: monospace; font-size: 20px }
+    .mono.large { font-size: 40px }
+    .inch div { height: 1px }
+</style></head><body style=\"margin: 0\">
+    <div class=\"inch\">
+        <div id=\"in\" style=\"width: 1in\"></div>
+        <div id=\"cm\" style=\"width: 2.54cm\"></div>
+        <div id=\"mm\" style=\"width: 25.4mm\"></div>
+        <div id=\"q\" style=\"width: 101.6Q\"></div>
+        <div id=\"pt\" style=\"width: 72pt\"></div>
+        <div id=\"pc\" style=\"width: 6pc\"></div>
+    </div>
+    <div id=\"ch\" class=\"mono\" style=\"width: 10ch; height: 1ex\"></div>
+    <p class=\"mono\" style=\"margin: 0\"><span id=\"zeros\">0000000000</span></p>
+    <div id=\"vw\" style=\"width: 50vw; height: 10vh\"></div>
+    <div id=\"vmin\" style=\"width: 10vmin; height: 10vmax\"></div>
+</body></html>";
+
+/// `(width, height)` of `#id`, in pixels.
+fn size(page: &Page<'_>, renderer: &Renderer, id: &str) -> (f32, f32) {
+    let computed = renderer.computed_box(element_by_id(page, id));
+    (
+        fractional_px(computed.width),
+        fractional_px(computed.height),
+    )
+}
+
+#[test]
+fn absolute_units_convert_at_96_dpi() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    for id in ["in", "cm", "mm", "q", "pt", "pc"] {
+        let (width, _) = size(&page, &renderer, id);
+        assert_eq!(width.round(), 96.0, "one inch in `{id}`");
+    }
+}
+
+#[test]
+fn ch_and_ex_are_measured_in_the_font() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    let (width, height) = size(&page, &renderer, "ch");
+    let (zeros, _) = size(&page, &renderer, "zeros");
+    assert!(
+        (width - zeros).abs() < 1.0,
+        "10ch is the advance of ten zeros: {width} vs {zeros}"
+    );
+    assert!(
+        height > 0.0 && (height - 10.0).abs() > 0.5,
+        "1ex is the font's x-height, not the half-em fallback: {height}"
+    );
+
+    page.set_attribute(element_by_id(&page, "ch"), "class", Some("mono large"));
+    let (large_width, large_height) = size(&page, &renderer, "ch");
+    assert!(
+        (large_width - 2.0 * width).abs() < 1.0,
+        "ch follows font-size"
+    );
+    assert!(
+        (large_height - 2.0 * height).abs() < 1.0,
+        "ex follows font-size"
+    );
+}
+
+#[test]
+fn viewport_units_follow_the_viewport() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    assert_eq!(size(&page, &renderer, "vw"), (400.0, 60.0));
+    assert_eq!(size(&page, &renderer, "vmin"), (60.0, 80.0));
+
+    renderer.set_viewport(400, 1000);
+    assert_eq!(size(&page, &renderer, "vw"), (200.0, 100.0));
+    assert_eq!(size(&page, &renderer, "vmin"), (40.0, 100.0));
+}
@@ -0,0 +1,77 @@
+//! Line box tests - checks that text runs and inline boxes of a block
+//! container are placed on the line boxes that make up its height, and
+//! are rewrapped when the container's width changes.
+
+mod common;
+
+use common::{element_by_id, geometry, node_geometry};
+use rewrite_core::NodeId;
+use rewrite_page::{Browser, Page};
+
+/// Three lines at 200px wide: "Hello world", " again and again tail" and
+/// "more words here", which doesn't fit after "tail".
+const DOCUMENT: &str = "<html><body style=\"margin: 0\">
+    <p id=\"para\" style=\"margin: 0; width: 200px\">Hello <b id=\"world\">world</b> again and again <span id=\"tail\" style=\"margin-left: 6px\">tail</span><span id=\"more\">more words here</span></p>
+</body></html>";
+
+/// The text node whose content is exactly `text`.
+///
+/// # Panics
+///
+/// Panics if there is none.
+fn text_node(page: &Page<'_>, text: &str) -> NodeId {
+    (0..page.styler.styled_node_count())
+        .map(|index| NodeId(index as u32))
+        .find(|&node| page.tree.text_content(node).as_deref() == Some(text))
+        .unwrap_or_else(|| panic!("no text node {text:?}"))
+}
+
+#[test]
+fn inline_content_is_placed_on_line_boxes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let (_, hello_y, hello_width, line) = node_geometry(&renderer, text_node(&page, "Hello "));
+    let (world_x, world_y, _, _) = geometry(&page, &renderer, "world");
+    assert_eq!((world_x, world_y), (hello_width, hello_y));
+
+    let (again_x, again_y, again_width, _) =
+        node_geometry(&renderer, text_node(&page, " again and again "));
+    let (tail_x, tail_y, _, _) = geometry(&page, &renderer, "tail");
+    assert_eq!((again_x, again_y), (0, hello_y + line), "wraps to line 2");
+    assert_eq!(
+        (tail_x, tail_y),
+        (again_width + 6, again_y),
+        "follows the text on line 2"
+    );
+
+    let (more_x, more_y, _, _) = geometry(&page, &renderer, "more");
+    assert_eq!((more_x, more_y), (0, hello_y + 2 * line), "wraps to line 3");
+    let (_, _, _, para_height) = geometry(&page, &renderer, "para");
+    assert_eq!(para_height, 3 * line);
+}
+
+#[test]
+fn lines_rewrap_when_the_container_resizes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+    let para = element_by_id(&page, "para");
+
+    // Widening the paragraph rewraps it into two lines.
+    renderer.reset_resolve_stats();
+    page.set_attribute(para, "style", Some("margin: 0; width: 300px"));
+    assert!(renderer.resolve_stats().line_boxes >= 2);
+    let (_, _, _, line) = geometry(&page, &renderer, "world");
+    let (_, _, _, para_height) = node_geometry(&renderer, para);
+    assert_eq!(para_height, 2 * line);
+    let (more_x, more_y, _, _) = geometry(&page, &renderer, "more");
+    assert_eq!((more_x, more_y), (0, line));
+}
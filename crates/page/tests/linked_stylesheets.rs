@@ -0,0 +1,82 @@
+//! `<link rel="stylesheet">` tests - checks that linked sheets are fetched
+//! through the page's resource resolver and cascade in document order with
+//! `<style>` sheets, and that unavailable or alternate sheets are skipped.
+
+mod common;
+
+use common::{element_by_id, load, whole_px};
+use rewrite_page::{Browser, ResourceResolver};
+use std::sync::Arc;
+
+fn resolver() -> ResourceResolver {
+    Arc::new(|url| {
+        let css = match url {
+            "wide.css" => "#box { width: 50px; height: 5px }",
+            "narrow.css" => "#box { width: 20px }",
+            _ => return None,
+        };
+        Some(css.to_owned())
+    })
+}
+
+/// `(width, height)` of `#box` in `html` loaded with `resolver`, in whole
+/// pixels.
+fn box_size(html: &str, resolver: Option<ResourceResolver>) -> (i32, i32) {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    let page = match resolver {
+        Some(resolver) => page.with_resource_resolver(resolver),
+        None => page,
+    };
+    load(&page, &renderer, html);
+    let computed = renderer.computed_box(element_by_id(&page, "box"));
+    (whole_px(computed.width), whole_px(computed.height))
+}
+
+fn document(head: &str) -> String {
+    format!(
+        "<html><head><style>body {{ margin: 0 }}</style>{head}</head>\
+         <body><div id=\"box\"></div></body></html>"
+    )
+}
+
+#[test]
+fn linked_sheets_cascade_in_document_order_with_style_sheets() {
+    let link_first = document(
+        "<link rel=\"stylesheet\" href=\"wide.css\">
+         <style>#box { width: 30px }</style>",
+    );
+    assert_eq!(box_size(&link_first, Some(resolver())), (30, 5));
+
+    let style_first = document(
+        "<style>#box { width: 30px }</style>
+         <link rel=\"stylesheet\" href=\"wide.css\">",
+    );
+    assert_eq!(box_size(&style_first, Some(resolver())), (50, 5));
+
+    let two_links = document(
+        "<link rel=\"stylesheet\" href=\"wide.css\">
+         <link rel=\"Stylesheet\" href=\"narrow.css\">",
+    );
+    assert_eq!(box_size(&two_links, Some(resolver())), (20, 5));
+}
+
+#[test]
+fn unavailable_and_alternate_sheets_are_skipped() {
+    let missing = document(
+        "<style>#box { width: 30px; height: 3px }</style>
+         <link rel=\"stylesheet\" href=\"missing.css\">",
+    );
+    assert_eq!(box_size(&missing, Some(resolver())), (30, 3));
+
+    let nothing: ResourceResolver = Arc::new(|_| None);
+    let linked = document(
+        "<link rel=\"stylesheet\" href=\"wide.css\">
+         <style>#box { height: 3px }</style>",
+    );
+    assert_eq!(box_size(&linked, Some(nothing)), (800, 3));
+    assert_eq!(box_size(&linked, None), (800, 3));
+
+    let alternate = document("<link rel=\"alternate stylesheet\" href=\"wide.css\">");
+    assert_eq!(box_size(&alternate, Some(resolver())), (800, 0));
+}
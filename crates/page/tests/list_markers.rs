@@ -0,0 +1,152 @@
+//! List marker tests - checks that `display: list-item` elements get a
+//! marker box, that outside markers sit in the marker area before the
+//! content box, that inside markers push the first line along, that
+//! markers are painted, and that the `list-item` counter numbers every
+//! list item and follows the counter properties and list attributes.
+
+mod common;
+
+use common::{element_by_id, load, open};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{BoxArea, DisplayItem, Renderer};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    ul { margin: 0; padding-left: 40px }
+    #inside { list-style: inside }
+    #block { display: block }
+    #div-item { display: list-item }
+</style></head><body>
+<ul>
+  <li id=\"outside\">Outside</li>
+  <li id=\"inside\">Inside</li>
+  <li id=\"block\">Block</li>
+</ul>
+<div id=\"div-item\">Div</div>
+</body></html>";
+
+/// The x of the text node inside the element `id`.
+fn text_x(page: &Page<'_>, renderer: &Renderer, id: &str) -> f32 {
+    let element = element_by_id(page, id);
+    let text = page
+        .tree
+        .children(element)
+        .find(|&child| page.tree.text_content(child).is_some())
+        .unwrap_or_else(|| panic!("#{id} has no text"));
+    renderer
+        .computed_box(text)
+        .x
+        .unwrap_or_else(|| panic!("text of #{id} is laid out"))
+        .to_f32()
+}
+
+#[test]
+fn outside_marker_ends_at_the_content_box() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let item = element_by_id(&page, "outside");
+    let marker = renderer.marker_box(item).expect("#outside has a marker");
+    let content = renderer
+        .used_values(item)
+        .expect("#outside is laid out")
+        .rect(BoxArea::Content);
+    assert!(marker.rect.width.to_f32() > 0.0);
+    assert_eq!(marker.rect.x + marker.rect.width, content.x);
+    assert_eq!(marker.rect.y, content.y);
+    assert_eq!(text_x(&page, &renderer, "outside"), content.x.to_f32());
+}
+
+#[test]
+fn inside_marker_pushes_the_first_line_along() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let item = element_by_id(&page, "inside");
+    let marker = renderer.marker_box(item).expect("#inside has a marker");
+    let content = renderer
+        .used_values(item)
+        .expect("#inside is laid out")
+        .rect(BoxArea::Content);
+    assert_eq!(marker.rect.x, content.x);
+    let indent = text_x(&page, &renderer, "inside") - content.x.to_f32();
+    assert!(
+        (indent - marker.rect.width.to_f32()).abs() < 0.5,
+        "text is pushed by the marker width: {indent} vs {:?}",
+        marker.rect.width
+    );
+}
+
+#[test]
+fn only_list_items_have_markers() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    assert!(renderer.marker_box(element_by_id(&page, "block")).is_none());
+    let div_item = renderer
+        .marker_box(element_by_id(&page, "div-item"))
+        .expect("a display: list-item div has a marker");
+    assert_eq!(div_item.text, "• ");
+}
+
+#[test]
+fn markers_are_painted() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let item = element_by_id(&page, "outside");
+    let marker = renderer.marker_box(item).expect("#outside has a marker");
+    let painted = renderer.display_list().items.into_iter().any(|entry| {
+        matches!(
+            entry,
+            DisplayItem::Text { node, rect, ref text, .. }
+                if node == item && rect == marker.rect && *text == marker.text
+        )
+    });
+    assert!(painted, "the marker of #outside is in the display list");
+}
+
+#[test]
+fn list_item_counter_numbers_every_list_item() {
+    const NUMBERED: &str = "<html><head><style>
+        div, li { list-style-type: decimal }
+        div { display: list-item }
+    </style></head><body>
+    <div id=\"first-div\">A</div>
+    <div id=\"second-div\">B</div>
+    <ol>
+      <li id=\"one\">One</li>
+      <li id=\"jump\" style=\"counter-increment: list-item 5\">Six</li>
+    </ol>
+    <ol style=\"counter-reset: list-item 10\"><li id=\"reset\">Eleven</li></ol>
+    <ol reversed><li id=\"down-two\">Two</li><li id=\"down-one\">One</li></ol>
+    <ol start=\"5\">
+      <li id=\"start\">Five</li><li id=\"value\" value=\"9\">Nine</li><li id=\"next\">Ten</li>
+    </ol>
+    </body></html>";
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, NUMBERED);
+
+    let expected = [
+        ("first-div", "1. "),
+        ("second-div", "2. "),
+        ("one", "1. "),
+        ("jump", "6. "),
+        ("reset", "11. "),
+        ("down-two", "2. "),
+        ("down-one", "1. "),
+        ("start", "5. "),
+        ("value", "9. "),
+        ("next", "10. "),
+    ];
+    for (id, text) in expected {
+        let marker = renderer
+            .marker_box(element_by_id(&page, id))
+            .unwrap_or_else(|| panic!("#{id} has a marker"));
+        assert_eq!(marker.text, text, "marker of #{id}");
+    }
+}
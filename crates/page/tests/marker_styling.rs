@@ -0,0 +1,101 @@
+//! `::marker` tests - checks nested `list-item` counter scoping in
+//! `ol > li > ol` structures and that `::marker` rules style and override
+//! the marker text.
+
+mod common;
+
+use common::element_by_id;
+use rewrite_css::{Property, PropertyId};
+use rewrite_page::{Browser, Page};
+
+const DOCUMENT: &str = "<html><head><style>
+    #legal, #legal ol { list-style-type: decimal }
+    #legal li::marker { content: counters(list-item, \".\") \" \"; color: red; margin-left: 4px }
+    #roman li::marker { font-size: 20px }
+    #dashes li::marker { content: \"-\" }
+    #plain > li:last-child::marker { content: none }
+</style></head><body>
+    <ol id=\"legal\">
+        <li id=\"l1\">One
+            <ol>
+                <li id=\"l1-1\">One.one</li>
+                <li id=\"l1-2\">One.two
+                    <ol><li id=\"l1-2-1\">Deep</li></ol>
+                </li>
+            </ol>
+        </li>
+        <li id=\"l2\">Two</li>
+    </ol>
+    <ol id=\"roman\" start=\"4\" style=\"list-style-type: upper-roman\">
+        <li id=\"r4\">Four</li>
+        <li id=\"r9\" value=\"9\">Nine</li>
+        <li id=\"r10\">Ten</li>
+    </ol>
+    <ol id=\"countdown\" reversed><li id=\"c2\">a</li><li id=\"c1\">b</li></ol>
+    <ul id=\"plain\">
+        <li id=\"disc\">Disc<ul><li id=\"circle\">Circle</li></ul></li>
+        <li id=\"hidden\">Hidden</li>
+    </ul>
+    <ul id=\"dashes\"><li id=\"dash\">Dash</li></ul>
+</body></html>";
+
+fn load(browser: &Browser) -> Page<'_> {
+    let page = browser.new_page_headless();
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+    page
+}
+
+fn marker_text(page: &Page<'_>, id: &str) -> Option<String> {
+    let node = element_by_id(page, id);
+    page.styler.marker(node).map(|marker| marker.text)
+}
+
+#[test]
+fn nested_lists_scope_their_counters() {
+    let browser = Browser::default();
+    let page = load(&browser);
+    let expected = [
+        ("l1", "1 "),
+        ("l1-1", "1.1 "),
+        ("l1-2", "1.2 "),
+        ("l1-2-1", "1.2.1 "),
+        ("l2", "2 "),
+        ("r4", "IV. "),
+        ("r9", "IX. "),
+        ("r10", "X. "),
+        ("c2", "2. "),
+        ("c1", "1. "),
+        ("disc", "\u{2022} "),
+        ("circle", "\u{25e6} "),
+        ("dash", "-"),
+    ];
+    for (id, text) in expected {
+        assert_eq!(marker_text(&page, id).as_deref(), Some(text), "#{id}");
+    }
+    assert_eq!(marker_text(&page, "hidden"), None);
+    let legal = element_by_id(&page, "legal");
+    assert_eq!(
+        page.styler.marker(legal),
+        None,
+        "only list items have markers"
+    );
+}
+
+#[test]
+fn marker_rules_style_the_marker_box() {
+    let browser = Browser::default();
+    let page = load(&browser);
+    let marker = page.styler.marker(element_by_id(&page, "l1-1")).unwrap();
+    assert!(matches!(
+        marker.property(&PropertyId::Color),
+        Some(Property::Color(_))
+    ));
+    // Box properties don't apply to markers.
+    assert_eq!(marker.property(&PropertyId::MarginLeft), None);
+
+    let roman = page.styler.marker(element_by_id(&page, "r4")).unwrap();
+    assert!(roman.property(&PropertyId::FontSize).is_some());
+    assert_eq!(roman.property(&PropertyId::Color), None);
+}
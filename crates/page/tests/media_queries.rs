@@ -0,0 +1,56 @@
+//! Media query tests - checks that `@media` rules with viewport features
+//! follow the renderer's viewport as it is resized.
+
+mod common;
+
+use common::element_by_id;
+use rewrite_core::Subpixel;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #box { height: 10px }
+    @media (max-width: 600px) { #box { width: 100px } }
+    @media (min-width: 601px) and (orientation: portrait) { #box { width: 200px } }
+</style></head><body>
+    <div id=\"box\"></div>
+</body></html>";
+
+fn box_width(page: &Page<'_>, renderer: &Renderer) -> i32 {
+    let node = element_by_id(page, "box");
+    let width: Option<Subpixel> = renderer.computed_box(node).width;
+    width.unwrap_or_default().to_f32().round() as i32
+}
+
+#[test]
+fn viewport_features_follow_resizes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(500, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+    assert_eq!(box_width(&page, &renderer), 100);
+
+    renderer.set_viewport(800, 600);
+    assert_eq!(box_width(&page, &renderer), 800);
+
+    renderer.set_viewport(700, 900);
+    assert_eq!(box_width(&page, &renderer), 200);
+
+    renderer.set_viewport(400, 900);
+    assert_eq!(box_width(&page, &renderer), 100);
+}
+
+#[test]
+fn viewport_carries_over_to_the_next_document() {
+    let browser = Browser::default();
+    let (mut page, renderer) = browser.new_page();
+    renderer.set_viewport(500, 600);
+    page.replace_document(Some(&renderer));
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+    assert_eq!(box_width(&page, &renderer), 100);
+}
@@ -0,0 +1,64 @@
+//! Mirror audit tests - checks that the Styler, sparse trees and layout
+//! stay consistent with the DOM through dynamic changes, and that the
+//! audit reports rule matches the Styler missed.
+
+mod common;
+
+use common::{element_by_id, open};
+use rewrite_html::NodeData;
+use rewrite_page::{Browser, MirrorDivergence};
+
+const DOCUMENT: &str = "<html><head><style>
+    .on { color: red }
+    .on + p { margin-left: 4px }
+    p:last-child { font-size: 20px }
+    div > .on em { font-weight: bold }
+</style></head><body>
+    <div id=\"outer\">
+        <p id=\"first\">First <em>emphasis</em></p>
+        <p id=\"second\">Second</p>
+    </div>
+</body></html>";
+
+#[test]
+fn dynamic_changes_keep_mirrors_consistent() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    assert_eq!(page.audit_mirrors(Some(&renderer)), Ok(()));
+
+    let first = element_by_id(&page, "first");
+    page.set_attribute(first, "class", Some("on"));
+    page.set_attribute(element_by_id(&page, "second"), "style", Some("width: 50px"));
+    assert_eq!(page.audit_mirrors(Some(&renderer)), Ok(()));
+
+    page.set_attribute(first, "class", None);
+    page.set_user_stylesheet("p { padding: 1px }");
+    assert_eq!(page.audit_mirrors(Some(&renderer)), Ok(()));
+}
+
+#[test]
+fn missed_attribute_change_is_reported() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    let first = element_by_id(&page, "first");
+    let class = page.tree.interner.get_or_intern("class");
+
+    // Change the attribute without notifying observers, as a lost update
+    // would.
+    let Some(NodeData::Element { attributes, .. }) = page.tree.get_node(first) else {
+        panic!("#first is not an element");
+    };
+    attributes.set(class, Some("on".into()));
+
+    let Err(MirrorDivergence::MatchedRules {
+        node,
+        expected,
+        actual,
+    }) = page.audit_mirrors(Some(&renderer))
+    else {
+        panic!("the missed `.on` match went unreported");
+    };
+    assert_eq!(node, first);
+    assert_eq!(expected.len(), actual.len() + 1);
+    assert!(actual.iter().all(|rule| expected.contains(rule)));
+}
@@ -0,0 +1,94 @@
+//! Node path tests - checks that nodes are named by their place in the
+//! DOM, with ids and sibling positions, and that diagnostics can name
+//! nodes that way instead of by ID.
+
+mod common;
+
+use common::open;
+use rewrite_core::{NodeId, ResolveDiagnostic, ResolveFailure, ResolveFrame};
+use rewrite_html::NodeData;
+use rewrite_page::{Browser, MirrorDivergence, Page};
+
+const DOCUMENT: &str = "<html><body>\
+    <div id=\"app\"><h1>Title</h1><p>first</p><p id=\"\">second</p></div>\
+    <section>text <em>only child</em><!-- note --></section>\
+</body></html>";
+
+fn load(browser: &Browser) -> Page<'_> {
+    open(browser, DOCUMENT).0
+}
+
+/// Every node with its path, in tree order.
+fn paths(page: &Page<'_>) -> Vec<String> {
+    (0..page.styler.styled_node_count())
+        .map(|index| NodeId(index as u32))
+        .filter(|&node| !matches!(page.tree.get_node(node), Some(NodeData::Document)))
+        .map(|node| page.tree.node_path(node))
+        .collect()
+}
+
+fn node_with_path(page: &Page<'_>, path: &str) -> NodeId {
+    (0..page.styler.styled_node_count())
+        .map(|index| NodeId(index as u32))
+        .find(|&node| page.tree.node_path(node) == path)
+        .unwrap_or_else(|| panic!("no node at {path}"))
+}
+
+#[test]
+fn paths_name_ids_and_sibling_positions() {
+    let browser = Browser::default();
+    let page = load(&browser);
+    let paths = paths(&page);
+    for path in [
+        "html",
+        "html > head",
+        "html > body",
+        "html > body > div#app",
+        "html > body > div#app > h1 > #text",
+        "html > body > div#app > p:nth-child(2)",
+        "html > body > div#app > p:nth-child(3)",
+        "html > body > section > #text",
+        "html > body > section > em",
+        "html > body > section > #comment",
+    ] {
+        assert!(
+            paths.iter().any(|found| found == path),
+            "{path} in {paths:#?}"
+        );
+    }
+}
+
+#[test]
+fn diagnostics_name_nodes_by_path() {
+    let browser = Browser::default();
+    let page = load(&browser);
+    let app = node_with_path(&page, "html > body > div#app");
+    let path = |node| page.tree.node_path(node);
+
+    let divergence = MirrorDivergence::LayoutMissingNode { node: app };
+    assert_eq!(
+        divergence.describe(&path),
+        "layout never saw attached node html > body > div#app"
+    );
+    assert_eq!(
+        divergence.to_string(),
+        format!("layout never saw attached node {}", app.0)
+    );
+
+    let paragraph = node_with_path(&page, "html > body > div#app > p:nth-child(2)");
+    let diagnostic = ResolveDiagnostic {
+        failure: ResolveFailure::Cycle,
+        path: [app, paragraph, app]
+            .map(|node| ResolveFrame {
+                node,
+                formula: 0x10,
+            })
+            .to_vec(),
+    };
+    assert_eq!(
+        diagnostic.describe(&path),
+        "dependency cycle: node html > body > div#app (formula 0x10) \
+         -> node html > body > div#app > p:nth-child(2) (formula 0x10) \
+         -> node html > body > div#app (formula 0x10)"
+    );
+}
@@ -0,0 +1,119 @@
+//! Paint info tests - checks that background, border radii, outline,
+//! opacity and transform are resolved against the laid-out box, and that
+//! caret and accent colors follow author theming.
+
+mod common;
+
+use common::find_element_by_id;
+use lightningcss::values::color::RGBA;
+use rewrite_core::Subpixel;
+use rewrite_css::ComputedColor;
+use rewrite_layout::Rect;
+use rewrite_page::Browser;
+
+/// A 200x100 box at (10, 0). The selector is specific enough to apply
+/// while streaming, before the cascade is flushed.
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div[id] {
+        margin: 0 10px;
+        width: 200px;
+        height: 100px;
+        background-color: #ff0000;
+        border-radius: 10px 50%;
+        outline: 2px solid blue;
+        outline-offset: 3px;
+        opacity: 0.5;
+        transform: translate(50%, 10px);
+        transform-origin: left top;
+    }
+</style></head><body><div id=\"box\"></div></body></html>";
+
+#[test]
+fn paint_info_resolves_against_the_box() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let node = find_element_by_id(&page, "box");
+    let info = node.and_then(|node| renderer.paint_info(node));
+    let Some(info) = info else {
+        panic!("box has no paint info");
+    };
+    let px = Subpixel::from_px;
+
+    assert_eq!(info.border_box, Rect::new(px(10), px(0), px(200), px(100)));
+    assert_eq!(
+        info.background_color,
+        ComputedColor::Srgb(RGBA::new(255, 0, 0, 1.0))
+    );
+    assert!((info.opacity - 0.5).abs() < f32::EPSILON);
+
+    // `10px 50%`: the top-right corner is 50% of 200x100. No side's radii
+    // add up to more than the side, so none are scaled down.
+    assert_eq!(info.radii.top_left.horizontal, px(10));
+    assert_eq!(info.radii.top_right.horizontal, px(100));
+    assert_eq!(info.radii.top_right.vertical, px(50));
+
+    let outline = info.outline.as_ref().map(|outline| outline.rect);
+    assert_eq!(outline, Some(Rect::new(px(5), px(-5), px(210), px(110))));
+
+    // translate(50%, 10px) about the top-left corner moves the box by
+    // (100, 10).
+    let matrix = info.transform.map(|matrix| (matrix.m41, matrix.m42));
+    assert_eq!(matrix, Some((100.0, 10.0)));
+}
+
+#[test]
+fn caret_and_accent_colors_follow_author_theming() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(
+        "<html><head><style>
+            form { color: #00ff00; caret-color: #ff0000; accent-color: #0000ff }
+            #auto { caret-color: auto; color: #808080 }
+        </style></head><body><form>
+            <div id=\"themed\" contenteditable>A</div><div id=\"auto\" contenteditable>B</div>
+        </form><p id=\"plain\">Text</p></body></html>",
+    );
+    document.finish();
+
+    let info = |id| {
+        find_element_by_id(&page, id)
+            .and_then(|node| renderer.paint_info(node))
+            .unwrap_or_else(|| panic!("#{id} has no paint info"))
+    };
+    let srgb = |red, green, blue| ComputedColor::Srgb(RGBA::new(red, green, blue, 1.0));
+
+    let themed = info("themed");
+    assert_eq!(themed.caret_color, srgb(255, 0, 0), "caret-color inherits");
+    assert_eq!(
+        themed.accent_color,
+        srgb(0, 0, 255),
+        "accent-color inherits"
+    );
+
+    let auto = info("auto");
+    assert_eq!(
+        auto.caret_color,
+        srgb(128, 128, 128),
+        "auto is currentColor"
+    );
+
+    let plain = info("plain");
+    assert_eq!(
+        plain.caret_color,
+        srgb(0, 0, 0),
+        "the initial value is auto"
+    );
+    assert_eq!(
+        plain.accent_color,
+        srgb(0, 120, 215),
+        "auto accent is the platform accent"
+    );
+}
@@ -0,0 +1,55 @@
+//! Paint order tests - checks that floats paint between in-flow boxes and
+//! text (CSS 2.2 Appendix E), and that positioned boxes inside a float are
+//! stacked in the enclosing context.
+
+mod common;
+
+use common::element_by_id;
+use rewrite_core::NodeId;
+use rewrite_page::Browser;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #float { float: left; width: 100px; height: 100px }
+    #inner { height: 10px }
+    #pinned { position: relative }
+</style></head><body>
+<div id=\"float\"><div id=\"inner\"></div><div id=\"pinned\">P</div>F</div>
+<div id=\"block\">Text</div>
+</body></html>";
+
+#[test]
+fn floats_paint_between_blocks_and_text() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let order = renderer.paint_order();
+    let at = |node: NodeId| {
+        order
+            .iter()
+            .position(|&painted| painted == node)
+            .unwrap_or_else(|| panic!("{node:?} isn't painted"))
+    };
+    let [float, inner, pinned, block] =
+        ["float", "inner", "pinned", "block"].map(|id| element_by_id(&page, id));
+    let text_of = |node| {
+        page.tree
+            .children(node)
+            .next()
+            .unwrap_or_else(|| panic!("{node:?} has no text"))
+    };
+
+    assert!(at(block) < at(float), "in-flow blocks paint under floats");
+    assert!(
+        at(float) < at(inner) && at(inner) < at(text_of(block)),
+        "a float paints its content as a unit"
+    );
+    assert!(
+        at(text_of(block)) < at(pinned),
+        "positioned boxes in a float are stacked in the enclosing context"
+    );
+}
@@ -0,0 +1,84 @@
+//! Absolute and fixed positioning tests - checks that out-of-flow boxes
+//! leave normal flow and are placed and sized against their containing
+//! block by their insets (CSS 2.2 §10.3.7 / §10.6.4).
+
+mod common;
+
+use common::{find_element_by_id, geometry};
+use rewrite_page::Browser;
+
+const DOCUMENT: &str = "<html><body style=\"margin: 0\">
+    <div id=\"frame\" style=\"position: relative; margin: 0 10px; box-sizing: border-box; width: 300px; height: 200px; border: 2px solid; padding: 8px\">
+        <div id=\"first\" style=\"height: 20px\"></div>
+        <div id=\"corner\" style=\"position: absolute; left: 5px; top: 6px; width: 30px; height: 30px; margin-left: 4px\"></div>
+        <div id=\"static\" style=\"position: absolute; width: 10px; height: 10px\"></div>
+        <div id=\"second\" style=\"height: 20px\"></div>
+        <div id=\"anchored\" style=\"position: absolute; right: 10px; bottom: 20%; width: 40px; height: 20px\"></div>
+        <div id=\"stretched\" style=\"position: absolute; left: 10px; right: 20px; top: 0; bottom: 50px\"></div>
+        <div id=\"centered\" style=\"position: absolute; left: 0; right: 0; top: 0; bottom: 0; width: 100px; height: 50px; margin: auto\"></div>
+        <div>
+            <div id=\"nested\" style=\"position: absolute; left: 1px; top: 2px; width: 5px; height: 5px\"></div>
+        </div>
+        <div id=\"shrunk\" style=\"position: absolute; left: 0; top: 0\">Hi</div>
+    </div>
+    <div id=\"pinned\" style=\"position: fixed; right: 0; bottom: 0; width: 50px; height: 25px\"></div>
+    <div style=\"transform: translateX(0); margin-left: 40px; height: 10px\">
+        <div id=\"trapped\" style=\"position: fixed; left: 3px; top: 4px; width: 5px; height: 5px\"></div>
+    </div>
+</body></html>";
+
+#[test]
+fn positioned_boxes_are_placed_against_their_containing_block() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    // `#frame`'s padding box starts at (12, 2) and is 296 by 196.
+    let expected = [
+        ("corner", (21, 8, 30, 30)),
+        ("static", (20, 30, 10, 10)),
+        ("second", (20, 30, 280, 20)),
+        ("anchored", (258, 139, 40, 20)),
+        ("stretched", (22, 2, 266, 146)),
+        ("centered", (110, 75, 100, 50)),
+        ("nested", (13, 4, 5, 5)),
+        ("pinned", (750, 575, 50, 25)),
+        ("trapped", (43, 204, 5, 5)),
+    ];
+    for (id, rect) in expected {
+        assert_eq!(geometry(&page, &renderer, id), rect, "#{id}");
+    }
+
+    let (_, _, shrunk_width, _) = geometry(&page, &renderer, "shrunk");
+    assert!(
+        shrunk_width > 0 && shrunk_width < 50,
+        "auto width shrinks to the text, got {shrunk_width}"
+    );
+}
+
+#[test]
+fn insets_follow_style_changes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    if let Some(corner) = find_element_by_id(&page, "corner") {
+        page.set_attribute(
+            corner,
+            "style",
+            Some("position: absolute; right: 0; top: 50%; width: 30px; height: 30px"),
+        );
+    }
+    assert_eq!(geometry(&page, &renderer, "corner"), (278, 100, 30, 30));
+    assert_eq!(
+        geometry(&page, &renderer, "second").1,
+        30,
+        "out-of-flow boxes don't move their siblings"
+    );
+}
@@ -0,0 +1,354 @@
+//! Property round-trip tests - for every property in the registry, checks
+//! that example values parse into computed values, and that serializing
+//! those and declaring them again gives the same computed values, so a
+//! value the engine prints is one it reads back. Shorthands are checked
+//! through the longhands they set.
+
+mod common;
+
+use common::element_by_id;
+use lightningcss::printer::PrinterOptions;
+use lightningcss::properties::PropertyId;
+use rewrite_core::{NodeId, PROPERTY_NAMES};
+use rewrite_page::{Browser, HtmlPage};
+use std::collections::BTreeMap;
+
+/// Example values for each property in `PROPERTY_NAMES`, in registry
+/// order. Each differs from what an unstyled `div` computes, so applying
+/// it is observable.
+const EXAMPLES: &[(&str, &[&str])] = &[
+    // ── Text ──
+    (
+        "font-family",
+        &["Georgia, serif", "\"Courier New\", monospace"],
+    ),
+    ("font-size", &["20px", "1.5em", "larger"]),
+    ("font-weight", &["bold", "300"]),
+    ("font-style", &["italic", "oblique 10deg"]),
+    ("font-variant-caps", &["small-caps"]),
+    ("font", &["italic bold 12px/30px Georgia, serif"]),
+    ("line-height", &["1.5", "20px"]),
+    ("letter-spacing", &["2px"]),
+    ("word-spacing", &["0.5em"]),
+    ("color", &["red", "rgb(0 128 255 / 50%)"]),
+    ("text-align", &["center", "right"]),
+    ("text-indent", &["2em", "10%"]),
+    ("white-space", &["pre-wrap", "nowrap"]),
+    ("text-transform", &["uppercase"]),
+    ("direction", &["rtl"]),
+    ("print-color-adjust", &["exact"]),
+    ("visibility", &["hidden"]),
+    ("caret-color", &["blue"]),
+    ("accent-color", &["green"]),
+    ("cursor", &["pointer", "move"]),
+    ("fill", &["red", "none"]),
+    ("fill-rule", &["evenodd"]),
+    ("fill-opacity", &["0.5"]),
+    ("stroke", &["blue"]),
+    ("stroke-opacity", &["0.25"]),
+    ("stroke-width", &["2px"]),
+    ("stroke-linecap", &["round"]),
+    ("stroke-linejoin", &["bevel"]),
+    ("stroke-miterlimit", &["8"]),
+    ("stroke-dasharray", &["4 2"]),
+    ("stroke-dashoffset", &["3px"]),
+    ("clip-rule", &["evenodd"]),
+    ("color-interpolation", &["linearRGB"]),
+    ("shape-rendering", &["crispEdges"]),
+    ("marker-start", &["url(#marker)"]),
+    ("marker-mid", &["url(#marker)"]),
+    ("marker-end", &["url(#marker)"]),
+    ("pointer-events", &["none"]),
+    ("text-wrap", &["balance", "pretty"]),
+    ("border-spacing", &["2px", "1px 3px"]),
+    // ── Background / visual ──
+    ("background-color", &["red"]),
+    ("background", &["red", "url(a.png) no-repeat"]),
+    (
+        "background-image",
+        &["url(a.png)", "linear-gradient(red, blue)"],
+    ),
+    ("background-position", &["center top", "10px 20%"]),
+    ("background-position-x", &["right 10px"]),
+    ("background-position-y", &["50%"]),
+    ("background-size", &["cover", "50% auto"]),
+    ("background-repeat", &["no-repeat", "repeat-x"]),
+    ("border-top-color", &["red"]),
+    ("border-right-color", &["red"]),
+    ("border-bottom-color", &["red"]),
+    ("border-left-color", &["red"]),
+    ("border-color", &["red green"]),
+    ("border-top-style", &["solid"]),
+    ("border-right-style", &["dashed"]),
+    ("border-bottom-style", &["dotted"]),
+    ("border-left-style", &["double"]),
+    ("border-style", &["solid dotted"]),
+    ("border-top-left-radius", &["4px", "4px 8px"]),
+    ("border-top-right-radius", &["50%"]),
+    ("border-bottom-left-radius", &["4px"]),
+    ("border-bottom-right-radius", &["4px"]),
+    ("border-radius", &["4px 8px", "10px / 20px"]),
+    ("box-shadow", &["2px 2px 4px red", "inset 0 0 2px blue"]),
+    ("opacity", &["0.5"]),
+    ("outline-color", &["red"]),
+    ("outline-style", &["dashed"]),
+    ("outline-width", &["3px", "thick"]),
+    ("outline-offset", &["2px"]),
+    ("overflow-anchor", &["none"]),
+    // ── Box model ──
+    ("width", &["100px", "50%", "calc(100% - 10px)"]),
+    ("height", &["20px", "2em"]),
+    ("min-width", &["10px"]),
+    ("min-height", &["10%"]),
+    ("max-width", &["100px"]),
+    ("max-height", &["50%"]),
+    ("margin", &["1px 2px 3px 4px", "auto"]),
+    ("margin-top", &["5px"]),
+    ("margin-right", &["auto"]),
+    ("margin-bottom", &["10%"]),
+    ("margin-left", &["-5px"]),
+    ("margin-block", &["1px 2px"]),
+    ("margin-block-start", &["3px"]),
+    ("margin-block-end", &["3px"]),
+    ("margin-inline", &["1px 2px"]),
+    ("margin-inline-start", &["3px"]),
+    ("margin-inline-end", &["auto"]),
+    ("padding", &["1px 2px 3px 4px"]),
+    ("padding-top", &["5px"]),
+    ("padding-right", &["1em"]),
+    ("padding-bottom", &["10%"]),
+    ("padding-left", &["5px"]),
+    ("padding-block", &["1px 2px"]),
+    ("padding-block-start", &["3px"]),
+    ("padding-block-end", &["3px"]),
+    ("padding-inline", &["1px 2px"]),
+    ("padding-inline-start", &["3px"]),
+    ("padding-inline-end", &["3px"]),
+    ("border-top-width", &["2px"]),
+    ("border-right-width", &["thin"]),
+    ("border-bottom-width", &["thick"]),
+    ("border-left-width", &["1em"]),
+    ("border-width", &["1px 2px"]),
+    ("box-sizing", &["border-box"]),
+    // ── Layout mode ──
+    (
+        "display",
+        &["flex", "inline-block", "grid", "none", "list-item"],
+    ),
+    ("flex-direction", &["column", "row-reverse"]),
+    ("flex-wrap", &["wrap"]),
+    ("flex-flow", &["column wrap"]),
+    ("flex-grow", &["2"]),
+    ("flex-shrink", &["0"]),
+    ("flex-basis", &["100px", "content"]),
+    ("flex", &["1", "2 0 10px", "none"]),
+    ("justify-content", &["center", "space-between"]),
+    ("align-items", &["center", "baseline"]),
+    ("align-self", &["flex-end"]),
+    ("align-content", &["space-around"]),
+    ("order", &["2", "-1"]),
+    ("grid-template-columns", &["100px 1fr", "repeat(2, 1fr)"]),
+    ("grid-template-rows", &["auto 20px"]),
+    ("grid-template-areas", &["\"a b\" \"c d\""]),
+    ("grid-auto-columns", &["100px"]),
+    ("grid-auto-rows", &["minmax(10px, auto)"]),
+    ("grid-auto-flow", &["column", "row dense"]),
+    ("grid-column", &["1 / 3", "span 2"]),
+    ("grid-row", &["2 / 4"]),
+    ("grid-column-start", &["2"]),
+    ("grid-column-end", &["3"]),
+    ("grid-row-start", &["1"]),
+    ("grid-row-end", &["span 2"]),
+    ("gap", &["10px 20px"]),
+    ("row-gap", &["10px"]),
+    ("column-gap", &["5%"]),
+    ("overflow", &["hidden", "hidden scroll"]),
+    ("overflow-x", &["scroll"]),
+    ("overflow-y", &["auto"]),
+    // ── Position ──
+    ("position", &["relative", "absolute", "sticky"]),
+    ("top", &["10px"]),
+    ("right", &["5%"]),
+    ("bottom", &["0"]),
+    ("left", &["-1em"]),
+    ("inset-block-start", &["10px"]),
+    ("inset-block-end", &["10px"]),
+    ("inset-inline-start", &["10px"]),
+    ("inset-inline-end", &["10px"]),
+    ("inset", &["1px 2px"]),
+    ("inset-block", &["1px 2px"]),
+    ("inset-inline", &["1px 2px"]),
+    ("z-index", &["3"]),
+    (
+        "transform",
+        &["translate(10px, 20px) rotate(45deg)", "scale(2)"],
+    ),
+    ("transform-origin", &["left top", "10px 20px"]),
+    ("perspective", &["100px"]),
+    ("filter", &["blur(2px)", "grayscale(50%)"]),
+    ("backdrop-filter", &["blur(2px)"]),
+    ("float", &["left", "right"]),
+];
+
+/// Declarations properties only compute as declared alongside: a border
+/// side with no style computes to zero width.
+const CONTEXT: &[(&str, &str)] = &[
+    ("border-top-width", "border-top-style: solid"),
+    ("border-right-width", "border-right-style: solid"),
+    ("border-bottom-width", "border-bottom-style: solid"),
+    ("border-left-width", "border-left-style: solid"),
+    ("border-width", "border-style: solid"),
+];
+
+/// A page with an unstyled `#reference` box and a box `#example-<index>`
+/// styled by each of `declarations`. They're all in a `display: none`
+/// box: they're cascaded like any other, and layout skips them.
+fn load<'browser>(browser: &'browser Browser, declarations: &[String]) -> HtmlPage<'browser> {
+    let boxes: String = declarations
+        .iter()
+        .enumerate()
+        .map(|(index, style)| {
+            let style = style.replace('"', "&quot;");
+            format!("<div id=\"example-{index}\" style=\"{style}\"></div>")
+        })
+        .collect();
+    let mut html_page = HtmlPage::new(browser, 800, 600);
+    html_page.push(
+        format!(
+            "<html><body><div style=\"display: none\">\
+             <div id=\"reference\"></div>{boxes}</div></body></html>"
+        )
+        .as_bytes(),
+    );
+    html_page.finish();
+    html_page.tick();
+    html_page
+}
+
+/// Every registered property, and the longhands registered shorthands
+/// are stored as.
+fn observed_properties() -> Vec<PropertyId<'static>> {
+    let mut observed: Vec<PropertyId<'static>> = Vec::new();
+    for &name in PROPERTY_NAMES {
+        let prop_id = PropertyId::from(name);
+        for prop_id in prop_id
+            .longhands()
+            .unwrap_or_default()
+            .into_iter()
+            .chain([prop_id])
+        {
+            if !observed.contains(&prop_id) {
+                observed.push(prop_id);
+            }
+        }
+    }
+    observed
+}
+
+/// The serialized computed values of a page's boxes.
+struct ComputedValues<'page> {
+    html_page: &'page HtmlPage<'page>,
+    observed: Vec<PropertyId<'static>>,
+    reference: Vec<Option<String>>,
+}
+
+impl<'page> ComputedValues<'page> {
+    fn new(html_page: &'page HtmlPage<'page>) -> Self {
+        let observed = observed_properties();
+        let reference = element_by_id(html_page.page(), "reference");
+        let reference = observed
+            .iter()
+            .map(|prop_id| serialize(html_page, reference, prop_id))
+            .collect();
+        Self {
+            html_page,
+            observed,
+            reference,
+        }
+    }
+
+    /// The values of `#id` that differ from `#reference`'s, by name.
+    fn styled(&self, id: &str) -> BTreeMap<String, String> {
+        let node = element_by_id(self.html_page.page(), id);
+        self.observed
+            .iter()
+            .zip(&self.reference)
+            .filter_map(|(prop_id, reference)| {
+                let value = serialize(self.html_page, node, prop_id)?;
+                (reference.as_ref() != Some(&value)).then(|| (prop_id.name().to_owned(), value))
+            })
+            .collect()
+    }
+}
+
+fn serialize(
+    html_page: &HtmlPage<'_>,
+    node: NodeId,
+    prop_id: &PropertyId<'static>,
+) -> Option<String> {
+    html_page
+        .computed_style(node, prop_id)
+        .and_then(|prop| prop.value_to_css_string(PrinterOptions::default()).ok())
+}
+
+/// `values` as the declarations of a `style` attribute.
+fn declarations(values: &BTreeMap<String, String>) -> String {
+    values
+        .iter()
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[test]
+fn every_registered_property_has_examples() {
+    let names: Vec<&str> = EXAMPLES.iter().map(|&(name, _)| name).collect();
+    assert_eq!(names, PROPERTY_NAMES);
+    for &(name, values) in EXAMPLES {
+        assert!(!values.is_empty(), "{name} has no examples");
+    }
+}
+
+#[test]
+fn example_values_round_trip() {
+    let examples: Vec<String> = EXAMPLES
+        .iter()
+        .flat_map(|&(name, values)| {
+            let context = CONTEXT
+                .iter()
+                .find(|&&(property, _)| property == name)
+                .map_or(String::new(), |&(_, context)| format!("{context}; "));
+            values
+                .iter()
+                .map(move |value| format!("{context}{name}: {value}"))
+        })
+        .collect();
+    let browser = Browser::default();
+    let parsed = load(&browser, &examples);
+    let parsed_values = ComputedValues::new(&parsed);
+    let computed: Vec<_> = (0..examples.len())
+        .map(|index| parsed_values.styled(&format!("example-{index}")))
+        .collect();
+
+    let reparse_browser = Browser::default();
+    let serialized: Vec<String> = computed.iter().map(declarations).collect();
+    let reparsed = load(&reparse_browser, &serialized);
+    let reparsed_values = ComputedValues::new(&reparsed);
+
+    let mut failures = Vec::new();
+    for (index, (example, values)) in examples.iter().zip(&computed).enumerate() {
+        if values.is_empty() {
+            failures.push(format!("`{example}` changed no computed value"));
+            continue;
+        }
+        let round_tripped = reparsed_values.styled(&format!("example-{index}"));
+        if &round_tripped != values {
+            failures.push(format!(
+                "`{example}` computed `{}`, which reads back as `{}`",
+                declarations(values),
+                declarations(&round_tripped)
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,107 @@
+//! Replay tests - checks that a recorded page rebuilds the same DOM and
+//! layout when its log is replayed, text form included, into a page with
+//! no resolver of its own.
+
+mod common;
+
+use common::{element_by_id, find_element_by_id, whole_px};
+use rewrite_core::NodeId;
+use rewrite_css::SheetId;
+use rewrite_page::{Browser, Page, ReplayError, ReplayLog, ResourceResolver};
+use rewrite_renderer::Renderer;
+use std::sync::Arc;
+use std::time::Duration;
+
+const HEAD: &str = "<html><head>
+    <link rel=\"stylesheet\" href=\"theme.css\">
+    <style>#first { height: 7px } .wide { width: 300px }</style>
+</head><body>";
+
+const BODY: &str = "<div id=\"first\"></div>
+    <div id=\"second\"><span>text</span></div>
+    <!-- comment -->
+</body></html>";
+
+fn resolver() -> ResourceResolver {
+    Arc::new(|url| (url == "theme.css").then(|| "#first { width: 50px }".to_owned()))
+}
+
+/// `(width, height)` of `#id`, in whole pixels.
+fn size(page: &Page<'_>, renderer: &Renderer, id: &str) -> (i32, i32) {
+    let node = element_by_id(page, id);
+    let computed = renderer.computed_box(node);
+    (whole_px(computed.width), whole_px(computed.height))
+}
+
+#[test]
+fn replayed_log_rebuilds_the_recorded_layout() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    let page = page.with_resource_resolver(resolver());
+    renderer.set_viewport(640, 480);
+    page.start_recording();
+
+    let mut document = page.begin_html();
+    document.push(HEAD);
+    document.push(BODY);
+    document.finish();
+    let second = element_by_id(&page, "second");
+    page.set_attribute(second, "class", Some("wide"));
+    page.parse_stylesheet(SheetId(1), "#second { height: 11px }".to_owned());
+    let applied = page.wait_for_parsed_stylesheets(Duration::from_secs(10));
+    assert_eq!(applied, 1, "stylesheet never parsed");
+    let log = page.stop_recording().unwrap();
+    assert_eq!(size(&page, &renderer, "first"), (50, 7));
+    assert_eq!(size(&page, &renderer, "second"), (300, 11));
+
+    let text = log.to_text();
+    assert_eq!(ReplayLog::from_text(&text), Ok(log.clone()));
+
+    let (mut replayed, replay_renderer) = browser.new_page();
+    replayed
+        .replay(
+            &ReplayLog::from_text(&text).unwrap(),
+            Some(&replay_renderer),
+        )
+        .unwrap();
+    assert_eq!(
+        replayed.styler.styled_node_count(),
+        page.styler.styled_node_count()
+    );
+    assert_eq!(replay_renderer.viewport_width(), 640);
+    for id in ["first", "second"] {
+        assert_eq!(
+            find_element_by_id(&replayed, id),
+            find_element_by_id(&page, id),
+            "#{id} moved"
+        );
+        assert_eq!(
+            size(&replayed, &replay_renderer, id),
+            size(&page, &renderer, id),
+            "#{id} laid out differently"
+        );
+    }
+}
+
+#[test]
+fn replay_reports_divergence_and_malformed_logs() {
+    let browser = Browser::default();
+    let (mut page, renderer) = browser.new_page();
+    let log = ReplayLog::from_text("replay-log 1\nnode 5 document\n").unwrap();
+    assert_eq!(
+        page.replay(&log, Some(&renderer)),
+        Err(ReplayError::Diverged {
+            expected: NodeId(5),
+            actual: NodeId(0),
+        })
+    );
+
+    assert_eq!(
+        ReplayLog::from_text("replay-log 1\nappend 0\n"),
+        Err(ReplayError::Malformed { line: 2 })
+    );
+    assert_eq!(
+        ReplayLog::from_text("style-snapshot 1\n"),
+        Err(ReplayError::Malformed { line: 1 })
+    );
+}
@@ -0,0 +1,111 @@
+//! Scroll anchoring tests - selects an anchor in a scrolled viewport,
+//! changes content above it, and checks the adjustment that keeps it in
+//! place.
+
+mod common;
+
+use common::find_element_by_id;
+use rewrite_core::Subpixel;
+use rewrite_page::Browser;
+
+const VIEWPORT_WIDTH: u32 = 800;
+const VIEWPORT_HEIGHT: u32 = 600;
+
+/// A 100px header followed by 100px blocks, so that at a scroll position
+/// of 450 the first fully visible block is `#b5` (y = 500). The selector
+/// is specific enough to apply while streaming, before the cascade is
+/// flushed.
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div[id] { height: 100px }
+</style></head><body>
+    <div id=\"header\"></div>
+    <div id=\"b1\"></div><div id=\"b2\"></div><div id=\"b3\"></div>
+    <div id=\"b4\"></div><div id=\"b5\"></div><div id=\"b6\"></div>
+    <div id=\"b7\"></div><div id=\"b8\"></div><div id=\"b9\"></div>
+    <div id=\"b10\"></div><div id=\"b11\"></div><div id=\"b12\"></div>
+</body></html>";
+
+#[test]
+fn anchor_compensates_for_content_above() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(VIEWPORT_WIDTH, VIEWPORT_HEIGHT);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    assert_eq!(renderer.scroll_anchor(Subpixel::ZERO), None);
+
+    let anchor = renderer.scroll_anchor(Subpixel::from_px(450));
+    assert_eq!(
+        anchor.map(|anchor| anchor.node),
+        find_element_by_id(&page, "b5")
+    );
+    assert_eq!(
+        anchor.map(|anchor| anchor.top),
+        Some(Subpixel::from_px(500))
+    );
+
+    let header = find_element_by_id(&page, "header");
+    if let (Some(anchor), Some(header)) = (anchor, header) {
+        page.set_attribute(header, "style", Some("height: 300px"));
+        assert_eq!(
+            renderer.scroll_anchor_adjustment(&anchor),
+            Some(Subpixel::from_px(200))
+        );
+
+        page.set_attribute(header, "style", Some("height: 50px"));
+        assert_eq!(
+            renderer.scroll_anchor_adjustment(&anchor),
+            Some(Subpixel::from_px(-50))
+        );
+    }
+}
+
+#[test]
+fn anchor_skips_opted_out_and_positioned_boxes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(VIEWPORT_WIDTH, VIEWPORT_HEIGHT);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let opted_out = find_element_by_id(&page, "b5");
+    let positioned = find_element_by_id(&page, "b6");
+    if let (Some(opted_out), Some(positioned)) = (opted_out, positioned) {
+        page.set_attribute(opted_out, "style", Some("overflow-anchor: none"));
+        page.set_attribute(positioned, "style", Some("position: absolute"));
+    }
+
+    let anchor = renderer.scroll_anchor(Subpixel::from_px(450));
+    assert_eq!(
+        anchor.map(|anchor| anchor.node),
+        find_element_by_id(&page, "b7")
+    );
+}
+
+#[test]
+fn streamed_content_below_needs_no_adjustment() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(VIEWPORT_WIDTH, VIEWPORT_HEIGHT);
+    // Styles apply to nodes created after the chunk that defines them.
+    let (head, body) = DOCUMENT.split_at(DOCUMENT.find("<body>").unwrap_or(0));
+    let (loaded, streamed) = body.split_at(body.find("<div id=\"b7\">").unwrap_or(0));
+    let mut document = page.begin_html();
+    document.push(head);
+    document.push(loaded);
+
+    let anchor = renderer.scroll_anchor(Subpixel::from_px(150));
+    assert_eq!(
+        anchor.map(|anchor| anchor.node),
+        find_element_by_id(&page, "b2")
+    );
+
+    document.push(streamed);
+    document.finish();
+    let adjustment = anchor.and_then(|anchor| renderer.scroll_anchor_adjustment(&anchor));
+    assert_eq!(adjustment, Some(Subpixel::ZERO));
+}
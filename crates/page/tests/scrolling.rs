@@ -0,0 +1,146 @@
+//! Scroll container tests - checks the client and scrollable areas of
+//! boxes with non-visible `overflow`, that their content is clipped to the
+//! padding box, and that scroll offsets shift where descendants are drawn
+//! and hit without changing layout.
+
+mod common;
+
+use common::{element_by_id, load, px};
+use rewrite_layout::Rect;
+use rewrite_page::Browser;
+use rewrite_renderer::DisplayItem;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #scroller { overflow: auto; width: 100px; height: 50px }
+    #content { width: 300px; height: 200px; background: green }
+    #hidden { overflow: hidden; width: 100px; height: 50px }
+    #short { height: 10px }
+</style></head><body id=\"body\">
+<div id=\"scroller\"><div id=\"content\"></div></div>
+<div id=\"hidden\"><div id=\"short\"></div></div>
+<div id=\"plain\">text</div>
+</body></html>";
+
+#[test]
+fn scroll_containers_report_client_and_scroll_areas() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let metrics = renderer
+        .scroll_metrics(element_by_id(&page, "scroller"))
+        .unwrap();
+    assert_eq!(
+        metrics.client,
+        Rect::new(px(0.0), px(0.0), px(100.0), px(50.0))
+    );
+    assert_eq!(
+        metrics.scroll,
+        Rect::new(px(0.0), px(0.0), px(300.0), px(200.0))
+    );
+    assert_eq!(metrics.max_offset(), (px(200.0), px(150.0)));
+    assert_eq!((metrics.offset_x, metrics.offset_y), (px(0.0), px(0.0)));
+
+    let hidden = renderer
+        .scroll_metrics(element_by_id(&page, "hidden"))
+        .unwrap();
+    assert_eq!(
+        hidden.scroll, hidden.client,
+        "content that fits doesn't grow the scrollable area"
+    );
+
+    let content = element_by_id(&page, "content");
+    assert_eq!(
+        renderer.clip_rect(content),
+        Some(metrics.client),
+        "content is clipped to the padding box"
+    );
+    assert_eq!(renderer.clip_rect(element_by_id(&page, "plain")), None);
+}
+
+#[test]
+fn scroll_offset_shifts_descendants_where_they_are_drawn() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+    let scroller = element_by_id(&page, "scroller");
+    let content = element_by_id(&page, "content");
+    let laid_out = renderer.computed_box(content).border_box();
+
+    assert!(renderer.set_scroll_offset(scroller, px(30.0), px(500.0)));
+    let metrics = renderer.scroll_metrics(scroller).unwrap();
+    assert_eq!(
+        (metrics.offset_x, metrics.offset_y),
+        (px(30.0), px(150.0)),
+        "offsets are clamped to the scrollable range"
+    );
+
+    let tree = renderer.fragment_tree();
+    assert_eq!(
+        tree.find(content).map(|fragment| fragment.rect),
+        Some(Rect::new(px(-30.0), px(-150.0), px(300.0), px(200.0)))
+    );
+    assert_eq!(
+        tree.find(scroller).map(|fragment| fragment.rect),
+        Some(metrics.client),
+        "the container itself doesn't move"
+    );
+    assert_eq!(
+        renderer.computed_box(content).border_box(),
+        laid_out,
+        "scrolling doesn't change layout"
+    );
+
+    let background = renderer
+        .display_list()
+        .items
+        .into_iter()
+        .find(|item| item.node() == content)
+        .unwrap();
+    let DisplayItem::SolidRect { rect, clip, .. } = background else {
+        panic!("expected the background of #content, got {background:?}");
+    };
+    assert_eq!((rect.x, rect.y), (px(-30.0), px(-150.0)));
+    assert_eq!(clip, Some(metrics.client));
+}
+
+#[test]
+fn clipped_content_is_only_hit_inside_the_clip() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+    let scroller = element_by_id(&page, "scroller");
+    let content = element_by_id(&page, "content");
+
+    assert_eq!(renderer.hit_test(px(10.0), px(10.0)), Some(content));
+    assert_eq!(
+        renderer.hit_test(px(150.0), px(10.0)),
+        Some(element_by_id(&page, "body")),
+        "content overflowing the scroller isn't hit"
+    );
+
+    renderer.set_scroll_offset(scroller, px(0.0), px(150.0));
+    assert_eq!(
+        renderer.hit_test(px(10.0), px(45.0)),
+        Some(content),
+        "the bottom of the content is scrolled into view"
+    );
+}
+
+#[test]
+fn only_scroll_containers_scroll() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let plain = element_by_id(&page, "plain");
+    assert!(!renderer.set_scroll_offset(plain, px(0.0), px(10.0)));
+    let hidden = element_by_id(&page, "hidden");
+    assert!(
+        renderer.set_scroll_offset(hidden, px(0.0), px(10.0)),
+        "overflow: hidden can be scrolled programmatically"
+    );
+    let metrics = renderer.scroll_metrics(hidden).unwrap();
+    assert_eq!(metrics.offset_y, px(0.0), "there is nothing to scroll to");
+}
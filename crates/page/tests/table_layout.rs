@@ -0,0 +1,109 @@
+//! Table layout tests - checks that columns are sized from their cells
+//! and share the table's width, that rows are as tall as their tallest
+//! cell, that `border-spacing` separates them, and that header and footer
+//! groups go first and last.
+
+mod common;
+
+use common::{geometry, open};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+use std::sync::Arc;
+
+/// A page with `body` in a document without body margins or cell padding.
+fn load<'browser>(browser: &'browser Browser, body: &str) -> (Page<'browser>, Arc<Renderer>) {
+    open(
+        browser,
+        &format!(
+            "<html><head><style>body {{ margin: 0 }} td {{ padding: 0 }}</style></head>\
+             <body>{body}</body></html>"
+        ),
+    )
+}
+
+#[test]
+fn columns_and_rows_are_separated_by_border_spacing() {
+    let browser = Browser::default();
+    let (page, renderer) = load(
+        &browser,
+        "<table id=\"table\" style=\"border-spacing: 10px 5px\">\
+           <tr id=\"first\">\
+             <td id=\"a\" style=\"width: 50px; height: 20px\"></td>\
+             <td id=\"b\" style=\"width: 100px\"></td>\
+           </tr>\
+           <tr id=\"second\">\
+             <td id=\"c\"></td>\
+             <td id=\"d\" style=\"height: 30px\"></td>\
+           </tr>\
+         </table>",
+    );
+    // Two columns and three gaps wide, two rows and three gaps tall.
+    assert_eq!(geometry(&page, &renderer, "table"), (0, 0, 180, 65));
+    assert_eq!(geometry(&page, &renderer, "a"), (10, 5, 50, 20));
+    assert_eq!(geometry(&page, &renderer, "b"), (70, 5, 100, 20));
+    // Every cell in a row is as tall as its tallest.
+    assert_eq!(geometry(&page, &renderer, "c"), (10, 30, 50, 30));
+    assert_eq!(geometry(&page, &renderer, "d"), (70, 30, 100, 30));
+    assert_eq!(geometry(&page, &renderer, "second"), (10, 30, 160, 30));
+}
+
+#[test]
+fn extra_width_is_shared_in_proportion_to_the_columns() {
+    let browser = Browser::default();
+    let (page, renderer) = load(
+        &browser,
+        "<table id=\"table\" style=\"width: 330px; border-spacing: 10px\">\
+           <tr>\
+             <td id=\"narrow\" style=\"width: 50px; height: 10px\"></td>\
+             <td id=\"wide\" style=\"width: 100px\"></td>\
+           </tr>\
+         </table>",
+    );
+    assert_eq!(geometry(&page, &renderer, "table").2, 330);
+    assert_eq!(geometry(&page, &renderer, "narrow"), (10, 10, 100, 10));
+    assert_eq!(geometry(&page, &renderer, "wide"), (120, 10, 200, 10));
+}
+
+#[test]
+fn auto_tables_fit_their_containing_block() {
+    let text = "Cells wrap their text once the table is as wide as its container";
+    let browser = Browser::default();
+    let (page, renderer) = load(
+        &browser,
+        &format!(
+            "<table id=\"wide\" style=\"border-spacing: 0\">\
+               <tr><td id=\"one-line\">{text}</td><td>{text}</td></tr>\
+             </table>\
+             <div style=\"width: 200px\">\
+               <table id=\"narrow\" style=\"border-spacing: 0\">\
+                 <tr><td id=\"wrapped\">{text}</td><td>{text}</td></tr>\
+               </table>\
+             </div>"
+        ),
+    );
+    let (_, _, wide_width, line_height) = geometry(&page, &renderer, "one-line");
+    assert!(
+        wide_width * 2 > 200,
+        "the text doesn't fit twice on one line"
+    );
+    assert_eq!(geometry(&page, &renderer, "narrow").2, 200);
+    let (_, _, wrapped_width, wrapped_height) = geometry(&page, &renderer, "wrapped");
+    assert_eq!(wrapped_width, 100, "both columns ask for the same width");
+    assert!(wrapped_height > line_height, "the narrow cell wraps");
+}
+
+#[test]
+fn header_and_footer_groups_go_first_and_last() {
+    let browser = Browser::default();
+    let (page, renderer) = load(
+        &browser,
+        "<table style=\"border-spacing: 0\">\
+           <tfoot id=\"foot\"><tr><td style=\"height: 10px\"></td></tr></tfoot>\
+           <tbody id=\"body\"><tr><td style=\"height: 20px\"></td></tr></tbody>\
+           <thead id=\"head\"><tr><td style=\"height: 30px\"></td></tr></thead>\
+         </table>",
+    );
+    assert_eq!(geometry(&page, &renderer, "head").1, 0);
+    assert_eq!(geometry(&page, &renderer, "body").1, 30);
+    assert_eq!(geometry(&page, &renderer, "foot").1, 50);
+}
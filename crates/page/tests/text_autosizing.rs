@@ -0,0 +1,58 @@
+//! Text autosizing tests - checks that the minimum font size and scale
+//! factor change text measurement only on narrow viewports, and that the
+//! applied scale is reported per node.
+
+mod common;
+
+use common::element_by_id;
+use rewrite_core::{Subpixel, TextAutosizing};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    div { line-height: normal }
+</style></head><body>
+    <div id=\"small\" style=\"font-size: 8px\">Small print</div>
+    <div id=\"large\" style=\"font-size: 16px\">Small print</div>
+</body></html>";
+
+/// Raises 8px text to 16px on viewports up to 480px wide.
+const AUTOSIZING: TextAutosizing = TextAutosizing {
+    max_viewport_width: 480,
+    scale: 1.0,
+    min_font_size: 16.0,
+};
+
+/// Content height of `#id`, rounded to whole pixels.
+fn height(page: &Page<'_>, renderer: &Renderer, id: &str) -> i32 {
+    let node = element_by_id(page, id);
+    let computed = renderer.computed_box(node);
+    computed.height.map_or(0.0, Subpixel::to_f32).round() as i32
+}
+
+#[test]
+fn autosizing_applies_minimum_font_size_on_narrow_viewports() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(400, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let small = element_by_id(&page, "small");
+    assert!(height(&page, &renderer, "small") < height(&page, &renderer, "large"));
+    assert_eq!(renderer.text_autosize_scale(small), Some(1.0));
+
+    renderer.set_text_autosizing(Some(AUTOSIZING));
+    assert_eq!(
+        height(&page, &renderer, "small"),
+        height(&page, &renderer, "large")
+    );
+    assert_eq!(renderer.text_autosize_scale(small), Some(2.0));
+
+    // Wider than `max_viewport_width`: laid out at the computed size.
+    renderer.set_viewport(800, 600);
+    assert!(height(&page, &renderer, "small") < height(&page, &renderer, "large"));
+    assert_eq!(renderer.text_autosize_scale(small), Some(1.0));
+}
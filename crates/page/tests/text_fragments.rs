@@ -0,0 +1,103 @@
+//! Text fragment tests - checks that text queries match the rendered text
+//! case-insensitively and across inline boxes but not across blocks, that
+//! the first match is located on its line, and that the scroll offset
+//! returned for a scroll container centers it.
+
+mod common;
+
+use common::{element_by_id, load};
+use rewrite_core::{NodeId, Subpixel};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{BoxArea, TextRange};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #scroller { overflow: auto; width: 300px; height: 100px }
+    #scroller p { margin: 0; height: 40px }
+    p { margin: 0 }
+</style></head><body>
+<div id=\"scroller\">
+<p>alpha</p><p>beta</p><p>gamma</p>
+<p id=\"target\">Find the <b id=\"bold\">Needle  in</b> here</p>
+<p>delta</p><p>epsilon</p><p>zeta</p>
+</div>
+<p>end of one</p><p>block</p>
+<p style=\"display: none\">hidden words</p>
+</body></html>";
+
+/// The text node children of `element`, in DOM order.
+fn text_children(page: &Page<'_>, element: NodeId) -> Vec<NodeId> {
+    let mut children: Vec<NodeId> = page
+        .tree
+        .children(element)
+        .filter(|&child| page.tree.text_content(child).is_some())
+        .collect();
+    children.reverse();
+    children
+}
+
+#[test]
+fn matches_span_inline_boxes_but_not_blocks() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let matches = renderer.find_text("THE needle in\nhere");
+    assert_eq!(matches.len(), 1, "{matches:?}");
+    let target = text_children(&page, element_by_id(&page, "target"));
+    let bold = text_children(&page, element_by_id(&page, "bold"));
+    assert_eq!(
+        matches[0],
+        vec![
+            TextRange {
+                node: target[0],
+                range: 5..9,
+            },
+            TextRange {
+                node: bold[0],
+                range: 0..9,
+            },
+            TextRange {
+                node: target[1],
+                range: 0..5,
+            },
+        ]
+    );
+
+    assert_eq!(renderer.find_text("a").len(), 7);
+    assert!(renderer.find_text("one block").is_empty());
+    assert!(renderer.find_text("hidden").is_empty());
+    assert!(renderer.find_text("  ").is_empty());
+}
+
+#[test]
+fn first_match_is_located_and_centered() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+
+    let scroller = element_by_id(&page, "scroller");
+    let bold_text = text_children(&page, element_by_id(&page, "bold"))[0];
+    let bold = renderer
+        .used_values(bold_text)
+        .expect("the text of #bold is laid out")
+        .rect(BoxArea::Border);
+    let target = renderer
+        .scroll_to_text("needle", scroller)
+        .expect("the text is found");
+    assert_eq!(target.rect.x, bold.x);
+    assert_eq!(target.rect.y, bold.y);
+    assert!(target.rect.width > Subpixel::ZERO && target.rect.width < bold.width);
+
+    let client = renderer.scroll_metrics(scroller).unwrap().client;
+    let center = target.rect.y + target.rect.height / Subpixel::raw(2) - target.scroll_y;
+    let client_center = client.y + client.height / Subpixel::raw(2);
+    assert!((center - client_center).to_f32().abs() <= 0.02);
+    assert_eq!(target.scroll_x, Subpixel::ZERO);
+
+    // Near the end, the offset stops at the scrollable range.
+    let last = renderer.scroll_to_text("zeta", scroller).unwrap();
+    let (_, max_y) = renderer.scroll_metrics(scroller).unwrap().max_offset();
+    assert_eq!(last.scroll_y, max_y);
+    assert!(renderer.scroll_to_text("missing", scroller).is_none());
+}
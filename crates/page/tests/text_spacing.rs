@@ -0,0 +1,136 @@
+//! Text spacing tests - checks that `letter-spacing`, `word-spacing` and
+//! `text-transform` change measured text, and that changing them re-measures
+//! intrinsic widths while paint-only inherited properties re-measure nothing.
+
+mod common;
+
+use common::element_by_id;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    span { position: absolute }
+    .min { width: min-content }
+    .letters { letter-spacing: 4px }
+    .words { word-spacing: 20px }
+    .upper { text-transform: uppercase }
+    .em { font-size: 10px; letter-spacing: 0.5em }
+    .em > span { font-size: 20px }
+</style></head><body>
+<span id=\"plain\">one two three</span>
+<span id=\"letters\" class=\"letters\">one two three</span>
+<span id=\"words\" class=\"words\">one two three</span>
+<span id=\"upper\" class=\"upper\">one two three</span>
+<span id=\"min\" class=\"min\">longest a b</span>
+<div class=\"em\"><span id=\"inherited\">ab</span></div>
+<span id=\"inherited-plain\" style=\"font-size: 20px\">ab</span>
+</body></html>";
+
+/// Width of `#id`, in pixels.
+fn width(page: &Page<'_>, renderer: &Renderer, id: &str) -> f32 {
+    let node = element_by_id(page, id);
+    renderer
+        .computed_box(node)
+        .width
+        .unwrap_or_default()
+        .to_f32()
+}
+
+#[test]
+fn spacing_and_transform_change_measured_text() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+    let width = |id| width(&page, &renderer, id);
+
+    let plain = width("plain");
+    assert!(plain > 0.0);
+    // 13 characters, each followed by 4px.
+    let letters = width("letters") - plain;
+    assert!(
+        (letters - 52.0).abs() < 1.0,
+        "letter-spacing adds after every character, got {letters}"
+    );
+    let words = width("words") - plain;
+    assert!(
+        (words - 40.0).abs() < 1.0,
+        "word-spacing adds at each of the two spaces, got {words}"
+    );
+    assert!(width("upper") > plain, "uppercase text is wider");
+
+    // `em` spacing is computed against the declaring element's font size
+    // and inherited as a length: 2 characters at 5px.
+    let inherited = width("inherited") - width("inherited-plain");
+    assert!(
+        (inherited - 10.0).abs() < 1.0,
+        "em spacing inherits as a computed length, got {inherited}"
+    );
+}
+
+#[test]
+fn changing_spacing_re_measures_intrinsic_widths() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let min = element_by_id(&page, "min");
+    let before = width(&page, &renderer, "min");
+    page.set_attribute(min, "class", Some("min letters"));
+    let letters = width(&page, &renderer, "min");
+    // The longest word, "longest", gains 7 characters of spacing.
+    assert!(
+        (letters - before - 28.0).abs() < 1.0,
+        "min-content width follows letter-spacing, {before} -> {letters}"
+    );
+
+    page.set_attribute(min, "class", Some("min letters upper"));
+    assert!(
+        width(&page, &renderer, "min") > letters,
+        "text-transform re-measures"
+    );
+
+    page.set_attribute(min, "class", Some("min"));
+    assert_eq!(
+        width(&page, &renderer, "min"),
+        before,
+        "removing the spacing restores the width"
+    );
+
+    let plain = element_by_id(&page, "plain");
+    let plain_before = width(&page, &renderer, "plain");
+    page.set_attribute(plain, "style", Some("word-spacing: 10px"));
+    assert!(
+        (width(&page, &renderer, "plain") - plain_before - 20.0).abs() < 1.0,
+        "changing word-spacing re-measures shrink-to-fit widths"
+    );
+}
+
+#[test]
+fn only_measurement_changes_re_resolve_text() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+    let plain = element_by_id(&page, "plain");
+
+    renderer.reset_resolve_stats();
+    page.set_attribute(plain, "style", Some("color: red; cursor: pointer"));
+    assert_eq!(
+        renderer.resolve_stats().top_level,
+        0,
+        "paint-only inherited properties don't re-resolve"
+    );
+
+    renderer.reset_resolve_stats();
+    page.set_attribute(plain, "style", Some("letter-spacing: 2px"));
+    assert!(renderer.resolve_stats().top_level > 0);
+}
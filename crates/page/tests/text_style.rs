@@ -0,0 +1,97 @@
+//! Text style tests - checks that text nodes get the inherited style of
+//! their anonymous inline box, and that the mirror audit finds every text
+//! node inheriting exactly its parent's values.
+
+mod common;
+
+use common::element_by_id;
+use lightningcss::properties::font::{AbsoluteFontWeight, FontWeight};
+use lightningcss::properties::text::{TextTransformCase, WhiteSpace};
+use rewrite_core::{NodeId, Subpixel};
+use rewrite_page::{Browser, Page};
+
+const DOCUMENT: &str = "<html><head><style>
+    #block { font-size: 20px; font-weight: bold; letter-spacing: 0.1em; white-space: pre;
+             width: 100px; margin: 7px; background: red }
+    #inline { text-transform: uppercase; word-spacing: 3px }
+</style></head><body>
+<div id=\"block\">block text<span id=\"inline\">inline text</span></div>
+</body></html>";
+
+/// The text child of `element`.
+fn text_of(page: &Page<'_>, element: NodeId) -> NodeId {
+    page.tree
+        .children(element)
+        .find(|&child| page.tree.text_content(child).is_some())
+        .unwrap_or_else(|| panic!("no text in node {}", element.0))
+}
+
+#[test]
+fn text_nodes_inherit_their_elements_style() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let block = element_by_id(&page, "block");
+    assert_eq!(
+        renderer.text_style(block),
+        None,
+        "elements have no text style"
+    );
+
+    let style = renderer
+        .text_style(text_of(&page, block))
+        .expect("text directly in a block has a style");
+    assert!((style.font_size - 20.0).abs() < f32::EPSILON);
+    assert_eq!(
+        style.font_weight,
+        FontWeight::Absolute(AbsoluteFontWeight::Bold)
+    );
+    assert_eq!(style.white_space, WhiteSpace::Pre);
+    assert_eq!(
+        style.letter_spacing,
+        Subpixel::from_px(2),
+        "em spacing is computed against the element's font"
+    );
+    assert_eq!(style.text_transform, None);
+
+    let inline = element_by_id(&page, "inline");
+    let style = renderer
+        .text_style(text_of(&page, inline))
+        .expect("text in an inline has a style");
+    assert!((style.font_size - 20.0).abs() < f32::EPSILON);
+    assert_eq!(style.letter_spacing, Subpixel::from_px(2));
+    assert_eq!(style.word_spacing, Subpixel::from_px(3));
+    assert_eq!(
+        style.text_transform.map(|transform| transform.case),
+        Some(TextTransformCase::Uppercase)
+    );
+
+    assert_eq!(page.audit_mirrors(Some(&renderer)), Ok(()));
+}
+
+#[test]
+fn text_styles_follow_style_changes() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(DOCUMENT);
+    document.finish();
+
+    let block = element_by_id(&page, "block");
+    let text = text_of(&page, block);
+    page.set_attribute(block, "style", Some("font-size: 10px; white-space: normal"));
+    let style = renderer.text_style(text).expect("text keeps a style");
+    assert!((style.font_size - 10.0).abs() < f32::EPSILON);
+    assert_eq!(style.white_space, WhiteSpace::Normal);
+    assert_eq!(
+        style.letter_spacing,
+        Subpixel::from_px(1),
+        "em spacing follows the declaring element's new font"
+    );
+    assert_eq!(page.audit_mirrors(Some(&renderer)), Ok(()));
+}
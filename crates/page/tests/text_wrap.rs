@@ -0,0 +1,122 @@
+//! `text-wrap` tests - checks that `balance` and `pretty` break text at a
+//! narrower width with as many lines, that `balance` leaves text alone
+//! past its line limit, and that changing `text-wrap` rebreaks the text.
+
+mod common;
+
+use common::{element_by_id, fractional_px, open};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+use std::sync::Arc;
+
+const HEAD: &str = "Headings wrap onto a second";
+const LAST: &str = "line";
+
+/// `(width, height)` of the text in `#id`.
+fn text_size(page: &Page<'_>, renderer: &Renderer, id: &str) -> (f32, f32) {
+    let element = element_by_id(page, id);
+    let Some(text) = page.tree.children(element).next() else {
+        panic!("no text in #{id}");
+    };
+    let computed = renderer.computed_box(text);
+    (
+        fractional_px(computed.width),
+        fractional_px(computed.height),
+    )
+}
+
+/// A page with `body` in an unmargined 16px body.
+fn load<'browser>(browser: &'browser Browser, body: &str) -> (Page<'browser>, Arc<Renderer>) {
+    open(
+        browser,
+        &format!("<html><body style=\"margin: 0; font-size: 16px\">{body}</body></html>"),
+    )
+}
+
+/// A page with `HEAD LAST` in a box of each `text-wrap` value, just wide
+/// enough for `HEAD`, so that `auto` leaves `LAST` alone on a second line.
+fn load_heading(browser: &Browser) -> (Page<'_>, Arc<Renderer>) {
+    let probe_browser = Browser::default();
+    let (probe, probe_renderer) = load(&probe_browser, &format!("<div id=\"head\">{HEAD}</div>"));
+    let (head_width, _) = text_size(&probe, &probe_renderer, "head");
+    let width = head_width.ceil() + 2.0;
+    let boxes = ["auto", "balance", "pretty"]
+        .map(|wrap| {
+            format!(
+                "<div id=\"{wrap}\" style=\"width: {width}px; text-wrap: {wrap}\">\
+                 {HEAD} {LAST}</div>"
+            )
+        })
+        .concat();
+    load(browser, &boxes)
+}
+
+#[test]
+fn balance_and_pretty_keep_the_line_count() {
+    let browser = Browser::default();
+    let (page, renderer) = load_heading(&browser);
+    let (auto_width, auto_height) = text_size(&page, &renderer, "auto");
+    let (balanced_width, balanced_height) = text_size(&page, &renderer, "balance");
+    let (pretty_width, pretty_height) = text_size(&page, &renderer, "pretty");
+
+    assert_eq!(balanced_height, auto_height, "balancing keeps two lines");
+    assert_eq!(
+        pretty_height, auto_height,
+        "avoiding the orphan keeps two lines"
+    );
+    assert!(
+        balanced_width < auto_width * 0.75,
+        "balanced lines are about half as long: {balanced_width} vs {auto_width}"
+    );
+    assert!(
+        balanced_width <= pretty_width && pretty_width < auto_width,
+        "`pretty` moves only a word down: {balanced_width} <= {pretty_width} < {auto_width}"
+    );
+}
+
+#[test]
+fn balance_leaves_single_and_long_text_alone() {
+    let browser = Browser::default();
+    let long = format!("{HEAD} {LAST} ").repeat(20);
+    let (page, renderer) = load(
+        &browser,
+        &format!(
+            "<div id=\"short\" style=\"text-wrap: balance\">{HEAD}</div>\
+             <div id=\"short-auto\">{HEAD}</div>\
+             <div id=\"long\" style=\"width: 200px; text-wrap: balance\">{long}</div>\
+             <div id=\"long-auto\" style=\"width: 200px\">{long}</div>"
+        ),
+    );
+    assert_eq!(
+        text_size(&page, &renderer, "short"),
+        text_size(&page, &renderer, "short-auto")
+    );
+    assert_eq!(
+        text_size(&page, &renderer, "long"),
+        text_size(&page, &renderer, "long-auto")
+    );
+}
+
+#[test]
+fn changing_text_wrap_rebreaks_lines() {
+    let browser = Browser::default();
+    let (page, renderer) = load_heading(&browser);
+    let auto = text_size(&page, &renderer, "auto");
+    let balanced = text_size(&page, &renderer, "balance");
+
+    let node = element_by_id(&page, "auto");
+    let width = renderer
+        .computed_box(node)
+        .width
+        .unwrap_or_default()
+        .to_f32();
+    page.set_attribute(
+        node,
+        "style",
+        Some(&format!("width: {width}px; text-wrap: balance")),
+    );
+    assert_eq!(text_size(&page, &renderer, "auto"), balanced);
+
+    page.set_attribute(node, "style", Some(&format!("width: {width}px")));
+    assert_eq!(text_size(&page, &renderer, "auto"), auto);
+}
@@ -0,0 +1,92 @@
+//! Embedder UA stylesheet tests - checks that appended and replacement UA
+//! rules apply at the UA origin, so author CSS still overrides them, and
+//! that they carry over to the next document.
+
+mod common;
+
+use common::{element_by_id, load};
+use rewrite_core::Subpixel;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+const DOCUMENT: &str = "<html><head><style>
+    #authored { margin-top: 5px }
+</style></head><body>
+    <p id=\"plain\">Plain</p>
+    <p id=\"authored\">Authored</p>
+    <h1 id=\"heading\">Heading</h1>
+</body></html>";
+
+/// Used `(margin-top, margin-left)` of `#id`, in whole pixels.
+fn margins(page: &Page<'_>, renderer: &Renderer, id: &str) -> (i32, i32) {
+    let node = element_by_id(page, id);
+    let Some(used) = renderer.used_values(node) else {
+        panic!("#{id} has no box");
+    };
+    let px = |value: Subpixel| value.to_f32().round() as i32;
+    (px(used.margin.top), px(used.margin.left))
+}
+
+#[test]
+fn appended_rules_adjust_defaults_below_author_css() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    page.append_ua_rules("p { margin-top: 30px; margin-left: 12px }");
+    load(&page, &renderer, DOCUMENT);
+
+    assert_eq!(margins(&page, &renderer, "plain"), (30, 12));
+    // Author CSS beats the embedder's UA rule despite lower specificity.
+    assert_eq!(margins(&page, &renderer, "authored"), (5, 12));
+    // Built-in defaults the embedder didn't touch still apply.
+    assert!(margins(&page, &renderer, "heading").0 > 0);
+}
+
+#[test]
+fn replacement_drops_builtin_defaults() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(&page, &renderer, DOCUMENT);
+    assert_eq!(margins(&page, &renderer, "plain"), (16, 0));
+
+    page.set_ua_stylesheet("body, p, h1 { display: block } p { margin-left: 7px }");
+    assert_eq!(margins(&page, &renderer, "plain"), (0, 7));
+    assert_eq!(margins(&page, &renderer, "authored"), (5, 7));
+    assert_eq!(margins(&page, &renderer, "heading"), (0, 0));
+}
+
+#[test]
+fn embedder_rules_carry_over_to_the_next_document() {
+    let browser = Browser::default();
+    let (mut page, renderer) = browser.new_page();
+    page.append_ua_rules("p { margin-left: 9px }");
+    page.replace_document(Some(&renderer));
+    load(&page, &renderer, DOCUMENT);
+
+    assert_eq!(margins(&page, &renderer, "plain").1, 9);
+}
+
+#[test]
+fn builtin_defaults_sit_below_author_css_of_any_specificity() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    load(
+        &page,
+        &renderer,
+        "<html><head><style>* { padding-left: 3px }</style></head><body>
+            <blockquote id=\"quote\">Quote</blockquote>
+            <ul id=\"outer\"><li><ul id=\"nested\"><li>Nested</li></ul></li></ul>
+            <p id=\"hidden\" hidden>Hidden</p>
+        </body></html>",
+    );
+
+    assert_eq!(margins(&page, &renderer, "quote"), (16, 40));
+    assert_eq!(margins(&page, &renderer, "outer").0, 16);
+    assert_eq!(margins(&page, &renderer, "nested").0, 0);
+    // The universal author rule beats the UA `ul { padding-left: 40px }`.
+    let Some(outer) = renderer.used_values(element_by_id(&page, "outer")) else {
+        panic!("#outer has no box");
+    };
+    assert_eq!(outer.padding.left, Subpixel::from_f32(3.0));
+    let hidden = element_by_id(&page, "hidden");
+    assert!(renderer.used_values(hidden).is_none());
+}
@@ -0,0 +1,114 @@
+//! Used value tests - checks that margins, border widths and padding are
+//! reported in px after layout, that negative margins and unstyled borders
+//! keep their used values, and that boxless nodes have none.
+
+mod common;
+
+use common::{element_by_id, open, px};
+use rewrite_layout::EdgeSizes;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{BoxArea, Renderer, UsedValues};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0; width: 400px }
+    #sides {
+        margin: 1px 2px 3px 4px;
+        border: 5px solid;
+        border-left-width: 6px;
+        padding: 7px 8px 9px 10%;
+    }
+    #negative { margin: -12px 0 0 -8px }
+    #unstyled { border-width: 9px; padding: 0 }
+    #thin { border: thin dashed }
+    #gone { display: none }
+</style></head><body>
+    <div id=\"sides\">Sides</div>
+    <div id=\"negative\">Negative</div>
+    <div id=\"unstyled\">Unstyled</div>
+    <div id=\"thin\">Thin</div>
+    <div id=\"gone\">Gone</div>
+</body></html>";
+
+/// The used values of `#id`.
+fn used(page: &Page<'_>, renderer: &Renderer, id: &str) -> UsedValues {
+    let Some(used) = renderer.used_values(element_by_id(page, id)) else {
+        panic!("#{id} has no box");
+    };
+    used
+}
+
+#[test]
+fn box_model_values_resolve_to_px() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+
+    let sides = used(&page, &renderer, "sides");
+    assert_eq!(
+        sides.margin,
+        EdgeSizes::new(px(1.0), px(2.0), px(3.0), px(4.0))
+    );
+    assert_eq!(
+        sides.border,
+        EdgeSizes::new(px(5.0), px(5.0), px(5.0), px(6.0))
+    );
+    assert_eq!(
+        sides.padding,
+        EdgeSizes::new(px(7.0), px(8.0), px(9.0), px(40.0)),
+        "percent padding resolves against the containing block's width"
+    );
+
+    let thin = used(&page, &renderer, "thin");
+    assert_eq!(thin.border, EdgeSizes::uniform(px(1.0)));
+}
+
+#[test]
+fn negative_margins_and_unstyled_borders() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+
+    let negative = used(&page, &renderer, "negative");
+    assert_eq!(
+        negative.margin,
+        EdgeSizes::new(px(-12.0), px(0.0), px(0.0), px(-8.0))
+    );
+    let margin_box = negative.margin_box();
+    assert_eq!(margin_box.x, negative.border_box.x + px(8.0));
+    assert_eq!(margin_box.y, negative.border_box.y + px(12.0));
+
+    let unstyled = used(&page, &renderer, "unstyled");
+    assert_eq!(
+        unstyled.border,
+        EdgeSizes::default(),
+        "border-style: none zeroes the width"
+    );
+    assert_eq!(unstyled.content_box(), unstyled.border_box);
+}
+
+#[test]
+fn rects_nest_and_match_the_renderer() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    let node = element_by_id(&page, "sides");
+    let sides = used(&page, &renderer, "sides");
+
+    assert_eq!(
+        Some(sides.border_box),
+        renderer.computed_box(node).border_box()
+    );
+    let padding = sides.padding_box();
+    assert_eq!(padding.x, sides.border_box.x + px(6.0));
+    assert_eq!(padding.width, sides.border_box.width - px(11.0));
+    let content = sides.content_box();
+    assert_eq!(content.y, padding.y + px(7.0));
+    assert_eq!(content.height, padding.height - px(16.0));
+
+    for area in [
+        BoxArea::Margin,
+        BoxArea::Border,
+        BoxArea::Padding,
+        BoxArea::Content,
+    ] {
+        assert_eq!(renderer.box_rect(node, area), Some(sides.rect(area)));
+    }
+    assert_eq!(renderer.used_values(element_by_id(&page, "gone")), None);
+}
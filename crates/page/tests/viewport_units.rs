@@ -0,0 +1,162 @@
+//! Viewport unit tests - checks that `sv*`, `lv*` and `dv*` lengths
+//! resolve against the small, large and dynamic viewports, that resizing
+//! any viewport re-resolves only the boxes depending on it, and that an
+//! orientation change resets the small and dynamic viewports and applies
+//! `@media` rules testing it.
+
+mod common;
+
+use common::{element_by_id, open, whole_px};
+use rewrite_core::NodeId;
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::{GeometryChange, Renderer};
+
+const DOCUMENT: &str = "<html><head><style>
+    body { margin: 0 }
+    #oriented { height: 10px }
+    @media (orientation: portrait) { #oriented { height: 20px } }
+</style></head><body>
+    <div id=\"small\" style=\"width: 50svw; height: 10svh\"></div>
+    <div id=\"large\" style=\"width: 50lvw; height: 10lvh\"></div>
+    <div id=\"dynamic\" style=\"width: 50dvw; height: 10dvh\"></div>
+    <div id=\"after\" style=\"height: 10px\"></div>
+    <div style=\"font-size: 4dvh; height: 40px\">
+        <p style=\"margin: 0; height: 40px\"><span id=\"text\">text</span></p>
+    </div>
+    <div id=\"oriented\"></div>
+</body></html>";
+
+/// `(width, height)` of `#id`, in whole pixels.
+fn size(page: &Page<'_>, renderer: &Renderer, id: &str) -> (i32, i32) {
+    let computed = renderer.computed_box(element_by_id(page, id));
+    (whole_px(computed.width), whole_px(computed.height))
+}
+
+fn changed(renderer: &Renderer) -> Vec<NodeId> {
+    let diff = renderer.take_geometry_diff();
+    let nodes =
+        |changes: &[GeometryChange]| changes.iter().map(|change| change.node).collect::<Vec<_>>();
+    [nodes(&diff.moved), nodes(&diff.resized)].concat()
+}
+
+#[test]
+fn units_resolve_against_their_viewport() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    for id in ["small", "large", "dynamic"] {
+        assert_eq!(size(&page, &renderer, id), (400, 60), "#{id}");
+    }
+
+    renderer.set_small_viewport(Some((800, 500)));
+    renderer.set_dynamic_viewport(Some((700, 550)));
+    assert_eq!(size(&page, &renderer, "small"), (400, 50));
+    assert_eq!(size(&page, &renderer, "large"), (400, 60));
+    assert_eq!(size(&page, &renderer, "dynamic"), (350, 55));
+
+    renderer.set_dynamic_viewport(None);
+    assert_eq!(size(&page, &renderer, "dynamic"), (400, 60));
+}
+
+#[test]
+fn dynamic_viewport_changes_re_resolve_only_its_users() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    let (text_width, _) = size(&page, &renderer, "text");
+    assert!(text_width > 0);
+    renderer.take_geometry_diff();
+
+    renderer.set_dynamic_viewport(Some((800, 1200)));
+    assert_eq!(size(&page, &renderer, "dynamic"), (400, 120));
+    let (wide_width, _) = size(&page, &renderer, "text");
+    assert!(
+        (wide_width - 2 * text_width).abs() <= 1,
+        "text in a `dvh` font: {wide_width} vs {text_width}"
+    );
+
+    let changed = changed(&renderer);
+    assert!(changed.contains(&element_by_id(&page, "dynamic")));
+    assert!(changed.contains(&element_by_id(&page, "after")));
+    assert!(!changed.contains(&element_by_id(&page, "small")));
+    assert!(!changed.contains(&element_by_id(&page, "large")));
+}
+
+#[test]
+fn small_viewport_changes_re_resolve_only_its_users() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    renderer.take_geometry_diff();
+
+    renderer.set_small_viewport(Some((600, 500)));
+    assert_eq!(size(&page, &renderer, "small"), (300, 50));
+    let resized: Vec<NodeId> = renderer
+        .take_geometry_diff()
+        .resized
+        .iter()
+        .map(|change| change.node)
+        .collect();
+    assert!(resized.contains(&element_by_id(&page, "small")));
+    assert!(!resized.contains(&element_by_id(&page, "large")));
+    assert!(!resized.contains(&element_by_id(&page, "dynamic")));
+    assert!(!resized.contains(&element_by_id(&page, "text")));
+}
+
+#[test]
+fn resize_re_resolves_only_boxes_depending_on_it() {
+    let browser = Browser::default();
+    let rows = "<div>Row</div>".repeat(100);
+    let (page, renderer) = open(
+        &browser,
+        &format!(
+            "<html><head><style>
+                body {{ margin: 0 }}
+                div {{ height: 10px }}
+            </style></head><body>
+                <div id=\"fixed\" style=\"width: 100px\"></div>
+                <div id=\"half\" style=\"width: 50%\"></div>
+                <section style=\"width: 300px\">{rows}</section>
+                <div id=\"tall\" style=\"height: 10vh\"></div>
+                <div id=\"pinned\" style=\"position: fixed; right: 0; width: 10px\"></div>
+            </body></html>"
+        ),
+    );
+    renderer.take_geometry_diff();
+    renderer.reset_resolve_stats();
+
+    renderer.set_viewport(800, 700);
+    assert!(
+        renderer.resolve_stats().top_level < renderer.tracked_nodes().len() as u64,
+        "a taller viewport leaves the rows alone"
+    );
+    assert_eq!(size(&page, &renderer, "tall"), (800, 70));
+    let changed = changed(&renderer);
+    assert!(changed.contains(&element_by_id(&page, "tall")));
+    assert!(!changed.contains(&element_by_id(&page, "fixed")));
+    assert!(!changed.contains(&element_by_id(&page, "half")));
+
+    renderer.set_viewport(600, 700);
+    assert_eq!(size(&page, &renderer, "fixed"), (100, 10));
+    assert_eq!(size(&page, &renderer, "half"), (300, 10));
+    assert_eq!(size(&page, &renderer, "tall"), (600, 70));
+    let pinned = renderer.computed_box(element_by_id(&page, "pinned"));
+    assert_eq!(whole_px(pinned.x), 590, "placed against the viewport");
+}
+
+#[test]
+fn orientation_change_resets_the_viewports() {
+    let browser = Browser::default();
+    let (page, renderer) = open(&browser, DOCUMENT);
+    renderer.set_small_viewport(Some((800, 500)));
+    renderer.set_dynamic_viewport(Some((800, 550)));
+    assert_eq!(size(&page, &renderer, "oriented"), (800, 10));
+
+    renderer.set_viewport(600, 800);
+    for id in ["small", "large", "dynamic"] {
+        assert_eq!(size(&page, &renderer, id), (300, 80), "#{id}");
+    }
+    assert_eq!(size(&page, &renderer, "oriented"), (600, 20));
+
+    // A resize no `@media` rule tests keeps the rules as they are.
+    renderer.set_viewport(500, 900);
+    assert_eq!(size(&page, &renderer, "oriented"), (500, 20));
+    assert_eq!(size(&page, &renderer, "large"), (250, 90));
+}
@@ -0,0 +1,122 @@
+//! `white-space` tests - checks that text is collapsed or preserved per
+//! its `white-space` value, and that only the wrapping values wrap at the
+//! container's edge while preserved segment breaks always start new lines.
+
+mod common;
+
+use common::{element_by_id, find_element_by_id, whole_px};
+use rewrite_page::{Browser, Page};
+use rewrite_renderer::Renderer;
+
+const WORDS: &str = "alpha beta gamma delta epsilon zeta eta theta iota kappa";
+
+fn markup() -> String {
+    let boxes = [
+        ("normal", "normal"),
+        ("nowrap", "nowrap"),
+        ("pre-wrap", "pre-wrap"),
+    ]
+    .map(|(id, value)| {
+        format!("<div id=\"{id}\" style=\"width: 100px; white-space: {value}\">{WORDS}</div>")
+    })
+    .concat();
+    format!(
+        "<html><body style=\"margin: 0\">{boxes}\
+         <div id=\"pre\" style=\"width: 100px; white-space: pre\">one\ntwo\nthree</div>\
+         <div id=\"pre-line\" style=\"white-space: pre-line\">one   \n   two</div>\
+         <div id=\"collapsed\">one\ntwo</div>\
+         <div id=\"line\">x</div>\
+         </body></html>"
+    )
+}
+
+/// Height of `#id`, in whole pixels.
+fn height(page: &Page<'_>, renderer: &Renderer, id: &str) -> i32 {
+    let node = element_by_id(page, id);
+    whole_px(renderer.computed_box(node).height)
+}
+
+/// Width of the text in `#id`, in whole pixels.
+fn text_width(page: &Page<'_>, renderer: &Renderer, id: &str) -> i32 {
+    let Some(text) = find_element_by_id(page, id).and_then(|node| page.tree.children(node).next())
+    else {
+        panic!("no text in #{id}");
+    };
+    whole_px(renderer.computed_box(text).width)
+}
+
+#[test]
+fn white_space_controls_wrapping_and_line_breaks() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(&markup());
+    document.finish();
+
+    let line = height(&page, &renderer, "line");
+    assert!(line > 0, "a single line has a height");
+
+    let wrapped = height(&page, &renderer, "normal");
+    assert!(wrapped > line, "normal text wraps in 100px");
+    assert_eq!(
+        height(&page, &renderer, "pre-wrap"),
+        wrapped,
+        "pre-wrap wraps like normal"
+    );
+
+    assert_eq!(
+        height(&page, &renderer, "nowrap"),
+        line,
+        "nowrap keeps the text on one line"
+    );
+    let nowrap_width = text_width(&page, &renderer, "nowrap");
+    assert!(
+        nowrap_width > 100,
+        "nowrap text overflows, got {nowrap_width}"
+    );
+
+    assert_eq!(
+        height(&page, &renderer, "pre"),
+        3 * line,
+        "pre starts a line at each segment break"
+    );
+    assert_eq!(
+        height(&page, &renderer, "pre-line"),
+        2 * line,
+        "pre-line keeps segment breaks"
+    );
+    assert_eq!(
+        height(&page, &renderer, "collapsed"),
+        line,
+        "normal collapses segment breaks to spaces"
+    );
+}
+
+#[test]
+fn changing_white_space_rewraps_the_text() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(&markup());
+    document.finish();
+
+    let line = height(&page, &renderer, "line");
+    let wrapped = height(&page, &renderer, "normal");
+    if let Some(node) = find_element_by_id(&page, "normal") {
+        page.set_attribute(node, "style", Some("width: 100px; white-space: nowrap"));
+    }
+    assert_eq!(height(&page, &renderer, "normal"), line);
+
+    if let Some(node) = find_element_by_id(&page, "normal") {
+        page.set_attribute(node, "style", Some("width: 100px"));
+    }
+    assert_eq!(height(&page, &renderer, "normal"), wrapped);
+
+    // Switching to `pre` turns the segment breaks into line breaks.
+    if let Some(node) = find_element_by_id(&page, "collapsed") {
+        page.set_attribute(node, "style", Some("white-space: pre"));
+    }
+    assert_eq!(height(&page, &renderer, "collapsed"), 2 * line);
+}
@@ -0,0 +1,66 @@
+//! The public API of the engine, under one crate.
+//!
+//! Embedders should import from here rather than from the `rewrite_*`
+//! crates directly. Those crates are free to move and rename their items;
+//! what this crate re-exports, and under which name, is the surface kept
+//! stable. Each area has its own module, and [`prelude`] gathers what
+//! nearly every embedder needs.
+//!
+//! Names are unambiguous across modules. Where two crates use the same
+//! word for different things, the re-export says which one it is:
+//! `TextDirection` is the CSS `direction` property, `ListMarker` the
+//! `::marker` box of a list item, and `StreamingParser` the chunked input
+//! trait the HTML and CSS parsers share.
+
+/// Loading documents and driving a page.
+pub mod page {
+    pub use rewrite_page::{
+        AccessibilityNode, AccessibilityRole, Browser, HtmlPage, HtmlStream, Page, ReplayError,
+        ReplayEvent, ReplayLog, ReplayNode, ResourceResolver, accessibility_tree, focus_order,
+        next_focus, prev_focus,
+    };
+}
+
+/// The document tree.
+pub mod dom {
+    pub use rewrite_core::Parser as StreamingParser;
+    pub use rewrite_core::{NodeId, Subscriptions};
+    pub use rewrite_html::{Attributes, DomTree, DomUpdate, ElementState, HtmlParser, NodeData};
+}
+
+/// Stylesheets and computed values.
+pub mod style {
+    pub use rewrite_css::Marker as ListMarker;
+    pub use rewrite_css::{
+        ComputedColor, CssParser, ForcedColorsPalette, GeneratedContent, MediaEnvironment,
+        Property, PropertyId, StyleSnapshot, Styler,
+    };
+}
+
+/// Box geometry and the layout of the document.
+pub mod layout {
+    pub use rewrite_core::{GeneratedBox, LayoutFlags, ResolveDiagnostic, ResolveStats, Subpixel};
+    pub use rewrite_layout::Direction as TextDirection;
+    pub use rewrite_layout::{EdgeSizes, FormattingContext, Rect, ViewportOverflow, WritingMode};
+    pub use rewrite_renderer::{
+        BoxArea, ComputedBox, ContainerMetrics, Fragment, FragmentTree, GeneratedContentBox,
+        GeometryDiff, LayoutState, MarkerBox, Renderer, ScrollAnchor, ScrollMetrics, TextStyle,
+        UsedValues,
+    };
+}
+
+/// What is drawn, and where input lands.
+pub mod paint {
+    pub use rewrite_renderer::{
+        BorderSide, BorderSides, Cursor, CursorImage, DisplayItem, DisplayList, FocusRing,
+        PaintInfo,
+    };
+}
+
+/// The items nearly every embedder uses: `use rewrite_prelude::prelude::*`.
+pub mod prelude {
+    pub use crate::dom::{NodeData, NodeId};
+    pub use crate::layout::{BoxArea, LayoutFlags, Rect, Renderer, Subpixel, UsedValues};
+    pub use crate::page::{Browser, HtmlPage, Page};
+    pub use crate::paint::{DisplayItem, DisplayList};
+}
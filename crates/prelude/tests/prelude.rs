@@ -0,0 +1,41 @@
+//! Prelude tests - checks that a document can be loaded, laid out and
+//! painted through the re-exported names alone.
+
+use rewrite_prelude::prelude::*;
+
+#[test]
+fn loads_and_paints_through_the_prelude() {
+    let browser = Browser::default();
+    let (page, renderer) = browser.new_page();
+    renderer.set_viewport(800, 600);
+    let mut document = page.begin_html();
+    document.push(
+        "<html><body style=\"margin: 0\">\
+         <div style=\"height: 40px; background: red\"></div></body></html>",
+    );
+    document.finish();
+
+    let name = page.tree.interner.get("style");
+    let div = (0..page.styler.styled_node_count())
+        .rev()
+        .map(|index| NodeId(index as u32))
+        .find(|&node| match (page.tree.get_node(node), name) {
+            (Some(NodeData::Element { attributes, .. }), Some(name)) => {
+                attributes.get(&name).is_some()
+            }
+            _ => false,
+        })
+        .expect("no styled div");
+
+    let rect: Rect = renderer
+        .box_rect(div, BoxArea::Border)
+        .expect("div has a box");
+    assert_eq!(rect.height, Subpixel::from_f32(40.0));
+    assert!(
+        renderer
+            .display_list()
+            .items
+            .iter()
+            .any(|item| matches!(item, DisplayItem::SolidRect { node, .. } if *node == div))
+    );
+}
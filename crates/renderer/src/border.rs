@@ -0,0 +1,104 @@
+//! Per-side border values.
+//!
+//! Each side of a border keeps its own width, style and color, so a rule
+//! such as `border-left: 2px dashed` only affects the left edge. Widths are
+//! the used values from layout; colors are used colors (forced colors and
+//! `currentColor` applied) in their authored color space.
+
+use crate::renderer::LayoutState;
+use lightningcss::properties::border::LineStyle;
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::values::color::RGBA;
+use rewrite_core::{NodeId, PropertyResolver as _, Subpixel};
+use rewrite_css::ComputedColor;
+
+/// One edge of a node's border.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BorderSide {
+    /// Used width in px. Zero when the style is `none` or `hidden`.
+    pub width: Subpixel,
+    pub style: LineStyle,
+    pub color: ComputedColor,
+}
+
+impl BorderSide {
+    /// Whether this side paints anything.
+    pub fn is_visible(&self) -> bool {
+        self.width > Subpixel::ZERO
+            && !matches!(self.style, LineStyle::None | LineStyle::Hidden)
+            && !self.color.is_transparent()
+    }
+}
+
+/// The four border sides of a node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BorderSides {
+    pub top: BorderSide,
+    pub right: BorderSide,
+    pub bottom: BorderSide,
+    pub left: BorderSide,
+}
+
+impl BorderSides {
+    /// Whether all four sides are identical, so the border can be drawn as
+    /// a single stroked rectangle.
+    pub fn is_uniform(&self) -> bool {
+        self.top == self.right && self.top == self.bottom && self.top == self.left
+    }
+}
+
+/// Longhand property IDs for one side, in (width, style, color) order.
+type SideProperties = [PropertyId<'static>; 3];
+
+const TOP: SideProperties = [
+    PropertyId::BorderTopWidth,
+    PropertyId::BorderTopStyle,
+    PropertyId::BorderTopColor,
+];
+const RIGHT: SideProperties = [
+    PropertyId::BorderRightWidth,
+    PropertyId::BorderRightStyle,
+    PropertyId::BorderRightColor,
+];
+const BOTTOM: SideProperties = [
+    PropertyId::BorderBottomWidth,
+    PropertyId::BorderBottomStyle,
+    PropertyId::BorderBottomColor,
+];
+const LEFT: SideProperties = [
+    PropertyId::BorderLeftWidth,
+    PropertyId::BorderLeftStyle,
+    PropertyId::BorderLeftColor,
+];
+
+impl LayoutState {
+    /// Resolve the border of `node` side by side.
+    pub fn border_sides(&self, node: NodeId) -> BorderSides {
+        let resolver = self.resolver();
+        let side = |[width_id, style_id, color_id]: SideProperties| {
+            let style = match resolver.get_css_property(node, &style_id) {
+                Some(
+                    Property::BorderTopStyle(style)
+                    | Property::BorderRightStyle(style)
+                    | Property::BorderBottomStyle(style)
+                    | Property::BorderLeftStyle(style),
+                ) => style,
+                _ => LineStyle::None,
+            };
+            BorderSide {
+                width: self.cached_property(node, &width_id).unwrap_or(Subpixel::ZERO),
+                style,
+                color: self
+                    .used_computed_color(node, &color_id)
+                    .unwrap_or_else(|| RGBA::transparent().into()),
+            }
+        };
+
+        BorderSides {
+            top: side(TOP),
+            right: side(RIGHT),
+            bottom: side(BOTTOM),
+            left: side(LEFT),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Container metrics: the layout inputs of a box.
+//!
+//! When a box disagrees with a reference browser, the first question is
+//! whether it was laid out against the wrong container or did the wrong
+//! math against the right one. With `LayoutFlags::record_container_metrics`
+//! set, every box records the metrics of the box it was laid out in each
+//! time it is resolved. Comparing the recording with the container's final
+//! box also catches boxes left laid out against a stale container.
+
+use crate::renderer::LayoutState;
+use rewrite_core::{NodeId, PropertyResolver, Subpixel};
+use rewrite_layout::{
+    ContainingBlock, FormattingContext, Rect, containing_block, formatting_context,
+};
+
+/// The box a node was laid out in, as it was when the node was resolved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContainerMetrics {
+    /// The parent for in-flow boxes, the containing block for absolutely
+    /// positioned and fixed ones. `None` for the viewport, which contains
+    /// the root element and out-of-flow boxes without a positioned
+    /// ancestor.
+    pub container: Option<NodeId>,
+    /// The kind of box the container generates. `None` for the viewport.
+    pub formatting_context: Option<FormattingContext>,
+    /// The rect sizes, percentages and offsets resolved against: the
+    /// container's content box, its padding box for out-of-flow boxes, or
+    /// the viewport. `None` if the container had no box.
+    pub rect: Option<Rect>,
+    /// The line length inline content of the container was broken
+    /// against. `None` for the viewport.
+    pub available_inline_size: Option<Subpixel>,
+    pub viewport_width: u32,
+    pub viewport_height: u32,
+}
+
+impl LayoutState {
+    /// Metrics of the box `node` is laid out in, from the current layout.
+    pub(crate) fn measure_container(&mut self, node: NodeId) -> ContainerMetrics {
+        let resolver = self.resolver();
+        let viewport_width = resolver.viewport_width();
+        let viewport_height = resolver.viewport_height();
+        let viewport = Rect::from_size(
+            Subpixel::from_f32(viewport_width as f32),
+            Subpixel::from_f32(viewport_height as f32),
+        );
+        let out_of_flow = matches!(
+            formatting_context(node, &resolver),
+            Some(FormattingContext::AbsolutelyPositioned)
+        );
+        let container = if out_of_flow {
+            match containing_block(node, &resolver) {
+                ContainingBlock::Element(element) => Some(element),
+                ContainingBlock::Viewport => None,
+            }
+        } else {
+            resolver
+                .parent(node)
+                .filter(|&parent| parent != node && parent != NodeId::ROOT)
+        };
+        let Some(container) = container else {
+            return ContainerMetrics {
+                container: None,
+                formatting_context: None,
+                rect: Some(viewport),
+                available_inline_size: None,
+                viewport_width,
+                viewport_height,
+            };
+        };
+
+        let used = self.resolve_used_values(container);
+        ContainerMetrics {
+            container: Some(container),
+            formatting_context: formatting_context(container, &resolver),
+            rect: used.map(|used| {
+                if out_of_flow {
+                    used.padding_box()
+                } else {
+                    used.content_box()
+                }
+            }),
+            available_inline_size: self.available_inline_size(container),
+            viewport_width,
+            viewport_height,
+        }
+    }
+
+    /// The metrics of the container `node` was last resolved against.
+    /// `None` unless `LayoutFlags::record_container_metrics` was set when
+    /// it was, or if it generates no box.
+    pub fn container_metrics(&self, node: NodeId) -> Option<ContainerMetrics> {
+        self.container_metrics.get(&node).copied()
+    }
+}
@@ -0,0 +1,90 @@
+//! Mouse cursor.
+//!
+//! `cursor` is inherited and lists custom images to try in order before a
+//! keyword fallback. `cursor_at` combines it with hit testing to give the
+//! cursor an embedder should show with the pointer at a point, resolving
+//! `auto` to the text cursor over text and the default arrow elsewhere.
+
+use crate::renderer::LayoutState;
+use lightningcss::properties::ui::CursorKeyword;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{NodeId, PropertyResolver, Subpixel};
+
+/// A custom cursor image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorImage {
+    /// The URL as written, not resolved against the sheet's base URL.
+    pub url: String,
+    /// Where in the image the pointer is, in image pixels. `None` for the
+    /// image's own hotspot, or its top-left corner.
+    pub hotspot: Option<(f32, f32)>,
+}
+
+/// The computed `cursor` of a box.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    /// Images to try in order; the first that loads is shown.
+    pub images: Vec<CursorImage>,
+    /// Shown when no image loads.
+    pub keyword: CursorKeyword,
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self {
+            images: Vec::new(),
+            keyword: CursorKeyword::Auto,
+        }
+    }
+}
+
+impl LayoutState {
+    /// The computed `cursor` of `node`, with `auto` left as is.
+    pub fn cursor(&self, node: NodeId) -> Cursor {
+        let resolver = self.resolver();
+        let Some(Property::Cursor(cursor)) = resolver.get_css_property(node, &PropertyId::Cursor)
+        else {
+            return Cursor::default();
+        };
+        let images = cursor
+            .images
+            .iter()
+            .map(|image| CursorImage {
+                url: image.url.url.to_string(),
+                hotspot: image.hotspot,
+            })
+            .collect();
+        Cursor {
+            images,
+            keyword: cursor.keyword,
+        }
+    }
+
+    /// The cursor to show with the pointer at `(x, y)`: the `cursor` of
+    /// the box `hit_test` finds there, with `auto` resolved. The default
+    /// arrow if no box there takes pointer input.
+    pub fn cursor_at(&self, x: Subpixel, y: Subpixel) -> Cursor {
+        let Some(hit) = self.hit_box(x, y) else {
+            return Cursor {
+                keyword: CursorKeyword::Default,
+                ..Cursor::default()
+            };
+        };
+        let resolver = self.resolver();
+        let over_text = !resolver.is_element(hit);
+        let element = if over_text {
+            resolver.parent(hit).unwrap_or(hit)
+        } else {
+            hit
+        };
+        let mut cursor = self.cursor(element);
+        if cursor.keyword == CursorKeyword::Auto {
+            cursor.keyword = if over_text {
+                CursorKeyword::Text
+            } else {
+                CursorKeyword::Default
+            };
+        }
+        cursor
+    }
+}
@@ -0,0 +1,268 @@
+//! Display list.
+//!
+//! Flattens the laid-out document into drawing commands a backend or test
+//! harness can replay without querying layout or style: for each box in
+//! paint order (stacking contexts and `z-index` applied, see
+//! `paint_order`), its background, then its border (left open behind a
+//! fieldset's legend, see `fieldset`), the marker of a list item and its
+//! `::before` and `::after` text, and for each text node its text run.
+//! Items are drawn in list order, later over earlier. Boxes are placed
+//! where they are drawn, with scroll offsets applied, and each item carries
+//! the clip of the `overflow` of the boxes around it.
+
+use crate::border::{BorderSide, BorderSides};
+use crate::paint_info::BorderRadii;
+use crate::renderer::LayoutState;
+use crate::used_values::BoxArea;
+use lightningcss::properties::PropertyId;
+use lightningcss::values::color::RGBA;
+use rewrite_core::{GeneratedBox, NodeId, PropertyResolver, Subpixel};
+use rewrite_css::ComputedColor;
+use rewrite_layout::Rect;
+use rewrite_text::DEFAULT_FONT_SIZE_PX;
+use std::collections::HashMap;
+
+/// One drawing command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayItem {
+    /// A box's background, filling its border box.
+    SolidRect {
+        node: NodeId,
+        rect: Rect,
+        clip: Option<Rect>,
+        color: ComputedColor,
+        radii: BorderRadii,
+    },
+    /// A box's border, drawn inward from `rect`, its border box.
+    Border {
+        node: NodeId,
+        rect: Rect,
+        clip: Option<Rect>,
+        sides: BorderSides,
+        radii: BorderRadii,
+    },
+    /// The text of a text node, or the marker or generated content of the
+    /// element `node`, laid out in `rect` at `font_size` px.
+    Text {
+        node: NodeId,
+        rect: Rect,
+        clip: Option<Rect>,
+        text: String,
+        color: ComputedColor,
+        font_size: f32,
+    },
+}
+
+impl DisplayItem {
+    /// The rect drawing is clipped to, if any boxes around the node clip
+    /// their content.
+    pub fn clip(&self) -> Option<Rect> {
+        match self {
+            Self::SolidRect { clip, .. } | Self::Border { clip, .. } | Self::Text { clip, .. } => {
+                *clip
+            }
+        }
+    }
+
+    /// The node the item was generated for.
+    pub fn node(&self) -> NodeId {
+        match self {
+            Self::SolidRect { node, .. } | Self::Border { node, .. } | Self::Text { node, .. } => {
+                *node
+            }
+        }
+    }
+}
+
+/// Drawing commands for a whole document, back to front.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DisplayList {
+    pub items: Vec<DisplayItem>,
+}
+
+impl DisplayList {
+    /// Number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the list draws nothing.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl LayoutState {
+    /// Build the display list of the document from the current layout.
+    /// Transparent backgrounds, invisible borders, whitespace-only text and
+    /// boxes clipped away entirely emit nothing.
+    pub fn display_list(&self) -> DisplayList {
+        let resolver = self.resolver();
+        let fragments = self.fragment_tree();
+        let placed: HashMap<NodeId, Rect> = fragments
+            .iter()
+            .map(|fragment| (fragment.node, fragment.rect))
+            .collect();
+        let clips = fragments.clip_rects();
+        let mut items = Vec::new();
+        for node in self.paint_order() {
+            let Some(&rect) = placed.get(&node) else {
+                continue;
+            };
+            let clip = clips.get(&node).copied();
+            if clip.is_some_and(|clip| !clip.intersects(&rect)) {
+                continue;
+            }
+            if resolver.is_element(node) {
+                self.push_box_items(node, rect, clip, &mut items);
+                self.push_marker_item(node, rect, clip, &mut items);
+                self.push_generated_items(node, rect, clip, &mut items);
+            } else {
+                self.push_text_item(node, rect, clip, &resolver, &mut items);
+            }
+        }
+        DisplayList { items }
+    }
+
+    /// The background and border of an element drawn at `rect`.
+    fn push_box_items(
+        &self,
+        node: NodeId,
+        rect: Rect,
+        clip: Option<Rect>,
+        items: &mut Vec<DisplayItem>,
+    ) {
+        let Some(info) = self.paint_info(node) else {
+            return;
+        };
+        let fieldset = self.fieldset_border(node, rect);
+        let rect = fieldset.map_or(rect, |fieldset| fieldset.rect);
+        if !info.background_color.is_transparent() {
+            items.push(DisplayItem::SolidRect {
+                node,
+                rect,
+                clip,
+                color: info.background_color,
+                radii: info.radii,
+            });
+        }
+        let mut sides = info.border;
+        if let Some(fieldset) = fieldset
+            && sides.top.is_visible()
+        {
+            let (without_top, top_segments) = fieldset.split(sides);
+            sides = without_top;
+            for (segment, segment_sides) in top_segments {
+                items.push(DisplayItem::Border {
+                    node,
+                    rect: segment,
+                    clip,
+                    sides: segment_sides,
+                    radii: BorderRadii::default(),
+                });
+            }
+        }
+        if [sides.top, sides.right, sides.bottom, sides.left]
+            .iter()
+            .any(BorderSide::is_visible)
+        {
+            items.push(DisplayItem::Border {
+                node,
+                rect,
+                clip,
+                sides,
+                radii: info.radii,
+            });
+        }
+    }
+
+    /// The `::marker` of a list item whose border box is drawn at `rect`,
+    /// moved with the box by any scrolling.
+    fn push_marker_item(
+        &self,
+        node: NodeId,
+        rect: Rect,
+        clip: Option<Rect>,
+        items: &mut Vec<DisplayItem>,
+    ) {
+        let Some(marker) = self.marker_box(node) else {
+            return;
+        };
+        let Some(used) = self.resolve_used_values(node) else {
+            return;
+        };
+        let border = used.rect(BoxArea::Border);
+        items.push(DisplayItem::Text {
+            node,
+            rect: marker.rect.translate(rect.x - border.x, rect.y - border.y),
+            clip,
+            text: marker.text,
+            color: marker.color,
+            font_size: marker.font_size,
+        });
+    }
+
+    /// The `::before` and `::after` text of an element whose border box is
+    /// drawn at `rect`, moved with the box by any scrolling.
+    fn push_generated_items(
+        &self,
+        node: NodeId,
+        rect: Rect,
+        clip: Option<Rect>,
+        items: &mut Vec<DisplayItem>,
+    ) {
+        let Some(used) = self.resolve_used_values(node) else {
+            return;
+        };
+        let border = used.rect(BoxArea::Border);
+        for pseudo in [GeneratedBox::Before, GeneratedBox::After] {
+            let Some(generated) = self.generated_box(node, pseudo) else {
+                continue;
+            };
+            if generated.text.trim().is_empty() {
+                continue;
+            }
+            items.push(DisplayItem::Text {
+                node,
+                rect: generated
+                    .rect
+                    .translate(rect.x - border.x, rect.y - border.y),
+                clip,
+                text: generated.text,
+                color: generated.color,
+                font_size: generated.font_size,
+            });
+        }
+    }
+
+    /// The text run of a text node drawn at `rect`.
+    fn push_text_item(
+        &self,
+        node: NodeId,
+        rect: Rect,
+        clip: Option<Rect>,
+        resolver: &dyn PropertyResolver,
+        items: &mut Vec<DisplayItem>,
+    ) {
+        let Some(text) = resolver.text_content(node) else {
+            return;
+        };
+        if text.trim().is_empty() {
+            return;
+        }
+        let color = self
+            .used_computed_color(node, &PropertyId::Color)
+            .unwrap_or_else(|| RGBA::new(0, 0, 0, 1.0).into());
+        let font_size = resolver
+            .get_property(node, &PropertyId::FontSize)
+            .map_or(DEFAULT_FONT_SIZE_PX, Subpixel::to_f32);
+        items.push(DisplayItem::Text {
+            node,
+            rect,
+            clip,
+            text,
+            color,
+            font_size,
+        });
+    }
+}
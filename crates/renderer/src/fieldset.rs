@@ -0,0 +1,87 @@
+//! Fieldset borders around the rendered legend.
+//!
+//! A fieldset's rendered legend sits over its top border (HTML §15.3.13).
+//! When the legend's margin box is taller than the border, the border and
+//! background start lower, so the top border runs through the middle of
+//! the legend; either way the top border isn't painted behind it.
+
+use crate::border::{BorderSide, BorderSides};
+use crate::renderer::LayoutState;
+use crate::used_values::BoxArea;
+use lightningcss::properties::border::LineStyle;
+use rewrite_core::{NodeId, PropertyResolver as _, Subpixel};
+use rewrite_layout::Rect;
+
+/// Part of a border drawn as its own item, and the sides it draws.
+pub type BorderSegment = (Rect, BorderSides);
+
+/// Where a fieldset's border is painted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldsetBorder {
+    /// The painted border box: the laid-out one, less any part above the
+    /// top border's line through the legend.
+    pub rect: Rect,
+    /// Horizontal extent of the legend's border box, where the top border
+    /// is left out.
+    pub gap_start: Subpixel,
+    pub gap_end: Subpixel,
+}
+
+impl FieldsetBorder {
+    /// The border sides without the top one, and the top border left and
+    /// right of the legend as `(rect, sides)` pairs drawing only the top.
+    pub fn split(&self, sides: BorderSides) -> (BorderSides, [BorderSegment; 2]) {
+        let hidden = BorderSide {
+            width: Subpixel::ZERO,
+            style: LineStyle::None,
+            ..sides.top
+        };
+        let top_only = BorderSides {
+            top: sides.top,
+            right: hidden,
+            bottom: hidden,
+            left: hidden,
+        };
+        let rect = self.rect;
+        let start = self.gap_start.clamp(rect.x, rect.right());
+        let end = self.gap_end.clamp(start, rect.right());
+        let before = Rect::new(rect.x, rect.y, start - rect.x, sides.top.width);
+        let after = Rect::new(end, rect.y, rect.right() - end, sides.top.width);
+        (
+            BorderSides {
+                top: hidden,
+                ..sides
+            },
+            [(before, top_only), (after, top_only)],
+        )
+    }
+}
+
+impl LayoutState {
+    /// The rendered legend of `node`, if it is a fieldset with one.
+    pub fn rendered_legend(&self, node: NodeId) -> Option<NodeId> {
+        let resolver = self.resolver();
+        resolver
+            .children(node)
+            .into_iter()
+            .find(|&child| resolver.is_rendered_legend(child))
+    }
+
+    /// Where the border of fieldset `node` is painted, when its border box
+    /// is drawn at `rect`. `None` if it has no laid-out rendered legend.
+    pub fn fieldset_border(&self, node: NodeId, rect: Rect) -> Option<FieldsetBorder> {
+        let border = self.resolve_used_values(node)?.rect(BoxArea::Border);
+        let legend = self.resolve_used_values(self.rendered_legend(node)?)?;
+        let legend_margin = legend.rect(BoxArea::Margin);
+        let legend_border = legend.rect(BoxArea::Border);
+        let top_width = self.border_sides(node).top.width;
+        // The border's line runs through the middle of a taller legend.
+        let inset = ((legend_margin.height - top_width) / Subpixel::raw(2)).max(Subpixel::ZERO);
+        let shift_x = rect.x - border.x;
+        Some(FieldsetBorder {
+            rect: Rect::new(rect.x, rect.y + inset, rect.width, rect.height - inset),
+            gap_start: legend_border.x + shift_x,
+            gap_end: legend_border.right() + shift_x,
+        })
+    }
+}
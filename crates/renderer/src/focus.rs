@@ -0,0 +1,91 @@
+//! Focus-ring geometry.
+//!
+//! Computes where an embedder should draw a keyboard focus indicator for a
+//! node, from the node's laid-out boxes and its `outline-*` properties.
+
+use crate::renderer::LayoutState;
+use lightningcss::properties::border::LineStyle;
+use lightningcss::properties::outline::OutlineStyle;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{NodeId, PropertyResolver, Subpixel, outline_offset_id};
+use rewrite_layout::Rect;
+
+/// Ring width used when the node has no outline of its own, matching the
+/// UA `:focus-visible { outline: auto 1px }` rule.
+const DEFAULT_RING_WIDTH: Subpixel = Subpixel::from_px(1);
+
+/// Ring width for a styled outline whose width is a keyword (`medium`).
+pub(crate) const MEDIUM_OUTLINE_WIDTH: Subpixel = Subpixel::from_px(3);
+
+/// Focus indicator geometry for a single node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusRing {
+    /// Outer edge of the ring, in absolute coordinates.
+    pub rect: Rect,
+    /// Thickness of the ring, drawn inward from `rect`.
+    pub width: Subpixel,
+    /// Whether the ring should use the platform's focus style
+    /// (`outline-style: auto`, or no outline set on the node).
+    pub platform_style: bool,
+}
+
+impl LayoutState {
+    /// Compute the focus ring for `node`.
+    ///
+    /// The ring encloses the union of the node's border box and the boxes
+    /// of its laid-out descendants (so an inline element's line fragments
+    /// are all covered), pushed out by `outline-offset` and then by the ring
+    /// width. A node without a usable outline gets a default `auto` ring.
+    /// Returns `None` if the node has no laid-out box.
+    pub fn focus_ring(&self, node: NodeId) -> Option<FocusRing> {
+        let resolver = self.resolver();
+        let bounds = self.fragment_union(node, &resolver)?;
+
+        let style = match resolver.get_css_property(node, &PropertyId::OutlineStyle) {
+            Some(Property::OutlineStyle(style)) => Some(style),
+            _ => None,
+        };
+        let declared_width = resolver.get_property(node, &PropertyId::OutlineWidth);
+
+        let (width, offset, platform_style) = match style {
+            None | Some(OutlineStyle::LineStyle(LineStyle::None)) => {
+                (DEFAULT_RING_WIDTH, Subpixel::ZERO, true)
+            }
+            Some(style) => {
+                let offset = resolver
+                    .get_property(node, &outline_offset_id())
+                    .unwrap_or(Subpixel::ZERO);
+                let is_auto = matches!(style, OutlineStyle::Auto);
+                let fallback = if is_auto {
+                    DEFAULT_RING_WIDTH
+                } else {
+                    MEDIUM_OUTLINE_WIDTH
+                };
+                (declared_width.unwrap_or(fallback), offset, is_auto)
+            }
+        };
+
+        Some(FocusRing {
+            rect: bounds.inflate(offset + width),
+            width,
+            platform_style,
+        })
+    }
+
+    /// Union of the border boxes of `node` and its laid-out descendants.
+    fn fragment_union(&self, node: NodeId, resolver: &dyn PropertyResolver) -> Option<Rect> {
+        let computed = self.cached_box(node);
+        let own = match (computed.x, computed.y, computed.width, computed.height) {
+            (Some(x), Some(y), Some(width), Some(height)) => Some(Rect::new(x, y, width, height)),
+            _ => None,
+        };
+
+        resolver
+            .children(node)
+            .into_iter()
+            .filter_map(|child| self.fragment_union(child, resolver))
+            .fold(own, |acc, rect| {
+                Some(acc.map_or(rect, |bounds| bounds.union(&rect)))
+            })
+    }
+}
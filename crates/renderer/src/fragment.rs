@@ -0,0 +1,188 @@
+//! Fragment tree.
+//!
+//! A snapshot of the laid-out document: one fragment per node that
+//! generates a box, nested like the DOM, with its border box and the area
+//! its content overflows into. Painting, hit testing and scrolling walk
+//! this tree instead of querying layout node by node.
+//!
+//! Boxes are where they are drawn: the content of a scroll container is
+//! shifted by its scroll offset. Layout values (`get_node`, used values)
+//! stay in document coordinates, unaffected by scrolling.
+
+use crate::renderer::LayoutState;
+use lightningcss::properties::overflow::OverflowKeyword;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{NodeId, PropertyResolver, Subpixel};
+use rewrite_layout::{Rect, propagates_overflow_to_viewport};
+
+/// The laid-out box of a single node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    pub node: NodeId,
+    /// Border box, in absolute coordinates.
+    pub rect: Rect,
+    /// Scrollable overflow: the border box united with the overflow of
+    /// every descendant that isn't clipped by an intermediate box. For a
+    /// scroll container, as if it weren't scrolled.
+    pub overflow: Rect,
+    /// The padding box, if descendants are clipped to it (`overflow` other
+    /// than `visible` on either axis).
+    pub clip: Option<Rect>,
+    /// Child fragments in DOM order.
+    pub children: Vec<Self>,
+}
+
+impl Fragment {
+    /// This fragment and all its descendants, in tree order (parents before
+    /// children, siblings in DOM order).
+    pub fn iter(&self) -> Fragments<'_> {
+        Fragments { stack: vec![self] }
+    }
+
+    /// The area this fragment covers as seen by its parent: a clipping
+    /// fragment contributes only its own box.
+    pub(crate) fn overflow_contribution(&self) -> Rect {
+        if self.clip.is_some() {
+            self.rect
+        } else {
+            self.overflow
+        }
+    }
+}
+
+/// Fragments of a whole document at a given viewport.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FragmentTree {
+    /// The initial containing block.
+    pub viewport: Rect,
+    /// Top-level fragments (normally just the document's).
+    pub roots: Vec<Fragment>,
+}
+
+impl FragmentTree {
+    /// Every fragment in the tree, in tree order.
+    pub fn iter(&self) -> Fragments<'_> {
+        Fragments {
+            stack: self.roots.iter().rev().collect(),
+        }
+    }
+
+    /// The fragment generated by `node`, if it has a box.
+    pub fn find(&self, node: NodeId) -> Option<&Fragment> {
+        self.iter().find(|fragment| fragment.node == node)
+    }
+}
+
+impl<'tree> IntoIterator for &'tree Fragment {
+    type Item = &'tree Fragment;
+    type IntoIter = Fragments<'tree>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'tree> IntoIterator for &'tree FragmentTree {
+    type Item = &'tree Fragment;
+    type IntoIter = Fragments<'tree>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Depth-first iterator over fragments. See `FragmentTree::iter`.
+#[derive(Debug, Clone)]
+pub struct Fragments<'tree> {
+    /// Fragments still to visit, next on top.
+    stack: Vec<&'tree Fragment>,
+}
+
+impl<'tree> Iterator for Fragments<'tree> {
+    type Item = &'tree Fragment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let fragment = self.stack.pop()?;
+        self.stack.extend(fragment.children.iter().rev());
+        Some(fragment)
+    }
+}
+
+impl LayoutState {
+    /// Build the fragment tree of the document from the current layout,
+    /// with scroll offsets applied.
+    pub fn fragment_tree(&self) -> FragmentTree {
+        let mut tree = self.unscrolled_fragment_tree();
+        self.apply_scroll_offsets(&mut tree.roots);
+        tree
+    }
+
+    /// The fragment tree in document coordinates, as if no box were
+    /// scrolled.
+    pub(crate) fn unscrolled_fragment_tree(&self) -> FragmentTree {
+        let resolver = self.resolver();
+        let viewport = Rect::from_size(
+            Subpixel::from_f32(resolver.viewport_width() as f32),
+            Subpixel::from_f32(resolver.viewport_height() as f32),
+        );
+        FragmentTree {
+            viewport,
+            roots: self.fragments(NodeId::ROOT, &resolver),
+        }
+    }
+
+    /// Fragments generated by `node`: its own, or, for a boxless
+    /// non-element such as the document, those of its children. Boxless
+    /// elements (`display: none`) hide their whole subtree.
+    pub(crate) fn fragments(&self, node: NodeId, resolver: &dyn PropertyResolver) -> Vec<Fragment> {
+        let computed = self.cached_box(node);
+        let rect = match computed.border_box() {
+            Some(rect) => rect,
+            None if resolver.is_element(node) => return Vec::new(),
+            None => return self.child_fragments(node, resolver),
+        };
+
+        let children = self.child_fragments(node, resolver);
+        let overflow = children
+            .iter()
+            .fold(rect, |acc, child| acc.union(&child.overflow_contribution()));
+        vec![Fragment {
+            node,
+            rect,
+            overflow,
+            clip: clips_overflow(node, resolver)
+                .then(|| self.resolve_used_values(node))
+                .flatten()
+                .map(|used| used.padding_box()),
+            children,
+        }]
+    }
+
+    /// Fragments of the children of `node`, in DOM order.
+    fn child_fragments(&self, node: NodeId, resolver: &dyn PropertyResolver) -> Vec<Fragment> {
+        // Children are stored in reverse DOM order.
+        let mut children = resolver.children(node);
+        children.reverse();
+        children
+            .into_iter()
+            .flat_map(|child| self.fragments(child, resolver))
+            .collect()
+    }
+}
+
+/// Whether `node` clips its content on either axis. Overflow propagated to
+/// the viewport clips the viewport, not the element.
+fn clips_overflow(node: NodeId, resolver: &dyn PropertyResolver) -> bool {
+    if propagates_overflow_to_viewport(node, resolver) {
+        return false;
+    }
+    [PropertyId::OverflowX, PropertyId::OverflowY]
+        .iter()
+        .any(|prop_id| {
+            matches!(
+                resolver.get_css_property(node, prop_id),
+                Some(Property::OverflowX(keyword) | Property::OverflowY(keyword))
+                    if keyword != OverflowKeyword::Visible
+            )
+        })
+}
@@ -0,0 +1,115 @@
+//! `::before` and `::after` boxes of elements.
+//!
+//! Layout makes room for an element's generated boxes next to its real
+//! children (see `rewrite_layout`'s generated boxes). The `::before` box
+//! starts the element's content, after an inside marker. The `::after`
+//! box follows the last inline-level child on its line, or starts a line
+//! below a block-level last child. Both are as tall as their text.
+
+use crate::renderer::LayoutState;
+use crate::used_values::BoxArea;
+use lightningcss::properties::display::{Display, DisplayOutside};
+use lightningcss::properties::list::ListStylePosition;
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::values::color::RGBA;
+use rewrite_core::{GeneratedBox, NodeId, PropertyResolver, Subpixel};
+use rewrite_css::ComputedColor;
+use rewrite_layout::Rect;
+
+/// A `::before` or `::after` box.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedContentBox {
+    /// The element generating the box.
+    pub element: NodeId,
+    pub pseudo: GeneratedBox,
+    /// The text `content` evaluates to.
+    pub text: String,
+    /// Where the text is laid out, in document coordinates.
+    pub rect: Rect,
+    /// Font size of the text in px.
+    pub font_size: f32,
+    pub color: ComputedColor,
+}
+
+impl LayoutState {
+    /// The `pseudo` box of `node`. `None` if `node` isn't laid out or
+    /// generates no such box.
+    pub fn generated_box(&self, node: NodeId, pseudo: GeneratedBox) -> Option<GeneratedContentBox> {
+        let generated = self.styler.generated_content(node, pseudo)?;
+        let content = self.resolve_used_values(node)?.content_box();
+        let resolver = self.resolver();
+        let measured = resolver.measure_generated(node, pseudo)?;
+        let width = Subpixel::from_f32(measured.width);
+        // The `::before` box follows an inside marker.
+        let line_start = content.x
+            + self
+                .marker_box(node)
+                .filter(|marker| marker.position == ListStylePosition::Inside)
+                .map_or(Subpixel::ZERO, |marker| marker.rect.width);
+        let (x, y) = match pseudo {
+            GeneratedBox::Before => (line_start, content.y),
+            GeneratedBox::After => self
+                .last_in_flow_child(node, &resolver)
+                .and_then(|child| Some((child, self.resolve_used_values(child)?)))
+                .map_or_else(
+                    || {
+                        let before = resolver
+                            .measure_generated(node, GeneratedBox::Before)
+                            .map_or(Subpixel::ZERO, |before| Subpixel::from_f32(before.width));
+                        (line_start + before, content.y)
+                    },
+                    |(child, used)| {
+                        let margin_box = used.rect(BoxArea::Margin);
+                        if is_block_level(child, &resolver) {
+                            (content.x, margin_box.y + margin_box.height)
+                        } else {
+                            (
+                                margin_box.x + margin_box.width,
+                                used.rect(BoxArea::Border).y,
+                            )
+                        }
+                    },
+                ),
+        };
+        let font_size = resolver.pseudo_font_size(node, generated.property(&PropertyId::FontSize));
+        let color = match generated.property(&PropertyId::Color) {
+            Some(Property::Color(color)) => ComputedColor::from_css(color),
+            _ => None,
+        }
+        .or_else(|| self.used_computed_color(node, &PropertyId::Color))
+        .unwrap_or_else(|| RGBA::new(0, 0, 0, 1.0).into());
+        Some(GeneratedContentBox {
+            element: node,
+            pseudo,
+            text: generated.text,
+            rect: Rect::new(x, y, width, Subpixel::from_f32(measured.height)),
+            font_size,
+            color,
+        })
+    }
+
+    /// The last child of `node` in document order that takes room in its
+    /// flow.
+    fn last_in_flow_child(&self, node: NodeId, resolver: &dyn PropertyResolver) -> Option<NodeId> {
+        // The resolver lists children in reverse document order.
+        resolver.children(node).into_iter().find(|&child| {
+            if resolver.is_element(child) {
+                !resolver.is_out_of_flow(child) && resolver.generates_box(child)
+            } else {
+                resolver
+                    .text_content(child)
+                    .is_some_and(|text| !text.trim().is_empty())
+            }
+        })
+    }
+}
+
+fn is_block_level(node: NodeId, resolver: &dyn PropertyResolver) -> bool {
+    resolver.is_element(node)
+        && !resolver.is_intrinsic(node)
+        && !matches!(
+            resolver.get_css_property(node, &PropertyId::Display),
+            Some(Property::Display(Display::Pair(pair)))
+                if matches!(pair.outside, DisplayOutside::Inline)
+        )
+}
@@ -0,0 +1,88 @@
+//! Geometry changes between layout passes.
+//!
+//! Incremental layout already compares each re-resolved node's box with
+//! its previous one to decide whether to propagate. The layout state
+//! remembers the box each such node had when the diff was last taken, so
+//! embedders can animate changes (FLIP-style) from `take_geometry_diff`
+//! without keeping their own copy of every rect.
+
+use crate::renderer::{ComputedBox, LayoutState};
+use rewrite_core::NodeId;
+use rewrite_layout::Rect;
+
+/// A node's border box before and after, in absolute coordinates. `None`
+/// when the node had no box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeometryChange {
+    pub node: NodeId,
+    pub old: Option<Rect>,
+    pub new: Option<Rect>,
+}
+
+/// Border-box changes since the previous diff, each list in node order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeometryDiff {
+    /// Same size, different position.
+    pub moved: Vec<GeometryChange>,
+    /// Different size; the position may have changed too.
+    pub resized: Vec<GeometryChange>,
+    /// Boxes that didn't exist before (new nodes, `display` no longer
+    /// `none`).
+    pub appeared: Vec<GeometryChange>,
+    /// Boxes that no longer exist.
+    pub disappeared: Vec<GeometryChange>,
+}
+
+impl GeometryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.moved.is_empty()
+            && self.resized.is_empty()
+            && self.appeared.is_empty()
+            && self.disappeared.is_empty()
+    }
+
+    fn push(&mut self, change: GeometryChange) {
+        let list = match (change.old, change.new) {
+            (Some(old), Some(new)) if old == new => return,
+            (Some(old), Some(new)) if old.width == new.width && old.height == new.height => {
+                &mut self.moved
+            }
+            (Some(_), Some(_)) => &mut self.resized,
+            (None, Some(_)) => &mut self.appeared,
+            (Some(_), None) => &mut self.disappeared,
+            (None, None) => return,
+        };
+        list.push(change);
+    }
+}
+
+impl ComputedBox {
+    /// The border box, if every dimension is resolved.
+    pub fn border_box(&self) -> Option<Rect> {
+        Some(Rect::new(self.x?, self.y?, self.width?, self.height?))
+    }
+}
+
+impl LayoutState {
+    /// Remember `old` as `node`'s box before the current changes, unless
+    /// the node already changed since the last diff.
+    pub(crate) fn record_geometry(&mut self, node: NodeId, old: &ComputedBox) {
+        self.geometry_baseline
+            .entry(node)
+            .or_insert_with(|| old.border_box());
+    }
+
+    /// Border boxes that changed since the last call, with their box at
+    /// that time. Nodes that changed and changed back are left out.
+    pub fn take_geometry_diff(&mut self) -> GeometryDiff {
+        let mut baseline: Vec<(NodeId, Option<Rect>)> = self.geometry_baseline.drain().collect();
+        baseline.sort_unstable_by_key(|(node, _)| node.0);
+
+        let mut diff = GeometryDiff::default();
+        for (node, old) in baseline {
+            let new = self.cached_box(node).border_box();
+            diff.push(GeometryChange { node, old, new });
+        }
+        diff
+    }
+}
@@ -0,0 +1,117 @@
+//! Hit testing.
+//!
+//! Finds the node an input event at a point is routed to: the topmost box
+//! in paint order whose border box contains the point. Border boxes are
+//! the used ones, so relative offsets, floats and `z-index` are all taken
+//! into account. Boxes that don't take pointer input are passed over, and
+//! the boxes under them are considered instead:
+//!
+//! - `pointer-events: none` skips the box itself only. The property is
+//!   inherited, so descendants skip too unless they set `auto` again.
+//! - `visibility: hidden` and `collapse` skip the box, with the same
+//!   inheritance.
+//! - An `inert` attribute makes the element and its whole subtree
+//!   unhittable, whatever their styles.
+//!
+//! Boxes are hit where they are drawn: content of scrolled boxes is
+//! shifted by the scroll offset, and content clipped by an ancestor's
+//! `overflow` is only hit inside the clip.
+
+use crate::renderer::LayoutState;
+use lightningcss::properties::display::Visibility;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{NodeId, PropertyResolver, Subpixel, pointer_events_id};
+use rewrite_css::{CssValue, values_from_tokens};
+use rewrite_html::{DomTree, NodeData};
+use rewrite_layout::Rect;
+use std::collections::HashMap;
+use std::iter;
+
+impl LayoutState {
+    /// The node under `(x, y)`, in absolute coordinates, or `None` if no
+    /// box there takes pointer input. A point over text hits the element
+    /// around the text.
+    pub fn hit_test(&self, x: Subpixel, y: Subpixel) -> Option<NodeId> {
+        let resolver = self.resolver();
+        self.hit_box(x, y).map(|node| element_of(node, &resolver))
+    }
+
+    /// The topmost box under `(x, y)` that takes pointer input, which is a
+    /// text node's box when the point is over text.
+    pub(crate) fn hit_box(&self, x: Subpixel, y: Subpixel) -> Option<NodeId> {
+        let resolver = self.resolver();
+        let tree = self.styler.tree();
+        let fragments = self.fragment_tree();
+        let placed: HashMap<NodeId, Rect> = fragments
+            .iter()
+            .map(|fragment| (fragment.node, fragment.rect))
+            .collect();
+        let clips = fragments.clip_rects();
+        self.paint_order().into_iter().rev().find(|&node| {
+            placed
+                .get(&node)
+                .is_some_and(|rect| rect.contains_point(x, y))
+                && clips
+                    .get(&node)
+                    .is_none_or(|clip| clip.contains_point(x, y))
+                && is_hittable(element_of(node, &resolver), &resolver, tree)
+        })
+    }
+
+    /// The node `hit_test` finds at `(x, y)` followed by the elements
+    /// around it, innermost first: the chain an event bubbles through.
+    /// Empty if nothing there takes pointer input.
+    pub fn hit_test_all(&self, x: Subpixel, y: Subpixel) -> Vec<NodeId> {
+        let resolver = self.resolver();
+        let parent = |&node: &NodeId| {
+            resolver
+                .parent(node)
+                .filter(|&parent| parent != node && parent != NodeId::ROOT)
+        };
+        iter::successors(self.hit_test(x, y), parent).collect()
+    }
+}
+
+/// `node` if it's an element, otherwise the element around it.
+fn element_of(node: NodeId, resolver: &dyn PropertyResolver) -> NodeId {
+    if resolver.is_element(node) {
+        node
+    } else {
+        resolver.parent(node).unwrap_or(node)
+    }
+}
+
+/// Whether `node`'s box takes pointer input.
+fn is_hittable(node: NodeId, resolver: &dyn PropertyResolver, tree: &DomTree) -> bool {
+    let visible = !matches!(
+        resolver.get_css_property(node, &PropertyId::Visibility),
+        Some(Property::Visibility(
+            Visibility::Hidden | Visibility::Collapse
+        ))
+    );
+    let ignores_pointer = match resolver.get_css_property(node, &pointer_events_id()) {
+        Some(Property::Custom(custom)) => matches!(
+            values_from_tokens(&custom.value).as_slice(),
+            [CssValue::Keyword(keyword)] if keyword == "none"
+        ),
+        _ => false,
+    };
+    visible && !ignores_pointer && !is_inert(node, tree)
+}
+
+/// Whether `node` or an element around it has the `inert` attribute.
+fn is_inert(node: NodeId, tree: &DomTree) -> bool {
+    let Some(inert) = tree.interner.get("inert") else {
+        return false;
+    };
+    let mut current = Some(node);
+    while let Some(id) = current {
+        if let Some(NodeData::Element { attributes, .. }) = tree.get_node(id)
+            && attributes.get(&inert).is_some()
+        {
+            return true;
+        }
+        current = tree.parent(id);
+    }
+    false
+}
@@ -33,6 +33,41 @@
 //! The GPU traverses the formula graph and applies transformed deltas,
 //! marking affected tiles for redraw.
 
+pub mod border;
+pub mod container_metrics;
+pub mod cursor;
+pub mod display_list;
+pub mod fieldset;
+pub mod focus;
+pub mod fragment;
+pub mod generated;
+pub mod geometry_diff;
+pub mod hit_test;
+pub mod marker;
+pub mod paint_info;
+pub mod paint_order;
 pub mod renderer;
+pub mod scroll;
+pub mod scroll_anchor;
+pub mod text_fragment;
+pub mod text_style;
+pub mod used_values;
 
+pub use border::{BorderSide, BorderSides};
+pub use container_metrics::ContainerMetrics;
+pub use cursor::{Cursor, CursorImage};
+pub use display_list::{DisplayItem, DisplayList};
+pub use fieldset::FieldsetBorder;
+pub use focus::FocusRing;
+pub use fragment::{Fragment, FragmentTree, Fragments};
+pub use generated::GeneratedContentBox;
+pub use geometry_diff::{GeometryChange, GeometryDiff};
+pub use marker::MarkerBox;
+pub use paint_info::{BorderRadii, CornerRadius, Outline, PaintInfo};
+pub use paint_order::{PaintOrderMismatch, StackingTree};
 pub use renderer::{ComputedBox, LayoutState, Renderer};
+pub use scroll::ScrollMetrics;
+pub use scroll_anchor::ScrollAnchor;
+pub use text_fragment::{TextRange, TextTarget};
+pub use text_style::TextStyle;
+pub use used_values::{BoxArea, UsedValues};
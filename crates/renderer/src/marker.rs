@@ -0,0 +1,63 @@
+//! Marker boxes of list items.
+//!
+//! Each `display: list-item` element generates a `::marker` box before its
+//! content (CSS Lists 3 §3). An `outside` marker sits in the marker area:
+//! it ends at the start of the item's content box, over the padding the
+//! list indents its items by, and takes no room in the item. An `inside`
+//! marker starts the item's first line, which layout has already moved
+//! along to make room. Both share the top of the item's content box with
+//! its first line.
+
+use crate::renderer::LayoutState;
+use lightningcss::properties::list::ListStylePosition;
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::values::color::RGBA;
+use rewrite_core::{NodeId, PropertyResolver, Subpixel};
+use rewrite_css::ComputedColor;
+use rewrite_layout::Rect;
+
+/// The `::marker` box of a list item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkerBox {
+    /// The list item.
+    pub item: NodeId,
+    /// The text the marker shows, suffix included.
+    pub text: String,
+    pub position: ListStylePosition,
+    /// Where the marker's text is laid out, in document coordinates.
+    pub rect: Rect,
+    /// Font size of the marker's text in px.
+    pub font_size: f32,
+    pub color: ComputedColor,
+}
+
+impl LayoutState {
+    /// The marker box of `node`. `None` if it isn't a laid-out list item
+    /// or its marker is suppressed.
+    pub fn marker_box(&self, node: NodeId) -> Option<MarkerBox> {
+        let marker = self.styler.marker(node)?;
+        let content = self.resolve_used_values(node)?.content_box();
+        let resolver = self.resolver();
+        let measured = resolver.measure_marker(node)?;
+        let width = Subpixel::from_f32(measured.width);
+        let x = match marker.position {
+            ListStylePosition::Outside => content.x - width,
+            ListStylePosition::Inside => content.x,
+        };
+        let font_size = resolver.pseudo_font_size(node, marker.property(&PropertyId::FontSize));
+        let color = match marker.property(&PropertyId::Color) {
+            Some(Property::Color(color)) => ComputedColor::from_css(color),
+            _ => None,
+        }
+        .or_else(|| self.used_computed_color(node, &PropertyId::Color))
+        .unwrap_or_else(|| RGBA::new(0, 0, 0, 1.0).into());
+        Some(MarkerBox {
+            item: node,
+            text: marker.text,
+            position: marker.position,
+            rect: Rect::new(x, content.y, width, Subpixel::from_f32(measured.height)),
+            font_size,
+            color,
+        })
+    }
+}
@@ -0,0 +1,342 @@
+//! Everything needed to paint one box.
+//!
+//! Painting a box reads its geometry from layout and a dozen properties
+//! from style, several of which (percentage radii, `transform-origin`,
+//! outline geometry) only make sense relative to the laid-out box.
+//! `PaintInfo` resolves all of them together so a renderer consumes one
+//! struct per box.
+
+use crate::border::BorderSides;
+use crate::focus::MEDIUM_OUTLINE_WIDTH;
+use crate::renderer::LayoutState;
+use lightningcss::properties::border::LineStyle;
+use lightningcss::properties::outline::OutlineStyle;
+use lightningcss::properties::transform::{Matrix3d, Transform, TransformList};
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::values::color::RGBA;
+use lightningcss::values::length::{LengthPercentage, LengthValue};
+use lightningcss::values::percentage::DimensionPercentage;
+use lightningcss::values::position::{
+    HorizontalPositionKeyword, PositionComponent, VerticalPositionKeyword,
+};
+use lightningcss::vendor_prefix::VendorPrefix;
+use rewrite_core::{NodeId, PropertyResolver, Subpixel, outline_offset_id};
+use rewrite_css::ComputedColor;
+use rewrite_css::value_resolver::{NodeContext, resolve_length_percentage};
+use rewrite_layout::Rect;
+
+/// One rounded corner's radii in px.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CornerRadius {
+    pub horizontal: Subpixel,
+    pub vertical: Subpixel,
+}
+
+impl CornerRadius {
+    fn is_zero(&self) -> bool {
+        self.horizontal <= Subpixel::ZERO || self.vertical <= Subpixel::ZERO
+    }
+}
+
+/// Used border radii, scaled down so adjacent corners don't overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BorderRadii {
+    pub top_left: CornerRadius,
+    pub top_right: CornerRadius,
+    pub bottom_right: CornerRadius,
+    pub bottom_left: CornerRadius,
+}
+
+impl BorderRadii {
+    /// Whether every corner is square.
+    pub fn is_zero(&self) -> bool {
+        [
+            self.top_left,
+            self.top_right,
+            self.bottom_right,
+            self.bottom_left,
+        ]
+        .iter()
+        .all(CornerRadius::is_zero)
+    }
+}
+
+/// A box's outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outline {
+    /// Outer edge of the outline, in absolute coordinates: the border box
+    /// pushed out by `outline-offset` and the width.
+    pub rect: Rect,
+    /// Thickness, drawn inward from `rect`.
+    pub width: Subpixel,
+    pub style: OutlineStyle,
+    pub color: ComputedColor,
+}
+
+/// Paint-time values for a single box.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaintInfo {
+    /// Border box, in absolute coordinates.
+    pub border_box: Rect,
+    pub background_color: ComputedColor,
+    pub border: BorderSides,
+    pub radii: BorderRadii,
+    /// `None` when the outline style is `none` or its width is zero.
+    pub outline: Option<Outline>,
+    /// Opacity of the box and its descendants as a group, 0 to 1.
+    pub opacity: f32,
+    /// The `transform` in absolute coordinates, applied about
+    /// `transform-origin`, in lightningcss's row-vector convention. `None`
+    /// without a transform, or when it can't be reduced to a matrix (font-
+    /// relative translations).
+    pub transform: Option<Matrix3d<f32>>,
+    /// Color of the text insertion caret, for editable content.
+    /// `caret-color: auto` is the box's `color`.
+    pub caret_color: ComputedColor,
+    /// Accent of checkboxes, radio buttons, range sliders and progress
+    /// bars. `accent-color: auto` is the platform accent.
+    pub accent_color: ComputedColor,
+}
+
+impl LayoutState {
+    /// Assemble the paint info of `node`. `None` if it has no laid-out box.
+    pub fn paint_info(&self, node: NodeId) -> Option<PaintInfo> {
+        let resolver = self.resolver();
+        let border_box = self.cached_box(node).border_box()?;
+        let background_color = self
+            .used_computed_color(node, &PropertyId::BackgroundColor)
+            .unwrap_or_else(|| RGBA::transparent().into());
+        let opacity = match resolver.get_css_property(node, &PropertyId::Opacity) {
+            Some(Property::Opacity(opacity)) => opacity.0.clamp(0.0, 1.0),
+            _ => 1.0,
+        };
+        let theme_color = |prop_id| {
+            self.used_computed_color(node, &prop_id)
+                .unwrap_or_else(|| RGBA::new(0, 0, 0, 1.0).into())
+        };
+        Some(PaintInfo {
+            border_box,
+            background_color,
+            border: self.border_sides(node),
+            radii: border_radii(node, &border_box, &resolver),
+            outline: self.outline(node, &border_box, &resolver),
+            opacity,
+            transform: transform_matrix(node, &border_box, &resolver),
+            caret_color: theme_color(PropertyId::CaretColor),
+            accent_color: theme_color(PropertyId::AccentColor),
+        })
+    }
+
+    fn outline(
+        &self,
+        node: NodeId,
+        border_box: &Rect,
+        resolver: &dyn PropertyResolver,
+    ) -> Option<Outline> {
+        let Some(Property::OutlineStyle(style)) =
+            resolver.get_css_property(node, &PropertyId::OutlineStyle)
+        else {
+            return None;
+        };
+        if matches!(style, OutlineStyle::LineStyle(LineStyle::None)) {
+            return None;
+        }
+        let width = resolver
+            .get_property(node, &PropertyId::OutlineWidth)
+            .unwrap_or(MEDIUM_OUTLINE_WIDTH);
+        if width <= Subpixel::ZERO {
+            return None;
+        }
+        let offset = resolver
+            .get_property(node, &outline_offset_id())
+            .unwrap_or(Subpixel::ZERO);
+        let color = self
+            .used_computed_color(node, &PropertyId::OutlineColor)
+            .unwrap_or_else(|| RGBA::transparent().into());
+        Some(Outline {
+            rect: border_box.inflate(offset + width),
+            width,
+            style,
+            color,
+        })
+    }
+}
+
+/// A length or percentage in px, percentages against `basis`. A math
+/// function without a result resolves to zero.
+fn length_percentage(value: &LengthPercentage, basis: Subpixel, ctx: &NodeContext<'_>) -> Subpixel {
+    resolve_length_percentage(value, basis, ctx).unwrap_or(Subpixel::ZERO)
+}
+
+/// Used radii of the four corners (CSS Backgrounds 3 §5.5).
+fn border_radii(node: NodeId, border_box: &Rect, resolver: &dyn PropertyResolver) -> BorderRadii {
+    let ctx = NodeContext { node, resolver };
+    let prefix = VendorPrefix::None;
+    let corner = |prop_id: PropertyId<'static>| match resolver.get_css_property(node, &prop_id) {
+        Some(
+            Property::BorderTopLeftRadius(size, _)
+            | Property::BorderTopRightRadius(size, _)
+            | Property::BorderBottomRightRadius(size, _)
+            | Property::BorderBottomLeftRadius(size, _),
+        ) => CornerRadius {
+            horizontal: length_percentage(&size.0, border_box.width, &ctx).max(Subpixel::ZERO),
+            vertical: length_percentage(&size.1, border_box.height, &ctx).max(Subpixel::ZERO),
+        },
+        _ => CornerRadius::default(),
+    };
+    let radii = BorderRadii {
+        top_left: corner(PropertyId::BorderTopLeftRadius(prefix)),
+        top_right: corner(PropertyId::BorderTopRightRadius(prefix)),
+        bottom_right: corner(PropertyId::BorderBottomRightRadius(prefix)),
+        bottom_left: corner(PropertyId::BorderBottomLeftRadius(prefix)),
+    };
+
+    // Radii on one side that add up to more than the side are all scaled
+    // down by the same factor.
+    let fits = |length: Subpixel, first: Subpixel, second: Subpixel| {
+        let sum = (first + second).to_f32();
+        if sum > 0.0 {
+            length.to_f32() / sum
+        } else {
+            1.0
+        }
+    };
+    let factor = [
+        fits(
+            border_box.width,
+            radii.top_left.horizontal,
+            radii.top_right.horizontal,
+        ),
+        fits(
+            border_box.width,
+            radii.bottom_left.horizontal,
+            radii.bottom_right.horizontal,
+        ),
+        fits(
+            border_box.height,
+            radii.top_left.vertical,
+            radii.bottom_left.vertical,
+        ),
+        fits(
+            border_box.height,
+            radii.top_right.vertical,
+            radii.bottom_right.vertical,
+        ),
+    ]
+    .into_iter()
+    .fold(1.0f32, f32::min);
+    if factor >= 1.0 {
+        return radii;
+    }
+    let scale = |radius: CornerRadius| CornerRadius {
+        horizontal: Subpixel::from_f32(radius.horizontal.to_f32() * factor),
+        vertical: Subpixel::from_f32(radius.vertical.to_f32() * factor),
+    };
+    BorderRadii {
+        top_left: scale(radii.top_left),
+        top_right: scale(radii.top_right),
+        bottom_right: scale(radii.bottom_right),
+        bottom_left: scale(radii.bottom_left),
+    }
+}
+
+/// Replace percentages in translations with px against the border box,
+/// since lightningcss can only build matrices from absolute lengths.
+fn resolve_translation(transform: &Transform, border_box: &Rect) -> Transform {
+    let absolute = |value: &LengthPercentage, basis: Subpixel| match value {
+        DimensionPercentage::Percentage(pct) => {
+            DimensionPercentage::Dimension(LengthValue::Px(basis.to_f32() * pct.0))
+        }
+        other => other.clone(),
+    };
+    let (width, height) = (border_box.width, border_box.height);
+    match transform {
+        Transform::Translate(along_x, along_y) => {
+            Transform::Translate(absolute(along_x, width), absolute(along_y, height))
+        }
+        Transform::TranslateX(along_x) => Transform::TranslateX(absolute(along_x, width)),
+        Transform::TranslateY(along_y) => Transform::TranslateY(absolute(along_y, height)),
+        Transform::Translate3d(along_x, along_y, along_z) => Transform::Translate3d(
+            absolute(along_x, width),
+            absolute(along_y, height),
+            along_z.clone(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// The point `transform-origin` names, in absolute coordinates. Defaults to
+/// the center of the border box.
+fn transform_origin(
+    node: NodeId,
+    border_box: &Rect,
+    resolver: &dyn PropertyResolver,
+) -> (Subpixel, Subpixel) {
+    let ctx = NodeContext { node, resolver };
+    let half = |length: Subpixel| Subpixel::from_f32(length.to_f32() / 2.0);
+    let Some(Property::TransformOrigin(origin, _)) =
+        resolver.get_css_property(node, &PropertyId::TransformOrigin(VendorPrefix::None))
+    else {
+        return (
+            border_box.x + half(border_box.width),
+            border_box.y + half(border_box.height),
+        );
+    };
+    let x = match &origin.x {
+        PositionComponent::Center => half(border_box.width),
+        PositionComponent::Length(length) => length_percentage(length, border_box.width, &ctx),
+        PositionComponent::Side { side, offset } => {
+            let offset = offset.as_ref().map_or(Subpixel::ZERO, |offset| {
+                length_percentage(offset, border_box.width, &ctx)
+            });
+            match side {
+                HorizontalPositionKeyword::Left => offset,
+                HorizontalPositionKeyword::Right => border_box.width - offset,
+            }
+        }
+    };
+    let y = match &origin.y {
+        PositionComponent::Center => half(border_box.height),
+        PositionComponent::Length(length) => length_percentage(length, border_box.height, &ctx),
+        PositionComponent::Side { side, offset } => {
+            let offset = offset.as_ref().map_or(Subpixel::ZERO, |offset| {
+                length_percentage(offset, border_box.height, &ctx)
+            });
+            match side {
+                VerticalPositionKeyword::Top => offset,
+                VerticalPositionKeyword::Bottom => border_box.height - offset,
+            }
+        }
+    };
+    (border_box.x + x, border_box.y + y)
+}
+
+/// The box's transform as an absolute-coordinate matrix about its origin.
+fn transform_matrix(
+    node: NodeId,
+    border_box: &Rect,
+    resolver: &dyn PropertyResolver,
+) -> Option<Matrix3d<f32>> {
+    let Some(Property::Transform(list, _)) =
+        resolver.get_css_property(node, &PropertyId::Transform(VendorPrefix::None))
+    else {
+        return None;
+    };
+    if list.0.is_empty() {
+        return None;
+    }
+    let absolute_list = TransformList(
+        list.0
+            .iter()
+            .map(|transform| resolve_translation(transform, border_box))
+            .collect(),
+    );
+    let matrix = absolute_list.to_matrix()?;
+    let (origin_x, origin_y) = transform_origin(node, border_box, resolver);
+    let (origin_x, origin_y) = (origin_x.to_f32(), origin_y.to_f32());
+    Some(
+        Matrix3d::translate(-origin_x, -origin_y, 0.0)
+            .multiply(&matrix)
+            .multiply(&Matrix3d::translate(origin_x, origin_y, 0.0)),
+    )
+}
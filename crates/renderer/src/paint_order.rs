@@ -0,0 +1,458 @@
+//! Paint order.
+//!
+//! Stacking contexts and positioned boxes form a tree of layers (CSS 2.2
+//! Appendix E). Every stacking context keeps the layers stacked in it
+//! sorted by `z-index`, then tree order, and painting flattens the tree
+//! back to front. `z-index` rarely changes between frames, so rather than
+//! rebuilding the tree on every pass `LayoutState` patches it as style
+//! changes arrive: a new `z-index` moves one entry within its context, a
+//! box that becomes or stops being a positioned box is inserted or
+//! removed, and only a box that becomes or stops being a stacking context
+//! rebuilds the context around it. With `LayoutFlags::verify_paint_order`
+//! every patch is checked against a full rebuild.
+//!
+//! Within a layer, painting follows the phases of Appendix E: in-flow
+//! boxes, then floats, then text, so text in normal flow is drawn over a
+//! float that overlaps its block.
+
+use crate::renderer::LayoutState;
+use lightningcss::properties::PropertyId;
+use rewrite_core::{NodeId, PropertyResolver};
+use rewrite_layout::{creates_stacking_context, is_positioned, used_z_index};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::mem;
+
+/// A box painted as a unit with its in-flow descendants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Layer {
+    /// The stacking context the layer is stacked in. The document is
+    /// stacked in itself.
+    context: NodeId,
+    z_index: i32,
+    /// Whether the layer is a stacking context. Positioned boxes with
+    /// `z-index: auto` aren't: the layers inside them are stacked in
+    /// `context`.
+    is_context: bool,
+    /// Layers stacked in this one, by `z-index` and then tree order.
+    /// Empty unless `is_context`.
+    stacked: Vec<NodeId>,
+}
+
+/// The stacking contexts and positioned boxes of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackingTree {
+    layers: HashMap<NodeId, Layer>,
+}
+
+impl Default for StackingTree {
+    fn default() -> Self {
+        let root = Layer {
+            context: NodeId::ROOT,
+            z_index: 0,
+            is_context: true,
+            stacked: Vec::new(),
+        };
+        Self {
+            layers: HashMap::from([(NodeId::ROOT, root)]),
+        }
+    }
+}
+
+/// Whether a change of `prop_id` on an element can make it start or stop
+/// being a layer, or change its `z-index`. `display` counts because it
+/// decides whether the children are flex or grid items.
+pub(crate) fn affects_paint_order(prop_id: &PropertyId<'static>) -> bool {
+    matches!(
+        prop_id,
+        PropertyId::Position
+            | PropertyId::ZIndex
+            | PropertyId::Opacity
+            | PropertyId::Display
+            | PropertyId::Transform(_)
+            | PropertyId::Perspective(_)
+            | PropertyId::Filter(_)
+            | PropertyId::BackdropFilter(_)
+    )
+}
+
+/// Whether `node` is a layer, and if so whether it is a stacking context
+/// and its `z-index`.
+fn classify(node: NodeId, resolver: &dyn PropertyResolver) -> Option<(bool, i32)> {
+    if node == NodeId::ROOT || !resolver.is_element(node) {
+        return None;
+    }
+    if creates_stacking_context(node, resolver) {
+        return Some((true, used_z_index(node, resolver).unwrap_or(0)));
+    }
+    is_positioned(node, resolver).then_some((false, 0))
+}
+
+/// Children of `node` in DOM order.
+fn children_in_order(node: NodeId, resolver: &dyn PropertyResolver) -> Vec<NodeId> {
+    // Children are stored in reverse DOM order.
+    let mut children = resolver.children(node);
+    children.reverse();
+    children
+}
+
+/// `node` and its ancestors, outermost first.
+fn ancestry(node: NodeId, resolver: &dyn PropertyResolver) -> Vec<NodeId> {
+    let mut chain = vec![node];
+    let mut current = node;
+    while let Some(parent) = resolver.parent(current) {
+        if parent == current {
+            break;
+        }
+        chain.push(parent);
+        current = parent;
+    }
+    chain.reverse();
+    chain
+}
+
+/// Compare two nodes by their position in a preorder walk of the DOM.
+fn tree_order(first: NodeId, second: NodeId, resolver: &dyn PropertyResolver) -> Ordering {
+    let first_chain = ancestry(first, resolver);
+    let second_chain = ancestry(second, resolver);
+    let shared = first_chain
+        .iter()
+        .zip(&second_chain)
+        .take_while(|(left, right)| left == right)
+        .count();
+    match (first_chain.get(shared), second_chain.get(shared)) {
+        (Some(&left), Some(&right)) => {
+            if resolver.prev_siblings(right).contains(&left) {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        }
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+impl StackingTree {
+    /// Build the tree of the whole document from scratch.
+    pub fn build(resolver: &dyn PropertyResolver) -> Self {
+        let mut tree = Self::default();
+        tree.collect(NodeId::ROOT, NodeId::ROOT, resolver);
+        tree.sort_stacked(NodeId::ROOT);
+        tree
+    }
+
+    /// The layers stacked in `context`, back to front. Empty if `context`
+    /// isn't a stacking context.
+    pub fn stacked(&self, context: NodeId) -> &[NodeId] {
+        self.layers
+            .get(&context)
+            .map_or(&[], |layer| layer.stacked.as_slice())
+    }
+
+    /// Add the layers among the descendants of `node` to the tree, with
+    /// the outermost ones stacked in `context`.
+    fn collect(&mut self, node: NodeId, context: NodeId, resolver: &dyn PropertyResolver) {
+        for child in children_in_order(node, resolver) {
+            let Some((is_context, z_index)) = classify(child, resolver) else {
+                self.collect(child, context, resolver);
+                continue;
+            };
+            self.layers.insert(
+                child,
+                Layer {
+                    context,
+                    z_index,
+                    is_context,
+                    stacked: Vec::new(),
+                },
+            );
+            if let Some(layer) = self.layers.get_mut(&context) {
+                layer.stacked.push(child);
+            }
+            if is_context {
+                self.collect(child, child, resolver);
+                self.sort_stacked(child);
+            } else {
+                self.collect(child, context, resolver);
+            }
+        }
+    }
+
+    /// Sort the layers stacked in `context`, which are in tree order, by
+    /// `z-index`. The sort is stable, so ties stay in tree order.
+    fn sort_stacked(&mut self, context: NodeId) {
+        let Some(mut stacked) = self
+            .layers
+            .get_mut(&context)
+            .map(|layer| mem::take(&mut layer.stacked))
+        else {
+            return;
+        };
+        stacked.sort_by_key(|node| self.layers.get(node).map_or(0, |layer| layer.z_index));
+        if let Some(layer) = self.layers.get_mut(&context) {
+            layer.stacked = stacked;
+        }
+    }
+
+    /// The nearest stacking context around `node`, not counting `node`.
+    fn enclosing_context(&self, node: NodeId, resolver: &dyn PropertyResolver) -> NodeId {
+        let mut current = node;
+        while let Some(parent) = resolver.parent(current) {
+            if parent == current {
+                break;
+            }
+            if self
+                .layers
+                .get(&parent)
+                .is_some_and(|layer| layer.is_context)
+            {
+                return parent;
+            }
+            current = parent;
+        }
+        NodeId::ROOT
+    }
+
+    /// Update the tree after a change to `node` that may have changed
+    /// whether it is a layer or its `z-index`.
+    pub fn node_changed(&mut self, node: NodeId, resolver: &dyn PropertyResolver) {
+        // Nodes are styled before they're inserted; until then they aren't
+        // painted at all.
+        if resolver.parent(node).is_none() {
+            return;
+        }
+        let old = self
+            .layers
+            .get(&node)
+            .map(|layer| (layer.is_context, layer.z_index));
+        let new = classify(node, resolver);
+        match (old, new) {
+            _ if old == new || node == NodeId::ROOT => {}
+            (Some((was_context, _)), Some((is_context, z_index))) if was_context == is_context => {
+                self.restack(node, z_index, resolver);
+            }
+            // Nothing is stacked differently when a positioned box appears,
+            // or a stacking context appears with nothing inside it yet.
+            (None, Some((is_context, z_index)))
+                if !is_context || resolver.children(node).is_empty() =>
+            {
+                let layer = Layer {
+                    context: self.enclosing_context(node, resolver),
+                    z_index,
+                    is_context,
+                    stacked: Vec::new(),
+                };
+                self.insert(node, layer, resolver);
+            }
+            // Layers inside a positioned box are stacked in its context
+            // already, so they stay where they are.
+            (Some((false, _)), None) => {
+                self.remove(node);
+            }
+            // A stacking context appeared or went away, which moves the
+            // layers inside it between contexts.
+            _ => {
+                let context = self.enclosing_context(node, resolver);
+                self.rebuild(context, resolver);
+            }
+        }
+    }
+
+    /// Add `layer` to the list of its context at the position its
+    /// `z-index` and tree order sort to.
+    fn insert(&mut self, node: NodeId, layer: Layer, resolver: &dyn PropertyResolver) {
+        let (context, z_index) = (layer.context, layer.z_index);
+        self.layers.insert(node, layer);
+        let Some(stacked) = self.layers.get(&context).map(|owner| &owner.stacked) else {
+            return;
+        };
+        let index = stacked.partition_point(|&other| {
+            let other_z_index = self.layers.get(&other).map_or(0, |owner| owner.z_index);
+            other_z_index
+                .cmp(&z_index)
+                .then_with(|| tree_order(other, node, resolver))
+                == Ordering::Less
+        });
+        if let Some(owner) = self.layers.get_mut(&context) {
+            owner.stacked.insert(index, node);
+        }
+    }
+
+    /// Take a layer out of the tree and the list of its context, and
+    /// return it.
+    fn remove(&mut self, node: NodeId) -> Option<Layer> {
+        let layer = self.layers.remove(&node)?;
+        if let Some(owner) = self.layers.get_mut(&layer.context) {
+            owner.stacked.retain(|&other| other != node);
+        }
+        Some(layer)
+    }
+
+    /// Move a layer to the position its new `z-index` sorts to.
+    fn restack(&mut self, node: NodeId, z_index: i32, resolver: &dyn PropertyResolver) {
+        if let Some(layer) = self.remove(node) {
+            self.insert(node, Layer { z_index, ..layer }, resolver);
+        }
+    }
+
+    /// Rebuild everything stacked in `context` and the contexts inside it.
+    fn rebuild(&mut self, context: NodeId, resolver: &dyn PropertyResolver) {
+        let mut pending = children_in_order(context, resolver);
+        while let Some(node) = pending.pop() {
+            self.layers.remove(&node);
+            pending.extend(resolver.children(node));
+        }
+        if let Some(layer) = self.layers.get_mut(&context) {
+            layer.stacked.clear();
+        }
+        self.collect(context, context, resolver);
+        self.sort_stacked(context);
+    }
+}
+
+/// Where the incrementally maintained paint order went wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaintOrderMismatch {
+    /// The nodes whose change was applied incrementally.
+    pub nodes: Vec<NodeId>,
+    /// Paint order after the incremental update.
+    pub incremental: Vec<NodeId>,
+    /// Paint order after a full rebuild.
+    pub rebuilt: Vec<NodeId>,
+}
+
+/// The non-layer content of a layer, by paint phase.
+#[derive(Default)]
+struct FlowPhases {
+    boxes: Vec<NodeId>,
+    floats: Vec<NodeId>,
+    text: Vec<NodeId>,
+}
+
+impl LayoutState {
+    /// Every node that generates a box, back to front: the order to paint
+    /// them in.
+    pub fn paint_order(&self) -> Vec<NodeId> {
+        let resolver = self.resolver();
+        let mut order = Vec::new();
+        self.paint_layer(NodeId::ROOT, &resolver, &mut order);
+        order
+    }
+
+    /// Paint a layer (CSS 2.2 Appendix E): the box, the layers with
+    /// negative `z-index`, the in-flow content, then the other layers.
+    fn paint_layer(&self, node: NodeId, resolver: &dyn PropertyResolver, order: &mut Vec<NodeId>) {
+        let Some(layer) = self.stacking.layers.get(&node) else {
+            return;
+        };
+        if !self.is_rendered(node, resolver) {
+            return;
+        }
+        if node != NodeId::ROOT {
+            order.push(node);
+        }
+        let (negative, rest): (Vec<NodeId>, Vec<NodeId>) =
+            layer.stacked.iter().partition(|stacked| {
+                self.stacking
+                    .layers
+                    .get(stacked)
+                    .is_some_and(|stacked| stacked.z_index < 0)
+            });
+        for stacked in negative {
+            self.paint_layer(stacked, resolver, order);
+        }
+        self.paint_flow(node, resolver, order);
+        for stacked in rest {
+            self.paint_layer(stacked, resolver, order);
+        }
+    }
+
+    /// Paint the descendants of `node` that aren't layers: the in-flow
+    /// boxes in tree order, then each float as a unit, then the text. A
+    /// float is a pseudo-stacking context: its content paints with it, but
+    /// the layers inside it are stacked in the enclosing context.
+    fn paint_flow(&self, node: NodeId, resolver: &dyn PropertyResolver, order: &mut Vec<NodeId>) {
+        let mut phases = FlowPhases::default();
+        self.collect_flow(node, resolver, &mut phases);
+        order.extend(phases.boxes);
+        for float in phases.floats {
+            order.push(float);
+            self.paint_flow(float, resolver, order);
+        }
+        order.extend(phases.text);
+    }
+
+    /// Sort the descendants of `node` that aren't layers or inside a float
+    /// into paint phases, in tree order.
+    fn collect_flow(&self, node: NodeId, resolver: &dyn PropertyResolver, phases: &mut FlowPhases) {
+        for child in children_in_order(node, resolver) {
+            if self.stacking.layers.contains_key(&child) {
+                continue;
+            }
+            let has_box = self.cached_box(child).border_box().is_some();
+            if !resolver.is_element(child) {
+                if has_box {
+                    phases.text.push(child);
+                }
+                continue;
+            }
+            if !has_box {
+                continue;
+            }
+            if resolver.float_side(child).is_some() {
+                phases.floats.push(child);
+                continue;
+            }
+            phases.boxes.push(child);
+            self.collect_flow(child, resolver, phases);
+        }
+    }
+
+    /// Whether `node` and every element around it generate a box.
+    fn is_rendered(&self, node: NodeId, resolver: &dyn PropertyResolver) -> bool {
+        ancestry(node, resolver).into_iter().all(|ancestor| {
+            ancestor == NodeId::ROOT
+                || !resolver.is_element(ancestor)
+                || self.cached_box(ancestor).border_box().is_some()
+        })
+    }
+
+    /// Apply a change that may have made `nodes` start or stop being
+    /// layers to the stacking tree, and in verification mode compare the
+    /// result with a full rebuild.
+    pub(crate) fn update_stacking(&mut self, nodes: &[NodeId]) {
+        let resolver = self.resolver();
+        for &node in nodes {
+            self.stacking.node_changed(node, &resolver);
+        }
+        if !self.layout_flags().verify_paint_order {
+            return;
+        }
+        let rebuilt = StackingTree::build(&resolver);
+        if rebuilt == self.stacking {
+            return;
+        }
+        let incremental = self.paint_order();
+        self.stacking = rebuilt;
+        let mismatch = PaintOrderMismatch {
+            nodes: nodes.to_vec(),
+            incremental,
+            rebuilt: self.paint_order(),
+        };
+        self.paint_order_mismatches.push(mismatch);
+    }
+
+    /// Discard the stacking tree and build it again from the document.
+    pub(crate) fn rebuild_stacking(&mut self) {
+        self.stacking = StackingTree::build(&self.resolver());
+    }
+
+    /// Changes whose incremental paint order update disagreed with a full
+    /// rebuild since the last call. Only recorded with
+    /// `LayoutFlags::verify_paint_order`.
+    pub fn take_paint_order_mismatches(&mut self) -> Vec<PaintOrderMismatch> {
+        mem::take(&mut self.paint_order_mismatches)
+    }
+}
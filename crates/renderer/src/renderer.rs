@@ -1,14 +1,34 @@
 //! Main renderer.
 
+use crate::paint_order::{StackingTree, affects_paint_order};
+use crate::{
+    BorderSides, BoxArea, ContainerMetrics, Cursor, DisplayList, FieldsetBorder, FocusRing,
+    FragmentTree, GeneratedContentBox, GeometryDiff, MarkerBox, PaintInfo, PaintOrderMismatch,
+    ScrollAnchor, ScrollMetrics, TextRange, TextStyle, TextTarget, UsedValues,
+};
+use lightningcss::properties::display::{Display, DisplayOutside};
 use lightningcss::properties::{Property, PropertyId};
+use lightningcss::values::color::RGBA;
 use rewrite_core::{
-    Axis, Database, DomBroadcast, Formula, NodeId, ResolveContext, Subpixel, Subscriber,
+    Axis, CustomLayout, CustomLayoutRegistry, Database, DomBroadcast, FontDependents, Formula,
+    GeneratedBox, IntrinsicSize, LayoutFlags, NodeId, PropertyResolver, ResolveContext,
+    ResolveDiagnostic, ResolveStats, Subpixel, Subscriber, TextAutosizing, ViewportDependents,
+    ViewportKind,
+};
+use rewrite_css::{
+    ComputedColor, CssPropertyResolver, MediaEnvironment, Styler, is_counter_property, used_color,
+    used_computed_color,
+};
+use rewrite_layout::{
+    ContainingBlock, FormattingContext, Rect, ViewportOverflow, affects_containing_blocks,
+    available_inline_size_query, containing_block, formatting_context, has_transform_or_filter,
+    is_absolute_containing_block, offset_query, property_query, size_query, viewport_overflow,
 };
-use rewrite_css::{CssPropertyResolver, Styler};
-use rewrite_layout::{offset_query, property_query, size_query};
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::iter;
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
 /// Computed layout box values for a single node.
 #[derive(Debug, Clone, Default)]
@@ -28,14 +48,44 @@ struct NodeFormulas {
     offset_y: Option<&'static Formula>,
 }
 
-/// Create a `CssPropertyResolver` from shared state.
-fn make_resolver(
-    styler: &Arc<Styler>,
-    db: &Arc<Database>,
-    vw: u32,
-    vh: u32,
-) -> CssPropertyResolver {
-    CssPropertyResolver::new(styler.clone(), db.clone(), vw, vh)
+/// The border-width property whose used value depends on a border-style
+/// property, or `None` if `prop_id` isn't a border-style.
+fn border_width_for_style(prop_id: &PropertyId<'static>) -> Option<PropertyId<'static>> {
+    match prop_id {
+        PropertyId::BorderTopStyle => Some(PropertyId::BorderTopWidth),
+        PropertyId::BorderRightStyle => Some(PropertyId::BorderRightWidth),
+        PropertyId::BorderBottomStyle => Some(PropertyId::BorderBottomWidth),
+        PropertyId::BorderLeftStyle => Some(PropertyId::BorderLeftWidth),
+        _ => None,
+    }
+}
+
+/// Whether `prop_id` insets the content box horizontally.
+fn changes_content_width(prop_id: &PropertyId<'static>) -> bool {
+    matches!(
+        prop_id,
+        PropertyId::PaddingLeft
+            | PropertyId::PaddingRight
+            | PropertyId::BorderLeftWidth
+            | PropertyId::BorderRightWidth
+    )
+}
+
+/// Whether `prop_id` is a margin, which places a box's siblings and sizes
+/// its parent without changing the box itself.
+fn is_margin(prop_id: &PropertyId<'static>) -> bool {
+    matches!(
+        prop_id,
+        PropertyId::MarginTop
+            | PropertyId::MarginRight
+            | PropertyId::MarginBottom
+            | PropertyId::MarginLeft
+    )
+}
+
+/// Whether `prop_id` is one font-relative lengths resolve against.
+fn is_font_metric(prop_id: &PropertyId<'static>) -> bool {
+    matches!(prop_id, PropertyId::FontSize | PropertyId::LineHeight)
 }
 
 /// Persistent layout state that owns a `ResolveContext` and tracks
@@ -43,8 +93,44 @@ fn make_resolver(
 pub struct LayoutState {
     ctx: ResolveContext,
     formulas: HashMap<NodeId, NodeFormulas>,
-    styler: Arc<Styler>,
+    /// Nodes `resolve_node` last skipped because they generate no box.
+    boxless: HashSet<NodeId>,
+    /// Container each node was last resolved against, while
+    /// `LayoutFlags::record_container_metrics` is set.
+    pub(crate) container_metrics: HashMap<NodeId, ContainerMetrics>,
+    /// Scroll offsets of scroll containers set by the embedder, clamped to
+    /// the scrollable range when set.
+    pub(crate) scroll_offsets: HashMap<NodeId, (Subpixel, Subpixel)>,
+    pub(crate) styler: Arc<Styler>,
     db: Arc<Database>,
+    flags: LayoutFlags,
+    autosizing: Option<TextAutosizing>,
+    custom_layouts: Arc<CustomLayoutRegistry>,
+    /// Natural sizes of replaced elements whose content has loaded.
+    intrinsic_sizes: Arc<HashMap<NodeId, IntrinsicSize>>,
+    /// Boxes with `em`/`rem`-like lengths, recorded as they're resolved.
+    font_dependents: Arc<FontDependents>,
+    /// Small and dynamic viewports set by the embedder; `None` while they
+    /// are the layout viewport.
+    small_viewport: Option<(u32, u32)>,
+    dynamic_viewport: Option<(u32, u32)>,
+    /// Boxes with `dv*` lengths, recorded as they're resolved.
+    viewport_dependents: Arc<ViewportDependents>,
+    /// Border box of each node changed since the last geometry diff, as it
+    /// was before the first change.
+    pub(crate) geometry_baseline: HashMap<NodeId, Option<Rect>>,
+    /// Stacking contexts and positioned boxes, kept up to date as style
+    /// changes arrive.
+    pub(crate) stacking: StackingTree,
+    pub(crate) paint_order_mismatches: Vec<PaintOrderMismatch>,
+    /// Children of each block container that changed size since it was
+    /// last re-resolved. `resolve_dirty_flows` re-resolves the container and
+    /// the boxes after the first of them once for a run of changes, before
+    /// layout is read.
+    dirty_flows: HashMap<NodeId, HashSet<NodeId>>,
+    /// The containers in `dirty_flows` by depth, so a container is
+    /// re-resolved after the containers inside it.
+    dirty_flow_order: BinaryHeap<(usize, NodeId)>,
 }
 
 impl LayoutState {
@@ -55,19 +141,245 @@ impl LayoutState {
         viewport_width: u32,
         viewport_height: u32,
     ) -> Self {
+        let mut ctx = ResolveContext::new(viewport_width, viewport_height);
+        ctx.set_available_inline_size(available_inline_size_query);
         Self {
-            ctx: ResolveContext::new(viewport_width, viewport_height),
+            ctx,
             formulas: HashMap::new(),
+            boxless: HashSet::new(),
+            container_metrics: HashMap::new(),
+            scroll_offsets: HashMap::new(),
             styler,
             db,
+            flags: LayoutFlags::default(),
+            autosizing: None,
+            custom_layouts: Arc::default(),
+            intrinsic_sizes: Arc::default(),
+            font_dependents: Arc::default(),
+            small_viewport: None,
+            dynamic_viewport: None,
+            viewport_dependents: Arc::default(),
+            geometry_baseline: HashMap::new(),
+            stacking: StackingTree::default(),
+            paint_order_mismatches: Vec::new(),
+            dirty_flows: HashMap::new(),
+            dirty_flow_order: BinaryHeap::new(),
+        }
+    }
+
+    /// Current layout debug flags.
+    pub fn layout_flags(&self) -> LayoutFlags {
+        self.flags
+    }
+
+    /// Replace the layout debug flags. Cached values were computed with
+    /// the old flags, so every known node is re-resolved.
+    pub fn set_layout_flags(&mut self, flags: LayoutFlags) {
+        if self.flags == flags {
+            return;
+        }
+        self.flags = flags;
+        self.relayout_all();
+    }
+
+    /// Current text autosizing, if any.
+    pub fn text_autosizing(&self) -> Option<TextAutosizing> {
+        self.autosizing
+    }
+
+    /// Enable, change or (with `None`) disable text autosizing. Every
+    /// known node is re-resolved, since text was measured at the old size.
+    pub fn set_text_autosizing(&mut self, autosizing: Option<TextAutosizing>) {
+        if self.autosizing == autosizing {
+            return;
         }
+        self.autosizing = autosizing;
+        self.relayout_all();
     }
 
-    /// Read cached layout values for a node.
-    pub fn get_node(&self, node: NodeId) -> ComputedBox {
+    /// Lay out the children of `display: layout(<name>)` boxes with
+    /// `layout`, replacing any layout registered under `name` before.
+    /// Every known node is re-resolved, since such boxes were laid out as
+    /// blocks until now.
+    pub fn register_custom_layout(&mut self, name: &str, layout: Arc<dyn CustomLayout>) {
+        Arc::make_mut(&mut self.custom_layouts).insert(name.to_owned(), layout);
+        self.relayout_all();
+    }
+
+    /// Set, or with `None` forget, the natural size of replaced element
+    /// `node`, e.g. once an image's header has arrived. Only the element
+    /// and the boxes its new size moves are re-resolved; the changes show
+    /// up in the next geometry diff.
+    pub fn set_intrinsic_size(&mut self, node: NodeId, size: Option<IntrinsicSize>) {
+        let sizes = Arc::make_mut(&mut self.intrinsic_sizes);
+        let changed = match size {
+            Some(size) => sizes.insert(node, size) != Some(size),
+            None => sizes.remove(&node).is_some(),
+        };
+        if !changed {
+            return;
+        }
+        if self.flags.force_full_layout {
+            self.relayout_all();
+            return;
+        }
+        self.re_resolve_and_propagate(node);
+    }
+
+    /// Add a downloaded font under `family`, e.g. once an `@font-face`
+    /// source has arrived. Text listing the family, and `ch` and `ex`
+    /// lengths of boxes listing it, were measured with a fallback font;
+    /// only those and the boxes their new size moves are re-resolved.
+    pub fn load_font(&mut self, family: &str, data: Vec<u8>) {
+        if !rewrite_text::load_font(family, data) {
+            return;
+        }
+        if self.flags.force_full_layout {
+            self.relayout_all();
+            return;
+        }
+        let resolver = self.resolver();
+        let runs: Vec<NodeId> = self
+            .formulas
+            .keys()
+            .copied()
+            .filter(|&node| {
+                resolver.text_content(node).is_some()
+                    && rewrite_text::lists_family(
+                        self.db.get_property(node, PropertyId::FontFamily).as_ref(),
+                        family,
+                    )
+            })
+            .collect();
+        for node in runs {
+            self.re_resolve_and_propagate(node);
+        }
+        // `ch` and `ex` lengths were measured in the fallback font.
+        let measured: Vec<NodeId> = self
+            .formulas
+            .keys()
+            .copied()
+            .filter(|&node| {
+                self.font_dependents.uses_own_font(node)
+                    && rewrite_text::lists_family(
+                        self.db.get_property(node, PropertyId::FontFamily).as_ref(),
+                        family,
+                    )
+            })
+            .collect();
+        for node in measured {
+            self.re_resolve_lengths(node);
+        }
+    }
+
+    /// Factor text autosizing scaled `node`'s font size by, `1.0` when it
+    /// applied none. `None` if the node has no font size.
+    pub fn text_autosize_scale(&self, node: NodeId) -> Option<f32> {
+        let font_size = self
+            .resolver()
+            .get_property(node, &PropertyId::FontSize)?
+            .to_f32();
+        if font_size <= 0.0 {
+            return Some(1.0);
+        }
         let vw = self.ctx.viewport_width;
-        let vh = self.ctx.viewport_height;
-        let resolver = make_resolver(&self.styler, &self.db, vw, vh);
+        let used = self.autosizing.map_or(font_size, |autosizing| {
+            autosizing.used_font_size(font_size, vw)
+        });
+        Some(used / font_size)
+    }
+
+    /// Change the viewport size, e.g. as the device is rotated. The small
+    /// and dynamic viewports go back to being the new layout viewport.
+    /// Like a change of the root element's width, this re-resolves the
+    /// root element and the boxes its new size moves, plus the boxes with
+    /// lengths resolved against any viewport.
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        if self.ctx.viewport_width == width && self.ctx.viewport_height == height {
+            return;
+        }
+        self.ctx.viewport_width = width;
+        self.ctx.viewport_height = height;
+        self.small_viewport = None;
+        self.dynamic_viewport = None;
+        // Autosized text is scaled by the viewport width.
+        if self.flags.force_full_layout || self.autosizing.is_some() {
+            self.relayout_all();
+            return;
+        }
+        let roots: Vec<NodeId> = self
+            .formulas
+            .keys()
+            .copied()
+            .filter(|&node| self.db.dom_parent(node) == Some(NodeId::ROOT))
+            .collect();
+        for root in roots {
+            self.re_resolve_and_propagate(root);
+        }
+        self.re_resolve_viewport_users(&[
+            ViewportKind::Small,
+            ViewportKind::Large,
+            ViewportKind::Dynamic,
+        ]);
+    }
+
+    /// Set, or with `None` reset to the layout viewport, the
+    /// `(width, height)` of the small viewport: the viewport with
+    /// retractable browser UI shown, which `sv*` lengths resolve against.
+    /// Only boxes with such lengths and the boxes their new size moves are
+    /// re-resolved.
+    pub fn set_small_viewport(&mut self, size: Option<(u32, u32)>) {
+        if self.small_viewport == size {
+            return;
+        }
+        self.small_viewport = size;
+        self.re_resolve_viewport_users(&[ViewportKind::Small]);
+    }
+
+    /// Set, or with `None` reset to the layout viewport, the
+    /// `(width, height)` of the dynamic viewport `dv*` lengths resolve
+    /// against, e.g. as an address bar slides out. Only boxes with such
+    /// lengths and the boxes their new size moves are re-resolved; the
+    /// changes show up in the next geometry diff.
+    pub fn set_dynamic_viewport(&mut self, size: Option<(u32, u32)>) {
+        if self.dynamic_viewport == size {
+            return;
+        }
+        self.dynamic_viewport = size;
+        self.re_resolve_viewport_users(&[ViewportKind::Dynamic]);
+    }
+
+    /// Re-resolve the boxes with lengths resolved against a viewport of
+    /// one of `kinds`, after it was resized.
+    fn re_resolve_viewport_users(&mut self, kinds: &[ViewportKind]) {
+        if self.flags.force_full_layout {
+            self.relayout_all();
+            return;
+        }
+        // Inherited viewport-relative font sizes and line heights are
+        // resolved where they're read, so the text below is among the
+        // users too.
+        for node in self.viewport_dependents.users(kinds) {
+            self.re_resolve_lengths(node);
+        }
+    }
+
+    /// Nodes that layout has formulas for, i.e. has seen via `on_node_created`.
+    pub fn tracked_nodes(&self) -> Vec<NodeId> {
+        self.formulas.keys().copied().collect()
+    }
+
+    /// Read cached layout values for a node, after resolving the boxes
+    /// moved by earlier changes.
+    pub fn get_node(&mut self, node: NodeId) -> ComputedBox {
+        self.resolve_dirty_flows();
+        self.cached_box(node)
+    }
+
+    /// Cached layout values for a node, as they stand. Boxes waiting in
+    /// `dirty_flows` still have their old values.
+    pub(crate) fn cached_box(&self, node: NodeId) -> ComputedBox {
+        let resolver = self.resolver();
         let mut result = ComputedBox::default();
 
         if let Some(formula) = size_query(node, &resolver, Axis::Horizontal) {
@@ -86,6 +398,25 @@ impl LayoutState {
         result
     }
 
+    /// Cached values of the formulas `node` was last resolved with. Unlike
+    /// `cached_box`, this doesn't re-run the queries, so it still sees the
+    /// old box after a style change that replaces a formula (such as
+    /// `display: none` removing it).
+    fn last_resolved(&self, node: NodeId) -> ComputedBox {
+        let Some(nf) = self.formulas.get(&node) else {
+            return ComputedBox::default();
+        };
+        let cached = |formula: Option<&'static Formula>| {
+            formula.and_then(|formula| self.ctx.get_cached(formula, node))
+        };
+        ComputedBox {
+            width: cached(nf.width),
+            height: cached(nf.height),
+            x: cached(nf.offset_x),
+            y: cached(nf.offset_y),
+        }
+    }
+
     /// All box-model properties that serialization may read.
     const BOX_MODEL_PROPS: [PropertyId<'static>; 12] = [
         PropertyId::MarginTop,
@@ -104,9 +435,20 @@ impl LayoutState {
 
     /// Full resolve of all layout dimensions and box-model properties for a node.
     pub fn resolve_node(&mut self, node: NodeId) -> ComputedBox {
-        let vw = self.ctx.viewport_width;
-        let vh = self.ctx.viewport_height;
-        let resolver = make_resolver(&self.styler, &self.db, vw, vh);
+        // Nothing inside `display: none` is laid out: no formulas, no
+        // cached values, no rect.
+        if !self.db.generates_box(node) {
+            self.formulas.insert(node, NodeFormulas::default());
+            self.boxless.insert(node);
+            self.container_metrics.remove(&node);
+            self.font_dependents.forget(node);
+            self.viewport_dependents.forget(node);
+            self.ctx.record_skipped_node();
+            return ComputedBox::default();
+        }
+        self.boxless.remove(&node);
+
+        let resolver = self.resolver();
         let nf = self.formulas.entry(node).or_default();
         let mut result = ComputedBox::default();
 
@@ -142,71 +484,244 @@ impl LayoutState {
             }
         }
 
+        if self.flags.record_container_metrics {
+            let metrics = self.measure_container(node);
+            self.container_metrics.insert(node, metrics);
+        }
+
         result
     }
 
     /// Handle a new DOM node being created.
     pub fn on_node_created(&mut self, node: NodeId, _parent: NodeId) {
-
+        self.record_geometry(node, &ComputedBox::default());
         self.resolve_node(node);
+        self.update_stacking(&[node]);
+        if self.boxless.contains(&node) {
+            return;
+        }
+        if self.flags.force_full_layout {
+            self.relayout_all();
+            return;
+        }
         self.propagate_changes(node);
     }
 
     /// Handle a property change on a node.
     pub fn on_property_change(&mut self, node: NodeId, property: &Property<'static>) {
         let prop_id = property.property_id();
-        let group = rewrite_core::classify_property(&prop_id);
-        if matches!(group, Some(rewrite_core::PropertyGroup::Background)) {
+        self.on_property_id_change(node, &prop_id);
+        self.on_counters_change(node, &prop_id);
+    }
+
+    /// Handle a property that no longer applies to a node.
+    pub fn on_property_removed(&mut self, node: NodeId, prop_id: &PropertyId<'static>) {
+        self.on_property_id_change(node, prop_id);
+        self.on_counters_change(node, prop_id);
+    }
+
+    /// Handle a rule for a node's `::before`, `::after` or `::marker` box
+    /// starting or stopping to apply. The boxes may change, and with them
+    /// the counters every later box reads.
+    pub fn on_generated_content(&mut self, node: NodeId) {
+        if !self.formulas.contains_key(&node) {
+            return;
+        }
+        if self.flags.force_full_layout {
+            self.relayout_all();
             return;
         }
+        self.refresh_generated_boxes(node);
+        for user in self.styler.changed_counter_users() {
+            if user != node {
+                self.refresh_generated_boxes(user);
+            }
+        }
+    }
 
-        // Check if any layout formula on this node reads the changed property.
-        // If the node has no formulas yet, resolve it fully (first time).
-        if let Some(nf) = self.formulas.get(&node) {
-            let any_affected = [nf.width, nf.height, nf.offset_x, nf.offset_y]
-                .iter()
-                .flatten()
-                .any(|f| f.depends_on_css_property(&prop_id));
-            if !any_affected {
-                return;
+    /// Refresh the boxes that read counters after a property that changes
+    /// them, or whether an element counts, changed on a laid-out `node`.
+    /// Boxes are only refreshed if the counters they read changed.
+    fn on_counters_change(&mut self, node: NodeId, prop_id: &PropertyId<'static>) {
+        if !(is_counter_property(prop_id) || matches!(prop_id, PropertyId::Display))
+            || !self.formulas.contains_key(&node)
+        {
+            return;
+        }
+        let users = self.styler.changed_counter_users();
+        if users.is_empty() {
+            return;
+        }
+        if self.flags.force_full_layout {
+            self.relayout_all();
+            return;
+        }
+        for user in users {
+            self.refresh_generated_boxes(user);
+        }
+    }
+
+    /// Re-lay out an element after the text of its generated boxes may
+    /// have changed: they take room on its first and last lines.
+    fn refresh_generated_boxes(&mut self, node: NodeId) {
+        if !self.formulas.contains_key(&node) || !self.db.generates_box(node) {
+            return;
+        }
+        self.invalidate_subtree_aggregates(node);
+        self.re_resolve_lengths(node);
+    }
+
+    fn on_property_id_change(&mut self, node: NodeId, prop_id: &PropertyId<'static>) {
+        // `border-*-style: none | hidden` zeroes the used border width, so
+        // for layout a style change is a change of that side's width.
+        let width_id = border_width_for_style(prop_id);
+        let prop_id = width_id.as_ref().unwrap_or(prop_id);
+
+        if affects_paint_order(prop_id) {
+            let mut changed = vec![node];
+            if matches!(prop_id, PropertyId::Display) {
+                changed.extend(self.db.dom_children(node));
             }
+            self.update_stacking(&changed);
         }
 
-        let old_values = self.get_node(node);
+        let group = rewrite_core::classify_property(prop_id);
+        if matches!(group, Some(rewrite_core::PropertyGroup::Background))
+            || rewrite_core::is_paint_only(prop_id)
+        {
+            return;
+        }
 
-        self.resolve_node(node);
-        let new_values = self.get_node(node);
+        if self.flags.force_full_layout {
+            self.relayout_all();
+            return;
+        }
 
-        let changed = old_values.width != new_values.width
-            || old_values.height != new_values.height
-            || old_values.x != new_values.x
-            || old_values.y != new_values.y;
+        // A new `display` can take the subtree out of layout or put it
+        // back. Otherwise a style change inside `display: none` has no box
+        // to affect.
+        if matches!(prop_id, PropertyId::Display) && self.refresh_box_generation(node) {
+            self.propagate_changes(node);
+            return;
+        }
+        if !self.db.generates_box(node) {
+            return;
+        }
+
+        // Padding and border move the content edge, against which the
+        // children's percentages and line lengths resolve. A new display
+        // type changes which formulas lay the children out.
+        if changes_content_width(prop_id) || matches!(prop_id, PropertyId::Display) {
+            self.ctx.invalidate_parent_aggregates(node);
+            for child in self.db.dom_children(node) {
+                self.re_resolve_and_propagate(child);
+            }
+        }
+
+        // Lengths in `em` and `lh` (and `rem` and `rlh`, for the root)
+        // were resolved against the old font, which no formula names.
+        // `rem` users may sit below boxes with fonts of their own, which
+        // inheritance doesn't reach.
+        if is_font_metric(prop_id) {
+            for dependent in self.font_dependents_of(node) {
+                self.re_resolve_lengths(dependent);
+                if dependent != node {
+                    self.propagate_inherited_down(dependent, prop_id);
+                }
+            }
+        }
+
+        // Preserved segment breaks force line breaks, so a new
+        // `white-space` rebreaks the lines of the node and its descendants
+        // even when no text is measured differently. An inside marker takes
+        // room on its item's first line, so a new marker position or style
+        // rebreaks the item's lines too.
+        if matches!(
+            prop_id,
+            PropertyId::WhiteSpace | PropertyId::ListStylePosition | PropertyId::ListStyleType
+        ) {
+            self.invalidate_subtree_aggregates(node);
+            self.re_resolve_and_propagate(node);
+        }
 
-        if changed {
+        // Re-resolve the node itself only if one of its layout formulas
+        // reads the changed property. If the node has no formulas yet,
+        // resolve it fully (first time).
+        let self_affected = self.formulas.get(&node).is_none_or(|nf| {
+            [nf.width, nf.height, nf.offset_x, nf.offset_y]
+                .iter()
+                .flatten()
+                .any(|f| f.depends_on_css_property(prop_id))
+        });
+        if self_affected {
+            self.re_resolve_and_propagate(node);
+        }
+
+        // Whether a box is a cell, row or row group shapes the grid of the
+        // table around it, even when its own box stays the same.
+        if matches!(prop_id, PropertyId::Display) && self.enclosing_table(node).is_some() {
             self.propagate_changes(node);
+        } else if is_margin(prop_id) {
+            self.propagate_to_parent(node);
         }
 
         // Inherited properties (font-size, etc.) affect descendants via
-        // inheritance even if this node's layout values didn't change.
-        // A wrapper div's size doesn't change when font-size changes,
-        // but its text node grandchildren measure differently.
-        if matches!(group, Some(rewrite_core::PropertyGroup::Text)) {
-            self.propagate_inherited_down(node);
+        // inheritance even if this node's layout values didn't change, or
+        // none of its formulas read the property. A fixed-size div doesn't
+        // change when font-size changes, but its text children measure
+        // differently. Likewise a new transform or position can make the
+        // node the containing block of out-of-flow descendants.
+        if matches!(group, Some(rewrite_core::PropertyGroup::Text))
+            || affects_containing_blocks(prop_id)
+        {
+            self.propagate_inherited_down(node, prop_id);
+        }
+    }
+
+    /// Re-resolve `node` and its descendants if whether they generate a
+    /// box changed since they were last resolved. Returns whether it did
+    /// for `node`.
+    fn refresh_box_generation(&mut self, node: NodeId) -> bool {
+        if self.boxless.contains(&node) != self.db.generates_box(node) {
+            return false;
+        }
+        let old_values = self.last_resolved(node);
+        self.record_geometry(node, &old_values);
+        self.ctx.invalidate_parent_aggregates(node);
+        self.resolve_node(node);
+        for child in self.db.dom_children(node) {
+            self.refresh_box_generation(child);
         }
+        true
     }
 
     /// Propagate inherited property changes to ALL descendants.
     /// Inherited properties bypass intermediate nodes — a font-size
     /// change on a grandparent affects text nodes even if the parent
-    /// div's layout values are unchanged.
-    fn propagate_inherited_down(&mut self, node: NodeId) {
+    /// div's layout values are unchanged. Subtrees whose root sets its own
+    /// font size or line height don't see a change of either.
+    fn propagate_inherited_down(&mut self, node: NodeId, prop_id: &PropertyId<'static>) {
+        let font_metric = is_font_metric(prop_id);
         for child in self.db.dom_children(node) {
-            let old_values = self.get_node(child);
+            if !self.db.generates_box(child) {
+                continue;
+            }
+            if font_metric && self.resolver().overrides_inherited_font(child, prop_id) {
+                continue;
+            }
+            // The child's `em` lengths may move its content edge, against
+            // which its own children were broken into lines.
+            if font_metric && self.font_dependents.uses_own_font(child) {
+                self.ctx.invalidate_parent_aggregates(child);
+            }
+
+            let old_values = self.last_resolved(child);
+            self.record_geometry(child, &old_values);
             self.resolve_node(child);
-            let new_values = self.get_node(child);
+            let new_values = self.cached_box(child);
 
-            // Always recurse — inheritance goes through entire subtree.
-            self.propagate_inherited_down(child);
+            // Recurse — inheritance goes through the entire subtree.
+            self.propagate_inherited_down(child, prop_id);
 
             // If size changed, propagate to parent and siblings.
             if old_values.width != new_values.width
@@ -217,54 +732,408 @@ impl LayoutState {
         }
     }
 
+    /// Boxes with lengths resolved against the font of `node`.
+    fn font_dependents_of(&self, node: NodeId) -> Vec<NodeId> {
+        let mut dependents = Vec::new();
+        if self.font_dependents.uses_own_font(node) {
+            dependents.push(node);
+        }
+        let resolver = self.resolver();
+        if resolver.parent(node) == Some(NodeId::ROOT) && resolver.is_element(node) {
+            dependents.extend(self.font_dependents.root_font_users());
+        }
+        dependents
+    }
+
+    /// Re-resolve `node` after its own lengths changed, along with its
+    /// children, which are laid out in its content box.
+    fn re_resolve_lengths(&mut self, node: NodeId) {
+        self.ctx.invalidate_parent_aggregates(node);
+        for child in self.db.dom_children(node) {
+            self.re_resolve_and_propagate(child);
+        }
+        self.re_resolve_and_propagate(node);
+    }
+
+    /// Invalidate the line assignments of `node` and all its descendants.
+    fn invalidate_subtree_aggregates(&mut self, node: NodeId) {
+        self.ctx.invalidate_parent_aggregates(node);
+        for child in self.db.dom_children(node) {
+            self.invalidate_subtree_aggregates(child);
+        }
+    }
+
     /// Propagate changes from a node to all dependents.
     fn propagate_changes(&mut self, node: NodeId) {
-        if let Some(parent) = self.db.dom_parent(node) {
-            self.re_resolve_and_propagate(parent);
+        self.propagate_box_change(node, None);
+    }
+
+    /// Propagate a change of `node`'s box from `old` to `new`, if known,
+    /// to the boxes that depend on it.
+    fn propagate_box_change(&mut self, node: NodeId, change: Option<(&ComputedBox, &ComputedBox)>) {
+        // Every part of a table is placed by the grid its cells size, so a
+        // change to one can move or resize parts that aren't its siblings.
+        if let Some(table) = self.enclosing_table(node) {
+            self.re_resolve_table_parts(table, node);
         }
-        if let Some(parent) = self.db.dom_parent(node) {
-            for sibling in self.db.dom_children(parent) {
-                if sibling != node {
-                    self.re_resolve_and_propagate(sibling);
+        self.propagate_to_parent(node);
+        // Children laid out top-down in a box that kept its place and width
+        // don't depend on its height.
+        let reflows_children = change.is_none_or(|(old, new)| {
+            old.width != new.width
+                || old.x != new.x
+                || old.y != new.y
+                || !self.ignores_used_height(node)
+        });
+        if reflows_children {
+            for child in self.db.dom_children(node) {
+                self.re_resolve_dependent(child);
+            }
+        }
+        // Absolutely positioned descendants are placed against the padding
+        // box of their containing block, however deep they sit in it.
+        let resized =
+            change.is_none_or(|(old, new)| old.width != new.width || old.height != new.height);
+        if resized && is_absolute_containing_block(node, &self.resolver()) {
+            self.re_resolve_positioned_descendants(node);
+        }
+    }
+
+    /// Re-resolve the boxes that `node` is the containing block of.
+    fn re_resolve_positioned_descendants(&mut self, node: NodeId) {
+        let resolver = self.resolver();
+        let mut positioned = Vec::new();
+        let mut pending = self.db.dom_children(node);
+        while let Some(descendant) = pending.pop() {
+            if containing_block(descendant, &resolver) == ContainingBlock::Element(node) {
+                positioned.push(descendant);
+            }
+            // Boxes below a transform or filter are placed against it.
+            if !has_transform_or_filter(descendant, &resolver) {
+                pending.extend(self.db.dom_children(descendant));
+            }
+        }
+        for descendant in positioned {
+            self.re_resolve_dependent(descendant);
+        }
+    }
+
+    /// Parents and siblings depend on where a box is and how much room its
+    /// margin box takes: re-resolve them.
+    fn propagate_to_parent(&mut self, node: NodeId) {
+        let Some(parent) = self.db.dom_parent(node) else {
+            return;
+        };
+        // Prefix sums and line assignments over the parent's children
+        // include this node's old box.
+        self.ctx.invalidate_aggregates_from(parent, node);
+        // An out-of-flow box takes no room in its parent's flow, so
+        // neither the parent nor its siblings depend on it.
+        if self.resolver().is_out_of_flow(node) {
+            return;
+        }
+
+        // In a block flow only the parent and the boxes after the node
+        // depend on it, and they're re-resolved once however many of the
+        // boxes before them change.
+        if self.in_block_flow(node, parent) {
+            match self.dirty_flows.entry(parent) {
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().insert(node);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(HashSet::from([node]));
+                    let depth =
+                        iter::successors(Some(parent), |&ancestor| self.db.dom_parent(ancestor))
+                            .count();
+                    self.dirty_flow_order.push((depth, parent));
                 }
             }
+            return;
         }
-        for child in self.db.dom_children(node) {
-            self.re_resolve_and_propagate(child);
+        self.re_resolve_and_propagate(parent);
+        // Lines are broken again around the node's new size, which can move
+        // the node itself.
+        for sibling in self.db.dom_children(parent) {
+            self.re_resolve_dependent(sibling);
         }
     }
 
-    /// Invalidate, re-resolve, and propagate if values changed.
-    fn re_resolve_and_propagate(&mut self, node: NodeId) {
-        let old_values = self.get_node(node);
+    /// Whether `node` is a block-level box in the block flow of `parent`,
+    /// which only the boxes after it depend on.
+    fn in_block_flow(&self, node: NodeId, parent: NodeId) -> bool {
+        let is_block_container = |context| {
+            matches!(
+                context,
+                Some(
+                    FormattingContext::BlockContainer
+                        | FormattingContext::BlockFormattingContextRoot
+                )
+            )
+        };
+        let resolver = self.resolver();
+        is_block_container(self.formatting_context(parent))
+            && is_block_container(self.formatting_context(node))
+            && !resolver.is_out_of_flow(node)
+            && !matches!(
+                resolver.get_css_property(node, &PropertyId::Display),
+                Some(Property::Display(Display::Pair(pair)))
+                    if pair.outside == DisplayOutside::Inline
+            )
+    }
 
-        self.resolve_node(node);
-        let new_values = self.get_node(node);
+    /// Whether the descendants of `node` are laid out without its used
+    /// height: it's a block container with an auto height that isn't a
+    /// flex or grid item, whose percentage heights resolve against the
+    /// height it's stretched to, nor the containing block of absolutely
+    /// positioned boxes, which can be placed against its bottom edge.
+    fn ignores_used_height(&self, node: NodeId) -> bool {
+        let resolver = self.resolver();
+        let is_block_container = matches!(
+            self.formatting_context(node),
+            Some(FormattingContext::BlockContainer | FormattingContext::BlockFormattingContextRoot)
+        );
+        let in_flex_or_grid = self.db.dom_parent(node).is_some_and(|parent| {
+            matches!(
+                self.formatting_context(parent),
+                Some(FormattingContext::Flex | FormattingContext::Grid)
+            )
+        });
+        is_block_container
+            && !in_flex_or_grid
+            && resolver.parent(node) != Some(NodeId::ROOT)
+            && !is_absolute_containing_block(node, &resolver)
+            && resolver
+                .get_css_property(node, &PropertyId::Height)
+                .is_none()
+    }
 
+    /// Re-resolve each block container with children that changed size,
+    /// innermost first, and the boxes after the first of those children.
+    /// Called before layout is read.
+    pub fn resolve_dirty_flows(&mut self) {
+        while let Some((_, parent)) = self.dirty_flow_order.pop() {
+            let Some(changed) = self.dirty_flows.remove(&parent) else {
+                continue;
+            };
+            // Children are listed last first, so the boxes after the first
+            // one that changed are listed before it.
+            let children = self.db.dom_children(parent);
+            if let Some(first) = children.iter().rposition(|child| changed.contains(child)) {
+                for &sibling in children[..first].iter().rev() {
+                    self.re_resolve_dependent(sibling);
+                }
+            }
+            self.re_resolve_and_propagate(parent);
+        }
+    }
+
+    /// The outermost table, row group or row around `node` whose grid
+    /// places it.
+    fn enclosing_table(&self, node: NodeId) -> Option<NodeId> {
+        let mut table = None;
+        let mut ancestor = self.db.dom_parent(node);
+        while let Some(current) = ancestor
+            && self.formatting_context(current) == Some(FormattingContext::Table)
+        {
+            table = Some(current);
+            ancestor = self.db.dom_parent(current);
+        }
+        table
+    }
+
+    /// Re-resolve the row groups, rows and cells below `parent`, other
+    /// than `changed`.
+    fn re_resolve_table_parts(&mut self, parent: NodeId, changed: NodeId) {
+        // The parts share one layout of the grid, until a part's new box
+        // invalidates the caches it was laid out with.
+        self.ctx.begin_batch();
+        for child in self.db.dom_children(parent) {
+            if child == changed || !self.db.generates_box(child) {
+                continue;
+            }
+            self.re_resolve_dependent(child);
+            if self.formatting_context(child) == Some(FormattingContext::Table) {
+                self.re_resolve_table_parts(child, changed);
+            }
+        }
+        self.ctx.end_batch();
+    }
+
+    /// Invalidate, re-resolve, and propagate if values changed.
+    fn re_resolve_and_propagate(&mut self, node: NodeId) {
+        let (old_values, new_values) = self.re_resolve(node);
         if old_values.width != new_values.width
             || old_values.height != new_values.height
             || old_values.x != new_values.x
             || old_values.y != new_values.y
         {
-            self.propagate_changes(node);
+            self.propagate_box_change(node, Some((&old_values, &new_values)));
         }
     }
 
+    /// Re-resolve a box whose own style didn't change, after a change to
+    /// its parent, a sibling or a child. Its margins are as before, so if
+    /// it only moved, the parent and siblings, which depend on its size
+    /// and margins but not its position, are unaffected: only its subtree
+    /// moves with it.
+    fn re_resolve_dependent(&mut self, node: NodeId) {
+        let (old_values, new_values) = self.re_resolve(node);
+        if old_values.width != new_values.width || old_values.height != new_values.height {
+            self.propagate_box_change(node, Some((&old_values, &new_values)));
+        } else if old_values.x != new_values.x || old_values.y != new_values.y {
+            for child in self.db.dom_children(node) {
+                self.re_resolve_dependent(child);
+            }
+        }
+    }
 
-    /// Read a cached box-model property value.
-    pub fn get_property(&self, node: NodeId, prop_id: &PropertyId<'static>) -> Option<Subpixel> {
-        let vw = self.ctx.viewport_width;
-        let vh = self.ctx.viewport_height;
-        let resolver = make_resolver(&self.styler, &self.db, vw, vh);
+    /// Re-resolve `node`, recording its old box for the geometry diff.
+    /// Returns the box before and after.
+    fn re_resolve(&mut self, node: NodeId) -> (ComputedBox, ComputedBox) {
+        let old_values = self.last_resolved(node);
+        self.record_geometry(node, &old_values);
+
+        self.resolve_node(node);
+        let mut new_values = self.cached_box(node);
+        if old_values.width != new_values.width {
+            // The node's children were broken into lines against its old
+            // width.
+            self.ctx.invalidate_parent_aggregates(node);
+            self.resolve_node(node);
+            new_values = self.cached_box(node);
+        }
+        (old_values, new_values)
+    }
+
+
+    /// Read a cached box-model property value, after resolving the boxes
+    /// moved by earlier changes.
+    pub fn get_property(
+        &mut self,
+        node: NodeId,
+        prop_id: &PropertyId<'static>,
+    ) -> Option<Subpixel> {
+        self.resolve_dirty_flows();
+        self.cached_property(node, prop_id)
+    }
+
+    /// Cached box-model property value, as it stands.
+    pub(crate) fn cached_property(
+        &self,
+        node: NodeId,
+        prop_id: &PropertyId<'static>,
+    ) -> Option<Subpixel> {
+        let resolver = self.resolver();
         let formula = property_query(node, &resolver, prop_id)?;
         self.ctx.get_cached(formula, node)
     }
 
+    /// Line length for the inline content of `node`.
+    pub(crate) fn available_inline_size(&mut self, node: NodeId) -> Option<Subpixel> {
+        let resolver = self.resolver();
+        let formula = available_inline_size_query(node, &resolver)?;
+        self.ctx.resolve(formula, node, &resolver)
+    }
+
+    /// Property resolver over this state's styler and database.
+    pub(crate) fn resolver(&self) -> CssPropertyResolver {
+        let vw = self.ctx.viewport_width;
+        let vh = self.ctx.viewport_height;
+        CssPropertyResolver::new(self.styler.clone(), self.db.clone(), vw, vh)
+            .with_layout_flags(self.flags)
+            .with_text_autosizing(self.autosizing)
+            .with_custom_layouts(Arc::clone(&self.custom_layouts))
+            .with_intrinsic_sizes(Arc::clone(&self.intrinsic_sizes))
+            .with_font_dependents(Arc::clone(&self.font_dependents))
+            .with_viewports(self.small_viewport, self.dynamic_viewport)
+            .with_viewport_dependents(Arc::clone(&self.viewport_dependents))
+    }
+
+    /// Used value of a color property, with forced colors applied.
+    pub fn used_color(&self, node: NodeId, prop_id: &PropertyId<'static>) -> Option<RGBA> {
+        used_color(&self.styler, &self.db, node, prop_id)
+    }
+
+    /// Used value of a color property in its authored color space, for
+    /// painting wide-gamut colors unclipped.
+    pub fn used_computed_color(
+        &self,
+        node: NodeId,
+        prop_id: &PropertyId<'static>,
+    ) -> Option<ComputedColor> {
+        used_computed_color(&self.styler, &self.db, node, prop_id)
+    }
+
+    /// The kind of box `node` generates, or `None` for `display: none`.
+    pub fn formatting_context(&self, node: NodeId) -> Option<FormattingContext> {
+        formatting_context(node, &self.resolver())
+    }
+
+    /// Overflow applied to the viewport after `html`/`body` propagation.
+    /// The viewport is the root scroller; `source` names the element whose
+    /// overflow values it uses.
+    pub fn viewport_overflow(&self) -> ViewportOverflow {
+        viewport_overflow(&self.resolver())
+    }
+
     /// Resolve all nodes, reusing cached values from incremental updates.
     pub fn resolve_nodes(&mut self, nodes: &[NodeId]) {
+        // Nothing changes between the nodes, so the values laid out for
+        // one table part hold for the rest.
+        self.ctx.begin_batch();
         for &node in nodes {
             self.resolve_node(node);
         }
+        self.ctx.end_batch();
+        self.resolve_dirty_flows();
+    }
+
+    /// Drop every cached value and re-resolve all nodes seen so far.
+    fn relayout_all(&mut self) {
+        let nodes: Vec<NodeId> = self.formulas.keys().copied().collect();
+        for &node in &nodes {
+            let old_values = self.last_resolved(node);
+            self.record_geometry(node, &old_values);
+        }
+        self.clear_cache();
+        self.resolve_nodes(&nodes);
+        if !nodes.is_empty() {
+            self.rebuild_stacking();
+        }
+    }
+
+    /// Layout values that couldn't be resolved because of a dependency
+    /// cycle or runaway recursion since the last call. The affected values
+    /// fall back to their defaults.
+    pub fn take_resolve_diagnostics(&mut self) -> Vec<ResolveDiagnostic> {
+        self.ctx.take_diagnostics()
+    }
+
+    /// Formula evaluations since the last `reset_resolve_stats`.
+    pub fn resolve_stats(&self) -> ResolveStats {
+        self.ctx.stats()
+    }
+
+    /// Zero the formula evaluation counters.
+    pub fn reset_resolve_stats(&mut self) {
+        self.ctx.reset_stats();
+    }
+
+    /// Lay out a different document from now on. Cached values and
+    /// formulas of the old document are dropped, as are the natural sizes
+    /// of its replaced elements; viewport, flags and the resolve counters
+    /// carry over.
+    pub fn replace_document(&mut self, styler: Arc<Styler>, db: Arc<Database>) {
+        self.clear_cache();
+        self.geometry_baseline.clear();
+        self.scroll_offsets.clear();
+        self.intrinsic_sizes = Arc::default();
+        self.font_dependents.clear();
+        self.viewport_dependents.clear();
+        self.styler = styler;
+        self.db = db;
+        self.stacking = StackingTree::default();
     }
 
     /// Clear all cached layout values. Used for benchmarking to force
@@ -272,6 +1141,10 @@ impl LayoutState {
     pub fn clear_cache(&mut self) {
         self.ctx.clear_cache();
         self.formulas.clear();
+        self.boxless.clear();
+        self.container_metrics.clear();
+        self.dirty_flows.clear();
+        self.dirty_flow_order.clear();
     }
 }
 
@@ -291,9 +1164,44 @@ impl Renderer {
         }
     }
 
+    /// The layout state, with the boxes moved by earlier changes resolved.
+    fn layout(&self) -> MutexGuard<'_, LayoutState> {
+        let mut layout = self.layout.lock().unwrap_or_else(PoisonError::into_inner);
+        layout.resolve_dirty_flows();
+        layout
+    }
+
+    /// Change the viewport size, and with it the viewport features `@media`
+    /// queries are evaluated against.
     pub fn set_viewport(&self, width: u32, height: u32) {
         self.viewport_width.store(width, Ordering::Relaxed);
         self.viewport_height.store(height, Ordering::Relaxed);
+        let styler = {
+            let mut layout = self.layout();
+            layout.set_viewport(width, height);
+            Arc::clone(&layout.styler)
+        };
+        // Rematching notifies this renderer, so the layout lock must be
+        // released first.
+        styler.set_media_environment(MediaEnvironment {
+            viewport: Some((width, height)),
+            ..styler.media_environment()
+        });
+    }
+
+    /// Set, or with `None` reset to the layout viewport, the size `sv*`
+    /// lengths resolve against. `set_viewport` resets it.
+    pub fn set_small_viewport(&self, size: Option<(u32, u32)>) {
+        let mut layout = self.layout();
+        layout.set_small_viewport(size);
+    }
+
+    /// Set, or with `None` reset to the layout viewport, the size `dv*`
+    /// lengths resolve against, as retractable browser UI moves.
+    /// `set_viewport` resets it.
+    pub fn set_dynamic_viewport(&self, size: Option<(u32, u32)>) {
+        let mut layout = self.layout();
+        layout.set_dynamic_viewport(size);
     }
 
     pub fn viewport_width(&self) -> u32 {
@@ -303,6 +1211,300 @@ impl Renderer {
     pub fn viewport_height(&self) -> u32 {
         self.viewport_height.load(Ordering::Relaxed)
     }
+
+    /// Overflow applied to the viewport (the root scroller).
+    pub fn viewport_overflow(&self) -> ViewportOverflow {
+        let layout = self.layout();
+        layout.viewport_overflow()
+    }
+
+    /// Nodes the layout state has formulas for.
+    pub fn tracked_nodes(&self) -> Vec<NodeId> {
+        let layout = self.layout();
+        layout.tracked_nodes()
+    }
+
+    /// Used value of a color property on `node`. In forced-colors mode this
+    /// is the embedder palette color, not the author's.
+    pub fn used_color(&self, node: NodeId, prop_id: &PropertyId<'static>) -> Option<RGBA> {
+        let layout = self.layout();
+        layout.used_color(node, prop_id)
+    }
+
+    /// Used value of a color property on `node` without conversion to
+    /// sRGB, so `color(display-p3 …)` and other wide-gamut colors reach
+    /// the renderer intact. `ComputedColor::to_srgb` converts.
+    pub fn used_computed_color(
+        &self,
+        node: NodeId,
+        prop_id: &PropertyId<'static>,
+    ) -> Option<ComputedColor> {
+        let layout = self.layout();
+        layout.used_computed_color(node, prop_id)
+    }
+
+    /// Background, border, radii, outline, opacity, transform and theme
+    /// colors of `node`, resolved against its laid-out box.
+    pub fn paint_info(&self, node: NodeId) -> Option<PaintInfo> {
+        let layout = self.layout();
+        layout.paint_info(node)
+    }
+
+    /// Every node that generates a box, in the order to paint them: back
+    /// to front by stacking context and `z-index`.
+    pub fn paint_order(&self) -> Vec<NodeId> {
+        let layout = self.layout();
+        layout.paint_order()
+    }
+
+    /// Backgrounds, borders and text runs of the whole document, back to
+    /// front.
+    pub fn display_list(&self) -> DisplayList {
+        let layout = self.layout();
+        layout.display_list()
+    }
+
+    /// The node pointer input at `(x, y)` is routed to: the topmost box
+    /// there, passing over `pointer-events: none`, hidden and inert boxes.
+    pub fn hit_test(&self, x: Subpixel, y: Subpixel) -> Option<NodeId> {
+        let layout = self.layout();
+        layout.hit_test(x, y)
+    }
+
+    /// The node `hit_test` finds at `(x, y)` and the elements around it,
+    /// innermost first.
+    pub fn hit_test_all(&self, x: Subpixel, y: Subpixel) -> Vec<NodeId> {
+        let layout = self.layout();
+        layout.hit_test_all(x, y)
+    }
+
+    /// The client and scrollable areas of `node` and its scroll offset.
+    pub fn scroll_metrics(&self, node: NodeId) -> Option<ScrollMetrics> {
+        let layout = self.layout();
+        layout.scroll_metrics(node)
+    }
+
+    /// Scroll `node` to `(x, y)`, shifting where its descendants are drawn
+    /// and hit. Returns `false` if it isn't a scroll container.
+    pub fn set_scroll_offset(&self, node: NodeId, x: Subpixel, y: Subpixel) -> bool {
+        let mut layout = self.layout();
+        layout.set_scroll_offset(node, x, y)
+    }
+
+    /// Every match of `query` in the rendered text, in document order.
+    pub fn find_text(&self, query: &str) -> Vec<Vec<TextRange>> {
+        let layout = self.layout();
+        layout.find_text(query)
+    }
+
+    /// The first match of `query` and the scroll offset of `container`
+    /// that reveals it, for text fragment anchors and find-in-page.
+    pub fn scroll_to_text(&self, query: &str, container: NodeId) -> Option<TextTarget> {
+        let layout = self.layout();
+        layout.scroll_to_text(query, container)
+    }
+
+    /// The rect drawing `node` is clipped to by its ancestors' `overflow`.
+    pub fn clip_rect(&self, node: NodeId) -> Option<Rect> {
+        let layout = self.layout();
+        layout.clip_rect(node)
+    }
+
+    /// The computed `cursor` of `node`.
+    pub fn cursor(&self, node: NodeId) -> Cursor {
+        let layout = self.layout();
+        layout.cursor(node)
+    }
+
+    /// The cursor to show with the pointer at `(x, y)`, with `auto`
+    /// resolved against what's under it.
+    pub fn cursor_at(&self, x: Subpixel, y: Subpixel) -> Cursor {
+        let layout = self.layout();
+        layout.cursor_at(x, y)
+    }
+
+    /// The computed style of text node `node`, inherited from its parent
+    /// element through the anonymous inline box.
+    pub fn text_style(&self, node: NodeId) -> Option<TextStyle> {
+        let layout = self.layout();
+        layout.text_style(node)
+    }
+
+    /// The metrics of the container `node` was last laid out against.
+    /// Only recorded with `LayoutFlags::record_container_metrics`.
+    pub fn container_metrics(&self, node: NodeId) -> Option<ContainerMetrics> {
+        let layout = self.layout();
+        layout.container_metrics(node)
+    }
+
+    /// Changes whose incremental paint order update disagreed with a full
+    /// rebuild since the last call. Only recorded with
+    /// `LayoutFlags::verify_paint_order`.
+    pub fn take_paint_order_mismatches(&self) -> Vec<PaintOrderMismatch> {
+        let mut layout = self.layout();
+        layout.take_paint_order_mismatches()
+    }
+
+    /// Cached layout values for `node` (absolute border box).
+    pub fn computed_box(&self, node: NodeId) -> ComputedBox {
+        let layout = self.layout();
+        layout.cached_box(node)
+    }
+
+    /// Used box-model values for `node` (all px), if it has a laid-out box.
+    pub fn used_values(&self, node: NodeId) -> Option<UsedValues> {
+        let layout = self.layout();
+        layout.resolve_used_values(node)
+    }
+
+    /// The `area` rect of `node` in absolute coordinates, if it has a
+    /// laid-out box.
+    pub fn box_rect(&self, node: NodeId, area: BoxArea) -> Option<Rect> {
+        self.used_values(node).map(|used| used.rect(area))
+    }
+
+    /// Every node with a laid-out box and its `area` rect, in tree order.
+    pub fn laid_out_boxes(&self, area: BoxArea) -> Vec<(NodeId, Rect)> {
+        let layout = self.layout();
+        layout
+            .fragment_tree()
+            .iter()
+            .filter_map(|fragment| {
+                let used = layout.resolve_used_values(fragment.node)?;
+                Some((fragment.node, used.rect(area)))
+            })
+            .collect()
+    }
+
+    /// Per-side border width, style and color for `node`.
+    pub fn border_sides(&self, node: NodeId) -> BorderSides {
+        let layout = self.layout();
+        layout.border_sides(node)
+    }
+
+    /// The `pseudo` box of `node`, if it is laid out and generates one.
+    pub fn generated_box(&self, node: NodeId, pseudo: GeneratedBox) -> Option<GeneratedContentBox> {
+        let layout = self.layout();
+        layout.generated_box(node, pseudo)
+    }
+
+    /// Where the border of fieldset `node` is painted, if it has a laid-out
+    /// rendered legend.
+    pub fn fieldset_border(&self, node: NodeId) -> Option<FieldsetBorder> {
+        let layout = self.layout();
+        let border = layout.resolve_used_values(node)?.rect(BoxArea::Border);
+        layout.fieldset_border(node, border)
+    }
+
+    /// The `::marker` box of `node`, if it is a laid-out list item.
+    pub fn marker_box(&self, node: NodeId) -> Option<MarkerBox> {
+        let layout = self.layout();
+        layout.marker_box(node)
+    }
+
+    /// Focus indicator geometry for `node`, if it has a laid-out box.
+    pub fn focus_ring(&self, node: NodeId) -> Option<FocusRing> {
+        let layout = self.layout();
+        layout.focus_ring(node)
+    }
+
+    /// Fragment tree of the whole document from the current layout.
+    pub fn fragment_tree(&self) -> FragmentTree {
+        let layout = self.layout();
+        layout.fragment_tree()
+    }
+
+    /// Layout resolution failures (dependency cycles, runaway recursion)
+    /// since the last call.
+    pub fn take_resolve_diagnostics(&self) -> Vec<ResolveDiagnostic> {
+        let mut layout = self.layout();
+        layout.take_resolve_diagnostics()
+    }
+
+    /// Switch to laying out the document of `styler` and `db`, dropping
+    /// all layout state of the current one.
+    pub fn replace_document(&self, styler: Arc<Styler>, db: Arc<Database>) {
+        let mut layout = self.layout();
+        layout.replace_document(styler, db);
+    }
+
+    /// Border boxes that moved, resized, appeared or disappeared since
+    /// the last call.
+    pub fn take_geometry_diff(&self) -> GeometryDiff {
+        let mut layout = self.layout();
+        layout.take_geometry_diff()
+    }
+
+    /// Anchor node for the viewport scrolled to `scroll_y`, to select
+    /// before content may change height.
+    pub fn scroll_anchor(&self, scroll_y: Subpixel) -> Option<ScrollAnchor> {
+        let layout = self.layout();
+        layout.scroll_anchor(scroll_y)
+    }
+
+    /// Scroll delta that keeps `anchor` in place, or `None` if it lost its
+    /// box.
+    pub fn scroll_anchor_adjustment(&self, anchor: &ScrollAnchor) -> Option<Subpixel> {
+        let layout = self.layout();
+        layout.scroll_anchor_adjustment(anchor)
+    }
+
+    /// Formula evaluations since the last `reset_resolve_stats`.
+    pub fn resolve_stats(&self) -> ResolveStats {
+        let layout = self.layout();
+        layout.resolve_stats()
+    }
+
+    /// Zero the formula evaluation counters.
+    pub fn reset_resolve_stats(&self) {
+        let mut layout = self.layout();
+        layout.reset_resolve_stats();
+    }
+
+    /// Set layout debug flags, re-resolving cached layout.
+    pub fn set_layout_flags(&self, flags: LayoutFlags) {
+        let mut layout = self.layout();
+        layout.set_layout_flags(flags);
+    }
+
+    /// The kind of box `node` generates, or `None` for `display: none`.
+    pub fn formatting_context(&self, node: NodeId) -> Option<FormattingContext> {
+        let layout = self.layout();
+        layout.formatting_context(node)
+    }
+
+    /// Set text autosizing, re-resolving cached layout.
+    pub fn set_text_autosizing(&self, autosizing: Option<TextAutosizing>) {
+        let mut layout = self.layout();
+        layout.set_text_autosizing(autosizing);
+    }
+
+    /// Register a custom layout for `display: layout(<name>)` boxes,
+    /// re-resolving cached layout.
+    pub fn register_custom_layout(&self, name: &str, layout: Arc<dyn CustomLayout>) {
+        let mut layout_state = self.layout();
+        layout_state.register_custom_layout(name, layout);
+    }
+
+    /// Set the natural size of a replaced element, re-resolving the boxes
+    /// it moves.
+    pub fn set_intrinsic_size(&self, node: NodeId, size: Option<IntrinsicSize>) {
+        let mut layout = self.layout();
+        layout.set_intrinsic_size(node, size);
+    }
+
+    /// Add a downloaded font under `family`, re-measuring the text that
+    /// lists it.
+    pub fn load_font(&self, family: &str, data: Vec<u8>) {
+        let mut layout = self.layout();
+        layout.load_font(family, data);
+    }
+
+    /// Factor text autosizing scaled `node`'s font size by.
+    pub fn text_autosize_scale(&self, node: NodeId) -> Option<f32> {
+        let layout = self.layout();
+        layout.text_autosize_scale(node)
+    }
 }
 
 impl Subscriber for Renderer {
@@ -311,6 +1513,16 @@ impl Subscriber for Renderer {
         layout.on_property_change(node, property);
     }
 
+    fn on_property_removed(&self, node: NodeId, property: &PropertyId<'static>) {
+        let mut layout = self.layout.lock().expect("lock poisoned");
+        layout.on_property_removed(node, property);
+    }
+
+    fn on_generated_content(&self, node: NodeId) {
+        let mut layout = self.layout.lock().expect("lock poisoned");
+        layout.on_generated_content(node);
+    }
+
     fn on_dom(&self, update: DomBroadcast) {
         match update {
             DomBroadcast::CreateNode { node, parent } => {
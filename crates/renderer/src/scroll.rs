@@ -0,0 +1,185 @@
+//! Scroll containers.
+//!
+//! A box with `overflow: hidden`, `scroll` or `auto` on either axis is a
+//! scroll container: its content is clipped to its padding box and can be
+//! scrolled within it. Its scrollable overflow is the padding box grown to
+//! the content overflowing its right and bottom edges; content overflowing
+//! the left or top edge can't be scrolled to (CSS Overflow 3 §2.2).
+//!
+//! Scroll offsets are set by the embedder and kept within the scrollable
+//! range. They don't change layout, only where the container's
+//! descendants are drawn and hit: the fragment tree, the display list and
+//! hit testing see them shifted. Every DOM descendant is shifted, including
+//! out-of-flow boxes whose containing block is outside the container.
+
+use crate::fragment::{Fragment, FragmentTree};
+use crate::renderer::LayoutState;
+use lightningcss::properties::overflow::OverflowKeyword;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{NodeId, PropertyResolver, Subpixel};
+use rewrite_layout::{Rect, propagates_overflow_to_viewport};
+use std::collections::HashMap;
+
+/// The client and scrollable areas of a box, in document coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrollMetrics {
+    /// The padding box, through which the content is seen.
+    pub client: Rect,
+    /// The scrollable overflow: the client area grown to the content
+    /// overflowing its right and bottom edges.
+    pub scroll: Rect,
+    /// Horizontal scroll offset, from zero to `max_offset().0`.
+    pub offset_x: Subpixel,
+    /// Vertical scroll offset, from zero to `max_offset().1`.
+    pub offset_y: Subpixel,
+}
+
+impl ScrollMetrics {
+    /// The largest offset on each axis: how far the content overflows the
+    /// client area.
+    pub fn max_offset(&self) -> (Subpixel, Subpixel) {
+        (
+            self.scroll.width - self.client.width,
+            self.scroll.height - self.client.height,
+        )
+    }
+}
+
+/// Whether `node` is a scroll container. Overflow propagated to the
+/// viewport scrolls the viewport, not the element.
+fn is_scroll_container(node: NodeId, resolver: &dyn PropertyResolver) -> bool {
+    !propagates_overflow_to_viewport(node, resolver)
+        && [PropertyId::OverflowX, PropertyId::OverflowY]
+            .iter()
+            .any(|prop_id| {
+                matches!(
+                    resolver.get_css_property(node, prop_id),
+                    Some(Property::OverflowX(keyword) | Property::OverflowY(keyword))
+                        if matches!(
+                            keyword,
+                            OverflowKeyword::Hidden | OverflowKeyword::Scroll | OverflowKeyword::Auto
+                        )
+                )
+            })
+}
+
+impl LayoutState {
+    /// The client and scrollable areas of `node` and its scroll offset.
+    /// `None` if it has no box. Boxes that aren't scroll containers have
+    /// metrics too, with a zero offset.
+    pub fn scroll_metrics(&self, node: NodeId) -> Option<ScrollMetrics> {
+        let resolver = self.resolver();
+        let fragment = self
+            .fragments(node, &resolver)
+            .into_iter()
+            .find(|fragment| fragment.node == node)?;
+        let client = match fragment.clip {
+            Some(clip) => clip,
+            None => self.resolve_used_values(node)?.padding_box(),
+        };
+        Some(self.metrics_of(&fragment, client))
+    }
+
+    /// Scroll `node` to `(x, y)`, clamped to its scrollable range.
+    /// Returns `false`, changing nothing, if it isn't a scroll container.
+    pub fn set_scroll_offset(&mut self, node: NodeId, x: Subpixel, y: Subpixel) -> bool {
+        if !is_scroll_container(node, &self.resolver()) {
+            return false;
+        }
+        let Some(metrics) = self.scroll_metrics(node) else {
+            return false;
+        };
+        let (max_x, max_y) = metrics.max_offset();
+        let offset = (
+            x.min(max_x).max(Subpixel::ZERO),
+            y.min(max_y).max(Subpixel::ZERO),
+        );
+        self.scroll_offsets.insert(node, offset);
+        true
+    }
+
+    /// The rect drawing `node` is clipped to, where it is drawn: the
+    /// intersection of the padding boxes of its clipping ancestors. `None`
+    /// if no ancestor clips it or it has no box.
+    pub fn clip_rect(&self, node: NodeId) -> Option<Rect> {
+        self.fragment_tree().clip_rects().get(&node).copied()
+    }
+
+    /// Metrics of the box of `fragment`, laid out without scrolling, for
+    /// the client area `client`. The stored offset is clamped to the
+    /// current range, which shrinks when the content does.
+    fn metrics_of(&self, fragment: &Fragment, client: Rect) -> ScrollMetrics {
+        let content = fragment.children.iter().fold(client, |acc, child| {
+            acc.union(&child.overflow_contribution())
+        });
+        let scroll = Rect::new(
+            client.x,
+            client.y,
+            content.right() - client.x,
+            content.bottom() - client.y,
+        );
+        let mut metrics = ScrollMetrics {
+            client,
+            scroll,
+            offset_x: Subpixel::ZERO,
+            offset_y: Subpixel::ZERO,
+        };
+        if let Some(&(x, y)) = self.scroll_offsets.get(&fragment.node) {
+            let (max_x, max_y) = metrics.max_offset();
+            metrics.offset_x = x.min(max_x);
+            metrics.offset_y = y.min(max_y);
+        }
+        metrics
+    }
+
+    /// Shift the descendants of scrolled boxes among `fragments`, laid out
+    /// without scrolling, to where they are drawn.
+    pub(crate) fn apply_scroll_offsets(&self, fragments: &mut [Fragment]) {
+        if !self.scroll_offsets.is_empty() {
+            self.shift_fragments(fragments, Subpixel::ZERO, Subpixel::ZERO);
+        }
+    }
+
+    /// Move `fragments` by `(dx, dy)` and their content by the scroll
+    /// offsets of the scrolled ones as well.
+    fn shift_fragments(&self, fragments: &mut [Fragment], dx: Subpixel, dy: Subpixel) {
+        for fragment in fragments {
+            let (mut content_dx, mut content_dy) = (dx, dy);
+            if let Some(client) = fragment.clip
+                && self.scroll_offsets.contains_key(&fragment.node)
+            {
+                let metrics = self.metrics_of(fragment, client);
+                content_dx -= metrics.offset_x;
+                content_dy -= metrics.offset_y;
+            }
+            fragment.rect = fragment.rect.translate(dx, dy);
+            fragment.overflow = fragment.overflow.translate(dx, dy);
+            fragment.clip = fragment.clip.map(|clip| clip.translate(dx, dy));
+            self.shift_fragments(&mut fragment.children, content_dx, content_dy);
+        }
+    }
+}
+
+impl FragmentTree {
+    /// For every clipped fragment, the rect it is clipped to: the
+    /// intersection of the clips of its ancestors. Unclipped fragments
+    /// have no entry.
+    pub fn clip_rects(&self) -> HashMap<NodeId, Rect> {
+        let mut clips = HashMap::new();
+        collect_clips(&self.roots, None, &mut clips);
+        clips
+    }
+}
+
+fn collect_clips(fragments: &[Fragment], clip: Option<Rect>, clips: &mut HashMap<NodeId, Rect>) {
+    for fragment in fragments {
+        if let Some(clip) = clip {
+            clips.insert(fragment.node, clip);
+        }
+        let inner = match (clip, fragment.clip) {
+            (Some(outer), Some(own)) => Some(outer.intersection(&own)),
+            (outer, own) => outer.or(own),
+        };
+        collect_clips(&fragment.children, inner, clips);
+    }
+}
@@ -0,0 +1,104 @@
+//! Scroll anchoring.
+//!
+//! When content above the visible part of the document changes height
+//! (an image or a streamed chunk arrives), everything in view would jump.
+//! Following CSS Scroll Anchoring, an embedder selects an anchor node in
+//! view before the change and, after it, scrolls by however far the
+//! anchor moved. Only the viewport (the root scroller) is anchored, and
+//! only in the block direction.
+
+use crate::fragment::Fragment;
+use crate::renderer::LayoutState;
+use lightningcss::properties::position::Position;
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{NodeId, PropertyResolver, Subpixel, overflow_anchor_id};
+use rewrite_css::{CssValue, values_from_tokens};
+use rewrite_layout::Rect;
+
+/// A node chosen to keep in place across layout changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrollAnchor {
+    pub node: NodeId,
+    /// Top of the node's border box when it was selected, in absolute
+    /// coordinates.
+    pub top: Subpixel,
+}
+
+impl LayoutState {
+    /// Select the anchor node for the viewport scrolled to `scroll_y`.
+    ///
+    /// Candidates are examined in tree order. The first one whose box
+    /// (including unclipped overflow) is entirely in view is the anchor. A
+    /// partially visible text node or replaced element is the anchor
+    /// itself; any other partially visible box is searched for one.
+    /// Boxes with `overflow-anchor: none` and absolutely positioned or
+    /// fixed boxes are skipped with their subtrees. Returns `None` at the top of the document, where
+    /// anchoring is suppressed.
+    pub fn scroll_anchor(&self, scroll_y: Subpixel) -> Option<ScrollAnchor> {
+        if scroll_y <= Subpixel::ZERO {
+            return None;
+        }
+        let resolver = self.resolver();
+        let tree = self.unscrolled_fragment_tree();
+        let region = Rect::new(
+            tree.viewport.x,
+            scroll_y,
+            tree.viewport.width,
+            tree.viewport.height,
+        );
+        let anchor = select_anchor(&tree.roots, &region, &resolver)?;
+        Some(ScrollAnchor {
+            node: anchor.node,
+            top: anchor.rect.y,
+        })
+    }
+
+    /// How far to scroll so that `anchor` stays where it was when it was
+    /// selected. `None` if the anchor no longer has a box, in which case
+    /// the embedder should select a new one.
+    pub fn scroll_anchor_adjustment(&self, anchor: &ScrollAnchor) -> Option<Subpixel> {
+        let rect = self.cached_box(anchor.node).border_box()?;
+        Some(rect.y - anchor.top)
+    }
+}
+
+/// The anchor among `candidates` and their descendants, for the visible
+/// `region`. See `LayoutState::scroll_anchor`.
+fn select_anchor<'tree>(
+    candidates: &'tree [Fragment],
+    region: &Rect,
+    resolver: &dyn PropertyResolver,
+) -> Option<&'tree Fragment> {
+    candidates
+        .iter()
+        .filter(|fragment| !is_excluded(fragment.node, resolver))
+        .find_map(|fragment| {
+            let bounds = fragment.overflow_contribution();
+            if bounds.y >= region.y && bounds.bottom() <= region.bottom() {
+                return Some(fragment);
+            }
+            if bounds.y >= region.bottom() || bounds.bottom() <= region.y {
+                return None;
+            }
+            if resolver.is_intrinsic(fragment.node) {
+                return Some(fragment);
+            }
+            select_anchor(&fragment.children, region, resolver)
+        })
+}
+
+/// Whether `node` and its subtree can't hold the anchor.
+fn is_excluded(node: NodeId, resolver: &dyn PropertyResolver) -> bool {
+    let out_of_flow = matches!(
+        resolver.get_css_property(node, &PropertyId::Position),
+        Some(Property::Position(Position::Absolute | Position::Fixed))
+    );
+    let opted_out = match resolver.get_css_property(node, &overflow_anchor_id()) {
+        Some(Property::Custom(custom)) => matches!(
+            values_from_tokens(&custom.value).as_slice(),
+            [CssValue::Keyword(keyword)] if keyword == "none"
+        ),
+        _ => false,
+    };
+    out_of_flow || opted_out
+}
@@ -0,0 +1,244 @@
+//! Text fragment anchors.
+//!
+//! Finds text in the laid-out document, as `#:~:text=` URL fragments and
+//! find-in-page do, and works out where a scroll container must scroll to
+//! reveal it. Matching is modelled on URL Fragment Text Directives, without
+//! the word-boundary check: it is case-insensitive, runs over the rendered
+//! text (whitespace collapsed as painted, runs of whitespace in the query
+//! matching any run of whitespace), and a match can span text nodes within
+//! a block but not cross into another block. Text in `display: none`
+//! subtrees has no box and can't match.
+//!
+//! Geometry is in document coordinates, as laid out without scrolling. A
+//! match in a text node that wraps is located on its line by measuring the
+//! wrapped text before it; past the first line it spans the whole line.
+
+use crate::fragment::Fragment;
+use crate::renderer::LayoutState;
+use lightningcss::properties::display::{Display, DisplayInside, DisplayOutside};
+use lightningcss::properties::{Property, PropertyId};
+use rewrite_core::{NodeId, PropertyResolver, Subpixel};
+use rewrite_layout::Rect;
+use rewrite_text::DEFAULT_FONT_SIZE_PX;
+use std::ops::Range;
+
+/// Part of a match within one text node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextRange {
+    pub node: NodeId,
+    /// Byte range in the node's rendered text (`text_content`).
+    pub range: Range<usize>,
+}
+
+/// The first match of a text query, and how to scroll to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextTarget {
+    /// The matched text, in document order.
+    pub ranges: Vec<TextRange>,
+    /// Bounding box of the matched text.
+    pub rect: Rect,
+    /// Scroll offset of the container that centers the match vertically
+    /// and brings it into view horizontally, within the scrollable range.
+    pub scroll_x: Subpixel,
+    pub scroll_y: Subpixel,
+}
+
+/// The rendered text of the text nodes of one block, in document order.
+#[derive(Debug, Default)]
+struct TextRun {
+    text: String,
+    /// Text nodes with the byte offset of their text in `text`.
+    nodes: Vec<(NodeId, usize)>,
+}
+
+impl TextRun {
+    /// The parts of the byte range `range` of the run in each text node.
+    fn split(&self, range: &Range<usize>) -> Vec<TextRange> {
+        let mut ranges = Vec::new();
+        for (index, &(node, start)) in self.nodes.iter().enumerate() {
+            let end = self
+                .nodes
+                .get(index + 1)
+                .map_or(self.text.len(), |&(_, next)| next);
+            let (first, past_end) = (range.start.max(start), range.end.min(end));
+            if first < past_end {
+                ranges.push(TextRange {
+                    node,
+                    range: first - start..past_end - start,
+                });
+            }
+        }
+        ranges
+    }
+}
+
+/// Text folded for matching: lowercase, with each run of whitespace a
+/// single space.
+struct Folded {
+    chars: Vec<char>,
+    /// For each folded char, the byte range it came from.
+    sources: Vec<Range<usize>>,
+}
+
+fn fold(text: &str) -> Folded {
+    let mut folded = Folded {
+        chars: Vec::new(),
+        sources: Vec::new(),
+    };
+    for (start, original) in text.char_indices() {
+        let source = start..start + original.len_utf8();
+        if original.is_whitespace() {
+            if folded.chars.last() == Some(&' ') {
+                if let Some(last) = folded.sources.last_mut() {
+                    last.end = source.end;
+                }
+            } else {
+                folded.chars.push(' ');
+                folded.sources.push(source);
+            }
+            continue;
+        }
+        for lower in original.to_lowercase() {
+            folded.chars.push(lower);
+            folded.sources.push(source.clone());
+        }
+    }
+    folded
+}
+
+/// Whether `node` starts a new block of text: an element other than a
+/// plain inline box.
+fn starts_block(node: NodeId, resolver: &dyn PropertyResolver) -> bool {
+    resolver.is_element(node)
+        && !matches!(
+            resolver.get_css_property(node, &PropertyId::Display),
+            Some(Property::Display(Display::Pair(pair)))
+                if pair.outside == DisplayOutside::Inline && pair.inside == DisplayInside::Flow
+        )
+}
+
+/// Append the text of `fragments` to `runs`, starting a new run at every
+/// block boundary.
+fn collect_runs(fragments: &[Fragment], resolver: &dyn PropertyResolver, runs: &mut Vec<TextRun>) {
+    for fragment in fragments {
+        let block = starts_block(fragment.node, resolver);
+        if block {
+            runs.push(TextRun::default());
+        }
+        if let Some(text) = resolver.text_content(fragment.node) {
+            if runs.is_empty() {
+                runs.push(TextRun::default());
+            }
+            if let Some(run) = runs.last_mut() {
+                run.nodes.push((fragment.node, run.text.len()));
+                run.text.push_str(&text);
+            }
+        }
+        collect_runs(&fragment.children, resolver, runs);
+        if block {
+            runs.push(TextRun::default());
+        }
+    }
+}
+
+impl LayoutState {
+    /// Every match of `query` in the rendered text, in document order. An
+    /// empty or all-whitespace query matches nothing.
+    pub fn find_text(&self, query: &str) -> Vec<Vec<TextRange>> {
+        let needle = fold(query.trim()).chars;
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        let resolver = self.resolver();
+        let mut runs = Vec::new();
+        collect_runs(&self.unscrolled_fragment_tree().roots, &resolver, &mut runs);
+
+        let mut matches = Vec::new();
+        for run in runs.iter().filter(|run| !run.nodes.is_empty()) {
+            let haystack = fold(&run.text);
+            let mut start = 0;
+            while start + needle.len() <= haystack.chars.len() {
+                if haystack.chars[start..start + needle.len()] != needle[..] {
+                    start += 1;
+                    continue;
+                }
+                let last = start + needle.len() - 1;
+                let range = haystack.sources[start].start..haystack.sources[last].end;
+                matches.push(run.split(&range));
+                start += needle.len();
+            }
+        }
+        matches
+    }
+
+    /// Bounding box of `ranges`. `None` if none of them is laid out.
+    pub fn text_ranges_rect(&self, ranges: &[TextRange]) -> Option<Rect> {
+        let resolver = self.resolver();
+        ranges
+            .iter()
+            .filter_map(|range| self.text_range_rect(range, &resolver))
+            .reduce(|acc, rect| acc.union(&rect))
+    }
+
+    /// The first match of `query`, with the scroll offset of `container`
+    /// that reveals it. `None` if nothing matches or `container` has no
+    /// box.
+    pub fn scroll_to_text(&self, query: &str, container: NodeId) -> Option<TextTarget> {
+        let ranges = self.find_text(query).into_iter().next()?;
+        let rect = self.text_ranges_rect(&ranges)?;
+        let metrics = self.scroll_metrics(container)?;
+        let (max_x, max_y) = metrics.max_offset();
+        let client = metrics.client;
+
+        // Block axis: center the match. Inline axis: scroll as little as
+        // needed to show it.
+        let centered = rect.y - client.y - (client.height - rect.height) / Subpixel::raw(2);
+        let left = rect.x - client.x;
+        let right = rect.right() - client.x - client.width;
+        let scroll_x = if left < metrics.offset_x {
+            left
+        } else if right > metrics.offset_x {
+            right.min(left)
+        } else {
+            metrics.offset_x
+        };
+        Some(TextTarget {
+            ranges,
+            rect,
+            scroll_x: scroll_x.min(max_x).max(Subpixel::ZERO),
+            scroll_y: centered.min(max_y).max(Subpixel::ZERO),
+        })
+    }
+
+    /// Where the text of `range` is drawn, unscrolled.
+    fn text_range_rect(&self, range: &TextRange, resolver: &dyn PropertyResolver) -> Option<Rect> {
+        let node_rect = self.cached_box(range.node).border_box()?;
+        let text = resolver.text_content(range.node)?;
+        let font_size = resolver
+            .get_property(range.node, &PropertyId::FontSize)
+            .map_or(DEFAULT_FONT_SIZE_PX, Subpixel::to_f32);
+        let measure = |end: usize, max_width: Option<f32>| {
+            resolver.measure_text(range.node, text.get(..end)?, font_size, max_width)
+        };
+        let before = measure(range.range.start, None)?;
+        let through = measure(range.range.end, None)?;
+        let line_height = Subpixel::from_f32(through.height);
+        let width = node_rect.width.to_f32();
+        if through.width <= width {
+            return Some(Rect::new(
+                node_rect.x + Subpixel::from_f32(before.width),
+                node_rect.y,
+                Subpixel::from_f32(through.width - before.width),
+                line_height,
+            ));
+        }
+        // Past the first line: the line the wrapped text ends on.
+        let wrapped = measure(range.range.end, Some(width))?;
+        Some(Rect::new(
+            node_rect.x,
+            node_rect.y + Subpixel::from_f32(wrapped.height) - line_height,
+            node_rect.width,
+            line_height,
+        ))
+    }
+}
@@ -0,0 +1,91 @@
+//! Computed style of text runs.
+//!
+//! Text directly inside an element is laid out in an anonymous inline box
+//! that inherits every inherited property from the element and takes the
+//! initial value of the rest. Text nodes hold no declarations of their
+//! own, so their style is exactly that inherited set; `TextStyle` gathers
+//! the parts inline layout and painting read for one run of text.
+
+use crate::renderer::LayoutState;
+use lightningcss::properties::display::Visibility;
+use lightningcss::properties::font::{FontFamily, FontStyle, FontVariantCaps, FontWeight};
+use lightningcss::properties::text::{TextTransform, TextTransformCase, WhiteSpace};
+use lightningcss::properties::{Property, PropertyId};
+use lightningcss::values::color::RGBA;
+use rewrite_core::{NodeId, PropertyResolver, Subpixel};
+use rewrite_css::ComputedColor;
+use rewrite_text::DEFAULT_FONT_SIZE_PX;
+
+/// The computed style of a text node's anonymous inline box.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    pub font_family: Vec<FontFamily<'static>>,
+    /// Computed font size in px, before text autosizing.
+    pub font_size: f32,
+    pub font_weight: FontWeight,
+    pub font_style: FontStyle,
+    pub font_variant_caps: FontVariantCaps,
+    /// Line height in px. `None` for `normal`, which depends on the font.
+    pub line_height: Option<Subpixel>,
+    /// Extra advance after each character, in px.
+    pub letter_spacing: Subpixel,
+    /// Extra advance at each word separator, in px.
+    pub word_spacing: Subpixel,
+    /// `None` for `text-transform: none`.
+    pub text_transform: Option<TextTransform>,
+    pub white_space: WhiteSpace,
+    pub color: ComputedColor,
+    pub visibility: Visibility,
+}
+
+impl LayoutState {
+    /// The computed style of text node `node`, inherited from its parent
+    /// element. `None` if `node` is not a text node.
+    pub fn text_style(&self, node: NodeId) -> Option<TextStyle> {
+        self.styler.tree().text_content(node)?;
+        let resolver = self.resolver();
+        let css = |prop_id| resolver.get_css_property(node, &prop_id);
+        let length = |prop_id| resolver.get_property(node, &prop_id);
+        Some(TextStyle {
+            font_family: match css(PropertyId::FontFamily) {
+                Some(Property::FontFamily(families)) => families,
+                _ => Vec::new(),
+            },
+            font_size: length(PropertyId::FontSize).map_or(DEFAULT_FONT_SIZE_PX, Subpixel::to_f32),
+            font_weight: match css(PropertyId::FontWeight) {
+                Some(Property::FontWeight(weight)) => weight,
+                _ => FontWeight::default(),
+            },
+            font_style: match css(PropertyId::FontStyle) {
+                Some(Property::FontStyle(style)) => style,
+                _ => FontStyle::Normal,
+            },
+            font_variant_caps: match css(PropertyId::FontVariantCaps) {
+                Some(Property::FontVariantCaps(caps)) => caps,
+                _ => FontVariantCaps::Normal,
+            },
+            line_height: length(PropertyId::LineHeight),
+            letter_spacing: length(PropertyId::LetterSpacing).unwrap_or_default(),
+            word_spacing: length(PropertyId::WordSpacing).unwrap_or_default(),
+            text_transform: match css(PropertyId::TextTransform) {
+                Some(Property::TextTransform(transform))
+                    if transform.case != TextTransformCase::None || !transform.other.is_empty() =>
+                {
+                    Some(transform)
+                }
+                _ => None,
+            },
+            white_space: match css(PropertyId::WhiteSpace) {
+                Some(Property::WhiteSpace(white_space)) => white_space,
+                _ => WhiteSpace::Normal,
+            },
+            color: self
+                .used_computed_color(node, &PropertyId::Color)
+                .unwrap_or_else(|| RGBA::new(0, 0, 0, 1.0).into()),
+            visibility: match css(PropertyId::Visibility) {
+                Some(Property::Visibility(visibility)) => visibility,
+                _ => Visibility::Visible,
+            },
+        })
+    }
+}
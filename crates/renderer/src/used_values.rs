@@ -0,0 +1,119 @@
+//! Used-value queries.
+//!
+//! After layout, every box-model quantity of a node is known in px: `auto`
+//! margins are resolved, percentages are resolved against the containing
+//! block, and keyword widths (`thin`, `medium`) are converted. `UsedValues`
+//! gathers them into one snapshot so embedders don't need to know which
+//! formula backs which property.
+
+use crate::renderer::LayoutState;
+use lightningcss::properties::PropertyId;
+use rewrite_core::{NodeId, Subpixel};
+use rewrite_layout::{EdgeSizes, Rect};
+
+/// Fully resolved box-model values for a single node, in px.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsedValues {
+    /// Border box, in absolute coordinates.
+    pub border_box: Rect,
+    /// Used margins. Negative margins stay negative.
+    pub margin: EdgeSizes,
+    /// Used border widths (zero for `border-style: none`).
+    pub border: EdgeSizes,
+    /// Used padding.
+    pub padding: EdgeSizes,
+}
+
+/// One of the nested boxes of the CSS box model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoxArea {
+    Margin,
+    Border,
+    Padding,
+    Content,
+}
+
+impl UsedValues {
+    /// The rect of `area`, in absolute coordinates.
+    pub fn rect(&self, area: BoxArea) -> Rect {
+        match area {
+            BoxArea::Margin => self.margin_box(),
+            BoxArea::Border => self.border_box,
+            BoxArea::Padding => self.padding_box(),
+            BoxArea::Content => self.content_box(),
+        }
+    }
+
+    /// The border box pushed out by the margins.
+    pub fn margin_box(&self) -> Rect {
+        outset(self.border_box, &self.margin)
+    }
+
+    /// The border box minus the borders.
+    pub fn padding_box(&self) -> Rect {
+        inset(self.border_box, &self.border)
+    }
+
+    /// The padding box minus the padding.
+    pub fn content_box(&self) -> Rect {
+        inset(self.padding_box(), &self.padding)
+    }
+}
+
+/// Shrink `rect` by `edges`, clamping the size at zero.
+fn inset(rect: Rect, edges: &EdgeSizes) -> Rect {
+    Rect::new(
+        rect.x + edges.left,
+        rect.y + edges.top,
+        (rect.width - edges.horizontal()).max(Subpixel::ZERO),
+        (rect.height - edges.vertical()).max(Subpixel::ZERO),
+    )
+}
+
+/// Grow `rect` by `edges`.
+fn outset(rect: Rect, edges: &EdgeSizes) -> Rect {
+    Rect::new(
+        rect.x - edges.left,
+        rect.y - edges.top,
+        rect.width + edges.horizontal(),
+        rect.height + edges.vertical(),
+    )
+}
+
+impl LayoutState {
+    /// Resolve the used values of `node` from the current layout.
+    ///
+    /// Returns `None` if the node has no laid-out box (it was never seen by
+    /// layout, or it doesn't generate a box). Box-model properties that
+    /// layout has no value for are reported as zero.
+    pub fn resolve_used_values(&self, node: NodeId) -> Option<UsedValues> {
+        let computed = self.cached_box(node);
+        let border_box = Rect::new(computed.x?, computed.y?, computed.width?, computed.height?);
+        let edges = |top, right, bottom, left| {
+            let side = |prop_id| self.cached_property(node, &prop_id).unwrap_or(Subpixel::ZERO);
+            EdgeSizes::new(side(top), side(right), side(bottom), side(left))
+        };
+
+        Some(UsedValues {
+            border_box,
+            margin: edges(
+                PropertyId::MarginTop,
+                PropertyId::MarginRight,
+                PropertyId::MarginBottom,
+                PropertyId::MarginLeft,
+            ),
+            border: edges(
+                PropertyId::BorderTopWidth,
+                PropertyId::BorderRightWidth,
+                PropertyId::BorderBottomWidth,
+                PropertyId::BorderLeftWidth,
+            ),
+            padding: edges(
+                PropertyId::PaddingTop,
+                PropertyId::PaddingRight,
+                PropertyId::PaddingBottom,
+                PropertyId::PaddingLeft,
+            ),
+        })
+    }
+}
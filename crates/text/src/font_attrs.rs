@@ -4,11 +4,13 @@ use cosmic_text::{Attrs, Family, Style, Weight};
 use lightningcss::printer::PrinterOptions;
 use lightningcss::properties::Property;
 use lightningcss::properties::font::{
-    AbsoluteFontWeight, FontFamily, FontStyle as CssFontStyle, FontWeight as CssFontWeight,
+    AbsoluteFontWeight, FamilyName, FontFamily, FontStyle as CssFontStyle,
+    FontWeight as CssFontWeight,
 };
 use lightningcss::traits::ToCss as _;
 
 use crate::font_system::{default_font_family, map_font_family};
+use crate::small_caps::caps_features;
 
 /// Default user-agent font size in pixels (CSS spec default).
 pub const DEFAULT_FONT_SIZE_PX: f32 = 16.0;
@@ -21,12 +23,14 @@ pub fn build_attrs<'prop>(
     font_family: Option<&'prop Property<'static>>,
     font_weight: Option<&Property<'static>>,
     font_style: Option<&Property<'static>>,
+    font_variant_caps: Option<&Property<'static>>,
 ) -> Attrs<'prop> {
     let mut attrs = Attrs::new();
 
     attrs = attrs.family(resolve_family(font_family));
     attrs = attrs.weight(resolve_weight(font_weight));
     attrs = attrs.style(resolve_style(font_style));
+    attrs = attrs.font_features(caps_features(font_variant_caps));
 
     attrs
 }
@@ -60,22 +64,40 @@ fn resolve_family(prop: Option<&Property<'static>>) -> Family<'static> {
             }
         }
         FontFamily::FamilyName(name) => {
-            // FamilyName's inner field is private, so use ToCss to get the string.
-            let css_str = name
-                .to_css_string(PrinterOptions::default())
-                .unwrap_or_default();
-            // Strip surrounding quotes that ToCss may add.
-            let trimmed = css_str
-                .trim_start_matches(['\'', '"'])
-                .trim_end_matches(['\'', '"']);
             // Leak the string so it lives for `'static`.  This is acceptable
             // because font family names are a small, bounded set per page.
-            let leaked: &'static str = String::from(trimmed).leak();
+            let leaked: &'static str = family_name(name).leak();
             Family::Name(leaked)
         }
     }
 }
 
+/// Whether a CSS font-family list names `family` (ASCII
+/// case-insensitively), e.g. to find the text a newly loaded font
+/// applies to.
+pub fn lists_family(font_family: Option<&Property<'static>>, family: &str) -> bool {
+    let Some(Property::FontFamily(families)) = font_family else {
+        return false;
+    };
+    families.iter().any(|candidate| match candidate {
+        FontFamily::FamilyName(name) => family_name(name).eq_ignore_ascii_case(family),
+        FontFamily::Generic(_) => false,
+    })
+}
+
+/// The unquoted name of a CSS family name.
+fn family_name(name: &FamilyName<'_>) -> String {
+    // FamilyName's inner field is private, so use ToCss to get the string.
+    let css_str = name
+        .to_css_string(PrinterOptions::default())
+        .unwrap_or_default();
+    // Strip surrounding quotes that ToCss may add.
+    css_str
+        .trim_start_matches(['\'', '"'])
+        .trim_end_matches(['\'', '"'])
+        .to_owned()
+}
+
 /// Resolve CSS font-weight to a cosmic-text `Weight`.
 fn resolve_weight(prop: Option<&Property<'static>>) -> Weight {
     match prop {
@@ -1,7 +1,9 @@
 //! Global font system initialization and font family mapping.
 
-use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Wrap};
+use cosmic_text::fontdb::{Language, Source};
+use cosmic_text::{Attrs, Buffer, Family, Font, FontSystem, Metrics, Shaping, Wrap};
 use std::sync::{Arc, Mutex, PoisonError};
+use swash::scale::ScaleContext;
 
 type FontSystemOption = Option<Arc<Mutex<FontSystem>>>;
 
@@ -45,6 +47,27 @@ pub fn get_font_system() -> Arc<Mutex<FontSystem>> {
     }
 }
 
+/// Add a downloaded font to the global font system under `family`, as an
+/// `@font-face` rule names it, whatever families the font data declares.
+///
+/// Returns whether the data held any font faces. Text already measured
+/// with a fallback font has to be measured again.
+pub fn load_font(family: &str, data: Vec<u8>) -> bool {
+    let font_sys = get_font_system();
+    let mut guard = font_sys.lock().unwrap_or_else(PoisonError::into_inner);
+    let font_db = guard.db_mut();
+    let ids = font_db.load_font_source(Source::Binary(Arc::new(data)));
+    for &id in &ids {
+        let Some(mut face) = font_db.face(id).cloned() else {
+            continue;
+        };
+        font_db.remove_face(id);
+        face.families = vec![(family.to_owned(), Language::English_UnitedStates)];
+        font_db.push_face_info(face);
+    }
+    !ids.is_empty()
+}
+
 /// Map a CSS font family name to a cosmic-text `Family`.
 ///
 /// Single source of truth for font family mapping used by both
@@ -153,3 +176,55 @@ pub fn get_font_metrics(
         line_height,
     })
 }
+
+/// The sizes the `ch` and `ex` units resolve to, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitMetrics {
+    /// Advance of the "0" glyph.
+    pub zero_advance: f32,
+    /// x-height of the font, or `None` if the font doesn't record one.
+    pub x_height: Option<f32>,
+}
+
+/// Shape "0" at the given size and read the advance and the matched font's
+/// x-height, for resolving `ch` and `ex`.
+pub fn get_unit_metrics(
+    font_sys: &mut FontSystem,
+    attrs: &Attrs<'_>,
+    font_size: f32,
+) -> Option<UnitMetrics> {
+    let mut buffer = Buffer::new(font_sys, Metrics::new(font_size, font_size * 2.0));
+    buffer.set_size(font_sys, None, None);
+    buffer.set_wrap(font_sys, Wrap::None);
+    buffer.set_text(font_sys, "0", attrs, Shaping::Advanced, None);
+    buffer.shape_until_scroll(font_sys, false);
+    let glyph = buffer
+        .layout_runs()
+        .find_map(|run| run.glyphs.first().cloned())?;
+
+    let x_height = font_sys
+        .get_font(glyph.font_id, glyph.font_weight)
+        .and_then(|font| x_height(&font, font_size));
+    Some(UnitMetrics {
+        zero_advance: glyph.w,
+        x_height,
+    })
+}
+
+/// The x-height of `font` at the given size: the OS/2 value, or the top of
+/// the "x" glyph for fonts whose OS/2 table predates it.
+fn x_height(font: &Font, font_size: f32) -> Option<f32> {
+    let swash_font = font.as_swash();
+    let recorded = swash_font.metrics(&[]).scale(font_size).x_height;
+    if recorded > 0.0 {
+        return Some(recorded);
+    }
+    let glyph_id = swash_font.charmap().map('x');
+    if glyph_id == 0 {
+        return None;
+    }
+    let mut context = ScaleContext::new();
+    let mut scaler = context.builder(swash_font).size(font_size).build();
+    let top = scaler.scale_outline(glyph_id)?.bounds().max.y;
+    (top > 0.0).then_some(top)
+}
@@ -8,16 +8,32 @@
 //! - [`font_system`]: Global font database singleton and platform font mapping.
 //! - [`font_attrs`]: Convert CSS properties (lightningcss) to cosmic-text `Attrs`.
 //! - [`measure`]: Single-line and wrapped text measurement with Chrome-compatible rounding.
+//! - [`small_caps`]: `font-variant-caps` features and small-caps synthesis.
+//! - [`spacing`]: `letter-spacing` and `word-spacing`.
+//! - [`transform`]: `text-transform` case mapping.
+//! - [`segmentation`]: Grapheme, word and line-break boundaries (UAX #29, UAX #14).
 
 pub mod font_attrs;
 pub mod font_system;
 pub mod measure;
+pub mod segmentation;
+pub mod small_caps;
+pub mod spacing;
+pub mod transform;
 pub mod whitespace;
 
 // Re-export the main public API at crate root.
-pub use font_attrs::{DEFAULT_FONT_SIZE_PX, build_attrs};
-pub use font_system::{get_font_system, map_font_family};
+pub use font_attrs::{DEFAULT_FONT_SIZE_PX, build_attrs, lists_family};
+pub use font_system::{UnitMetrics, get_font_system, get_unit_metrics, load_font, map_font_family};
 pub use measure::{
-    TextMetrics, WrappedTextMetrics, measure_text, measure_text_width, measure_text_wrapped,
+    TextMetrics, WrapOptions, WrappedTextMetrics, measure_text, measure_text_width,
+    measure_text_wrapped,
 };
-pub use whitespace::collapse_whitespace;
+pub use segmentation::{
+    LineBreak, grapheme_count, line_breaks, next_grapheme_boundary, next_word_boundary,
+    prev_grapheme_boundary, prev_word_boundary, unbreakable_segments,
+};
+pub use small_caps::{CapsSynthesis, SYNTHETIC_SMALL_CAPS_SCALE, caps_synthesis};
+pub use spacing::{TextSpacing, is_word_separator};
+pub use transform::transform_text;
+pub use whitespace::{collapse_whitespace, preserves_segment_breaks, process_whitespace, wraps};
@@ -7,6 +7,8 @@
 use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
 
 use crate::font_system::get_font_metrics;
+use crate::small_caps::{SYNTHETIC_SMALL_CAPS_SCALE, caps_synthesis, synthesized_runs};
+use crate::spacing::{TextSpacing, separator_runs};
 
 /// Result of measuring a single line of text.
 #[derive(Debug, Clone, Copy)]
@@ -19,6 +21,10 @@ pub struct TextMetrics {
     pub ascent: f32,
     /// Descent below baseline in pixels (rounded).
     pub descent: f32,
+    /// Small capitals were requested but the font lacks them, so they were
+    /// synthesized from scaled-down capitals. Renderers must fake them the
+    /// same way.
+    pub synthesized_small_caps: bool,
 }
 
 /// Result of measuring text that may wrap across multiple lines.
@@ -36,6 +42,19 @@ pub struct WrappedTextMetrics {
     pub ascent: f32,
     /// Descent below baseline in pixels (rounded).
     pub descent: f32,
+    /// See [`TextMetrics::synthesized_small_caps`].
+    pub synthesized_small_caps: bool,
+}
+
+/// Font size, line length and spacing to measure wrapped text with.
+#[derive(Debug, Clone, Copy)]
+pub struct WrapOptions {
+    /// Font size in pixels.
+    pub font_size: f32,
+    /// Width lines wrap at, in pixels.
+    pub max_width: f32,
+    /// Letter and word spacing.
+    pub spacing: TextSpacing,
 }
 
 /// Resolve font metrics, falling back to CSS default 1.2 line-height.
@@ -53,6 +72,68 @@ fn resolve_metrics(
     )
 }
 
+/// What text is shaped with.
+struct ShapingOptions<'attrs> {
+    /// Font attributes, which also request small capitals.
+    attrs: &'attrs Attrs<'attrs>,
+    /// Font size and line height.
+    metrics: Metrics,
+    /// Letter and word spacing.
+    spacing: TextSpacing,
+}
+
+/// Set the buffer's text, shaped with `options`.
+///
+/// Lowercase runs are shaped as reduced capitals when small capitals have
+/// to be synthesized, and word separators apart when they get extra
+/// spacing. Returns whether capitals were synthesized.
+fn set_buffer_text(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    text: &str,
+    options: &ShapingOptions<'_>,
+) -> bool {
+    let ShapingOptions {
+        attrs,
+        metrics,
+        spacing,
+    } = *options;
+    let synthesis = caps_synthesis(font_system, attrs);
+    if synthesis.is_none() && spacing.is_none() {
+        buffer.set_text(font_system, text, attrs, Shaping::Advanced, None);
+        return false;
+    }
+    let reduced_size = metrics.font_size * SYNTHETIC_SMALL_CAPS_SCALE;
+    let reduced = attrs
+        .clone()
+        .metrics(Metrics::new(reduced_size, metrics.line_height));
+    let runs = synthesis.map_or_else(
+        || vec![(text.to_owned(), false)],
+        |synthesis| synthesized_runs(text, synthesis),
+    );
+    let mut spans = Vec::new();
+    for (run, is_reduced) in &runs {
+        let (run_attrs, font_size) = if *is_reduced {
+            (&reduced, reduced_size)
+        } else {
+            (attrs, metrics.font_size)
+        };
+        let pieces = if spacing.word == 0.0 {
+            vec![(run.as_str(), false)]
+        } else {
+            separator_runs(run)
+        };
+        for (piece, separator) in pieces {
+            spans.push((
+                piece,
+                spacing.apply(run_attrs.clone(), separator, font_size),
+            ));
+        }
+    }
+    buffer.set_rich_text(font_system, spans, attrs, Shaping::Advanced, None);
+    synthesis.is_some()
+}
+
 /// Measure a single line of text (no wrapping).
 ///
 /// Returns the advance width and the Chrome-compatible line height.
@@ -61,6 +142,7 @@ pub fn measure_text(
     text: &str,
     attrs: &Attrs<'_>,
     font_size: f32,
+    spacing: TextSpacing,
 ) -> TextMetrics {
     let (line_height, ascent, descent) = resolve_metrics(font_system, attrs, font_size);
 
@@ -69,7 +151,12 @@ pub fn measure_text(
 
     buffer.set_size(font_system, None, None);
     buffer.set_wrap(font_system, Wrap::None);
-    buffer.set_text(font_system, text, attrs, Shaping::Advanced, None);
+    let shaping = ShapingOptions {
+        attrs,
+        metrics,
+        spacing,
+    };
+    let synthesized_small_caps = set_buffer_text(&mut buffer, font_system, text, &shaping);
     buffer.shape_until_scroll(font_system, false);
 
     let width = buffer
@@ -86,6 +173,7 @@ pub fn measure_text(
         height: line_height,
         ascent,
         descent,
+        synthesized_small_caps,
     }
 }
 
@@ -97,20 +185,25 @@ pub fn measure_text_width(
     text: &str,
     attrs: &Attrs<'_>,
     font_size: f32,
+    spacing: TextSpacing,
 ) -> f32 {
-    measure_text(font_system, text, attrs, font_size).width
+    measure_text(font_system, text, attrs, font_size, spacing).width
 }
 
-/// Measure text that may wrap within `max_width` pixels.
+/// Measure text that may wrap within `options.max_width` pixels.
 ///
 /// Uses word-or-glyph wrapping (same as CSS `overflow-wrap: break-word`).
 pub fn measure_text_wrapped(
     font_system: &mut FontSystem,
     text: &str,
     attrs: &Attrs<'_>,
-    font_size: f32,
-    max_width: f32,
+    options: WrapOptions,
 ) -> WrappedTextMetrics {
+    let WrapOptions {
+        font_size,
+        max_width,
+        spacing,
+    } = options;
     let (line_height, ascent, descent) = resolve_metrics(font_system, attrs, font_size);
 
     let glyph_height = ascent + descent;
@@ -120,7 +213,12 @@ pub fn measure_text_wrapped(
 
     buffer.set_size(font_system, Some(max_width), None);
     buffer.set_wrap(font_system, Wrap::WordOrGlyph);
-    buffer.set_text(font_system, text, attrs, Shaping::Advanced, None);
+    let shaping = ShapingOptions {
+        attrs,
+        metrics,
+        spacing,
+    };
+    let synthesized_small_caps = set_buffer_text(&mut buffer, font_system, text, &shaping);
     buffer.shape_until_scroll(font_system, false);
 
     let mut line_count: usize = 0;
@@ -166,5 +264,6 @@ pub fn measure_text_wrapped(
         line_height,
         ascent,
         descent,
+        synthesized_small_caps,
     }
 }
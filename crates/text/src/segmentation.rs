@@ -0,0 +1,178 @@
+//! Unicode text segmentation.
+//!
+//! Grapheme clusters and words (UAX #29) and line-break opportunities
+//! (UAX #14). Anything that measures, wraps or steps through text goes
+//! through here instead of splitting on chars or whitespace, so an emoji
+//! sequence or a base letter with its combining marks stays whole and CJK
+//! text breaks between ideographs. The line-break rules are the ones
+//! cosmic-text wraps with, so min-content widths agree with wrapping.
+
+use unicode_linebreak::{BreakOpportunity, linebreaks};
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation as _};
+
+/// Whitespace that hangs at the end of a line instead of taking space.
+const HANGING_WHITESPACE: [char; 4] = [' ', '\t', '\n', '\r'];
+
+/// A position where a line may or must end, as a byte offset just after
+/// the last character of the line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineBreak {
+    pub offset: usize,
+    /// Forced by a line feed or similar, or the end of the text.
+    pub mandatory: bool,
+}
+
+/// Every line-break opportunity in `text`, in order. The end of non-empty
+/// text is always a mandatory break.
+pub fn line_breaks(text: &str) -> impl Iterator<Item = LineBreak> + '_ {
+    linebreaks(text).map(|(offset, opportunity)| LineBreak {
+        offset,
+        mandatory: opportunity == BreakOpportunity::Mandatory,
+    })
+}
+
+/// The pieces of `text` between consecutive line-break opportunities,
+/// without their hanging trailing whitespace. The widest of them is the
+/// text's min-content width.
+pub fn unbreakable_segments(text: &str) -> Vec<&str> {
+    let mut start = 0;
+    line_breaks(text)
+        .filter_map(|line_break| {
+            let segment = &text[start..line_break.offset];
+            start = line_break.offset;
+            let segment = segment.trim_end_matches(HANGING_WHITESPACE);
+            (!segment.is_empty()).then_some(segment)
+        })
+        .collect()
+}
+
+/// Number of user-perceived characters (extended grapheme clusters).
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// The grapheme boundary after `offset`, or `text.len()` at the end. Moves
+/// a caret one character forward.
+pub fn next_grapheme_boundary(text: &str, offset: usize) -> usize {
+    let mut cursor = GraphemeCursor::new(offset.min(text.len()), text.len(), true);
+    cursor
+        .next_boundary(text, 0)
+        .ok()
+        .flatten()
+        .unwrap_or(text.len())
+}
+
+/// The grapheme boundary before `offset`, or 0 at the start. Moves a caret
+/// one character back.
+pub fn prev_grapheme_boundary(text: &str, offset: usize) -> usize {
+    let mut cursor = GraphemeCursor::new(offset.min(text.len()), text.len(), true);
+    cursor.prev_boundary(text, 0).ok().flatten().unwrap_or(0)
+}
+
+/// Whether a UAX #29 word segment is a word rather than spacing or
+/// punctuation.
+fn is_word(segment: &str) -> bool {
+    segment.chars().any(char::is_alphanumeric)
+}
+
+/// The end of the first word ending after `offset`, or `text.len()`.
+/// Moves a caret one word forward.
+pub fn next_word_boundary(text: &str, offset: usize) -> usize {
+    text.split_word_bound_indices()
+        .filter(|&(_, segment)| is_word(segment))
+        .map(|(start, segment)| start + segment.len())
+        .find(|&end| end > offset)
+        .unwrap_or(text.len())
+}
+
+/// The start of the last word starting before `offset`, or 0. Moves a
+/// caret one word back.
+pub fn prev_word_boundary(text: &str, offset: usize) -> usize {
+    text.split_word_bound_indices()
+        .filter(|&(_, segment)| is_word(segment))
+        .map(|(start, _)| start)
+        .take_while(|&start| start < offset)
+        .last()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_at_spaces() {
+        assert_eq!(
+            unbreakable_segments("hello big world"),
+            ["hello", "big", "world"]
+        );
+    }
+
+    #[test]
+    fn segments_after_hyphens() {
+        assert_eq!(unbreakable_segments("well-known"), ["well-", "known"]);
+    }
+
+    #[test]
+    fn segments_between_ideographs() {
+        assert_eq!(unbreakable_segments("漢字かな"), ["漢", "字", "か", "な"]);
+    }
+
+    #[test]
+    fn no_break_at_nbsp() {
+        assert_eq!(
+            unbreakable_segments("10\u{a0}km away"),
+            ["10\u{a0}km", "away"]
+        );
+    }
+
+    #[test]
+    fn whitespace_only_has_no_segments() {
+        assert!(unbreakable_segments("   ").is_empty());
+    }
+
+    #[test]
+    fn mandatory_break_at_line_feed() {
+        let breaks: Vec<LineBreak> = line_breaks("a\nb").collect();
+        assert_eq!(
+            breaks,
+            [
+                LineBreak {
+                    offset: 2,
+                    mandatory: true
+                },
+                LineBreak {
+                    offset: 3,
+                    mandatory: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn emoji_sequence_is_one_grapheme() {
+        // Family: man, ZWJ, woman, ZWJ, girl.
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        assert_eq!(grapheme_count(family), 1);
+        assert_eq!(next_grapheme_boundary(family, 0), family.len());
+        assert_eq!(prev_grapheme_boundary(family, family.len()), 0);
+    }
+
+    #[test]
+    fn combining_mark_stays_with_base() {
+        let text = "e\u{301}a";
+        assert_eq!(grapheme_count(text), 2);
+        assert_eq!(next_grapheme_boundary(text, 0), 3);
+        assert_eq!(prev_grapheme_boundary(text, text.len()), 3);
+    }
+
+    #[test]
+    fn word_navigation_skips_spacing() {
+        let text = "one, two  three";
+        assert_eq!(next_word_boundary(text, 0), 3);
+        assert_eq!(next_word_boundary(text, 3), 8);
+        assert_eq!(prev_word_boundary(text, text.len()), 10);
+        assert_eq!(prev_word_boundary(text, 10), 5);
+        assert_eq!(prev_word_boundary(text, 0), 0);
+    }
+}
@@ -0,0 +1,166 @@
+//! `font-variant-caps` and small-caps synthesis.
+//!
+//! Small and petite capitals are requested from the font as OpenType
+//! features, which makes them part of the `Attrs` text is shaped and
+//! cached with. When the matched font has none of the requested features,
+//! CSS Fonts 4 §6.4 has the engine synthesize them: lowercase letters (and
+//! uppercase ones too for `all-small-caps`) are drawn as capitals at a
+//! reduced size. Measurement shapes synthesized text the same way, and
+//! reports it, so a renderer that fakes the capitals gets the same widths.
+
+use cosmic_text::{Attrs, Buffer, FeatureTag, FontFeatures, FontSystem, Metrics, Shaping, Wrap};
+use lightningcss::properties::Property;
+use lightningcss::properties::font::FontVariantCaps;
+
+/// Size of synthesized small capitals relative to the font size.
+pub const SYNTHETIC_SMALL_CAPS_SCALE: f32 = 0.7;
+
+const PETITE_CAPS: FeatureTag = FeatureTag::new(b"pcap");
+const ALL_PETITE_CAPS: FeatureTag = FeatureTag::new(b"c2pc");
+const UNICASE: FeatureTag = FeatureTag::new(b"unic");
+const TITLING_CAPS: FeatureTag = FeatureTag::new(b"titl");
+
+/// The OpenType features `font-variant-caps` enables, none for `normal`.
+pub fn caps_features(prop: Option<&Property<'static>>) -> FontFeatures {
+    let tags: &[FeatureTag] = match prop {
+        Some(Property::FontVariantCaps(caps)) => match caps {
+            FontVariantCaps::Normal => &[],
+            FontVariantCaps::SmallCaps => &[FeatureTag::SMALL_CAPS],
+            FontVariantCaps::AllSmallCaps => &[FeatureTag::SMALL_CAPS, FeatureTag::ALL_SMALL_CAPS],
+            FontVariantCaps::PetiteCaps => &[PETITE_CAPS],
+            FontVariantCaps::AllPetiteCaps => &[PETITE_CAPS, ALL_PETITE_CAPS],
+            FontVariantCaps::Unicase => &[UNICASE],
+            FontVariantCaps::TitlingCaps => &[TITLING_CAPS],
+        },
+        _ => &[],
+    };
+    let mut features = FontFeatures::new();
+    for &tag in tags {
+        features.enable(tag);
+    }
+    features
+}
+
+/// How small capitals requested by `attrs` have to be faked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapsSynthesis {
+    /// Uppercase letters are reduced too (`all-small-caps`,
+    /// `all-petite-caps`).
+    pub all: bool,
+}
+
+/// Whether `attrs` enables `tag`.
+fn requests(attrs: &Attrs<'_>, tag: FeatureTag) -> bool {
+    attrs
+        .font_features
+        .features
+        .iter()
+        .any(|feature| feature.tag == tag && feature.value != 0)
+}
+
+/// The synthesis needed to show the small or petite capitals `attrs`
+/// requests, or `None` if it requests none or the matched font has them.
+/// Unicase and titling capitals are never synthesized.
+pub fn caps_synthesis(font_system: &mut FontSystem, attrs: &Attrs<'_>) -> Option<CapsSynthesis> {
+    let lowercase = [FeatureTag::SMALL_CAPS, PETITE_CAPS];
+    let uppercase = [FeatureTag::ALL_SMALL_CAPS, ALL_PETITE_CAPS];
+    let requested: Vec<FeatureTag> = lowercase
+        .into_iter()
+        .chain(uppercase)
+        .filter(|&tag| requests(attrs, tag))
+        .collect();
+    if requested.is_empty() || font_has_any_feature(font_system, attrs, &requested) {
+        return None;
+    }
+    Some(CapsSynthesis {
+        all: uppercase.into_iter().any(|tag| requests(attrs, tag)),
+    })
+}
+
+/// Whether the font `attrs` matches for Latin text supports any of `tags`.
+fn font_has_any_feature(
+    font_system: &mut FontSystem,
+    attrs: &Attrs<'_>,
+    tags: &[FeatureTag],
+) -> bool {
+    let mut buffer = Buffer::new(font_system, Metrics::new(16.0, 32.0));
+    buffer.set_size(font_system, None, None);
+    buffer.set_wrap(font_system, Wrap::None);
+    buffer.set_text(font_system, "x", attrs, Shaping::Advanced, None);
+    buffer.shape_until_scroll(font_system, false);
+    let Some(glyph) = buffer
+        .layout_runs()
+        .find_map(|run| run.glyphs.first().cloned())
+    else {
+        return false;
+    };
+    let Some(font) = font_system.get_font(glyph.font_id, glyph.font_weight) else {
+        return false;
+    };
+    font.as_swash().features().any(|feature| {
+        tags.iter()
+            .any(|tag| feature.tag() == u32::from_be_bytes(*tag.as_bytes()))
+    })
+}
+
+/// Split `text` into the runs to shape for synthesized capitals: each run's
+/// text, uppercased where it's reduced, and whether it's reduced.
+pub fn synthesized_runs(text: &str, synthesis: CapsSynthesis) -> Vec<(String, bool)> {
+    let mut runs: Vec<(String, bool)> = Vec::new();
+    for character in text.chars() {
+        let reduced = character.is_lowercase() || (synthesis.all && character.is_uppercase());
+        match runs.last_mut() {
+            Some((run, run_reduced)) if *run_reduced == reduced => {
+                push_char(run, character, reduced);
+            }
+            _ => {
+                let mut run = String::new();
+                push_char(&mut run, character, reduced);
+                runs.push((run, reduced));
+            }
+        }
+    }
+    runs
+}
+
+fn push_char(run: &mut String, character: char, reduced: bool) {
+    if reduced {
+        run.extend(character.to_uppercase());
+    } else {
+        run.push(character);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_caps_reduce_lowercase_only() {
+        let runs = synthesized_runs("Hello World", CapsSynthesis { all: false });
+        assert_eq!(
+            runs,
+            [
+                ("H".to_owned(), false),
+                ("ELLO".to_owned(), true),
+                (" W".to_owned(), false),
+                ("ORLD".to_owned(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn all_small_caps_reduce_every_letter() {
+        let runs = synthesized_runs("Hi 42", CapsSynthesis { all: true });
+        assert_eq!(runs, [("HI".to_owned(), true), (" 42".to_owned(), false)]);
+    }
+
+    #[test]
+    fn normal_caps_enable_no_features() {
+        assert_eq!(caps_features(None), FontFeatures::new());
+        let small = Property::FontVariantCaps(FontVariantCaps::SmallCaps);
+        let features = caps_features(Some(&small));
+        assert_eq!(features.features.len(), 1);
+        assert_eq!(features.features[0].tag, FeatureTag::SMALL_CAPS);
+    }
+}
@@ -0,0 +1,87 @@
+//! CSS Text 3 §7 `letter-spacing` and `word-spacing`.
+//!
+//! Both are applied while shaping, as extra advance after each glyph, so
+//! wrapping sees the spaced widths. Word spacing is added to word
+//! separators on top of the letter spacing every glyph gets.
+
+use cosmic_text::Attrs;
+
+/// Extra advance in px, resolved from `letter-spacing` and
+/// `word-spacing`. `normal` is zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TextSpacing {
+    /// Added after every glyph.
+    pub letter: f32,
+    /// Added after every word separator.
+    pub word: f32,
+}
+
+impl TextSpacing {
+    /// Whether no spacing is added.
+    pub fn is_none(self) -> bool {
+        self.letter == 0.0 && self.word == 0.0
+    }
+
+    /// `attrs` with the spacing for a run of word separators, or of other
+    /// characters, shaped at `font_size` px.
+    pub(crate) fn apply(self, attrs: Attrs<'_>, separator: bool, font_size: f32) -> Attrs<'_> {
+        let extra = if separator {
+            self.letter + self.word
+        } else {
+            self.letter
+        };
+        if extra == 0.0 || font_size <= 0.0 {
+            return attrs;
+        }
+        // cosmic-text takes letter spacing in em of the run's font size.
+        attrs.letter_spacing(extra / font_size)
+    }
+}
+
+/// Whether `character` is a word separator (CSS Text 3 §7.1).
+pub fn is_word_separator(character: char) -> bool {
+    matches!(
+        character,
+        ' ' | '\u{A0}' | '\u{1361}' | '\u{10100}' | '\u{10101}' | '\u{1039F}' | '\u{1091F}'
+    )
+}
+
+/// Split `text` into runs of word separators and of other characters.
+/// Returns each run and whether it's separators.
+pub(crate) fn separator_runs(text: &str) -> Vec<(&str, bool)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut current = None;
+    for (index, character) in text.char_indices() {
+        let separator = is_word_separator(character);
+        if current.is_some_and(|run| run != separator) {
+            runs.push((&text[start..index], !separator));
+            start = index;
+        }
+        current = Some(separator);
+    }
+    if let Some(separator) = current {
+        runs.push((&text[start..], separator));
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_word_separators() {
+        assert_eq!(
+            separator_runs("ab  c\u{A0}d"),
+            [
+                ("ab", false),
+                ("  ", true),
+                ("c", false),
+                ("\u{A0}", true),
+                ("d", false)
+            ]
+        );
+        assert_eq!(separator_runs(""), []);
+    }
+}
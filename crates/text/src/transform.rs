@@ -0,0 +1,65 @@
+//! CSS Text 3 §2.1 `text-transform`.
+//!
+//! Case transforms change the characters that are shaped, so text is
+//! measured after transforming it, the same way a renderer must draw it.
+//! `full-width` and `full-size-kana` aren't applied.
+
+use lightningcss::properties::text::{TextTransform, TextTransformCase};
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation as _;
+
+/// `text` with the case transform of `transform` applied. Borrows `text`
+/// when there's nothing to transform.
+pub fn transform_text<'text>(text: &'text str, transform: &TextTransform) -> Cow<'text, str> {
+    match transform.case {
+        TextTransformCase::None => Cow::Borrowed(text),
+        TextTransformCase::Uppercase => Cow::Owned(text.to_uppercase()),
+        TextTransformCase::Lowercase => Cow::Owned(text.to_lowercase()),
+        TextTransformCase::Capitalize => Cow::Owned(capitalize(text)),
+    }
+}
+
+/// Uppercase the first letter of each word (UAX #29), leaving the rest.
+fn capitalize(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for word in text.split_word_bounds() {
+        let Some(first) = word.find(char::is_alphabetic) else {
+            result.push_str(word);
+            continue;
+        };
+        let mut rest = word[first..].chars();
+        result.push_str(&word[..first]);
+        result.extend(rest.next().into_iter().flat_map(char::to_uppercase));
+        result.push_str(rest.as_str());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightningcss::properties::text::TextTransformOther;
+
+    fn transform(case: TextTransformCase, text: &str) -> String {
+        let transform = TextTransform {
+            case,
+            other: TextTransformOther::empty(),
+        };
+        transform_text(text, &transform).into_owned()
+    }
+
+    #[test]
+    fn case_transforms() {
+        assert_eq!(transform(TextTransformCase::Uppercase, "straße"), "STRASSE");
+        assert_eq!(transform(TextTransformCase::Lowercase, "ÀB"), "àb");
+        assert_eq!(
+            transform(TextTransformCase::Capitalize, "don't stop (now)"),
+            "Don't Stop (Now)"
+        );
+        let none = TextTransform {
+            case: TextTransformCase::None,
+            other: TextTransformOther::empty(),
+        };
+        assert!(matches!(transform_text("as is", &none), Cow::Borrowed(_)));
+    }
+}
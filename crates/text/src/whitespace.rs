@@ -1,8 +1,10 @@
-//! CSS Text 3 §4.1.1 whitespace collapsing for `white-space: normal`.
+//! CSS Text 3 §4.1 whitespace processing.
 //!
 //! Implements Phase I (collapsing and transformation) and a Phase II
-//! approximation (trimming at block boundaries).
+//! approximation (trimming at block boundaries) for each `white-space`
+//! value, and which values allow soft wrapping.
 
+use lightningcss::properties::text::WhiteSpace;
 use std::iter::Peekable;
 use std::str::CharIndices;
 
@@ -106,6 +108,73 @@ pub fn collapse_whitespace(text: &str, at_block_start: bool, at_block_end: bool)
     result
 }
 
+/// Process whitespace in `text` per its `white-space` value.
+///
+/// - `normal`, `nowrap`: collapse as `collapse_whitespace`.
+/// - `pre-line`: collapse spaces and tabs, removing them around segment
+///   breaks, but keep each segment break as a forced line break (`\n`).
+/// - `pre`, `pre-wrap`, `break-spaces`: preserve everything; only `\r\n`
+///   and lone `\r` are normalised to `\n`.
+///
+/// Phase II trimming at block boundaries applies to the collapsing values
+/// only.
+pub fn process_whitespace(
+    text: &str,
+    white_space: WhiteSpace,
+    at_block_start: bool,
+    at_block_end: bool,
+) -> String {
+    match white_space {
+        WhiteSpace::Normal | WhiteSpace::NoWrap => {
+            collapse_whitespace(text, at_block_start, at_block_end)
+        }
+        WhiteSpace::PreLine => {
+            let normalised = normalise_segment_breaks(text);
+            let lines: Vec<&str> = normalised.split('\n').collect();
+            let last = lines.len() - 1;
+            lines
+                .iter()
+                .enumerate()
+                .map(|(index, line)| {
+                    let collapsed = collapse_whitespace(line, false, false);
+                    let start = if index > 0 || at_block_start {
+                        collapsed.trim_start_matches(' ')
+                    } else {
+                        &collapsed
+                    };
+                    let trimmed = if index < last || at_block_end {
+                        start.trim_end_matches(' ')
+                    } else {
+                        start
+                    };
+                    trimmed.to_owned()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::BreakSpaces => {
+            normalise_segment_breaks(text)
+        }
+    }
+}
+
+/// Whether text with this `white-space` value may wrap at soft wrap
+/// opportunities. Forced line breaks apply either way.
+pub fn wraps(white_space: WhiteSpace) -> bool {
+    !matches!(white_space, WhiteSpace::Pre | WhiteSpace::NoWrap)
+}
+
+/// Whether text with this `white-space` value keeps segment breaks as
+/// forced line breaks.
+pub fn preserves_segment_breaks(white_space: WhiteSpace) -> bool {
+    !matches!(white_space, WhiteSpace::Normal | WhiteSpace::NoWrap)
+}
+
+/// Replace `\r\n` and lone `\r` with `\n`.
+fn normalise_segment_breaks(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -192,4 +261,44 @@ mod tests {
     fn crlf_handling() {
         assert_eq!(collapse_whitespace("a\r\nb", false, false), "a b");
     }
+
+    #[test]
+    fn pre_preserves_everything() {
+        for white_space in [
+            WhiteSpace::Pre,
+            WhiteSpace::PreWrap,
+            WhiteSpace::BreakSpaces,
+        ] {
+            assert_eq!(
+                process_whitespace("  a\t b\r\n\n c  ", white_space, true, true),
+                "  a\t b\n\n c  "
+            );
+        }
+    }
+
+    #[test]
+    fn pre_line_keeps_segment_breaks() {
+        assert_eq!(
+            process_whitespace(" a  b \n\n   c\t d ", WhiteSpace::PreLine, false, false),
+            " a b\n\nc d "
+        );
+        assert_eq!(
+            process_whitespace("\n  a \n", WhiteSpace::PreLine, true, true),
+            "\na\n"
+        );
+    }
+
+    #[test]
+    fn nowrap_collapses_but_does_not_wrap() {
+        assert_eq!(
+            process_whitespace(" a \n b ", WhiteSpace::NoWrap, true, true),
+            "a b"
+        );
+        assert!(!wraps(WhiteSpace::NoWrap));
+        assert!(!wraps(WhiteSpace::Pre));
+        assert!(wraps(WhiteSpace::PreWrap));
+        assert!(wraps(WhiteSpace::PreLine));
+        assert!(!preserves_segment_breaks(WhiteSpace::NoWrap));
+        assert!(preserves_segment_breaks(WhiteSpace::PreLine));
+    }
 }